@@ -0,0 +1,10 @@
+use hlsl_parser::language_server::LanguageServer;
+use hlsl_parser::parser::ParserSettings;
+
+// Speaks the Language Server Protocol over stdin and stdout, messages for people go to stderr
+fn main()
+{
+    let mut server = LanguageServer::new(ParserSettings::default());
+    let code = server.run(&mut std::io::stdin().lock(), &mut std::io::stdout().lock());
+    std::process::exit(code);
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use hlsl_parser::formatting::{FormatSettings, Formatter};
+use hlsl_parser::parser::ParserSettings;
+
+const USAGE: &str = "usage: hlslfmt [--check] [--config <file>] [-I <include directory>] <files>...";
+
+// Formats the file in place, returns whether it was already formatted
+fn format_file(path: &Path, settings: &FormatSettings, include_paths: &[PathBuf], check: bool) -> Result<bool, String>
+{
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let text = String::from_utf8(bytes).map_err(|_| format!("{}: not UTF-8, the file is left alone", path.display()))?;
+
+    let mut parser_settings = ParserSettings::default();
+    if let Some(directory) = path.parent()
+    {
+        parser_settings.include_paths.push(directory.to_path_buf());
+    }
+    parser_settings.include_paths.extend(include_paths.iter().cloned());
+
+    let formatted = match Formatter::format_text(&text, &parser_settings, settings)
+    {
+        Ok(formatted) => formatted,
+        Err(errors) => {
+            let messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(format!("{}: has syntax errors, the file is left alone: {}", path.display(), messages.join("; ")));
+        }
+    };
+    if formatted == text
+    {
+        return Ok(true);
+    }
+    if !check
+    {
+        std::fs::write(path, formatted).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    return Ok(false);
+}
+
+fn main() -> ExitCode
+{
+    let mut check = false;
+    let mut settings = FormatSettings::default();
+    let mut include_paths = Vec::new();
+    let mut files = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next()
+    {
+        match arg.as_str()
+        {
+            "--check" => check = true,
+            "--config" | "-I" => {
+                let Some(value) = args.next() else {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                };
+                if arg == "-I"
+                {
+                    include_paths.push(PathBuf::from(value));
+                    continue;
+                }
+                match FormatSettings::from_path(Path::new(&value))
+                {
+                    Ok(loaded) => settings = loaded,
+                    Err(error) => {
+                        eprintln!("hlslfmt: {}", error);
+                        return ExitCode::from(2);
+                    }
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            },
+            _ => files.push(PathBuf::from(arg))
+        }
+    }
+    if files.is_empty()
+    {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    let mut failed = false;
+    for file in &files
+    {
+        match format_file(file, &settings, &include_paths, check)
+        {
+            Ok(true) => {},
+            Ok(false) if check => {
+                println!("{}: not formatted", file.display());
+                failed = true;
+            },
+            Ok(false) => {},
+            Err(error) => {
+                eprintln!("hlslfmt: {}", error);
+                failed = true;
+            }
+        }
+    }
+    return if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+}
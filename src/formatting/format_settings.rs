@@ -0,0 +1,112 @@
+use std::path::Path;
+
+// Where the opening brace of functions, types, buffers and statements goes
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BraceStyle
+{
+    // On a line of its own, lined up with the declaration or statement
+    #[default]
+    Allman,
+    // At the end of the line of the declaration or statement
+    Attach,
+}
+
+impl BraceStyle
+{
+    pub fn from_name(name: &str) -> Option<BraceStyle>
+    {
+        return match name.to_ascii_lowercase().as_str()
+        {
+            "allman" | "next-line" => Some(BraceStyle::Allman),
+            "attach" | "same-line" | "k&r" => Some(BraceStyle::Attach),
+            _ => None
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatSettings
+{
+    // Spaces per indentation level, also the width of a tab
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    pub brace_style: BraceStyle,
+    // Lines longer than this are wrapped after commas and operators when possible
+    pub max_line_width: usize,
+    // 'a + b' instead of 'a+b', for binary, assignment and conditional operators
+    pub space_around_operators: bool,
+    // Lines up the semantics, registers and packoffsets of consecutive members of structs and constant buffers
+    pub align_semantics: bool,
+    // Empty lines that are kept between declarations and statements
+    pub max_blank_lines: usize,
+}
+
+impl Default for FormatSettings
+{
+    fn default() -> Self
+    {
+        return Self{
+            indent_width: 4,
+            use_tabs: false,
+            brace_style: BraceStyle::Allman,
+            max_line_width: 120,
+            space_around_operators: true,
+            align_semantics: true,
+            max_blank_lines: 1
+        };
+    }
+}
+
+impl FormatSettings
+{
+    // Reads a config file with a 'key = value' line per setting, '#' starts a comment and a [format] section header is allowed:
+    //   [format]
+    //   indent_width = 2
+    //   brace_style = attach
+    pub fn from_config(text: &str) -> Result<FormatSettings, String>
+    {
+        let mut settings = FormatSettings::default();
+        for (index, line) in text.lines().enumerate()
+        {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() || line.eq_ignore_ascii_case("[format]")
+            {
+                continue;
+            }
+            let (key, value) = match line.split_once('=')
+            {
+                Some((key, value)) => (key.trim().trim_matches('"'), value.trim().trim_matches('"')),
+                None => return Err(format!("line {}: expected 'key = value'", index + 1))
+            };
+            let invalid = || format!("line {}: invalid value '{}' for '{}'", index + 1, value, key);
+            match key
+            {
+                "indent_width" => settings.indent_width = value.parse().ok().filter(|w| *w > 0).ok_or_else(invalid)?,
+                "use_tabs" => settings.use_tabs = value.parse().map_err(|_| invalid())?,
+                "brace_style" => settings.brace_style = BraceStyle::from_name(value).ok_or_else(invalid)?,
+                "max_line_width" => settings.max_line_width = value.parse().ok().filter(|w| *w > 0).ok_or_else(invalid)?,
+                "space_around_operators" => settings.space_around_operators = value.parse().map_err(|_| invalid())?,
+                "align_semantics" => settings.align_semantics = value.parse().map_err(|_| invalid())?,
+                "max_blank_lines" => settings.max_blank_lines = value.parse().map_err(|_| invalid())?,
+                _ => return Err(format!("line {}: unknown setting '{}'", index + 1, key))
+            }
+        }
+        return Ok(settings);
+    }
+
+    pub fn from_path(path: &Path) -> Result<FormatSettings, String>
+    {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        return FormatSettings::from_config(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    // The whitespace in front of a line at the indentation level
+    pub(crate) fn indentation(&self, level: usize) -> String
+    {
+        if self.use_tabs
+        {
+            return "\t".repeat(level);
+        }
+        return " ".repeat(level * self.indent_width);
+    }
+}
@@ -0,0 +1,1376 @@
+use crate::parser::{diagnostics::Diagnostic, syntax_nodes::{SyntaxElement, SyntaxNode, SyntaxNodeType}, syntax_parser::SyntaxTree,
+    syntax_tokens::{SyntaxToken, SyntaxTokenType, SyntaxTrivia, SyntaxTriviaType, TextSpan}, ParserSettings};
+
+use std::collections::HashMap;
+
+use super::format_settings::{BraceStyle, FormatSettings};
+
+// How a token is placed relative to the token in front of it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Break
+{
+    // On the same line
+    None,
+    // On the same line, unless a comment or directive ends the line, then at the indentation
+    Attach(usize),
+    // After commas, operators and opening brackets, wrapped when the line gets too long or when the source had a line break
+    Soft,
+    // The closing bracket of a list, keeps a line break of the source
+    Closing,
+    // Keeps a line break of the source at the indentation, like after attributes
+    Keep(usize),
+    // Starts a line at the indentation, blank lines of the source are kept when allowed
+    Line{ indent: usize, blank_lines: bool },
+}
+
+impl Break
+{
+    fn strength(&self) -> u32
+    {
+        return match self
+        {
+            Break::None => 0,
+            Break::Attach(_) => 1,
+            Break::Soft | Break::Closing => 2,
+            Break::Keep(_) => 3,
+            Break::Line{..} => 4
+        };
+    }
+}
+
+// A token of the syntax tree with the layout the structure around it asks for
+struct FormatToken<'a>
+{
+    token: &'a SyntaxToken,
+    parent: SyntaxNodeType,
+    // A missing token or a token the parser skipped
+    is_error: bool,
+    first_child: bool,
+    break_before: Break,
+    // Indentation of comments on a line of their own in front of the token, closing braces indent them like the body
+    comment_indent: Option<usize>,
+    // The closing bracket of '(', '[', and '{' or '<' of lists
+    closer: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum UnitKind
+{
+    Token,
+    // The invocation of a macro, written as it is in the source
+    Macro,
+    // Source text no token covers, like a macro that expands to nothing
+    Gap,
+    EndOfFile,
+}
+
+// A piece of the source the formatter moves as a whole, a token or a macro invocation with everything it expands to
+struct Unit<'a>
+{
+    kind: UnitKind,
+    span: TextSpan,
+    text: &'a str,
+    first: usize,
+    last: usize,
+    trivia: &'a [SyntaxTrivia],
+    break_before: Break,
+    space_before: bool,
+    // Spaces in front of the unit to line it up with the units above, 0 when it is not aligned
+    padding: usize,
+}
+
+// The comments, directives and disabled regions in front of a unit
+enum TriviaItem<'a>
+{
+    Comment{ text: &'a str, is_line: bool, newlines_before: usize, space_before: bool },
+    Directive{ text: &'a str, newlines_before: usize },
+    // A region disabled by #if, copied as it is
+    Verbatim(&'a str),
+}
+
+// The whitespace and comments between two units of the source, and the text the formatter writes in their place
+struct Separator
+{
+    span: TextSpan,
+    text: String,
+}
+
+// Replaces the text of the span in the source
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit
+{
+    pub span: TextSpan,
+    pub new_text: String,
+}
+
+// Two character operators and comment starts that two tokens must not form when they are written without a space
+const JOINED_OPERATORS: &[&str] = &["++", "--", "+=", "-=", "*=", "/=", "%=", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||",
+    "&=", "|=", "^=", "::", "//", "/*", "*/", "->", "##"];
+
+fn is_word_char(c: char) -> bool
+{
+    return c.is_alphanumeric() || c == '_';
+}
+
+// Whether the texts would lex differently when written next to each other
+fn needs_separation(left: &str, right: &str) -> bool
+{
+    let (Some(last), Some(first)) = (left.chars().last(), right.chars().next()) else { return false };
+    if is_word_char(last) && is_word_char(first)
+    {
+        return true;
+    }
+    // 1 .x would become the float 1.
+    if first == '.' && left.chars().all(|c| c.is_ascii_digit())
+    {
+        return true;
+    }
+    let pair: String = [last, first].iter().collect();
+    return JOINED_OPERATORS.contains(&pair.as_str());
+}
+
+// Formatter
+// Rewrites the whitespace of a file from its syntax tree. Tokens, comments, directives and macro invocations are written as
+// they are in the source, regions disabled by #if are left alone, and formatting the result again gives the same text
+pub struct Formatter<'a>
+{
+    root: &'a SyntaxNode,
+    text: &'a str,
+    settings: &'a FormatSettings,
+    tokens: Vec<FormatToken<'a>>,
+    // Index of every token by its address, to find the tokens of a node
+    token_indices: HashMap<*const SyntaxToken, usize>,
+    units: Vec<Unit<'a>>,
+    // The unit every token is written in, tokens that are not written belong to the unit after them
+    unit_of: Vec<usize>,
+    // The members of struct and constant buffer bodies, with the first token and the ':' of their semantic or register
+    alignments: Vec<Vec<Option<(usize, usize)>>>,
+    has_syntax_errors: bool,
+    newline: &'static str,
+
+    output: String,
+    separators: Vec<Separator>,
+    column: usize,
+    // Something was written on the current line
+    line_open: bool,
+    blank_lines: usize,
+    pending_indent: usize,
+    line_indent: usize,
+    // Indentation of the line the current declaration or statement started on
+    base_indent: usize,
+    // A line comment or directive ended the line
+    line_ended: bool,
+    // Open brackets with the index of their closing bracket and the indentation of the line they were opened on
+    brackets: Vec<(usize, usize)>,
+}
+
+impl<'a> Formatter<'a>
+{
+    pub fn new(tree: &'a SyntaxTree, text: &'a str, settings: &'a FormatSettings) -> Self
+    {
+        let mut formatter = Self{
+            root: &tree.root,
+            text: text,
+            settings: settings,
+            tokens: Vec::new(),
+            token_indices: HashMap::new(),
+            units: Vec::new(),
+            unit_of: Vec::new(),
+            alignments: Vec::new(),
+            has_syntax_errors: false,
+            newline: if text.contains("\r\n") { "\r\n" } else { "\n" },
+            output: String::new(),
+            separators: Vec::new(),
+            column: 0,
+            line_open: false,
+            blank_lines: 0,
+            pending_indent: 0,
+            line_indent: 0,
+            base_indent: 0,
+            line_ended: false,
+            brackets: Vec::new()
+        };
+        formatter.visit(&tree.root, 0);
+        formatter.token_indices = formatter.tokens.iter().enumerate().map(|(index, t)| (t.token as *const SyntaxToken, index)).collect();
+        formatter.find_soft_breaks();
+        formatter.build_units();
+        formatter.align_members();
+        return formatter;
+    }
+
+    // Formats the text of a file, files with syntax errors are left alone and their errors are returned
+    pub fn format_text(text: &str, parser_settings: &ParserSettings, settings: &FormatSettings) -> Result<String, Vec<Diagnostic>>
+    {
+        let tree = SyntaxTree::parse_text(text, parser_settings);
+        let formatter = Formatter::new(&tree, text, settings);
+        if formatter.has_syntax_errors
+        {
+            return Err(tree.diagnostics.iter().filter(|d| d.is_error() && d.file_index == 0).cloned().collect());
+        }
+        return Ok(formatter.format());
+    }
+
+    // Edits that format the declarations and statements the range touches, for formatting a selection in an editor.
+    // Only whitespace between tokens that overlaps the range changes, syntax errors in there leave the text alone
+    pub fn format_range(text: &str, parser_settings: &ParserSettings, settings: &FormatSettings, range: TextSpan) -> Result<Vec<TextEdit>, Vec<Diagnostic>>
+    {
+        let tree = SyntaxTree::parse_text(text, parser_settings);
+        let formatter = Formatter::new(&tree, text, settings);
+        let node = formatter.covering_node(range);
+        if formatter.has_errors_in(node)
+        {
+            let span = formatter.node_span(node).unwrap_or(range);
+            return Err(tree.diagnostics.iter().filter(|d| d.is_error() && d.file_index == 0 && d.span.start <= span.end && span.start <= d.span.end).cloned().collect());
+        }
+        return Ok(formatter.format_node(node, range));
+    }
+
+    // Edits that format the statement or declaration a ';' or '}' typed just before the offset ends, up to that character
+    pub fn format_on_type(text: &str, parser_settings: &ParserSettings, settings: &FormatSettings, offset: usize) -> Result<Vec<TextEdit>, Vec<Diagnostic>>
+    {
+        let tree = SyntaxTree::parse_text(text, parser_settings);
+        let formatter = Formatter::new(&tree, text, settings);
+        let typed = formatter.tokens.iter().find(|t| t.token.file_index == 0 && t.token.spelling.is_none() && !t.token.is_missing &&
+            t.token.span.end == offset && matches!(t.token.token_type, SyntaxTokenType::SemiToken | SyntaxTokenType::CloseBraceToken));
+        let Some(typed) = typed else { return Ok(Vec::new()) };
+
+        let node = formatter.covering_node(typed.token.span);
+        if formatter.has_errors_in(node) || node.node_type == SyntaxNodeType::CompilationUnit
+        {
+            return Ok(Vec::new());
+        }
+        let start = formatter.node_span(node).map(|s| s.start).unwrap_or(offset);
+        return Ok(formatter.format_node(node, TextSpan::new(start, offset)));
+    }
+
+    pub fn has_syntax_errors(&self) -> bool
+    {
+        return self.has_syntax_errors;
+    }
+
+    // The formatted text of the whole file
+    pub fn format(mut self) -> String
+    {
+        self.emit(0, self.units.len());
+        return self.output;
+    }
+
+    // Edits that format the node, limited to the whitespace between tokens that overlaps the range
+    pub fn format_node(mut self, node: &SyntaxNode, range: TextSpan) -> Vec<TextEdit>
+    {
+        let Some((first, last)) = self.node_units(node) else { return Vec::new() };
+        self.emit(first, last + 1);
+
+        let mut edits = Vec::new();
+        for separator in &self.separators
+        {
+            if separator.span.end < range.start || separator.span.start > range.end
+            {
+                continue;
+            }
+            if let Some(edit) = minimal_edit(&self.text[separator.span.start..separator.span.end], separator.span.start, &separator.text)
+            {
+                edits.push(edit);
+            }
+        }
+        return edits;
+    }
+
+    // The units the node is written in
+    fn node_units(&self, node: &SyntaxNode) -> Option<(usize, usize)>
+    {
+        let first = *self.token_indices.get(&(node.first_token()? as *const SyntaxToken))?;
+        let last = *self.token_indices.get(&(node.last_token()? as *const SyntaxToken))?;
+        let (first, last) = (self.unit_of[first], self.unit_of[last]);
+        if first > last || last >= self.units.len()
+        {
+            return None;
+        }
+        return Some((first, last));
+    }
+
+    fn node_span(&self, node: &SyntaxNode) -> Option<TextSpan>
+    {
+        let (first, last) = self.node_units(node)?;
+        return Some(TextSpan::new(self.units[first].span.start, self.units[last].span.end));
+    }
+
+    // Whether the node starts on a line of its own, it can be formatted without the text around it
+    fn starts_line(&self, node: &SyntaxNode) -> bool
+    {
+        return self.node_units(node).is_some_and(|(first, _)| first == 0 || matches!(self.units[first].break_before, Break::Line{..}));
+    }
+
+    // The smallest declaration or statement that starts a line and covers the range, the whole file when there is none
+    pub fn covering_node(&self, range: TextSpan) -> &'a SyntaxNode
+    {
+        let mut node = self.root;
+        let mut covering = self.root;
+        // The end of the file belongs to the whole file
+        if self.units.last().is_some_and(|eof| range.end >= eof.span.start)
+        {
+            return self.root;
+        }
+        loop
+        {
+            let child = node.child_nodes().find(|child| self.node_span(child).is_some_and(|span| span.start <= range.start && range.end <= span.end));
+            match child
+            {
+                Some(child) => {
+                    // The body of a function or statement is formatted with it, it starts a line only in the Allman style
+                    let is_body = matches!(child.node_type, SyntaxNodeType::Block | SyntaxNodeType::StateBlock) &&
+                        !matches!(node.node_type, SyntaxNodeType::Block | SyntaxNodeType::SwitchSection);
+                    // The type of a declaration starts the line of the declaration, not one of its own
+                    let same_start = !std::ptr::eq(covering, self.root) && self.node_units(child).map(|u| u.0) == self.node_units(covering).map(|u| u.0);
+                    if self.starts_line(child) && !is_body && !same_start
+                    {
+                        covering = child;
+                    }
+                    node = child;
+                },
+                None => return covering
+            }
+        }
+    }
+
+    // Whether the node has missing or skipped tokens
+    pub fn has_errors_in(&self, node: &SyntaxNode) -> bool
+    {
+        let first = node.first_token().and_then(|t| self.token_indices.get(&(t as *const SyntaxToken)));
+        let last = node.last_token().and_then(|t| self.token_indices.get(&(t as *const SyntaxToken)));
+        let (Some(first), Some(last)) = (first, last) else { return false };
+        return self.tokens[*first..=*last].iter().any(|t| t.is_error);
+    }
+
+    // Layout of the syntax tree
+
+    fn push_token(&mut self, token: &'a SyntaxToken, parent: SyntaxNodeType, first_child: bool)
+    {
+        let is_error = token.file_index == 0 && (token.is_missing || parent == SyntaxNodeType::SkippedTokens);
+        self.has_syntax_errors |= is_error;
+        self.tokens.push(FormatToken{
+            token: token,
+            parent: parent,
+            is_error: is_error,
+            first_child: first_child,
+            break_before: Break::None,
+            comment_indent: None,
+            closer: None
+        });
+    }
+
+    fn set_break(&mut self, index: usize, break_before: Break)
+    {
+        if let Some(token) = self.tokens.get_mut(index)
+        {
+            token.break_before = break_before;
+        }
+    }
+
+    fn line(&mut self, index: usize, indent: usize, blank_lines: bool)
+    {
+        self.set_break(index, Break::Line{ indent: indent, blank_lines: blank_lines });
+    }
+
+    // The opening brace of a body that does not start the line by itself
+    fn brace(&mut self, index: usize, indent: usize)
+    {
+        match self.settings.brace_style
+        {
+            BraceStyle::Allman => self.line(index, indent, false),
+            BraceStyle::Attach => self.set_break(index, Break::Attach(indent))
+        }
+    }
+
+    // Pairs the brackets of the node so wrapped lines can be indented by the bracket they are in
+    fn pair_bracket(&mut self, node: &SyntaxNode, index: usize, openers: &mut Vec<usize>)
+    {
+        use SyntaxTokenType::*;
+        let is_list = matches!(node.node_type, SyntaxNodeType::ArrayInitializerExpression);
+        let is_angle = matches!(node.node_type, SyntaxNodeType::TypeArgumentList | SyntaxNodeType::TemplateParameterList |
+            SyntaxNodeType::Annotations | SyntaxNodeType::StateReferenceExpression);
+        match self.tokens[index].token.token_type
+        {
+            OpenParenToken | OpenBracketToken => openers.push(index),
+            OpenBraceToken if is_list => openers.push(index),
+            LessThanToken if is_angle => openers.push(index),
+            CloseParenToken | CloseBracketToken => {
+                if let Some(open) = openers.pop()
+                {
+                    self.tokens[open].closer = Some(index);
+                }
+            },
+            CloseBraceToken if is_list => {
+                if let Some(open) = openers.pop()
+                {
+                    self.tokens[open].closer = Some(index);
+                }
+            },
+            GreaterThanToken if is_angle => {
+                if let Some(open) = openers.pop()
+                {
+                    self.tokens[open].closer = Some(index);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn visit(&mut self, node: &'a SyntaxNode, indent: usize)
+    {
+        use SyntaxNodeType::*;
+        match node.node_type
+        {
+            CompilationUnit => {
+                for child in &node.children
+                {
+                    let start = self.tokens.len();
+                    match child
+                    {
+                        SyntaxElement::Node(child) => self.visit(child, indent),
+                        SyntaxElement::Token(token) => self.push_token(token, node.node_type, false)
+                    }
+                    self.line(start, indent, true);
+                }
+            },
+            NamespaceDeclaration | ConstantBufferDeclaration | TechniqueDeclaration | StructType | SwitchStatement | Block | StateBlock => {
+                self.visit_body(node, indent);
+            },
+            IfStatement | ForStatement | WhileStatement | DoStatement => self.visit_statement(node, indent),
+            SwitchSection => {
+                let mut first_statement = true;
+                for child in node.child_nodes()
+                {
+                    let start = self.tokens.len();
+                    if matches!(child.node_type, CaseLabel | DefaultLabel)
+                    {
+                        self.visit(child, indent);
+                        self.line(start, indent, start > 0);
+                    }
+                    else if first_statement && child.node_type == Block
+                    {
+                        // case 0:
+                        // {
+                        self.visit(child, indent);
+                        self.brace(start, indent);
+                    }
+                    else
+                    {
+                        self.visit(child, indent + 1);
+                        self.line(start, indent + 1, true);
+                    }
+                    first_statement &= matches!(child.node_type, CaseLabel | DefaultLabel);
+                }
+            },
+            TemplateDeclaration => {
+                self.visit_children(node, indent);
+                if let Some(declaration) = node.child_nodes().last()
+                {
+                    let start = self.tokens.len() - declaration.descendant_tokens().len();
+                    self.line(start, indent, false);
+                }
+            },
+            Annotations => {
+                let mut openers = Vec::new();
+                for (index, child) in node.children.iter().enumerate()
+                {
+                    let start = self.tokens.len();
+                    match child
+                    {
+                        SyntaxElement::Token(token) => {
+                            self.push_token(token, node.node_type, index == 0);
+                            self.pair_bracket(node, start, &mut openers);
+                        },
+                        SyntaxElement::Node(child) => {
+                            self.visit(child, indent);
+                            self.set_break(start, Break::Soft);
+                        }
+                    }
+                }
+            },
+            _ => self.visit_children(node, indent)
+        }
+    }
+
+    // Children on the line of their parent, bodies of functions and state blocks get their braces placed
+    fn visit_children(&mut self, node: &'a SyntaxNode, indent: usize)
+    {
+        let mut openers = Vec::new();
+        let mut after_attributes = false;
+        for (index, child) in node.children.iter().enumerate()
+        {
+            let start = self.tokens.len();
+            match child
+            {
+                SyntaxElement::Token(token) => {
+                    self.push_token(token, node.node_type, index == 0);
+                    self.pair_bracket(node, start, &mut openers);
+                },
+                SyntaxElement::Node(child) => {
+                    self.visit(child, indent);
+                    if matches!(child.node_type, SyntaxNodeType::Block | SyntaxNodeType::StateBlock)
+                    {
+                        self.brace(start, indent);
+                    }
+                }
+            }
+
+            // [numthreads(8, 8, 1)] on a line of its own stays there
+            if after_attributes && node.node_type != SyntaxNodeType::Parameter && self.tokens.get(start).is_some_and(|t| t.break_before == Break::None)
+            {
+                self.set_break(start, Break::Keep(indent));
+            }
+            after_attributes = matches!(child, SyntaxElement::Node(child) if child.node_type == SyntaxNodeType::AttributeList);
+        }
+    }
+
+    // A node with a body between braces, every member of the body starts a line
+    fn visit_body(&mut self, node: &'a SyntaxNode, indent: usize)
+    {
+        use SyntaxTokenType::*;
+        let mut openers = Vec::new();
+        let mut in_body = false;
+        let mut first_member = true;
+        let mut after_attributes = false;
+        let mut members = Vec::new();
+        for (index, child) in node.children.iter().enumerate()
+        {
+            let start = self.tokens.len();
+            match child
+            {
+                SyntaxElement::Token(token) if token.token_type == OpenBraceToken && !in_body => {
+                    self.push_token(token, node.node_type, index == 0);
+                    // Blocks and state blocks start with their brace, the parent places it
+                    if index > 0
+                    {
+                        self.brace(start, indent);
+                    }
+                    in_body = true;
+                },
+                SyntaxElement::Token(token) if token.token_type == CloseBraceToken && in_body => {
+                    self.push_token(token, node.node_type, false);
+                    self.line(start, indent, false);
+                    self.tokens[start].comment_indent = Some(indent + 1);
+                    in_body = false;
+                },
+                SyntaxElement::Token(token) => {
+                    self.push_token(token, node.node_type, index == 0);
+                    self.pair_bracket(node, start, &mut openers);
+                },
+                SyntaxElement::Node(child) if in_body => {
+                    self.visit(child, indent + 1);
+                    self.line(start, indent + 1, !first_member);
+                    first_member = false;
+                    members.push(Self::aligned_location(child).map(|offset| (start, start + offset)));
+                },
+                SyntaxElement::Node(child) => {
+                    self.visit(child, indent);
+                    if after_attributes && self.tokens.get(start).is_some_and(|t| t.break_before == Break::None)
+                    {
+                        self.set_break(start, Break::Keep(indent));
+                    }
+                }
+            }
+            after_attributes = matches!(child, SyntaxElement::Node(child) if child.node_type == SyntaxNodeType::AttributeList);
+        }
+
+        if matches!(node.node_type, SyntaxNodeType::StructType | SyntaxNodeType::ConstantBufferDeclaration)
+        {
+            self.alignments.push(members);
+        }
+    }
+
+    // The offset of the ':' of the semantic, register or packoffset of a member with a single declarator
+    fn aligned_location(member: &SyntaxNode) -> Option<usize>
+    {
+        if member.node_type != SyntaxNodeType::VariableDeclarationStatement
+        {
+            return None;
+        }
+        let mut declarators = member.child_nodes().filter(|n| n.node_type == SyntaxNodeType::VariableDeclarator);
+        let declarator = declarators.next()?;
+        if declarators.next().is_some()
+        {
+            return None;
+        }
+        let location = declarator.child_nodes().find(|n| matches!(n.node_type,
+            SyntaxNodeType::Semantic | SyntaxNodeType::RegisterLocation | SyntaxNodeType::PackOffsetLocation))?;
+        let colon = location.first_token()?;
+        return member.descendant_tokens().iter().position(|t| std::ptr::eq(*t, colon));
+    }
+
+    // if, for, while and do statements with the statements they control
+    fn visit_statement(&mut self, node: &'a SyntaxNode, indent: usize)
+    {
+        use SyntaxTokenType::*;
+        let mut openers = Vec::new();
+        let mut after_attributes = false;
+        let mut after_condition = false;
+        let mut body_is_block = false;
+        for (index, child) in node.children.iter().enumerate()
+        {
+            let start = self.tokens.len();
+            match child
+            {
+                SyntaxElement::Token(token) => {
+                    self.push_token(token, node.node_type, index == 0);
+                    self.pair_bracket(node, start, &mut openers);
+                    if token.token_type == CloseParenToken && openers.is_empty() && node.node_type != SyntaxNodeType::DoStatement
+                    {
+                        after_condition = true;
+                    }
+                    // } while (condition);
+                    if token.token_type == WhileKeyword && node.node_type == SyntaxNodeType::DoStatement
+                    {
+                        self.close_statement(start, indent, body_is_block);
+                    }
+                },
+                SyntaxElement::Node(child) if child.node_type == SyntaxNodeType::ElseClause => {
+                    self.push_token(child.first_token().unwrap(), child.node_type, true);
+                    self.close_statement(start, indent, body_is_block);
+                    let statement = child.child_nodes().next().unwrap();
+                    let statement_start = self.tokens.len();
+                    if statement.node_type == SyntaxNodeType::IfStatement
+                    {
+                        // else if
+                        self.visit(statement, indent);
+                        self.set_break(statement_start, Break::Attach(indent));
+                    }
+                    else
+                    {
+                        self.visit_embedded(statement, indent);
+                    }
+                },
+                SyntaxElement::Node(child) => {
+                    let is_body = after_condition || (node.node_type == SyntaxNodeType::DoStatement && child.node_type != SyntaxNodeType::AttributeList &&
+                        self.tokens.last().is_some_and(|t| t.token.token_type == DoKeyword));
+                    if is_body
+                    {
+                        body_is_block = self.visit_embedded(child, indent);
+                        after_condition = false;
+                    }
+                    else
+                    {
+                        self.visit(child, indent);
+                    }
+                }
+            }
+
+            if after_attributes && self.tokens.get(start).is_some_and(|t| t.break_before == Break::None)
+            {
+                self.set_break(start, Break::Keep(indent));
+            }
+            after_attributes = matches!(child, SyntaxElement::Node(child) if child.node_type == SyntaxNodeType::AttributeList);
+        }
+    }
+
+    // else and the while of a do statement follow the closing brace of the body in the attached style
+    fn close_statement(&mut self, index: usize, indent: usize, body_is_block: bool)
+    {
+        if body_is_block && self.settings.brace_style == BraceStyle::Attach
+        {
+            self.set_break(index, Break::Attach(indent));
+        }
+        else
+        {
+            self.line(index, indent, false);
+        }
+    }
+
+    // The statement controlled by an if, else, for, while or do, returns whether it is a block
+    fn visit_embedded(&mut self, statement: &'a SyntaxNode, indent: usize) -> bool
+    {
+        let start = self.tokens.len();
+        if statement.node_type == SyntaxNodeType::Block
+        {
+            self.visit(statement, indent);
+            self.brace(start, indent);
+            return true;
+        }
+        self.visit(statement, indent + 1);
+        self.line(start, indent + 1, false);
+        return false;
+    }
+
+    // Line breaks are allowed after commas, operators and the opening brackets of lists
+    fn find_soft_breaks(&mut self)
+    {
+        use SyntaxNodeType as N;
+        use SyntaxTokenType::*;
+        for index in 1..self.tokens.len()
+        {
+            if self.tokens[index].break_before != Break::None
+            {
+                continue;
+            }
+
+            let is_list = |token: &FormatToken| matches!(token.parent, N::ArgumentList | N::ParameterList | N::AttributeArgumentList |
+                N::ArrayInitializerExpression | N::Annotations);
+            let token = &self.tokens[index];
+            if is_list(token) && matches!(token.token.token_type, CloseParenToken | CloseBraceToken | GreaterThanToken)
+            {
+                self.tokens[index].break_before = Break::Closing;
+                continue;
+            }
+
+            let previous = &self.tokens[index - 1];
+            let after_list_opener = is_list(previous) && matches!(previous.token.token_type, OpenParenToken | OpenBraceToken | LessThanToken) && previous.first_child;
+            // "a" "b" concatenates strings over several lines
+            let between_strings = matches!(previous.token.token_type, StringLiteralToken{..}) && matches!(token.token.token_type, StringLiteralToken{..});
+            if previous.token.token_type == CommaToken || after_list_opener || between_strings || self.is_binary_operator(previous)
+            {
+                self.tokens[index].break_before = Break::Soft;
+            }
+        }
+    }
+
+    // Binary, assignment and conditional operators, and the '=' of initializers and state assignments
+    fn is_binary_operator(&self, token: &FormatToken) -> bool
+    {
+        use SyntaxNodeType as N;
+        use SyntaxTokenType::*;
+        return match token.parent
+        {
+            N::BinaryExpression => token.token.token_type != CommaToken,
+            N::AssignmentExpression => true,
+            N::EqualsValueClause | N::StateAssignment => token.token.token_type == EqualsToken,
+            N::ConditionalExpression => matches!(token.token.token_type, QuestionToken | ColonToken),
+            _ => false
+        };
+    }
+
+    // Whether a space goes between the tokens when they are on the same line
+    fn space_between(&self, left: &FormatToken, right: &FormatToken) -> bool
+    {
+        use SyntaxNodeType as N;
+        use SyntaxTokenType::*;
+        let (l, r) = (&left.token.token_type, &right.token.token_type);
+        if matches!(r, CommaToken | SemiToken)
+        {
+            return false;
+        }
+        if *l == CommaToken
+        {
+            return true;
+        }
+        // for (;;)
+        if *l == SemiToken
+        {
+            return *r != CloseParenToken;
+        }
+        if *l == DotToken || *r == DotToken || *l == ColonColonToken
+        {
+            return false;
+        }
+        // ::GlobalName
+        if *r == ColonColonToken
+        {
+            return right.parent == N::QualifiedName && right.first_child;
+        }
+        if left.parent == N::PrefixUnaryExpression || right.parent == N::PostfixUnaryExpression
+        {
+            return false;
+        }
+        // (float4)0
+        if *l == CloseParenToken && left.parent == N::CastExpression
+        {
+            return false;
+        }
+        if matches!(l, OpenParenToken | OpenBracketToken) || matches!(r, CloseParenToken | CloseBracketToken)
+        {
+            return false;
+        }
+        if self.is_binary_operator(left) || self.is_binary_operator(right)
+        {
+            return self.settings.space_around_operators;
+        }
+
+        let is_angle = |token: &FormatToken| matches!(token.parent, N::TypeArgumentList | N::TemplateParameterList | N::StateReferenceExpression);
+        if (*l == LessThanToken && is_angle(left)) || (*r == GreaterThanToken && is_angle(right))
+        {
+            return false;
+        }
+        if *r == LessThanToken && matches!(right.parent, N::TypeArgumentList | N::TemplateParameterList)
+        {
+            return false;
+        }
+        if left.parent == N::OperatorName && right.parent == N::OperatorName
+        {
+            return false;
+        }
+
+        return match r
+        {
+            // Calls, constructors and parameter lists, but 'if (' and 'return (x)'
+            OpenParenToken => !matches!(right.parent, N::ArgumentList | N::ParameterList | N::AttributeArgumentList |
+                N::RegisterLocation | N::PackOffsetLocation | N::CompileExpression | N::OperatorName),
+            // Attribute lists, but not 'a[i]' or 'float x[4]'
+            OpenBracketToken => right.parent == N::AttributeList,
+            CloseBraceToken => *l != OpenBraceToken,
+            ColonToken => !matches!(right.parent, N::CaseLabel | N::DefaultLabel),
+            _ => true
+        };
+    }
+
+    // Splits the tokens of the parsed file into units and places them
+    fn build_units(&mut self)
+    {
+        let mut cursor = 0;
+        let mut carried = Break::None;
+        for index in 0..self.tokens.len()
+        {
+            let token = self.tokens[index].token;
+            // Tokens of included files are not written, the line they start moves to the next token
+            self.unit_of.push(self.units.len());
+            if token.file_index != 0 || token.is_missing
+            {
+                if self.tokens[index].break_before.strength() > carried.strength()
+                {
+                    carried = self.tokens[index].break_before;
+                }
+                continue;
+            }
+
+            if token.spelling.is_some()
+            {
+                if let Some(unit) = self.units.last_mut()
+                {
+                    if unit.kind == UnitKind::Macro && unit.span == token.span
+                    {
+                        unit.last = index;
+                        self.unit_of[index] = self.units.len() - 1;
+                        continue;
+                    }
+                }
+            }
+
+            let start = token.leading_trivia.first().map(|t| t.span.start).unwrap_or(token.span.start);
+            if start > cursor && cursor < self.text.len()
+            {
+                let text = &self.text[cursor..start.min(self.text.len())];
+                let gap = text.trim();
+                if !gap.is_empty()
+                {
+                    let gap_start = cursor + text.len() - text.trim_start().len();
+                    self.units.push(Unit{
+                        kind: UnitKind::Gap,
+                        span: TextSpan::new(gap_start, gap_start + gap.len()),
+                        text: gap,
+                        first: index,
+                        last: index,
+                        trivia: &[],
+                        break_before: if text.contains('\n') { Break::Keep(0) } else { Break::None },
+                        space_before: text.starts_with(char::is_whitespace),
+                        padding: 0
+                    });
+                }
+            }
+
+            self.unit_of[index] = self.units.len();
+            let kind = match token.token_type
+            {
+                SyntaxTokenType::EndOfFileToken => UnitKind::EndOfFile,
+                _ if token.spelling.is_some() => UnitKind::Macro,
+                _ => UnitKind::Token
+            };
+            let mut break_before = self.tokens[index].break_before;
+            if carried.strength() > break_before.strength()
+            {
+                break_before = carried;
+            }
+            carried = Break::None;
+            let span = token.span;
+            self.units.push(Unit{
+                kind: kind,
+                span: span,
+                text: &self.text[span.start..span.end],
+                first: index,
+                last: index,
+                trivia: &token.leading_trivia,
+                break_before: break_before,
+                space_before: false,
+                padding: 0
+            });
+            cursor = cursor.max(span.end);
+        }
+
+        for index in 1..self.units.len()
+        {
+            let (previous, unit) = (&self.units[index - 1], &self.units[index]);
+            // Macros are spaced like the tokens they expand to, text no token covers keeps the spacing of the source
+            let mut space = if previous.kind != UnitKind::Gap && unit.kind != UnitKind::Gap
+            {
+                self.space_between(&self.tokens[previous.last], &self.tokens[unit.first])
+            }
+            else
+            {
+                unit.kind == UnitKind::Gap && unit.space_before || unit.span.start > previous.span.end || !unit.trivia.is_empty()
+            };
+            space |= needs_separation(previous.text, unit.text);
+            self.units[index].space_before = space;
+        }
+    }
+
+    // Lines up the semantics and registers of consecutive members of struct and constant buffer bodies
+    fn align_members(&mut self)
+    {
+        if !self.settings.align_semantics
+        {
+            return;
+        }
+
+        let mut unit_of = vec![None; self.tokens.len()];
+        for (index, unit) in self.units.iter().enumerate()
+        {
+            if unit.kind == UnitKind::Token
+            {
+                unit_of[unit.first] = Some(index);
+            }
+        }
+
+        for body in std::mem::take(&mut self.alignments)
+        {
+            let mut group: Vec<(usize, usize)> = Vec::new();
+            for member in body
+            {
+                let member = member.and_then(|(first, colon)| Some((unit_of[first]?, unit_of[colon]?)));
+                let width = member.and_then(|(first, colon)| self.prefix_width(first, colon));
+                let separated = member.is_some_and(|(first, _)| self.keeps_blank_line(first));
+                if width.is_none() || separated
+                {
+                    self.pad_group(&group);
+                    group.clear();
+                }
+                if let (Some((_, colon)), Some(width)) = (member, width)
+                {
+                    group.push((colon, width));
+                }
+            }
+            self.pad_group(&group);
+        }
+    }
+
+    // Whether the unit is written after an empty line, alignment groups end there. The output decides and not the source
+    // so formatting the output again finds the same groups
+    fn keeps_blank_line(&self, index: usize) -> bool
+    {
+        let unit = &self.units[index];
+        let allowed = matches!(unit.break_before, Break::Line{ blank_lines: true, .. }) ||
+            unit.trivia.iter().any(|t| t.trivia_type == SyntaxTriviaType::Directive);
+        return allowed && blank_lines_in(unit.trivia).min(self.settings.max_blank_lines) > 0;
+    }
+
+    // The width of a member up to the ':', None when something in between can end the line
+    fn prefix_width(&self, first: usize, colon: usize) -> Option<usize>
+    {
+        let mut width = 0;
+        for index in first..colon
+        {
+            let unit = &self.units[index];
+            if index > first && (unit.break_before.strength() >= Break::Keep(0).strength() || !unit.trivia.iter().all(is_blank_trivia))
+            {
+                return None;
+            }
+            if unit.text.contains('\n')
+            {
+                return None;
+            }
+            if index > first && unit.space_before
+            {
+                width += 1;
+            }
+            width += unit.text.chars().count();
+        }
+        if !self.units[colon].trivia.iter().all(is_blank_trivia) || self.units[colon].text.contains('\n')
+        {
+            return None;
+        }
+        return Some(width);
+    }
+
+    fn pad_group(&mut self, group: &[(usize, usize)])
+    {
+        if group.len() < 2
+        {
+            return;
+        }
+        let column = group.iter().map(|(_, width)| *width).max().unwrap() + 1;
+        for (colon, width) in group
+        {
+            self.units[*colon].padding = column - width;
+        }
+    }
+
+    // Writing
+
+    fn write(&mut self, text: &str)
+    {
+        if text.is_empty()
+        {
+            return;
+        }
+        if !self.line_open
+        {
+            let indentation = self.settings.indentation(self.pending_indent);
+            self.output.push_str(&indentation);
+            self.column = self.pending_indent * self.settings.indent_width;
+            self.line_indent = self.pending_indent;
+            self.line_open = true;
+            self.blank_lines = 0;
+        }
+        self.output.push_str(text);
+        match text.rfind('\n')
+        {
+            Some(position) => self.column = text[position + 1..].chars().count(),
+            None => self.column += text.chars().count()
+        }
+    }
+
+    // Text copied from the source, disabled regions end with a line break
+    fn write_verbatim(&mut self, text: &str)
+    {
+        self.write(text);
+        if text.ends_with('\n')
+        {
+            self.line_open = false;
+            self.pending_indent = 0;
+        }
+    }
+
+    fn trim_line_end(&mut self)
+    {
+        let trimmed = self.output.trim_end_matches([' ', '\t']).len();
+        self.output.truncate(trimmed);
+    }
+
+    fn start_line(&mut self, indent: usize, blank_lines: usize)
+    {
+        if self.line_open
+        {
+            self.trim_line_end();
+            self.output.push_str(self.newline);
+            self.line_open = false;
+        }
+        if !self.output.trim_start_matches('\u{feff}').is_empty()
+        {
+            while self.blank_lines < blank_lines
+            {
+                self.output.push_str(self.newline);
+                self.blank_lines += 1;
+            }
+        }
+        self.pending_indent = indent;
+        self.column = 0;
+    }
+
+    fn indent_width(&self, indent: usize) -> usize
+    {
+        return indent * self.settings.indent_width;
+    }
+
+    // Indentation of a line that continues a declaration or statement, one level deeper than the bracket it is in
+    fn continuation_indent(&self, unit: &Unit) -> usize
+    {
+        let token = &self.tokens[unit.first];
+        if unit.break_before == Break::Closing
+        {
+            if let Some((_, indent)) = self.brackets.iter().rev().find(|(closer, _)| *closer == unit.first)
+            {
+                return *indent;
+            }
+        }
+        let outer = self.brackets.last().map(|(_, indent)| *indent).unwrap_or(self.base_indent);
+        // float values[2] =
+        // {
+        let is_initializer = token.token.token_type == SyntaxTokenType::OpenBraceToken && token.parent == SyntaxNodeType::ArrayInitializerExpression &&
+            unit.first > 0 && self.tokens[unit.first - 1].token.token_type == SyntaxTokenType::EqualsToken;
+        return if is_initializer { outer } else { outer + 1 };
+    }
+
+    // Width of the unit and the units that have to stay on its line
+    fn chunk_width(&self, index: usize) -> usize
+    {
+        let mut width = 0;
+        for (offset, unit) in self.units[index..].iter().enumerate()
+        {
+            if offset > 0 && (!matches!(unit.break_before, Break::None | Break::Closing) || !unit.trivia.iter().all(is_blank_trivia))
+            {
+                break;
+            }
+            if offset > 0 && unit.space_before
+            {
+                width += 1;
+            }
+            match unit.text.find('\n')
+            {
+                Some(position) => return width + unit.text[..position].chars().count(),
+                None => width += unit.text.chars().count()
+            }
+        }
+        return width;
+    }
+
+    fn trivia_items(&self, trivia: &'a [SyntaxTrivia]) -> (Vec<TriviaItem<'a>>, usize, bool)
+    {
+        let text: &'a str = self.text;
+        let mut items = Vec::new();
+        let mut newlines = 0;
+        let mut space = false;
+        let mut index = 0;
+        while index < trivia.len()
+        {
+            let piece = &trivia[index];
+            let piece_text = &text[piece.span.start..piece.span.end];
+            match piece.trivia_type
+            {
+                SyntaxTriviaType::WhiteSpace | SyntaxTriviaType::LineContinuation => space = true,
+                SyntaxTriviaType::EndOfLine => {
+                    newlines += 1;
+                    space = true;
+                },
+                SyntaxTriviaType::LineComment | SyntaxTriviaType::BlockComment | SyntaxTriviaType::DisabledText => {
+                    let is_line = piece.trivia_type == SyntaxTriviaType::LineComment;
+                    items.push(TriviaItem::Comment{ text: piece_text, is_line: is_line, newlines_before: newlines, space_before: space });
+                    newlines = 0;
+                    space = false;
+                },
+                SyntaxTriviaType::Directive => {
+                    items.push(TriviaItem::Directive{ text: piece_text, newlines_before: newlines });
+                    newlines = 0;
+                    space = false;
+
+                    // A region disabled by #if is copied up to the line of the next directive
+                    let next = trivia[index + 1..].iter().position(|t| t.trivia_type == SyntaxTriviaType::Directive).map(|p| index + 1 + p).unwrap_or(trivia.len());
+                    let region = &trivia[index + 1..next];
+                    if region.iter().any(|t| t.trivia_type == SyntaxTriviaType::DisabledText)
+                    {
+                        let end = region.iter().rev().find(|t| t.trivia_type == SyntaxTriviaType::EndOfLine).map(|t| t.span.end).unwrap_or(region.last().unwrap().span.end);
+                        items.push(TriviaItem::Verbatim(&text[piece.span.end..end]));
+                        index = region.iter().position(|t| t.span.start >= end).map(|p| index + 1 + p).unwrap_or(next);
+                        continue;
+                    }
+                }
+            }
+            index += 1;
+        }
+        return (items, newlines, space);
+    }
+
+    // Writes the units in the range, a range after the start of the file continues the line of the unit in front of it
+    fn emit(&mut self, start: usize, end: usize)
+    {
+        let mut previous_end = 0;
+        if start > 0
+        {
+            previous_end = self.units[start - 1].span.end;
+            self.line_open = true;
+        }
+        else if self.text.starts_with('\u{feff}')
+        {
+            self.output.push('\u{feff}');
+            previous_end = '\u{feff}'.len_utf8();
+        }
+
+        for index in start..end
+        {
+            let first = self.units[index].first;
+            self.brackets.retain(|(closer, _)| *closer >= first);
+            let separator_start = self.output.len();
+            self.emit_separator(index);
+            let unit = &self.units[index];
+            let text = unit.text;
+            let span = unit.span;
+            let (first, last) = (unit.first, unit.last);
+
+            if unit.kind == UnitKind::EndOfFile
+            {
+                self.trim_line_end();
+                let trimmed = self.output.trim_end_matches(['\r', '\n']).len();
+                self.output.truncate(trimmed.max(separator_start.min(trimmed)));
+                if !self.output.is_empty() && !self.output.ends_with('\u{feff}')
+                {
+                    self.output.push_str(self.newline);
+                }
+                let separator_start = separator_start.min(self.output.len());
+                self.separators.push(Separator{ span: TextSpan::new(previous_end, self.text.len()), text: self.output[separator_start..].to_string() });
+                break;
+            }
+
+            if !self.line_open
+            {
+                // The indentation belongs to the separator
+                self.write(" ");
+                self.output.pop();
+                self.column -= 1;
+            }
+            self.separators.push(Separator{ span: TextSpan::new(previous_end, span.start), text: self.output[separator_start..].to_string() });
+            self.write(text);
+            previous_end = span.end;
+            self.line_ended = false;
+
+            for token in first..=last
+            {
+                if let Some(position) = self.brackets.iter().rposition(|(closer, _)| *closer == token)
+                {
+                    self.brackets.truncate(position);
+                }
+                if let Some(closer) = self.tokens[token].closer
+                {
+                    self.brackets.push((closer, self.line_indent));
+                }
+            }
+        }
+    }
+
+    // The comments and directives in front of a unit and the line break or spaces that separate it from the previous unit
+    fn emit_separator(&mut self, index: usize)
+    {
+        let unit = &self.units[index];
+        let (items, newlines, space_after) = self.trivia_items(unit.trivia);
+        let continuation = self.continuation_indent(unit);
+        let comment_indent = match unit.break_before
+        {
+            Break::Line{ indent, .. } => self.tokens[unit.first].comment_indent.unwrap_or(indent),
+            Break::Keep(indent) | Break::Attach(indent) => indent,
+            _ => continuation
+        };
+        let blank_lines_allowed = matches!(unit.break_before, Break::Line{ blank_lines: true, .. });
+        let max_blank_lines = self.settings.max_blank_lines;
+
+        let mut inline_comment = false;
+        let mut after_directive = false;
+        for item in items
+        {
+            match item
+            {
+                TriviaItem::Comment{ text, is_line, newlines_before, space_before } if newlines_before == 0 && after_directive => {
+                    // #define SCALE 2 // comment
+                    if space_before
+                    {
+                        self.write(" ");
+                    }
+                    self.write(text);
+                    after_directive = !is_line;
+                },
+                TriviaItem::Comment{ text, is_line, newlines_before, space_before } => {
+                    after_directive = false;
+                    if newlines_before == 0 && self.line_open && !self.line_ended
+                    {
+                        // A comment at the end of the line of the previous unit or in between units
+                        if space_before
+                        {
+                            self.write(" ");
+                        }
+                        inline_comment = true;
+                    }
+                    else
+                    {
+                        let blank_lines = if blank_lines_allowed { newlines_before.saturating_sub(1).min(max_blank_lines) } else { 0 };
+                        self.start_line(comment_indent, blank_lines);
+                        inline_comment = false;
+                    }
+                    self.write(text);
+                    self.line_ended = is_line;
+                },
+                TriviaItem::Directive{ text, newlines_before } => {
+                    self.start_line(0, newlines_before.saturating_sub(1).min(max_blank_lines));
+                    self.write(text);
+                    self.line_ended = true;
+                    inline_comment = false;
+                    after_directive = true;
+                },
+                TriviaItem::Verbatim(text) => {
+                    after_directive = false;
+                    self.write_verbatim(text);
+                    self.line_ended = false;
+                }
+            }
+        }
+
+        let unit = &self.units[index];
+        let newline = newlines > 0 || self.line_ended;
+        let line = match unit.break_before
+        {
+            Break::Line{ indent, blank_lines } => {
+                let blank_lines = if blank_lines { newlines.saturating_sub(1).min(max_blank_lines) } else { 0 };
+                Some((indent, blank_lines, true))
+            },
+            Break::Keep(indent) if newline => Some((indent, 0, true)),
+            Break::Attach(indent) if self.line_ended => Some((indent, 0, true)),
+            Break::Closing if newline => Some((continuation, 0, false)),
+            Break::Soft if newline || self.exceeds_line(index, continuation) => Some((continuation, 0, false)),
+            _ if self.line_ended || (newline && inline_comment) => Some((continuation, 0, false)),
+            _ => None
+        };
+
+        match line
+        {
+            Some((indent, blank_lines, starts_statement)) => {
+                self.start_line(indent, blank_lines);
+                if starts_statement
+                {
+                    self.base_indent = indent;
+                }
+            },
+            None if self.line_open => {
+                let unit = &self.units[index];
+                let spaces = if inline_comment { usize::from(space_after) } else if unit.padding > 0 { unit.padding } else { usize::from(unit.space_before) };
+                self.write(&" ".repeat(spaces));
+            },
+            None => {}
+        }
+    }
+
+    // Whether the unit goes past the maximum width when it is written on the current line
+    fn exceeds_line(&self, index: usize, continuation: usize) -> bool
+    {
+        if !self.line_open || self.column <= self.indent_width(continuation)
+        {
+            return false;
+        }
+        let unit = &self.units[index];
+        let space = usize::from(unit.space_before);
+        return self.column + space + self.chunk_width(index) > self.settings.max_line_width;
+    }
+}
+
+fn is_blank_trivia(trivia: &SyntaxTrivia) -> bool
+{
+    return matches!(trivia.trivia_type, SyntaxTriviaType::WhiteSpace | SyntaxTriviaType::EndOfLine | SyntaxTriviaType::LineContinuation);
+}
+
+// The empty lines in trivia, a line with only whitespace counts as empty
+fn blank_lines_in(trivia: &[SyntaxTrivia]) -> usize
+{
+    let mut most = 0;
+    let mut newlines: usize = 0;
+    for piece in trivia
+    {
+        match piece.trivia_type
+        {
+            SyntaxTriviaType::EndOfLine => {
+                newlines += 1;
+                most = most.max(newlines.saturating_sub(1));
+            },
+            SyntaxTriviaType::WhiteSpace | SyntaxTriviaType::LineContinuation => {},
+            _ => newlines = 0
+        }
+    }
+    return most;
+}
+
+// The edit that turns the old text at the offset into the new text, without the characters they start and end with in common
+fn minimal_edit(old: &str, offset: usize, new: &str) -> Option<TextEdit>
+{
+    if old == new
+    {
+        return None;
+    }
+    let prefix: usize = old.chars().zip(new.chars()).take_while(|(a, b)| a == b).map(|(c, _)| c.len_utf8()).sum();
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let suffix: usize = old_rest.chars().rev().zip(new_rest.chars().rev()).take_while(|(a, b)| a == b).map(|(c, _)| c.len_utf8()).sum();
+    return Some(TextEdit{
+        span: TextSpan::new(offset + prefix, offset + old.len() - suffix),
+        new_text: new_rest[..new_rest.len() - suffix].to_string()
+    });
+}
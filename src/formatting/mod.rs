@@ -0,0 +1,5 @@
+pub mod format_settings;
+pub mod formatter;
+
+pub use format_settings::{BraceStyle, FormatSettings};
+pub use formatter::{Formatter, TextEdit};
@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use crate::parser::syntax_tokens::TextSpan;
+
+use super::json::JsonValue;
+
+// A position in a document as the protocol counts it, the character is in UTF-16 code units
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Position
+{
+    pub line: usize,
+    pub character: usize,
+}
+
+impl Position
+{
+    pub fn from_json(value: &JsonValue) -> Option<Position>
+    {
+        return Some(Position{ line: value.get("line")?.as_usize()?, character: value.get("character")?.as_usize()? });
+    }
+
+    pub fn to_json(&self) -> JsonValue
+    {
+        return JsonValue::object(vec![("line", self.line.into()), ("character", self.character.into())]);
+    }
+}
+
+// Document
+// The text of a document the client has open, the client sends its changes and the file on disk is not read
+pub struct Document
+{
+    pub uri: String,
+    pub version: i64,
+    text: String,
+    // Byte offset of the start of every line
+    line_starts: Vec<usize>,
+}
+
+impl Document
+{
+    pub fn new(uri: &str, version: i64, text: &str) -> Self
+    {
+        let mut document = Self{
+            uri: String::from(uri),
+            version: version,
+            text: String::new(),
+            line_starts: Vec::new()
+        };
+        document.set_text(String::from(text));
+        return document;
+    }
+
+    pub fn get_text(&self) -> &str
+    {
+        return &self.text;
+    }
+
+    fn set_text(&mut self, text: String)
+    {
+        self.line_starts = std::iter::once(0).chain(text.match_indices('\n').map(|(offset, _)| offset + 1)).collect();
+        self.text = text;
+    }
+
+    // The byte offset of a position, positions past the end of a line or the document are clamped
+    pub fn offset(&self, position: Position) -> usize
+    {
+        let Some(&start) = self.line_starts.get(position.line) else { return self.text.len() };
+        let end = self.line_starts.get(position.line + 1).map(|next| next - 1).unwrap_or(self.text.len());
+        let line = self.text[start..end].trim_end_matches('\r');
+        let mut units = 0;
+        for (offset, c) in line.char_indices()
+        {
+            if units >= position.character
+            {
+                return start + offset;
+            }
+            units += c.len_utf16();
+        }
+        return start + line.len();
+    }
+
+    pub fn position(&self, offset: usize) -> Position
+    {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset)
+        {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].chars().map(char::len_utf16).sum();
+        return Position{ line: line, character: character };
+    }
+
+    pub fn range(&self, span: TextSpan) -> JsonValue
+    {
+        return JsonValue::object(vec![("start", self.position(span.start).to_json()), ("end", self.position(span.end).to_json())]);
+    }
+
+    // The span of a range of the protocol
+    pub fn span(&self, range: &JsonValue) -> Option<TextSpan>
+    {
+        let start = self.offset(Position::from_json(range.get("start")?)?);
+        let end = self.offset(Position::from_json(range.get("end")?)?);
+        return Some(TextSpan::new(start, end.max(start)));
+    }
+
+    // Replaces the range with the text, or the whole document when there is no range
+    pub fn apply_change(&mut self, range: Option<TextSpan>, text: &str)
+    {
+        match range
+        {
+            Some(span) => {
+                let mut changed = std::mem::take(&mut self.text);
+                changed.replace_range(span.start..span.end, text);
+                self.set_text(changed);
+            },
+            None => self.set_text(String::from(text))
+        }
+    }
+
+    // The path of a file:// document, includes are looked up next to it
+    pub fn path(&self) -> Option<PathBuf>
+    {
+        return uri_to_path(&self.uri);
+    }
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf>
+{
+    let path = uri.strip_prefix("file://")?;
+    // The host is empty or localhost
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let mut bytes = Vec::new();
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first()
+    {
+        let escaped = if byte == b'%' && tail.len() >= 2 { std::str::from_utf8(&tail[..2]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) } else { None };
+        match escaped
+        {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            },
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // file:///C:/shaders on Windows
+    let is_drive = path.len() > 2 && path.as_bytes()[2] == b':' && path.as_bytes()[1].is_ascii_alphabetic();
+    return Some(PathBuf::from(if is_drive { &path[1..] } else { &path }));
+}
@@ -0,0 +1,381 @@
+use std::fmt;
+
+// A JSON value, objects keep their members in order
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue
+{
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue
+{
+    pub fn parse(text: &str) -> Result<JsonValue, String>
+    {
+        let mut parser = JsonParser{ chars: text.char_indices().peekable(), text: text, depth: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if let Some((offset, _)) = parser.chars.peek()
+        {
+            return Err(format!("unexpected text at offset {}", offset));
+        }
+        return Ok(value);
+    }
+
+    pub fn object(members: Vec<(&str, JsonValue)>) -> JsonValue
+    {
+        return JsonValue::Object(members.into_iter().map(|(key, value)| (String::from(key), value)).collect());
+    }
+
+    // The member of an object, None for other values and missing members
+    pub fn get(&self, key: &str) -> Option<&JsonValue>
+    {
+        return match self
+        {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, value)| value),
+            _ => None
+        };
+    }
+
+    pub fn as_str(&self) -> Option<&str>
+    {
+        return match self
+        {
+            JsonValue::String(value) => Some(value),
+            _ => None
+        };
+    }
+
+    pub fn as_bool(&self) -> Option<bool>
+    {
+        return match self
+        {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None
+        };
+    }
+
+    pub fn as_i64(&self) -> Option<i64>
+    {
+        return match self
+        {
+            JsonValue::Number(value) if value.fract() == 0.0 => Some(*value as i64),
+            _ => None
+        };
+    }
+
+    pub fn as_usize(&self) -> Option<usize>
+    {
+        return self.as_i64().and_then(|value| usize::try_from(value).ok());
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>>
+    {
+        return match self
+        {
+            JsonValue::Array(values) => Some(values),
+            _ => None
+        };
+    }
+
+    pub fn is_null(&self) -> bool
+    {
+        return *self == JsonValue::Null;
+    }
+}
+
+impl From<&str> for JsonValue
+{
+    fn from(value: &str) -> Self
+    {
+        return JsonValue::String(String::from(value));
+    }
+}
+
+impl From<String> for JsonValue
+{
+    fn from(value: String) -> Self
+    {
+        return JsonValue::String(value);
+    }
+}
+
+impl From<bool> for JsonValue
+{
+    fn from(value: bool) -> Self
+    {
+        return JsonValue::Bool(value);
+    }
+}
+
+impl From<i64> for JsonValue
+{
+    fn from(value: i64) -> Self
+    {
+        return JsonValue::Number(value as f64);
+    }
+}
+
+impl From<usize> for JsonValue
+{
+    fn from(value: usize) -> Self
+    {
+        return JsonValue::Number(value as f64);
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue
+{
+    fn from(values: Vec<JsonValue>) -> Self
+    {
+        return JsonValue::Array(values);
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result
+{
+    f.write_str("\"")?;
+    for c in value.chars()
+    {
+        match c
+        {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?
+        }
+    }
+    return f.write_str("\"");
+}
+
+// Writes the value without whitespace
+impl fmt::Display for JsonValue
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(value) => write!(f, "{}", value),
+            // Integers are written without a fraction, ids and positions are integers
+            JsonValue::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => write!(f, "{}", *value as i64),
+            JsonValue::Number(value) if value.is_finite() => write!(f, "{}", value),
+            JsonValue::Number(_) => f.write_str("null"),
+            JsonValue::String(value) => write_string(f, value),
+            JsonValue::Array(values) => {
+                f.write_str("[")?;
+                for (index, value) in values.iter().enumerate()
+                {
+                    if index > 0
+                    {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            },
+            JsonValue::Object(members) => {
+                f.write_str("{")?;
+                for (index, (key, value)) in members.iter().enumerate()
+                {
+                    if index > 0
+                    {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+// Objects and arrays nested deeper than this are rejected, the parser would otherwise run out of stack
+const MAX_DEPTH: usize = 512;
+
+struct JsonParser<'a>
+{
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    text: &'a str,
+    // The objects and arrays the parser is inside of
+    depth: usize,
+}
+
+impl JsonParser<'_>
+{
+    fn skip_whitespace(&mut self)
+    {
+        while self.chars.next_if(|(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r')).is_some() {}
+    }
+
+    fn error(&mut self, expected: &str) -> String
+    {
+        return match self.chars.peek()
+        {
+            Some((offset, c)) => format!("expected {} but found '{}' at offset {}", expected, c, offset),
+            None => format!("expected {} but found the end of the text", expected)
+        };
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String>
+    {
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == expected).is_none()
+        {
+            return Err(self.error(&format!("'{}'", expected)));
+        }
+        return Ok(());
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String>
+    {
+        self.skip_whitespace();
+        let Some(&(offset, c)) = self.chars.peek() else { return Err(self.error("a value")) };
+        return match c
+        {
+            '{' | '[' => {
+                if self.depth == MAX_DEPTH
+                {
+                    return Err(String::from("nesting too deep"));
+                }
+                self.depth += 1;
+                let value = if c == '{' { self.parse_object() } else { self.parse_array() };
+                self.depth -= 1;
+                value
+            },
+            '"' => Ok(JsonValue::String(self.parse_string()?)),
+            '-' | '0'..='9' => self.parse_number(),
+            _ => {
+                for (word, value) in [("null", JsonValue::Null), ("true", JsonValue::Bool(true)), ("false", JsonValue::Bool(false))]
+                {
+                    if self.text[offset..].starts_with(word)
+                    {
+                        for _ in 0..word.len()
+                        {
+                            self.chars.next();
+                        }
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("a value"))
+            }
+        };
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String>
+    {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some()
+        {
+            return Ok(JsonValue::Object(members));
+        }
+        loop
+        {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.chars.next()
+            {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(JsonValue::Object(members)),
+                _ => return Err(String::from("expected ',' or '}' in an object"))
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String>
+    {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some()
+        {
+            return Ok(JsonValue::Array(values));
+        }
+        loop
+        {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.next()
+            {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(JsonValue::Array(values)),
+                _ => return Err(String::from("expected ',' or ']' in an array"))
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String>
+    {
+        let start = self.chars.peek().map(|(offset, _)| *offset).unwrap_or(self.text.len());
+        while self.chars.next_if(|(_, c)| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')).is_some() {}
+        let end = self.chars.peek().map(|(offset, _)| *offset).unwrap_or(self.text.len());
+        return self.text[start..end].parse().map(JsonValue::Number).map_err(|_| format!("invalid number '{}'", &self.text[start..end]));
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, String>
+    {
+        let mut value = 0;
+        for _ in 0..4
+        {
+            let digit = self.chars.next().and_then(|(_, c)| c.to_digit(16)).ok_or_else(|| String::from("invalid unicode escape"))?;
+            value = value * 16 + digit;
+        }
+        return Ok(value);
+    }
+
+    fn parse_string(&mut self) -> Result<String, String>
+    {
+        if self.chars.next_if(|(_, c)| *c == '"').is_none()
+        {
+            return Err(self.error("a string"));
+        }
+        let mut value = String::new();
+        loop
+        {
+            match self.chars.next()
+            {
+                Some((_, '"')) => return Ok(value),
+                Some((_, '\\')) => {
+                    let escaped = match self.chars.next()
+                    {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'u')) => {
+                            let mut code = self.parse_hex()?;
+                            // Characters outside the basic plane are written as a surrogate pair
+                            if (0xd800..0xdc00).contains(&code) && self.text[self.chars.peek().map(|(o, _)| *o).unwrap_or(self.text.len())..].starts_with("\\u")
+                            {
+                                self.chars.next();
+                                self.chars.next();
+                                let low = self.parse_hex()?;
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                            }
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        },
+                        Some((_, c)) => c,
+                        None => return Err(String::from("unterminated string"))
+                    };
+                    value.push(escaped);
+                },
+                Some((_, c)) => value.push(c),
+                None => return Err(String::from("unterminated string"))
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod json;
+pub mod transport;
+pub mod document;
+pub mod server;
+
+pub use json::JsonValue;
+pub use server::LanguageServer;
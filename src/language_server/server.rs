@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::formatting::{FormatSettings, Formatter, TextEdit};
+use crate::parser::{diagnostics::{Diagnostic, DiagnosticSeverity}, syntax_parser::SyntaxTree, syntax_tokens::TextSpan, ParserSettings};
+use crate::semantics::{binder::Binder, type_checker::TypeChecker};
+
+use super::document::{uri_to_path, Document, Position};
+use super::json::JsonValue;
+use super::transport::{read_message, write_message};
+
+// Error codes of JSON-RPC and the protocol
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+// Full and incremental document sync
+const SYNC_INCREMENTAL: usize = 2;
+
+pub fn response(id: &JsonValue, result: JsonValue) -> JsonValue
+{
+    return JsonValue::object(vec![("jsonrpc", "2.0".into()), ("id", id.clone()), ("result", result)]);
+}
+
+pub fn error_response(id: &JsonValue, code: i64, message: &str) -> JsonValue
+{
+    let error = JsonValue::object(vec![("code", code.into()), ("message", message.into())]);
+    return JsonValue::object(vec![("jsonrpc", "2.0".into()), ("id", id.clone()), ("error", error)]);
+}
+
+pub fn notification(method: &str, params: JsonValue) -> JsonValue
+{
+    return JsonValue::object(vec![("jsonrpc", "2.0".into()), ("method", method.into()), ("params", params)]);
+}
+
+// LanguageServer
+// Keeps the documents the client has open and answers its messages. Every change to a document publishes the diagnostics of
+// the preprocessor, parser, binder and type checker for it
+pub struct LanguageServer
+{
+    documents: HashMap<String, Document>,
+    settings: ParserSettings,
+    format_settings: FormatSettings,
+    initialized: bool,
+    shutdown_requested: bool,
+    exit_code: Option<i32>,
+}
+
+impl LanguageServer
+{
+    pub fn new(settings: ParserSettings) -> Self
+    {
+        return Self{
+            documents: HashMap::new(),
+            settings: settings,
+            format_settings: FormatSettings::default(),
+            initialized: false,
+            shutdown_requested: false,
+            exit_code: None
+        };
+    }
+
+    // Set after the exit notification, 0 when a shutdown request came first
+    pub fn get_exit_code(&self) -> Option<i32>
+    {
+        return self.exit_code;
+    }
+
+    pub fn get_settings(&self) -> &ParserSettings
+    {
+        return &self.settings;
+    }
+
+    pub fn get_document(&self, uri: &str) -> Option<&Document>
+    {
+        return self.documents.get(uri);
+    }
+
+    // Serves the client until it sends exit or closes the stream, returns the exit code of the process
+    pub fn run(&mut self, reader: &mut impl BufRead, writer: &mut impl Write) -> i32
+    {
+        loop
+        {
+            let body = match read_message(reader)
+            {
+                Ok(Some(body)) => body,
+                Ok(None) => return 1,
+                Err(error) => {
+                    eprintln!("hlsl-language-server: {}", error);
+                    return 1;
+                }
+            };
+            let replies = match JsonValue::parse(&body)
+            {
+                Ok(message) => self.handle_message(&message),
+                Err(error) => vec![error_response(&JsonValue::Null, PARSE_ERROR, &error)]
+            };
+            for reply in &replies
+            {
+                if write_message(writer, reply).is_err()
+                {
+                    return 1;
+                }
+            }
+            if let Some(code) = self.exit_code
+            {
+                return code;
+            }
+        }
+    }
+
+    // Handles a request or notification, returns the response and notifications for the client
+    pub fn handle_message(&mut self, message: &JsonValue) -> Vec<JsonValue>
+    {
+        let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
+            // Responses to requests of the server are not expected
+            return Vec::new();
+        };
+        let params = message.get("params").unwrap_or(&JsonValue::Null);
+        let Some(id) = message.get("id") else {
+            return self.handle_notification(method, params);
+        };
+
+        if method == "initialize"
+        {
+            if self.initialized
+            {
+                return vec![error_response(id, INVALID_REQUEST, "the server is already initialized")];
+            }
+            self.initialized = true;
+            return vec![response(id, self.initialize(params))];
+        }
+        if !self.initialized
+        {
+            return vec![error_response(id, SERVER_NOT_INITIALIZED, "the server is not initialized")];
+        }
+        if self.shutdown_requested
+        {
+            return vec![error_response(id, INVALID_REQUEST, "the server is shutting down")];
+        }
+
+        let result = match method
+        {
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(JsonValue::Null)
+            },
+            "textDocument/formatting" => self.format(params, |document, text, parser_settings, settings| {
+                Formatter::format_range(text, parser_settings, settings, TextSpan::new(0, document.get_text().len()))
+            }),
+            "textDocument/rangeFormatting" => {
+                let range = params.get("range");
+                self.format(params, |document, text, parser_settings, settings| {
+                    match range.and_then(|r| document.span(r))
+                    {
+                        Some(span) => Formatter::format_range(text, parser_settings, settings, span),
+                        None => Ok(Vec::new())
+                    }
+                })
+            },
+            "textDocument/onTypeFormatting" => {
+                let position = params.get("position").and_then(Position::from_json);
+                self.format(params, |document, text, parser_settings, settings| {
+                    match position
+                    {
+                        Some(position) => Formatter::format_on_type(text, parser_settings, settings, document.offset(position)),
+                        None => Ok(Vec::new())
+                    }
+                })
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method)))
+        };
+        return match result
+        {
+            Ok(result) => vec![response(id, result)],
+            Err((code, message)) => vec![error_response(id, code, &message)]
+        };
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &JsonValue) -> Vec<JsonValue>
+    {
+        if method == "exit"
+        {
+            self.exit_code = Some(if self.shutdown_requested { 0 } else { 1 });
+            return Vec::new();
+        }
+        if !self.initialized
+        {
+            return Vec::new();
+        }
+
+        let uri = params.get("textDocument").and_then(|d| d.get("uri")).and_then(|u| u.as_str()).unwrap_or("");
+        match method
+        {
+            "textDocument/didOpen" => {
+                let Some(document) = params.get("textDocument") else { return Vec::new() };
+                let version = document.get("version").and_then(|v| v.as_i64()).unwrap_or(0);
+                let text = document.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                self.documents.insert(String::from(uri), Document::new(uri, version, text));
+                return self.publish_diagnostics(uri);
+            },
+            "textDocument/didChange" => {
+                let Some(document) = self.documents.get_mut(uri) else { return Vec::new() };
+                let changes = params.get("contentChanges").and_then(|c| c.as_array()).map(|c| c.as_slice()).unwrap_or(&[]);
+                for change in changes
+                {
+                    let range = change.get("range").and_then(|r| document.span(r));
+                    document.apply_change(range, change.get("text").and_then(|t| t.as_str()).unwrap_or(""));
+                }
+                if let Some(version) = params.get("textDocument").and_then(|d| d.get("version")).and_then(|v| v.as_i64())
+                {
+                    document.version = version;
+                }
+                return self.publish_diagnostics(uri);
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                let params = JsonValue::object(vec![("uri", uri.into()), ("diagnostics", JsonValue::Array(Vec::new()))]);
+                return vec![notification("textDocument/publishDiagnostics", params)];
+            },
+            _ => return Vec::new()
+        }
+    }
+
+    fn initialize(&mut self, params: &JsonValue) -> JsonValue
+    {
+        // Includes are looked up next to the document, then in the workspace and the include paths of the client
+        let mut include_paths: Vec<PathBuf> = params.get("rootUri").and_then(|u| u.as_str()).and_then(uri_to_path).into_iter().collect();
+        if let Some(options) = params.get("initializationOptions")
+        {
+            for path in options.get("includePaths").and_then(|p| p.as_array()).into_iter().flatten()
+            {
+                if let Some(path) = path.as_str()
+                {
+                    include_paths.push(PathBuf::from(path));
+                }
+            }
+            if let Some(JsonValue::Object(defines)) = options.get("defines")
+            {
+                for (name, value) in defines
+                {
+                    self.settings.defines.push((name.clone(), value.as_str().map(String::from).unwrap_or_else(|| value.to_string())));
+                }
+            }
+            if let Some(config) = options.get("formatConfig").and_then(|c| c.as_str())
+            {
+                match FormatSettings::from_config(config)
+                {
+                    Ok(settings) => self.format_settings = settings,
+                    Err(error) => eprintln!("hlsl-language-server: format config {}", error)
+                }
+            }
+        }
+        self.settings.include_paths = include_paths;
+
+        let on_type = JsonValue::object(vec![("firstTriggerCharacter", ";".into()), ("moreTriggerCharacter", vec!["}".into()].into())]);
+        let sync = JsonValue::object(vec![("openClose", true.into()), ("change", SYNC_INCREMENTAL.into())]);
+        let capabilities = JsonValue::object(vec![
+            ("textDocumentSync", sync),
+            ("documentFormattingProvider", true.into()),
+            ("documentRangeFormattingProvider", true.into()),
+            ("documentOnTypeFormattingProvider", on_type),
+        ]);
+        let server_info = JsonValue::object(vec![("name", "hlsl-language-server".into()), ("version", env!("CARGO_PKG_VERSION").into())]);
+        return JsonValue::object(vec![("capabilities", capabilities), ("serverInfo", server_info)]);
+    }
+
+    // Parser settings for a document, includes next to it come first
+    fn document_settings(&self, document: &Document) -> ParserSettings
+    {
+        let mut settings = self.settings.clone();
+        if let Some(directory) = document.path().as_ref().and_then(|p| p.parent())
+        {
+            settings.include_paths.insert(0, directory.to_path_buf());
+        }
+        return settings;
+    }
+
+    // The diagnostics of every stage for the text of the document
+    pub fn analyze(&self, document: &Document) -> Vec<Diagnostic>
+    {
+        let settings = self.document_settings(document);
+        let tree = SyntaxTree::parse_text(document.get_text(), &settings);
+        let mut diagnostics = tree.diagnostics.clone();
+        let table = Binder::bind(tree.compilation_unit(), &settings);
+        diagnostics.extend(table.get_diagnostics().iter().cloned());
+        let checker = TypeChecker::new(tree.compilation_unit(), &table, &settings);
+        diagnostics.extend(checker.get_diagnostics().iter().cloned());
+
+        // Diagnostics in included files belong to those files
+        diagnostics.retain(|d| d.file_index == 0);
+        return diagnostics;
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Vec<JsonValue>
+    {
+        let Some(document) = self.documents.get(uri) else { return Vec::new() };
+        let diagnostics: Vec<JsonValue> = self.analyze(document).iter().map(|d| diagnostic_to_json(document, d)).collect();
+        let params = JsonValue::object(vec![("uri", uri.into()), ("version", document.version.into()), ("diagnostics", diagnostics.into())]);
+        return vec![notification("textDocument/publishDiagnostics", params)];
+    }
+
+    fn format(&self, params: &JsonValue, edits: impl FnOnce(&Document, &str, &ParserSettings, &FormatSettings) -> Result<Vec<TextEdit>, Vec<Diagnostic>>)
+        -> Result<JsonValue, (i64, String)>
+    {
+        let uri = params.get("textDocument").and_then(|d| d.get("uri")).and_then(|u| u.as_str()).unwrap_or("");
+        let Some(document) = self.documents.get(uri) else {
+            return Err((INVALID_PARAMS, format!("'{}' is not open", uri)));
+        };
+
+        // The options of the request override the indentation of the config
+        let mut settings = self.format_settings.clone();
+        if let Some(options) = params.get("options")
+        {
+            if let Some(tab_size) = options.get("tabSize").and_then(|t| t.as_usize()).filter(|t| *t > 0)
+            {
+                settings.indent_width = tab_size;
+            }
+            if let Some(insert_spaces) = options.get("insertSpaces").and_then(|s| s.as_bool())
+            {
+                settings.use_tabs = !insert_spaces;
+            }
+        }
+
+        // Text with syntax errors is not formatted, the client shows the errors already
+        let edits = edits(document, document.get_text(), &self.document_settings(document), &settings).unwrap_or_default();
+        let edits = edits.iter().map(|e| JsonValue::object(vec![("range", document.range(e.span)), ("newText", e.new_text.as_str().into())])).collect();
+        return Ok(JsonValue::Array(edits));
+    }
+}
+
+fn diagnostic_to_json(document: &Document, diagnostic: &Diagnostic) -> JsonValue
+{
+    let severity: usize = match diagnostic.severity
+    {
+        DiagnosticSeverity::Error => 1,
+        DiagnosticSeverity::Warning => 2,
+        DiagnosticSeverity::Info => 3,
+        DiagnosticSeverity::Hint => 4
+    };
+    let mut members = vec![
+        ("range", document.range(diagnostic.span)),
+        ("severity", severity.into()),
+        ("source", "hlsl".into()),
+        ("message", diagnostic.message.as_str().into()),
+    ];
+    if let Some(code) = diagnostic.code
+    {
+        members.push(("code", code.into()));
+    }
+    // Notes like the candidates of an ambiguous call
+    let related: Vec<JsonValue> = diagnostic.notes.iter().filter(|n| n.file_index == 0).map(|note| {
+        let location = JsonValue::object(vec![("uri", document.uri.as_str().into()), ("range", document.range(note.span))]);
+        JsonValue::object(vec![("location", location), ("message", note.message.as_str().into())])
+    }).collect();
+    if !related.is_empty()
+    {
+        members.push(("relatedInformation", related.into()));
+    }
+    return JsonValue::object(members);
+}
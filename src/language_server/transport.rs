@@ -0,0 +1,60 @@
+use std::io::{self, BufRead, Write};
+
+use super::json::JsonValue;
+
+// Larger bodies are refused instead of allocated, no editor sends documents of this size
+const MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+// Reads the body of the next message, a 'Content-Length' header and an empty line come before it. None when the stream ended
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>>
+{
+    let mut content_length = None;
+    let mut has_headers = false;
+    loop
+    {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0
+        {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty()
+        {
+            // Blank lines in front of the headers are skipped, the first one after them ends the headers
+            if has_headers
+            {
+                break;
+            }
+            continue;
+        }
+        has_headers = true;
+        if let Some((name, value)) = line.split_once(':')
+        {
+            if name.trim().eq_ignore_ascii_case("Content-Length")
+            {
+                let length: usize = value.trim().parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid Content-Length '{}'", value.trim())))?;
+                if length > MAX_CONTENT_LENGTH
+                {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Content-Length {} is larger than {} bytes", length, MAX_CONTENT_LENGTH)));
+                }
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let content_length = match content_length
+    {
+        Some(length) => length,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, "the message has no Content-Length header"))
+    };
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    return String::from_utf8(body).map(Some).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the message is not UTF-8"));
+}
+
+pub fn write_message(writer: &mut impl Write, message: &JsonValue) -> io::Result<()>
+{
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    return writer.flush();
+}
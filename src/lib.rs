@@ -1,6 +1,9 @@
 #![allow(dead_code)]
 
 pub mod parser;
+pub mod semantics;
+pub mod formatting;
+pub mod language_server;
 mod tests;
 
 pub fn add(left: u64, right: u64) -> u64 {
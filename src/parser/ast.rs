@@ -0,0 +1,1320 @@
+use super::{syntax_nodes::{SyntaxElement, SyntaxNode, SyntaxNodeType}, syntax_tokens::{SyntaxToken, SyntaxTokenType, TextSpan}};
+
+// AstNode
+// Typed view over a node of the concrete syntax tree. The views only borrow the tree, nothing is copied,
+// and every accessor returns either another view or a token, so spans are available for everything
+pub trait AstNode<'a>: Sized
+{
+    fn cast(node: &'a SyntaxNode) -> Option<Self>;
+    fn syntax(&self) -> &'a SyntaxNode;
+
+    fn span(&self) -> TextSpan
+    {
+        return self.syntax().span();
+    }
+
+    fn file_index(&self) -> usize
+    {
+        return self.syntax().file_index();
+    }
+}
+
+// Declares a view struct for the given node types
+macro_rules! ast_node {
+    ($name:ident, $($node_type:ident)|+) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<'a>
+        {
+            node: &'a SyntaxNode,
+        }
+
+        impl<'a> AstNode<'a> for $name<'a>
+        {
+            fn cast(node: &'a SyntaxNode) -> Option<Self>
+            {
+                if matches!(node.node_type, $(SyntaxNodeType::$node_type)|+)
+                {
+                    return Some(Self{ node: node });
+                }
+                return None;
+            }
+
+            fn syntax(&self) -> &'a SyntaxNode
+            {
+                return self.node;
+            }
+        }
+    };
+}
+
+// Declares an enum over views, casting to the first variant that accepts the node
+macro_rules! ast_enum {
+    ($name:ident { $($variant:ident($view:ident)),+ $(,)? }) => {
+        #[derive(Clone, Copy, Debug)]
+        pub enum $name<'a>
+        {
+            $($variant($view<'a>)),+
+        }
+
+        impl<'a> AstNode<'a> for $name<'a>
+        {
+            fn cast(node: &'a SyntaxNode) -> Option<Self>
+            {
+                $(
+                    if let Some(view) = $view::cast(node)
+                    {
+                        return Some($name::$variant(view));
+                    }
+                )+
+                return None;
+            }
+
+            fn syntax(&self) -> &'a SyntaxNode
+            {
+                return match self
+                {
+                    $($name::$variant(view) => view.syntax()),+
+                };
+            }
+        }
+    };
+}
+
+fn children<'a, T: AstNode<'a> + 'a>(node: &'a SyntaxNode) -> impl Iterator<Item = T> + 'a
+{
+    return node.child_nodes().filter_map(T::cast);
+}
+
+fn child<'a, T: AstNode<'a>>(node: &'a SyntaxNode) -> Option<T>
+{
+    return node.child_nodes().find_map(T::cast);
+}
+
+fn nth_node(node: &SyntaxNode, index: usize) -> Option<&SyntaxNode>
+{
+    return node.child_nodes().nth(index);
+}
+
+fn token_of<'a>(node: &'a SyntaxNode, token_type: &SyntaxTokenType) -> Option<&'a SyntaxToken>
+{
+    return node.child_tokens().find(|t| t.token_type == *token_type && !t.is_missing);
+}
+
+// The first token that is not punctuation, used for the keyword or name at the start of a node
+fn first_word(node: &SyntaxNode) -> Option<&SyntaxToken>
+{
+    return node.child_tokens().find(|t| t.is_identifier_like() && !t.is_missing);
+}
+
+// Attributes of all attribute lists in front of a declaration or statement
+fn attributes<'a>(node: &'a SyntaxNode) -> impl Iterator<Item = Attribute<'a>> + 'a
+{
+    return children::<AttributeList>(node).flat_map(|list| children(list.node));
+}
+
+// Modifier keywords written directly in front of the type of a declaration
+fn modifiers(node: &SyntaxNode) -> Vec<&SyntaxToken>
+{
+    return node.child_tokens().take_while(|t| t.is_identifier_like()).collect();
+}
+
+// Declarations
+
+ast_node!(CompilationUnit, CompilationUnit);
+ast_node!(FunctionDecl, FunctionDeclaration | FunctionDefinition);
+ast_node!(ParameterList, ParameterList);
+ast_node!(Parameter, Parameter);
+ast_node!(VariableDecl, VariableDeclarationStatement);
+ast_node!(VariableDeclarator, VariableDeclarator);
+ast_node!(ArrayRank, ArrayRankSpecifier);
+ast_node!(Semantic, Semantic);
+ast_node!(RegisterLocation, RegisterLocation);
+ast_node!(PackOffsetLocation, PackOffsetLocation);
+ast_node!(TypeDecl, TypeDeclarationStatement);
+ast_node!(StructDecl, StructType);
+ast_node!(ConstantBufferDecl, ConstantBufferDeclaration);
+ast_node!(NamespaceDecl, NamespaceDeclaration);
+ast_node!(TypedefDecl, TypedefStatement);
+ast_node!(TypedefDeclarator, TypedefDeclarator);
+ast_node!(AttributeList, AttributeList);
+ast_node!(Attribute, Attribute);
+ast_node!(TechniqueDecl, TechniqueDeclaration);
+ast_node!(PassDecl, PassDeclaration);
+ast_node!(Annotations, Annotations);
+ast_node!(StateBlock, StateBlock);
+ast_node!(StateAssignment, StateAssignment);
+ast_node!(SkippedTokens, SkippedTokens);
+ast_node!(TemplateDecl, TemplateDeclaration);
+ast_node!(TemplateParameter, TemplateParameter);
+
+ast_enum!(Declaration {
+    Function(FunctionDecl),
+    Variable(VariableDecl),
+    Type(TypeDecl),
+    ConstantBuffer(ConstantBufferDecl),
+    Namespace(NamespaceDecl),
+    Typedef(TypedefDecl),
+    Technique(TechniqueDecl),
+    Template(TemplateDecl),
+    Skipped(SkippedTokens),
+});
+
+impl<'a> CompilationUnit<'a>
+{
+    pub fn declarations(&self) -> impl Iterator<Item = Declaration<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    // All functions, including the ones declared in namespaces
+    pub fn functions(&self) -> Vec<FunctionDecl<'a>>
+    {
+        return self.node.descendant_nodes().into_iter().filter_map(FunctionDecl::cast).collect();
+    }
+}
+
+impl<'a> FunctionDecl<'a>
+{
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a
+    {
+        return attributes(self.node);
+    }
+
+    pub fn modifiers(&self) -> Vec<&'a SyntaxToken>
+    {
+        return modifiers(self.node);
+    }
+
+    pub fn return_type(&self) -> Option<TypeSyntax<'a>>
+    {
+        return child(self.node);
+    }
+
+    // The plain or qualified name, like Outer::MyClass::TestFunc
+    pub fn name(&self) -> Option<Name<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn parameter_list(&self) -> Option<ParameterList<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn params(&self) -> Vec<Parameter<'a>>
+    {
+        return match self.parameter_list()
+        {
+            Some(list) => list.params().collect(),
+            None => Vec::new()
+        };
+    }
+
+    pub fn semantic(&self) -> Option<Semantic<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn body(&self) -> Option<Block<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn is_definition(&self) -> bool
+    {
+        return self.node.node_type == SyntaxNodeType::FunctionDefinition;
+    }
+}
+
+impl<'a> ParameterList<'a>
+{
+    pub fn params(&self) -> impl Iterator<Item = Parameter<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> Parameter<'a>
+{
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a
+    {
+        return attributes(self.node);
+    }
+
+    // in, out, inout, uniform and the geometry and mesh shader modifiers
+    pub fn modifiers(&self) -> Vec<&'a SyntaxToken>
+    {
+        return modifiers(self.node);
+    }
+
+    pub fn param_type(&self) -> Option<TypeSyntax<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn declarator(&self) -> Option<VariableDeclarator<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.declarator().and_then(|d| d.name());
+    }
+
+    pub fn semantic(&self) -> Option<Semantic<'a>>
+    {
+        return self.declarator().and_then(|d| d.semantic());
+    }
+
+    pub fn default_value(&self) -> Option<Expression<'a>>
+    {
+        return self.declarator().and_then(|d| d.initializer());
+    }
+}
+
+impl<'a> VariableDecl<'a>
+{
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a
+    {
+        return attributes(self.node);
+    }
+
+    pub fn modifiers(&self) -> Vec<&'a SyntaxToken>
+    {
+        return modifiers(self.node);
+    }
+
+    pub fn var_type(&self) -> Option<TypeSyntax<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn declarators(&self) -> impl Iterator<Item = VariableDeclarator<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> VariableDeclarator<'a>
+{
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().next().filter(|t| !t.is_missing);
+    }
+
+    pub fn array_ranks(&self) -> impl Iterator<Item = ArrayRank<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    pub fn semantic(&self) -> Option<Semantic<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn register(&self) -> Option<RegisterLocation<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn packoffset(&self) -> Option<PackOffsetLocation<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn annotations(&self) -> Option<Annotations<'a>>
+    {
+        return child(self.node);
+    }
+
+    // The width of a bitfield, like the 4 in 'uint flags : 4'
+    pub fn bit_width(&self) -> Option<Expression<'a>>
+    {
+        let bit_field = self.node.find_node(SyntaxNodeType::BitField)?;
+        return child(bit_field);
+    }
+
+    // The value after '='
+    pub fn initializer(&self) -> Option<Expression<'a>>
+    {
+        let clause = self.node.find_node(SyntaxNodeType::EqualsValueClause)?;
+        return child(clause);
+    }
+
+    // State objects like 'BlendState b { ... }' are initialized with a state block
+    pub fn state_block(&self) -> Option<StateBlock<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> ArrayRank<'a>
+{
+    // None for unsized arrays
+    pub fn size(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> Semantic<'a>
+{
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return first_word(self.node);
+    }
+}
+
+impl<'a> RegisterLocation<'a>
+{
+    // The tokens in between the parentheses, like 't0', ',' and 'space1'
+    pub fn arguments(&self) -> Vec<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().skip(3).filter(|t| t.token_type != SyntaxTokenType::CloseParenToken && !t.is_missing).collect();
+    }
+
+    // The register like t0, b3 or s1[2], profile specific registers like register(ps_5_0, s0) return the register
+    pub fn register(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.arguments().into_iter().rev().find(|t| t.is_identifier_like() && !t.text.starts_with("space"));
+    }
+
+    pub fn space(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.arguments().into_iter().find(|t| t.is_identifier_like() && t.text.starts_with("space"));
+    }
+}
+
+impl<'a> PackOffsetLocation<'a>
+{
+    pub fn arguments(&self) -> Vec<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().skip(3).filter(|t| t.token_type != SyntaxTokenType::CloseParenToken && !t.is_missing).collect();
+    }
+}
+
+impl<'a> TypeDecl<'a>
+{
+    pub fn struct_decl(&self) -> Option<StructDecl<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StructKind
+{
+    Struct,
+    Class,
+    Interface,
+}
+
+impl<'a> StructDecl<'a>
+{
+    pub fn keyword(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().next();
+    }
+
+    pub fn kind(&self) -> StructKind
+    {
+        return match self.keyword().map(|t| &t.token_type)
+        {
+            Some(SyntaxTokenType::ClassKeyword) => StructKind::Class,
+            Some(SyntaxTokenType::InterfaceKeyword) => StructKind::Interface,
+            _ => StructKind::Struct
+        };
+    }
+
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().nth(1).filter(|t| t.is_identifier_like() && !t.is_missing);
+    }
+
+    pub fn base_list(&self) -> Vec<Name<'a>>
+    {
+        return match self.node.find_node(SyntaxNodeType::BaseList)
+        {
+            Some(list) => children(list).collect(),
+            None => Vec::new()
+        };
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = Declaration<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = VariableDecl<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = FunctionDecl<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> ConstantBufferDecl<'a>
+{
+    // Attributes like [[vk::binding(0, 1)]] in front of the keyword
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a
+    {
+        return attributes(self.node);
+    }
+
+    // cbuffer or tbuffer
+    pub fn keyword(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().next();
+    }
+
+    pub fn is_tbuffer(&self) -> bool
+    {
+        return token_of(self.node, &SyntaxTokenType::TBufferKeyword).is_some();
+    }
+
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().nth(1).filter(|t| !t.is_missing);
+    }
+
+    pub fn register(&self) -> Option<RegisterLocation<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = VariableDecl<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> NamespaceDecl<'a>
+{
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().nth(1).filter(|t| !t.is_missing);
+    }
+
+    pub fn declarations(&self) -> impl Iterator<Item = Declaration<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> TypedefDecl<'a>
+{
+    // Modifiers after the typedef keyword, like 'const' in typedef const float Scale;
+    pub fn modifiers(&self) -> Vec<&'a SyntaxToken>
+    {
+        return modifiers(self.node).into_iter().skip(1).collect();
+    }
+
+    pub fn aliased_type(&self) -> Option<TypeSyntax<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn declarators(&self) -> impl Iterator<Item = TypedefDeclarator<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> TypedefDeclarator<'a>
+{
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().next().filter(|t| !t.is_missing);
+    }
+
+    pub fn array_ranks(&self) -> impl Iterator<Item = ArrayRank<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> AttributeList<'a>
+{
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> Attribute<'a>
+{
+    // numthreads, unroll or vk::binding
+    pub fn name(&self) -> Option<Name<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn arguments(&self) -> Vec<Expression<'a>>
+    {
+        return match self.node.find_node(SyntaxNodeType::AttributeArgumentList)
+        {
+            Some(list) => children(list).collect(),
+            None => Vec::new()
+        };
+    }
+}
+
+impl<'a> TechniqueDecl<'a>
+{
+    // technique, technique10 or technique11
+    pub fn keyword(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().next();
+    }
+
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().nth(1).filter(|t| t.is_identifier_like());
+    }
+
+    pub fn annotations(&self) -> Option<Annotations<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn passes(&self) -> impl Iterator<Item = PassDecl<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> PassDecl<'a>
+{
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().nth(1).filter(|t| t.is_identifier_like());
+    }
+
+    pub fn annotations(&self) -> Option<Annotations<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn state_block(&self) -> Option<StateBlock<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> Annotations<'a>
+{
+    pub fn declarations(&self) -> impl Iterator<Item = VariableDecl<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> StateBlock<'a>
+{
+    pub fn assignments(&self) -> impl Iterator<Item = StateAssignment<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    // The blocks of the elements of a state object array
+    pub fn elements(&self) -> impl Iterator<Item = StateBlock<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    // Calls like SetVertexShader(CompileShader(vs_4_0, VS()))
+    pub fn calls(&self) -> impl Iterator<Item = Expression<'a>> + 'a
+    {
+        return children::<ExpressionStatement>(self.node).filter_map(|s| s.expression());
+    }
+}
+
+impl<'a> StateAssignment<'a>
+{
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().next();
+    }
+
+    // BlendEnable[0] = TRUE;
+    pub fn index(&self) -> Option<Expression<'a>>
+    {
+        let rank: ArrayRank = child(self.node)?;
+        return rank.size();
+    }
+
+    pub fn value(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> TemplateDecl<'a>
+{
+    pub fn parameters(&self) -> Vec<TemplateParameter<'a>>
+    {
+        return match self.node.find_node(SyntaxNodeType::TemplateParameterList)
+        {
+            Some(list) => children(list).collect(),
+            None => Vec::new()
+        };
+    }
+
+    // The templated struct or function
+    pub fn declaration(&self) -> Option<Declaration<'a>>
+    {
+        return self.node.child_nodes().last().and_then(Declaration::cast);
+    }
+}
+
+impl<'a> TemplateParameter<'a>
+{
+    // typename T and class T, as opposed to values like int N
+    pub fn is_type_parameter(&self) -> bool
+    {
+        return self.node.child_tokens().next().map(|t| matches!(t.token_type, SyntaxTokenType::TypenameKeyword | SyntaxTokenType::ClassKeyword)).unwrap_or(false);
+    }
+
+    pub fn name(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().filter(|t| !t.is_missing).last().filter(|t| t.token_type == SyntaxTokenType::IdentifierToken);
+    }
+
+    // The type of a value parameter
+    pub fn value_type(&self) -> Option<TypeSyntax<'a>>
+    {
+        return child(self.node);
+    }
+
+    // The type or value after '='
+    pub fn default(&self) -> Option<&'a SyntaxNode>
+    {
+        let clause = self.node.find_node(SyntaxNodeType::EqualsValueClause)?;
+        return clause.child_nodes().next();
+    }
+}
+
+impl<'a> SkippedTokens<'a>
+{
+    pub fn tokens(&self) -> impl Iterator<Item = &'a SyntaxToken> + 'a
+    {
+        return self.node.child_tokens();
+    }
+}
+
+// Types
+
+ast_node!(PredefinedType, PredefinedType);
+ast_node!(UserType, UserType);
+ast_node!(TypeArgumentList, TypeArgumentList);
+
+ast_enum!(TypeSyntax {
+    Predefined(PredefinedType),
+    User(UserType),
+    Struct(StructDecl),
+});
+
+impl<'a> PredefinedType<'a>
+{
+    // The keyword naming the type, for 'unsigned int' this is 'int'
+    pub fn keyword(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().last();
+    }
+
+    pub fn type_arguments(&self) -> Option<TypeArgumentList<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> UserType<'a>
+{
+    pub fn name(&self) -> Option<Name<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn type_arguments(&self) -> Option<TypeArgumentList<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> TypeArgumentList<'a>
+{
+    pub fn types(&self) -> impl Iterator<Item = TypeSyntax<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    // Arguments that are values instead of types, like the 4 in vector<float, 4>
+    pub fn values(&self) -> impl Iterator<Item = Expression<'a>> + 'a
+    {
+        return children(self.node).filter(|e| !matches!(e, Expression::Type(_)));
+    }
+
+    // All arguments in order, the types are type expressions
+    pub fn arguments(&self) -> impl Iterator<Item = Expression<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+// Names
+
+ast_node!(IdentifierName, IdentifierName);
+ast_node!(QualifiedName, QualifiedName);
+ast_node!(OperatorName, OperatorName);
+
+ast_enum!(Name {
+    Identifier(IdentifierName),
+    Qualified(QualifiedName),
+    Operator(OperatorName),
+});
+
+impl<'a> IdentifierName<'a>
+{
+    pub fn identifier(&self) -> &'a SyntaxToken
+    {
+        return self.node.child_tokens().next().unwrap();
+    }
+}
+
+impl<'a> QualifiedName<'a>
+{
+    // None for names starting at the global namespace, like ::Name
+    pub fn left(&self) -> Option<Name<'a>>
+    {
+        let first = self.node.child_nodes().next()?;
+        if self.node.child_nodes().count() < 2
+        {
+            return None;
+        }
+        return Name::cast(first);
+    }
+
+    pub fn right(&self) -> Option<IdentifierName<'a>>
+    {
+        return self.node.child_nodes().last().and_then(IdentifierName::cast);
+    }
+}
+
+impl<'a> OperatorName<'a>
+{
+    // The tokens after the operator keyword, like '+' or '[' ']'
+    pub fn operator_tokens(&self) -> Vec<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().skip(1).collect();
+    }
+}
+
+impl<'a> Name<'a>
+{
+    // The last identifier, TestFunc in Outer::MyClass::TestFunc
+    pub fn identifier(&self) -> Option<&'a SyntaxToken>
+    {
+        return match self
+        {
+            Name::Identifier(name) => Some(name.identifier()),
+            Name::Qualified(name) => name.right().map(|n| n.identifier()),
+            Name::Operator(_) => None
+        };
+    }
+
+    // All parts of the name, without the separators
+    pub fn parts(&self) -> Vec<&'a SyntaxToken>
+    {
+        return self.syntax().descendant_tokens().into_iter().filter(|t| t.token_type != SyntaxTokenType::ColonColonToken).collect();
+    }
+
+    pub fn is_global(&self) -> bool
+    {
+        return self.syntax().first_token().map(|t| t.token_type == SyntaxTokenType::ColonColonToken).unwrap_or(false);
+    }
+
+    pub fn text(&self) -> String
+    {
+        return self.syntax().descendant_tokens().iter().map(|t| t.text.as_str()).collect();
+    }
+}
+
+// Statements
+
+ast_node!(Block, Block);
+ast_node!(EmptyStatement, EmptyStatement);
+ast_node!(ExpressionStatement, ExpressionStatement);
+ast_node!(ReturnStatement, ReturnStatement);
+ast_node!(IfStatement, IfStatement);
+ast_node!(ElseClause, ElseClause);
+ast_node!(ForStatement, ForStatement);
+ast_node!(WhileStatement, WhileStatement);
+ast_node!(DoStatement, DoStatement);
+ast_node!(SwitchStatement, SwitchStatement);
+ast_node!(SwitchSection, SwitchSection);
+ast_node!(CaseLabel, CaseLabel | DefaultLabel);
+ast_node!(BreakStatement, BreakStatement);
+ast_node!(ContinueStatement, ContinueStatement);
+ast_node!(DiscardStatement, DiscardStatement);
+
+ast_enum!(Statement {
+    Block(Block),
+    Empty(EmptyStatement),
+    Expression(ExpressionStatement),
+    Return(ReturnStatement),
+    If(IfStatement),
+    For(ForStatement),
+    While(WhileStatement),
+    Do(DoStatement),
+    Switch(SwitchStatement),
+    Break(BreakStatement),
+    Continue(ContinueStatement),
+    Discard(DiscardStatement),
+    Variable(VariableDecl),
+    Typedef(TypedefDecl),
+    Type(TypeDecl),
+    Skipped(SkippedTokens),
+});
+
+impl<'a> Statement<'a>
+{
+    // Attributes like [unroll] or [branch] in front of the statement
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a
+    {
+        return attributes(self.syntax());
+    }
+}
+
+impl<'a> Block<'a>
+{
+    pub fn statements(&self) -> impl Iterator<Item = Statement<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> ExpressionStatement<'a>
+{
+    pub fn expression(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> ReturnStatement<'a>
+{
+    pub fn value(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> IfStatement<'a>
+{
+    pub fn condition(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn then_statement(&self) -> Option<Statement<'a>>
+    {
+        return children(self.node).next();
+    }
+
+    pub fn else_statement(&self) -> Option<Statement<'a>>
+    {
+        let clause: ElseClause = child(self.node)?;
+        return child(clause.node);
+    }
+}
+
+impl<'a> ForStatement<'a>
+{
+    // A variable declaration or expression statement, None for 'for (;;)'
+    pub fn initializer(&self) -> Option<Statement<'a>>
+    {
+        let open = self.open_paren_index()?;
+        let first = self.node.children.get(open + 1)?.as_node()?;
+        return Statement::cast(first);
+    }
+
+    // Attributes like [unroll] come before the 'for' keyword
+    fn open_paren_index(&self) -> Option<usize>
+    {
+        return self.node.children.iter().position(|c| matches!(c, SyntaxElement::Token(t) if t.token_type == SyntaxTokenType::OpenParenToken));
+    }
+
+    pub fn condition(&self) -> Option<Expression<'a>>
+    {
+        return self.expression_after_semicolon(0);
+    }
+
+    pub fn incrementor(&self) -> Option<Expression<'a>>
+    {
+        return self.expression_after_semicolon(1);
+    }
+
+    // After the initializer the children are: condition?, ';', incrementor?, ')'
+    fn expression_after_semicolon(&self, semicolon: usize) -> Option<Expression<'a>>
+    {
+        let open = self.open_paren_index()?;
+
+        let mut seen = 0;
+        for element in self.node.children.iter().skip(open + 2)
+        {
+            match element
+            {
+                SyntaxElement::Token(token) if token.token_type == SyntaxTokenType::SemiToken => seen += 1,
+                SyntaxElement::Token(token) if token.token_type == SyntaxTokenType::CloseParenToken => return None,
+                SyntaxElement::Token(_) => {},
+                SyntaxElement::Node(node) => {
+                    if seen == semicolon
+                    {
+                        return Expression::cast(node);
+                    }
+                }
+            }
+        }
+        return None;
+    }
+
+    pub fn body(&self) -> Option<Statement<'a>>
+    {
+        return self.node.child_nodes().last().and_then(Statement::cast);
+    }
+}
+
+impl<'a> WhileStatement<'a>
+{
+    pub fn condition(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn body(&self) -> Option<Statement<'a>>
+    {
+        return children(self.node).next();
+    }
+}
+
+impl<'a> DoStatement<'a>
+{
+    pub fn body(&self) -> Option<Statement<'a>>
+    {
+        return children(self.node).next();
+    }
+
+    pub fn condition(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> SwitchStatement<'a>
+{
+    pub fn expression(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = SwitchSection<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> SwitchSection<'a>
+{
+    pub fn labels(&self) -> impl Iterator<Item = CaseLabel<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    pub fn statements(&self) -> impl Iterator<Item = Statement<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> CaseLabel<'a>
+{
+    pub fn is_default(&self) -> bool
+    {
+        return self.node.node_type == SyntaxNodeType::DefaultLabel;
+    }
+
+    pub fn value(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+// Expressions
+
+ast_node!(LiteralExpr, LiteralExpression);
+ast_node!(ParenthesizedExpr, ParenthesizedExpression);
+ast_node!(CastExpr, CastExpression);
+ast_node!(BinaryExpr, BinaryExpression);
+ast_node!(AssignmentExpr, AssignmentExpression);
+ast_node!(PrefixUnaryExpr, PrefixUnaryExpression);
+ast_node!(PostfixUnaryExpr, PostfixUnaryExpression);
+ast_node!(ConditionalExpr, ConditionalExpression);
+ast_node!(CallExpr, CallExpression);
+ast_node!(MemberAccessExpr, MemberAccessExpression);
+ast_node!(ElementAccessExpr, ElementAccessExpression);
+ast_node!(ArrayInitializerExpr, ArrayInitializerExpression);
+ast_node!(CompileExpr, CompileExpression);
+ast_node!(SamplerStateExpr, SamplerStateExpression);
+ast_node!(StateReferenceExpr, StateReferenceExpression);
+
+ast_enum!(Expression {
+    Identifier(IdentifierName),
+    Qualified(QualifiedName),
+    Literal(LiteralExpr),
+    Parenthesized(ParenthesizedExpr),
+    Cast(CastExpr),
+    Binary(BinaryExpr),
+    Assignment(AssignmentExpr),
+    PrefixUnary(PrefixUnaryExpr),
+    PostfixUnary(PostfixUnaryExpr),
+    Conditional(ConditionalExpr),
+    Call(CallExpr),
+    MemberAccess(MemberAccessExpr),
+    ElementAccess(ElementAccessExpr),
+    ArrayInitializer(ArrayInitializerExpr),
+    Compile(CompileExpr),
+    SamplerState(SamplerStateExpr),
+    StateReference(StateReferenceExpr),
+    // Constructors like float3(1, 2, 3) have the type as callee
+    Type(TypeSyntax),
+});
+
+impl<'a> Expression<'a>
+{
+    // True for placeholders inserted by error recovery
+    pub fn is_missing(&self) -> bool
+    {
+        return self.syntax().descendant_tokens().iter().all(|t| t.is_missing);
+    }
+}
+
+impl<'a> LiteralExpr<'a>
+{
+    pub fn token(&self) -> &'a SyntaxToken
+    {
+        return self.node.child_tokens().next().unwrap();
+    }
+
+    // Adjacent string literals are concatenated
+    pub fn tokens(&self) -> impl Iterator<Item = &'a SyntaxToken> + 'a
+    {
+        return self.node.child_tokens();
+    }
+}
+
+impl<'a> ParenthesizedExpr<'a>
+{
+    pub fn expression(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> CastExpr<'a>
+{
+    pub fn target_type(&self) -> Option<TypeSyntax<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn array_ranks(&self) -> impl Iterator<Item = ArrayRank<'a>> + 'a
+    {
+        return children(self.node);
+    }
+
+    pub fn operand(&self) -> Option<Expression<'a>>
+    {
+        return self.node.child_nodes().last().and_then(Expression::cast);
+    }
+}
+
+// Shared accessors of the expressions laid out as 'left operator right'
+macro_rules! binary_accessors {
+    ($name:ident) => {
+        impl<'a> $name<'a>
+        {
+            pub fn left(&self) -> Option<Expression<'a>>
+            {
+                return nth_node(self.node, 0).and_then(Expression::cast);
+            }
+
+            pub fn operator(&self) -> &'a SyntaxToken
+            {
+                return self.node.child_tokens().next().unwrap();
+            }
+
+            pub fn right(&self) -> Option<Expression<'a>>
+            {
+                return nth_node(self.node, 1).and_then(Expression::cast);
+            }
+        }
+    };
+}
+
+binary_accessors!(BinaryExpr);
+binary_accessors!(AssignmentExpr);
+
+impl<'a> PrefixUnaryExpr<'a>
+{
+    pub fn operator(&self) -> &'a SyntaxToken
+    {
+        return self.node.child_tokens().next().unwrap();
+    }
+
+    pub fn operand(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> PostfixUnaryExpr<'a>
+{
+    pub fn operand(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn operator(&self) -> &'a SyntaxToken
+    {
+        return self.node.child_tokens().next().unwrap();
+    }
+}
+
+impl<'a> ConditionalExpr<'a>
+{
+    pub fn condition(&self) -> Option<Expression<'a>>
+    {
+        return nth_node(self.node, 0).and_then(Expression::cast);
+    }
+
+    pub fn when_true(&self) -> Option<Expression<'a>>
+    {
+        return nth_node(self.node, 1).and_then(Expression::cast);
+    }
+
+    pub fn when_false(&self) -> Option<Expression<'a>>
+    {
+        return nth_node(self.node, 2).and_then(Expression::cast);
+    }
+}
+
+impl<'a> CallExpr<'a>
+{
+    // The called function, a method access like tex.Sample or a type for constructors
+    pub fn callee(&self) -> Option<Expression<'a>>
+    {
+        return nth_node(self.node, 0).and_then(Expression::cast);
+    }
+
+    pub fn arguments(&self) -> Vec<Expression<'a>>
+    {
+        return match self.node.find_node(SyntaxNodeType::ArgumentList)
+        {
+            Some(list) => children(list).collect(),
+            None => Vec::new()
+        };
+    }
+
+    // Name of the called function or method, mul for mul(a, b) and Sample for tex.Sample(s, uv)
+    pub fn function_name(&self) -> Option<&'a SyntaxToken>
+    {
+        return match self.callee()?
+        {
+            Expression::Identifier(name) => Some(name.identifier()),
+            Expression::Qualified(name) => name.right().map(|n| n.identifier()),
+            Expression::MemberAccess(access) => access.member(),
+            _ => None
+        };
+    }
+}
+
+impl<'a> MemberAccessExpr<'a>
+{
+    pub fn object(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+
+    pub fn member(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().nth(1).filter(|t| !t.is_missing);
+    }
+}
+
+impl<'a> ElementAccessExpr<'a>
+{
+    pub fn object(&self) -> Option<Expression<'a>>
+    {
+        return nth_node(self.node, 0).and_then(Expression::cast);
+    }
+
+    pub fn index(&self) -> Option<Expression<'a>>
+    {
+        return nth_node(self.node, 1).and_then(Expression::cast);
+    }
+}
+
+impl<'a> ArrayInitializerExpr<'a>
+{
+    pub fn elements(&self) -> impl Iterator<Item = Expression<'a>> + 'a
+    {
+        return children(self.node);
+    }
+}
+
+impl<'a> CompileExpr<'a>
+{
+    // The shader profile, like vs_4_0
+    pub fn profile(&self) -> Option<&'a SyntaxToken>
+    {
+        return self.node.child_tokens().find(|t| t.is_identifier_like() && !t.is_missing &&
+            t.token_type != SyntaxTokenType::CompileKeyword && t.text != "CompileShader");
+    }
+
+    // The call of the entry point, like VS() in CompileShader(vs_4_0, VS())
+    pub fn shader(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+
+    // Name of the compiled entry point
+    pub fn entry_point(&self) -> Option<&'a SyntaxToken>
+    {
+        return match self.shader()?
+        {
+            Expression::Call(call) => call.function_name(),
+            Expression::Identifier(name) => Some(name.identifier()),
+            _ => None
+        };
+    }
+}
+
+impl<'a> SamplerStateExpr<'a>
+{
+    pub fn state_block(&self) -> Option<StateBlock<'a>>
+    {
+        return child(self.node);
+    }
+}
+
+impl<'a> StateReferenceExpr<'a>
+{
+    pub fn expression(&self) -> Option<Expression<'a>>
+    {
+        return child(self.node);
+    }
+}
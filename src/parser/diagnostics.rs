@@ -0,0 +1,103 @@
+use std::fmt;
+
+use super::syntax_tokens::TextSpan;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash)]
+pub enum DiagnosticSeverity
+{
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+// Diagnostic
+// A message produced by any stage of the pipeline, pointing at a span in the file given by file_index
+#[derive(Debug, Clone)]
+pub struct Diagnostic
+{
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    pub span: TextSpan,
+    pub file_index: usize,
+    // Related information, like the candidates of an ambiguous call
+    pub notes: Vec<Diagnostic>,
+    // The lint rule that produced the diagnostic, like unused-variable
+    pub code: Option<&'static str>,
+}
+
+impl Diagnostic
+{
+    pub fn error(message: String, span: TextSpan) -> Self
+    {
+        return Self{
+            severity: DiagnosticSeverity::Error,
+            message: message,
+            span: span,
+            file_index: 0,
+            notes: Vec::new(),
+            code: None
+        };
+    }
+
+    pub fn warning(message: String, span: TextSpan) -> Self
+    {
+        return Self{
+            severity: DiagnosticSeverity::Warning,
+            message: message,
+            span: span,
+            file_index: 0,
+            notes: Vec::new(),
+            code: None
+        };
+    }
+
+    pub fn note(message: String, span: TextSpan) -> Self
+    {
+        return Self{
+            severity: DiagnosticSeverity::Info,
+            message: message,
+            span: span,
+            file_index: 0,
+            notes: Vec::new(),
+            code: None
+        };
+    }
+
+    pub fn with_note(mut self, note: Diagnostic) -> Self
+    {
+        self.notes.push(note);
+        return self;
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self
+    {
+        self.code = Some(code);
+        return self;
+    }
+
+    pub fn in_file(mut self, file_index: usize) -> Self
+    {
+        self.file_index = file_index;
+        return self;
+    }
+
+    pub fn is_error(&self) -> bool
+    {
+        return self.severity == DiagnosticSeverity::Error;
+    }
+}
+
+impl fmt::Display for Diagnostic
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity
+        {
+            DiagnosticSeverity::Hint => "hint",
+            DiagnosticSeverity::Info => "info",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Error => "error",
+        };
+        write!(f, "{}: {} ({}..{})", severity, self.message, self.span.start, self.span.end)
+    }
+}
@@ -1,9 +1,8 @@
 use std::fmt::Write;
 
 use crate::parser::syntax_tokens::SyntaxTokenType;
-use std::collections::HashMap;
 
-use super::{source_file::{ISourceFile, SourceFile, SourceText}, syntax_tokens::{get_keyword_token_type, SyntaxToken}};
+use super::{diagnostics::Diagnostic, source_file::{ISourceFile, SourceFile, SourceText}, syntax_tokens::{get_keyword_token_type, SyntaxToken, SyntaxTrivia, SyntaxTriviaType, TextSpan}};
 // ILexer
 // Generates a tokenized version of a source file, also contains pre-parsed tokens for defines and includes
 // This tokenized version can be traversed by a LexerTraverser
@@ -14,81 +13,280 @@ pub trait ILexer
 
 pub struct Lexer
 {
-    tokens: Vec<SyntaxToken>
+    tokens: Vec<SyntaxToken>,
+    diagnostics: Vec<Diagnostic>
 }
 
 impl Lexer
 {
-    fn process_preprocessor_directive(source: &mut SourceText, preprocessor_vars: &mut HashMap<String, SyntaxTokenType>, preprocessor_if_stack: &mut Vec<SyntaxTokenType>) -> SyntaxTokenType
+    fn push_trivia(source: &SourceText, trivia: &mut Vec<SyntaxTrivia>, trivia_type: SyntaxTriviaType, start: usize, base_offset: usize)
     {
-        let mut directive = String::new();
-
-        // process #
-        source.next_char();
-
-        while source.current_char() != '\r' && source.current_char() != '\n' && source.current_char() != ' '
-        {
-            directive.write_char(source.current_char());
-            source.next_char();
-        }
+        let end = source.get_char_index();
 
-        if directive == "endif"
+        // Merge whitespace so a run of spaces and tabs is a single trivia
+        if let Some(last) = trivia.last_mut()
         {
-            return SyntaxTokenType::EndIfDirective;
+            if trivia_type == SyntaxTriviaType::WhiteSpace && last.trivia_type == SyntaxTriviaType::WhiteSpace && last.span.end == start + base_offset
+            {
+                last.span.end = end + base_offset;
+                last.text.push_str(&source.get_text_range(start, end));
+                return;
+            }
         }
 
-        let allow_break = directive == "define";
-        let mut skip_break = false;
+        trivia.push(SyntaxTrivia{
+            trivia_type: trivia_type,
+            span: TextSpan::new(start + base_offset, end + base_offset),
+            text: source.get_text_range(start, end)
+        });
+    }
 
-        let mut value = String::new();
+    // Reads whitespace, newlines and comments in front of a token
+    // Returns true when a new line was started
+    fn read_trivia(source: &mut SourceText, trivia: &mut Vec<SyntaxTrivia>, diagnostics: &mut Vec<Diagnostic>, base_offset: usize) -> bool
+    {
+        let mut saw_end_of_line = false;
+        loop
+        {
+            let start = source.get_char_index();
+            match source.current_char()
+            {
+                ' ' | '\t' | '\x0b' | '\x0c' => {
+                    source.next_char();
+                    Lexer::push_trivia(source, trivia, SyntaxTriviaType::WhiteSpace, start, base_offset);
+                },
+                '\u{ef}' if start == 0 && source.peek() == '\u{bb}' && source.peek_at(2) == '\u{bf}' => {
+                    // Byte order mark
+                    source.next_char();
+                    source.next_char();
+                    source.next_char();
+                    Lexer::push_trivia(source, trivia, SyntaxTriviaType::WhiteSpace, start, base_offset);
+                },
+                '\r' | '\n' => {
+                    if source.current_char() == '\r' && source.peek() == '\n'
+                    {
+                        source.next_char();
+                    }
+                    source.next_char();
+                    saw_end_of_line = true;
+                    Lexer::push_trivia(source, trivia, SyntaxTriviaType::EndOfLine, start, base_offset);
+                },
+                '\\' => {
+                    // A backslash followed by a new line continues the line
+                    let mut offset = 1;
+                    while matches!(source.peek_at(offset), ' ' | '\t')
+                    {
+                        offset += 1;
+                    }
 
-        loop {
+                    if !matches!(source.peek_at(offset), '\r' | '\n')
+                    {
+                        break;
+                    }
 
-            if source.current_char() == '/' && source.peek() == '/'
-            {
-                // hit a comment
-                break;
-            }
+                    for _ in 0..offset
+                    {
+                        source.next_char();
+                    }
+                    if source.current_char() == '\r' && source.peek() == '\n'
+                    {
+                        source.next_char();
+                    }
+                    source.next_char();
+                    Lexer::push_trivia(source, trivia, SyntaxTriviaType::LineContinuation, start, base_offset);
+                },
+                '/' if source.peek() == '/' => {
+                    while !source.reached_eof() && source.current_char() != '\r' && source.current_char() != '\n'
+                    {
+                        source.next_char();
+                    }
+                    Lexer::push_trivia(source, trivia, SyntaxTriviaType::LineComment, start, base_offset);
+                },
+                '/' if source.peek() == '*' => {
+                    source.next_char();
+                    source.next_char();
+                    while !(source.reached_eof() || source.current_char() == '*' && source.peek() == '/')
+                    {
+                        source.next_char();
+                    }
 
-            if source.current_char() == '\\'
-            {
-                skip_break = allow_break;
-                source.next_char();
-                continue;
+                    if source.reached_eof()
+                    {
+                        diagnostics.push(Diagnostic::error(String::from("Unterminated comment"), TextSpan::new(start + base_offset, source.get_char_index() + base_offset)));
+                    }
+                    else
+                    {
+                        source.next_char();
+                        source.next_char();
+                    }
+                    Lexer::push_trivia(source, trivia, SyntaxTriviaType::BlockComment, start, base_offset);
+                },
+                _ => break
             }
+        }
+        return saw_end_of_line;
+    }
 
-            if source.current_char() == '\r' || source.current_char() == '\n'
+    // Reads the raw text of a preprocessor directive up to the end of the line, following line continuations
+    fn read_directive_text(source: &mut SourceText)
+    {
+        let mut quote: Option<char> = None;
+        while !source.reached_eof()
+        {
+            let c = source.current_char();
+            match c
             {
-                if skip_break
-                {
-                    skip_break = false;
-                    
-                    if source.current_char() == '\r' && source.peek() == '\n'
+                '\r' | '\n' => break,
+                '\\' => {
+                    source.next_char();
+                    while matches!(source.current_char(), ' ' | '\t') && matches!(source.peek(), ' ' | '\t' | '\r' | '\n')
                     {
                         source.next_char();
+                    }
+                    if source.current_char() == '\r' && source.peek() == '\n'
+                    {
                         source.next_char();
                     }
-                    else {
+                    if !source.reached_eof()
+                    {
                         source.next_char();
                     }
                     continue;
-                }
-
-                // Exit loop as we hit the end of the line
-                break;
+                },
+                '"' | '\'' => {
+                    if quote == Some(c)
+                    {
+                        quote = None;
+                    }
+                    else if quote.is_none()
+                    {
+                        quote = Some(c);
+                    }
+                },
+                '/' if quote.is_none() && source.peek() == '/' => break,
+                '/' if quote.is_none() && source.peek() == '*' => {
+                    // Block comments can continue the directive over multiple lines
+                    source.next_char();
+                    source.next_char();
+                    while !(source.reached_eof() || source.current_char() == '*' && source.peek() == '/')
+                    {
+                        source.next_char();
+                    }
+                    if !source.reached_eof()
+                    {
+                        source.next_char();
+                    }
+                },
+                _ => {}
             }
-
-            value.write_char(source.current_char());
             source.next_char();
         }
 
-        match directive.as_str()
+        // An unterminated comment at the end of the file can move past the end
+        while source.get_char_index() > source.len()
         {
-            "if" => {},
-            _ => return SyntaxTokenType::BadDirective { name: directive }
+            source.prev_char();
         }
 
-        todo!()
+        // Trailing whitespace belongs to the trivia of the next token
+        while source.get_char_index() > 0 && matches!(source.text[source.get_char_index() - 1] as char, ' ' | '\t')
+        {
+            source.prev_char();
+        }
+    }
+
+    fn trimmed_text_after(text: &str, base_offset: usize, token: Option<&SyntaxToken>) -> String
+    {
+        return match token
+        {
+            Some(t) => String::from(text.get(t.span.end - base_offset..).unwrap_or("").trim()),
+            None => String::new()
+        };
+    }
+
+    // Turns the raw text of a directive into its token type
+    fn process_preprocessor_directive(text: &str, base_offset: usize) -> SyntaxTokenType
+    {
+        let lexer = Lexer::from_directive_text(text, base_offset);
+        let tokens = lexer.get_tokens();
+
+        // tokens[0] is the # token
+        let directive = match tokens.get(1)
+        {
+            Some(token) if token.token_type != SyntaxTokenType::EndOfFileToken => token,
+            _ => return SyntaxTokenType::BadDirective { name: String::new() }
+        };
+
+        let argument = tokens.get(2).filter(|t| t.token_type != SyntaxTokenType::EndOfFileToken);
+        let argument_name = match argument
+        {
+            Some(t) => t.text.clone(),
+            None => String::new()
+        };
+
+        match directive.text.as_str()
+        {
+            "define" => {
+                let name = match argument
+                {
+                    Some(t) => t,
+                    None => return SyntaxTokenType::BadDirective { name: directive.text.clone() }
+                };
+
+                // A function like macro has its parameter list directly after the name
+                let open_paren = tokens.get(3);
+                if let Some(paren) = open_paren
+                {
+                    if paren.token_type == SyntaxTokenType::OpenParenToken && paren.span.start == name.span.end
+                    {
+                        let close_paren = tokens[3..].iter().find(|t| t.token_type == SyntaxTokenType::CloseParenToken || t.token_type == SyntaxTokenType::EndOfFileToken);
+                        let args = match close_paren
+                        {
+                            Some(close) => String::from(text.get(paren.span.end - base_offset..close.span.start - base_offset).unwrap_or("").trim()),
+                            None => String::new()
+                        };
+                        return SyntaxTokenType::PreProcessorDefinitionFunction {
+                            name: name.text.clone(),
+                            args: args,
+                            value: Lexer::trimmed_text_after(text, base_offset, close_paren)
+                        };
+                    }
+                }
+
+                return SyntaxTokenType::PreProcessorDefinition {
+                    name: name.text.clone(),
+                    value: Lexer::trimmed_text_after(text, base_offset, Some(name))
+                };
+            },
+            "undef" => return SyntaxTokenType::UndefDirective { name: argument_name },
+            "ifdef" => return SyntaxTokenType::IfDefDirective { name: argument_name },
+            "ifndef" => return SyntaxTokenType::IfNDefDirective { name: argument_name },
+            "if" => return SyntaxTokenType::IfDirective { condition: Lexer::trimmed_text_after(text, base_offset, Some(directive)) },
+            "elif" => return SyntaxTokenType::ElifDirective { condition: Lexer::trimmed_text_after(text, base_offset, Some(directive)) },
+            "else" => return SyntaxTokenType::ElseDirective,
+            "endif" => return SyntaxTokenType::EndIfDirective,
+            "pragma" => return SyntaxTokenType::PragmaDirective { value: Lexer::trimmed_text_after(text, base_offset, Some(directive)) },
+            "error" => return SyntaxTokenType::ErrorDirective { value: Lexer::trimmed_text_after(text, base_offset, Some(directive)) },
+            "line" => return SyntaxTokenType::LineDirective { value: Lexer::trimmed_text_after(text, base_offset, Some(directive)) },
+            "include" => {
+                let path = match argument.map(|t| &t.token_type)
+                {
+                    Some(SyntaxTokenType::StringLiteralToken { value }) => value.clone(),
+                    Some(SyntaxTokenType::LessThanToken) => {
+                        // <path> is lexed as separate tokens, so take the raw text up to the >
+                        let rest = text.get(argument.unwrap().span.end - base_offset..).unwrap_or("");
+                        match rest.find('>')
+                        {
+                            Some(end) => String::from(&rest[..end]),
+                            None => String::from(rest.trim())
+                        }
+                    },
+                    _ => String::new()
+                };
+                return SyntaxTokenType::IncludeDirective { path: path };
+            },
+            _ => return SyntaxTokenType::BadDirective { name: directive.text.clone() }
+        }
     }
 
     fn read_number(source: &mut SourceText) -> SyntaxTokenType
@@ -218,20 +416,12 @@ impl Lexer
         if has_dot || has_exponential_modifier || has_float_suffix
         {
 
-            let value: f64 = match sb.parse()
-            {
-                Ok(x) => x,
-                Err(_) => 0.0
-            };
+            let value: f64 = sb.parse().unwrap_or(0.0);
             return SyntaxTokenType::FloatLiteralToken{value: value};
         }
         else 
         {
-            let value: i128 = match i128::from_str_radix(&sb, base)
-            {
-                Ok(x) => x,
-                Err(_) => 0
-            };
+            let value: i128 = i128::from_str_radix(&sb, base).unwrap_or_default();
             return SyntaxTokenType::IntegerLiteralToken{value: value};
         }
     }
@@ -325,7 +515,7 @@ impl Lexer
         {
             return Lexer::read_identifier_or_keyword(source);
         }
-        else if source.current_char().is_digit(10)
+        else if source.current_char().is_ascii_digit()
         {
             return Lexer::read_number(source);
         }
@@ -407,6 +597,7 @@ impl Lexer
             source.next_char();
             if source.current_char() == '='
             {
+                source.next_char();
                 return double_eq_token;
             }
             else 
@@ -418,24 +609,40 @@ impl Lexer
         return original_token
     }
 
-    pub fn from_text(source: &mut Box<SourceText>) -> Self
+    // Lexes the source, in directive mode # is a regular token and spans are offset by base_offset
+    fn lex(source: &mut SourceText, directive_mode: bool, base_offset: usize) -> Self
     {
-        // Collection containing #define variables and macros
-        // Vec<String> serves as the input to a macro
-        let mut preprocessor_vars: HashMap<String, SyntaxTokenType> = HashMap::new();
-        let mut preprocessor_if_stack: Vec<SyntaxTokenType> = Vec::new();
         let mut tokens: Vec<SyntaxToken> = Vec::new();
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let mut at_line_start = true;
         loop
         {
             use SyntaxTokenType::*;
 
+            let mut trivia: Vec<SyntaxTrivia> = Vec::new();
+            if Lexer::read_trivia(source, &mut trivia, &mut diagnostics, base_offset)
+            {
+                at_line_start = true;
+            }
+
             let start_index = source.get_char_index();
 
+            if source.reached_eof()
+            {
+                let mut eof = SyntaxToken::new(EndOfFileToken, TextSpan::new(start_index + base_offset, start_index + base_offset), String::new());
+                eof.leading_trivia = trivia;
+                tokens.push(eof);
+                break;
+            }
+
             let token = match source.current_char()
             {
-                '\0' => break, // reached eof
-                '#' => todo!(), //Lexer::process_preprocessor_directive(source, preprocessor_vars, preprocessor_if_stack),
-                ' ' => WhiteSpace,
+                '#' if !directive_mode && at_line_start => {
+                    Lexer::read_directive_text(source);
+                    let text = source.get_text_range(start_index, source.get_char_index());
+                    Lexer::process_preprocessor_directive(&text, start_index + base_offset)
+                },
+                '#' => Lexer::read_double_char(source, HashToken, HashHashToken),
                 '~'  => TildeToken,
                 '&'  => Lexer::read_double_char_or_eq_op(source, AmpersandToken, AmpersandAmpersandToken, AmpersandEqualsToken),
                 '|'  => Lexer::read_double_char_or_eq_op(source, BarToken, BarBarToken, BarEqualsToken),
@@ -449,7 +656,7 @@ impl Lexer
                 '}' => SyntaxTokenType::CloseBraceToken,
                 '.' => {
                     let mut token = SyntaxTokenType::DotToken;
-                    if source.peek().is_digit(10)
+                    if source.peek().is_ascii_digit()
                     {
                         token = Lexer::read_number(source);
                     }
@@ -478,32 +685,56 @@ impl Lexer
                 source.next_char();
             }
 
-            if token != WhiteSpace
-            {
-                let end_index = source.get_char_index();
+            let end_index = source.get_char_index();
+            let span = TextSpan::new(start_index + base_offset, end_index + base_offset);
 
-                let syntax_token = SyntaxToken{
-                    token_type: token
-                };
-    
-                tokens.push(syntax_token);
+            match &token
+            {
+                BadToken => diagnostics.push(Diagnostic::error(format!("Unexpected character '{}'", source.get_text_range(start_index, end_index)), span)),
+                StringLiteralToken{..} if source.get_text_range(end_index - 1, end_index) != "\"" || end_index - start_index < 2 => {
+                    diagnostics.push(Diagnostic::error(String::from("Unterminated string literal"), span));
+                },
+                _ => {}
             }
-            
-        }
 
-        tokens.push(SyntaxToken{
-            token_type: SyntaxTokenType::EndOfFileToken
-        });
+            let mut syntax_token = SyntaxToken::new(token, span, source.get_text_range(start_index, end_index));
+            syntax_token.leading_trivia = trivia;
+            tokens.push(syntax_token);
+            at_line_start = false;
+        }
 
         return Lexer{
-            tokens: tokens
+            tokens: tokens,
+            diagnostics: diagnostics
         }
     }
 
+    pub fn from_text(source: &mut Box<SourceText>) -> Self
+    {
+        return Self::lex(source, false, 0);
+    }
+
+    // Lexes the text of a directive or macro body, in which # and ## are operators
+    pub fn from_directive_text(text: &str, base_offset: usize) -> Self
+    {
+        let mut source = SourceText::from(text);
+        return Self::lex(&mut source, true, base_offset);
+    }
+
     pub fn from_file(source: &mut SourceFile) -> Self
     {
         return Self::from_text(source.get_text_mut());
     }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    pub fn into_tokens(self) -> Vec<SyntaxToken>
+    {
+        return self.tokens;
+    }
 }
 
 impl ILexer for Lexer
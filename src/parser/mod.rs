@@ -1,6 +1,13 @@
 
 pub mod parser_settings;
-pub use parser_settings::ParserSettings;
+pub use parser_settings::{HlslVersion, LintLevel, LintSettings, ParserSettings, ShaderModel, ShaderStage, ShaderTarget};
 pub mod source_file;
 pub mod syntax_tokens;
+pub mod diagnostics;
 pub mod lexer;
+pub mod preprocessor;
+pub mod root_signature;
+pub mod syntax_nodes;
+pub mod syntax_parser;
+pub mod ast;
+pub mod syntax_visitor;
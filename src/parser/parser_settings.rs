@@ -1,7 +1,282 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Default)]
+use crate::semantics::linter::lint_rule;
+
+use super::diagnostics::DiagnosticSeverity;
+
+// Language version, like the -HV option of DXC
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum HlslVersion
+{
+    Hlsl2016,
+    Hlsl2017,
+    Hlsl2018,
+    // Adds templates, operator overloading and bitfields
+    #[default]
+    Hlsl2021,
+}
+
+// Shader model version, like 6.0 in cs_6_0
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ShaderModel
+{
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl ShaderModel
+{
+    pub const fn new(major: u32, minor: u32) -> Self
+    {
+        return Self{ major: major, minor: minor };
+    }
+}
+
+impl std::fmt::Display for ShaderModel
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        return write!(f, "{}.{}", self.major, self.minor);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ShaderStage
+{
+    Vertex,
+    Hull,
+    Domain,
+    Geometry,
+    Pixel,
+    Compute,
+    Amplification,
+    Mesh,
+    RayGeneration,
+    Intersection,
+    AnyHit,
+    ClosestHit,
+    Miss,
+    Callable,
+}
+
+// Names used by the [shader("...")] attribute
+const STAGE_NAMES: &[(ShaderStage, &str)] = &[
+    (ShaderStage::Vertex, "vertex"),
+    (ShaderStage::Hull, "hull"),
+    (ShaderStage::Domain, "domain"),
+    (ShaderStage::Geometry, "geometry"),
+    (ShaderStage::Pixel, "pixel"),
+    (ShaderStage::Compute, "compute"),
+    (ShaderStage::Amplification, "amplification"),
+    (ShaderStage::Mesh, "mesh"),
+    (ShaderStage::RayGeneration, "raygeneration"),
+    (ShaderStage::Intersection, "intersection"),
+    (ShaderStage::AnyHit, "anyhit"),
+    (ShaderStage::ClosestHit, "closesthit"),
+    (ShaderStage::Miss, "miss"),
+    (ShaderStage::Callable, "callable"),
+];
+
+impl ShaderStage
+{
+    pub fn from_name(name: &str) -> Option<ShaderStage>
+    {
+        return STAGE_NAMES.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)).map(|(s, _)| *s);
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        return STAGE_NAMES.iter().find(|(s, _)| s == self).map(|(_, n)| *n).unwrap();
+    }
+
+    // The stage and shader model of a profile like vs_5_0, ps_2_a or vs_4_0_level_9_1, None for library and effect profiles
+    pub fn from_profile(profile: &str) -> Option<(ShaderStage, ShaderModel)>
+    {
+        let mut parts = profile.split('_');
+        let stage = match parts.next()?.to_ascii_lowercase().as_str()
+        {
+            "vs" => ShaderStage::Vertex,
+            "hs" => ShaderStage::Hull,
+            "ds" => ShaderStage::Domain,
+            "gs" => ShaderStage::Geometry,
+            "ps" => ShaderStage::Pixel,
+            "cs" => ShaderStage::Compute,
+            "as" => ShaderStage::Amplification,
+            "ms" => ShaderStage::Mesh,
+            _ => return None
+        };
+        let major = parts.next()?.parse().ok()?;
+        // The minor version of ps_2_a and ps_2_b is a letter
+        let minor = parts.next().map(|minor| minor.parse().unwrap_or(0)).unwrap_or(0);
+        return Some((stage, ShaderModel::new(major, minor)));
+    }
+
+    // The prefix of the profiles of the stage, like vs for vs_6_0, ray tracing stages are compiled with lib profiles
+    pub fn profile_prefix(&self) -> &'static str
+    {
+        return match self
+        {
+            ShaderStage::Vertex => "vs",
+            ShaderStage::Hull => "hs",
+            ShaderStage::Domain => "ds",
+            ShaderStage::Geometry => "gs",
+            ShaderStage::Pixel => "ps",
+            ShaderStage::Compute => "cs",
+            ShaderStage::Amplification => "as",
+            ShaderStage::Mesh => "ms",
+            _ => "lib"
+        };
+    }
+
+    pub fn is_ray_tracing(&self) -> bool
+    {
+        return matches!(self, ShaderStage::RayGeneration | ShaderStage::Intersection | ShaderStage::AnyHit |
+            ShaderStage::ClosestHit | ShaderStage::Miss | ShaderStage::Callable);
+    }
+}
+
+// The profile code is compiled for, like -T ps_6_0 or lib_6_3
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ShaderTarget
+{
+    // None for library profiles
+    pub stage: Option<ShaderStage>,
+    pub shader_model: ShaderModel,
+}
+
+impl ShaderTarget
+{
+    pub fn from_profile(profile: &str) -> Option<ShaderTarget>
+    {
+        if let Some(version) = profile.strip_prefix("lib_")
+        {
+            let (major, minor) = version.split_once('_')?;
+            return Some(ShaderTarget{ stage: None, shader_model: ShaderModel::new(major.parse().ok()?, minor.parse().ok()?) });
+        }
+        let (stage, shader_model) = ShaderStage::from_profile(profile)?;
+        return Some(ShaderTarget{ stage: Some(stage), shader_model: shader_model });
+    }
+}
+
+impl std::fmt::Display for ShaderTarget
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        let prefix = self.stage.map(|s| s.profile_prefix()).unwrap_or("lib");
+        return write!(f, "{}_{}_{}", prefix, self.shader_model.major, self.shader_model.minor);
+    }
+}
+
+// How the findings of a lint rule are reported
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LintLevel
+{
+    Off,
+    Hint,
+    Warning,
+    Error,
+}
+
+impl LintLevel
+{
+    pub fn from_name(name: &str) -> Option<LintLevel>
+    {
+        return match name.to_ascii_lowercase().as_str()
+        {
+            "off" | "allow" | "none" => Some(LintLevel::Off),
+            "hint" | "info" => Some(LintLevel::Hint),
+            "warning" | "warn" => Some(LintLevel::Warning),
+            "error" | "deny" => Some(LintLevel::Error),
+            _ => None
+        };
+    }
+
+    // None for rules that are turned off
+    pub fn severity(&self) -> Option<DiagnosticSeverity>
+    {
+        return match self
+        {
+            LintLevel::Off => None,
+            LintLevel::Hint => Some(DiagnosticSeverity::Hint),
+            LintLevel::Warning => Some(DiagnosticSeverity::Warning),
+            LintLevel::Error => Some(DiagnosticSeverity::Error)
+        };
+    }
+}
+
+// The levels of lint rules, rules that are not listed are reported at their default level
+#[derive(Default, Clone, Debug)]
+pub struct LintSettings
+{
+    pub levels: Vec<(String, LintLevel)>,
+}
+
+impl LintSettings
+{
+    // Later levels for the same rule replace earlier ones
+    pub fn set(&mut self, rule: &str, level: LintLevel)
+    {
+        self.levels.retain(|(name, _)| name != rule);
+        self.levels.push((rule.to_string(), level));
+    }
+
+    pub fn level(&self, rule: &str) -> Option<LintLevel>
+    {
+        return self.levels.iter().find(|(name, _)| name == rule).map(|(_, level)| *level);
+    }
+
+    // Reads a config file with a 'rule = level' line per rule, '#' starts a comment and a [lints] section header is allowed:
+    //   [lints]
+    //   unused-parameter = off
+    //   division-by-zero = error
+    pub fn from_config(text: &str) -> Result<LintSettings, String>
+    {
+        let mut settings = LintSettings::default();
+        for (index, line) in text.lines().enumerate()
+        {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() || line.eq_ignore_ascii_case("[lints]")
+            {
+                continue;
+            }
+            let (rule, level) = match line.split_once('=')
+            {
+                Some((rule, level)) => (rule.trim(), level.trim()),
+                None => return Err(format!("line {}: expected 'rule = level'", index + 1))
+            };
+            let rule = rule.trim_matches('"');
+            if lint_rule(rule).is_none()
+            {
+                return Err(format!("line {}: unknown lint rule '{}'", index + 1, rule));
+            }
+            match LintLevel::from_name(level.trim_matches('"'))
+            {
+                Some(level) => settings.set(rule, level),
+                None => return Err(format!("line {}: unknown lint level '{}'", index + 1, level))
+            }
+        }
+        return Ok(settings);
+    }
+
+    pub fn from_path(path: &Path) -> Result<LintSettings, String>
+    {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        return LintSettings::from_config(&text).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+}
+
+#[derive(Default, Clone)]
 pub struct ParserSettings
 {
     pub include_paths: Vec<PathBuf>,
-}
\ No newline at end of file
+    // Macros that are defined before the file is processed, like -D on the command line
+    pub defines: Vec<(String, String)>,
+    // Older versions treat template, typename and operator as identifiers
+    pub hlsl_version: HlslVersion,
+    // Features beyond the shader model of the target are reported, None skips the checks
+    pub target: Option<ShaderTarget>,
+    // Native 16-bit types like float16_t, the -enable-16bit-types option of DXC
+    pub enable_16bit_types: bool,
+    // Levels of the lint rules, from the settings of the client or a config file
+    pub lints: LintSettings,
+}
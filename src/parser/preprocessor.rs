@@ -0,0 +1,972 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, path::{Path, PathBuf}};
+
+use super::{diagnostics::Diagnostic, lexer::{ILexer, Lexer}, source_file::{ISourceFile, SourceFile}, syntax_tokens::{SourceLocation, SyntaxToken, SyntaxTokenType, SyntaxTrivia, SyntaxTriviaType, TextSpan}, ParserSettings};
+
+// Includes nested deeper than this are assumed to be recursive
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct MacroDefinition
+{
+    pub name: String,
+    // None for object like macros
+    pub parameters: Option<Vec<String>>,
+    pub is_variadic: bool,
+    pub body: Vec<SyntaxToken>,
+    pub span: TextSpan,
+    pub file_index: usize,
+}
+
+struct ConditionalState
+{
+    active: bool,
+    // True when one of the branches was taken, so later #elif and #else branches are skipped
+    taken: bool,
+    seen_else: bool,
+    span: TextSpan,
+}
+
+// A token in the middle of a macro expansion, the hide set contains the macros that may not be expanded again
+#[derive(Clone)]
+struct ExpansionToken
+{
+    token: SyntaxToken,
+    hide_set: Vec<String>,
+}
+
+impl ExpansionToken
+{
+    fn new(token: SyntaxToken) -> Self
+    {
+        return Self{ token: token, hide_set: Vec::new() };
+    }
+}
+
+// Shaders are mostly written on Windows, where the case of include paths does not matter
+fn find_file_ignoring_case(directory: &Path, path: &str) -> Option<PathBuf>
+{
+    let exact = directory.join(path);
+    if exact.is_file()
+    {
+        return Some(exact);
+    }
+
+    let mut current = directory.to_path_buf();
+    for component in path.split('/')
+    {
+        let next = match component
+        {
+            "" | "." => current.clone(),
+            ".." => current.join(".."),
+            _ => {
+                let entries = std::fs::read_dir(&current).ok()?;
+                let lower = component.to_lowercase();
+                entries.filter_map(|e| e.ok()).map(|e| e.path())
+                    .find(|p| p.file_name().map(|n| n.to_string_lossy().to_lowercase() == lower).unwrap_or(false))?
+            }
+        };
+        current = next;
+    }
+
+    return if current.is_file() { Some(current) } else { None };
+}
+
+// Preprocessor
+// Runs the preprocessor directives found by the lexer: conditionals are evaluated, files are included and macros are expanded
+// The resulting token stream only contains the tokens the parser should see, directives and disabled text become trivia
+pub struct Preprocessor
+{
+    tokens: Vec<SyntaxToken>,
+    diagnostics: Vec<Diagnostic>,
+    macros: HashMap<String, MacroDefinition>,
+    files: Vec<PathBuf>,
+    pragma_once_files: HashSet<PathBuf>,
+    include_paths: Vec<PathBuf>,
+}
+
+impl Preprocessor
+{
+    fn new(settings: &ParserSettings, root_path: PathBuf) -> Self
+    {
+        let mut preprocessor = Self{
+            tokens: Vec::new(),
+            diagnostics: Vec::new(),
+            macros: HashMap::new(),
+            files: vec![root_path],
+            pragma_once_files: HashSet::new(),
+            include_paths: settings.include_paths.clone(),
+        };
+
+        for (name, value) in &settings.defines
+        {
+            let body = Lexer::from_directive_text(value, 0).into_tokens();
+            preprocessor.macros.insert(name.clone(), MacroDefinition{
+                name: name.clone(),
+                parameters: None,
+                is_variadic: false,
+                body: body[..body.len() - 1].to_vec(),
+                span: TextSpan::default(),
+                file_index: 0
+            });
+        }
+
+        return preprocessor;
+    }
+
+    pub fn from_text(text: &str, settings: &ParserSettings) -> Self
+    {
+        let mut source = SourceFile::from_text(text);
+        return Self::from_file(&mut source, settings);
+    }
+
+    pub fn from_file(source: &mut SourceFile, settings: &ParserSettings) -> Self
+    {
+        let mut preprocessor = Self::new(settings, PathBuf::from(source.get_file_path()));
+        let lexer = Lexer::from_file(source);
+        preprocessor.diagnostics.extend(lexer.get_diagnostics().iter().cloned());
+
+        let tokens = preprocessor.process_tokens(lexer.into_tokens(), 0, 0);
+        preprocessor.tokens = tokens;
+        return preprocessor;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    pub fn get_macros(&self) -> &HashMap<String, MacroDefinition>
+    {
+        return &self.macros;
+    }
+
+    // All files that took part, index 0 is the file that was processed, the others were included
+    pub fn get_files(&self) -> &Vec<PathBuf>
+    {
+        return &self.files;
+    }
+
+    pub fn into_tokens(self) -> Vec<SyntaxToken>
+    {
+        return self.tokens;
+    }
+
+    pub fn into_parts(self) -> (Vec<SyntaxToken>, Vec<Diagnostic>, Vec<PathBuf>)
+    {
+        return (self.tokens, self.diagnostics, self.files);
+    }
+
+    fn error(&mut self, message: String, span: TextSpan, file_index: usize)
+    {
+        self.diagnostics.push(Diagnostic::error(message, span).in_file(file_index));
+    }
+
+    fn process_tokens(&mut self, tokens: Vec<SyntaxToken>, file_index: usize, depth: usize) -> Vec<SyntaxToken>
+    {
+        let mut output: Vec<SyntaxToken> = Vec::new();
+        let mut conditions: Vec<ConditionalState> = Vec::new();
+        let mut run: Vec<SyntaxToken> = Vec::new();
+        let mut pending_trivia: Vec<SyntaxTrivia> = Vec::new();
+
+        for mut token in tokens
+        {
+            token.file_index = file_index;
+            let active = conditions.iter().all(|c| c.active);
+
+            if token.token_type == SyntaxTokenType::EndOfFileToken
+            {
+                self.flush_run(&mut run, &mut pending_trivia, &mut output);
+                for condition in &conditions
+                {
+                    self.error(String::from("Unterminated conditional directive"), condition.span, file_index);
+                }
+
+                // Only the file that was processed keeps its end of file token
+                if depth == 0
+                {
+                    pending_trivia.append(&mut token.leading_trivia);
+                    token.leading_trivia = pending_trivia;
+                    output.push(token);
+                }
+                break;
+            }
+
+            if token.is_directive()
+            {
+                self.flush_run(&mut run, &mut pending_trivia, &mut output);
+                pending_trivia.append(&mut token.leading_trivia);
+                pending_trivia.push(SyntaxTrivia{
+                    trivia_type: SyntaxTriviaType::Directive,
+                    span: token.span,
+                    text: token.text.clone()
+                });
+
+                let included = self.process_directive(&token, &mut conditions, active, file_index, depth);
+                if !included.is_empty()
+                {
+                    // Trivia in front of the include stays in this file
+                    output.extend(included);
+                }
+                continue;
+            }
+
+            if active
+            {
+                run.push(token);
+            }
+            else
+            {
+                pending_trivia.append(&mut token.leading_trivia);
+                pending_trivia.push(SyntaxTrivia{
+                    trivia_type: SyntaxTriviaType::DisabledText,
+                    span: token.span,
+                    text: token.text.clone()
+                });
+            }
+        }
+
+        return output;
+    }
+
+    // Expands the macros in a run of active tokens and moves them to the output
+    fn flush_run(&mut self, run: &mut Vec<SyntaxToken>, pending_trivia: &mut Vec<SyntaxTrivia>, output: &mut Vec<SyntaxToken>)
+    {
+        if run.is_empty()
+        {
+            return;
+        }
+
+        let input: Vec<ExpansionToken> = run.drain(..).map(ExpansionToken::new).collect();
+        let expanded = self.expand(input);
+
+        let mut first = true;
+        for expansion_token in expanded
+        {
+            let mut token = expansion_token.token;
+            if first && !pending_trivia.is_empty()
+            {
+                pending_trivia.append(&mut token.leading_trivia);
+                token.leading_trivia = std::mem::take(pending_trivia);
+            }
+            first = false;
+            output.push(token);
+        }
+    }
+
+    // Returns the tokens of an included file
+    fn process_directive(&mut self, token: &SyntaxToken, conditions: &mut Vec<ConditionalState>, active: bool, file_index: usize, depth: usize) -> Vec<SyntaxToken>
+    {
+        use SyntaxTokenType::*;
+
+        match &token.token_type
+        {
+            IfDirective{..} | IfDefDirective{..} | IfNDefDirective{..} => {
+                let taken = active && self.evaluate_conditional(token, file_index);
+                conditions.push(ConditionalState{
+                    active: taken,
+                    // When the parent is inactive no branch may become active
+                    taken: taken || !active,
+                    seen_else: false,
+                    span: token.span
+                });
+            },
+            ElifDirective{..} => {
+                let parent_active = conditions.len() < 2 || conditions[..conditions.len() - 1].iter().all(|c| c.active);
+                match conditions.last()
+                {
+                    None => self.error(String::from("#elif without #if"), token.span, file_index),
+                    Some(condition) => {
+                        if condition.seen_else
+                        {
+                            self.error(String::from("#elif after #else"), token.span, file_index);
+                        }
+
+                        let taken = !condition.taken && parent_active && self.evaluate_conditional(token, file_index);
+                        let condition = conditions.last_mut().unwrap();
+                        condition.active = taken;
+                        condition.taken |= taken;
+                    }
+                }
+            },
+            ElseDirective => {
+                match conditions.last_mut()
+                {
+                    None => self.error(String::from("#else without #if"), token.span, file_index),
+                    Some(condition) => {
+                        let seen_else = condition.seen_else;
+                        condition.active = !condition.taken;
+                        condition.taken = true;
+                        condition.seen_else = true;
+                        if seen_else
+                        {
+                            self.error(String::from("#else after #else"), token.span, file_index);
+                        }
+                    }
+                }
+            },
+            EndIfDirective => {
+                if conditions.pop().is_none()
+                {
+                    self.error(String::from("#endif without #if"), token.span, file_index);
+                }
+            },
+            _ if !active => {},
+            PreProcessorDefinition{..} | PreProcessorDefinitionFunction{..} => self.define_macro(token, file_index),
+            UndefDirective{ name } => {
+                self.macros.remove(name);
+            },
+            IncludeDirective{ path } => return self.include_file(path, token, file_index, depth),
+            PragmaDirective{ value } => {
+                if value == "once"
+                {
+                    let path = self.files[file_index].clone();
+                    self.pragma_once_files.insert(path);
+                }
+            },
+            ErrorDirective{ value } => self.error(format!("#error {}", value), token.span, file_index),
+            BadDirective{ name } => {
+                if !name.is_empty()
+                {
+                    self.error(format!("Invalid preprocessor directive '#{}'", name), token.span, file_index);
+                }
+            },
+            _ => {}
+        }
+
+        return Vec::new();
+    }
+
+    fn find_include(&self, path: &str, file_index: usize) -> Option<PathBuf>
+    {
+        // Shaders written on Windows use (escaped) backslashes as separators
+        let mut path = path.replace('\\', "/");
+        while path.contains("//")
+        {
+            path = path.replace("//", "/");
+        }
+
+        // Includes are first searched for next to the including file, then next to the file being processed
+        let mut directories: Vec<PathBuf> = Vec::new();
+        for index in [file_index, 0]
+        {
+            if let Some(parent) = self.files[index].parent()
+            {
+                directories.push(parent.to_path_buf());
+            }
+        }
+        directories.extend(self.include_paths.iter().cloned());
+
+        for directory in &directories
+        {
+            if let Some(found) = find_file_ignoring_case(directory, &path)
+            {
+                return Some(found);
+            }
+        }
+
+        return None;
+    }
+
+    fn include_file(&mut self, path: &str, token: &SyntaxToken, file_index: usize, depth: usize) -> Vec<SyntaxToken>
+    {
+        if path.is_empty()
+        {
+            self.error(String::from("Expected a file name after #include"), token.span, file_index);
+            return Vec::new();
+        }
+
+        let full_path = match self.find_include(path, file_index)
+        {
+            Some(p) => p,
+            None => {
+                self.error(format!("Cannot open include file '{}'", path), token.span, file_index);
+                return Vec::new();
+            }
+        };
+
+        if self.pragma_once_files.contains(&full_path)
+        {
+            return Vec::new();
+        }
+
+        if depth >= MAX_INCLUDE_DEPTH
+        {
+            self.error(format!("Include depth exceeded while including '{}'", path), token.span, file_index);
+            return Vec::new();
+        }
+
+        let settings = ParserSettings::default();
+        let mut source = SourceFile::from_path(&full_path, &settings);
+        if !source.exists()
+        {
+            self.error(format!("Cannot open include file '{}'", path), token.span, file_index);
+            return Vec::new();
+        }
+
+        let included_index = self.files.len();
+        self.files.push(full_path);
+
+        let lexer = Lexer::from_file(&mut source);
+        self.diagnostics.extend(lexer.get_diagnostics().iter().map(|d| d.clone().in_file(included_index)));
+        return self.process_tokens(lexer.into_tokens(), included_index, depth + 1);
+    }
+
+    // Lexes the directive again so the tokens of the body have exact locations
+    fn directive_tokens(token: &SyntaxToken) -> Vec<SyntaxToken>
+    {
+        let mut tokens = Lexer::from_directive_text(&token.text, token.span.start).into_tokens();
+        tokens.pop();
+        for t in tokens.iter_mut()
+        {
+            t.file_index = token.file_index;
+        }
+        return tokens;
+    }
+
+    fn define_macro(&mut self, token: &SyntaxToken, file_index: usize)
+    {
+        let tokens = Preprocessor::directive_tokens(token);
+
+        // tokens: # define NAME [( params )] body
+        let name = match tokens.get(2)
+        {
+            Some(t) if t.is_identifier_like() => t.clone(),
+            _ => {
+                self.error(String::from("Macro names must be identifiers"), token.span, file_index);
+                return;
+            }
+        };
+
+        let mut parameters: Option<Vec<String>> = None;
+        let mut is_variadic = false;
+        let mut body_start = 3;
+
+        if let SyntaxTokenType::PreProcessorDefinitionFunction{..} = token.token_type
+        {
+            let mut params = Vec::new();
+            let mut index = 4;
+            while index < tokens.len() && tokens[index].token_type != SyntaxTokenType::CloseParenToken
+            {
+                let t = &tokens[index];
+                if t.is_identifier_like()
+                {
+                    params.push(t.text.clone());
+                }
+                else if t.token_type == SyntaxTokenType::DotToken
+                {
+                    // ... is lexed as three dots
+                    if !is_variadic
+                    {
+                        params.push(String::from("__VA_ARGS__"));
+                    }
+                    is_variadic = true;
+                }
+                else if t.token_type != SyntaxTokenType::CommaToken
+                {
+                    self.error(format!("Unexpected '{}' in macro parameter list", t.text), t.span, file_index);
+                }
+                index += 1;
+            }
+
+            if index >= tokens.len()
+            {
+                self.error(String::from("Missing ')' in macro parameter list"), token.span, file_index);
+            }
+
+            parameters = Some(params);
+            body_start = index + 1;
+        }
+
+        let body = if body_start < tokens.len() { tokens[body_start..].to_vec() } else { Vec::new() };
+        self.macros.insert(name.text.clone(), MacroDefinition{
+            name: name.text.clone(),
+            parameters: parameters,
+            is_variadic: is_variadic,
+            body: body,
+            span: token.span,
+            file_index: file_index
+        });
+    }
+
+    fn evaluate_conditional(&mut self, token: &SyntaxToken, file_index: usize) -> bool
+    {
+        match &token.token_type
+        {
+            SyntaxTokenType::IfDefDirective{ name } => return self.macros.contains_key(name),
+            SyntaxTokenType::IfNDefDirective{ name } => return !self.macros.contains_key(name),
+            _ => {}
+        }
+
+        // Skip the # and the directive name
+        let tokens = Preprocessor::directive_tokens(token);
+        let tokens = if tokens.len() > 2 { &tokens[2..] } else { &[] };
+        if tokens.is_empty()
+        {
+            self.error(String::from("Expected a condition"), token.span, file_index);
+            return false;
+        }
+
+        // defined must be resolved before macros are expanded
+        let mut resolved: Vec<ExpansionToken> = Vec::new();
+        let mut index = 0;
+        while index < tokens.len()
+        {
+            let t = &tokens[index];
+            if t.text == "defined"
+            {
+                let has_paren = tokens.get(index + 1).map(|n| n.token_type == SyntaxTokenType::OpenParenToken).unwrap_or(false);
+                let name_index = if has_paren { index + 2 } else { index + 1 };
+                let defined = match tokens.get(name_index)
+                {
+                    Some(name) => self.macros.contains_key(&name.text),
+                    None => {
+                        self.error(String::from("Expected a macro name after defined"), t.span, file_index);
+                        false
+                    }
+                };
+
+                let mut value = t.clone();
+                value.token_type = SyntaxTokenType::IntegerLiteralToken { value: defined as i128 };
+                resolved.push(ExpansionToken::new(value));
+                index = name_index + if has_paren { 2 } else { 1 };
+                continue;
+            }
+            resolved.push(ExpansionToken::new(t.clone()));
+            index += 1;
+        }
+
+        let expanded: Vec<SyntaxToken> = self.expand(resolved).into_iter().map(|t| t.token).collect();
+        let mut evaluator = ConditionEvaluator{ tokens: &expanded, index: 0, error: None };
+        let value = evaluator.parse_expression(0);
+
+        if evaluator.error.is_none() && evaluator.index < expanded.len()
+        {
+            evaluator.error = Some(format!("Unexpected '{}' in preprocessor expression", expanded[evaluator.index].text));
+        }
+
+        if let Some(error) = evaluator.error
+        {
+            self.error(error, token.span, file_index);
+            return false;
+        }
+
+        return value != 0;
+    }
+
+    // Expands all macros in the input, the result is rescanned until no more macros can be expanded
+    fn expand(&mut self, input: Vec<ExpansionToken>) -> Vec<ExpansionToken>
+    {
+        let mut input: VecDeque<ExpansionToken> = input.into();
+        let mut output: Vec<ExpansionToken> = Vec::new();
+
+        while let Some(current) = input.pop_front()
+        {
+            let definition = match self.macros.get(&current.token.text)
+            {
+                Some(definition) if current.token.is_identifier_like() && !current.hide_set.contains(&current.token.text) => definition.clone(),
+                _ => {
+                    output.push(current);
+                    continue;
+                }
+            };
+
+            let replacement = match &definition.parameters
+            {
+                None => self.substitute(&definition, &current, &current.token.span, Vec::new()),
+                Some(_) => {
+                    let is_invocation = input.front().map(|t| t.token.token_type == SyntaxTokenType::OpenParenToken).unwrap_or(false);
+                    if !is_invocation
+                    {
+                        output.push(current);
+                        continue;
+                    }
+
+                    let (arguments, close_span) = match Preprocessor::collect_arguments(&mut input)
+                    {
+                        Some(result) => result,
+                        None => {
+                            self.error(format!("Unterminated invocation of macro '{}'", definition.name), current.token.span, current.token.file_index);
+                            output.push(current);
+                            continue;
+                        }
+                    };
+
+                    // A token produced by an earlier expansion is already located at the outermost invocation
+                    let span = if current.token.spelling.is_none() { current.token.span.union(&close_span) } else { current.token.span };
+                    self.substitute(&definition, &current, &span, arguments)
+                }
+            };
+
+            for token in replacement.into_iter().rev()
+            {
+                input.push_front(token);
+            }
+        }
+
+        return output;
+    }
+
+    // Removes the argument list from the input, returns the arguments and the span of the closing parenthesis
+    fn collect_arguments(input: &mut VecDeque<ExpansionToken>) -> Option<(Vec<Vec<ExpansionToken>>, TextSpan)>
+    {
+        // Check that the list is terminated before consuming it
+        let mut depth = 0;
+        let mut length = None;
+        for (index, t) in input.iter().enumerate()
+        {
+            match t.token.token_type
+            {
+                SyntaxTokenType::OpenParenToken => depth += 1,
+                SyntaxTokenType::CloseParenToken => {
+                    depth -= 1;
+                    if depth == 0
+                    {
+                        length = Some(index + 1);
+                        break;
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        let length = length?;
+        let mut list: Vec<ExpansionToken> = input.drain(..length).collect();
+        let close = list.pop().unwrap();
+        list.remove(0);
+
+        let mut arguments: Vec<Vec<ExpansionToken>> = vec![Vec::new()];
+        let mut depth = 0;
+        for t in list
+        {
+            match t.token.token_type
+            {
+                SyntaxTokenType::OpenParenToken => depth += 1,
+                SyntaxTokenType::CloseParenToken => depth -= 1,
+                SyntaxTokenType::CommaToken if depth == 0 => {
+                    arguments.push(Vec::new());
+                    continue;
+                },
+                _ => {}
+            }
+            arguments.last_mut().unwrap().push(t);
+        }
+
+        return Some((arguments, close.token.span));
+    }
+
+    fn stringize(argument: &[ExpansionToken], template: &SyntaxToken) -> SyntaxToken
+    {
+        let mut text = String::new();
+        for (index, t) in argument.iter().enumerate()
+        {
+            if index > 0 && !t.token.leading_trivia.is_empty()
+            {
+                text.push(' ');
+            }
+            text.push_str(&t.token.text);
+        }
+
+        let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut token = template.clone();
+        token.text = format!("\"{}\"", escaped);
+        token.token_type = SyntaxTokenType::StringLiteralToken { value: escaped };
+        return token;
+    }
+
+    // Replaces an invocation by the body of the macro with the parameters substituted
+    fn substitute(&mut self, definition: &MacroDefinition, invocation: &ExpansionToken, span: &TextSpan, mut arguments: Vec<Vec<ExpansionToken>>) -> Vec<ExpansionToken>
+    {
+        let parameters = definition.parameters.clone().unwrap_or_default();
+
+        // F() has a single empty argument, which is the same as no arguments
+        if parameters.is_empty() && arguments.len() == 1 && arguments[0].is_empty()
+        {
+            arguments.clear();
+        }
+
+        if definition.is_variadic && arguments.len() > parameters.len()
+        {
+            // Everything after the named parameters is part of __VA_ARGS__
+            let rest: Vec<Vec<ExpansionToken>> = arguments.drain(parameters.len() - 1..).collect();
+            let mut variadic: Vec<ExpansionToken> = Vec::new();
+            for (index, argument) in rest.into_iter().enumerate()
+            {
+                if index > 0
+                {
+                    let mut comma = invocation.clone();
+                    comma.token.token_type = SyntaxTokenType::CommaToken;
+                    comma.token.text = String::from(",");
+                    variadic.push(comma);
+                }
+                variadic.extend(argument);
+            }
+            arguments.push(variadic);
+        }
+
+        if definition.parameters.is_some() && arguments.len() != parameters.len() && !(definition.is_variadic && arguments.len() + 1 == parameters.len())
+        {
+            self.error(
+                format!("Macro '{}' expects {} arguments, but {} were given", definition.name, parameters.len(), arguments.len()),
+                *span,
+                invocation.token.file_index);
+        }
+
+        let parameter_index = |token: &SyntaxToken| -> Option<usize> {
+            if !token.is_identifier_like()
+            {
+                return None;
+            }
+            return parameters.iter().position(|p| *p == token.text);
+        };
+
+        let body = &definition.body;
+        let mut result: Vec<ExpansionToken> = Vec::new();
+        let mut index = 0;
+        while index < body.len()
+        {
+            let token = &body[index];
+            let next_is_paste = body.get(index + 1).map(|t| t.token_type == SyntaxTokenType::HashHashToken).unwrap_or(false);
+            let previous_is_paste = index > 0 && body[index - 1].token_type == SyntaxTokenType::HashHashToken;
+
+            if token.token_type == SyntaxTokenType::HashToken && definition.parameters.is_some()
+            {
+                if let Some(param) = body.get(index + 1).and_then(&parameter_index)
+                {
+                    let argument = arguments.get(param).cloned().unwrap_or_default();
+                    result.push(ExpansionToken::new(Preprocessor::stringize(&argument, token)));
+                    index += 2;
+                    continue;
+                }
+            }
+
+            if token.token_type == SyntaxTokenType::HashHashToken && !result.is_empty()
+            {
+                // Paste the previous token with the first token of the right hand side
+                let mut right: Vec<ExpansionToken> = match body.get(index + 1)
+                {
+                    None => Vec::new(),
+                    Some(next) => match parameter_index(next)
+                    {
+                        Some(param) => arguments.get(param).cloned().unwrap_or_default(),
+                        None => vec![ExpansionToken::new(next.clone())]
+                    }
+                };
+
+                if !right.is_empty()
+                {
+                    let first = right.remove(0);
+                    let left = result.pop().unwrap();
+                    result.extend(self.paste(left, first, invocation));
+                }
+                result.extend(right);
+                index += 2;
+                continue;
+            }
+
+            match parameter_index(token)
+            {
+                Some(param) if definition.parameters.is_some() => {
+                    let argument = arguments.get(param).cloned().unwrap_or_default();
+                    if next_is_paste || previous_is_paste
+                    {
+                        result.extend(argument);
+                    }
+                    else
+                    {
+                        // Arguments are fully expanded before they are substituted
+                        result.extend(self.expand(argument));
+                    }
+                },
+                _ => result.push(ExpansionToken::new(token.clone()))
+            }
+            index += 1;
+        }
+
+        // Every token of the replacement is located at the invocation
+        let mut hide_set = invocation.hide_set.clone();
+        hide_set.push(definition.name.clone());
+        for (index, t) in result.iter_mut().enumerate()
+        {
+            if t.token.spelling.is_none()
+            {
+                t.token.spelling = Some(SourceLocation{ file_index: t.token.file_index, span: t.token.span });
+            }
+            t.token.span = *span;
+            t.token.file_index = invocation.token.file_index;
+            t.token.leading_trivia = if index == 0 { invocation.token.leading_trivia.clone() } else { Vec::new() };
+            for name in &hide_set
+            {
+                if !t.hide_set.contains(name)
+                {
+                    t.hide_set.push(name.clone());
+                }
+            }
+        }
+
+        return result;
+    }
+
+    fn paste(&mut self, left: ExpansionToken, right: ExpansionToken, invocation: &ExpansionToken) -> Vec<ExpansionToken>
+    {
+        let text = format!("{}{}", left.token.text, right.token.text);
+        let tokens = Lexer::from_directive_text(&text, 0).into_tokens();
+        if tokens.len() != 2
+        {
+            self.error(
+                format!("Pasting '{}' and '{}' does not give a valid token", left.token.text, right.token.text),
+                invocation.token.span,
+                invocation.token.file_index);
+            return vec![left, right];
+        }
+
+        let mut pasted = left;
+        pasted.token.token_type = tokens[0].token_type.clone();
+        pasted.token.text = text;
+        return vec![pasted];
+    }
+}
+
+impl ILexer for Preprocessor
+{
+    fn get_tokens(&self) -> &Vec<SyntaxToken> {
+        return &self.tokens;
+    }
+}
+
+// Evaluates the integer expression of an #if or #elif directive
+struct ConditionEvaluator<'a>
+{
+    tokens: &'a [SyntaxToken],
+    index: usize,
+    error: Option<String>,
+}
+
+impl ConditionEvaluator<'_>
+{
+    fn binary_precedence(token_type: &SyntaxTokenType) -> Option<u8>
+    {
+        use SyntaxTokenType::*;
+        return match token_type
+        {
+            BarBarToken => Some(1),
+            AmpersandAmpersandToken => Some(2),
+            BarToken => Some(3),
+            CaretToken => Some(4),
+            AmpersandToken => Some(5),
+            EqualsEqualsToken | ExclamationEqualsToken => Some(6),
+            LessThanToken | LessThanEqualsToken | GreaterThanToken | GreaterThanEqualsToken => Some(7),
+            LessThanLessThanToken | GreaterThanGreaterThanToken => Some(8),
+            PlusToken | MinusToken => Some(9),
+            AsteriskToken | SlashToken | PercentToken => Some(10),
+            _ => None
+        };
+    }
+
+    fn fail(&mut self, message: String) -> i64
+    {
+        if self.error.is_none()
+        {
+            self.error = Some(message);
+        }
+        self.index = self.tokens.len();
+        return 0;
+    }
+
+    fn parse_expression(&mut self, min_precedence: u8) -> i64
+    {
+        let mut left = self.parse_unary();
+
+        while let Some(token) = self.tokens.get(self.index)
+        {
+            let token_type = token.token_type.clone();
+
+            if token_type == SyntaxTokenType::QuestionToken && min_precedence == 0
+            {
+                self.index += 1;
+                let when_true = self.parse_expression(0);
+                if self.tokens.get(self.index).map(|t| t.token_type != SyntaxTokenType::ColonToken).unwrap_or(true)
+                {
+                    return self.fail(String::from("Expected ':' in preprocessor expression"));
+                }
+                self.index += 1;
+                let when_false = self.parse_expression(0);
+                left = if left != 0 { when_true } else { when_false };
+                continue;
+            }
+
+            let precedence = match ConditionEvaluator::binary_precedence(&token_type)
+            {
+                Some(p) if p > min_precedence => p,
+                _ => break
+            };
+
+            self.index += 1;
+            let right = self.parse_expression(precedence);
+
+            use SyntaxTokenType::*;
+            left = match token_type
+            {
+                BarBarToken => ((left != 0) || (right != 0)) as i64,
+                AmpersandAmpersandToken => ((left != 0) && (right != 0)) as i64,
+                BarToken => left | right,
+                CaretToken => left ^ right,
+                AmpersandToken => left & right,
+                EqualsEqualsToken => (left == right) as i64,
+                ExclamationEqualsToken => (left != right) as i64,
+                LessThanToken => (left < right) as i64,
+                LessThanEqualsToken => (left <= right) as i64,
+                GreaterThanToken => (left > right) as i64,
+                GreaterThanEqualsToken => (left >= right) as i64,
+                LessThanLessThanToken => left.wrapping_shl(right as u32),
+                GreaterThanGreaterThanToken => left.wrapping_shr(right as u32),
+                PlusToken => left.wrapping_add(right),
+                MinusToken => left.wrapping_sub(right),
+                AsteriskToken => left.wrapping_mul(right),
+                SlashToken | PercentToken if right == 0 => return self.fail(String::from("Division by zero in preprocessor expression")),
+                SlashToken => left.wrapping_div(right),
+                PercentToken => left.wrapping_rem(right),
+                _ => left
+            };
+        }
+
+        return left;
+    }
+
+    fn parse_unary(&mut self) -> i64
+    {
+        let token = match self.tokens.get(self.index)
+        {
+            Some(t) => t,
+            None => return self.fail(String::from("Unexpected end of preprocessor expression"))
+        };
+        self.index += 1;
+
+        use SyntaxTokenType::*;
+        match &token.token_type
+        {
+            NotToken => return (self.parse_unary() == 0) as i64,
+            TildeToken => return !self.parse_unary(),
+            MinusToken => return self.parse_unary().wrapping_neg(),
+            PlusToken => return self.parse_unary(),
+            OpenParenToken => {
+                let value = self.parse_expression(0);
+                if self.tokens.get(self.index).map(|t| t.token_type != CloseParenToken).unwrap_or(true)
+                {
+                    return self.fail(String::from("Expected ')' in preprocessor expression"));
+                }
+                self.index += 1;
+                return value;
+            },
+            IntegerLiteralToken{ value } => return *value as i64,
+            CharacterLiteralToken{ value } => return *value as i64,
+            TrueKeyword => return 1,
+            FalseKeyword => return 0,
+            // Identifiers that are not macros evaluate to 0
+            _ if token.is_identifier_like() => return 0,
+            _ => {
+                let message = format!("Unexpected '{}' in preprocessor expression", token.text);
+                return self.fail(message);
+            }
+        }
+    }
+}
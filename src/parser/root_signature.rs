@@ -0,0 +1,1066 @@
+use super::{diagnostics::Diagnostic, syntax_tokens::{SourceLocation, SyntaxToken, SyntaxTokenType, TextSpan}};
+
+// Root signatures are written as strings in the grammar described at
+// https://learn.microsoft.com/en-us/windows/win32/direct3d12/specifying-root-signatures-in-hlsl
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RootFlag
+{
+    AllowInputAssemblerInputLayout,
+    DenyVertexShaderRootAccess,
+    DenyHullShaderRootAccess,
+    DenyDomainShaderRootAccess,
+    DenyGeometryShaderRootAccess,
+    DenyPixelShaderRootAccess,
+    DenyAmplificationShaderRootAccess,
+    DenyMeshShaderRootAccess,
+    AllowStreamOutput,
+    LocalRootSignature,
+    CbvSrvUavHeapDirectlyIndexed,
+    SamplerHeapDirectlyIndexed,
+}
+
+const ROOT_FLAGS: &[(&str, RootFlag)] = &[
+    ("ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT", RootFlag::AllowInputAssemblerInputLayout),
+    ("DENY_VERTEX_SHADER_ROOT_ACCESS", RootFlag::DenyVertexShaderRootAccess),
+    ("DENY_HULL_SHADER_ROOT_ACCESS", RootFlag::DenyHullShaderRootAccess),
+    ("DENY_DOMAIN_SHADER_ROOT_ACCESS", RootFlag::DenyDomainShaderRootAccess),
+    ("DENY_GEOMETRY_SHADER_ROOT_ACCESS", RootFlag::DenyGeometryShaderRootAccess),
+    ("DENY_PIXEL_SHADER_ROOT_ACCESS", RootFlag::DenyPixelShaderRootAccess),
+    ("DENY_AMPLIFICATION_SHADER_ROOT_ACCESS", RootFlag::DenyAmplificationShaderRootAccess),
+    ("DENY_MESH_SHADER_ROOT_ACCESS", RootFlag::DenyMeshShaderRootAccess),
+    ("ALLOW_STREAM_OUTPUT", RootFlag::AllowStreamOutput),
+    ("LOCAL_ROOT_SIGNATURE", RootFlag::LocalRootSignature),
+    ("CBV_SRV_UAV_HEAP_DIRECTLY_INDEXED", RootFlag::CbvSrvUavHeapDirectlyIndexed),
+    ("SAMPLER_HEAP_DIRECTLY_INDEXED", RootFlag::SamplerHeapDirectlyIndexed),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ShaderVisibility
+{
+    All,
+    Vertex,
+    Hull,
+    Domain,
+    Geometry,
+    Pixel,
+    Amplification,
+    Mesh,
+}
+
+const SHADER_VISIBILITIES: &[(&str, ShaderVisibility)] = &[
+    ("SHADER_VISIBILITY_ALL", ShaderVisibility::All),
+    ("SHADER_VISIBILITY_VERTEX", ShaderVisibility::Vertex),
+    ("SHADER_VISIBILITY_HULL", ShaderVisibility::Hull),
+    ("SHADER_VISIBILITY_DOMAIN", ShaderVisibility::Domain),
+    ("SHADER_VISIBILITY_GEOMETRY", ShaderVisibility::Geometry),
+    ("SHADER_VISIBILITY_PIXEL", ShaderVisibility::Pixel),
+    ("SHADER_VISIBILITY_AMPLIFICATION", ShaderVisibility::Amplification),
+    ("SHADER_VISIBILITY_MESH", ShaderVisibility::Mesh),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DescriptorFlag
+{
+    DataVolatile,
+    DataStaticWhileSetAtExecute,
+    DataStatic,
+    DescriptorsVolatile,
+    DescriptorsStaticKeepingBufferBoundsChecks,
+}
+
+const DESCRIPTOR_FLAGS: &[(&str, DescriptorFlag)] = &[
+    ("DATA_VOLATILE", DescriptorFlag::DataVolatile),
+    ("DATA_STATIC_WHILE_SET_AT_EXECUTE", DescriptorFlag::DataStaticWhileSetAtExecute),
+    ("DATA_STATIC", DescriptorFlag::DataStatic),
+    ("DESCRIPTORS_VOLATILE", DescriptorFlag::DescriptorsVolatile),
+    ("DESCRIPTORS_STATIC_KEEPING_BUFFER_BOUNDS_CHECKS", DescriptorFlag::DescriptorsStaticKeepingBufferBoundsChecks),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TextureAddressMode
+{
+    Wrap,
+    Mirror,
+    Clamp,
+    Border,
+    MirrorOnce,
+}
+
+const TEXTURE_ADDRESS_MODES: &[(&str, TextureAddressMode)] = &[
+    ("TEXTURE_ADDRESS_WRAP", TextureAddressMode::Wrap),
+    ("TEXTURE_ADDRESS_MIRROR", TextureAddressMode::Mirror),
+    ("TEXTURE_ADDRESS_CLAMP", TextureAddressMode::Clamp),
+    ("TEXTURE_ADDRESS_BORDER", TextureAddressMode::Border),
+    ("TEXTURE_ADDRESS_MIRROR_ONCE", TextureAddressMode::MirrorOnce),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ComparisonFunc
+{
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+const COMPARISON_FUNCS: &[(&str, ComparisonFunc)] = &[
+    ("COMPARISON_NEVER", ComparisonFunc::Never),
+    ("COMPARISON_LESS", ComparisonFunc::Less),
+    ("COMPARISON_EQUAL", ComparisonFunc::Equal),
+    ("COMPARISON_LESS_EQUAL", ComparisonFunc::LessEqual),
+    ("COMPARISON_GREATER", ComparisonFunc::Greater),
+    ("COMPARISON_NOT_EQUAL", ComparisonFunc::NotEqual),
+    ("COMPARISON_GREATER_EQUAL", ComparisonFunc::GreaterEqual),
+    ("COMPARISON_ALWAYS", ComparisonFunc::Always),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StaticBorderColor
+{
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+    OpaqueBlackUint,
+    OpaqueWhiteUint,
+}
+
+const STATIC_BORDER_COLORS: &[(&str, StaticBorderColor)] = &[
+    ("STATIC_BORDER_COLOR_TRANSPARENT_BLACK", StaticBorderColor::TransparentBlack),
+    ("STATIC_BORDER_COLOR_OPAQUE_BLACK", StaticBorderColor::OpaqueBlack),
+    ("STATIC_BORDER_COLOR_OPAQUE_WHITE", StaticBorderColor::OpaqueWhite),
+    ("STATIC_BORDER_COLOR_OPAQUE_BLACK_UINT", StaticBorderColor::OpaqueBlackUint),
+    ("STATIC_BORDER_COLOR_OPAQUE_WHITE_UINT", StaticBorderColor::OpaqueWhiteUint),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FilterType
+{
+    Point,
+    Linear,
+    Anisotropic,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FilterReduction
+{
+    Standard,
+    Comparison,
+    Minimum,
+    Maximum,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Filter
+{
+    pub reduction: FilterReduction,
+    pub min: FilterType,
+    pub mag: FilterType,
+    pub mip: FilterType,
+}
+
+const FILTER_TYPES: &[(&str, [FilterType; 3])] = &[
+    ("MIN_MAG_MIP_POINT", [FilterType::Point, FilterType::Point, FilterType::Point]),
+    ("MIN_MAG_POINT_MIP_LINEAR", [FilterType::Point, FilterType::Point, FilterType::Linear]),
+    ("MIN_POINT_MAG_LINEAR_MIP_POINT", [FilterType::Point, FilterType::Linear, FilterType::Point]),
+    ("MIN_POINT_MAG_MIP_LINEAR", [FilterType::Point, FilterType::Linear, FilterType::Linear]),
+    ("MIN_LINEAR_MAG_MIP_POINT", [FilterType::Linear, FilterType::Point, FilterType::Point]),
+    ("MIN_LINEAR_MAG_POINT_MIP_LINEAR", [FilterType::Linear, FilterType::Point, FilterType::Linear]),
+    ("MIN_MAG_LINEAR_MIP_POINT", [FilterType::Linear, FilterType::Linear, FilterType::Point]),
+    ("MIN_MAG_MIP_LINEAR", [FilterType::Linear, FilterType::Linear, FilterType::Linear]),
+    ("MIN_MAG_ANISOTROPIC_MIP_POINT", [FilterType::Anisotropic, FilterType::Anisotropic, FilterType::Point]),
+    ("ANISOTROPIC", [FilterType::Anisotropic, FilterType::Anisotropic, FilterType::Anisotropic]),
+];
+
+impl Filter
+{
+    // Parses names like FILTER_COMPARISON_MIN_MAG_MIP_LINEAR
+    pub fn from_name(name: &str) -> Option<Filter>
+    {
+        let rest = name.strip_prefix("FILTER_")?;
+        let (reduction, rest) = if let Some(r) = rest.strip_prefix("COMPARISON_") {
+            (FilterReduction::Comparison, r)
+        } else if let Some(r) = rest.strip_prefix("MINIMUM_") {
+            (FilterReduction::Minimum, r)
+        } else if let Some(r) = rest.strip_prefix("MAXIMUM_") {
+            (FilterReduction::Maximum, r)
+        } else {
+            (FilterReduction::Standard, rest)
+        };
+
+        let types = FILTER_TYPES.iter().find(|(n, _)| *n == rest)?.1;
+        return Some(Filter{ reduction: reduction, min: types[0], mag: types[1], mip: types[2] });
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RegisterType
+{
+    ConstantBuffer,
+    ShaderResource,
+    UnorderedAccess,
+    Sampler,
+}
+
+impl RegisterType
+{
+    pub fn from_prefix(prefix: char) -> Option<Self>
+    {
+        return match prefix.to_ascii_lowercase()
+        {
+            'b' => Some(RegisterType::ConstantBuffer),
+            't' => Some(RegisterType::ShaderResource),
+            'u' => Some(RegisterType::UnorderedAccess),
+            's' => Some(RegisterType::Sampler),
+            _ => None
+        };
+    }
+
+    pub fn prefix(&self) -> char
+    {
+        return match self
+        {
+            RegisterType::ConstantBuffer => 'b',
+            RegisterType::ShaderResource => 't',
+            RegisterType::UnorderedAccess => 'u',
+            RegisterType::Sampler => 's',
+        };
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Register
+{
+    pub register_type: RegisterType,
+    pub index: u32,
+    // Location of the register inside the root signature string
+    pub span: TextSpan,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct RootConstants
+{
+    pub num_32bit_constants: u32,
+    pub register: Register,
+    pub space: u32,
+    pub visibility: ShaderVisibility,
+}
+
+// A CBV, SRV or UAV bound directly in the root signature
+#[derive(PartialEq, Debug, Clone)]
+pub struct RootDescriptor
+{
+    pub register: Register,
+    pub space: u32,
+    pub visibility: ShaderVisibility,
+    pub flags: Vec<DescriptorFlag>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DescriptorCount
+{
+    Bounded(u32),
+    Unbounded,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DescriptorRangeOffset
+{
+    Append,
+    Offset(u32),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct DescriptorRange
+{
+    pub register: Register,
+    pub num_descriptors: DescriptorCount,
+    pub space: u32,
+    pub offset: DescriptorRangeOffset,
+    pub flags: Vec<DescriptorFlag>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct DescriptorTable
+{
+    pub ranges: Vec<DescriptorRange>,
+    pub visibility: ShaderVisibility,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum RootParameter
+{
+    Constants(RootConstants),
+    Descriptor(RootDescriptor),
+    DescriptorTable(DescriptorTable),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct StaticSampler
+{
+    pub register: Register,
+    pub filter: Filter,
+    pub address_u: TextureAddressMode,
+    pub address_v: TextureAddressMode,
+    pub address_w: TextureAddressMode,
+    pub mip_lod_bias: f32,
+    pub max_anisotropy: u32,
+    pub comparison_func: ComparisonFunc,
+    pub border_color: StaticBorderColor,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub space: u32,
+    pub visibility: ShaderVisibility,
+}
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RootSignature
+{
+    pub flags: Vec<RootFlag>,
+    pub parameters: Vec<RootParameter>,
+    pub static_samplers: Vec<StaticSampler>,
+}
+
+// Error with a span relative to the start of the root signature string
+#[derive(PartialEq, Debug, Clone)]
+pub struct RootSignatureError
+{
+    pub message: String,
+    pub span: TextSpan,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum RootSignatureTokenType
+{
+    Identifier,
+    Number(f64),
+    OpenParen,
+    CloseParen,
+    Comma,
+    Equals,
+    Bar,
+    EndOfString,
+    Bad,
+}
+
+#[derive(Debug, Clone)]
+struct RootSignatureToken
+{
+    token_type: RootSignatureTokenType,
+    text: String,
+    span: TextSpan,
+}
+
+fn tokenize(text: &str) -> Vec<RootSignatureToken>
+{
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next()
+    {
+        if c.is_ascii_whitespace()
+        {
+            continue;
+        }
+
+        let token_type = if c.is_ascii_alphabetic() || c == '_' {
+            while chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_').is_some() {}
+            RootSignatureTokenType::Identifier
+        } else if c.is_ascii_digit() || c == '.' || ((c == '-' || c == '+') && text[start + 1..].starts_with(|n: char| n.is_ascii_digit() || n == '.')) {
+            let mut previous = c;
+            while let Some((_, n)) = chars.next_if(|(_, n)| n.is_ascii_digit() || *n == '.' || *n == 'e' || *n == 'E' ||
+                  ((*n == '-' || *n == '+') && (previous == 'e' || previous == 'E')))
+            {
+                previous = n;
+            }
+            let end = chars.peek().map(|(index, _)| *index).unwrap_or(text.len());
+            let number = text[start..end].trim_start_matches('+').parse::<f64>();
+            chars.next_if(|(_, n)| *n == 'f' || *n == 'F');
+            match number
+            {
+                Ok(value) => RootSignatureTokenType::Number(value),
+                Err(_) => RootSignatureTokenType::Bad
+            }
+        } else {
+            match c
+            {
+                '(' => RootSignatureTokenType::OpenParen,
+                ')' => RootSignatureTokenType::CloseParen,
+                ',' => RootSignatureTokenType::Comma,
+                '=' => RootSignatureTokenType::Equals,
+                '|' => RootSignatureTokenType::Bar,
+                _ => RootSignatureTokenType::Bad
+            }
+        };
+
+        let end = chars.peek().map(|(index, _)| *index).unwrap_or(text.len());
+        tokens.push(RootSignatureToken{
+            token_type: token_type,
+            text: String::from(&text[start..end]),
+            span: TextSpan::new(start, end)
+        });
+    }
+
+    tokens.push(RootSignatureToken{
+        token_type: RootSignatureTokenType::EndOfString,
+        text: String::new(),
+        span: TextSpan::new(text.len(), text.len())
+    });
+
+    return tokens;
+}
+
+fn find_in_table<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T>
+{
+    return table.iter().find(|(n, _)| *n == name).map(|(_, v)| *v);
+}
+
+// Keeps track of which optional parameters were already given, as each may only appear once
+#[derive(Default)]
+struct SeenParameters
+{
+    names: Vec<String>,
+}
+
+impl SeenParameters
+{
+    fn check(&mut self, token: &RootSignatureToken) -> Result<(), RootSignatureError>
+    {
+        let name = token.text.to_ascii_lowercase();
+        if self.names.contains(&name)
+        {
+            return Err(RootSignatureError{ message: format!("'{}' was already specified", token.text), span: token.span });
+        }
+        self.names.push(name);
+        return Ok(());
+    }
+}
+
+struct RootSignatureParser
+{
+    tokens: Vec<RootSignatureToken>,
+    index: usize,
+}
+
+impl RootSignatureParser
+{
+    fn current(&self) -> &RootSignatureToken
+    {
+        return &self.tokens[self.index.min(self.tokens.len() - 1)];
+    }
+
+    fn advance(&mut self) -> RootSignatureToken
+    {
+        let token = self.current().clone();
+        if self.index < self.tokens.len() - 1
+        {
+            self.index += 1;
+        }
+        return token;
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, RootSignatureError>
+    {
+        return Err(RootSignatureError{ message: message, span: self.current().span });
+    }
+
+    fn describe(token: &RootSignatureToken) -> String
+    {
+        if token.token_type == RootSignatureTokenType::EndOfString
+        {
+            return String::from("end of root signature");
+        }
+        return format!("'{}'", token.text);
+    }
+
+    fn expect(&mut self, token_type: RootSignatureTokenType, description: &str) -> Result<RootSignatureToken, RootSignatureError>
+    {
+        if self.current().token_type != token_type
+        {
+            return self.error(format!("Expected {} but found {}", description, RootSignatureParser::describe(self.current())));
+        }
+        return Ok(self.advance());
+    }
+
+    fn accept(&mut self, token_type: RootSignatureTokenType) -> bool
+    {
+        if self.current().token_type == token_type
+        {
+            self.advance();
+            return true;
+        }
+        return false;
+    }
+
+    fn parse_root_signature(&mut self) -> Result<RootSignature, RootSignatureError>
+    {
+        let mut root_signature = RootSignature::default();
+        let mut seen_flags = false;
+
+        if self.current().token_type == RootSignatureTokenType::EndOfString
+        {
+            return Ok(root_signature);
+        }
+
+        loop
+        {
+            let element = self.expect(RootSignatureTokenType::Identifier, "a root signature element")?;
+            self.expect(RootSignatureTokenType::OpenParen, "'('")?;
+
+            match element.text.as_str()
+            {
+                "RootFlags" => {
+                    if seen_flags
+                    {
+                        return Err(RootSignatureError{ message: String::from("RootFlags cannot be specified more than once"), span: element.span });
+                    }
+                    seen_flags = true;
+                    root_signature.flags = self.parse_root_flags()?;
+                },
+                "RootConstants" => {
+                    let constants = self.parse_root_constants()?;
+                    root_signature.parameters.push(RootParameter::Constants(constants));
+                },
+                "CBV" | "SRV" | "UAV" => {
+                    let descriptor = self.parse_root_descriptor(&element)?;
+                    root_signature.parameters.push(RootParameter::Descriptor(descriptor));
+                },
+                "DescriptorTable" => {
+                    let table = self.parse_descriptor_table()?;
+                    root_signature.parameters.push(RootParameter::DescriptorTable(table));
+                },
+                "StaticSampler" => {
+                    let sampler = self.parse_static_sampler()?;
+                    root_signature.static_samplers.push(sampler);
+                },
+                _ => return Err(RootSignatureError{ message: format!("Unknown root signature element '{}'", element.text), span: element.span })
+            }
+
+            self.expect(RootSignatureTokenType::CloseParen, "')'")?;
+
+            if !self.accept(RootSignatureTokenType::Comma)
+            {
+                break;
+            }
+        }
+
+        if self.current().token_type != RootSignatureTokenType::EndOfString
+        {
+            return self.error(format!("Expected ',' but found {}", RootSignatureParser::describe(self.current())));
+        }
+
+        return Ok(root_signature);
+    }
+
+    // Parses flags separated by |, the value 0 means no flags
+    fn parse_flags<T: Copy>(&mut self, table: &[(&str, T)], kind: &str) -> Result<Vec<T>, RootSignatureError>
+    {
+        let mut flags = Vec::new();
+        loop
+        {
+            let token = self.current().clone();
+            match token.token_type
+            {
+                RootSignatureTokenType::Number(0.0) => {},
+                RootSignatureTokenType::Identifier => {
+                    match find_in_table(table, &token.text)
+                    {
+                        Some(flag) => flags.push(flag),
+                        None => return self.error(format!("Unknown {} '{}'", kind, token.text))
+                    }
+                },
+                _ => return self.error(format!("Expected {} but found {}", kind, RootSignatureParser::describe(&token)))
+            }
+            self.advance();
+
+            if !self.accept(RootSignatureTokenType::Bar)
+            {
+                break;
+            }
+        }
+        return Ok(flags);
+    }
+
+    fn parse_root_flags(&mut self) -> Result<Vec<RootFlag>, RootSignatureError>
+    {
+        if self.current().token_type == RootSignatureTokenType::CloseParen
+        {
+            return Ok(Vec::new());
+        }
+        return self.parse_flags(ROOT_FLAGS, "root flag");
+    }
+
+    fn parse_register(&mut self, expected: RegisterType) -> Result<Register, RootSignatureError>
+    {
+        let token = self.current().clone();
+        let register = match token.token_type
+        {
+            RootSignatureTokenType::Identifier => {
+                let mut chars = token.text.chars();
+                let register_type = chars.next().and_then(RegisterType::from_prefix);
+                let index = chars.as_str().parse::<u32>();
+                match (register_type, index)
+                {
+                    (Some(register_type), Ok(index)) => Register{ register_type: register_type, index: index, span: token.span },
+                    _ => return self.error(format!("Expected a register like '{}0' but found '{}'", expected.prefix(), token.text))
+                }
+            },
+            _ => return self.error(format!("Expected a register like '{}0' but found {}", expected.prefix(), RootSignatureParser::describe(&token)))
+        };
+
+        if register.register_type != expected
+        {
+            return self.error(format!("Invalid register '{}', expected a '{}' register", token.text, expected.prefix()));
+        }
+
+        self.advance();
+        return Ok(register);
+    }
+
+    fn parse_unsigned(&mut self) -> Result<u32, RootSignatureError>
+    {
+        match self.current().token_type
+        {
+            RootSignatureTokenType::Number(value) if value >= 0.0 && value.fract() == 0.0 && value <= u32::MAX as f64 => {
+                self.advance();
+                return Ok(value as u32);
+            },
+            _ => return self.error(format!("Expected an unsigned integer but found {}", RootSignatureParser::describe(self.current())))
+        }
+    }
+
+    fn parse_float(&mut self) -> Result<f32, RootSignatureError>
+    {
+        match self.current().token_type
+        {
+            RootSignatureTokenType::Number(value) => {
+                self.advance();
+                return Ok(value as f32);
+            },
+            _ => return self.error(format!("Expected a number but found {}", RootSignatureParser::describe(self.current())))
+        }
+    }
+
+    fn parse_enum<T: Copy>(&mut self, table: &[(&str, T)], kind: &str) -> Result<T, RootSignatureError>
+    {
+        let token = self.expect(RootSignatureTokenType::Identifier, kind)?;
+        match find_in_table(table, &token.text)
+        {
+            Some(value) => return Ok(value),
+            None => return Err(RootSignatureError{ message: format!("Unknown {} '{}'", kind, token.text), span: token.span })
+        }
+    }
+
+    // Reads "name =" and returns the name token
+    fn parse_parameter_name(&mut self, seen: &mut SeenParameters) -> Result<RootSignatureToken, RootSignatureError>
+    {
+        let name = self.expect(RootSignatureTokenType::Identifier, "a parameter name")?;
+        seen.check(&name)?;
+        self.expect(RootSignatureTokenType::Equals, "'='")?;
+        return Ok(name);
+    }
+
+    fn unknown_parameter<T>(name: &RootSignatureToken, element: &str) -> Result<T, RootSignatureError>
+    {
+        return Err(RootSignatureError{ message: format!("Unknown parameter '{}' for {}", name.text, element), span: name.span });
+    }
+
+    fn parse_root_constants(&mut self) -> Result<RootConstants, RootSignatureError>
+    {
+        let mut seen = SeenParameters::default();
+        let mut num_32bit_constants = None;
+        let mut register = None;
+        let mut space = 0;
+        let mut visibility = ShaderVisibility::All;
+
+        loop
+        {
+            if self.current().token_type == RootSignatureTokenType::Identifier && self.current().text.starts_with(['b', 'B']) &&
+               self.tokens.get(self.index + 1).map(|t| t.token_type != RootSignatureTokenType::Equals).unwrap_or(true)
+            {
+                if register.is_some()
+                {
+                    return self.error(String::from("The register was already specified"));
+                }
+                register = Some(self.parse_register(RegisterType::ConstantBuffer)?);
+            }
+            else
+            {
+                let name = self.parse_parameter_name(&mut seen)?;
+                match name.text.as_str()
+                {
+                    "num32BitConstants" => num_32bit_constants = Some(self.parse_unsigned()?),
+                    "space" => space = self.parse_unsigned()?,
+                    "visibility" => visibility = self.parse_enum(SHADER_VISIBILITIES, "shader visibility")?,
+                    _ => return RootSignatureParser::unknown_parameter(&name, "RootConstants")
+                }
+            }
+
+            if !self.accept(RootSignatureTokenType::Comma)
+            {
+                break;
+            }
+        }
+
+        let num_32bit_constants = match num_32bit_constants
+        {
+            Some(n) => n,
+            None => return self.error(String::from("RootConstants requires num32BitConstants"))
+        };
+        let register = match register
+        {
+            Some(r) => r,
+            None => return self.error(String::from("RootConstants requires a 'b' register"))
+        };
+
+        return Ok(RootConstants{ num_32bit_constants: num_32bit_constants, register: register, space: space, visibility: visibility });
+    }
+
+    fn descriptor_register_type(name: &str) -> RegisterType
+    {
+        return match name
+        {
+            "CBV" => RegisterType::ConstantBuffer,
+            "SRV" => RegisterType::ShaderResource,
+            "UAV" => RegisterType::UnorderedAccess,
+            _ => RegisterType::Sampler
+        };
+    }
+
+    fn parse_root_descriptor(&mut self, element: &RootSignatureToken) -> Result<RootDescriptor, RootSignatureError>
+    {
+        let register = self.parse_register(RootSignatureParser::descriptor_register_type(&element.text))?;
+        let mut seen = SeenParameters::default();
+        let mut descriptor = RootDescriptor{ register: register, space: 0, visibility: ShaderVisibility::All, flags: Vec::new() };
+
+        while self.accept(RootSignatureTokenType::Comma)
+        {
+            let name = self.parse_parameter_name(&mut seen)?;
+            match name.text.as_str()
+            {
+                "space" => descriptor.space = self.parse_unsigned()?,
+                "visibility" => descriptor.visibility = self.parse_enum(SHADER_VISIBILITIES, "shader visibility")?,
+                "flags" => descriptor.flags = self.parse_flags(DESCRIPTOR_FLAGS, "descriptor flag")?,
+                _ => return RootSignatureParser::unknown_parameter(&name, &element.text)
+            }
+        }
+
+        return Ok(descriptor);
+    }
+
+    fn parse_descriptor_range(&mut self, element: &RootSignatureToken) -> Result<DescriptorRange, RootSignatureError>
+    {
+        let register = self.parse_register(RootSignatureParser::descriptor_register_type(&element.text))?;
+        let mut seen = SeenParameters::default();
+        let mut range = DescriptorRange{
+            register: register,
+            num_descriptors: DescriptorCount::Bounded(1),
+            space: 0,
+            offset: DescriptorRangeOffset::Append,
+            flags: Vec::new()
+        };
+
+        while self.accept(RootSignatureTokenType::Comma)
+        {
+            let name = self.parse_parameter_name(&mut seen)?;
+            match name.text.as_str()
+            {
+                "numDescriptors" => {
+                    if self.current().text == "unbounded"
+                    {
+                        self.advance();
+                        range.num_descriptors = DescriptorCount::Unbounded;
+                    }
+                    else
+                    {
+                        range.num_descriptors = DescriptorCount::Bounded(self.parse_unsigned()?);
+                    }
+                },
+                "space" => range.space = self.parse_unsigned()?,
+                "offset" => {
+                    if self.current().text == "DESCRIPTOR_RANGE_OFFSET_APPEND"
+                    {
+                        self.advance();
+                        range.offset = DescriptorRangeOffset::Append;
+                    }
+                    else
+                    {
+                        range.offset = DescriptorRangeOffset::Offset(self.parse_unsigned()?);
+                    }
+                },
+                "flags" => range.flags = self.parse_flags(DESCRIPTOR_FLAGS, "descriptor flag")?,
+                _ => return RootSignatureParser::unknown_parameter(&name, &element.text)
+            }
+        }
+
+        return Ok(range);
+    }
+
+    fn parse_descriptor_table(&mut self) -> Result<DescriptorTable, RootSignatureError>
+    {
+        let mut table = DescriptorTable{ ranges: Vec::new(), visibility: ShaderVisibility::All };
+        let mut seen = SeenParameters::default();
+
+        if self.current().token_type == RootSignatureTokenType::CloseParen
+        {
+            return Ok(table);
+        }
+
+        loop
+        {
+            let element = self.expect(RootSignatureTokenType::Identifier, "a descriptor range")?;
+            match element.text.as_str()
+            {
+                "CBV" | "SRV" | "UAV" | "Sampler" => {
+                    self.expect(RootSignatureTokenType::OpenParen, "'('")?;
+                    let range = self.parse_descriptor_range(&element)?;
+                    self.expect(RootSignatureTokenType::CloseParen, "')'")?;
+
+                    let is_sampler = range.register.register_type == RegisterType::Sampler;
+                    if table.ranges.first().map(|r| (r.register.register_type == RegisterType::Sampler) != is_sampler).unwrap_or(false)
+                    {
+                        return Err(RootSignatureError{ message: String::from("Samplers cannot be mixed with other resources in a descriptor table"), span: element.span });
+                    }
+                    table.ranges.push(range);
+                },
+                "visibility" => {
+                    seen.check(&element)?;
+                    self.expect(RootSignatureTokenType::Equals, "'='")?;
+                    table.visibility = self.parse_enum(SHADER_VISIBILITIES, "shader visibility")?;
+                },
+                _ => return Err(RootSignatureError{ message: format!("Unknown descriptor range '{}'", element.text), span: element.span })
+            }
+
+            if !self.accept(RootSignatureTokenType::Comma)
+            {
+                break;
+            }
+        }
+
+        return Ok(table);
+    }
+
+    fn parse_static_sampler(&mut self) -> Result<StaticSampler, RootSignatureError>
+    {
+        let register = self.parse_register(RegisterType::Sampler)?;
+        let mut seen = SeenParameters::default();
+        let mut sampler = StaticSampler{
+            register: register,
+            filter: Filter::from_name("FILTER_ANISOTROPIC").unwrap(),
+            address_u: TextureAddressMode::Wrap,
+            address_v: TextureAddressMode::Wrap,
+            address_w: TextureAddressMode::Wrap,
+            mip_lod_bias: 0.0,
+            max_anisotropy: 16,
+            comparison_func: ComparisonFunc::LessEqual,
+            border_color: StaticBorderColor::OpaqueWhite,
+            min_lod: 0.0,
+            max_lod: f32::MAX,
+            space: 0,
+            visibility: ShaderVisibility::All
+        };
+
+        while self.accept(RootSignatureTokenType::Comma)
+        {
+            let name = self.parse_parameter_name(&mut seen)?;
+            match name.text.as_str()
+            {
+                "filter" => {
+                    let token = self.expect(RootSignatureTokenType::Identifier, "a filter")?;
+                    sampler.filter = match Filter::from_name(&token.text)
+                    {
+                        Some(filter) => filter,
+                        None => return Err(RootSignatureError{ message: format!("Unknown filter '{}'", token.text), span: token.span })
+                    };
+                },
+                "addressU" => sampler.address_u = self.parse_enum(TEXTURE_ADDRESS_MODES, "texture address mode")?,
+                "addressV" => sampler.address_v = self.parse_enum(TEXTURE_ADDRESS_MODES, "texture address mode")?,
+                "addressW" => sampler.address_w = self.parse_enum(TEXTURE_ADDRESS_MODES, "texture address mode")?,
+                "mipLODBias" => sampler.mip_lod_bias = self.parse_float()?,
+                "maxAnisotropy" => sampler.max_anisotropy = self.parse_unsigned()?,
+                "comparisonFunc" => sampler.comparison_func = self.parse_enum(COMPARISON_FUNCS, "comparison function")?,
+                "borderColor" => sampler.border_color = self.parse_enum(STATIC_BORDER_COLORS, "border color")?,
+                "minLOD" => sampler.min_lod = self.parse_float()?,
+                "maxLOD" => sampler.max_lod = self.parse_float()?,
+                "space" => sampler.space = self.parse_unsigned()?,
+                "visibility" => sampler.visibility = self.parse_enum(SHADER_VISIBILITIES, "shader visibility")?,
+                _ => return RootSignatureParser::unknown_parameter(&name, "StaticSampler")
+            }
+        }
+
+        return Ok(sampler);
+    }
+}
+
+// The text of a root signature together with where each part of it came from,
+// adjacent string literals are concatenated and can come from different lines of a macro
+pub struct RootSignatureSource
+{
+    pub text: String,
+    segments: Vec<RootSignatureSegment>,
+}
+
+// A part of the text that came from one string literal
+struct RootSignatureSegment
+{
+    // Offset into the text where the part starts
+    offset: usize,
+    location: SourceLocation,
+    // The source offset of every byte of the part and of its end, the literal is not always spelled like its value.
+    // None when the literal is not spelled at its location, like a stringized macro argument
+    source_offsets: Option<Vec<usize>>,
+}
+
+impl RootSignatureSource
+{
+    pub fn from_text(text: &str) -> Self
+    {
+        let segment = RootSignatureSegment{ offset: 0, location: SourceLocation::default(), source_offsets: Some((0..=text.len()).collect()) };
+        return Self{ text: String::from(text), segments: vec![segment] };
+    }
+
+    // Builds the source from a list of string literal tokens, returns None if a token is not a string literal
+    pub fn from_tokens(tokens: &[SyntaxToken]) -> Option<Self>
+    {
+        let mut source = Self{ text: String::new(), segments: Vec::new() };
+        for token in tokens
+        {
+            let value = match &token.token_type
+            {
+                SyntaxTokenType::StringLiteralToken{ value } => value,
+                _ => return None
+            };
+
+            // A line continuation inside the literal is not part of its value
+            let value = &value.replace("\\\r\n", "").replace("\\\n", "");
+            let location = token.spelling_location();
+            source.segments.push(RootSignatureSegment{
+                offset: source.text.len(),
+                location: location,
+                source_offsets: RootSignatureSource::source_offsets(value, &token.text, location.span)
+            });
+            source.text.push_str(value);
+        }
+
+        if tokens.is_empty()
+        {
+            return None;
+        }
+        return Some(source);
+    }
+
+    // Lines up the value of a string literal with the literal as it is spelled at the span
+    fn source_offsets(value: &str, literal: &str, span: TextSpan) -> Option<Vec<usize>>
+    {
+        if literal.len() != span.len() || !literal.starts_with('"')
+        {
+            return None;
+        }
+
+        let mut offsets = Vec::with_capacity(value.len() + 1);
+        // Skip the opening quote
+        let mut position = 1;
+        for c in value.chars()
+        {
+            // Line continuations and escapes can spell a character with more than itself
+            if !literal[position..].starts_with(c)
+            {
+                position = literal[position..].find(c).map(|found| position + found).unwrap_or(position);
+            }
+            offsets.extend((0..c.len_utf8()).map(|index| span.start + (position + index).min(literal.len())));
+            position = (position + c.len_utf8()).min(literal.len());
+        }
+        offsets.push(span.start + position);
+        return Some(offsets);
+    }
+
+    // Maps a span inside the text to the source, spans crossing multiple parts are cut off at the end of the first part
+    pub fn map_span(&self, span: TextSpan) -> SourceLocation
+    {
+        let index = self.segments.iter().rposition(|segment| segment.offset <= span.start).unwrap_or(0);
+        let segment = &self.segments[index];
+        let Some(source_offsets) = &segment.source_offsets else { return segment.location };
+        let last = source_offsets.len() - 1;
+        let start = source_offsets[(span.start - segment.offset).min(last)];
+        let end = source_offsets[(span.end.max(span.start) - segment.offset).min(last)];
+        return SourceLocation{ file_index: segment.location.file_index, span: TextSpan::new(start, end.max(start)) };
+    }
+
+    pub fn parse(&self) -> Result<RootSignature, Diagnostic>
+    {
+        return match RootSignature::parse(&self.text)
+        {
+            Ok(root_signature) => Ok(root_signature),
+            Err(error) => {
+                let location = self.map_span(error.span);
+                Err(Diagnostic::error(error.message, location.span).in_file(location.file_index))
+            }
+        };
+    }
+}
+
+impl RootSignature
+{
+    pub fn parse(text: &str) -> Result<RootSignature, RootSignatureError>
+    {
+        let mut parser = RootSignatureParser{ tokens: tokenize(text), index: 0 };
+
+        if let Some(bad) = parser.tokens.iter().find(|t| t.token_type == RootSignatureTokenType::Bad)
+        {
+            return Err(RootSignatureError{ message: format!("Unexpected '{}' in root signature", bad.text), span: bad.span });
+        }
+
+        return parser.parse_root_signature();
+    }
+}
+
+// A [RootSignature(...)] attribute found in a token stream
+pub struct RootSignatureAttribute
+{
+    pub span: TextSpan,
+    pub root_signature: Result<RootSignature, Diagnostic>,
+}
+
+// Finds all [RootSignature(...)] attributes in a preprocessed token stream
+pub fn find_root_signature_attributes(tokens: &[SyntaxToken]) -> Vec<RootSignatureAttribute>
+{
+    let mut attributes = Vec::new();
+    let mut index = 0;
+    while index + 3 < tokens.len()
+    {
+        if tokens[index].token_type != SyntaxTokenType::OpenBracketToken ||
+           tokens[index + 1].text != "RootSignature" ||
+           tokens[index + 2].token_type != SyntaxTokenType::OpenParenToken
+        {
+            index += 1;
+            continue;
+        }
+
+        let start = index + 3;
+        let mut end = start;
+        while end < tokens.len() && matches!(tokens[end].token_type, SyntaxTokenType::StringLiteralToken{..})
+        {
+            end += 1;
+        }
+
+        let argument_span = tokens[start.min(tokens.len() - 1)].span;
+        let root_signature = match RootSignatureSource::from_tokens(&tokens[start..end])
+        {
+            Some(source) => source.parse(),
+            None => Err(Diagnostic::error(String::from("RootSignature expects a string literal"), argument_span).in_file(tokens[start.min(tokens.len() - 1)].file_index))
+        };
+
+        let close = tokens[end..].iter().position(|t| t.token_type == SyntaxTokenType::CloseBracketToken).map(|p| end + p).unwrap_or(end.min(tokens.len() - 1));
+        attributes.push(RootSignatureAttribute{
+            span: tokens[index].span.union(&tokens[close].span),
+            root_signature: root_signature
+        });
+        index = close + 1;
+    }
+
+    return attributes;
+}
@@ -1,4 +1,4 @@
-use std::{fs, path::{absolute, Path, PathBuf}};
+use std::{fmt, fs, path::{absolute, Path, PathBuf}};
 use crate::parser::ParserSettings;
 
 // SourceText
@@ -81,13 +81,31 @@ impl SourceText
     {
         return self.char_index;
     }
+
+    pub fn len(&self) -> usize
+    {
+        return self.text.len();
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.text.is_empty();
+    }
+
+    // Text in between two byte offsets, invalid utf8 is replaced
+    pub fn get_text_range(&self, start: usize, end: usize) -> String
+    {
+        let end = end.min(self.text.len());
+        let start = start.min(end);
+        return String::from_utf8_lossy(&self.text[start..end]).into_owned();
+    }
 }
 
-impl ToString for SourceText
+impl fmt::Display for SourceText
 {
-    fn to_string(&self) -> String 
-    { 
-        return String::from_utf8(self.text.clone()).expect("String was not valid utf8");
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", String::from_utf8_lossy(&self.text))
     }
 }
 
@@ -142,7 +160,7 @@ impl SourcePath
                 }
             }
 
-            if found_paths.len() == 0
+            if found_paths.is_empty()
             {
                 // We did not find the file they asked for, so return that the file does not exist
                 return Self::default();
@@ -221,6 +239,11 @@ impl SourceFile
         }
     }
 
+    pub fn exists(&self) -> bool
+    {
+        return self.source_path.file_exists;
+    }
+
     // Get it from a path
     pub fn from_path(path: &Path, settings: &ParserSettings) -> Self
     {
@@ -234,15 +257,12 @@ impl SourceFile
             }
         }
 
-        let text = match fs::read_to_string(&source_path.full_file_path)
-        {
-            Ok(content) => content,
-            Err(_) => String::new(),
-        };
+        // Some shaders are saved with a legacy code page, so the raw bytes are kept instead of requiring utf8
+        let text = fs::read(&source_path.full_file_path).unwrap_or_default();
 
         return Self
         {
-            text: Box::new(SourceText::from(text)),
+            text: Box::new(SourceText{ text: text, char_index: 0 }),
             source_path: source_path
         }
     }
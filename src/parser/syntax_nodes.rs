@@ -0,0 +1,304 @@
+use std::fmt;
+
+use super::syntax_tokens::{SyntaxToken, SyntaxTokenType, TextSpan};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum SyntaxNodeType {
+    CompilationUnit,
+
+    // Declarations
+    FunctionDeclaration,
+    FunctionDefinition,
+    ParameterList,
+    Parameter,
+    VariableDeclarationStatement,
+    VariableDeclarator,
+    ArrayRankSpecifier,
+    EqualsValueClause,
+    Semantic,
+    RegisterLocation,
+    PackOffsetLocation,
+    TypeDeclarationStatement,
+    StructType,
+    BaseList,
+    ConstantBufferDeclaration,
+    NamespaceDeclaration,
+    TypedefStatement,
+    TypedefDeclarator,
+    AttributeList,
+    Attribute,
+    AttributeArgumentList,
+    // template<typename T> declaration
+    TemplateDeclaration,
+    TemplateParameterList,
+    TemplateParameter,
+    // operator+ as the name of a function
+    OperatorName,
+    // : 4 after the name of a bitfield member
+    BitField,
+
+    // Effects framework
+    TechniqueDeclaration,
+    PassDeclaration,
+    Annotations,
+    StateBlock,
+    StateAssignment,
+
+    // Types
+    PredefinedType,
+    UserType,
+    TypeArgumentList,
+
+    // Statements
+    Block,
+    EmptyStatement,
+    ExpressionStatement,
+    ReturnStatement,
+    IfStatement,
+    ElseClause,
+    ForStatement,
+    WhileStatement,
+    DoStatement,
+    SwitchStatement,
+    SwitchSection,
+    CaseLabel,
+    DefaultLabel,
+    BreakStatement,
+    ContinueStatement,
+    DiscardStatement,
+
+    // Expressions
+    IdentifierName,
+    QualifiedName,
+    LiteralExpression,
+    ParenthesizedExpression,
+    CastExpression,
+    BinaryExpression,
+    AssignmentExpression,
+    PrefixUnaryExpression,
+    PostfixUnaryExpression,
+    ConditionalExpression,
+    CallExpression,
+    ArgumentList,
+    MemberAccessExpression,
+    ElementAccessExpression,
+    ArrayInitializerExpression,
+    // compile vs_2_0 VS() and CompileShader(vs_4_0, VS())
+    CompileExpression,
+    // Legacy sampler_state { ... } initializer
+    SamplerStateExpression,
+    // <texture> inside a state assignment
+    StateReferenceExpression,
+
+    // Tokens skipped by the parser during error recovery
+    SkippedTokens,
+}
+
+impl fmt::Display for SyntaxNodeType
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SyntaxElement
+{
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement
+{
+    pub fn as_node(&self) -> Option<&SyntaxNode>
+    {
+        return match self
+        {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None
+        };
+    }
+
+    pub fn as_token(&self) -> Option<&SyntaxToken>
+    {
+        return match self
+        {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token)
+        };
+    }
+}
+
+// SyntaxNode
+// A node in the concrete syntax tree, the children keep every token of the source in order so the tree is lossless
+#[derive(Clone, Debug)]
+pub struct SyntaxNode
+{
+    pub node_type: SyntaxNodeType,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode
+{
+    pub fn new(node_type: SyntaxNodeType, children: Vec<SyntaxElement>) -> Self
+    {
+        return Self{ node_type: node_type, children: children };
+    }
+
+    pub fn first_token(&self) -> Option<&SyntaxToken>
+    {
+        for child in &self.children
+        {
+            let token = match child
+            {
+                SyntaxElement::Node(node) => node.first_token(),
+                SyntaxElement::Token(token) => Some(token)
+            };
+
+            if token.is_some()
+            {
+                return token;
+            }
+        }
+
+        return None;
+    }
+
+    pub fn last_token(&self) -> Option<&SyntaxToken>
+    {
+        for child in self.children.iter().rev()
+        {
+            let token = match child
+            {
+                SyntaxElement::Node(node) => node.last_token(),
+                SyntaxElement::Token(token) => Some(token)
+            };
+
+            if token.is_some()
+            {
+                return token;
+            }
+        }
+
+        return None;
+    }
+
+    // Span from the first to the last token, without leading trivia
+    // Tokens from other files than the first token are ignored, so a node ending in an included file still has a sensible span
+    pub fn span(&self) -> TextSpan
+    {
+        let tokens = self.descendant_tokens();
+        let first = match tokens.first()
+        {
+            Some(token) => *token,
+            None => return TextSpan::default()
+        };
+
+        let mut span = first.span;
+        for token in tokens.iter().skip(1)
+        {
+            if token.file_index == first.file_index
+            {
+                span = span.union(&token.span);
+            }
+        }
+
+        return span;
+    }
+
+    // Index of the file the node starts in
+    pub fn file_index(&self) -> usize
+    {
+        return match self.first_token()
+        {
+            Some(token) => token.file_index,
+            None => 0
+        };
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode>
+    {
+        return self.children.iter().filter_map(|c| c.as_node());
+    }
+
+    pub fn child_tokens(&self) -> impl Iterator<Item = &SyntaxToken>
+    {
+        return self.children.iter().filter_map(|c| c.as_token());
+    }
+
+    pub fn find_node(&self, node_type: SyntaxNodeType) -> Option<&SyntaxNode>
+    {
+        return self.child_nodes().find(|n| n.node_type == node_type);
+    }
+
+    pub fn find_token(&self, token_type: &SyntaxTokenType) -> Option<&SyntaxToken>
+    {
+        return self.child_tokens().find(|t| t.token_type == *token_type);
+    }
+
+    // All tokens below this node in source order
+    pub fn descendant_tokens(&self) -> Vec<&SyntaxToken>
+    {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        return tokens;
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>)
+    {
+        for child in &self.children
+        {
+            match child
+            {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token)
+            }
+        }
+    }
+
+    // All nodes below this node, including itself, in pre-order
+    pub fn descendant_nodes(&self) -> Vec<&SyntaxNode>
+    {
+        let mut nodes = Vec::new();
+        self.collect_nodes(&mut nodes);
+        return nodes;
+    }
+
+    fn collect_nodes<'a>(&'a self, nodes: &mut Vec<&'a SyntaxNode>)
+    {
+        nodes.push(self);
+        for child in self.child_nodes()
+        {
+            child.collect_nodes(nodes);
+        }
+    }
+
+    // The text of the tokens without trivia, separated by a single space
+    pub fn to_text(&self) -> String
+    {
+        let texts: Vec<&str> = self.descendant_tokens().iter().map(|t| t.text.as_str()).collect();
+        return texts.join(" ");
+    }
+
+    // The text of the tokens including their trivia, only tokens from the file of the first token are written
+    pub fn to_full_string(&self) -> String
+    {
+        let mut text = String::new();
+        let file_index = self.file_index();
+        for token in self.descendant_tokens()
+        {
+            if token.file_index != file_index || token.spelling.is_some()
+            {
+                continue;
+            }
+
+            for trivia in &token.leading_trivia
+            {
+                text.push_str(&trivia.text);
+            }
+            text.push_str(&token.text);
+        }
+
+        return text;
+    }
+}
@@ -0,0 +1,1765 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use super::{ast::{AstNode, CompilationUnit}, diagnostics::Diagnostic, preprocessor::Preprocessor, root_signature::find_root_signature_attributes, source_file::SourceFile, syntax_nodes::{SyntaxElement, SyntaxNode, SyntaxNodeType}, syntax_tokens::{SyntaxToken, SyntaxTokenType, TextSpan}, HlslVersion, ParserSettings};
+
+// SyntaxTree
+// The result of parsing a file: the concrete syntax tree together with the diagnostics of all stages
+pub struct SyntaxTree
+{
+    pub root: SyntaxNode,
+    pub diagnostics: Vec<Diagnostic>,
+    // All files that took part, index 0 is the file that was parsed
+    pub files: Vec<PathBuf>,
+}
+
+impl SyntaxTree
+{
+    pub fn parse_text(text: &str, settings: &ParserSettings) -> Self
+    {
+        return Self::from_preprocessor(Preprocessor::from_text(text, settings), settings);
+    }
+
+    pub fn parse_file(source: &mut SourceFile, settings: &ParserSettings) -> Self
+    {
+        return Self::from_preprocessor(Preprocessor::from_file(source, settings), settings);
+    }
+
+    fn from_preprocessor(preprocessor: Preprocessor, settings: &ParserSettings) -> Self
+    {
+        let (tokens, mut diagnostics, files) = preprocessor.into_parts();
+        // Root signatures are strings, their errors are mapped back to the literals they were written in
+        for attribute in find_root_signature_attributes(&tokens)
+        {
+            if let Err(diagnostic) = attribute.root_signature
+            {
+                diagnostics.push(diagnostic);
+            }
+        }
+        let mut parser = Parser::new(tokens, settings);
+        let root = parser.parse_compilation_unit();
+        diagnostics.extend(parser.diagnostics);
+
+        return Self{
+            root: root,
+            diagnostics: diagnostics,
+            files: files
+        };
+    }
+
+    pub fn has_errors(&self) -> bool
+    {
+        return self.diagnostics.iter().any(|d| d.is_error());
+    }
+
+    // Typed view of the root
+    pub fn compilation_unit(&self) -> CompilationUnit<'_>
+    {
+        return CompilationUnit::cast(&self.root).unwrap();
+    }
+}
+
+// Binary operators with their precedence, higher binds tighter
+fn binary_precedence(token_type: &SyntaxTokenType) -> u32
+{
+    use SyntaxTokenType::*;
+    return match token_type
+    {
+        BarBarToken => 1,
+        AmpersandAmpersandToken => 2,
+        BarToken => 3,
+        CaretToken => 4,
+        AmpersandToken => 5,
+        EqualsEqualsToken | ExclamationEqualsToken => 6,
+        LessThanToken | LessThanEqualsToken | GreaterThanToken | GreaterThanEqualsToken => 7,
+        LessThanLessThanToken | GreaterThanGreaterThanToken => 8,
+        PlusToken | MinusToken => 9,
+        AsteriskToken | SlashToken | PercentToken => 10,
+        _ => 0
+    };
+}
+
+// Precedence of the shift operators, used for expressions inside template arguments where '>' closes the list
+const SHIFT_PRECEDENCE: u32 = 8;
+
+fn is_assignment_operator(token_type: &SyntaxTokenType) -> bool
+{
+    use SyntaxTokenType::*;
+    return matches!(token_type,
+        EqualsToken | AsteriskEqualsToken | SlashEqualsToken | PercentEqualsToken | PlusEqualsToken | MinusEqualsToken |
+        LessThanLessThanEqualsToken | GreaterThanGreaterThanEqualsToken | AmpersandEqualsToken | CaretEqualsToken | BarEqualsToken);
+}
+
+// Modifiers that can be placed in front of the type of a declaration
+fn is_declaration_modifier(token_type: &SyntaxTokenType) -> bool
+{
+    use SyntaxTokenType::*;
+    return matches!(token_type,
+        ConstKeyword | StaticKeyword | UniformKeyword | ExternKeyword | VolatileKeyword | PreciseKeyword |
+        GroupsharedKeyword | SharedKeyword | RowMajorKeyword | ColumnMajorKeyword | SNormKeyword | UNormKeyword |
+        InlineKeyword | ExportKeyword | NointerpolationKeyword | NoperspectiveKeyword | LinearKeyword | CentroidKeyword |
+        GloballycoherentKeyword | InKeyword | OutKeyword | InoutKeyword |
+        PointKeyword | TriangleKeyword | LineAdjKeyword | TriangleAdjKeyword);
+}
+
+// Words that only act as a modifier when followed by a type, like 'line' in a geometry shader or 'indices' in a mesh shader
+fn is_contextual_modifier(text: &str) -> bool
+{
+    return matches!(text, "line" | "sample" | "indices" | "vertices" | "primitives" | "payload" | "center");
+}
+
+// Keywords that are also commonly used as names of variables and fields
+fn is_contextual_name(token_type: &SyntaxTokenType) -> bool
+{
+    use SyntaxTokenType::*;
+    return matches!(token_type,
+        PointKeyword | TriangleKeyword | LineAdjKeyword | TriangleAdjKeyword | LinearKeyword | CentroidKeyword | PassKeyword);
+}
+
+// Builtin templates that are not keywords, like ConstantBuffer<T>
+fn is_builtin_template(name: &str) -> bool
+{
+    return matches!(name, "ConstantBuffer" | "TextureBuffer" | "FeedbackTexture2D" | "FeedbackTexture2DArray" | "RWTexture2DMS" | "RWTexture2DMSArray");
+}
+
+fn is_technique_keyword(token_type: &SyntaxTokenType) -> bool
+{
+    use SyntaxTokenType::*;
+    return matches!(token_type, TechniqueKeyword | Technique10Keyword | Technique11Keyword);
+}
+
+// Determines where skipping tokens stops after a syntax error
+#[derive(Clone, Copy, PartialEq)]
+enum RecoveryContext
+{
+    TopLevel,
+    Statement,
+    // Fields of structs and constant buffers, and state assignments
+    Member,
+    Annotation,
+    Pass,
+}
+
+fn node(node_type: SyntaxNodeType, children: Vec<SyntaxElement>) -> SyntaxNode
+{
+    return SyntaxNode::new(node_type, children);
+}
+
+// Parser
+// Recursive descent parser that turns the preprocessed token stream into a concrete syntax tree
+pub struct Parser
+{
+    tokens: Vec<SyntaxToken>,
+    index: usize,
+    diagnostics: Vec<Diagnostic>,
+    // Names of user defined types seen so far, needed to tell casts apart from parenthesized expressions
+    type_names: HashSet<String>,
+    // Index of the token the last error was reported at
+    last_error_index: Option<usize>,
+    hlsl_version: HlslVersion,
+    // Names of template structs and functions, a '<' after these starts a type argument list
+    template_names: HashSet<String>,
+}
+
+impl Parser
+{
+    pub fn new(tokens: Vec<SyntaxToken>, settings: &ParserSettings) -> Self
+    {
+        let mut tokens = tokens;
+        // Words that only became keywords in HLSL 2021 are identifiers in older versions
+        if settings.hlsl_version < HlslVersion::Hlsl2021
+        {
+            for token in tokens.iter_mut()
+            {
+                if matches!(token.token_type, SyntaxTokenType::TemplateKeyword | SyntaxTokenType::TypenameKeyword | SyntaxTokenType::OperatorKeyword)
+                {
+                    token.token_type = SyntaxTokenType::IdentifierToken;
+                }
+            }
+        }
+
+        // The parser relies on the stream ending with an end of file token
+        if tokens.last().map(|t| t.token_type != SyntaxTokenType::EndOfFileToken).unwrap_or(true)
+        {
+            let end = tokens.last().map(|t| t.span.end).unwrap_or(0);
+            tokens.push(SyntaxToken::new(SyntaxTokenType::EndOfFileToken, TextSpan::new(end, end), String::new()));
+        }
+
+        return Self{
+            tokens: tokens,
+            index: 0,
+            diagnostics: Vec::new(),
+            type_names: HashSet::new(),
+            last_error_index: None,
+            hlsl_version: settings.hlsl_version,
+            template_names: HashSet::new()
+        };
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    fn current(&self) -> &SyntaxToken
+    {
+        return self.peek(0);
+    }
+
+    fn peek(&self, offset: usize) -> &SyntaxToken
+    {
+        let index = (self.index + offset).min(self.tokens.len() - 1);
+        return &self.tokens[index];
+    }
+
+    fn at(&self, token_type: &SyntaxTokenType) -> bool
+    {
+        return self.current().token_type == *token_type;
+    }
+
+    fn peek_is(&self, offset: usize, token_type: &SyntaxTokenType) -> bool
+    {
+        return self.peek(offset).token_type == *token_type;
+    }
+
+    fn at_end(&self) -> bool
+    {
+        return self.at(&SyntaxTokenType::EndOfFileToken);
+    }
+
+    // Consumes the current token, the end of file token is never consumed
+    fn next(&mut self) -> SyntaxToken
+    {
+        let token = self.current().clone();
+        if self.index < self.tokens.len() - 1
+        {
+            self.index += 1;
+        }
+        return token;
+    }
+
+    // Reports an error at the current token, only the first error at a token is kept to avoid cascading errors
+    fn error_at_current(&mut self, message: String)
+    {
+        let token = self.current();
+        let diagnostic = Diagnostic::error(message, token.span).in_file(token.file_index);
+        self.report(diagnostic);
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic)
+    {
+        if self.last_error_index == Some(self.index)
+        {
+            return;
+        }
+
+        self.last_error_index = Some(self.index);
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn found_text(&self) -> String
+    {
+        let token = self.current();
+        if token.token_type == SyntaxTokenType::EndOfFileToken
+        {
+            return String::from("end of file");
+        }
+        return format!("'{}'", token.text);
+    }
+
+    // Inserts a zero width token right after the previous token, so a missing ';' is reported at the end of the line
+    fn missing_token(&mut self, token_type: SyntaxTokenType, message: String) -> SyntaxToken
+    {
+        let current = self.current();
+        let (position, file_index) = match self.index.checked_sub(1).map(|i| &self.tokens[i])
+        {
+            Some(previous) if previous.file_index == current.file_index => (previous.span.end, previous.file_index),
+            _ => (current.span.start, current.file_index)
+        };
+
+        if self.last_error_index != Some(self.index)
+        {
+            self.last_error_index = Some(self.index);
+            self.diagnostics.push(Diagnostic::error(message, TextSpan::new(position, position)).in_file(file_index));
+        }
+        return SyntaxToken::missing(token_type, position, file_index);
+    }
+
+    fn expect(&mut self, token_type: SyntaxTokenType, text: &str) -> SyntaxToken
+    {
+        if self.at(&token_type)
+        {
+            return self.next();
+        }
+
+        let message = format!("Expected '{}' but found {}", text, self.found_text());
+        return self.missing_token(token_type, message);
+    }
+
+    fn is_name_token(token: &SyntaxToken) -> bool
+    {
+        return token.token_type == SyntaxTokenType::IdentifierToken || is_contextual_name(&token.token_type);
+    }
+
+    fn expect_name(&mut self) -> SyntaxToken
+    {
+        if Self::is_name_token(self.current())
+        {
+            return self.next();
+        }
+
+        let message = format!("Expected identifier but found {}", self.found_text());
+        return self.missing_token(SyntaxTokenType::IdentifierToken, message);
+    }
+
+    // Expects any identifier or keyword, used for member names, state names and shader profiles
+    fn expect_identifier_like(&mut self) -> SyntaxToken
+    {
+        if self.current().is_identifier_like()
+        {
+            return self.next();
+        }
+
+        let message = format!("Expected identifier but found {}", self.found_text());
+        return self.missing_token(SyntaxTokenType::IdentifierToken, message);
+    }
+
+    // Placeholder for an expression or type that could not be parsed, it does not consume any tokens
+    fn missing_name(&mut self, message: String) -> SyntaxNode
+    {
+        self.error_at_current(message);
+        let current = self.current();
+        let token = SyntaxToken::missing(SyntaxTokenType::IdentifierToken, current.span.start, current.file_index);
+        return node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(token)]);
+    }
+
+    // Checks whether skipping tokens should stop at the current token
+    fn is_synchronization_point(&self, context: RecoveryContext) -> bool
+    {
+        use SyntaxTokenType::*;
+        let token = self.current();
+        let token_type = &token.token_type;
+        if matches!(token_type, EndOfFileToken | CloseBraceToken)
+        {
+            return true;
+        }
+
+        let starts_declaration = matches!(token_type,
+            StructKeyword | CBufferKeyword | TBufferKeyword | NamespaceKeyword | TypedefKeyword | ClassKeyword | InterfaceKeyword | TemplateKeyword) ||
+            is_technique_keyword(token_type) || (is_declaration_modifier(token_type) && !matches!(token_type, InKeyword | OutKeyword | InoutKeyword));
+
+        return match context
+        {
+            RecoveryContext::TopLevel => starts_declaration || token.is_predefined_type(),
+            RecoveryContext::Statement => starts_declaration || token.is_predefined_type() || token_type == &OpenBraceToken || matches!(token_type,
+                IfKeyword | ForKeyword | WhileKeyword | DoKeyword | SwitchKeyword | ReturnKeyword | BreakKeyword |
+                ContinueKeyword | DiscardKeyword | CaseKeyword | DefaultKeyword),
+            RecoveryContext::Member => starts_declaration || token.is_predefined_type(),
+            RecoveryContext::Annotation => token_type == &GreaterThanToken || token.is_predefined_type(),
+            RecoveryContext::Pass => token_type == &PassKeyword,
+        };
+    }
+
+    // Skips at least one token, stopping after a ';' or in front of a token where parsing can continue
+    fn skip_tokens(&mut self, context: RecoveryContext) -> SyntaxNode
+    {
+        let message = format!("Unexpected {}", self.found_text());
+        let first = self.current().clone();
+        let start_index = self.index;
+
+        let mut children = Vec::new();
+        loop
+        {
+            let token = self.next();
+            let is_semi = token.token_type == SyntaxTokenType::SemiToken;
+            children.push(SyntaxElement::Token(token));
+            if is_semi || self.is_synchronization_point(context)
+            {
+                break;
+            }
+        }
+
+        let skipped = node(SyntaxNodeType::SkippedTokens, children);
+        if self.last_error_index != Some(start_index)
+        {
+            let mut span = first.span;
+            for token in skipped.descendant_tokens()
+            {
+                if token.file_index == first.file_index && token.span.start >= span.start
+                {
+                    span = span.union(&token.span);
+                }
+            }
+            self.last_error_index = Some(start_index);
+            self.diagnostics.push(Diagnostic::error(message, span).in_file(first.file_index));
+        }
+        return skipped;
+    }
+
+    // Parses an element of a list, when the element does not consume any tokens its errors are dropped and
+    // tokens are skipped instead, which guarantees progress
+    fn parse_list_element(&mut self, context: RecoveryContext, parse: fn(&mut Self) -> SyntaxNode) -> SyntaxNode
+    {
+        let start_index = self.index;
+        let diagnostic_count = self.diagnostics.len();
+        let last_error_index = self.last_error_index;
+
+        let element = parse(self);
+        if self.index != start_index
+        {
+            return element;
+        }
+
+        self.diagnostics.truncate(diagnostic_count);
+        self.last_error_index = last_error_index;
+        return self.skip_tokens(context);
+    }
+
+    // Splits a '>>' token into two '>' tokens so nested template arguments can be closed
+    fn split_greater_than(&mut self)
+    {
+        if !self.at(&SyntaxTokenType::GreaterThanGreaterThanToken)
+        {
+            return;
+        }
+
+        let token = self.current().clone();
+        let mut first = token.clone();
+        first.token_type = SyntaxTokenType::GreaterThanToken;
+        first.text = String::from(">");
+        first.span = TextSpan::new(token.span.start, token.span.start + 1);
+
+        let mut second = token.clone();
+        second.token_type = SyntaxTokenType::GreaterThanToken;
+        second.text = String::from(">");
+        second.span = TextSpan::new(token.span.start + 1, token.span.end);
+        second.leading_trivia.clear();
+
+        self.tokens[self.index] = first;
+        self.tokens.insert(self.index + 1, second);
+    }
+
+    pub fn parse_compilation_unit(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        while !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::TopLevel, Self::parse_top_level_declaration)));
+        }
+
+        let end_of_file = self.tokens[self.tokens.len() - 1].clone();
+        children.push(SyntaxElement::Token(end_of_file));
+        return node(SyntaxNodeType::CompilationUnit, children);
+    }
+
+    fn parse_top_level_declaration(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        let token_type = self.current().token_type.clone();
+        return match token_type
+        {
+            SemiToken => node(SyntaxNodeType::EmptyStatement, vec![SyntaxElement::Token(self.next())]),
+            NamespaceKeyword => self.parse_namespace(),
+            CBufferKeyword | TBufferKeyword => self.parse_constant_buffer(),
+            OpenBracketToken if self.at_attributed_constant_buffer() => self.parse_constant_buffer(),
+            TypedefKeyword => self.parse_typedef(),
+            _ if is_technique_keyword(&token_type) => self.parse_technique(),
+            _ => self.parse_declaration(true)
+        };
+    }
+
+    fn parse_namespace(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.next()));
+        children.push(SyntaxElement::Token(self.expect_name()));
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::TopLevel, Self::parse_top_level_declaration)));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        return node(SyntaxNodeType::NamespaceDeclaration, children);
+    }
+
+    // [[vk::binding(0, 1)]] cbuffer Frame { ... }
+    fn at_attributed_constant_buffer(&self) -> bool
+    {
+        let mut offset = 0;
+        let mut depth = 0;
+        loop
+        {
+            match self.peek(offset).token_type
+            {
+                SyntaxTokenType::OpenBracketToken => depth += 1,
+                SyntaxTokenType::CloseBracketToken => depth -= 1,
+                SyntaxTokenType::EndOfFileToken => return false,
+                _ => {}
+            }
+            offset += 1;
+            if depth == 0 && !self.peek_is(offset, &SyntaxTokenType::OpenBracketToken)
+            {
+                return matches!(self.peek(offset).token_type, SyntaxTokenType::CBufferKeyword | SyntaxTokenType::TBufferKeyword);
+            }
+        }
+    }
+
+    fn parse_constant_buffer(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        self.parse_attributes(&mut children);
+        children.push(SyntaxElement::Token(self.next()));
+        children.push(SyntaxElement::Token(self.expect_name()));
+        while self.at(&SyntaxTokenType::ColonToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_variable_location()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Member, |p| p.parse_declaration(false))));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        if self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        return node(SyntaxNodeType::ConstantBufferDeclaration, children);
+    }
+
+    fn parse_typedef(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.next()));
+        while is_declaration_modifier(&self.current().token_type)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        children.push(SyntaxElement::Node(self.parse_type()));
+
+        loop
+        {
+            let mut declarator = Vec::new();
+            let name = self.expect_name();
+            self.type_names.insert(name.text.clone());
+            declarator.push(SyntaxElement::Token(name));
+            while self.at(&SyntaxTokenType::OpenBracketToken)
+            {
+                declarator.push(SyntaxElement::Node(self.parse_array_rank_specifier()));
+            }
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::TypedefDeclarator, declarator)));
+
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::SemiToken, ";")));
+        return node(SyntaxNodeType::TypedefStatement, children);
+    }
+
+    // technique Name < annotations > { pass ... }
+    fn parse_technique(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.next()));
+        if Self::is_name_token(self.current())
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        if self.at(&SyntaxTokenType::LessThanToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_annotations()));
+        }
+
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Pass, Self::parse_pass)));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        if self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        return node(SyntaxNodeType::TechniqueDeclaration, children);
+    }
+
+    // pass Name < annotations > { state assignments }
+    fn parse_pass(&mut self) -> SyntaxNode
+    {
+        if !self.at(&SyntaxTokenType::PassKeyword)
+        {
+            return self.skip_tokens(RecoveryContext::Pass);
+        }
+
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        if Self::is_name_token(self.current())
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        if self.at(&SyntaxTokenType::LessThanToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_annotations()));
+        }
+        children.push(SyntaxElement::Node(self.parse_state_block()));
+        return node(SyntaxNodeType::PassDeclaration, children);
+    }
+
+    // < string UIName = "Name"; float UIMin = 0.0; >
+    fn parse_annotations(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::LessThanToken, "<")));
+        while !self.at(&SyntaxTokenType::GreaterThanToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Annotation, |p| p.parse_declaration(false))));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::GreaterThanToken, ">")));
+        return node(SyntaxNodeType::Annotations, children);
+    }
+
+    // { AddressU = Clamp; Texture = <tex>; SetPixelShader(CompileShader(ps_4_0, PS())); }
+    fn parse_state_block(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+
+        // State object arrays have a block per element: RasterizerState states[2] { { ... }, { ... } };
+        if self.at(&SyntaxTokenType::OpenBraceToken)
+        {
+            loop
+            {
+                children.push(SyntaxElement::Node(self.parse_state_block()));
+                if !self.at(&SyntaxTokenType::CommaToken)
+                {
+                    break;
+                }
+                children.push(SyntaxElement::Token(self.next()));
+            }
+        }
+
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Member, Self::parse_state_statement)));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        return node(SyntaxNodeType::StateBlock, children);
+    }
+
+    fn parse_state_statement(&mut self) -> SyntaxNode
+    {
+        let is_assignment = self.current().is_identifier_like() &&
+            (self.peek_is(1, &SyntaxTokenType::EqualsToken) || self.peek_is(1, &SyntaxTokenType::OpenBracketToken));
+
+        if is_assignment
+        {
+            return self.parse_state_assignment();
+        }
+        return self.parse_expression_statement();
+    }
+
+    fn parse_state_assignment(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.next()));
+        if self.at(&SyntaxTokenType::OpenBracketToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_array_rank_specifier()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::EqualsToken, "=")));
+        children.push(SyntaxElement::Node(self.parse_state_value()));
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::SemiToken, ";")));
+        return node(SyntaxNodeType::StateAssignment, children);
+    }
+
+    fn parse_state_value(&mut self) -> SyntaxNode
+    {
+        // Texture = <gDiffuseTexture>;
+        if self.at(&SyntaxTokenType::LessThanToken)
+        {
+            let open = self.next();
+            let reference = self.parse_binary_expression(SHIFT_PRECEDENCE + 1);
+            let close = self.expect(SyntaxTokenType::GreaterThanToken, ">");
+            return node(SyntaxNodeType::StateReferenceExpression, vec![SyntaxElement::Token(open), SyntaxElement::Node(reference), SyntaxElement::Token(close)]);
+        }
+
+        // Values are often plain words that happen to be keywords, like 'MinFilter = linear;'
+        if self.current().token_type != SyntaxTokenType::IdentifierToken && self.current().is_identifier_like() && self.peek_is(1, &SyntaxTokenType::SemiToken)
+        {
+            return node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.next())]);
+        }
+
+        if self.at(&SyntaxTokenType::OpenBraceToken)
+        {
+            return self.parse_array_initializer();
+        }
+
+        return self.parse_expression();
+    }
+
+    fn parse_attribute_list(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.next()));
+        // [[vk::binding(0, 1)]]
+        let is_double = self.at(&SyntaxTokenType::OpenBracketToken);
+        if is_double
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        loop
+        {
+            let mut attribute = Vec::new();
+            attribute.push(SyntaxElement::Node(self.parse_attribute_name()));
+            if self.at(&SyntaxTokenType::OpenParenToken)
+            {
+                let mut arguments = self.parse_argument_list();
+                arguments.node_type = SyntaxNodeType::AttributeArgumentList;
+                attribute.push(SyntaxElement::Node(arguments));
+            }
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::Attribute, attribute)));
+
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBracketToken, "]")));
+        if is_double
+        {
+            children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBracketToken, "]")));
+        }
+        return node(SyntaxNodeType::AttributeList, children);
+    }
+
+    // Attribute names can be keywords like [domain("tri")] or qualified like [[vk::binding(0)]]
+    fn parse_attribute_name(&mut self) -> SyntaxNode
+    {
+        let mut name = node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.expect_identifier_like())]);
+        while self.at(&SyntaxTokenType::ColonColonToken)
+        {
+            let separator = self.next();
+            let right = node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.expect_identifier_like())]);
+            name = node(SyntaxNodeType::QualifiedName, vec![SyntaxElement::Node(name), SyntaxElement::Token(separator), SyntaxElement::Node(right)]);
+        }
+        return name;
+    }
+
+    fn parse_attributes(&mut self, children: &mut Vec<SyntaxElement>)
+    {
+        while self.at(&SyntaxTokenType::OpenBracketToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_attribute_list()));
+        }
+    }
+
+    // Parses a variable, function or type declaration
+    fn parse_declaration(&mut self, allow_functions: bool) -> SyntaxNode
+    {
+        if self.at(&SyntaxTokenType::TemplateKeyword)
+        {
+            return self.parse_template_declaration();
+        }
+
+        let start_index = self.index;
+        let mut children = Vec::new();
+        self.parse_attributes(&mut children);
+        while is_declaration_modifier(&self.current().token_type) ||
+            (is_contextual_modifier(&self.current().text) && self.peek(1).is_identifier_like())
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        let declaration_type = self.parse_type();
+        if self.index == start_index
+        {
+            // Nothing that looks like a declaration, the caller skips the tokens
+            children.push(SyntaxElement::Node(declaration_type));
+            return node(SyntaxNodeType::VariableDeclarationStatement, children);
+        }
+
+        let is_struct = declaration_type.node_type == SyntaxNodeType::StructType;
+        children.push(SyntaxElement::Node(declaration_type));
+
+        // struct S { ... };
+        if is_struct && self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+            return node(SyntaxNodeType::TypeDeclarationStatement, children);
+        }
+
+        if allow_functions
+        {
+            if self.at(&SyntaxTokenType::OperatorKeyword)
+            {
+                children.push(SyntaxElement::Node(self.parse_operator_name()));
+                return self.parse_function_rest(children);
+            }
+
+            let is_function = Self::is_name_token(self.current()) &&
+                (self.peek_is(1, &SyntaxTokenType::OpenParenToken) || self.peek_is(1, &SyntaxTokenType::ColonColonToken));
+
+            if is_function
+            {
+                children.push(SyntaxElement::Node(self.parse_name()));
+                return self.parse_function_rest(children);
+            }
+        }
+
+        loop
+        {
+            children.push(SyntaxElement::Node(self.parse_variable_declarator()));
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::SemiToken, ";")));
+        return node(SyntaxNodeType::VariableDeclarationStatement, children);
+    }
+
+    // template<typename T, int N = 4> declaration
+    fn parse_template_declaration(&mut self) -> SyntaxNode
+    {
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        let mut parameters = vec![SyntaxElement::Token(self.expect(SyntaxTokenType::LessThanToken, "<"))];
+        while !self.at(&SyntaxTokenType::GreaterThanToken) && !self.at_end()
+        {
+            parameters.push(SyntaxElement::Node(self.parse_template_parameter()));
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            parameters.push(SyntaxElement::Token(self.next()));
+        }
+        parameters.push(SyntaxElement::Token(self.expect(SyntaxTokenType::GreaterThanToken, ">")));
+        children.push(SyntaxElement::Node(node(SyntaxNodeType::TemplateParameterList, parameters)));
+
+        // The struct can refer to itself with type arguments
+        if Self::is_struct_keyword(self.current()) && self.peek_is(1, &SyntaxTokenType::IdentifierToken)
+        {
+            self.template_names.insert(self.peek(1).text.clone());
+        }
+
+        let declaration = self.parse_declaration(true);
+        if let Some(name) = Self::declared_name(&declaration)
+        {
+            self.template_names.insert(name);
+        }
+        children.push(SyntaxElement::Node(declaration));
+        return node(SyntaxNodeType::TemplateDeclaration, children);
+    }
+
+    // typename T, class T = float or int N = 4
+    fn parse_template_parameter(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        let is_type_parameter = matches!(self.current().token_type, SyntaxTokenType::TypenameKeyword | SyntaxTokenType::ClassKeyword);
+        if is_type_parameter
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        else
+        {
+            children.push(SyntaxElement::Node(self.parse_type()));
+        }
+
+        let name = self.expect_name();
+        if is_type_parameter
+        {
+            self.type_names.insert(name.text.clone());
+        }
+        children.push(SyntaxElement::Token(name));
+
+        if self.at(&SyntaxTokenType::EqualsToken)
+        {
+            let equals = self.next();
+            let value = if is_type_parameter { self.parse_type() } else { self.parse_binary_expression(SHIFT_PRECEDENCE + 1) };
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::EqualsValueClause, vec![SyntaxElement::Token(equals), SyntaxElement::Node(value)])));
+        }
+        return node(SyntaxNodeType::TemplateParameter, children);
+    }
+
+    // Name of the struct or function a template declares
+    fn declared_name(declaration: &SyntaxNode) -> Option<String>
+    {
+        if let Some(struct_type) = declaration.find_node(SyntaxNodeType::StructType)
+        {
+            return struct_type.child_tokens().nth(1).filter(|t| t.token_type == SyntaxTokenType::IdentifierToken).map(|t| t.text.clone());
+        }
+
+        let name = declaration.child_nodes().find(|n| matches!(n.node_type, SyntaxNodeType::IdentifierName | SyntaxNodeType::QualifiedName))?;
+        return name.last_token().map(|t| t.text.clone());
+    }
+
+    // operator+, operator== or operator[]
+    fn parse_operator_name(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        match self.current().token_type
+        {
+            OpenParenToken => {
+                children.push(SyntaxElement::Token(self.next()));
+                children.push(SyntaxElement::Token(self.expect(CloseParenToken, ")")));
+            },
+            OpenBracketToken => {
+                children.push(SyntaxElement::Token(self.next()));
+                children.push(SyntaxElement::Token(self.expect(CloseBracketToken, "]")));
+            },
+            _ if !self.current().is_identifier_like() && !self.at_end() && !self.at(&OpenBraceToken) && !self.at(&SemiToken) => {
+                children.push(SyntaxElement::Token(self.next()));
+            },
+            _ => {
+                let message = format!("Expected operator but found {}", self.found_text());
+                children.push(SyntaxElement::Token(self.missing_token(PlusToken, message)));
+            }
+        }
+        return node(SyntaxNodeType::OperatorName, children);
+    }
+
+    fn parse_function_rest(&mut self, mut children: Vec<SyntaxElement>) -> SyntaxNode
+    {
+        children.push(SyntaxElement::Node(self.parse_parameter_list()));
+        while self.at(&SyntaxTokenType::ColonToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_variable_location()));
+        }
+
+        if self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+            return node(SyntaxNodeType::FunctionDeclaration, children);
+        }
+
+        children.push(SyntaxElement::Node(self.parse_block()));
+        return node(SyntaxNodeType::FunctionDefinition, children);
+    }
+
+    fn parse_parameter_list(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenParenToken, "(")));
+
+        // float4 main(void)
+        if self.at(&SyntaxTokenType::VoidKeyword) && self.peek_is(1, &SyntaxTokenType::CloseParenToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        while !self.at(&SyntaxTokenType::CloseParenToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_parameter()));
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseParenToken, ")")));
+        return node(SyntaxNodeType::ParameterList, children);
+    }
+
+    fn parse_parameter(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        self.parse_attributes(&mut children);
+        while is_declaration_modifier(&self.current().token_type) ||
+            (is_contextual_modifier(&self.current().text) && self.peek(1).is_identifier_like())
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        children.push(SyntaxElement::Node(self.parse_type()));
+        children.push(SyntaxElement::Node(self.parse_variable_declarator()));
+        return node(SyntaxNodeType::Parameter, children);
+    }
+
+    // name[N] : SEMANTIC : register(t0) < annotations > = initializer
+    fn parse_variable_declarator(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        // Names can be left out in function declarations
+        if Self::is_name_token(self.current())
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        while self.at(&SyntaxTokenType::OpenBracketToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_array_rank_specifier()));
+        }
+
+        while self.at(&SyntaxTokenType::ColonToken)
+        {
+            // uint flags : 4;
+            if !self.peek(1).is_identifier_like()
+            {
+                children.push(SyntaxElement::Node(self.parse_bit_field()));
+                continue;
+            }
+            children.push(SyntaxElement::Node(self.parse_variable_location()));
+        }
+
+        if self.at(&SyntaxTokenType::LessThanToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_annotations()));
+        }
+
+        if self.at(&SyntaxTokenType::EqualsToken)
+        {
+            let equals = self.next();
+            let value = self.parse_initializer();
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::EqualsValueClause, vec![SyntaxElement::Token(equals), SyntaxElement::Node(value)])));
+        }
+        else if self.at(&SyntaxTokenType::OpenBraceToken)
+        {
+            // BlendState AdditiveBlending { BlendEnable[0] = TRUE; };
+            children.push(SyntaxElement::Node(self.parse_state_block()));
+        }
+
+        return node(SyntaxNodeType::VariableDeclarator, children);
+    }
+
+    fn parse_bit_field(&mut self) -> SyntaxNode
+    {
+        let colon = self.next();
+        if self.hlsl_version < HlslVersion::Hlsl2021
+        {
+            let message = String::from("Bitfields are only supported in HLSL 2021");
+            self.report(Diagnostic::error(message, colon.span).in_file(colon.file_index));
+        }
+        let width = self.parse_conditional_expression();
+        return node(SyntaxNodeType::BitField, vec![SyntaxElement::Token(colon), SyntaxElement::Node(width)]);
+    }
+
+    fn parse_array_rank_specifier(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBracketToken, "[")));
+        if !self.at(&SyntaxTokenType::CloseBracketToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_expression()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBracketToken, "]")));
+        return node(SyntaxNodeType::ArrayRankSpecifier, children);
+    }
+
+    // : SEMANTIC, : register(b0, space1) or : packoffset(c0.x)
+    fn parse_variable_location(&mut self) -> SyntaxNode
+    {
+        let colon = self.expect(SyntaxTokenType::ColonToken, ":");
+        let node_type = match self.current().token_type
+        {
+            SyntaxTokenType::RegisterKeyword => SyntaxNodeType::RegisterLocation,
+            SyntaxTokenType::PackoffsetKeyword => SyntaxNodeType::PackOffsetLocation,
+            _ => {
+                let semantic = self.expect_identifier_like();
+                return node(SyntaxNodeType::Semantic, vec![SyntaxElement::Token(colon), SyntaxElement::Token(semantic)]);
+            }
+        };
+
+        let mut children = vec![SyntaxElement::Token(colon), SyntaxElement::Token(self.next())];
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenParenToken, "(")));
+        while !self.at(&SyntaxTokenType::CloseParenToken) && !self.at_end() && !self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseParenToken, ")")));
+        return node(node_type, children);
+    }
+
+    fn parse_initializer(&mut self) -> SyntaxNode
+    {
+        if self.at(&SyntaxTokenType::OpenBraceToken)
+        {
+            return self.parse_array_initializer();
+        }
+        return self.parse_assignment_expression();
+    }
+
+    // { 1, 2, { 3, 4 }, }
+    fn parse_array_initializer(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_initializer()));
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        return node(SyntaxNodeType::ArrayInitializerExpression, children);
+    }
+
+    // Types
+
+    fn is_scalar_or_vector_type(token: &SyntaxToken) -> bool
+    {
+        return token.is_predefined_type() && !token.is_object_type();
+    }
+
+    // struct, class and interface all declare a StructType
+    fn is_struct_keyword(token: &SyntaxToken) -> bool
+    {
+        return matches!(token.token_type, SyntaxTokenType::StructKeyword | SyntaxTokenType::ClassKeyword | SyntaxTokenType::InterfaceKeyword);
+    }
+
+    fn parse_type(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        if Self::is_struct_keyword(self.current())
+        {
+            return self.parse_struct_type();
+        }
+
+        // unsigned int
+        if self.at(&UnsignedKeyword) && Self::is_scalar_or_vector_type(self.peek(1))
+        {
+            let unsigned = self.next();
+            let keyword = self.next();
+            return node(SyntaxNodeType::PredefinedType, vec![SyntaxElement::Token(unsigned), SyntaxElement::Token(keyword)]);
+        }
+
+        if self.current().is_predefined_type()
+        {
+            let keyword = self.next();
+            let is_object_type = keyword.is_object_type();
+            let mut children = vec![SyntaxElement::Token(keyword)];
+            if is_object_type && self.at(&LessThanToken)
+            {
+                children.push(SyntaxElement::Node(self.parse_type_argument_list()));
+            }
+            return node(SyntaxNodeType::PredefinedType, children);
+        }
+
+        if self.at(&IdentifierToken) || self.at(&ColonColonToken)
+        {
+            let name = self.parse_name();
+            let is_template = name.node_type == SyntaxNodeType::IdentifierName &&
+                (is_builtin_template(&name.to_text()) || self.template_names.contains(&name.to_text()));
+            let mut children = vec![SyntaxElement::Node(name)];
+            if is_template && self.at(&LessThanToken)
+            {
+                children.push(SyntaxElement::Node(self.parse_type_argument_list()));
+            }
+            return node(SyntaxNodeType::UserType, children);
+        }
+
+        let message = format!("Expected type but found {}", self.found_text());
+        let name = self.missing_name(message);
+        return node(SyntaxNodeType::UserType, vec![SyntaxElement::Node(name)]);
+    }
+
+    // <float4>, <float, 4> or <MyStruct, 3>
+    fn parse_type_argument_list(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::LessThanToken, "<")));
+        loop
+        {
+            while matches!(self.current().token_type, SyntaxTokenType::UNormKeyword | SyntaxTokenType::SNormKeyword)
+            {
+                children.push(SyntaxElement::Token(self.next()));
+            }
+
+            let is_type = self.current().is_predefined_type() || self.at(&SyntaxTokenType::IdentifierToken) ||
+                Self::is_struct_keyword(self.current()) || self.at(&SyntaxTokenType::UnsignedKeyword);
+            if is_type
+            {
+                children.push(SyntaxElement::Node(self.parse_type()));
+            }
+            else
+            {
+                children.push(SyntaxElement::Node(self.parse_binary_expression(SHIFT_PRECEDENCE + 1)));
+            }
+
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+
+        self.split_greater_than();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::GreaterThanToken, ">")));
+        return node(SyntaxNodeType::TypeArgumentList, children);
+    }
+
+    // struct Name : Base { members }, class Name : Interface { members } or interface Name { methods }
+    fn parse_struct_type(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.next()));
+        if Self::is_name_token(self.current())
+        {
+            let name = self.next();
+            self.type_names.insert(name.text.clone());
+            children.push(SyntaxElement::Token(name));
+        }
+
+        if self.at(&SyntaxTokenType::ColonToken)
+        {
+            let mut base_list = vec![SyntaxElement::Token(self.next())];
+            loop
+            {
+                base_list.push(SyntaxElement::Node(self.parse_name()));
+                if !self.at(&SyntaxTokenType::CommaToken)
+                {
+                    break;
+                }
+                base_list.push(SyntaxElement::Token(self.next()));
+            }
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::BaseList, base_list)));
+        }
+
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            if self.at(&SyntaxTokenType::SemiToken)
+            {
+                children.push(SyntaxElement::Node(node(SyntaxNodeType::EmptyStatement, vec![SyntaxElement::Token(self.next())])));
+                continue;
+            }
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Member, |p| p.parse_declaration(true))));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        return node(SyntaxNodeType::StructType, children);
+    }
+
+    // Name, Outer::Inner::Name or ::Name
+    fn parse_name(&mut self) -> SyntaxNode
+    {
+        let mut name = if self.at(&SyntaxTokenType::ColonColonToken)
+        {
+            let separator = self.next();
+            let right = node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.expect_name())]);
+            node(SyntaxNodeType::QualifiedName, vec![SyntaxElement::Token(separator), SyntaxElement::Node(right)])
+        }
+        else
+        {
+            node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.expect_name())])
+        };
+
+        while self.at(&SyntaxTokenType::ColonColonToken)
+        {
+            let separator = self.next();
+            let right = node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.expect_name())]);
+            name = node(SyntaxNodeType::QualifiedName, vec![SyntaxElement::Node(name), SyntaxElement::Token(separator), SyntaxElement::Node(right)]);
+        }
+
+        return name;
+    }
+
+    // Statements
+
+    fn parse_block(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenBraceToken, "{")));
+        while !self.at(&SyntaxTokenType::CloseBraceToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Statement, Self::parse_statement)));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseBraceToken, "}")));
+        return node(SyntaxNodeType::Block, children);
+    }
+
+    // Checks whether the statement at the current token declares a variable
+    fn is_declaration_start(&self) -> bool
+    {
+        use SyntaxTokenType::*;
+        let token = self.current();
+        if is_declaration_modifier(&token.token_type) || Self::is_struct_keyword(token) || token.token_type == UnsignedKeyword
+        {
+            return true;
+        }
+
+        if is_contextual_modifier(&token.text) && self.peek(1).is_identifier_like() && self.peek(2).is_identifier_like()
+        {
+            return true;
+        }
+
+        if token.is_predefined_type()
+        {
+            // float3(1, 2, 3).x is an expression
+            return !self.peek_is(1, &OpenParenToken);
+        }
+
+        if token.token_type != IdentifierToken && token.token_type != ColonColonToken
+        {
+            return false;
+        }
+
+        // Skip over a possibly qualified type name
+        let mut offset = if token.token_type == ColonColonToken { 1 } else { 0 };
+        while self.peek_is(offset + 1, &ColonColonToken) && self.peek_is(offset + 2, &IdentifierToken)
+        {
+            offset += 2;
+        }
+
+        // Pair<float> p;
+        if self.peek_is(offset + 1, &LessThanToken) && self.template_names.contains(&self.peek(offset).text)
+        {
+            match self.skip_type_arguments(offset + 1)
+            {
+                Some(end) => offset = end,
+                None => return false
+            }
+        }
+
+        return Self::is_name_token(self.peek(offset + 1));
+    }
+
+    // Looks ahead over a type argument list starting at the offset, returns the offset of the closing '>'
+    fn skip_type_arguments(&self, offset: usize) -> Option<usize>
+    {
+        use SyntaxTokenType::*;
+        let mut depth = 0;
+        let mut offset = offset;
+        loop
+        {
+            match self.peek(offset).token_type
+            {
+                LessThanToken => depth += 1,
+                GreaterThanToken => depth -= 1,
+                GreaterThanGreaterThanToken => depth -= 2,
+                SemiToken | OpenBraceToken | CloseBraceToken | EndOfFileToken => return None,
+                _ => {}
+            }
+
+            if depth <= 0
+            {
+                return if depth == 0 { Some(offset) } else { None };
+            }
+            offset += 1;
+        }
+    }
+
+    fn parse_statement(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        if self.at(&OpenBracketToken)
+        {
+            // Attributes like [unroll] are stored as the first children of the statement they apply to
+            let mut attributes = Vec::new();
+            self.parse_attributes(&mut attributes);
+            let mut statement = self.parse_statement();
+            attributes.append(&mut statement.children);
+            statement.children = attributes;
+            return statement;
+        }
+
+        let token_type = self.current().token_type.clone();
+        return match token_type
+        {
+            OpenBraceToken => self.parse_block(),
+            SemiToken => node(SyntaxNodeType::EmptyStatement, vec![SyntaxElement::Token(self.next())]),
+            IfKeyword => self.parse_if_statement(),
+            ForKeyword => self.parse_for_statement(),
+            WhileKeyword => self.parse_while_statement(),
+            DoKeyword => self.parse_do_statement(),
+            SwitchKeyword => self.parse_switch_statement(),
+            ReturnKeyword => {
+                let mut children = vec![SyntaxElement::Token(self.next())];
+                if !self.at(&SemiToken)
+                {
+                    children.push(SyntaxElement::Node(self.parse_expression()));
+                }
+                children.push(SyntaxElement::Token(self.expect(SemiToken, ";")));
+                node(SyntaxNodeType::ReturnStatement, children)
+            },
+            BreakKeyword => self.parse_keyword_statement(SyntaxNodeType::BreakStatement),
+            ContinueKeyword => self.parse_keyword_statement(SyntaxNodeType::ContinueStatement),
+            DiscardKeyword => self.parse_keyword_statement(SyntaxNodeType::DiscardStatement),
+            TypedefKeyword => self.parse_typedef(),
+            _ if self.is_declaration_start() => self.parse_declaration(false),
+            _ => self.parse_expression_statement()
+        };
+    }
+
+    fn parse_keyword_statement(&mut self, node_type: SyntaxNodeType) -> SyntaxNode
+    {
+        let keyword = self.next();
+        let semi = self.expect(SyntaxTokenType::SemiToken, ";");
+        return node(node_type, vec![SyntaxElement::Token(keyword), SyntaxElement::Token(semi)]);
+    }
+
+    fn parse_expression_statement(&mut self) -> SyntaxNode
+    {
+        let expression = self.parse_expression();
+        let semi = self.expect(SyntaxTokenType::SemiToken, ";");
+        return node(SyntaxNodeType::ExpressionStatement, vec![SyntaxElement::Node(expression), SyntaxElement::Token(semi)]);
+    }
+
+    // Parses '( expression )' of if, while and switch statements
+    fn parse_condition(&mut self, children: &mut Vec<SyntaxElement>)
+    {
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenParenToken, "(")));
+        children.push(SyntaxElement::Node(self.parse_expression()));
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseParenToken, ")")));
+    }
+
+    fn parse_if_statement(&mut self) -> SyntaxNode
+    {
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        self.parse_condition(&mut children);
+        children.push(SyntaxElement::Node(self.parse_statement()));
+        if self.at(&SyntaxTokenType::ElseKeyword)
+        {
+            let else_keyword = self.next();
+            let statement = self.parse_statement();
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::ElseClause, vec![SyntaxElement::Token(else_keyword), SyntaxElement::Node(statement)])));
+        }
+        return node(SyntaxNodeType::IfStatement, children);
+    }
+
+    // for (initializer; condition; incrementor) statement
+    fn parse_for_statement(&mut self) -> SyntaxNode
+    {
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenParenToken, "(")));
+
+        if self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        else if self.is_declaration_start()
+        {
+            children.push(SyntaxElement::Node(self.parse_declaration(false)));
+        }
+        else
+        {
+            children.push(SyntaxElement::Node(self.parse_expression_statement()));
+        }
+
+        if !self.at(&SyntaxTokenType::SemiToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_expression()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::SemiToken, ";")));
+
+        if !self.at(&SyntaxTokenType::CloseParenToken)
+        {
+            children.push(SyntaxElement::Node(self.parse_expression()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseParenToken, ")")));
+        children.push(SyntaxElement::Node(self.parse_statement()));
+        return node(SyntaxNodeType::ForStatement, children);
+    }
+
+    fn parse_while_statement(&mut self) -> SyntaxNode
+    {
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        self.parse_condition(&mut children);
+        children.push(SyntaxElement::Node(self.parse_statement()));
+        return node(SyntaxNodeType::WhileStatement, children);
+    }
+
+    fn parse_do_statement(&mut self) -> SyntaxNode
+    {
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        children.push(SyntaxElement::Node(self.parse_statement()));
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::WhileKeyword, "while")));
+        self.parse_condition(&mut children);
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::SemiToken, ";")));
+        return node(SyntaxNodeType::DoStatement, children);
+    }
+
+    fn parse_switch_statement(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        let mut children = vec![SyntaxElement::Token(self.next())];
+        self.parse_condition(&mut children);
+        children.push(SyntaxElement::Token(self.expect(OpenBraceToken, "{")));
+        while !self.at(&CloseBraceToken) && !self.at_end()
+        {
+            let mut section = Vec::new();
+            while self.at(&CaseKeyword) || self.at(&DefaultKeyword)
+            {
+                if self.at(&CaseKeyword)
+                {
+                    let case = self.next();
+                    let value = self.parse_expression();
+                    let colon = self.expect(ColonToken, ":");
+                    section.push(SyntaxElement::Node(node(SyntaxNodeType::CaseLabel, vec![SyntaxElement::Token(case), SyntaxElement::Node(value), SyntaxElement::Token(colon)])));
+                }
+                else
+                {
+                    let default = self.next();
+                    let colon = self.expect(ColonToken, ":");
+                    section.push(SyntaxElement::Node(node(SyntaxNodeType::DefaultLabel, vec![SyntaxElement::Token(default), SyntaxElement::Token(colon)])));
+                }
+            }
+
+            // Statements need to be preceded by a label
+            if section.is_empty()
+            {
+                children.push(SyntaxElement::Node(self.skip_tokens(RecoveryContext::Statement)));
+                continue;
+            }
+
+            while !self.at(&CaseKeyword) && !self.at(&DefaultKeyword) && !self.at(&CloseBraceToken) && !self.at_end()
+            {
+                section.push(SyntaxElement::Node(self.parse_list_element(RecoveryContext::Statement, Self::parse_statement)));
+            }
+            children.push(SyntaxElement::Node(node(SyntaxNodeType::SwitchSection, section)));
+        }
+        children.push(SyntaxElement::Token(self.expect(CloseBraceToken, "}")));
+        return node(SyntaxNodeType::SwitchStatement, children);
+    }
+
+    // Expressions
+
+    // Full expression including the comma operator
+    fn parse_expression(&mut self) -> SyntaxNode
+    {
+        let mut left = self.parse_assignment_expression();
+        while self.at(&SyntaxTokenType::CommaToken)
+        {
+            let comma = self.next();
+            let right = self.parse_assignment_expression();
+            left = node(SyntaxNodeType::BinaryExpression, vec![SyntaxElement::Node(left), SyntaxElement::Token(comma), SyntaxElement::Node(right)]);
+        }
+        return left;
+    }
+
+    fn parse_assignment_expression(&mut self) -> SyntaxNode
+    {
+        let left = self.parse_conditional_expression();
+        if is_assignment_operator(&self.current().token_type)
+        {
+            let operator = self.next();
+            let right = self.parse_assignment_expression();
+            return node(SyntaxNodeType::AssignmentExpression, vec![SyntaxElement::Node(left), SyntaxElement::Token(operator), SyntaxElement::Node(right)]);
+        }
+        return left;
+    }
+
+    fn parse_conditional_expression(&mut self) -> SyntaxNode
+    {
+        let condition = self.parse_binary_expression(1);
+        if !self.at(&SyntaxTokenType::QuestionToken)
+        {
+            return condition;
+        }
+
+        let question = self.next();
+        let when_true = self.parse_assignment_expression();
+        let colon = self.expect(SyntaxTokenType::ColonToken, ":");
+        let when_false = self.parse_assignment_expression();
+        return node(SyntaxNodeType::ConditionalExpression, vec![
+            SyntaxElement::Node(condition),
+            SyntaxElement::Token(question),
+            SyntaxElement::Node(when_true),
+            SyntaxElement::Token(colon),
+            SyntaxElement::Node(when_false)
+        ]);
+    }
+
+    // Precedence climbing over the binary operators, only operators binding at least as tight as min_precedence are parsed
+    fn parse_binary_expression(&mut self, min_precedence: u32) -> SyntaxNode
+    {
+        let mut left = self.parse_unary_expression();
+        loop
+        {
+            let precedence = binary_precedence(&self.current().token_type);
+            if precedence == 0 || precedence < min_precedence
+            {
+                break;
+            }
+
+            let operator = self.next();
+            let right = self.parse_binary_expression(precedence + 1);
+            left = node(SyntaxNodeType::BinaryExpression, vec![SyntaxElement::Node(left), SyntaxElement::Token(operator), SyntaxElement::Node(right)]);
+        }
+        return left;
+    }
+
+    // Checks whether the '(' at the current token starts a cast like (float3) or (MyStruct)
+    fn is_cast(&self) -> bool
+    {
+        use SyntaxTokenType::*;
+        let token = self.peek(1);
+        if token.is_predefined_type() || token.token_type == UnsignedKeyword
+        {
+            return !self.peek_is(2, &OpenParenToken);
+        }
+
+        if token.token_type != IdentifierToken || !self.type_names.contains(&token.text)
+        {
+            return false;
+        }
+
+        let mut offset = 1;
+        while self.peek_is(offset + 1, &ColonColonToken) && self.peek_is(offset + 2, &IdentifierToken)
+        {
+            offset += 2;
+        }
+        return self.peek_is(offset + 1, &CloseParenToken) || self.peek_is(offset + 1, &OpenBracketToken);
+    }
+
+    fn parse_unary_expression(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        if matches!(self.current().token_type, PlusToken | MinusToken | NotToken | TildeToken | PlusPlusToken | MinusMinusToken)
+        {
+            let operator = self.next();
+            let operand = self.parse_unary_expression();
+            return node(SyntaxNodeType::PrefixUnaryExpression, vec![SyntaxElement::Token(operator), SyntaxElement::Node(operand)]);
+        }
+
+        if self.at(&OpenParenToken) && self.is_cast()
+        {
+            let mut children = vec![SyntaxElement::Token(self.next())];
+            children.push(SyntaxElement::Node(self.parse_type()));
+            while self.at(&OpenBracketToken)
+            {
+                children.push(SyntaxElement::Node(self.parse_array_rank_specifier()));
+            }
+            children.push(SyntaxElement::Token(self.expect(CloseParenToken, ")")));
+            children.push(SyntaxElement::Node(self.parse_unary_expression()));
+            return node(SyntaxNodeType::CastExpression, children);
+        }
+
+        let primary = self.parse_primary_expression();
+        return self.parse_postfix_expression(primary);
+    }
+
+    fn parse_postfix_expression(&mut self, mut expression: SyntaxNode) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        loop
+        {
+            let token_type = self.current().token_type.clone();
+            expression = match token_type
+            {
+                OpenParenToken => {
+                    let arguments = self.parse_argument_list();
+                    node(SyntaxNodeType::CallExpression, vec![SyntaxElement::Node(expression), SyntaxElement::Node(arguments)])
+                },
+                OpenBracketToken => {
+                    let open = self.next();
+                    let index = self.parse_expression();
+                    let close = self.expect(CloseBracketToken, "]");
+                    node(SyntaxNodeType::ElementAccessExpression, vec![SyntaxElement::Node(expression), SyntaxElement::Token(open), SyntaxElement::Node(index), SyntaxElement::Token(close)])
+                },
+                DotToken => {
+                    let dot = self.next();
+                    let name = self.expect_identifier_like();
+                    node(SyntaxNodeType::MemberAccessExpression, vec![SyntaxElement::Node(expression), SyntaxElement::Token(dot), SyntaxElement::Token(name)])
+                },
+                PlusPlusToken | MinusMinusToken => {
+                    let operator = self.next();
+                    node(SyntaxNodeType::PostfixUnaryExpression, vec![SyntaxElement::Node(expression), SyntaxElement::Token(operator)])
+                },
+                _ => return expression
+            };
+        }
+    }
+
+    fn parse_argument_list(&mut self) -> SyntaxNode
+    {
+        let mut children = Vec::new();
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::OpenParenToken, "(")));
+        while !self.at(&SyntaxTokenType::CloseParenToken) && !self.at_end()
+        {
+            children.push(SyntaxElement::Node(self.parse_initializer()));
+            if !self.at(&SyntaxTokenType::CommaToken)
+            {
+                break;
+            }
+            children.push(SyntaxElement::Token(self.next()));
+        }
+        children.push(SyntaxElement::Token(self.expect(SyntaxTokenType::CloseParenToken, ")")));
+        return node(SyntaxNodeType::ArgumentList, children);
+    }
+
+    fn parse_primary_expression(&mut self) -> SyntaxNode
+    {
+        use SyntaxTokenType::*;
+        let token = self.current();
+        match token.token_type
+        {
+            IntegerLiteralToken{..} | FloatLiteralToken{..} | CharacterLiteralToken{..} | TrueKeyword | FalseKeyword => {
+                return node(SyntaxNodeType::LiteralExpression, vec![SyntaxElement::Token(self.next())]);
+            },
+            StringLiteralToken{..} => {
+                // Adjacent string literals are concatenated
+                let mut children = Vec::new();
+                while matches!(self.current().token_type, StringLiteralToken{..})
+                {
+                    children.push(SyntaxElement::Token(self.next()));
+                }
+                return node(SyntaxNodeType::LiteralExpression, children);
+            },
+            OpenParenToken => {
+                let open = self.next();
+                let expression = self.parse_expression();
+                let close = self.expect(CloseParenToken, ")");
+                return node(SyntaxNodeType::ParenthesizedExpression, vec![SyntaxElement::Token(open), SyntaxElement::Node(expression), SyntaxElement::Token(close)]);
+            },
+            OpenBraceToken => return self.parse_array_initializer(),
+            CompileKeyword => {
+                // compile vs_2_0 VS(args)
+                let compile = self.next();
+                let profile = self.expect_identifier_like();
+                let function = self.parse_primary_expression();
+                let call = self.parse_postfix_expression(function);
+                return node(SyntaxNodeType::CompileExpression, vec![SyntaxElement::Token(compile), SyntaxElement::Token(profile), SyntaxElement::Node(call)]);
+            },
+            SamplerStateLegacyKeyword => {
+                let keyword = self.next();
+                let state_block = self.parse_state_block();
+                return node(SyntaxNodeType::SamplerStateExpression, vec![SyntaxElement::Token(keyword), SyntaxElement::Node(state_block)]);
+            },
+            IdentifierToken if token.text == "CompileShader" && self.peek_is(1, &OpenParenToken) => {
+                // CompileShader(vs_4_0, VS())
+                let mut children = vec![SyntaxElement::Token(self.next()), SyntaxElement::Token(self.next())];
+                children.push(SyntaxElement::Token(self.expect_identifier_like()));
+                children.push(SyntaxElement::Token(self.expect(CommaToken, ",")));
+                children.push(SyntaxElement::Node(self.parse_assignment_expression()));
+                children.push(SyntaxElement::Token(self.expect(CloseParenToken, ")")));
+                return node(SyntaxNodeType::CompileExpression, children);
+            },
+            IdentifierToken if self.peek_is(1, &LessThanToken) && self.template_names.contains(&token.text) => {
+                // Explicit template arguments, like Load<float4>(address)
+                let is_call = self.skip_type_arguments(1).map(|end| self.peek_is(end + 1, &OpenParenToken)).unwrap_or(false);
+                let name = self.parse_name();
+                if !is_call
+                {
+                    return name;
+                }
+                let arguments = self.parse_type_argument_list();
+                return node(SyntaxNodeType::UserType, vec![SyntaxElement::Node(name), SyntaxElement::Node(arguments)]);
+            },
+            IdentifierToken | ColonColonToken => return self.parse_name(),
+            _ => {}
+        }
+
+        // Constructors like float3(1, 2, 3)
+        if token.is_predefined_type() || token.token_type == UnsignedKeyword
+        {
+            return self.parse_type();
+        }
+
+        if is_contextual_name(&token.token_type)
+        {
+            return node(SyntaxNodeType::IdentifierName, vec![SyntaxElement::Token(self.next())]);
+        }
+
+        let message = format!("Expected expression but found {}", self.found_text());
+        return self.missing_name(message);
+    }
+}
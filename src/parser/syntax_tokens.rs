@@ -323,6 +323,7 @@ pub enum SyntaxTokenType {
     NointerpolationKeyword,
     NoperspectiveKeyword,
     NullKeyword,
+    OperatorKeyword,
     OutKeyword,
     OutputPatchKeyword,
     PackMatrixKeyword,
@@ -374,6 +375,7 @@ pub enum SyntaxTokenType {
     TechniqueKeyword,
     Technique10Keyword,
     Technique11Keyword,
+    TemplateKeyword,
     TextureKeyword,
     Texture2DLegacyKeyword,
     TextureCubeLegacyKeyword,
@@ -390,6 +392,7 @@ pub enum SyntaxTokenType {
     TriangleAdjKeyword,
     TriangleStreamKeyword,
     TypedefKeyword,
+    TypenameKeyword,
     UniformKeyword,
     UNormKeyword,
     UintKeyword,
@@ -532,10 +535,18 @@ pub enum SyntaxTokenType {
 
     EndOfFileToken,
 
-    IfDirective,
-    IfDefDirective,
-    IfNDefDirective,
-    ElifDirective,
+    IfDirective {
+        condition: String,
+    },
+    IfDefDirective {
+        name: String,
+    },
+    IfNDefDirective {
+        name: String,
+    },
+    ElifDirective {
+        condition: String,
+    },
     ElseDirective,
     EndIfDirective,
     PragmaDirective {
@@ -583,8 +594,8 @@ impl fmt::Display for SyntaxTokenType
     }
 }
 
-pub fn get_keyword_token_type(text: &String) -> SyntaxTokenType {
-    match text.as_str() {
+pub fn get_keyword_token_type(text: &str) -> SyntaxTokenType {
+    match text {
         "AppendStructuredBuffer" => SyntaxTokenType::AppendStructuredBufferKeyword,
         "BlendState" => SyntaxTokenType::BlendStateKeyword,
         "bool" => SyntaxTokenType::BoolKeyword,
@@ -896,6 +907,7 @@ pub fn get_keyword_token_type(text: &String) -> SyntaxTokenType {
         "namespace" => SyntaxTokenType::NamespaceKeyword,
         "nointerpolation" => SyntaxTokenType::NointerpolationKeyword,
         "noperspective" => SyntaxTokenType::NoperspectiveKeyword,
+        "operator" => SyntaxTokenType::OperatorKeyword,
         "out" => SyntaxTokenType::OutKeyword,
         "OutputPatch" => SyntaxTokenType::OutputPatchKeyword,
         "packoffset" => SyntaxTokenType::PackoffsetKeyword,
@@ -944,6 +956,7 @@ pub fn get_keyword_token_type(text: &String) -> SyntaxTokenType {
         "Technique" => SyntaxTokenType::TechniqueKeyword,
         "technique10" => SyntaxTokenType::Technique10Keyword,
         "technique11" => SyntaxTokenType::Technique11Keyword,
+        "template" => SyntaxTokenType::TemplateKeyword,
         "texture2D" => SyntaxTokenType::Texture2DLegacyKeyword,
         "textureCUBE" => SyntaxTokenType::TextureCubeLegacyKeyword,
         "Texture1D" => SyntaxTokenType::Texture1DKeyword,
@@ -959,6 +972,7 @@ pub fn get_keyword_token_type(text: &String) -> SyntaxTokenType {
         "triangleadj" => SyntaxTokenType::TriangleAdjKeyword,
         "TriangleStream" => SyntaxTokenType::TriangleStreamKeyword,
         "typedef" => SyntaxTokenType::TypedefKeyword,
+        "typename" => SyntaxTokenType::TypenameKeyword,
         "uniform" => SyntaxTokenType::UniformKeyword,
         "unorm" => SyntaxTokenType::UNormKeyword,
         "unsigned" => SyntaxTokenType::UnsignedKeyword,
@@ -1036,10 +1050,197 @@ pub fn get_keyword_token_type(text: &String) -> SyntaxTokenType {
     }
 }
 
+// TextSpan
+// Byte range [start, end) into the text of the file a token or node came from
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextSpan
+{
+    pub fn new(start: usize, end: usize) -> Self
+    {
+        return Self{ start: start, end: end };
+    }
+
+    pub fn len(&self) -> usize
+    {
+        return self.end - self.start;
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        return self.start == self.end;
+    }
+
+    pub fn contains(&self, offset: usize) -> bool
+    {
+        return self.start <= offset && offset < self.end;
+    }
+
+    // Smallest span covering both spans
+    pub fn union(&self, other: &TextSpan) -> TextSpan
+    {
+        return TextSpan::new(self.start.min(other.start), self.end.max(other.end));
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SyntaxTriviaType {
+    WhiteSpace,
+    EndOfLine,
+    LineContinuation,
+    LineComment,
+    BlockComment,
+    // Preprocessor directives and regions disabled by #if are turned into trivia by the preprocessor
+    Directive,
+    DisabledText,
+}
+
+// SyntaxTrivia
+// Text in between tokens that does not change the meaning of the program, kept so the token stream stays lossless
+#[derive(Clone, Debug)]
+pub struct SyntaxTrivia {
+    pub trivia_type: SyntaxTriviaType,
+    pub span: TextSpan,
+    pub text: String,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct SyntaxToken {
-    // needs type, reference to file, line, range of text etc..
     pub token_type: SyntaxTokenType,
+    // Location of the token in the file given by file_index
+    pub span: TextSpan,
+    // The text of the token as written in the source
+    pub text: String,
+    pub leading_trivia: Vec<SyntaxTrivia>,
+    // Index into the file list of the preprocessor, 0 is the file that was parsed
+    pub file_index: usize,
+    // Tokens produced by a macro expansion are located at the macro invocation,
+    // the spelling points to where the text of the token was actually written
+    pub spelling: Option<SourceLocation>,
+    // Inserted by the parser in place of a token that was expected but not found, these have an empty span and text
+    pub is_missing: bool,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file_index: usize,
+    pub span: TextSpan,
+}
+
+impl SyntaxToken
+{
+    pub fn new(token_type: SyntaxTokenType, span: TextSpan, text: String) -> Self
+    {
+        return Self{
+            token_type: token_type,
+            span: span,
+            text: text,
+            leading_trivia: Vec::new(),
+            file_index: 0,
+            spelling: None,
+            is_missing: false
+        };
+    }
+
+    // Creates a zero width token for error recovery
+    pub fn missing(token_type: SyntaxTokenType, position: usize, file_index: usize) -> Self
+    {
+        let mut token = Self::new(token_type, TextSpan::new(position, position), String::new());
+        token.file_index = file_index;
+        token.is_missing = true;
+        return token;
+    }
+
+    pub fn is_directive(&self) -> bool
+    {
+        use SyntaxTokenType::*;
+        return matches!(self.token_type,
+            IfDirective{..} | IfDefDirective{..} | IfNDefDirective{..} | ElifDirective{..} | ElseDirective | EndIfDirective |
+            PragmaDirective{..} | ErrorDirective{..} | LineDirective{..} | IncludeDirective{..} |
+            PreProcessorDefinition{..} | PreProcessorDefinitionFunction{..} | UndefDirective{..} | BadDirective{..});
+    }
+
+    // Identifiers and keywords can both be used as macro names and in some contexts as names
+    pub fn is_identifier_like(&self) -> bool
+    {
+        let first = self.text.chars().next().unwrap_or('0');
+        return (first.is_alphabetic() || first == '_') && !matches!(self.token_type,
+            SyntaxTokenType::IntegerLiteralToken{..} | SyntaxTokenType::FloatLiteralToken{..} |
+            SyntaxTokenType::StringLiteralToken{..} | SyntaxTokenType::CharacterLiteralToken{..}) && !self.is_directive();
+    }
+
+    // Keywords naming a builtin type, like float3x4, Texture2D or SamplerState
+    pub fn is_predefined_type(&self) -> bool
+    {
+        if self.token_type == SyntaxTokenType::IdentifierToken || !self.is_identifier_like()
+        {
+            return false;
+        }
+
+        if self.is_object_type() || self.token_type == SyntaxTokenType::VoidKeyword
+        {
+            return true;
+        }
+
+        // Scalar, vector and matrix keywords are a scalar name followed by the dimensions
+        let scalar = self.text.trim_end_matches(|c: char| c.is_ascii_digit() || c == 'x');
+        return matches!(scalar,
+            "bool" | "int" | "uint" | "dword" | "half" | "float" | "double" |
+            "min10float" | "min16float" | "min12int" | "min16int" | "min16uint" |
+            "int16_t" | "uint16_t" | "int32_t" | "uint32_t" | "int64_t" | "uint64_t" |
+            "float16_t" | "float32_t" | "float64_t");
+    }
+
+    // Builtin types that are not scalars, vectors or matrices, these can take template arguments
+    pub fn is_object_type(&self) -> bool
+    {
+        use SyntaxTokenType::*;
+        return matches!(self.token_type,
+            AppendStructuredBufferKeyword | BlendStateKeyword | BufferKeyword | ByteAddressBufferKeyword |
+            ConstantBufferKeyword | ConsumeStructuredBufferKeyword | DepthStencilStateKeyword |
+            InputPatchKeyword | OutputPatchKeyword | LineStreamKeyword | PointStreamKeyword | TriangleStreamKeyword |
+            MatrixKeyword | VectorKeyword | RasterizerStateKeyword |
+            RasterizerOrderedBufferKeyword | RasterizerOrderedByteAddressBufferKeyword | RasterizerOrderedStructuredBufferKeyword |
+            RasterizerOrderedTexture1DKeyword | RasterizerOrderedTexture1DArrayKeyword | RasterizerOrderedTexture2DKeyword |
+            RasterizerOrderedTexture2DArrayKeyword | RasterizerOrderedTexture3DKeyword |
+            RWBufferKeyword | RWByteAddressBufferKeyword | RWStructuredBufferKeyword |
+            RWTexture1DKeyword | RWTexture1DArrayKeyword | RWTexture2DKeyword | RWTexture2DArrayKeyword | RWTexture3DKeyword |
+            SamplerKeyword | Sampler1DKeyword | Sampler2DKeyword | Sampler3DKeyword | SamplerCubeKeyword |
+            SamplerComparisonStateKeyword | SamplerStateKeyword | StringKeyword | StructuredBufferKeyword |
+            Texture2DLegacyKeyword | TextureCubeLegacyKeyword |
+            Texture1DKeyword | Texture1DArrayKeyword | Texture2DKeyword | Texture2DArrayKeyword | Texture2DMSKeyword |
+            Texture2DMSArrayKeyword | Texture3DKeyword | TextureCubeKeyword | TextureCubeArrayKeyword);
+    }
+
+    // Returns true when the token comes directly from the parsed file and was not produced by an include or macro
+    pub fn is_from_source(&self) -> bool
+    {
+        return self.file_index == 0 && self.spelling.is_none();
+    }
+
+    // Location where the text of the token was written, for macro expansions this is inside the macro definition
+    pub fn spelling_location(&self) -> SourceLocation
+    {
+        return match self.spelling
+        {
+            Some(location) => location,
+            None => SourceLocation{ file_index: self.file_index, span: self.span }
+        };
+    }
+
+    // Full span including the leading trivia
+    pub fn full_span(&self) -> TextSpan
+    {
+        match self.leading_trivia.first()
+        {
+            Some(trivia) => TextSpan::new(trivia.span.start, self.span.end),
+            None => self.span
+        }
+    }
 }
 
 impl fmt::Display for SyntaxToken
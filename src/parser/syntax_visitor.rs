@@ -0,0 +1,243 @@
+use super::{syntax_nodes::{SyntaxElement, SyntaxNode, SyntaxNodeType}, syntax_tokens::SyntaxToken};
+
+// What the walker does after a node has been entered
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VisitAction
+{
+    Continue,
+    // Do not descend into the children of this node, the leave hook is still called
+    SkipChildren,
+    // End the walk, no more hooks are called
+    Stop,
+}
+
+// Generates the hooks for every node kind, the match statements make sure new node kinds get hooks as well
+macro_rules! node_hooks {
+    ($(($node_type:ident, $visit:ident, $leave:ident, $rewrite:ident)),+ $(,)?) => {
+        // Visitor
+        // Read-only walk over the syntax tree, every node kind has a hook before (visit) and after (leave) its children
+        pub trait Visitor<'a>
+        {
+            $(
+                fn $visit(&mut self, _node: &'a SyntaxNode) -> VisitAction
+                {
+                    return VisitAction::Continue;
+                }
+
+                fn $leave(&mut self, _node: &'a SyntaxNode)
+                {
+                }
+            )+
+
+            fn visit_token(&mut self, _token: &'a SyntaxToken) -> VisitAction
+            {
+                return VisitAction::Continue;
+            }
+
+            // Called for every node before the children, override to handle all nodes in one place
+            fn enter_node(&mut self, node: &'a SyntaxNode) -> VisitAction
+            {
+                return match node.node_type
+                {
+                    $(SyntaxNodeType::$node_type => self.$visit(node)),+
+                };
+            }
+
+            // Called for every node after the children
+            fn leave_node(&mut self, node: &'a SyntaxNode)
+            {
+                match node.node_type
+                {
+                    $(SyntaxNodeType::$node_type => self.$leave(node)),+
+                }
+            }
+        }
+
+        // Rewriter
+        // Bottom-up transformation of the syntax tree, the hooks receive a node with rewritten children
+        // and return the node to put in its place, or None to remove it
+        pub trait Rewriter
+        {
+            $(
+                fn $rewrite(&mut self, node: SyntaxNode) -> Option<SyntaxNode>
+                {
+                    return Some(node);
+                }
+            )+
+
+            fn rewrite_token(&mut self, token: SyntaxToken) -> Option<SyntaxToken>
+            {
+                return Some(token);
+            }
+
+            // Return false to keep the children of the node as they are
+            fn should_descend(&mut self, _node: &SyntaxNode) -> bool
+            {
+                return true;
+            }
+
+            fn rewrite_node(&mut self, node: SyntaxNode) -> Option<SyntaxNode>
+            {
+                return match node.node_type
+                {
+                    $(SyntaxNodeType::$node_type => self.$rewrite(node)),+
+                };
+            }
+        }
+    };
+}
+
+node_hooks!(
+    (CompilationUnit, visit_compilation_unit, leave_compilation_unit, rewrite_compilation_unit),
+    (FunctionDeclaration, visit_function_declaration, leave_function_declaration, rewrite_function_declaration),
+    (FunctionDefinition, visit_function_definition, leave_function_definition, rewrite_function_definition),
+    (ParameterList, visit_parameter_list, leave_parameter_list, rewrite_parameter_list),
+    (Parameter, visit_parameter, leave_parameter, rewrite_parameter),
+    (VariableDeclarationStatement, visit_variable_declaration_statement, leave_variable_declaration_statement, rewrite_variable_declaration_statement),
+    (VariableDeclarator, visit_variable_declarator, leave_variable_declarator, rewrite_variable_declarator),
+    (ArrayRankSpecifier, visit_array_rank_specifier, leave_array_rank_specifier, rewrite_array_rank_specifier),
+    (EqualsValueClause, visit_equals_value_clause, leave_equals_value_clause, rewrite_equals_value_clause),
+    (Semantic, visit_semantic, leave_semantic, rewrite_semantic),
+    (RegisterLocation, visit_register_location, leave_register_location, rewrite_register_location),
+    (PackOffsetLocation, visit_pack_offset_location, leave_pack_offset_location, rewrite_pack_offset_location),
+    (TypeDeclarationStatement, visit_type_declaration_statement, leave_type_declaration_statement, rewrite_type_declaration_statement),
+    (StructType, visit_struct_type, leave_struct_type, rewrite_struct_type),
+    (BaseList, visit_base_list, leave_base_list, rewrite_base_list),
+    (ConstantBufferDeclaration, visit_constant_buffer_declaration, leave_constant_buffer_declaration, rewrite_constant_buffer_declaration),
+    (NamespaceDeclaration, visit_namespace_declaration, leave_namespace_declaration, rewrite_namespace_declaration),
+    (TypedefStatement, visit_typedef_statement, leave_typedef_statement, rewrite_typedef_statement),
+    (TypedefDeclarator, visit_typedef_declarator, leave_typedef_declarator, rewrite_typedef_declarator),
+    (AttributeList, visit_attribute_list, leave_attribute_list, rewrite_attribute_list),
+    (Attribute, visit_attribute, leave_attribute, rewrite_attribute),
+    (AttributeArgumentList, visit_attribute_argument_list, leave_attribute_argument_list, rewrite_attribute_argument_list),
+    (TemplateDeclaration, visit_template_declaration, leave_template_declaration, rewrite_template_declaration),
+    (TemplateParameterList, visit_template_parameter_list, leave_template_parameter_list, rewrite_template_parameter_list),
+    (TemplateParameter, visit_template_parameter, leave_template_parameter, rewrite_template_parameter),
+    (OperatorName, visit_operator_name, leave_operator_name, rewrite_operator_name),
+    (BitField, visit_bit_field, leave_bit_field, rewrite_bit_field),
+    (TechniqueDeclaration, visit_technique_declaration, leave_technique_declaration, rewrite_technique_declaration),
+    (PassDeclaration, visit_pass_declaration, leave_pass_declaration, rewrite_pass_declaration),
+    (Annotations, visit_annotations, leave_annotations, rewrite_annotations),
+    (StateBlock, visit_state_block, leave_state_block, rewrite_state_block),
+    (StateAssignment, visit_state_assignment, leave_state_assignment, rewrite_state_assignment),
+    (PredefinedType, visit_predefined_type, leave_predefined_type, rewrite_predefined_type),
+    (UserType, visit_user_type, leave_user_type, rewrite_user_type),
+    (TypeArgumentList, visit_type_argument_list, leave_type_argument_list, rewrite_type_argument_list),
+    (Block, visit_block, leave_block, rewrite_block),
+    (EmptyStatement, visit_empty_statement, leave_empty_statement, rewrite_empty_statement),
+    (ExpressionStatement, visit_expression_statement, leave_expression_statement, rewrite_expression_statement),
+    (ReturnStatement, visit_return_statement, leave_return_statement, rewrite_return_statement),
+    (IfStatement, visit_if_statement, leave_if_statement, rewrite_if_statement),
+    (ElseClause, visit_else_clause, leave_else_clause, rewrite_else_clause),
+    (ForStatement, visit_for_statement, leave_for_statement, rewrite_for_statement),
+    (WhileStatement, visit_while_statement, leave_while_statement, rewrite_while_statement),
+    (DoStatement, visit_do_statement, leave_do_statement, rewrite_do_statement),
+    (SwitchStatement, visit_switch_statement, leave_switch_statement, rewrite_switch_statement),
+    (SwitchSection, visit_switch_section, leave_switch_section, rewrite_switch_section),
+    (CaseLabel, visit_case_label, leave_case_label, rewrite_case_label),
+    (DefaultLabel, visit_default_label, leave_default_label, rewrite_default_label),
+    (BreakStatement, visit_break_statement, leave_break_statement, rewrite_break_statement),
+    (ContinueStatement, visit_continue_statement, leave_continue_statement, rewrite_continue_statement),
+    (DiscardStatement, visit_discard_statement, leave_discard_statement, rewrite_discard_statement),
+    (IdentifierName, visit_identifier_name, leave_identifier_name, rewrite_identifier_name),
+    (QualifiedName, visit_qualified_name, leave_qualified_name, rewrite_qualified_name),
+    (LiteralExpression, visit_literal_expression, leave_literal_expression, rewrite_literal_expression),
+    (ParenthesizedExpression, visit_parenthesized_expression, leave_parenthesized_expression, rewrite_parenthesized_expression),
+    (CastExpression, visit_cast_expression, leave_cast_expression, rewrite_cast_expression),
+    (BinaryExpression, visit_binary_expression, leave_binary_expression, rewrite_binary_expression),
+    (AssignmentExpression, visit_assignment_expression, leave_assignment_expression, rewrite_assignment_expression),
+    (PrefixUnaryExpression, visit_prefix_unary_expression, leave_prefix_unary_expression, rewrite_prefix_unary_expression),
+    (PostfixUnaryExpression, visit_postfix_unary_expression, leave_postfix_unary_expression, rewrite_postfix_unary_expression),
+    (ConditionalExpression, visit_conditional_expression, leave_conditional_expression, rewrite_conditional_expression),
+    (CallExpression, visit_call_expression, leave_call_expression, rewrite_call_expression),
+    (ArgumentList, visit_argument_list, leave_argument_list, rewrite_argument_list),
+    (MemberAccessExpression, visit_member_access_expression, leave_member_access_expression, rewrite_member_access_expression),
+    (ElementAccessExpression, visit_element_access_expression, leave_element_access_expression, rewrite_element_access_expression),
+    (ArrayInitializerExpression, visit_array_initializer_expression, leave_array_initializer_expression, rewrite_array_initializer_expression),
+    (CompileExpression, visit_compile_expression, leave_compile_expression, rewrite_compile_expression),
+    (SamplerStateExpression, visit_sampler_state_expression, leave_sampler_state_expression, rewrite_sampler_state_expression),
+    (StateReferenceExpression, visit_state_reference_expression, leave_state_reference_expression, rewrite_state_reference_expression),
+    (SkippedTokens, visit_skipped_tokens, leave_skipped_tokens, rewrite_skipped_tokens),
+);
+
+// Walks the node and everything below it in source order, returns Stop when the visitor ended the walk
+pub fn walk<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, node: &'a SyntaxNode) -> VisitAction
+{
+    match visitor.enter_node(node)
+    {
+        VisitAction::Stop => return VisitAction::Stop,
+        VisitAction::SkipChildren => {
+            visitor.leave_node(node);
+            return VisitAction::Continue;
+        },
+        VisitAction::Continue => {}
+    }
+
+    for child in &node.children
+    {
+        let action = match child
+        {
+            SyntaxElement::Node(child) => walk(visitor, child),
+            SyntaxElement::Token(token) => visitor.visit_token(token)
+        };
+
+        if action == VisitAction::Stop
+        {
+            return VisitAction::Stop;
+        }
+    }
+
+    visitor.leave_node(node);
+    return VisitAction::Continue;
+}
+
+// Rewrites the children of the node and then the node itself
+pub fn rewrite<R: Rewriter + ?Sized>(rewriter: &mut R, node: SyntaxNode) -> Option<SyntaxNode>
+{
+    let mut node = node;
+    if rewriter.should_descend(&node)
+    {
+        let children = std::mem::take(&mut node.children);
+        for child in children
+        {
+            let child = match child
+            {
+                SyntaxElement::Node(child) => rewrite(rewriter, child).map(SyntaxElement::Node),
+                SyntaxElement::Token(token) => rewriter.rewrite_token(token).map(SyntaxElement::Token)
+            };
+
+            if let Some(child) = child
+            {
+                node.children.push(child);
+            }
+        }
+    }
+
+    return rewriter.rewrite_node(node);
+}
+
+struct Collector<F, T>
+{
+    select: F,
+    results: Vec<T>,
+}
+
+impl<'a, T, F: FnMut(&'a SyntaxNode) -> Option<T>> Visitor<'a> for Collector<F, T>
+{
+    fn enter_node(&mut self, node: &'a SyntaxNode) -> VisitAction
+    {
+        if let Some(result) = (self.select)(node)
+        {
+            self.results.push(result);
+        }
+        return VisitAction::Continue;
+    }
+}
+
+// Collects the results of the selector for all nodes below and including the node, in pre-order
+pub fn collect<'a, T>(node: &'a SyntaxNode, select: impl FnMut(&'a SyntaxNode) -> Option<T>) -> Vec<T>
+{
+    let mut collector = Collector{ select: select, results: Vec::new() };
+    walk(&mut collector, node);
+    return collector.results;
+}
@@ -0,0 +1,755 @@
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::SyntaxNode, syntax_tokens::SyntaxToken, HlslVersion, ParserSettings};
+
+use super::{class_table::method_signature, symbol_table::{ScopeId, ScopeKind, Symbol, SymbolId, SymbolKind, SymbolTable}};
+
+// Builtin types that are not keywords
+fn is_builtin_type_name(name: &str) -> bool
+{
+    return matches!(name,
+        "ConstantBuffer" | "TextureBuffer" | "FeedbackTexture2D" | "FeedbackTexture2DArray" | "RWTexture2DMS" | "RWTexture2DMSArray" |
+        "RaytracingAccelerationStructure" | "RayDesc" | "RayQuery" | "BuiltInTriangleIntersectionAttributes" |
+        "SAMPLER_FEEDBACK_MIN_MIP" | "SAMPLER_FEEDBACK_MIP_REGION_USED" | "texture" | "Texture" | "VertexShader" | "PixelShader" |
+        "GeometryShader" | "HullShader" | "DomainShader" | "ComputeShader" | "NodeOutput" | "DispatchNodeInputRecord");
+}
+
+// Builtin variables, the descriptor heaps of shader model 6.6
+pub(crate) fn is_builtin_variable_name(name: &str) -> bool
+{
+    return matches!(name, "ResourceDescriptorHeap" | "SamplerDescriptorHeap");
+}
+
+// Binder
+// Walks the tree in source order, declares symbols in their scopes and resolves every name to the symbol it refers to.
+// Names are only visible after their declaration, except for the members of a struct inside its methods
+pub struct Binder<'a>
+{
+    table: SymbolTable<'a>,
+    hlsl_version: HlslVersion,
+    // Undeclared names are not reported in state blocks, annotations and attributes, they hold effect states and strings
+    quiet: usize,
+}
+
+impl<'a> Binder<'a>
+{
+    pub fn bind(unit: CompilationUnit<'a>, settings: &ParserSettings) -> SymbolTable<'a>
+    {
+        let mut binder = Self{
+            table: SymbolTable::new(),
+            hlsl_version: settings.hlsl_version,
+            quiet: 0
+        };
+
+        let global = binder.table.global_scope();
+        for declaration in unit.declarations()
+        {
+            binder.bind_declaration(declaration, global);
+        }
+        return binder.table;
+    }
+
+    fn error(&mut self, message: String, node: &SyntaxNode)
+    {
+        self.table.diagnostics.push(Diagnostic::error(message, node.span()).in_file(node.file_index()));
+    }
+
+    fn error_at_token(&mut self, message: String, token: &SyntaxToken)
+    {
+        self.table.diagnostics.push(Diagnostic::error(message, token.span).in_file(token.file_index));
+    }
+
+    // Declares the symbol, reporting a redefinition when the scope already has a symbol with the name
+    fn declare(&mut self, name: &'a SyntaxToken, kind: SymbolKind, declaration: &'a SyntaxNode, scope: ScopeId) -> SymbolId
+    {
+        let existing = self.table.get_scope(scope).get(&name.text).to_vec();
+        let conflicts = existing.iter().any(|s| {
+            let other = self.table.get_symbol(*s);
+            // Loop variables that leaked out of a for statement can be declared again
+            if other.scope != scope
+            {
+                return false;
+            }
+
+            let other = other.kind;
+            // Functions can be overloaded, the callers check for identical signatures
+            return !(other.is_function() && kind.is_function());
+        });
+
+        if conflicts
+        {
+            self.error_at_token(format!("redefinition of '{}'", name.text), name);
+        }
+
+        return self.table.add_symbol(Symbol{
+            name: name.text.clone(),
+            kind: kind,
+            declaration: declaration,
+            parent: None,
+            definition: None,
+            name_token: Some(name),
+            scope: scope,
+            members: None
+        });
+    }
+
+    // Declarations
+
+    fn bind_declaration(&mut self, declaration: Declaration<'a>, scope: ScopeId)
+    {
+        match declaration
+        {
+            Declaration::Function(function) => self.bind_function(function, scope, None),
+            Declaration::Variable(variable) => self.bind_variable(variable, scope),
+            Declaration::Type(declaration) => {
+                if let Some(struct_decl) = declaration.struct_decl()
+                {
+                    self.bind_struct(struct_decl, scope, None);
+                }
+            },
+            Declaration::ConstantBuffer(buffer) => self.bind_constant_buffer(buffer, scope),
+            Declaration::Namespace(namespace) => self.bind_namespace(namespace, scope),
+            Declaration::Typedef(typedef) => self.bind_typedef(typedef, scope),
+            Declaration::Technique(technique) => self.bind_technique(technique, scope),
+            Declaration::Template(template) => self.bind_template(template, scope),
+            Declaration::Skipped(_) => {}
+        }
+    }
+
+    fn bind_namespace(&mut self, namespace: NamespaceDecl<'a>, scope: ScopeId)
+    {
+        let name = match namespace.name()
+        {
+            Some(name) => name,
+            None => return
+        };
+
+        // Namespaces can be opened more than once
+        let existing = self.table.get_scope(scope).get(&name.text).iter()
+            .find(|s| self.table.get_symbol(**s).kind == SymbolKind::Namespace).copied();
+
+        let members = match existing
+        {
+            Some(symbol) => self.table.get_symbol(symbol).members.unwrap(),
+            None => {
+                let symbol = self.declare(name, SymbolKind::Namespace, namespace.syntax(), scope);
+                let members = self.table.add_scope(ScopeKind::Namespace, scope, Some(namespace.syntax()), Some(symbol));
+                self.table.get_symbol_mut(symbol).members = Some(members);
+                members
+            }
+        };
+
+        for declaration in namespace.declarations()
+        {
+            self.bind_declaration(declaration, members);
+        }
+    }
+
+    fn bind_template(&mut self, template: TemplateDecl<'a>, scope: ScopeId)
+    {
+        match template.declaration()
+        {
+            Some(Declaration::Function(function)) => self.bind_function(function, scope, Some(template)),
+            Some(Declaration::Type(declaration)) => {
+                if let Some(struct_decl) = declaration.struct_decl()
+                {
+                    self.bind_struct(struct_decl, scope, Some(template));
+                }
+            },
+            Some(declaration) => self.bind_declaration(declaration, scope),
+            None => {}
+        }
+    }
+
+    fn bind_template_parameters(&mut self, template: Option<TemplateDecl<'a>>, scope: ScopeId)
+    {
+        let template = match template
+        {
+            Some(template) => template,
+            None => return
+        };
+
+        for parameter in template.parameters()
+        {
+            if let Some(value_type) = parameter.value_type()
+            {
+                self.bind_type(value_type, scope);
+            }
+
+            if let Some(name) = parameter.name()
+            {
+                self.declare(name, SymbolKind::TemplateParameter, parameter.syntax(), scope);
+            }
+        }
+    }
+
+    fn bind_struct(&mut self, declaration: StructDecl<'a>, scope: ScopeId, template: Option<TemplateDecl<'a>>)
+    {
+        let kind = match declaration.kind()
+        {
+            StructKind::Struct => SymbolKind::Struct,
+            StructKind::Class => SymbolKind::Class,
+            StructKind::Interface => SymbolKind::Interface
+        };
+
+        let symbol = declaration.name().map(|name| self.declare(name, kind, declaration.syntax(), scope));
+        let members = self.table.add_scope(ScopeKind::Struct, scope, Some(declaration.syntax()), symbol);
+        if let Some(symbol) = symbol
+        {
+            self.table.get_symbol_mut(symbol).members = Some(members);
+        }
+
+        self.bind_template_parameters(template, members);
+        for base in declaration.base_list()
+        {
+            if let Some(base_symbol) = self.bind_type_name(base, scope)
+            {
+                if let Some(base_members) = self.table.get_symbol(base_symbol).members
+                {
+                    self.table.get_scope_mut(members).bases.push(base_members);
+                }
+            }
+        }
+
+        // All members are declared before the method bodies are bound, so methods can use members declared after them
+        let mut methods = Vec::new();
+        for member in declaration.members()
+        {
+            match member
+            {
+                Declaration::Function(function) => {
+                    if let Some(symbol) = self.declare_function(function, members)
+                    {
+                        methods.push((function, symbol, None));
+                    }
+                },
+                Declaration::Template(member_template) => {
+                    if let Some(Declaration::Function(function)) = member_template.declaration()
+                    {
+                        if let Some(symbol) = self.declare_function(function, members)
+                        {
+                            methods.push((function, symbol, Some(member_template)));
+                        }
+                        continue;
+                    }
+                    self.bind_template(member_template, members);
+                },
+                Declaration::Variable(variable) => self.bind_variable(variable, members),
+                _ => self.bind_declaration(member, members)
+            }
+        }
+
+        for (function, symbol, template) in methods
+        {
+            self.bind_function_body(function, symbol, members, template);
+        }
+    }
+
+    fn bind_constant_buffer(&mut self, buffer: ConstantBufferDecl<'a>, scope: ScopeId)
+    {
+        if let Some(name) = buffer.name()
+        {
+            self.declare(name, SymbolKind::ConstantBuffer, buffer.syntax(), scope);
+        }
+
+        // The fields of a constant buffer are global variables
+        for field in buffer.fields()
+        {
+            self.bind_variable(field, scope);
+        }
+    }
+
+    fn bind_typedef(&mut self, typedef: TypedefDecl<'a>, scope: ScopeId)
+    {
+        if let Some(aliased_type) = typedef.aliased_type()
+        {
+            self.bind_type(aliased_type, scope);
+        }
+
+        for declarator in typedef.declarators()
+        {
+            for rank in declarator.array_ranks()
+            {
+                self.bind_optional_expression(rank.size(), scope);
+            }
+
+            if let Some(name) = declarator.name()
+            {
+                let symbol = self.declare(name, SymbolKind::Typedef, declarator.syntax(), scope);
+                self.table.get_symbol_mut(symbol).parent = Some(typedef.syntax());
+            }
+        }
+    }
+
+    fn bind_technique(&mut self, technique: TechniqueDecl<'a>, scope: ScopeId)
+    {
+        if let Some(name) = technique.name()
+        {
+            self.declare(name, SymbolKind::Technique, technique.syntax(), scope);
+        }
+
+        self.quiet += 1;
+        for pass in technique.passes()
+        {
+            if let Some(state_block) = pass.state_block()
+            {
+                self.bind_nested_expressions(state_block.syntax(), scope);
+            }
+        }
+        self.quiet -= 1;
+    }
+
+    fn bind_variable(&mut self, variable: VariableDecl<'a>, scope: ScopeId)
+    {
+        self.bind_attributes(variable.attributes(), scope);
+        if let Some(variable_type) = variable.var_type()
+        {
+            self.bind_type(variable_type, scope);
+        }
+
+        let kind = match self.table.get_scope(scope).kind
+        {
+            ScopeKind::Global | ScopeKind::Namespace => SymbolKind::GlobalVariable,
+            ScopeKind::Struct => SymbolKind::Field,
+            ScopeKind::Function | ScopeKind::Block => SymbolKind::LocalVariable
+        };
+
+        for declarator in variable.declarators()
+        {
+            for rank in declarator.array_ranks()
+            {
+                self.bind_optional_expression(rank.size(), scope);
+            }
+
+            // The initializer cannot refer to the variable itself
+            self.bind_optional_expression(declarator.initializer(), scope);
+            self.bind_optional_expression(declarator.bit_width(), scope);
+
+            self.quiet += 1;
+            if let Some(state_block) = declarator.state_block()
+            {
+                self.bind_nested_expressions(state_block.syntax(), scope);
+            }
+            self.quiet -= 1;
+
+            if let Some(name) = declarator.name()
+            {
+                let symbol = self.declare(name, kind, declarator.syntax(), scope);
+                self.table.get_symbol_mut(symbol).parent = Some(variable.syntax());
+            }
+        }
+    }
+
+    // Declares a function or method, a definition of an earlier declaration with the same signature becomes its definition
+    fn declare_function(&mut self, function: FunctionDecl<'a>, scope: ScopeId) -> Option<SymbolId>
+    {
+        let name = function.name()?;
+        let name_token = match name
+        {
+            Name::Operator(operator) => operator.syntax().first_token()?,
+            _ => name.identifier()?
+        };
+        let name_text = match name
+        {
+            Name::Operator(_) => name.text(),
+            _ => name_token.text.clone()
+        };
+
+        let signature = method_signature(&function);
+        let existing = self.table.get_scope(scope).get(&name_text).iter().copied().find(|s| {
+            let symbol = self.table.get_symbol(*s);
+            return symbol.kind.is_function() && method_signature(&FunctionDecl::cast(symbol.declaration).unwrap()) == signature;
+        });
+
+        if let Some(existing) = existing
+        {
+            self.table.add_reference(name.syntax(), existing);
+            if function.is_definition()
+            {
+                if self.table.get_symbol(existing).definition.is_some() || FunctionDecl::cast(self.table.get_symbol(existing).declaration).unwrap().is_definition()
+                {
+                    self.error_at_token(format!("redefinition of '{}'", name_text), name_token);
+                }
+                self.table.get_symbol_mut(existing).definition = Some(function.syntax());
+            }
+            return Some(existing);
+        }
+
+        let kind = if self.table.get_scope(scope).kind == ScopeKind::Struct { SymbolKind::Method } else { SymbolKind::Function };
+        let symbol = self.declare(name_token, kind, function.syntax(), scope);
+        self.table.get_symbol_mut(symbol).name = name_text;
+        return Some(symbol);
+    }
+
+    fn bind_function(&mut self, function: FunctionDecl<'a>, scope: ScopeId, template: Option<TemplateDecl<'a>>)
+    {
+        let mut target_scope = scope;
+        // Out-of-line definitions, like int Outer::Nested::MyClass::TestFunc() { }
+        if let Some(Name::Qualified(qualified)) = function.name()
+        {
+            let qualifier = match qualified.left()
+            {
+                Some(left) => self.bind_type_name(left, scope),
+                None => None
+            };
+
+            target_scope = match qualifier.and_then(|q| self.table.get_symbol(q).members)
+            {
+                Some(members) => members,
+                None => return
+            };
+
+            let name = qualified.right().unwrap().identifier();
+            let signature = method_signature(&function);
+            let matches_declaration = self.table.get_scope(target_scope).get(&name.text).iter().any(|s| {
+                let symbol = self.table.get_symbol(*s);
+                return symbol.kind.is_function() && method_signature(&FunctionDecl::cast(symbol.declaration).unwrap()) == signature;
+            });
+
+            if !matches_declaration
+            {
+                let owner = self.table.get_symbol(qualifier.unwrap()).name.clone();
+                self.error_at_token(format!("out-of-line definition of '{}' does not match any declaration in '{}'", name.text, owner), name);
+                return;
+            }
+        }
+
+        let symbol = match self.declare_function(function, target_scope)
+        {
+            Some(symbol) => symbol,
+            None => return
+        };
+        self.bind_function_body(function, symbol, target_scope, template);
+    }
+
+    fn bind_function_body(&mut self, function: FunctionDecl<'a>, symbol: SymbolId, scope: ScopeId, template: Option<TemplateDecl<'a>>)
+    {
+        self.bind_attributes(function.attributes(), scope);
+
+        // The parameters and the outer block of the body share the function scope
+        let function_scope = self.table.add_scope(ScopeKind::Function, scope, Some(function.syntax()), Some(symbol));
+        // The scope of the definition wins over the one of a forward declaration
+        if function.is_definition() || self.table.get_symbol(symbol).members.is_none()
+        {
+            self.table.get_symbol_mut(symbol).members = Some(function_scope);
+        }
+
+        self.bind_template_parameters(template, function_scope);
+        if let Some(return_type) = function.return_type()
+        {
+            self.bind_type(return_type, function_scope);
+        }
+
+        for parameter in function.params()
+        {
+            self.bind_attributes(parameter.attributes(), function_scope);
+            if let Some(parameter_type) = parameter.param_type()
+            {
+                self.bind_type(parameter_type, function_scope);
+            }
+
+            if let Some(declarator) = parameter.declarator()
+            {
+                for rank in declarator.array_ranks()
+                {
+                    self.bind_optional_expression(rank.size(), function_scope);
+                }
+                self.bind_optional_expression(declarator.initializer(), function_scope);
+            }
+
+            if let Some(name) = parameter.name()
+            {
+                self.declare(name, SymbolKind::Parameter, parameter.syntax(), function_scope);
+            }
+        }
+
+        if let Some(body) = function.body()
+        {
+            for statement in body.statements()
+            {
+                self.bind_statement(statement, function_scope);
+            }
+        }
+    }
+
+    fn bind_attributes(&mut self, attributes: impl Iterator<Item = Attribute<'a>>, scope: ScopeId)
+    {
+        self.quiet += 1;
+        for attribute in attributes
+        {
+            for argument in attribute.arguments()
+            {
+                self.bind_expression(argument, scope);
+            }
+        }
+        self.quiet -= 1;
+    }
+
+    // Types
+
+    fn bind_type(&mut self, type_syntax: TypeSyntax<'a>, scope: ScopeId)
+    {
+        match type_syntax
+        {
+            TypeSyntax::Predefined(predefined) => {
+                if let Some(arguments) = predefined.type_arguments()
+                {
+                    self.bind_type_arguments(arguments, scope);
+                }
+            },
+            TypeSyntax::User(user) => {
+                if let Some(name) = user.name()
+                {
+                    let is_builtin = matches!(name, Name::Identifier(_)) && is_builtin_type_name(&name.text());
+                    if !is_builtin
+                    {
+                        self.bind_type_name(name, scope);
+                    }
+                }
+
+                if let Some(arguments) = user.type_arguments()
+                {
+                    self.bind_type_arguments(arguments, scope);
+                }
+            },
+            TypeSyntax::Struct(declaration) => self.bind_struct(declaration, scope, None)
+        }
+    }
+
+    fn bind_type_arguments(&mut self, arguments: TypeArgumentList<'a>, scope: ScopeId)
+    {
+        for argument in arguments.types()
+        {
+            self.bind_type(argument, scope);
+        }
+
+        for value in arguments.values()
+        {
+            self.bind_expression(value, scope);
+        }
+    }
+
+    // Resolves the name of a type, namespace or any other symbol and records the reference
+    fn bind_type_name(&mut self, name: Name<'a>, scope: ScopeId) -> Option<SymbolId>
+    {
+        return self.bind_name(name, scope, true);
+    }
+
+    // Resolves a plain or qualified name, reporting undeclared names when requested
+    fn bind_name(&mut self, name: Name<'a>, scope: ScopeId, report: bool) -> Option<SymbolId>
+    {
+        let symbol = match name
+        {
+            Name::Identifier(identifier) => {
+                let token = identifier.identifier();
+                if token.is_missing
+                {
+                    return None;
+                }
+
+                let symbol = self.table.lookup(scope, &token.text).first().copied();
+                if symbol.is_none() && report && self.quiet == 0
+                {
+                    self.error_at_token(format!("use of undeclared identifier '{}'", token.text), token);
+                }
+                symbol
+            },
+            Name::Qualified(qualified) => {
+                let right = qualified.right()?;
+                let token = right.identifier();
+                let members = match qualified.left()
+                {
+                    Some(left) => {
+                        let left_symbol = self.bind_name(left, scope, report)?;
+                        match self.table.get_symbol(left_symbol).members
+                        {
+                            Some(members) => members,
+                            None => {
+                                if report && self.quiet == 0
+                                {
+                                    let message = format!("'{}' is not a namespace or struct", self.table.get_symbol(left_symbol).name);
+                                    self.error(message, left.syntax());
+                                }
+                                return None;
+                            }
+                        }
+                    },
+                    None => self.table.global_scope()
+                };
+
+                let symbol = self.table.lookup_member(members, &token.text).first().copied();
+                match symbol
+                {
+                    Some(symbol) => self.table.add_reference(right.syntax(), symbol),
+                    None => {
+                        if report && self.quiet == 0
+                        {
+                            let owner = qualified.left().map(|l| l.text()).unwrap_or_else(|| String::from("the global namespace"));
+                            self.error_at_token(format!("no member named '{}' in '{}'", token.text, owner), token);
+                        }
+                    }
+                }
+                symbol
+            },
+            Name::Operator(_) => None
+        };
+
+        if let Some(symbol) = symbol
+        {
+            self.table.add_reference(name.syntax(), symbol);
+        }
+        return symbol;
+    }
+
+    // Statements
+
+    fn bind_statement(&mut self, statement: Statement<'a>, scope: ScopeId)
+    {
+        self.bind_attributes(statement.attributes(), scope);
+        match statement
+        {
+            Statement::Block(block) => {
+                let block_scope = self.table.add_scope(ScopeKind::Block, scope, Some(block.syntax()), None);
+                for statement in block.statements()
+                {
+                    self.bind_statement(statement, block_scope);
+                }
+            },
+            Statement::Expression(statement) => self.bind_optional_expression(statement.expression(), scope),
+            Statement::Return(statement) => self.bind_optional_expression(statement.value(), scope),
+            Statement::If(statement) => {
+                self.bind_optional_expression(statement.condition(), scope);
+                self.bind_optional_statement(statement.then_statement(), scope);
+                self.bind_optional_statement(statement.else_statement(), scope);
+            },
+            Statement::For(statement) => {
+                let for_scope = self.table.add_scope(ScopeKind::Block, scope, Some(statement.syntax()), None);
+                self.bind_optional_statement(statement.initializer(), for_scope);
+                self.bind_optional_expression(statement.condition(), for_scope);
+                self.bind_optional_expression(statement.incrementor(), for_scope);
+                self.bind_optional_statement(statement.body(), for_scope);
+
+                // Before HLSL 2021 the variables declared in a for loop are visible after the loop
+                if self.hlsl_version < HlslVersion::Hlsl2021
+                {
+                    for symbol in self.table.get_scope(for_scope).symbols.clone()
+                    {
+                        self.table.add_alias(scope, symbol);
+                    }
+                }
+            },
+            Statement::While(statement) => {
+                self.bind_optional_expression(statement.condition(), scope);
+                self.bind_optional_statement(statement.body(), scope);
+            },
+            Statement::Do(statement) => {
+                self.bind_optional_statement(statement.body(), scope);
+                self.bind_optional_expression(statement.condition(), scope);
+            },
+            Statement::Switch(statement) => {
+                self.bind_optional_expression(statement.expression(), scope);
+                let switch_scope = self.table.add_scope(ScopeKind::Block, scope, Some(statement.syntax()), None);
+                for section in statement.sections()
+                {
+                    for label in section.labels()
+                    {
+                        self.bind_optional_expression(label.value(), switch_scope);
+                    }
+
+                    for statement in section.statements()
+                    {
+                        self.bind_statement(statement, switch_scope);
+                    }
+                }
+            },
+            Statement::Variable(variable) => self.bind_variable(variable, scope),
+            Statement::Typedef(typedef) => self.bind_typedef(typedef, scope),
+            Statement::Type(declaration) => {
+                if let Some(struct_decl) = declaration.struct_decl()
+                {
+                    self.bind_struct(struct_decl, scope, None);
+                }
+            },
+            Statement::Empty(_) | Statement::Break(_) | Statement::Continue(_) | Statement::Discard(_) | Statement::Skipped(_) => {}
+        }
+    }
+
+    fn bind_optional_statement(&mut self, statement: Option<Statement<'a>>, scope: ScopeId)
+    {
+        if let Some(statement) = statement
+        {
+            self.bind_statement(statement, scope);
+        }
+    }
+
+    // Expressions
+
+    fn bind_optional_expression(&mut self, expression: Option<Expression<'a>>, scope: ScopeId)
+    {
+        if let Some(expression) = expression
+        {
+            self.bind_expression(expression, scope);
+        }
+    }
+
+    fn bind_expression(&mut self, expression: Expression<'a>, scope: ScopeId)
+    {
+        match expression
+        {
+            Expression::Identifier(identifier) => {
+                // 'this' inside methods
+                let text = &identifier.identifier().text;
+                if text != "this"
+                {
+                    self.bind_name(Name::Identifier(identifier), scope, !is_builtin_variable_name(text));
+                }
+            },
+            Expression::Qualified(qualified) => {
+                self.bind_name(Name::Qualified(qualified), scope, true);
+            },
+            Expression::Call(call) => {
+                match call.callee()
+                {
+                    // Functions that are not declared are intrinsics
+                    Some(Expression::Identifier(identifier)) => {
+                        self.bind_name(Name::Identifier(identifier), scope, false);
+                    },
+                    Some(callee) => self.bind_expression(callee, scope),
+                    None => {}
+                }
+
+                for argument in call.arguments()
+                {
+                    self.bind_expression(argument, scope);
+                }
+            },
+            // Members are resolved by the type checker, only the object is bound
+            Expression::MemberAccess(access) => self.bind_optional_expression(access.object(), scope),
+            Expression::Type(type_syntax) => self.bind_type(type_syntax, scope),
+            Expression::Cast(cast) => {
+                if let Some(target_type) = cast.target_type()
+                {
+                    self.bind_type(target_type, scope);
+                }
+                self.bind_optional_expression(cast.operand(), scope);
+            },
+            Expression::SamplerState(_) | Expression::StateReference(_) | Expression::Compile(_) => {
+                self.quiet += 1;
+                self.bind_nested_expressions(expression.syntax(), scope);
+                self.quiet -= 1;
+            },
+            _ => self.bind_nested_expressions(expression.syntax(), scope)
+        }
+    }
+
+    // Binds the expressions below the node, looking through nodes that are not expressions like argument lists and state blocks
+    fn bind_nested_expressions(&mut self, node: &'a SyntaxNode, scope: ScopeId)
+    {
+        for child in node.child_nodes()
+        {
+            match Expression::cast(child)
+            {
+                Some(expression) => self.bind_expression(expression, scope),
+                None => self.bind_nested_expressions(child, scope)
+            }
+        }
+    }
+}
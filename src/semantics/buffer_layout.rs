@@ -0,0 +1,640 @@
+use std::fmt;
+
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::SyntaxNode, ParserSettings};
+
+use super::{symbol_table::{ScopeId, SymbolId, SymbolKind, SymbolTable}, types::*};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LayoutRules
+{
+    // D3D constant buffers, members may not straddle a 16-byte register and arrays, matrices and structs start a new one
+    Legacy,
+    // SPIR-V uniform buffers, arrays and structs are aligned to 16 bytes
+    Std140,
+    // SPIR-V storage buffers, arrays and structs are aligned like their members
+    Std430,
+    // VK_EXT_scalar_block_layout, everything is aligned to the size of its components
+    Scalar,
+}
+
+// Size, alignment and strides of a type under a set of rules
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TypeLayout
+{
+    pub size: u32,
+    pub alignment: u32,
+    // Distance between array elements, 0 when the type is not an array
+    pub array_stride: u32,
+    // Distance between the columns of a column major matrix or the rows of a row major one, 0 when there is no matrix
+    pub matrix_stride: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MemberLayout
+{
+    pub name: String,
+    pub ty: Type,
+    // Set for matrices and arrays of matrices, column major unless the member is declared row_major
+    pub orientation: Option<MatrixOrientation>,
+    pub offset: u32,
+    pub size: u32,
+    // Unused bytes between the end of the member and the next member or the end of the buffer
+    pub padding: u32,
+    pub array_stride: u32,
+    pub matrix_stride: u32,
+    // Fields of struct members, their offsets are relative to the start of the struct
+    pub members: Vec<MemberLayout>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BufferLayout
+{
+    pub name: String,
+    // ConstantBuffer for cbuffer, ConstantBuffer<T> and $Globals, TextureBuffer for tbuffer and TextureBuffer<T>
+    pub kind: ResourceKind,
+    // The T of ConstantBuffer<T> and TextureBuffer<T>
+    pub element: Option<Type>,
+    // The cbuffer or the ConstantBuffer<T> variable, None for the $Globals buffer holding global uniforms
+    pub symbol: Option<SymbolId>,
+    pub size: u32,
+    pub members: Vec<MemberLayout>,
+}
+
+// A member before it is laid out, the field of a buffer or struct
+struct Member<'a>
+{
+    name: String,
+    ty: QualifiedType,
+    // Byte offset of packoffset(c1.y)
+    packoffset: Option<u32>,
+    node: &'a SyntaxNode,
+}
+
+fn align_up(value: u32, alignment: u32) -> u32
+{
+    return value.div_ceil(alignment) * alignment;
+}
+
+// Bools take 4 bytes, half and the minimum precision types take 4 bytes unless 16-bit types are enabled
+fn component_size(scalar: ScalarType, enable_16bit_types: bool) -> u32
+{
+    return match scalar
+    {
+        ScalarType::Bool => 4,
+        ScalarType::Half | ScalarType::Min10Float | ScalarType::Min16Float | ScalarType::Min12Int | ScalarType::Min16Int | ScalarType::Min16Uint
+            if enable_16bit_types => 2,
+        scalar => scalar.bit_width() / 8
+    };
+}
+
+// The byte offset of packoffset(c1.y), components are 4 bytes apart
+fn packoffset_offset(location: &PackOffsetLocation) -> Option<u32>
+{
+    let arguments = location.arguments();
+    let register = arguments.first()?;
+    let index: u32 = register.text.strip_prefix(['c', 'C'])?.parse().ok()?;
+    let component = match arguments.get(2)
+    {
+        Some(component) => "xyzw".find(component.text.as_str()).or_else(|| "rgba".find(component.text.as_str()))? as u32,
+        None => 0
+    };
+    return Some(index * 16 + component * 4);
+}
+
+fn contains_matrix(ty: &Type) -> bool
+{
+    return match ty
+    {
+        Type::Matrix(..) => true,
+        Type::Array(element, _) => contains_matrix(element),
+        _ => false
+    };
+}
+
+// LayoutCalculator
+// Computes the offset, size and padding of every member of the constant and texture buffers of a compilation unit,
+// including the $Globals buffer that holds global variables that are not static
+pub struct LayoutCalculator<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    resolver: TypeResolver<'t, 'a>,
+    rules: LayoutRules,
+    enable_16bit_types: bool,
+    buffers: Vec<BufferLayout>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> LayoutCalculator<'t, 'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>, settings: &ParserSettings, rules: LayoutRules) -> Self
+    {
+        let mut calculator = Self{
+            table: table,
+            resolver: TypeResolver::new(table),
+            rules: rules,
+            enable_16bit_types: settings.enable_16bit_types,
+            buffers: Vec::new(),
+            diagnostics: Vec::new()
+        };
+
+        calculator.lay_out_globals();
+        for declaration in unit.declarations()
+        {
+            calculator.lay_out_declaration(declaration);
+        }
+        return calculator;
+    }
+
+    pub fn get_buffers(&self) -> &Vec<BufferLayout>
+    {
+        return &self.buffers;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    pub fn buffer(&self, name: &str) -> Option<&BufferLayout>
+    {
+        return self.buffers.iter().find(|b| b.name == name);
+    }
+
+    // The layout of a type outside of a buffer, None for resources and arrays without a known size
+    pub fn type_layout(&self, ty: &Type, orientation: Option<MatrixOrientation>) -> Option<TypeLayout>
+    {
+        return self.compute_type_layout(ty, orientation, &mut Vec::new(), &mut Vec::new()).map(|(layout, _)| layout);
+    }
+
+    fn lay_out_declaration(&mut self, declaration: Declaration<'a>)
+    {
+        match declaration
+        {
+            Declaration::ConstantBuffer(buffer) => self.lay_out_constant_buffer(buffer),
+            Declaration::Variable(variable) => {
+                for declarator in variable.declarators()
+                {
+                    if let Some(symbol) = self.table.declared_by(declarator.syntax())
+                    {
+                        self.lay_out_buffer_variable(symbol);
+                    }
+                }
+            },
+            Declaration::Namespace(namespace) => {
+                for declaration in namespace.declarations()
+                {
+                    self.lay_out_declaration(declaration);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn lay_out_constant_buffer(&mut self, buffer: ConstantBufferDecl<'a>)
+    {
+        let mut members = Vec::new();
+        for field in buffer.fields()
+        {
+            for declarator in field.declarators()
+            {
+                if let Some(member) = self.member(declarator)
+                {
+                    members.push(member);
+                }
+            }
+        }
+
+        let mut diagnostics = Vec::new();
+        let (members, end, _) = self.lay_out_members(members, &mut Vec::new(), &mut diagnostics);
+        self.diagnostics.extend(diagnostics);
+        let name = buffer.name().map(|n| n.text.clone()).unwrap_or_default();
+        self.add_buffer(BufferLayout{
+            name: name,
+            kind: if buffer.is_tbuffer() { ResourceKind::TextureBuffer } else { ResourceKind::ConstantBuffer },
+            element: None,
+            symbol: self.table.declared_by(buffer.syntax()),
+            size: end,
+            members: members
+        });
+    }
+
+    // ConstantBuffer<T> and TextureBuffer<T> variables, arrays of them share one layout
+    fn lay_out_buffer_variable(&mut self, symbol: SymbolId)
+    {
+        let mut ty = self.resolver.symbol_type(symbol).ty;
+        while let Type::Array(element, _) = ty
+        {
+            ty = *element;
+        }
+
+        let (kind, element) = match ty
+        {
+            Type::Resource{ kind: kind @ (ResourceKind::ConstantBuffer | ResourceKind::TextureBuffer), element: Some(element), .. } => (kind, *element),
+            _ => return
+        };
+
+        let mut diagnostics = Vec::new();
+        let mut members = Vec::new();
+        let mut size = 0;
+        if let Type::Struct{ symbol: structure, .. } = &element
+        {
+            let fields = self.struct_members(*structure);
+            let (layouts, end, _) = self.lay_out_members(fields, &mut vec![*structure], &mut diagnostics);
+            members = layouts;
+            size = end;
+        }
+        self.diagnostics.extend(diagnostics);
+        self.add_buffer(BufferLayout{
+            name: self.table.get_symbol(symbol).name.clone(),
+            kind: kind,
+            element: Some(element),
+            symbol: Some(symbol),
+            size: size,
+            members: members
+        });
+    }
+
+    // Global variables that are not static, groupshared or resources are uniforms in the $Globals buffer
+    fn lay_out_globals(&mut self)
+    {
+        let mut in_buffers = Vec::new();
+        for symbol in self.table.get_symbols()
+        {
+            if let Some(buffer) = ConstantBufferDecl::cast(symbol.declaration)
+            {
+                for field in buffer.fields()
+                {
+                    in_buffers.extend(field.declarators().map(|d| d.syntax() as *const SyntaxNode));
+                }
+            }
+        }
+
+        let mut members = Vec::new();
+        for symbol in self.table.get_symbols().iter().filter(|s| s.kind == SymbolKind::GlobalVariable)
+        {
+            if in_buffers.contains(&(symbol.declaration as *const SyntaxNode))
+            {
+                continue;
+            }
+            let member = match VariableDeclarator::cast(symbol.declaration).and_then(|d| self.member(d))
+            {
+                Some(member) => member,
+                None => continue
+            };
+
+            let mut element = &member.ty.ty;
+            while let Type::Array(inner, _) = element
+            {
+                element = inner;
+            }
+            if !member.ty.modifiers.is_groupshared && matches!(element, Type::Scalar(_) | Type::Vector(..) | Type::Matrix(..) | Type::Struct{ .. })
+            {
+                members.push(member);
+            }
+        }
+
+        // Buffers without members are not created
+        if members.is_empty()
+        {
+            return;
+        }
+        let mut diagnostics = Vec::new();
+        let (members, end, _) = self.lay_out_members(members, &mut Vec::new(), &mut diagnostics);
+        self.diagnostics.extend(diagnostics);
+        self.add_buffer(BufferLayout{
+            name: String::from("$Globals"),
+            kind: ResourceKind::ConstantBuffer,
+            element: None,
+            symbol: None,
+            size: end,
+            members: members
+        });
+    }
+
+    // Buffers are rounded up to whole registers, the SPIR-V rules round them up to their alignment which is done when the struct is laid out
+    fn add_buffer(&mut self, mut buffer: BufferLayout)
+    {
+        if self.rules == LayoutRules::Legacy || self.rules == LayoutRules::Std140
+        {
+            buffer.size = align_up(buffer.size, 16);
+        }
+        set_padding(&mut buffer.members, buffer.size);
+        self.buffers.push(buffer);
+    }
+
+    // The member declared by a field declarator, static fields are not stored in the buffer
+    fn member(&self, declarator: VariableDeclarator<'a>) -> Option<Member<'a>>
+    {
+        let symbol = self.table.declared_by(declarator.syntax())?;
+        let ty = self.resolver.symbol_type(symbol);
+        if ty.modifiers.is_static
+        {
+            return None;
+        }
+        return Some(Member{
+            name: self.table.get_symbol(symbol).name.clone(),
+            ty: ty,
+            packoffset: declarator.packoffset().and_then(|p| packoffset_offset(&p)),
+            node: declarator.syntax()
+        });
+    }
+
+    // The fields of a struct with the fields of its base structs first
+    fn struct_members(&self, structure: SymbolId) -> Vec<Member<'a>>
+    {
+        let mut members = Vec::new();
+        if let Some(scope) = self.table.get_symbol(structure).members
+        {
+            self.collect_scope_members(scope, &mut members);
+        }
+        return members;
+    }
+
+    fn collect_scope_members(&self, scope: ScopeId, members: &mut Vec<Member<'a>>)
+    {
+        let scope = self.table.get_scope(scope);
+        for base in &scope.bases
+        {
+            self.collect_scope_members(*base, members);
+        }
+        for symbol in &scope.symbols
+        {
+            let entry = self.table.get_symbol(*symbol);
+            if entry.kind != SymbolKind::Field
+            {
+                continue;
+            }
+            if let Some(member) = VariableDeclarator::cast(entry.declaration).and_then(|d| self.member(d))
+            {
+                members.push(member);
+            }
+        }
+    }
+
+    // Places the members in order, members with a packoffset go where they say. Returns the laid out members, the end of the
+    // last member and the largest alignment
+    fn lay_out_members(&self, members: Vec<Member<'a>>, visiting: &mut Vec<SymbolId>, diagnostics: &mut Vec<Diagnostic>) -> (Vec<MemberLayout>, u32, u32)
+    {
+        let mut layouts: Vec<MemberLayout> = Vec::new();
+        let mut explicit: Vec<(usize, &'a SyntaxNode)> = Vec::new();
+        let mut cursor = 0;
+        let mut alignment = 1;
+        for member in members
+        {
+            let orientation = match contains_matrix(&member.ty.ty)
+            {
+                true => Some(member.ty.modifiers.orientation.unwrap_or(MatrixOrientation::ColumnMajor)),
+                false => None
+            };
+
+            let (layout, fields) = match self.compute_type_layout(&member.ty.ty, orientation, visiting, diagnostics)
+            {
+                Some(layout) => layout,
+                None => {
+                    if member.ty.ty.is_numeric() || matches!(member.ty.ty, Type::Array(..) | Type::Struct{ .. })
+                    {
+                        diagnostics.push(Diagnostic::warning(format!("the layout of '{}' cannot be computed, its size is unknown", member.name), member.node.span())
+                            .in_file(member.node.file_index()));
+                    }
+                    continue;
+                }
+            };
+
+            let offset = match member.packoffset
+            {
+                Some(offset) => {
+                    let crosses = offset % layout.alignment.min(16) != 0 || (layout.alignment < 16 && offset % 16 + layout.size > 16);
+                    if self.rules == LayoutRules::Legacy && crosses
+                    {
+                        diagnostics.push(Diagnostic::error(format!("packoffset for '{}' crosses a register boundary", member.name), member.node.span())
+                            .in_file(member.node.file_index()));
+                    }
+                    explicit.push((layouts.len(), member.node));
+                    offset
+                },
+                None => self.place(cursor, &layout)
+            };
+
+            cursor = offset + layout.size;
+            // A struct pushes the next member to a new register
+            if self.rules == LayoutRules::Legacy && matches!(member.ty.ty, Type::Struct{ .. })
+            {
+                cursor = align_up(cursor, 16);
+            }
+            alignment = alignment.max(layout.alignment);
+            layouts.push(MemberLayout{
+                name: member.name,
+                ty: member.ty.ty,
+                orientation: orientation,
+                offset: offset,
+                size: layout.size,
+                padding: 0,
+                array_stride: layout.array_stride,
+                matrix_stride: layout.matrix_stride,
+                members: fields
+            });
+        }
+
+        // Only members with a packoffset can overlap others
+        for (index, node) in &explicit
+        {
+            let member = &layouts[*index];
+            let overlapped = layouts.iter().enumerate().find(|(other_index, other)| other_index != index &&
+                other.offset < member.offset + member.size && member.offset < other.offset + other.size &&
+                !explicit.iter().any(|(e, _)| e == other_index && e > index));
+            if let Some((_, other)) = overlapped
+            {
+                diagnostics.push(Diagnostic::error(format!("packoffset overlap between '{}' and '{}'", other.name, member.name), node.span())
+                    .in_file(node.file_index()));
+            }
+        }
+
+        let end = layouts.iter().map(|m| m.offset + m.size).max().unwrap_or(0);
+        return (layouts, end, alignment);
+    }
+
+    fn place(&self, cursor: u32, layout: &TypeLayout) -> u32
+    {
+        let offset = align_up(cursor, layout.alignment);
+        if self.rules == LayoutRules::Legacy && offset % 16 + layout.size > 16
+        {
+            return align_up(offset, 16);
+        }
+        return offset;
+    }
+
+    fn vector_layout(&self, scalar: ScalarType, count: u32) -> TypeLayout
+    {
+        let component = component_size(scalar, self.enable_16bit_types);
+        let alignment = match self.rules
+        {
+            LayoutRules::Legacy | LayoutRules::Scalar => component,
+            // Three component vectors are aligned like four component ones
+            LayoutRules::Std140 | LayoutRules::Std430 => component * if count == 1 { 1 } else if count == 2 { 2 } else { 4 }
+        };
+        return TypeLayout{
+            size: component * count,
+            alignment: alignment,
+            array_stride: 0,
+            matrix_stride: 0
+        };
+    }
+
+    // The layout of a type and the layouts of its fields when it is a struct or an array of structs
+    fn compute_type_layout(&self, ty: &Type, orientation: Option<MatrixOrientation>, visiting: &mut Vec<SymbolId>, diagnostics: &mut Vec<Diagnostic>)
+        -> Option<(TypeLayout, Vec<MemberLayout>)>
+    {
+        return match ty
+        {
+            Type::Scalar(scalar) => Some((self.vector_layout(*scalar, 1), Vec::new())),
+            Type::Vector(scalar, count) => Some((self.vector_layout(*scalar, *count), Vec::new())),
+            // Column major matrices are stored as columns, row major ones as rows
+            Type::Matrix(scalar, rows, columns) => {
+                let (vectors, components) = match orientation
+                {
+                    Some(MatrixOrientation::RowMajor) => (*rows, *columns),
+                    _ => (*columns, *rows)
+                };
+                let vector = self.vector_layout(*scalar, components);
+                let (stride, alignment) = match self.rules
+                {
+                    LayoutRules::Legacy => (align_up(vector.size, 16), 16),
+                    LayoutRules::Std140 => (align_up(vector.size, 16), align_up(vector.alignment, 16)),
+                    LayoutRules::Std430 => (align_up(vector.size, vector.alignment), vector.alignment),
+                    LayoutRules::Scalar => (vector.size, vector.alignment)
+                };
+                let size = match self.rules
+                {
+                    // The last column does not take a whole register
+                    LayoutRules::Legacy => (vectors - 1) * stride + vector.size,
+                    _ => vectors * stride
+                };
+                Some((TypeLayout{ size: size, alignment: alignment, array_stride: 0, matrix_stride: stride }, Vec::new()))
+            },
+            Type::Array(element, ArraySize::Fixed(count)) => {
+                let (element_layout, fields) = self.compute_type_layout(element, orientation, visiting, diagnostics)?;
+                let alignment = match self.rules
+                {
+                    LayoutRules::Legacy => 16,
+                    LayoutRules::Std140 => align_up(element_layout.alignment, 16),
+                    LayoutRules::Std430 | LayoutRules::Scalar => element_layout.alignment
+                };
+                let stride = match self.rules
+                {
+                    LayoutRules::Scalar => element_layout.size,
+                    _ => align_up(element_layout.size, alignment)
+                };
+                let size = match self.rules
+                {
+                    // Members after the array can be packed into the register of its last element
+                    LayoutRules::Legacy => count.saturating_sub(1) * stride + element_layout.size,
+                    _ => count * stride
+                };
+                let layout = TypeLayout{
+                    size: size,
+                    alignment: alignment,
+                    array_stride: stride,
+                    matrix_stride: element_layout.matrix_stride
+                };
+                Some((layout, fields))
+            },
+            Type::Struct{ symbol, .. } => {
+                // A struct can not contain itself
+                if visiting.contains(symbol)
+                {
+                    return None;
+                }
+                visiting.push(*symbol);
+                let members = self.struct_members(*symbol);
+                let (mut fields, end, largest) = self.lay_out_members(members, visiting, diagnostics);
+                visiting.pop();
+
+                let (size, alignment) = match self.rules
+                {
+                    LayoutRules::Legacy => (end, 16),
+                    LayoutRules::Std140 => {
+                        let alignment = align_up(largest, 16);
+                        (align_up(end, alignment), alignment)
+                    },
+                    LayoutRules::Std430 | LayoutRules::Scalar => (align_up(end, largest), largest)
+                };
+                set_padding(&mut fields, size);
+                Some((TypeLayout{ size: size, alignment: alignment, array_stride: 0, matrix_stride: 0 }, fields))
+            },
+            _ => None
+        };
+    }
+}
+
+// The padding of each member is the gap up to the member after it, or up to the end of the buffer or struct
+fn set_padding(members: &mut [MemberLayout], size: u32)
+{
+    let mut ends: Vec<u32> = members.iter().map(|m| m.offset).collect();
+    ends.sort();
+    for member in members.iter_mut()
+    {
+        let end = member.offset + member.size;
+        let next = ends.iter().copied().find(|offset| *offset >= end).unwrap_or(size);
+        member.padding = next.saturating_sub(end);
+    }
+}
+
+// Writes the members like the FXC reflection listing, 'float4x4 view; // Offset: 0, Size: 64'
+fn write_members(f: &mut fmt::Formatter<'_>, members: &[MemberLayout], depth: usize) -> fmt::Result
+{
+    let indent = "    ".repeat(depth);
+    for member in members
+    {
+        let mut element = &member.ty;
+        let mut ranks = String::new();
+        while let Type::Array(inner, size) = element
+        {
+            match size
+            {
+                ArraySize::Fixed(count) => ranks.push_str(&format!("[{}]", count)),
+                _ => ranks.push_str("[]")
+            }
+            element = inner;
+        }
+
+        let orientation = match member.orientation
+        {
+            Some(MatrixOrientation::RowMajor) => "row_major ",
+            _ => ""
+        };
+        if member.members.is_empty()
+        {
+            write!(f, "{}{}{} {}{};", indent, orientation, element, member.name, ranks)?;
+        }
+        else
+        {
+            writeln!(f, "{}struct {}", indent, element)?;
+            writeln!(f, "{}{{", indent)?;
+            write_members(f, &member.members, depth + 1)?;
+            write!(f, "{}}} {}{};", indent, member.name, ranks)?;
+        }
+
+        write!(f, " // Offset: {}, Size: {}", member.offset, member.size)?;
+        if member.padding > 0
+        {
+            write!(f, ", Padding: {}", member.padding)?;
+        }
+        writeln!(f)?;
+    }
+    return Ok(());
+}
+
+impl fmt::Display for BufferLayout
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.element, self.kind)
+        {
+            (Some(element), kind) => writeln!(f, "{}<{}> {} // Size: {}", kind.name(), element, self.name, self.size)?,
+            (None, ResourceKind::TextureBuffer) => writeln!(f, "tbuffer {} // Size: {}", self.name, self.size)?,
+            (None, _) => writeln!(f, "cbuffer {} // Size: {}", self.name, self.size)?
+        }
+        writeln!(f, "{{")?;
+        write_members(f, &self.members, 1)?;
+        return write!(f, "}}");
+    }
+}
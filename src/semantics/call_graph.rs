@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::{SyntaxNode, SyntaxNodeType}, ParserSettings, ShaderStage};
+
+use super::{constant_evaluator::attribute_name, entry_points::{global_function, EntryPointFinder}, reflection::{has_output_semantics, string_argument},
+    symbol_table::{SymbolId, SymbolKind, SymbolTable}, type_checker::TypeChecker};
+
+// A call from the body of one function to another function or method
+#[derive(Clone, Debug)]
+pub struct CallSite<'a>
+{
+    pub caller: SymbolId,
+    pub callee: SymbolId,
+    pub call: &'a SyntaxNode,
+}
+
+// The definition of a function or method, None for functions that are only declared
+pub(crate) fn function_body<'a>(table: &SymbolTable<'a>, symbol: SymbolId) -> Option<FunctionDecl<'a>>
+{
+    let entry = table.get_symbol(symbol);
+    if !entry.kind.is_function()
+    {
+        return None;
+    }
+    let function = FunctionDecl::cast(entry.definition.unwrap_or(entry.declaration))?;
+    return function.body().map(|_| function);
+}
+
+// The functions with the name of the function a call names
+fn call_candidates(table: &SymbolTable, call: &SyntaxNode) -> Vec<SymbolId>
+{
+    let symbol = match CallExpr::cast(call).and_then(|c| c.callee()).and_then(|callee| table.symbol_of(callee.syntax()))
+    {
+        Some(symbol) => symbol,
+        None => return Vec::new()
+    };
+    let entry = table.get_symbol(symbol);
+    if !entry.kind.is_function()
+    {
+        return Vec::new();
+    }
+    return table.get_scope(entry.scope).get(&entry.name).iter().copied().filter(|s| table.get_symbol(*s).kind.is_function()).collect();
+}
+
+// State used by Tarjan's algorithm to find the functions that call each other
+struct Components
+{
+    index: HashMap<SymbolId, usize>,
+    low_link: HashMap<SymbolId, usize>,
+    stack: Vec<SymbolId>,
+    on_stack: HashSet<SymbolId>,
+    components: Vec<Vec<SymbolId>>,
+}
+
+// CallGraph
+// The calls between the functions and methods of a compilation unit, including methods defined out of line like
+// 'int Outer::MyClass::TestFunc() { }', and the symbols each function refers to. Recursion is reported as an error because
+// HLSL inlines every call, and functions the entry points never reach are reported as never called
+pub struct CallGraph<'a>
+{
+    functions: Vec<SymbolId>,
+    calls: Vec<CallSite<'a>>,
+    // Symbols named in the body of each function, like globals, resources and locals
+    references: HashMap<SymbolId, HashSet<SymbolId>>,
+    entry_points: Vec<SymbolId>,
+    recursive: HashSet<SymbolId>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> CallGraph<'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &SymbolTable<'a>, settings: &ParserSettings) -> Self
+    {
+        let checker = TypeChecker::new(unit, table, settings);
+        let mut graph = Self{
+            functions: Vec::new(),
+            calls: Vec::new(),
+            references: HashMap::new(),
+            entry_points: Vec::new(),
+            recursive: HashSet::new(),
+            diagnostics: Vec::new()
+        };
+
+        let mut calls_in_functions = HashSet::new();
+        for (symbol, _) in table.get_symbols().iter().enumerate()
+        {
+            let body = match function_body(table, symbol)
+            {
+                Some(body) => body,
+                None => continue
+            };
+            graph.functions.push(symbol);
+            let references = graph.references.entry(symbol).or_default();
+            for node in body.syntax().descendant_nodes()
+            {
+                match node.node_type
+                {
+                    SyntaxNodeType::IdentifierName | SyntaxNodeType::QualifiedName => {
+                        references.extend(table.symbol_of(node));
+                    },
+                    SyntaxNodeType::CallExpression => {
+                        calls_in_functions.insert(node as *const SyntaxNode);
+                        // A call that is ambiguous or matches no overload can mean any overload of the name
+                        let callees = match checker.call_target(node)
+                        {
+                            Some(callee) => vec![callee],
+                            None => call_candidates(table, node)
+                        };
+                        for callee in callees
+                        {
+                            graph.calls.push(CallSite{ caller: symbol, callee: callee, call: node });
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        // Entry points, the patch constant functions of hull shaders and functions called by global initializers are used
+        for entry_point in EntryPointFinder::new(unit, table).get_entry_points()
+        {
+            graph.entry_points.push(entry_point.symbol);
+            if entry_point.stage == ShaderStage::Hull
+            {
+                let patch_function = entry_point.function.attributes().find(|a| attribute_name(a) == "patchconstantfunc")
+                    .and_then(|a| string_argument(&a)).and_then(|name| global_function(table, &name));
+                graph.entry_points.extend(patch_function.map(|(symbol, _)| symbol));
+            }
+        }
+        for node in unit.syntax().descendant_nodes()
+        {
+            if node.node_type == SyntaxNodeType::CallExpression && !calls_in_functions.contains(&(node as *const SyntaxNode))
+            {
+                // Arguments of effect compile expressions like 'compile vs_2_0 VS(offset())' are not type checked, they are looked up by name
+                let target = checker.call_target(node).or_else(|| CallExpr::cast(node).and_then(|c| c.function_name())
+                    .and_then(|name| global_function(table, &name.text)).map(|(symbol, _)| symbol));
+                graph.entry_points.extend(target);
+            }
+        }
+
+        graph.report_recursion(table);
+        graph.report_unused(table);
+        return graph;
+    }
+
+    // Functions and methods with a body in declaration order
+    pub fn get_functions(&self) -> &Vec<SymbolId>
+    {
+        return &self.functions;
+    }
+
+    pub fn get_calls(&self) -> &Vec<CallSite<'a>>
+    {
+        return &self.calls;
+    }
+
+    // The functions the graph starts from, entry points and the functions they need without calling them
+    pub fn get_entry_points(&self) -> &Vec<SymbolId>
+    {
+        return &self.entry_points;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    // The functions the function calls directly, each once in call order
+    pub fn callees(&self, function: SymbolId) -> Vec<SymbolId>
+    {
+        let mut callees = Vec::new();
+        for call in self.calls.iter().filter(|c| c.caller == function)
+        {
+            if !callees.contains(&call.callee)
+            {
+                callees.push(call.callee);
+            }
+        }
+        return callees;
+    }
+
+    // The functions that call the function directly, each once
+    pub fn callers(&self, function: SymbolId) -> Vec<SymbolId>
+    {
+        let mut callers = Vec::new();
+        for call in self.calls.iter().filter(|c| c.callee == function)
+        {
+            if !callers.contains(&call.caller)
+            {
+                callers.push(call.caller);
+            }
+        }
+        return callers;
+    }
+
+    // The functions and everything they call
+    pub fn reachable(&self, functions: &[SymbolId]) -> HashSet<SymbolId>
+    {
+        let mut visited: HashSet<SymbolId> = functions.iter().copied().collect();
+        let mut queue: VecDeque<SymbolId> = functions.iter().copied().collect();
+        while let Some(function) = queue.pop_front()
+        {
+            for call in self.calls.iter().filter(|c| c.caller == function)
+            {
+                if visited.insert(call.callee)
+                {
+                    queue.push_back(call.callee);
+                }
+            }
+        }
+        return visited;
+    }
+
+    // The symbols named by the functions and everything they call
+    pub fn referenced_symbols(&self, functions: &[SymbolId]) -> HashSet<SymbolId>
+    {
+        let mut referenced = HashSet::new();
+        for function in self.reachable(functions)
+        {
+            if let Some(references) = self.references.get(&function)
+            {
+                referenced.extend(references.iter().copied());
+            }
+        }
+        return referenced;
+    }
+
+    // The global variables, constant buffer fields and resources the function touches through its calls, in declaration order
+    pub fn globals_of(&self, table: &SymbolTable<'a>, function: SymbolId) -> Vec<SymbolId>
+    {
+        let mut globals: Vec<SymbolId> = self.referenced_symbols(&[function]).into_iter()
+            .filter(|s| table.get_symbol(*s).kind == SymbolKind::GlobalVariable).collect();
+        globals.sort();
+        return globals;
+    }
+
+    // Whether the function calls itself, directly or through other functions
+    pub fn is_recursive(&self, function: SymbolId) -> bool
+    {
+        return self.recursive.contains(&function);
+    }
+
+    fn report_recursion(&mut self, table: &SymbolTable<'a>)
+    {
+        let mut components = Components{
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new()
+        };
+        for function in self.functions.clone()
+        {
+            if !components.index.contains_key(&function)
+            {
+                self.connect(function, &mut components);
+            }
+        }
+
+        let mut components = components.components;
+        components.sort_by_key(|c| c.iter().min().copied());
+        for component in components
+        {
+            // The first call in the source that stays inside the component closes the cycle, a single function has to call itself
+            let call = match self.calls.iter().filter(|c| component.contains(&c.caller) && component.contains(&c.callee))
+                .min_by_key(|c| (c.call.file_index(), c.call.span().start))
+            {
+                Some(call) => call,
+                None => continue
+            };
+            self.recursive.extend(component.iter().copied());
+            let message = format!("recursive functions are not allowed: function '{}' calls recursive function '{}'",
+                table.get_symbol(call.caller).name, table.get_symbol(call.callee).name);
+            self.diagnostics.push(Diagnostic::error(message, call.call.span()).in_file(call.call.file_index()));
+        }
+    }
+
+    fn connect(&self, function: SymbolId, components: &mut Components)
+    {
+        let index = components.index.len();
+        components.index.insert(function, index);
+        components.low_link.insert(function, index);
+        components.stack.push(function);
+        components.on_stack.insert(function);
+
+        for callee in self.callees(function)
+        {
+            if !components.index.contains_key(&callee)
+            {
+                self.connect(callee, components);
+                let low_link = components.low_link[&function].min(components.low_link[&callee]);
+                components.low_link.insert(function, low_link);
+            }
+            else if components.on_stack.contains(&callee)
+            {
+                let low_link = components.low_link[&function].min(components.index[&callee]);
+                components.low_link.insert(function, low_link);
+            }
+        }
+
+        if components.low_link[&function] == index
+        {
+            let mut component = Vec::new();
+            while let Some(member) = components.stack.pop()
+            {
+                components.on_stack.remove(&member);
+                component.push(member);
+                if member == function
+                {
+                    break;
+                }
+            }
+            components.components.push(component);
+        }
+    }
+
+    fn report_unused(&mut self, table: &SymbolTable<'a>)
+    {
+        // Functions nothing calls are compiled on their own with -E when they output values with semantics, like a vertex shader
+        // that feeds a hull shader
+        let reachable = self.reachable(&self.entry_points);
+        for function in self.functions.clone()
+        {
+            let entry = table.get_symbol(function);
+            if reachable.contains(&function) || entry.kind != SymbolKind::Function || !self.callers(function).is_empty() ||
+                entry.scope != table.global_scope()
+            {
+                continue;
+            }
+            if function_body(table, function).map(|f| has_output_semantics(table, f)).unwrap_or(false)
+            {
+                self.entry_points.push(function);
+            }
+        }
+
+        // Files without entry points are headers and libraries, their functions are meant to be called by other files
+        if self.entry_points.is_empty()
+        {
+            return;
+        }
+        // Overloads are reported one by one, calls reach every overload they can mean so only the ones nothing can call are left
+        let reachable = self.reachable(&self.entry_points);
+        for function in &self.functions
+        {
+            let entry = table.get_symbol(*function);
+            // Methods of classes can be called through interfaces
+            if reachable.contains(function) || entry.file_index() != 0 || entry.kind == SymbolKind::Method
+            {
+                continue;
+            }
+            self.diagnostics.push(Diagnostic::warning(format!("function '{}' is never called", entry.name), entry.span()).in_file(entry.file_index()));
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_tokens::SyntaxToken};
+
+// ClassTable
+// All structs, classes and interfaces of a compilation unit. Checks that classes implement the methods of their interfaces
+// and resolves method calls on instances, like gAbstractColourChanger.ChangeColour(colour)
+pub struct ClassTable<'a>
+{
+    unit: CompilationUnit<'a>,
+    types: Vec<StructDecl<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+// The text of a method signature used to compare implementations, like 'float3(float3)' or 'void(out float)'
+pub fn method_signature(method: &FunctionDecl) -> String
+{
+    let return_type = method.return_type().map(|t| t.syntax().to_text()).unwrap_or_default();
+    let mut parameters = Vec::new();
+    for parameter in method.params()
+    {
+        let mut text = String::new();
+        for modifier in parameter.modifiers()
+        {
+            // Parameters are input by default
+            if modifier.text != "in"
+            {
+                text.push_str(&modifier.text);
+                text.push(' ');
+            }
+        }
+
+        text.push_str(&parameter.param_type().map(|t| t.syntax().to_text()).unwrap_or_default());
+        if let Some(declarator) = parameter.declarator()
+        {
+            for rank in declarator.array_ranks()
+            {
+                text.push_str(&rank.syntax().to_text().replace(' ', ""));
+            }
+        }
+        parameters.push(text);
+    }
+
+    return format!("{}({})", return_type, parameters.join(", "));
+}
+
+fn method_name<'a>(method: &FunctionDecl<'a>) -> Option<&'a SyntaxToken>
+{
+    return method.name().and_then(|n| n.identifier());
+}
+
+// The name of a user defined type, Outer::MyClass is found as MyClass
+fn type_name<'a>(type_syntax: &TypeSyntax<'a>) -> Option<&'a str>
+{
+    return match type_syntax
+    {
+        TypeSyntax::User(user) => user.name().and_then(|n| n.identifier()).map(|t| t.text.as_str()),
+        TypeSyntax::Struct(declaration) => declaration.name().map(|t| t.text.as_str()),
+        TypeSyntax::Predefined(_) => None
+    };
+}
+
+impl<'a> ClassTable<'a>
+{
+    pub fn new(unit: CompilationUnit<'a>) -> Self
+    {
+        let types = unit.syntax().descendant_nodes().into_iter()
+            .filter_map(StructDecl::cast)
+            .filter(|s| s.name().is_some())
+            .collect();
+
+        let mut table = Self{
+            unit: unit,
+            types: types,
+            diagnostics: Vec::new()
+        };
+        table.check();
+        return table;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    pub fn get_types(&self) -> &Vec<StructDecl<'a>>
+    {
+        return &self.types;
+    }
+
+    pub fn find(&self, name: &str) -> Option<StructDecl<'a>>
+    {
+        return self.types.iter().find(|t| t.name().map(|n| n.text == name).unwrap_or(false)).copied();
+    }
+
+    // Direct bases that could be found
+    pub fn bases(&self, declaration: &StructDecl<'a>) -> Vec<StructDecl<'a>>
+    {
+        return declaration.base_list().iter()
+            .filter_map(|base| base.identifier())
+            .filter_map(|name| self.find(&name.text))
+            .collect();
+    }
+
+    // Interfaces implemented by the type, including the ones of its base classes
+    pub fn interfaces(&self, declaration: &StructDecl<'a>) -> Vec<StructDecl<'a>>
+    {
+        let mut interfaces = Vec::new();
+        let mut pending = self.bases(declaration);
+        let mut depth = 0;
+        // Depth guard against cyclic inheritance
+        while let Some(base) = pending.pop()
+        {
+            depth += 1;
+            if depth > 64
+            {
+                break;
+            }
+
+            if base.kind() == StructKind::Interface
+            {
+                if !interfaces.iter().any(|i: &StructDecl| std::ptr::eq(i.syntax(), base.syntax()))
+                {
+                    interfaces.push(base);
+                }
+            }
+            pending.extend(self.bases(&base));
+        }
+        return interfaces;
+    }
+
+    // Methods with the name on the type or the first base class that has them, methods of implemented interfaces are not included
+    pub fn find_methods(&self, declaration: &StructDecl<'a>, name: &str) -> Vec<FunctionDecl<'a>>
+    {
+        let mut current = vec![*declaration];
+        for _ in 0..64
+        {
+            let mut next = Vec::new();
+            for declaration in &current
+            {
+                let methods: Vec<FunctionDecl> = declaration.methods().filter(|m| method_name(m).map(|n| n.text == name).unwrap_or(false)).collect();
+                if !methods.is_empty()
+                {
+                    return methods;
+                }
+                next.extend(self.bases(declaration).into_iter().filter(|b| b.kind() != StructKind::Interface));
+            }
+
+            if next.is_empty()
+            {
+                break;
+            }
+            current = next;
+        }
+        return Vec::new();
+    }
+
+    fn check(&mut self)
+    {
+        for declaration in self.types.clone()
+        {
+            self.check_bases(&declaration);
+            match declaration.kind()
+            {
+                StructKind::Interface => self.check_interface(&declaration),
+                _ => self.check_conformance(&declaration)
+            }
+        }
+    }
+
+    fn error(&mut self, message: String, token: &SyntaxToken)
+    {
+        self.diagnostics.push(Diagnostic::error(message, token.span).in_file(token.file_index));
+    }
+
+    fn check_bases(&mut self, declaration: &StructDecl<'a>)
+    {
+        for base in declaration.base_list()
+        {
+            let name = match base.identifier()
+            {
+                Some(name) => name,
+                None => continue
+            };
+
+            if self.find(&name.text).is_none()
+            {
+                self.error(format!("Unknown base type '{}'", base.text()), name);
+            }
+        }
+    }
+
+    fn check_interface(&mut self, declaration: &StructDecl<'a>)
+    {
+        let name = declaration.name().unwrap();
+        if declaration.fields().next().is_some()
+        {
+            self.error(format!("Interface '{}' cannot contain fields", name.text), name);
+        }
+
+        for method in declaration.methods()
+        {
+            if method.is_definition()
+            {
+                if let Some(method_name) = method_name(&method)
+                {
+                    self.error(format!("Interface method '{}::{}' cannot have a body", name.text, method_name.text), method_name);
+                }
+            }
+        }
+    }
+
+    fn check_conformance(&mut self, declaration: &StructDecl<'a>)
+    {
+        let name = declaration.name().unwrap();
+        for interface in self.interfaces(declaration)
+        {
+            let interface_name = &interface.name().unwrap().text;
+            for required in interface.methods()
+            {
+                let required_name = match method_name(&required)
+                {
+                    Some(required_name) => required_name,
+                    None => continue
+                };
+
+                let candidates = self.find_methods(declaration, &required_name.text);
+                if candidates.is_empty()
+                {
+                    self.error(format!("'{}' does not implement '{}::{}'", name.text, interface_name, required_name.text), name);
+                    continue;
+                }
+
+                let signature = method_signature(&required);
+                if !candidates.iter().any(|c| method_signature(c) == signature)
+                {
+                    let candidate_name = method_name(&candidates[0]).unwrap();
+                    let message = format!("'{}::{}' does not match the signature '{}' of '{}::{}'",
+                        name.text, candidate_name.text, signature, interface_name, required_name.text);
+                    self.error(message, candidate_name);
+                }
+            }
+        }
+    }
+
+    // Resolves a call like instance.Method(args) to the declaration of the method. Calls on interface instances resolve to the
+    // method of the interface
+    pub fn resolve_method_call(&self, call: &CallExpr<'a>) -> Option<FunctionDecl<'a>>
+    {
+        let access = match call.callee()?
+        {
+            Expression::MemberAccess(access) => access,
+            _ => return None
+        };
+
+        let object_type = self.expression_type(&access.object()?, call)?;
+        let methods = self.find_methods(&object_type, &access.member()?.text);
+        let argument_count = call.arguments().len();
+        return methods.iter().find(|m| m.params().len() == argument_count).or(methods.first()).copied();
+    }
+
+    // The struct, class or interface an expression refers to
+    fn expression_type(&self, expression: &Expression<'a>, call: &CallExpr<'a>) -> Option<StructDecl<'a>>
+    {
+        return match expression
+        {
+            Expression::Identifier(name) => {
+                let variable_type = self.find_variable_type(&name.identifier().text, call)?;
+                self.find(type_name(&variable_type)?)
+            },
+            // Arrays of instances, like gAbstractColourChanger[1]
+            Expression::ElementAccess(access) => self.expression_type(&access.object()?, call),
+            Expression::Parenthesized(parenthesized) => self.expression_type(&parenthesized.expression()?, call),
+            Expression::MemberAccess(access) => {
+                let object_type = self.expression_type(&access.object()?, call)?;
+                let member = access.member()?;
+                let field_type = self.find_field_type(&object_type, &member.text)?;
+                self.find(type_name(&field_type)?)
+            },
+            _ => None
+        };
+    }
+
+    fn find_field_type(&self, declaration: &StructDecl<'a>, name: &str) -> Option<TypeSyntax<'a>>
+    {
+        for field in declaration.fields()
+        {
+            if field.declarators().any(|d| d.name().map(|n| n.text == name).unwrap_or(false))
+            {
+                return field.var_type();
+            }
+        }
+
+        for base in self.bases(declaration)
+        {
+            if let Some(field_type) = self.find_field_type(&base, name)
+            {
+                return Some(field_type);
+            }
+        }
+        return None;
+    }
+
+    // Looks for the variable in the function around the call, declared before it, and then in the global scope
+    fn find_variable_type(&self, name: &str, call: &CallExpr<'a>) -> Option<TypeSyntax<'a>>
+    {
+        let file_index = call.file_index();
+        let position = call.span().start;
+        let function = self.unit.functions().into_iter()
+            .find(|f| f.body().is_some() && f.file_index() == file_index && f.span().contains(position));
+
+        if let Some(function) = function
+        {
+            let mut found = None;
+            for declaration in function.syntax().descendant_nodes().into_iter().filter_map(VariableDecl::cast)
+            {
+                if declaration.file_index() != file_index || declaration.span().start > position
+                {
+                    continue;
+                }
+
+                if declaration.declarators().any(|d| d.name().map(|n| n.text == name).unwrap_or(false))
+                {
+                    found = declaration.var_type();
+                }
+            }
+
+            if found.is_some()
+            {
+                return found;
+            }
+
+            for parameter in function.params()
+            {
+                if parameter.name().map(|n| n.text == name).unwrap_or(false)
+                {
+                    return parameter.param_type();
+                }
+            }
+
+            // Fields of the class a method belongs to
+            if let Some(class) = self.types.iter().find(|t| t.methods().any(|m| std::ptr::eq(m.syntax(), function.syntax())))
+            {
+                if let Some(field_type) = self.find_field_type(class, name)
+                {
+                    return Some(field_type);
+                }
+            }
+        }
+
+        return self.find_global_type(self.unit.declarations(), name);
+    }
+
+    fn find_global_type(&self, declarations: impl Iterator<Item = Declaration<'a>>, name: &str) -> Option<TypeSyntax<'a>>
+    {
+        for declaration in declarations
+        {
+            let found = match declaration
+            {
+                Declaration::Variable(variable) => {
+                    if variable.declarators().any(|d| d.name().map(|n| n.text == name).unwrap_or(false)) { variable.var_type() } else { None }
+                },
+                Declaration::ConstantBuffer(buffer) => {
+                    buffer.fields().find(|f| f.declarators().any(|d| d.name().map(|n| n.text == name).unwrap_or(false))).and_then(|f| f.var_type())
+                },
+                Declaration::Namespace(namespace) => self.find_global_type(namespace.declarations(), name),
+                _ => None
+            };
+
+            if found.is_some()
+            {
+                return found;
+            }
+        }
+        return None;
+    }
+}
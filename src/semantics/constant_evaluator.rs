@@ -0,0 +1,611 @@
+use std::{collections::HashMap, fmt};
+
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::{SyntaxNode, SyntaxNodeType}, syntax_tokens::SyntaxTokenType};
+
+use super::{symbol_table::{SymbolId, SymbolKind, SymbolTable}, type_checker::literal_type, types::*};
+
+fn address(node: &SyntaxNode) -> usize
+{
+    return node as *const SyntaxNode as usize;
+}
+
+// A value known at compile time. Integers are wrapped to the width of their type and floats are rounded to its precision,
+// the bits of uint64_t values are stored in the i64
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConstantValue
+{
+    Bool(bool),
+    Int(i64, ScalarType),
+    Float(f64, ScalarType),
+}
+
+impl ConstantValue
+{
+    pub fn scalar_type(&self) -> ScalarType
+    {
+        return match self
+        {
+            ConstantValue::Bool(_) => ScalarType::Bool,
+            ConstantValue::Int(_, scalar) | ConstantValue::Float(_, scalar) => *scalar
+        };
+    }
+
+    pub fn as_bool(&self) -> bool
+    {
+        return match self
+        {
+            ConstantValue::Bool(value) => *value,
+            ConstantValue::Int(value, _) => *value != 0,
+            ConstantValue::Float(value, _) => *value != 0.0
+        };
+    }
+
+    pub fn as_i64(&self) -> i64
+    {
+        return match self
+        {
+            ConstantValue::Bool(value) => *value as i64,
+            ConstantValue::Int(value, _) => *value,
+            // Float to integer conversions truncate towards zero
+            ConstantValue::Float(value, _) => *value as i64
+        };
+    }
+
+    pub fn as_f64(&self) -> f64
+    {
+        return match self
+        {
+            ConstantValue::Bool(value) => *value as u32 as f64,
+            ConstantValue::Int(value, ScalarType::Uint64) => *value as u64 as f64,
+            ConstantValue::Int(value, _) => *value as f64,
+            ConstantValue::Float(value, _) => *value
+        };
+    }
+
+    // Sizes and counts, None for negative values and values that do not fit in 32 bits
+    pub fn as_u32(&self) -> Option<u32>
+    {
+        return match self
+        {
+            ConstantValue::Int(value, ScalarType::Uint64) => u32::try_from(*value as u64).ok(),
+            ConstantValue::Int(value, _) => u32::try_from(*value).ok(),
+            _ => None
+        };
+    }
+
+    // The value converted to another scalar type with the conversion rules of HLSL
+    pub fn convert(&self, target: ScalarType) -> ConstantValue
+    {
+        if target == ScalarType::Bool
+        {
+            return ConstantValue::Bool(self.as_bool());
+        }
+        if target.is_float()
+        {
+            return ConstantValue::Float(round_to(self.as_f64(), target), target);
+        }
+
+        let value = match self
+        {
+            ConstantValue::Float(value, _) if target == ScalarType::Uint64 => *value as u64 as i64,
+            _ => self.as_i64()
+        };
+        return ConstantValue::Int(wrap(value, target), target);
+    }
+}
+
+impl fmt::Display for ConstantValue
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self
+        {
+            ConstantValue::Bool(value) => write!(f, "{}", value),
+            ConstantValue::Int(value, ScalarType::Uint64) => write!(f, "{}", *value as u64),
+            ConstantValue::Int(value, _) => write!(f, "{}", value),
+            ConstantValue::Float(value, _) => write!(f, "{:?}", value)
+        };
+    }
+}
+
+// Wraps an integer to the width of its type, minimum precision integers are evaluated with 32 bits
+fn wrap(value: i64, scalar: ScalarType) -> i64
+{
+    return match scalar
+    {
+        ScalarType::Int | ScalarType::Min12Int | ScalarType::Min16Int => value as i32 as i64,
+        ScalarType::Uint | ScalarType::Min16Uint => value as u32 as i64,
+        ScalarType::Int16 => value as i16 as i64,
+        ScalarType::Uint16 => value as u16 as i64,
+        _ => value
+    };
+}
+
+// Rounds a float to the precision of its type, literal floats and doubles keep 64 bits
+fn round_to(value: f64, scalar: ScalarType) -> f64
+{
+    return match scalar
+    {
+        ScalarType::Half | ScalarType::Float16 => round_to_half(value),
+        ScalarType::Float | ScalarType::Min10Float | ScalarType::Min16Float => value as f32 as f64,
+        _ => value
+    };
+}
+
+// Rounds to the nearest value a 16-bit float can hold, ties go to even like the hardware conversion
+fn round_to_half(value: f64) -> f64
+{
+    if !value.is_finite() || value == 0.0
+    {
+        return value;
+    }
+
+    // The largest half is 65504, anything from halfway to the next step up overflows
+    let magnitude = value.abs();
+    if magnitude >= 65520.0
+    {
+        return f64::INFINITY.copysign(value);
+    }
+
+    // Halves have 10 fraction bits, subnormals share the step of the smallest normal exponent
+    let exponent = (((magnitude.to_bits() >> 52) & 0x7ff) as i32 - 1023).max(-14);
+    let step = 2f64.powi(exponent - 10);
+    return ((magnitude / step).round_ties_even() * step).copysign(value);
+}
+
+// The value of a literal token with the type the type checker gives it
+fn literal_value(literal: &LiteralExpr) -> Option<ConstantValue>
+{
+    let scalar = match literal_type(literal)
+    {
+        Type::Scalar(scalar) => scalar,
+        _ => return None
+    };
+
+    return match &literal.token().token_type
+    {
+        SyntaxTokenType::TrueKeyword => Some(ConstantValue::Bool(true)),
+        SyntaxTokenType::FalseKeyword => Some(ConstantValue::Bool(false)),
+        SyntaxTokenType::IntegerLiteralToken{ value } => Some(ConstantValue::Int(wrap(*value as i64, scalar), scalar)),
+        SyntaxTokenType::FloatLiteralToken{ value } => Some(ConstantValue::Float(round_to(*value, scalar), scalar)),
+        _ => None
+    };
+}
+
+// The scalar type of a cast or constructor target, float1 and float1x1 behave like float
+fn scalar_target(ty: &Type) -> Option<ScalarType>
+{
+    return match ty
+    {
+        Type::Scalar(scalar) | Type::Vector(scalar, 1) | Type::Matrix(scalar, 1, 1) => Some(*scalar),
+        _ => None
+    };
+}
+
+// ConstantFolder
+// Folds scalar expressions of literals, operators, casts and constants to their value. Integer operations wrap at the
+// width of their type, float operations are rounded to the precision of their type
+pub struct ConstantFolder<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    resolver: TypeResolver<'t, 'a>,
+    // Values of the constants folded so far, None when the initializer is not constant
+    symbols: HashMap<SymbolId, Option<ConstantValue>>,
+    // Constants whose initializer is being folded, guards against constants defined in terms of themselves
+    folding: Vec<SymbolId>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> ConstantFolder<'t, 'a>
+{
+    pub fn new(table: &'t SymbolTable<'a>) -> Self
+    {
+        return Self{
+            table: table,
+            resolver: TypeResolver::new(table),
+            symbols: HashMap::new(),
+            folding: Vec::new(),
+            diagnostics: Vec::new()
+        };
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    // The value of a static const global or member, or of a const local, None for everything else
+    pub fn symbol_value(&mut self, symbol: SymbolId) -> Option<ConstantValue>
+    {
+        if let Some(value) = self.symbols.get(&symbol)
+        {
+            return *value;
+        }
+        if self.folding.contains(&symbol)
+        {
+            return None;
+        }
+
+        let entry = self.table.get_symbol(symbol);
+        if !matches!(entry.kind, SymbolKind::GlobalVariable | SymbolKind::Field | SymbolKind::LocalVariable)
+        {
+            return None;
+        }
+        let variable = entry.parent.and_then(VariableDecl::cast)?;
+        let declarator = VariableDeclarator::cast(entry.declaration)?;
+
+        // Globals and members without static are uniforms, their value is set by the application
+        let modifiers = TypeModifiers::from_tokens(variable.modifiers());
+        if !modifiers.is_const || (entry.kind != SymbolKind::LocalVariable && !modifiers.is_static) || declarator.array_ranks().next().is_some()
+        {
+            return None;
+        }
+
+        let scalar = variable.var_type().and_then(|t| scalar_target(&self.resolver.resolve(&t)))?;
+        self.folding.push(symbol);
+        let value = declarator.initializer().and_then(|i| self.fold(&i)).map(|v| v.convert(scalar));
+        self.folding.pop();
+        self.symbols.insert(symbol, value);
+        return value;
+    }
+
+    pub fn fold(&mut self, expression: &Expression<'a>) -> Option<ConstantValue>
+    {
+        return match expression
+        {
+            Expression::Literal(literal) => literal_value(literal),
+            Expression::Parenthesized(parenthesized) => self.fold(&parenthesized.expression()?),
+            Expression::Identifier(_) | Expression::Qualified(_) => {
+                let symbol = self.table.symbol_of(expression.syntax())?;
+                self.symbol_value(symbol)
+            },
+            Expression::Cast(cast) => {
+                if cast.array_ranks().next().is_some()
+                {
+                    return None;
+                }
+                let target = scalar_target(&self.resolver.resolve(&cast.target_type()?))?;
+                Some(self.fold(&cast.operand()?)?.convert(target))
+            },
+            // Scalar constructors like uint(3.5)
+            Expression::Call(call) => {
+                let target = match call.callee()?
+                {
+                    Expression::Type(ty) => scalar_target(&self.resolver.resolve(&ty))?,
+                    _ => return None
+                };
+                match call.arguments().as_slice()
+                {
+                    [argument] => Some(self.fold(argument)?.convert(target)),
+                    _ => None
+                }
+            },
+            Expression::PrefixUnary(unary) => {
+                let operand = self.fold(&unary.operand()?)?;
+                fold_unary(&unary.operator().text, operand)
+            },
+            Expression::Binary(binary) => {
+                let left = self.fold(&binary.left()?)?;
+                let right = self.fold(&binary.right()?)?;
+                self.fold_binary(&binary.operator().text, left, right, binary.syntax())
+            },
+            Expression::Conditional(conditional) => {
+                let condition = self.fold(&conditional.condition()?)?;
+                let when_true = self.fold(&conditional.when_true()?)?;
+                let when_false = self.fold(&conditional.when_false()?)?;
+                let scalar = promote_scalars(when_true.scalar_type(), when_false.scalar_type());
+                let chosen = if condition.as_bool() { when_true } else { when_false };
+                Some(if scalar == ScalarType::Bool { chosen } else { chosen.convert(scalar) })
+            },
+            _ => None
+        };
+    }
+
+    fn fold_binary(&mut self, operator: &str, left: ConstantValue, right: ConstantValue, node: &SyntaxNode) -> Option<ConstantValue>
+    {
+        match operator
+        {
+            "&&" => return Some(ConstantValue::Bool(left.as_bool() && right.as_bool())),
+            "||" => return Some(ConstantValue::Bool(left.as_bool() || right.as_bool())),
+            _ => {}
+        }
+
+        // Shifts keep the type of the left operand and only use the low bits of the shift amount
+        if operator == "<<" || operator == ">>"
+        {
+            let scalar = match left.scalar_type()
+            {
+                ScalarType::Bool => ScalarType::Int,
+                scalar => scalar
+            };
+            if !scalar.is_integer() || !right.scalar_type().is_integer()
+            {
+                return None;
+            }
+
+            let bits = if scalar == ScalarType::LiteralInt { 64 } else { scalar.bit_width() };
+            let amount = (right.as_i64() as u32) & (bits - 1);
+            let value = left.convert(scalar).as_i64();
+            let shifted = match (operator, scalar)
+            {
+                ("<<", _) => value.wrapping_shl(amount),
+                (_, ScalarType::Uint64) => ((value as u64) >> amount) as i64,
+                _ => value >> amount
+            };
+            return Some(ConstantValue::Int(wrap(shifted, scalar), scalar));
+        }
+
+        let scalar = promote_scalars(left.scalar_type(), right.scalar_type());
+        let (left, right) = (left.convert(scalar), right.convert(scalar));
+        if scalar.is_float()
+        {
+            let (a, b) = (left.as_f64(), right.as_f64());
+            let value = match operator
+            {
+                "+" => a + b,
+                "-" => a - b,
+                "*" => a * b,
+                "/" => a / b,
+                "%" => a % b,
+                _ => return compare(operator, a.partial_cmp(&b))
+            };
+            return Some(ConstantValue::Float(round_to(value, scalar), scalar));
+        }
+
+        let unsigned = scalar == ScalarType::Uint64;
+        let (a, b) = (left.as_i64(), right.as_i64());
+        if (operator == "/" || operator == "%") && b == 0
+        {
+            let message = if operator == "/" { "division by zero is undefined" } else { "remainder by zero is undefined" };
+            self.diagnostics.push(Diagnostic::warning(String::from(message), node.span()).in_file(node.file_index()));
+            return None;
+        }
+
+        let value = match operator
+        {
+            "+" => a.wrapping_add(b),
+            "-" => a.wrapping_sub(b),
+            "*" => a.wrapping_mul(b),
+            "/" if unsigned => ((a as u64) / (b as u64)) as i64,
+            "/" => a.wrapping_div(b),
+            "%" if unsigned => ((a as u64) % (b as u64)) as i64,
+            "%" => a.wrapping_rem(b),
+            "&" => a & b,
+            "|" => a | b,
+            "^" => a ^ b,
+            _ if unsigned => return compare(operator, Some((a as u64).cmp(&(b as u64)))),
+            _ => return compare(operator, Some(a.cmp(&b)))
+        };
+        return Some(ConstantValue::Int(wrap(value, scalar), scalar));
+    }
+}
+
+fn fold_unary(operator: &str, operand: ConstantValue) -> Option<ConstantValue>
+{
+    if operator == "!"
+    {
+        return Some(ConstantValue::Bool(!operand.as_bool()));
+    }
+
+    let scalar = match operand.scalar_type()
+    {
+        ScalarType::Bool => ScalarType::Int,
+        scalar => scalar
+    };
+    return match (operator, operand.convert(scalar))
+    {
+        ("+", value) => Some(value),
+        ("-", ConstantValue::Float(value, _)) => Some(ConstantValue::Float(-value, scalar)),
+        ("-", ConstantValue::Int(value, _)) => Some(ConstantValue::Int(wrap(value.wrapping_neg(), scalar), scalar)),
+        ("~", ConstantValue::Int(value, _)) => Some(ConstantValue::Int(wrap(!value, scalar), scalar)),
+        _ => None
+    };
+}
+
+fn compare(operator: &str, ordering: Option<std::cmp::Ordering>) -> Option<ConstantValue>
+{
+    use std::cmp::Ordering::*;
+    let result = match (operator, ordering)
+    {
+        ("==", ordering) => ordering == Some(Equal),
+        ("!=", ordering) => ordering != Some(Equal),
+        ("<", ordering) => ordering == Some(Less),
+        ("<=", ordering) => matches!(ordering, Some(Less | Equal)),
+        (">", ordering) => ordering == Some(Greater),
+        (">=", ordering) => matches!(ordering, Some(Greater | Equal)),
+        _ => return None
+    };
+    return Some(ConstantValue::Bool(result));
+}
+
+// Attributes whose arguments must be integer constants
+const INTEGER_ATTRIBUTES: &[&str] = &["numthreads", "unroll", "maxvertexcount", "outputcontrolpoints"];
+
+// ConstantEvaluator
+// Folds the constants, array sizes and attribute arguments of a compilation unit so later passes and reflection can read
+// their values, like the thread group size of [numthreads(TILE_SIZE * 2, 1, 1)]
+pub struct ConstantEvaluator<'t, 'a>
+{
+    folder: ConstantFolder<'t, 'a>,
+    // Folded array sizes and attribute arguments keyed by node address
+    values: HashMap<usize, ConstantValue>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> ConstantEvaluator<'t, 'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>) -> Self
+    {
+        let mut evaluator = Self{
+            folder: ConstantFolder::new(table),
+            values: HashMap::new(),
+            diagnostics: Vec::new()
+        };
+
+        for symbol in 0..table.get_symbols().len()
+        {
+            evaluator.folder.symbol_value(symbol);
+        }
+
+        for node in unit.syntax().descendant_nodes()
+        {
+            if let Some(rank) = ArrayRank::cast(node)
+            {
+                evaluator.evaluate_array_size(rank);
+            }
+            else if let Some(attribute) = Attribute::cast(node)
+            {
+                evaluator.evaluate_attribute(attribute);
+            }
+        }
+
+        evaluator.diagnostics.splice(0..0, evaluator.folder.diagnostics.drain(..));
+        return evaluator;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    // The value of a static const global or member or a const local
+    pub fn symbol_value(&self, symbol: SymbolId) -> Option<ConstantValue>
+    {
+        return self.folder.symbols.get(&symbol).copied().flatten();
+    }
+
+    // The value of an array size or attribute argument
+    pub fn value_of(&self, node: &SyntaxNode) -> Option<ConstantValue>
+    {
+        return self.values.get(&address(node)).copied();
+    }
+
+    pub fn array_size(&self, rank: ArrayRank<'a>) -> Option<u32>
+    {
+        return self.value_of(rank.size()?.syntax())?.as_u32();
+    }
+
+    // The integer arguments of an attribute, None when one of them is not constant
+    pub fn attribute_values(&self, attribute: Attribute<'a>) -> Option<Vec<u32>>
+    {
+        return attribute.arguments().iter().map(|a| self.value_of(a.syntax()).and_then(|v| v.as_u32())).collect();
+    }
+
+    // The thread group size of a compute, mesh or amplification shader
+    pub fn numthreads(&self, function: FunctionDecl<'a>) -> Option<[u32; 3]>
+    {
+        let attribute = function.attributes().find(|a| attribute_name(a) == "numthreads")?;
+        return match self.attribute_values(attribute)?.as_slice()
+        {
+            [x, y, z] => Some([*x, *y, *z]),
+            _ => None
+        };
+    }
+
+    // The iteration count of [unroll(N)], None for [unroll] without a count
+    pub fn unroll_count(&self, statement: Statement<'a>) -> Option<u32>
+    {
+        let attribute = statement.attributes().find(|a| attribute_name(a) == "unroll")?;
+        return self.attribute_values(attribute)?.first().copied();
+    }
+
+    // The register class and index of register(t3), register(s1[2]) is register s3
+    pub fn register_index(&self, location: RegisterLocation<'a>) -> Option<(char, u32)>
+    {
+        let register = location.register()?;
+        let class = register.text.chars().next()?.to_ascii_lowercase();
+        let mut index = match &register.text[1..]
+        {
+            "" => 0,
+            digits => digits.parse::<u32>().ok()?
+        };
+
+        let arguments = location.arguments();
+        let position = arguments.iter().position(|t| std::ptr::eq(*t, register))?;
+        if arguments.get(position + 1).map(|t| t.token_type == SyntaxTokenType::OpenBracketToken).unwrap_or(false)
+        {
+            match arguments.get(position + 2).map(|t| &t.token_type)
+            {
+                Some(SyntaxTokenType::IntegerLiteralToken{ value }) => index = index.checked_add(u32::try_from(*value).ok()?)?,
+                _ => return None
+            }
+        }
+        return Some((class, index));
+    }
+
+    // The register space of register(t0, space1), space0 when it is not written
+    pub fn register_space(&self, location: RegisterLocation<'a>) -> Option<u32>
+    {
+        return match location.space()
+        {
+            Some(space) => space.text["space".len()..].parse().ok(),
+            None => Some(0)
+        };
+    }
+
+    fn evaluate_array_size(&mut self, rank: ArrayRank<'a>)
+    {
+        let size = match rank.size()
+        {
+            Some(size) if !size.is_missing() => size,
+            _ => return
+        };
+
+        match self.folder.fold(&size)
+        {
+            Some(value) => {
+                if value.as_i64() < 0 && value.scalar_type().is_signed()
+                {
+                    self.error(String::from("array size is negative"), size.syntax());
+                }
+                self.values.insert(address(size.syntax()), value);
+            },
+            None => {
+                if !has_unbound_names(&size, self.folder.table)
+                {
+                    self.error(String::from("variable length arrays are not supported in HLSL"), size.syntax());
+                }
+            }
+        }
+    }
+
+    fn evaluate_attribute(&mut self, attribute: Attribute<'a>)
+    {
+        let name = attribute_name(&attribute);
+        for argument in attribute.arguments()
+        {
+            match self.folder.fold(&argument)
+            {
+                Some(value) => {
+                    self.values.insert(address(argument.syntax()), value);
+                },
+                None => {
+                    if INTEGER_ATTRIBUTES.contains(&name.as_str()) && !has_unbound_names(&argument, self.folder.table)
+                    {
+                        self.error(format!("'{}' attribute requires an integer constant", name), argument.syntax());
+                    }
+                }
+            }
+        }
+    }
+
+    fn error(&mut self, message: String, node: &SyntaxNode)
+    {
+        self.diagnostics.push(Diagnostic::error(message, node.span()).in_file(node.file_index()));
+    }
+}
+
+// Names the binder could not resolve were reported already, like macros that were never defined
+fn has_unbound_names(expression: &Expression, table: &SymbolTable) -> bool
+{
+    let nodes = expression.syntax().descendant_nodes();
+    let parts: Vec<usize> = nodes.iter().filter(|n| n.node_type == SyntaxNodeType::QualifiedName)
+        .flat_map(|n| n.descendant_nodes().into_iter().skip(1)).map(address).collect();
+    return nodes.iter().filter(|n| matches!(n.node_type, SyntaxNodeType::IdentifierName | SyntaxNodeType::QualifiedName))
+        .any(|n| !parts.contains(&address(n)) && table.symbol_of(n).is_none());
+}
+
+// Attribute names are not case sensitive, [NumThreads] is [numthreads]
+pub(crate) fn attribute_name(attribute: &Attribute) -> String
+{
+    return attribute.name().map(|n| n.text().to_ascii_lowercase()).unwrap_or_default();
+}
@@ -0,0 +1,340 @@
+use std::collections::VecDeque;
+
+use crate::parser::{ast::*, syntax_tokens::SyntaxTokenType};
+
+pub type BlockId = usize;
+
+// Something a basic block evaluates, in the order they run
+#[derive(Clone, Copy, Debug)]
+pub enum FlowItem<'a>
+{
+    // Conditions, expression statements, return values, loop incrementors and switch values
+    Expression(Expression<'a>),
+    // Local variables, the declarators are initialized in order
+    Declaration(VariableDecl<'a>),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BasicBlock<'a>
+{
+    pub items: Vec<FlowItem<'a>>,
+    pub successors: Vec<BlockId>,
+    // The block ends with a return statement
+    pub returns: bool,
+}
+
+// Where break and continue jump to
+struct JumpTargets
+{
+    break_target: BlockId,
+    continue_target: Option<BlockId>,
+}
+
+// ControlFlowGraph
+// The basic blocks of a function body and the jumps between them. Returns and falling off the end of the body go to the exit
+// block, code after a return, break or continue ends up in blocks that cannot be reached from the entry
+pub struct ControlFlowGraph<'a>
+{
+    blocks: Vec<BasicBlock<'a>>,
+    entry: BlockId,
+    exit: BlockId,
+    // The block that runs when control reaches the closing brace of the body
+    end: BlockId,
+    reachable: Vec<bool>,
+    jumps: Vec<JumpTargets>,
+}
+
+impl<'a> ControlFlowGraph<'a>
+{
+    // None for functions that are only declared
+    pub fn new(function: FunctionDecl<'a>) -> Option<Self>
+    {
+        let body = function.body()?;
+        let mut graph = Self{
+            blocks: Vec::new(),
+            entry: 0,
+            exit: 0,
+            end: 0,
+            reachable: Vec::new(),
+            jumps: Vec::new()
+        };
+        graph.entry = graph.add_block();
+        graph.exit = graph.add_block();
+
+        let end = graph.statement(Statement::Block(body), graph.entry);
+        graph.connect(end, graph.exit);
+        graph.end = end;
+        graph.find_reachable();
+        return Some(graph);
+    }
+
+    pub fn get_blocks(&self) -> &Vec<BasicBlock<'a>>
+    {
+        return &self.blocks;
+    }
+
+    pub fn entry(&self) -> BlockId
+    {
+        return self.entry;
+    }
+
+    pub fn exit(&self) -> BlockId
+    {
+        return self.exit;
+    }
+
+    pub fn end(&self) -> BlockId
+    {
+        return self.end;
+    }
+
+    pub fn is_reachable(&self, block: BlockId) -> bool
+    {
+        return self.reachable[block];
+    }
+
+    pub fn predecessors(&self, block: BlockId) -> Vec<BlockId>
+    {
+        return (0..self.blocks.len()).filter(|b| self.blocks[*b].successors.contains(&block)).collect();
+    }
+
+    // Whether control can reach the closing brace of the body without returning
+    pub fn falls_through(&self) -> bool
+    {
+        return self.reachable[self.end];
+    }
+
+    // Whether a return statement can be reached
+    pub fn has_reachable_return(&self) -> bool
+    {
+        return self.blocks.iter().enumerate().any(|(index, block)| block.returns && self.reachable[index]);
+    }
+
+    fn add_block(&mut self) -> BlockId
+    {
+        self.blocks.push(BasicBlock::default());
+        return self.blocks.len() - 1;
+    }
+
+    fn connect(&mut self, from: BlockId, to: BlockId)
+    {
+        if !self.blocks[from].successors.contains(&to)
+        {
+            self.blocks[from].successors.push(to);
+        }
+    }
+
+    fn add_expression(&mut self, block: BlockId, expression: Option<Expression<'a>>)
+    {
+        if let Some(expression) = expression
+        {
+            self.blocks[block].items.push(FlowItem::Expression(expression));
+        }
+    }
+
+    // Adds the statement to the block and returns the block that runs after it
+    fn statement(&mut self, statement: Statement<'a>, block: BlockId) -> BlockId
+    {
+        return match statement
+        {
+            Statement::Block(statements) => {
+                let mut current = block;
+                for statement in statements.statements()
+                {
+                    current = self.statement(statement, current);
+                }
+                current
+            },
+            Statement::Expression(expression) => {
+                self.add_expression(block, expression.expression());
+                block
+            },
+            Statement::Variable(declaration) => {
+                self.blocks[block].items.push(FlowItem::Declaration(declaration));
+                block
+            },
+            Statement::Return(statement) => {
+                self.add_expression(block, statement.value());
+                self.blocks[block].returns = true;
+                self.connect(block, self.exit);
+                self.add_block()
+            },
+            Statement::If(statement) => {
+                self.add_expression(block, statement.condition());
+                let after = self.add_block();
+                let then_block = self.add_block();
+                self.connect(block, then_block);
+                if let Some(then_statement) = statement.then_statement()
+                {
+                    let then_end = self.statement(then_statement, then_block);
+                    self.connect(then_end, after);
+                }
+                else
+                {
+                    self.connect(then_block, after);
+                }
+                match statement.else_statement()
+                {
+                    Some(else_statement) => {
+                        let else_block = self.add_block();
+                        self.connect(block, else_block);
+                        let else_end = self.statement(else_statement, else_block);
+                        self.connect(else_end, after);
+                    },
+                    None => self.connect(block, after)
+                }
+                after
+            },
+            Statement::While(statement) => {
+                let condition = self.add_block();
+                self.connect(block, condition);
+                self.add_expression(condition, statement.condition());
+                self.looping(condition, !is_always_true(statement.condition()), statement.body(), condition)
+            },
+            Statement::Do(statement) => {
+                let body = self.add_block();
+                let condition = self.add_block();
+                let after = self.add_block();
+                self.connect(block, body);
+                self.jumps.push(JumpTargets{ break_target: after, continue_target: Some(condition) });
+                let body_end = match statement.body()
+                {
+                    Some(statement) => self.statement(statement, body),
+                    None => body
+                };
+                self.jumps.pop();
+                self.connect(body_end, condition);
+                self.add_expression(condition, statement.condition());
+                self.connect(condition, body);
+                if !is_always_true(statement.condition())
+                {
+                    self.connect(condition, after);
+                }
+                after
+            },
+            Statement::For(statement) => {
+                let current = match statement.initializer()
+                {
+                    Some(initializer) => self.statement(initializer, block),
+                    None => block
+                };
+                let condition = self.add_block();
+                self.connect(current, condition);
+                self.add_expression(condition, statement.condition());
+                let incrementor = self.add_block();
+                self.add_expression(incrementor, statement.incrementor());
+                self.connect(incrementor, condition);
+                // 'for (;;)' loops until it breaks
+                let ends = statement.condition().is_some() && !is_always_true(statement.condition());
+                self.looping(condition, ends, statement.body(), incrementor)
+            },
+            Statement::Switch(statement) => {
+                self.add_expression(block, statement.expression());
+                let after = self.add_block();
+                self.jumps.push(JumpTargets{ break_target: after, continue_target: None });
+                let mut has_default = false;
+                let mut previous_end: Option<BlockId> = None;
+                for section in statement.sections()
+                {
+                    has_default |= section.labels().any(|l| l.is_default());
+                    let section_block = self.add_block();
+                    self.connect(block, section_block);
+                    if let Some(previous_end) = previous_end
+                    {
+                        self.connect(previous_end, section_block);
+                    }
+                    let mut current = section_block;
+                    for statement in section.statements()
+                    {
+                        current = self.statement(statement, current);
+                    }
+                    previous_end = Some(current);
+                }
+                self.jumps.pop();
+                if let Some(previous_end) = previous_end
+                {
+                    self.connect(previous_end, after);
+                }
+                if !has_default
+                {
+                    self.connect(block, after);
+                }
+                after
+            },
+            Statement::Break(_) => {
+                if let Some(target) = self.jumps.last().map(|j| j.break_target)
+                {
+                    self.connect(block, target);
+                }
+                self.add_block()
+            },
+            Statement::Continue(_) => {
+                if let Some(target) = self.jumps.iter().rev().find_map(|j| j.continue_target)
+                {
+                    self.connect(block, target);
+                }
+                self.add_block()
+            },
+            // Discard turns the invocation into a helper lane that keeps running
+            Statement::Discard(_) | Statement::Empty(_) | Statement::Typedef(_) | Statement::Type(_) | Statement::Skipped(_) => block
+        };
+    }
+
+    // The body of a while or for loop runs while the condition holds, continue jumps to the next iteration
+    fn looping(&mut self, condition: BlockId, ends: bool, body: Option<Statement<'a>>, next: BlockId) -> BlockId
+    {
+        let body_block = self.add_block();
+        let after = self.add_block();
+        self.connect(condition, body_block);
+        if ends
+        {
+            self.connect(condition, after);
+        }
+        self.jumps.push(JumpTargets{ break_target: after, continue_target: Some(next) });
+        let body_end = match body
+        {
+            Some(statement) => self.statement(statement, body_block),
+            None => body_block
+        };
+        self.jumps.pop();
+        self.connect(body_end, next);
+        return after;
+    }
+
+    fn find_reachable(&mut self)
+    {
+        self.reachable = vec![false; self.blocks.len()];
+        self.reachable[self.entry] = true;
+        let mut queue = VecDeque::from([self.entry]);
+        while let Some(block) = queue.pop_front()
+        {
+            for successor in self.blocks[block].successors.clone()
+            {
+                if !self.reachable[successor]
+                {
+                    self.reachable[successor] = true;
+                    queue.push_back(successor);
+                }
+            }
+        }
+    }
+}
+
+// Conditions like 'while (true)' or 'while (1)' never end the loop
+fn is_always_true(condition: Option<Expression>) -> bool
+{
+    return match condition
+    {
+        Some(Expression::Literal(literal)) => {
+            let token = literal.token();
+            match token.token_type
+            {
+                SyntaxTokenType::TrueKeyword => true,
+                SyntaxTokenType::IntegerLiteralToken{ value } => value != 0,
+                _ => false
+            }
+        },
+        Some(Expression::Parenthesized(parenthesized)) => is_always_true(parenthesized.expression()),
+        _ => false
+    };
+}
@@ -0,0 +1,413 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::SyntaxNode, syntax_tokens::{SyntaxToken, SyntaxTokenType}, ParserSettings};
+
+use super::{call_graph::function_body, control_flow::*, symbol_table::{SymbolId, SymbolKind, SymbolTable}, type_checker::TypeChecker, types::*};
+
+// The variables that are assigned when control reaches a point of the function
+#[derive(Clone, PartialEq, Debug)]
+struct Assigned
+{
+    // Assigned on every path
+    definitely: Vec<bool>,
+    // Assigned on at least one path
+    possibly: Vec<bool>,
+}
+
+impl Assigned
+{
+    fn new(count: usize) -> Self
+    {
+        return Self{
+            definitely: vec![false; count],
+            possibly: vec![false; count]
+        };
+    }
+
+    fn join(&mut self, other: &Assigned)
+    {
+        for index in 0..self.definitely.len()
+        {
+            self.definitely[index] &= other.definitely[index];
+            self.possibly[index] |= other.possibly[index];
+        }
+    }
+
+    fn set(&mut self, variable: usize, assigned: bool)
+    {
+        self.definitely[variable] = assigned;
+        self.possibly[variable] = assigned;
+    }
+}
+
+// Walks the items of the basic blocks, tracking which variables are assigned
+struct Flow<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    checker: &'t TypeChecker<'t, 'a>,
+    variables: HashMap<SymbolId, usize>,
+    // Only the last pass over the blocks records the reads of unassigned variables, with whether they are assigned on some path
+    recording: bool,
+    reads: Vec<(SymbolId, &'a SyntaxNode, bool)>,
+}
+
+impl<'t, 'a> Flow<'t, 'a>
+{
+    fn item(&mut self, item: FlowItem<'a>, state: &mut Assigned)
+    {
+        match item
+        {
+            FlowItem::Expression(expression) => self.read(expression, state),
+            FlowItem::Declaration(declaration) => {
+                for declarator in declaration.declarators()
+                {
+                    if let Some(initializer) = declarator.initializer()
+                    {
+                        self.read(initializer, state);
+                    }
+                    // A declaration inside a loop starts out unassigned on every iteration
+                    if let Some(variable) = self.table.declared_by(declarator.syntax()).and_then(|s| self.variables.get(&s))
+                    {
+                        state.set(*variable, declarator.initializer().is_some());
+                    }
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, expression: Expression<'a>, state: &mut Assigned)
+    {
+        match expression
+        {
+            Expression::Identifier(identifier) => {
+                let symbol = match self.table.symbol_of(identifier.syntax())
+                {
+                    Some(symbol) => symbol,
+                    None => return
+                };
+                if let Some(variable) = self.variables.get(&symbol)
+                {
+                    if self.recording && !state.definitely[*variable]
+                    {
+                        self.reads.push((symbol, identifier.syntax(), state.possibly[*variable]));
+                    }
+                }
+            },
+            Expression::Assignment(assignment) => {
+                let compound = assignment.operator().token_type != SyntaxTokenType::EqualsToken;
+                if let Some(right) = assignment.right()
+                {
+                    self.read(right, state);
+                }
+                if let Some(left) = assignment.left()
+                {
+                    self.write(left, compound, state);
+                }
+            },
+            Expression::PrefixUnary(unary) if is_increment(unary.operator()) => {
+                if let Some(operand) = unary.operand()
+                {
+                    self.write(operand, true, state);
+                }
+            },
+            Expression::PostfixUnary(unary) if is_increment(unary.operator()) => {
+                if let Some(operand) = unary.operand()
+                {
+                    self.write(operand, true, state);
+                }
+            },
+            Expression::Call(call) => self.call(call, state),
+            _ => {
+                for child in expression.syntax().child_nodes()
+                {
+                    if let Some(child) = Expression::cast(child)
+                    {
+                        self.read(child, state);
+                    }
+                }
+            }
+        }
+    }
+
+    // Writing a field, component or element of a variable counts as assigning all of it
+    fn write(&mut self, target: Expression<'a>, reads_first: bool, state: &mut Assigned)
+    {
+        match target
+        {
+            Expression::Identifier(identifier) => {
+                if reads_first
+                {
+                    self.read(target, state);
+                }
+                if let Some(variable) = self.table.symbol_of(identifier.syntax()).and_then(|s| self.variables.get(&s))
+                {
+                    state.set(*variable, true);
+                }
+            },
+            Expression::MemberAccess(access) => {
+                if let Some(object) = access.object()
+                {
+                    self.write(object, reads_first, state);
+                }
+            },
+            Expression::ElementAccess(access) => {
+                if let Some(index) = access.index()
+                {
+                    self.read(index, state);
+                }
+                if let Some(object) = access.object()
+                {
+                    self.write(object, reads_first, state);
+                }
+            },
+            Expression::Parenthesized(parenthesized) => {
+                if let Some(expression) = parenthesized.expression()
+                {
+                    self.write(expression, reads_first, state);
+                }
+            },
+            // Legacy effects assign to constructors, like 'float4(size, uv) = ddx(value)'
+            Expression::Call(call) if call.callee().is_some_and(|c| matches!(c, Expression::Type(_))) => {
+                for argument in call.arguments()
+                {
+                    self.write(argument, reads_first, state);
+                }
+            },
+            _ => self.read(target, state)
+        }
+    }
+
+    // Out arguments are assigned by the call, inout arguments are read first
+    fn call(&mut self, call: CallExpr<'a>, state: &mut Assigned)
+    {
+        let directions: Vec<ParameterDirection> = match (self.checker.call_target(call.syntax()), self.checker.intrinsic_of(call.syntax()))
+        {
+            (Some(target), _) => FunctionDecl::cast(self.table.get_symbol(target).declaration).map(|f| f.params()).unwrap_or_default().iter()
+                .map(|p| TypeModifiers::from_tokens(p.modifiers()).parameter_direction()).collect(),
+            (None, Some(intrinsic)) => intrinsic.overload.parameters.iter().map(|p| p.direction).collect(),
+            (None, None) => Vec::new()
+        };
+        if let Some(Expression::MemberAccess(access)) = call.callee()
+        {
+            if let Some(object) = access.object()
+            {
+                self.read(object, state);
+            }
+        }
+        for (index, argument) in call.arguments().into_iter().enumerate()
+        {
+            match directions.get(index)
+            {
+                Some(ParameterDirection::Out) => self.write(argument, false, state),
+                Some(ParameterDirection::InOut) => self.write(argument, true, state),
+                _ => self.read(argument, state)
+            }
+        }
+    }
+}
+
+fn is_increment(operator: &SyntaxToken) -> bool
+{
+    return matches!(operator.token_type, SyntaxTokenType::PlusPlusToken | SyntaxTokenType::MinusMinusToken);
+}
+
+// DefiniteAssignment
+// Follows the control flow graph of every function body to find the local variables that are read before they are assigned,
+// the out parameters that are not assigned on every path to a return and the non-void functions that can end without a
+// return. HLSL compiles all of these without complaint and leaves the values undefined
+pub struct DefiniteAssignment
+{
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DefiniteAssignment
+{
+    pub fn new<'a>(unit: CompilationUnit<'a>, table: &SymbolTable<'a>, settings: &ParserSettings) -> Self
+    {
+        let checker = TypeChecker::new(unit, table, settings);
+        let resolver = TypeResolver::new(table);
+        let mut analysis = Self{
+            diagnostics: Vec::new()
+        };
+        for symbol in 0..table.get_symbols().len()
+        {
+            let function = match function_body(table, symbol)
+            {
+                Some(function) => function,
+                None => continue
+            };
+            if let Some(graph) = ControlFlowGraph::new(function)
+            {
+                analysis.check_function(table, &checker, &resolver, function, &graph);
+            }
+        }
+        return analysis;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    fn check_function<'a>(&mut self, table: &SymbolTable<'a>, checker: &TypeChecker<'_, 'a>, resolver: &TypeResolver<'_, 'a>, function: FunctionDecl<'a>,
+        graph: &ControlFlowGraph<'a>)
+    {
+        // Out parameters start out unassigned, like locals declared without an initializer. Mesh shader outputs are
+        // written element by element after SetMeshOutputCounts
+        let mut variables = Vec::new();
+        let mut out_parameters = Vec::new();
+        for parameter in function.params()
+        {
+            let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+            if modifiers.direction == Some(ParameterDirection::Out) && modifiers.primitive.is_none()
+            {
+                if let Some(symbol) = table.declared_by(parameter.syntax())
+                {
+                    out_parameters.push(variables.len());
+                    variables.push(symbol);
+                }
+            }
+        }
+        // Static locals are zero initialized and arrays are usually filled element by element in a loop
+        for declaration in function.body().unwrap().syntax().descendant_nodes().into_iter().filter_map(VariableDecl::cast)
+        {
+            if TypeModifiers::from_tokens(declaration.modifiers()).is_static
+            {
+                continue;
+            }
+            for declarator in declaration.declarators()
+            {
+                let symbol = match table.declared_by(declarator.syntax())
+                {
+                    Some(symbol) if table.get_symbol(symbol).kind == SymbolKind::LocalVariable => symbol,
+                    _ => continue
+                };
+                if declarator.initializer().is_none() && declarator.array_ranks().next().is_none()
+                {
+                    variables.push(symbol);
+                }
+            }
+        }
+
+        let mut flow = Flow{
+            table: table,
+            checker: checker,
+            variables: variables.iter().enumerate().map(|(index, symbol)| (*symbol, index)).collect(),
+            recording: false,
+            reads: Vec::new()
+        };
+        let blocks = graph.get_blocks();
+        let entry_state = Assigned::new(variables.len());
+        let mut outputs: Vec<Option<Assigned>> = vec![None; blocks.len()];
+        // None until one of the predecessors has been walked
+        let input = |outputs: &Vec<Option<Assigned>>, block: BlockId| -> Option<Assigned>
+        {
+            if block == graph.entry()
+            {
+                return Some(entry_state.clone());
+            }
+            let mut state: Option<Assigned> = None;
+            for predecessor in graph.predecessors(block)
+            {
+                if let Some(output) = &outputs[predecessor]
+                {
+                    match &mut state
+                    {
+                        Some(state) => state.join(output),
+                        None => state = Some(output.clone())
+                    }
+                }
+            }
+            return state;
+        };
+
+        // The states only grow more pessimistic, so the loop ends once no block changes
+        let mut changed = true;
+        while changed
+        {
+            changed = false;
+            for block in 0..blocks.len()
+            {
+                let mut state = match input(&outputs, block)
+                {
+                    Some(state) => state,
+                    None => continue
+                };
+                for item in &blocks[block].items
+                {
+                    flow.item(*item, &mut state);
+                }
+                if outputs[block].as_ref() != Some(&state)
+                {
+                    outputs[block] = Some(state);
+                    changed = true;
+                }
+            }
+        }
+
+        flow.recording = true;
+        for (block, contents) in blocks.iter().enumerate()
+        {
+            let mut state = match input(&outputs, block)
+            {
+                Some(state) => state,
+                None => continue
+            };
+            for item in &contents.items
+            {
+                flow.item(*item, &mut state);
+            }
+        }
+
+        let mut reported = HashSet::new();
+        let mut reads = flow.reads;
+        reads.sort_by_key(|(_, node, _)| (node.file_index(), node.span().start));
+        for (symbol, node, possibly) in reads
+        {
+            if !reported.insert(symbol)
+            {
+                continue;
+            }
+            let message = match possibly
+            {
+                true => format!("variable '{}' may be uninitialized when used here", table.get_symbol(symbol).name),
+                false => format!("variable '{}' is uninitialized when used here", table.get_symbol(symbol).name)
+            };
+            self.diagnostics.push(Diagnostic::warning(message, node.span()).in_file(node.file_index()));
+        }
+
+        // Functions that never end, like an infinite loop, have nothing to check at the exit
+        let exit = match input(&outputs, graph.exit())
+        {
+            Some(exit) => exit,
+            None => return
+        };
+        for variable in out_parameters
+        {
+            let parameter = table.get_symbol(variables[variable]);
+            if reported.contains(&variables[variable]) || exit.definitely[variable]
+            {
+                continue;
+            }
+            let message = match exit.possibly[variable]
+            {
+                true => format!("out parameter '{}' is not assigned on all paths", parameter.name),
+                false => format!("out parameter '{}' is never assigned", parameter.name)
+            };
+            self.diagnostics.push(Diagnostic::warning(message, parameter.span()).in_file(parameter.file_index()));
+        }
+
+        let returns_value = function.return_type().map(|t| resolver.resolve(&t)).is_some_and(|t| t != Type::Void);
+        if returns_value && graph.falls_through()
+        {
+            let message = match graph.has_reachable_return()
+            {
+                true => "non-void function does not return a value in all control paths",
+                false => "non-void function does not return a value"
+            };
+            let body = function.body().unwrap();
+            let span = body.syntax().last_token().map(|t| t.span).unwrap_or(body.span());
+            self.diagnostics.push(Diagnostic::warning(String::from(message), span).in_file(body.file_index()));
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::parser::{ast::*, syntax_nodes::SyntaxNodeType, syntax_tokens::SyntaxToken, ParserSettings, ShaderModel, ShaderStage, ShaderTarget};
+
+use super::{constant_evaluator::attribute_name, reflection::string_argument, symbol_table::{SymbolId, SymbolKind, SymbolTable},
+    types::{ParameterDirection, Type, TypeModifiers, TypeResolver}};
+
+// A function a shader can be compiled from and the stage it runs in
+#[derive(Clone, Debug)]
+pub struct EntryPoint<'a>
+{
+    pub name: String,
+    pub symbol: SymbolId,
+    pub function: FunctionDecl<'a>,
+    pub stage: ShaderStage,
+    // The shader model of the profile in CompileShader(vs_5_0, VS()) or of the target profile, None when neither gives one
+    pub shader_model: Option<ShaderModel>,
+}
+
+// The stages of [shader("compute")] attributes
+fn shader_attribute_stages(function: &FunctionDecl) -> Vec<ShaderStage>
+{
+    let mut stages = Vec::new();
+    for attribute in function.attributes().filter(|a| attribute_name(a) == "shader")
+    {
+        stages.extend(string_argument(&attribute).and_then(|name| ShaderStage::from_name(&name)));
+    }
+    return stages;
+}
+
+// The stage implied by the attributes only a single stage accepts, like [maxvertexcount] for geometry shaders
+fn attribute_stage(function: &FunctionDecl) -> Option<ShaderStage>
+{
+    let names: Vec<String> = function.attributes().map(|a| attribute_name(&a)).collect();
+    let has = |name: &str| names.iter().any(|n| n == name);
+    if has("numthreads")
+    {
+        if has("outputtopology")
+        {
+            return Some(ShaderStage::Mesh);
+        }
+        // Amplification shaders launch mesh shaders with DispatchMesh
+        let calls_dispatch_mesh = function.body().map(|body| body.syntax().descendant_nodes().iter()
+            .filter_map(|n| CallExpr::cast(n)).any(|c| c.function_name().map(|n| n.text == "DispatchMesh").unwrap_or(false))).unwrap_or(false);
+        return Some(if calls_dispatch_mesh { ShaderStage::Amplification } else { ShaderStage::Compute });
+    }
+    if has("patchconstantfunc") || has("outputcontrolpoints") || has("outputtopology")
+    {
+        return Some(ShaderStage::Hull);
+    }
+    if has("domain")
+    {
+        return Some(ShaderStage::Domain);
+    }
+    if has("maxvertexcount")
+    {
+        return Some(ShaderStage::Geometry);
+    }
+    if has("earlydepthstencil")
+    {
+        return Some(ShaderStage::Pixel);
+    }
+    return None;
+}
+
+// Adds the semantic of a value, or the semantics of the fields of the structs it is made of, without their index
+fn collect_semantics(table: &SymbolTable, resolver: &TypeResolver, ty: &Type, semantic: Option<&SyntaxToken>, semantics: &mut Vec<String>)
+{
+    if let Some(semantic) = semantic
+    {
+        semantics.push(semantic.text.trim_end_matches(|c: char| c.is_ascii_digit()).to_string());
+    }
+
+    let element = match ty
+    {
+        Type::Array(element, _) => return collect_semantics(table, resolver, element, None, semantics),
+        // Patches and streams hold the elements of their type
+        Type::Resource{ element: Some(element), .. } => return collect_semantics(table, resolver, element, None, semantics),
+        Type::Struct{ symbol, .. } => *symbol,
+        _ => return
+    };
+    let mut scopes: Vec<_> = table.get_symbol(element).members.into_iter().collect();
+    while let Some(scope) = scopes.pop()
+    {
+        let scope = table.get_scope(scope);
+        scopes.extend(scope.bases.iter().copied());
+        for field in &scope.symbols
+        {
+            let entry = table.get_symbol(*field);
+            if entry.kind != SymbolKind::Field
+            {
+                continue;
+            }
+            let declarator = VariableDeclarator::cast(entry.declaration);
+            collect_semantics(table, resolver, &resolver.symbol_type(*field).ty, declarator.and_then(|d| d.semantic()).and_then(|s| s.name()), semantics);
+        }
+    }
+}
+
+// The stage the semantics of the parameters and the return value imply, like a pixel shader for a function returning SV_Target
+fn semantic_stage(table: &SymbolTable, function: &FunctionDecl) -> Option<ShaderStage>
+{
+    let resolver = TypeResolver::new(table);
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for parameter in function.params()
+    {
+        let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+        if modifiers.is_uniform
+        {
+            continue;
+        }
+        let ty = parameter.param_type().map(|t| resolver.resolve(&t)).unwrap_or(Type::Error);
+        let semantic = parameter.semantic().and_then(|s| s.name());
+        let direction = modifiers.parameter_direction();
+        if direction != ParameterDirection::Out
+        {
+            collect_semantics(table, &resolver, &ty, semantic, &mut inputs);
+        }
+        if direction != ParameterDirection::In
+        {
+            collect_semantics(table, &resolver, &ty, semantic, &mut outputs);
+        }
+    }
+    if let Some(return_type) = function.return_type()
+    {
+        collect_semantics(table, &resolver, &resolver.resolve(&return_type), function.semantic().and_then(|s| s.name()), &mut outputs);
+    }
+
+    let has = |semantics: &[String], names: &[&str]| semantics.iter().any(|s| names.iter().any(|n| s.eq_ignore_ascii_case(n)));
+    if has(&inputs, &["SV_DispatchThreadID", "SV_GroupID", "SV_GroupThreadID", "SV_GroupIndex"])
+    {
+        return Some(ShaderStage::Compute);
+    }
+    if has(&outputs, &["SV_Position", "POSITION"])
+    {
+        return Some(ShaderStage::Vertex);
+    }
+    if has(&outputs, &["SV_Target", "SV_Depth", "SV_DepthGreaterEqual", "SV_DepthLessEqual", "SV_Coverage", "SV_StencilRef", "COLOR", "DEPTH"])
+    {
+        return Some(ShaderStage::Pixel);
+    }
+    return None;
+}
+
+// The definition of a function in the global scope with the name
+pub(crate) fn global_function<'a>(table: &SymbolTable<'a>, name: &str) -> Option<(SymbolId, FunctionDecl<'a>)>
+{
+    return table.get_scope(table.global_scope()).get(name).iter().find_map(|s| {
+        let entry = table.get_symbol(*s);
+        if entry.kind != SymbolKind::Function
+        {
+            return None;
+        }
+        let function = FunctionDecl::cast(entry.definition.unwrap_or(entry.declaration))?;
+        return function.body().map(|_| (*s, function));
+    });
+}
+
+// EntryPointFinder
+// Lists the entry points of a compilation unit: functions with a [shader("...")] attribute or stage specific attributes like
+// [numthreads], and the functions effects compile with CompileShader(vs_5_0, VS()) or 'compile ps_2_0 PS()'
+pub struct EntryPointFinder<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    entry_points: Vec<EntryPoint<'a>>,
+}
+
+impl<'t, 'a> EntryPointFinder<'t, 'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>) -> Self
+    {
+        let mut finder = Self{
+            table: table,
+            entry_points: Vec::new()
+        };
+
+        for symbol in &table.get_scope(table.global_scope()).symbols
+        {
+            let entry = table.get_symbol(*symbol);
+            let function = match FunctionDecl::cast(entry.definition.unwrap_or(entry.declaration))
+            {
+                Some(function) if entry.kind == SymbolKind::Function && function.body().is_some() => function,
+                _ => continue
+            };
+
+            let mut stages = shader_attribute_stages(&function);
+            if stages.is_empty()
+            {
+                stages.extend(attribute_stage(&function));
+            }
+            for stage in stages
+            {
+                finder.add(*symbol, function, stage, None);
+            }
+        }
+
+        for node in unit.syntax().descendant_nodes()
+        {
+            if node.node_type != SyntaxNodeType::CompileExpression
+            {
+                continue;
+            }
+            let compile = CompileExpr::cast(node).unwrap();
+            let profile = compile.profile().and_then(|p| ShaderStage::from_profile(&p.text));
+            let function = compile.entry_point().and_then(|name| global_function(table, &name.text));
+            if let (Some((stage, shader_model)), Some((symbol, function))) = (profile, function)
+            {
+                finder.add(symbol, function, stage, Some(shader_model));
+            }
+        }
+        return finder;
+    }
+
+    pub fn get_entry_points(&self) -> &Vec<EntryPoint<'a>>
+    {
+        return &self.entry_points;
+    }
+
+    // The entry point with the name. A target profile decides the stage, without one functions that were not found as entry points
+    // get the stage their semantics imply, like a pixel shader for a function returning SV_Target, None when the stage cannot be told
+    pub fn entry_point(&self, name: &str, settings: &ParserSettings) -> Option<EntryPoint<'a>>
+    {
+        if let Some(ShaderTarget{ stage: Some(stage), shader_model }) = settings.target
+        {
+            if let Some(entry_point) = self.entry_points.iter().find(|e| e.name == name && e.stage == stage)
+            {
+                return Some(entry_point.clone());
+            }
+            let (symbol, function) = global_function(self.table, name)?;
+            return Some(EntryPoint{
+                name: String::from(name),
+                symbol: symbol,
+                function: function,
+                stage: stage,
+                shader_model: Some(shader_model)
+            });
+        }
+
+        if let Some(entry_point) = self.entry_points.iter().find(|e| e.name == name)
+        {
+            return Some(entry_point.clone());
+        }
+
+        let (symbol, function) = global_function(self.table, name)?;
+        let stage = semantic_stage(self.table, &function)?;
+        return Some(EntryPoint{
+            name: String::from(name),
+            symbol: symbol,
+            function: function,
+            stage: stage,
+            shader_model: None
+        });
+    }
+
+    // Functions compiled for several profiles of a stage are listed once
+    fn add(&mut self, symbol: SymbolId, function: FunctionDecl<'a>, stage: ShaderStage, shader_model: Option<ShaderModel>)
+    {
+        if self.entry_points.iter().any(|e| e.symbol == symbol && e.stage == stage)
+        {
+            return;
+        }
+        self.entry_points.push(EntryPoint{
+            name: self.table.get_symbol(symbol).name.clone(),
+            symbol: symbol,
+            function: function,
+            stage: stage,
+            shader_model: shader_model
+        });
+    }
+}
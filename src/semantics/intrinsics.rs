@@ -0,0 +1,947 @@
+use std::{collections::HashMap, fmt, sync::OnceLock};
+
+use crate::parser::{ShaderModel, ShaderStage};
+
+use super::types::*;
+
+// Stages an intrinsic can be used in, an empty list allows every stage
+const ALL: &[ShaderStage] = &[];
+const PIXEL: &[ShaderStage] = &[ShaderStage::Pixel];
+const HULL: &[ShaderStage] = &[ShaderStage::Hull];
+const GEOMETRY: &[ShaderStage] = &[ShaderStage::Geometry];
+const MESH: &[ShaderStage] = &[ShaderStage::Mesh];
+const AMPLIFICATION: &[ShaderStage] = &[ShaderStage::Amplification];
+const GROUP_SYNC: &[ShaderStage] = &[ShaderStage::Compute, ShaderStage::Amplification, ShaderStage::Mesh];
+const TRACE_RAY: &[ShaderStage] = &[ShaderStage::RayGeneration, ShaderStage::ClosestHit, ShaderStage::Miss];
+const CALL_SHADER: &[ShaderStage] = &[ShaderStage::RayGeneration, ShaderStage::ClosestHit, ShaderStage::Miss, ShaderStage::Callable];
+const RAY_TRACING: &[ShaderStage] = &[ShaderStage::RayGeneration, ShaderStage::Intersection, ShaderStage::AnyHit, ShaderStage::ClosestHit,
+    ShaderStage::Miss, ShaderStage::Callable];
+const RAY_HIT: &[ShaderStage] = &[ShaderStage::Intersection, ShaderStage::AnyHit, ShaderStage::ClosestHit, ShaderStage::Miss];
+const PRIMITIVE_HIT: &[ShaderStage] = &[ShaderStage::Intersection, ShaderStage::AnyHit, ShaderStage::ClosestHit];
+const ANY_HIT: &[ShaderStage] = &[ShaderStage::AnyHit];
+const INTERSECTION: &[ShaderStage] = &[ShaderStage::Intersection];
+
+const SM_1_0: ShaderModel = ShaderModel::new(1, 0);
+const SM_4_0: ShaderModel = ShaderModel::new(4, 0);
+const SM_4_1: ShaderModel = ShaderModel::new(4, 1);
+const SM_5_0: ShaderModel = ShaderModel::new(5, 0);
+const SM_6_0: ShaderModel = ShaderModel::new(6, 0);
+const SM_6_3: ShaderModel = ShaderModel::new(6, 3);
+const SM_6_4: ShaderModel = ShaderModel::new(6, 4);
+const SM_6_5: ShaderModel = ShaderModel::new(6, 5);
+const SM_6_6: ShaderModel = ShaderModel::new(6, 6);
+const SM_6_7: ShaderModel = ShaderModel::new(6, 7);
+
+// Intrinsic functions as (signature, shader model, stages).
+// Component classes: float_like, int_like, numeric (no bool) and any unify to one component type per call.
+// Shapes: no suffix is a scalar, <n> a vector, <r,c> a matrix and <*> any shape, letters are sizes shared by the parameters.
+// Several names separated by '|' share the signature
+const FUNCTIONS: &[(&str, ShaderModel, &[ShaderStage])] = &[
+    ("numeric<*> abs(numeric<*> x)", SM_1_0, ALL),
+    ("float_like<*> acos|asin|atan|cos|cosh|sin|sinh|tan|tanh|exp|exp2|log|log10|log2|sqrt|rsqrt|frac|ceil|floor|round|trunc|degrees|radians|saturate(float_like<*> x)", SM_1_0, ALL),
+    ("float_like<*> rcp(float_like<*> x)", SM_5_0, ALL),
+    ("float_like<*> atan2|fmod|pow|step|ldexp(float_like<*> x, float_like<*> y)", SM_1_0, ALL),
+    ("numeric<*> min|max(numeric<*> x, numeric<*> y)", SM_1_0, ALL),
+    ("numeric<*> clamp(numeric<*> x, numeric<*> min, numeric<*> max)", SM_1_0, ALL),
+    ("numeric<*> mad(numeric<*> m, numeric<*> a, numeric<*> b)", SM_1_0, ALL),
+    ("double<*> fma(double<*> a, double<*> b, double<*> c)", SM_5_0, ALL),
+    ("float_like<*> lerp|smoothstep(float_like<*> x, float_like<*> y, float_like<*> s)", SM_1_0, ALL),
+    ("int<*> sign(numeric<*> x)", SM_1_0, ALL),
+    ("bool<*> isfinite|isinf|isnan(float_like<*> x)", SM_1_0, ALL),
+    ("bool all|any(any<*> x)", SM_1_0, ALL),
+    ("float_like<*> frexp(float_like<*> x, out float_like<*> exp)", SM_1_0, ALL),
+    ("numeric<*> modf(numeric<*> x, out numeric<*> ip)", SM_1_0, ALL),
+    ("void sincos(float_like<*> x, out float_like<*> s, out float_like<*> c)", SM_1_0, ALL),
+    ("float_like<*> ddx|ddy|fwidth(float_like<*> x)", SM_1_0, PIXEL),
+    ("float_like<*> ddx_coarse|ddx_fine|ddy_coarse|ddy_fine(float_like<*> x)", SM_5_0, PIXEL),
+    ("void clip(float_like<*> x)", SM_1_0, PIXEL),
+    ("numeric dot(numeric<n> a, numeric<n> b)", SM_1_0, ALL),
+    ("float_like<3> cross(float_like<3> a, float_like<3> b)", SM_1_0, ALL),
+    ("float_like length(float_like<n> x)", SM_1_0, ALL),
+    ("float_like distance(float_like<n> a, float_like<n> b)", SM_1_0, ALL),
+    ("float_like<n> normalize(float_like<n> x)", SM_1_0, ALL),
+    ("float_like<n> reflect(float_like<n> i, float_like<n> n)", SM_1_0, ALL),
+    ("float_like<n> refract(float_like<n> i, float_like<n> n, float_like eta)", SM_1_0, ALL),
+    ("float_like<n> faceforward(float_like<n> n, float_like<n> i, float_like<n> ng)", SM_1_0, ALL),
+    ("float_like determinant(float_like<r,r> m)", SM_1_0, ALL),
+    ("any<c,r> transpose(any<r,c> m)", SM_1_0, ALL),
+    ("float4 lit(float n_dot_l, float n_dot_h, float m)", SM_1_0, ALL),
+    ("float_like<4> dst(float_like<4> a, float_like<4> b)", SM_1_0, ALL),
+    ("float noise(float<n> x)", SM_1_0, ALL),
+    ("int4 D3DCOLORtoUBYTE4(float4 x)", SM_1_0, ALL),
+    // mul is a matrix product, the scalar forms come last so equal costs prefer the vector forms
+    ("numeric mul(numeric<n> a, numeric<n> b)", SM_1_0, ALL),
+    ("numeric<c> mul(numeric<r> a, numeric<r,c> b)", SM_1_0, ALL),
+    ("numeric<r> mul(numeric<r,c> a, numeric<c> b)", SM_1_0, ALL),
+    ("numeric<r,c> mul(numeric<r,k> a, numeric<k,c> b)", SM_1_0, ALL),
+    ("numeric<n> mul(numeric a, numeric<n> b)", SM_1_0, ALL),
+    ("numeric<n> mul(numeric<n> a, numeric b)", SM_1_0, ALL),
+    ("numeric<r,c> mul(numeric a, numeric<r,c> b)", SM_1_0, ALL),
+    ("numeric<r,c> mul(numeric<r,c> a, numeric b)", SM_1_0, ALL),
+    ("numeric mul(numeric a, numeric b)", SM_1_0, ALL),
+    // HLSL 2021 replacements of the non-scalar logical operators
+    ("any<*> select(bool<*> condition, any<*> true_value, any<*> false_value)", SM_1_0, ALL),
+    ("bool<*> and|or(bool<*> a, bool<*> b)", SM_1_0, ALL),
+    // Bit casts and conversions
+    ("float<*> asfloat(numeric<*> x)", SM_4_0, ALL),
+    ("int<*> asint(numeric<*> x)", SM_4_0, ALL),
+    ("uint<*> asuint(numeric<*> x)", SM_4_0, ALL),
+    ("void asuint(double x, out uint low, out uint high)", SM_5_0, ALL),
+    ("double<n> asdouble(uint<n> low, uint<n> high)", SM_5_0, ALL),
+    ("float<*> f16tof32(uint<*> x)", SM_5_0, ALL),
+    ("uint<*> f32tof16(float<*> x)", SM_5_0, ALL),
+    ("uint<*> countbits|reversebits(uint<*> x)", SM_5_0, ALL),
+    ("int_like<*> firstbithigh|firstbitlow(int_like<*> x)", SM_5_0, ALL),
+    ("uint4 msad4(uint reference, uint2 source, uint4 accum)", SM_5_0, ALL),
+    ("uint NonUniformResourceIndex(uint index)", SM_5_0, ALL),
+    // Atomics on groupshared memory and typed UAVs
+    ("void InterlockedAdd|InterlockedAnd|InterlockedOr|InterlockedXor|InterlockedMin|InterlockedMax(inout int_like dest, int_like value)", SM_5_0, ALL),
+    ("void InterlockedAdd|InterlockedAnd|InterlockedOr|InterlockedXor|InterlockedMin|InterlockedMax|InterlockedExchange(inout int_like dest, int_like value, out int_like original)", SM_5_0, ALL),
+    ("void InterlockedCompareExchange(inout int_like dest, int_like compare, int_like value, out int_like original)", SM_5_0, ALL),
+    ("void InterlockedCompareStore(inout int_like dest, int_like compare, int_like value)", SM_5_0, ALL),
+    ("void AllMemoryBarrier|DeviceMemoryBarrier|GroupMemoryBarrier()", SM_5_0, ALL),
+    ("void AllMemoryBarrierWithGroupSync|DeviceMemoryBarrierWithGroupSync|GroupMemoryBarrierWithGroupSync()", SM_5_0, GROUP_SYNC),
+    // Tessellation factors of the patch constant function
+    ("void Process2DQuadTessFactorsAvg|Process2DQuadTessFactorsMax|Process2DQuadTessFactorsMin(float4 raw_edge_factors, float2 inside_scale, out float4 rounded_edge_factors, out float2 rounded_inside_factors, out float2 unrounded_inside_factors)", SM_5_0, HULL),
+    ("void ProcessQuadTessFactorsAvg|ProcessQuadTessFactorsMax|ProcessQuadTessFactorsMin(float4 raw_edge_factors, float inside_scale, out float4 rounded_edge_factors, out float2 rounded_inside_factors, out float2 unrounded_inside_factors)", SM_5_0, HULL),
+    ("void ProcessTriTessFactorsAvg|ProcessTriTessFactorsMax|ProcessTriTessFactorsMin(float3 raw_edge_factors, float inside_scale, out float3 rounded_edge_factors, out float rounded_inside_factor, out float unrounded_inside_factor)", SM_5_0, HULL),
+    ("void ProcessIsolineTessFactors(float raw_detail_factor, float raw_density_factor, out float rounded_detail_factor, out float rounded_density_factor)", SM_5_0, HULL),
+    // Pixel shader attribute evaluation
+    ("numeric<*> EvaluateAttributeAtCentroid(numeric<*> value)", SM_5_0, PIXEL),
+    ("numeric<*> EvaluateAttributeAtSample(numeric<*> value, uint index)", SM_5_0, PIXEL),
+    ("numeric<*> EvaluateAttributeSnapped(numeric<*> value, int2 offset)", SM_5_0, PIXEL),
+    ("uint GetRenderTargetSampleCount()", SM_4_1, ALL),
+    ("float2 GetRenderTargetSamplePosition(int index)", SM_4_1, ALL),
+    // Wave intrinsics
+    ("bool WaveIsFirstLane()", SM_6_0, ALL),
+    ("uint WaveGetLaneIndex|WaveGetLaneCount()", SM_6_0, ALL),
+    ("bool WaveActiveAnyTrue|WaveActiveAllTrue(bool value)", SM_6_0, ALL),
+    ("uint4 WaveActiveBallot(bool value)", SM_6_0, ALL),
+    ("uint WaveActiveCountBits|WavePrefixCountBits(bool value)", SM_6_0, ALL),
+    ("any<*> WaveReadLaneAt(any<*> value, uint lane)", SM_6_0, ALL),
+    ("any<*> WaveReadLaneFirst(any<*> value)", SM_6_0, ALL),
+    ("bool<*> WaveActiveAllEqual(any<*> value)", SM_6_0, ALL),
+    ("numeric<*> WaveActiveSum|WaveActiveProduct|WaveActiveMin|WaveActiveMax|WavePrefixSum|WavePrefixProduct(numeric<*> value)", SM_6_0, ALL),
+    ("int_like<*> WaveActiveBitAnd|WaveActiveBitOr|WaveActiveBitXor(int_like<*> value)", SM_6_0, ALL),
+    ("any<*> QuadReadAcrossX|QuadReadAcrossY|QuadReadAcrossDiagonal(any<*> value)", SM_6_0, ALL),
+    ("any<*> QuadReadLaneAt(any<*> value, uint lane)", SM_6_0, ALL),
+    ("uint4 WaveMatch(any<*> value)", SM_6_5, ALL),
+    ("numeric<*> WaveMultiPrefixSum|WaveMultiPrefixProduct(numeric<*> value, uint4 mask)", SM_6_5, ALL),
+    ("bool QuadAny|QuadAll(bool value)", SM_6_7, ALL),
+    ("bool IsHelperLane()", SM_6_6, ALL),
+    // Packed dot products
+    ("uint dot4add_u8packed(uint a, uint b, uint accumulator)", SM_6_4, ALL),
+    ("int dot4add_i8packed(uint a, uint b, int accumulator)", SM_6_4, ALL),
+    ("float dot2add(half2 a, half2 b, float accumulator)", SM_6_4, ALL),
+    // Mesh and amplification shaders
+    ("void SetMeshOutputCounts(uint vertex_count, uint primitive_count)", SM_6_5, MESH),
+    ("void DispatchMesh(uint x, uint y, uint z, udt payload)", SM_6_5, AMPLIFICATION),
+    // Ray tracing
+    ("void TraceRay(RaytracingAccelerationStructure scene, uint ray_flags, uint instance_inclusion_mask, uint ray_contribution_to_hit_group_index, uint multiplier_for_geometry_contribution_to_hit_group_index, uint miss_shader_index, RayDesc ray, inout udt payload)", SM_6_3, TRACE_RAY),
+    ("bool ReportHit(float t_hit, uint hit_kind, udt attributes)", SM_6_3, INTERSECTION),
+    ("void CallShader(uint shader_index, inout udt parameter)", SM_6_3, CALL_SHADER),
+    ("void IgnoreHit|AcceptHitAndEndSearch()", SM_6_3, ANY_HIT),
+    ("uint3 DispatchRaysIndex|DispatchRaysDimensions()", SM_6_3, RAY_TRACING),
+    ("float3 WorldRayOrigin|WorldRayDirection()", SM_6_3, RAY_HIT),
+    ("float RayTMin|RayTCurrent()", SM_6_3, RAY_HIT),
+    ("uint RayFlags()", SM_6_3, RAY_HIT),
+    ("float3 ObjectRayOrigin|ObjectRayDirection()", SM_6_3, PRIMITIVE_HIT),
+    ("uint InstanceIndex|InstanceID|PrimitiveIndex()", SM_6_3, PRIMITIVE_HIT),
+    ("uint GeometryIndex()", SM_6_5, PRIMITIVE_HIT),
+    ("uint HitKind()", SM_6_3, &[ShaderStage::AnyHit, ShaderStage::ClosestHit]),
+    ("float3x4 ObjectToWorld3x4|WorldToObject3x4()", SM_6_3, PRIMITIVE_HIT),
+    ("float4x3 ObjectToWorld4x3|WorldToObject4x3|ObjectToWorld|WorldToObject()", SM_6_3, PRIMITIVE_HIT),
+    // Effect framework texture lookups
+    ("float4 tex1D(sampler s, float t)", SM_1_0, ALL),
+    ("float4 tex1D|tex1Dgrad(sampler s, float t, float ddx, float ddy)", SM_1_0, ALL),
+    ("float4 tex2D(sampler s, float2 t)", SM_1_0, ALL),
+    ("float4 tex2D|tex2Dgrad(sampler s, float2 t, float2 ddx, float2 ddy)", SM_1_0, ALL),
+    ("float4 tex3D(sampler s, float3 t)", SM_1_0, ALL),
+    ("float4 tex3D|tex3Dgrad(sampler s, float3 t, float3 ddx, float3 ddy)", SM_1_0, ALL),
+    ("float4 texCUBE(sampler s, float3 t)", SM_1_0, ALL),
+    ("float4 texCUBE|texCUBEgrad(sampler s, float3 t, float3 ddx, float3 ddy)", SM_1_0, ALL),
+    ("float4 tex1Dbias|tex1Dlod|tex1Dproj|tex2Dbias|tex2Dlod|tex2Dproj|tex3Dbias|tex3Dlod|tex3Dproj|texCUBEbias|texCUBElod|texCUBEproj(sampler s, float4 t)", SM_1_0, ALL),
+];
+
+const SAMPLED: &str = "Texture1D Texture1DArray Texture2D Texture2DArray Texture3D TextureCube TextureCubeArray";
+const SAMPLED_WITH_OFFSET: &str = "Texture1D Texture1DArray Texture2D Texture2DArray Texture3D";
+const GATHERED: &str = "Texture2D Texture2DArray TextureCube TextureCubeArray";
+const GATHERED_WITH_OFFSET: &str = "Texture2D Texture2DArray";
+const MULTISAMPLED: &str = "Texture2DMS Texture2DMSArray";
+const READ_WRITE_TEXTURES: &str = "RWTexture1D RWTexture1DArray RWTexture2D RWTexture2DArray RWTexture3D RWTexture2DMS RWTexture2DMSArray \
+    RasterizerOrderedTexture1D RasterizerOrderedTexture1DArray RasterizerOrderedTexture2D RasterizerOrderedTexture2DArray RasterizerOrderedTexture3D";
+const TYPED_BUFFERS: &str = "Buffer RWBuffer RasterizerOrderedBuffer";
+const STRUCTURED_BUFFERS: &str = "StructuredBuffer RWStructuredBuffer RasterizerOrderedStructuredBuffer";
+const BYTE_ADDRESS_BUFFERS: &str = "ByteAddressBuffer RWByteAddressBuffer RasterizerOrderedByteAddressBuffer";
+const WRITABLE_BYTE_ADDRESS_BUFFERS: &str = "RWByteAddressBuffer RasterizerOrderedByteAddressBuffer";
+const STREAMS: &str = "PointStream LineStream TriangleStream";
+
+// Resource methods as (receiver types, signature, shader model, stages).
+// element is the template type of the resource and component its scalar type.
+// The sizes C (coordinates), O (offsets), G (gradients) and L (load locations) depend on the resource
+const METHODS: &[(&str, &str, ShaderModel, &[ShaderStage])] = &[
+    (SAMPLED, "element Sample(sampler s, float<C> location)", SM_4_0, PIXEL),
+    (SAMPLED_WITH_OFFSET, "element Sample(sampler s, float<C> location, int<O> offset)", SM_4_0, PIXEL),
+    (SAMPLED_WITH_OFFSET, "element Sample(sampler s, float<C> location, int<O> offset, float clamp)", SM_5_0, PIXEL),
+    (SAMPLED, "element SampleBias(sampler s, float<C> location, float bias)", SM_4_0, PIXEL),
+    (SAMPLED_WITH_OFFSET, "element SampleBias(sampler s, float<C> location, float bias, int<O> offset)", SM_4_0, PIXEL),
+    (SAMPLED, "float SampleCmp(sampler_cmp s, float<C> location, float compare_value)", SM_4_0, PIXEL),
+    (SAMPLED_WITH_OFFSET, "float SampleCmp(sampler_cmp s, float<C> location, float compare_value, int<O> offset)", SM_4_0, PIXEL),
+    (SAMPLED, "float SampleCmpLevelZero(sampler_cmp s, float<C> location, float compare_value)", SM_4_0, ALL),
+    (SAMPLED_WITH_OFFSET, "float SampleCmpLevelZero(sampler_cmp s, float<C> location, float compare_value, int<O> offset)", SM_4_0, ALL),
+    (SAMPLED, "element SampleGrad(sampler s, float<C> location, float<G> ddx, float<G> ddy)", SM_4_0, ALL),
+    (SAMPLED_WITH_OFFSET, "element SampleGrad(sampler s, float<C> location, float<G> ddx, float<G> ddy, int<O> offset)", SM_4_0, ALL),
+    (SAMPLED, "element SampleLevel(sampler s, float<C> location, float lod)", SM_4_0, ALL),
+    (SAMPLED_WITH_OFFSET, "element SampleLevel(sampler s, float<C> location, float lod, int<O> offset)", SM_4_0, ALL),
+    (SAMPLED, "float CalculateLevelOfDetail|CalculateLevelOfDetailUnclamped(sampler s, float<G> location)", SM_4_1, PIXEL),
+    (GATHERED, "component<4> Gather|GatherRed|GatherGreen|GatherBlue|GatherAlpha(sampler s, float<C> location)", SM_4_1, ALL),
+    (GATHERED_WITH_OFFSET, "component<4> Gather|GatherRed|GatherGreen|GatherBlue|GatherAlpha(sampler s, float<C> location, int<O> offset)", SM_5_0, ALL),
+    (GATHERED, "float4 GatherCmp|GatherCmpRed|GatherCmpGreen|GatherCmpBlue|GatherCmpAlpha(sampler_cmp s, float<C> location, float compare_value)", SM_5_0, ALL),
+    (GATHERED_WITH_OFFSET, "float4 GatherCmp|GatherCmpRed|GatherCmpGreen|GatherCmpBlue|GatherCmpAlpha(sampler_cmp s, float<C> location, float compare_value, int<O> offset)", SM_5_0, ALL),
+    (SAMPLED_WITH_OFFSET, "element Load(int<L> location)", SM_4_0, ALL),
+    (SAMPLED_WITH_OFFSET, "element Load(int<L> location, int<O> offset)", SM_4_0, ALL),
+    (MULTISAMPLED, "element Load(int<L> location, int sample_index)", SM_4_0, ALL),
+    (MULTISAMPLED, "element Load(int<L> location, int sample_index, int<O> offset)", SM_4_0, ALL),
+    (MULTISAMPLED, "float2 GetSamplePosition(int sample_index)", SM_4_1, ALL),
+    (READ_WRITE_TEXTURES, "element Load(int<L> location)", SM_5_0, ALL),
+    (TYPED_BUFFERS, "element Load(int location)", SM_4_0, ALL),
+    (STRUCTURED_BUFFERS, "element Load(int location)", SM_5_0, ALL),
+    (BYTE_ADDRESS_BUFFERS, "uint Load(uint address)", SM_5_0, ALL),
+    (BYTE_ADDRESS_BUFFERS, "uint2 Load2(uint address)", SM_5_0, ALL),
+    (BYTE_ADDRESS_BUFFERS, "uint3 Load3(uint address)", SM_5_0, ALL),
+    (BYTE_ADDRESS_BUFFERS, "uint4 Load4(uint address)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void Store(uint address, uint value)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void Store2(uint address, uint2 value)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void Store3(uint address, uint3 value)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void Store4(uint address, uint4 value)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void InterlockedAdd|InterlockedAnd|InterlockedOr|InterlockedXor|InterlockedMin|InterlockedMax(uint address, int_like value)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void InterlockedAdd|InterlockedAnd|InterlockedOr|InterlockedXor|InterlockedMin|InterlockedMax|InterlockedExchange(uint address, int_like value, out int_like original)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void InterlockedCompareExchange(uint address, int_like compare, int_like value, out int_like original)", SM_5_0, ALL),
+    (WRITABLE_BYTE_ADDRESS_BUFFERS, "void InterlockedCompareStore(uint address, int_like compare, int_like value)", SM_5_0, ALL),
+    ("RWStructuredBuffer", "uint IncrementCounter|DecrementCounter()", SM_5_0, ALL),
+    ("AppendStructuredBuffer", "void Append(element value)", SM_5_0, ALL),
+    ("ConsumeStructuredBuffer", "element Consume()", SM_5_0, ALL),
+    (STREAMS, "void Append(element vertex)", SM_4_0, GEOMETRY),
+    (STREAMS, "void RestartStrip()", SM_4_0, GEOMETRY),
+    // GetDimensions writes either uint or float sizes
+    ("Buffer RWBuffer RasterizerOrderedBuffer ByteAddressBuffer RWByteAddressBuffer RasterizerOrderedByteAddressBuffer \
+        AppendStructuredBuffer ConsumeStructuredBuffer", "void GetDimensions(out uint size)", SM_4_0, ALL),
+    (STRUCTURED_BUFFERS, "void GetDimensions(out uint count, out uint stride)", SM_5_0, ALL),
+    ("Texture1D RWTexture1D RasterizerOrderedTexture1D", "void GetDimensions(out numeric width)", SM_4_0, ALL),
+    ("Texture1D", "void GetDimensions(uint mip_level, out numeric width, out numeric levels)", SM_4_0, ALL),
+    ("Texture1DArray RWTexture1DArray RasterizerOrderedTexture1DArray", "void GetDimensions(out numeric width, out numeric elements)", SM_4_0, ALL),
+    ("Texture1DArray", "void GetDimensions(uint mip_level, out numeric width, out numeric elements, out numeric levels)", SM_4_0, ALL),
+    ("Texture2D TextureCube RWTexture2D RasterizerOrderedTexture2D", "void GetDimensions(out numeric width, out numeric height)", SM_4_0, ALL),
+    ("Texture2D TextureCube", "void GetDimensions(uint mip_level, out numeric width, out numeric height, out numeric levels)", SM_4_0, ALL),
+    ("Texture2DArray TextureCubeArray RWTexture2DArray RasterizerOrderedTexture2DArray", "void GetDimensions(out numeric width, out numeric height, out numeric elements)", SM_4_0, ALL),
+    ("Texture2DArray TextureCubeArray", "void GetDimensions(uint mip_level, out numeric width, out numeric height, out numeric elements, out numeric levels)", SM_4_0, ALL),
+    ("Texture3D RWTexture3D RasterizerOrderedTexture3D", "void GetDimensions(out numeric width, out numeric height, out numeric depth)", SM_4_0, ALL),
+    ("Texture3D", "void GetDimensions(uint mip_level, out numeric width, out numeric height, out numeric depth, out numeric levels)", SM_4_0, ALL),
+    ("Texture2DMS RWTexture2DMS", "void GetDimensions(out numeric width, out numeric height, out numeric samples)", SM_4_0, ALL),
+    ("Texture2DMSArray RWTexture2DMSArray", "void GetDimensions(out numeric width, out numeric height, out numeric elements, out numeric samples)", SM_4_0, ALL),
+    // Inline ray tracing
+    ("RayQuery", "void TraceRayInline(RaytracingAccelerationStructure scene, uint ray_flags, uint instance_inclusion_mask, RayDesc ray)", SM_6_5, ALL),
+    ("RayQuery", "bool Proceed()", SM_6_5, ALL),
+    ("RayQuery", "void Abort|CommitNonOpaqueTriangleHit()", SM_6_5, ALL),
+    ("RayQuery", "void CommitProceduralPrimitiveHit(float t_hit)", SM_6_5, ALL),
+    ("RayQuery", "uint CommittedStatus|CandidateType|RayFlags|CommittedInstanceIndex|CommittedInstanceID|CommittedPrimitiveIndex|CommittedGeometryIndex|\
+        CommittedInstanceContributionToHitGroupIndex|CandidateInstanceIndex|CandidateInstanceID|CandidatePrimitiveIndex|CandidateGeometryIndex|\
+        CandidateInstanceContributionToHitGroupIndex()", SM_6_5, ALL),
+    ("RayQuery", "float RayTMin|CommittedRayT|CandidateTriangleRayT()", SM_6_5, ALL),
+    ("RayQuery", "float3 WorldRayOrigin|WorldRayDirection|CommittedObjectRayOrigin|CommittedObjectRayDirection|CandidateObjectRayOrigin|CandidateObjectRayDirection()", SM_6_5, ALL),
+    ("RayQuery", "float2 CommittedTriangleBarycentrics|CandidateTriangleBarycentrics()", SM_6_5, ALL),
+    ("RayQuery", "bool CommittedTriangleFrontFace|CandidateTriangleFrontFace|CandidateProceduralPrimitiveNonOpaque()", SM_6_5, ALL),
+    ("RayQuery", "float3x4 CommittedObjectToWorld3x4|CommittedWorldToObject3x4|CandidateObjectToWorld3x4|CandidateWorldToObject3x4()", SM_6_5, ALL),
+    ("RayQuery", "float4x3 CommittedObjectToWorld4x3|CommittedWorldToObject4x3|CandidateObjectToWorld4x3|CandidateWorldToObject4x3()", SM_6_5, ALL),
+];
+
+// Component classes of intrinsic parameters
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ComponentClass
+{
+    FloatLike,
+    IntLike,
+    Numeric,
+    Any,
+    // The scalar type of the resource element
+    Component,
+    Scalar(ScalarType),
+}
+
+impl ComponentClass
+{
+    fn is_generic(&self) -> bool
+    {
+        return matches!(self, ComponentClass::FloatLike | ComponentClass::IntLike | ComponentClass::Numeric | ComponentClass::Any);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dimension
+{
+    Fixed(u32),
+    Variable(char),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TemplateLayout
+{
+    Scalar,
+    Vector(Dimension),
+    Matrix(Dimension, Dimension),
+    // Scalar, vector or matrix, the same for every parameter with this layout
+    Any,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TemplateType
+{
+    Void,
+    Numeric(ComponentClass, TemplateLayout),
+    // The template type of the resource
+    Element,
+    Sampler,
+    ComparisonSampler,
+    // A user defined struct, like ray payloads
+    UserDefined,
+    Resource(ResourceKind),
+    Object(ObjectKind),
+}
+
+#[derive(Clone, Debug)]
+pub struct IntrinsicParameter
+{
+    pub name: String,
+    pub ty: TemplateType,
+    pub direction: ParameterDirection,
+}
+
+#[derive(Clone, Debug)]
+pub struct IntrinsicOverload
+{
+    pub name: String,
+    // Types that have this method, empty for functions
+    pub receivers: Vec<String>,
+    pub return_type: TemplateType,
+    pub parameters: Vec<IntrinsicParameter>,
+    pub shader_model: ShaderModel,
+    // Empty when every stage is allowed
+    pub stages: &'static [ShaderStage],
+}
+
+impl IntrinsicOverload
+{
+    pub fn is_method(&self) -> bool
+    {
+        return !self.receivers.is_empty();
+    }
+
+    pub fn is_allowed_in(&self, stage: ShaderStage) -> bool
+    {
+        return self.stages.is_empty() || self.stages.contains(&stage);
+    }
+}
+
+// The overload chosen for a call, with the parameter types after template arguments are deduced
+#[derive(Clone, Debug)]
+pub struct IntrinsicCall
+{
+    pub overload: &'static IntrinsicOverload,
+    pub return_type: Type,
+    pub parameter_types: Vec<Type>,
+    // Sum of the conversion costs of the arguments, lower is a better match
+    pub cost: u32,
+    // An argument is a larger vector or matrix than its parameter
+    pub truncates: bool,
+}
+
+// Template arguments deduced from the arguments of a call
+#[derive(Default)]
+struct Deduction
+{
+    component: Option<ScalarType>,
+    shape: Option<Shape>,
+    sizes: HashMap<char, u32>,
+    element: Option<Type>,
+}
+
+impl Deduction
+{
+    fn bind_size(&mut self, dimension: Dimension, size: u32)
+    {
+        if let Dimension::Variable(name) = dimension
+        {
+            let bound = self.sizes.entry(name).or_insert(size);
+            *bound = (*bound).min(size);
+        }
+    }
+
+    fn size(&self, dimension: Dimension) -> u32
+    {
+        return match dimension
+        {
+            Dimension::Fixed(size) => size,
+            Dimension::Variable(name) => self.sizes.get(&name).copied().unwrap_or(1)
+        };
+    }
+
+    fn scalar(&self, class: ComponentClass) -> Option<ScalarType>
+    {
+        let component = self.component;
+        return match class
+        {
+            ComponentClass::Scalar(scalar) => Some(scalar),
+            ComponentClass::Component => self.element.as_ref().and_then(|e| e.scalar_type()),
+            ComponentClass::FloatLike => match component
+            {
+                Some(scalar) if scalar.is_float() && !scalar.is_literal() => Some(scalar),
+                _ => Some(ScalarType::Float)
+            },
+            ComponentClass::IntLike => match component
+            {
+                Some(scalar) if scalar.is_float() => None,
+                Some(scalar) if scalar.is_integer() && !scalar.is_literal() => Some(scalar),
+                _ => Some(ScalarType::Int)
+            },
+            ComponentClass::Numeric | ComponentClass::Any => match component
+            {
+                None | Some(ScalarType::LiteralFloat) => Some(ScalarType::Float),
+                Some(ScalarType::LiteralInt) => Some(ScalarType::Int),
+                Some(ScalarType::Bool) if class == ComponentClass::Numeric => Some(ScalarType::Int),
+                Some(scalar) => Some(scalar)
+            }
+        };
+    }
+
+    fn instantiate(&self, ty: &TemplateType) -> Option<Type>
+    {
+        return match ty
+        {
+            TemplateType::Void => Some(Type::Void),
+            TemplateType::Numeric(class, layout) => {
+                let scalar = self.scalar(*class)?;
+                Some(match layout
+                {
+                    TemplateLayout::Scalar => Type::Scalar(scalar),
+                    TemplateLayout::Vector(size) => match self.size(*size)
+                    {
+                        1 => Type::Scalar(scalar),
+                        size => Type::Vector(scalar, size)
+                    },
+                    TemplateLayout::Matrix(rows, columns) => Type::Matrix(scalar, self.size(*rows), self.size(*columns)),
+                    TemplateLayout::Any => Type::from_shape(scalar, self.shape.unwrap_or(Shape::Scalar))
+                })
+            },
+            TemplateType::Element => self.element.clone(),
+            TemplateType::Resource(kind) => Some(Type::Resource{ kind: *kind, element: None, count: None }),
+            TemplateType::Object(kind) => Some(Type::Object(*kind)),
+            TemplateType::Sampler | TemplateType::ComparisonSampler | TemplateType::UserDefined => Some(Type::Error)
+        };
+    }
+}
+
+// Coordinate, offset, gradient and load location sizes of a resource
+fn resource_sizes(kind: ResourceKind) -> [(char, u32); 4]
+{
+    let (coordinates, offset, gradient, location) = match kind
+    {
+        ResourceKind::Texture1D => (1, 1, 1, 2),
+        ResourceKind::Texture1DArray => (2, 1, 1, 3),
+        ResourceKind::Texture2D => (2, 2, 2, 3),
+        ResourceKind::Texture2DArray => (3, 2, 2, 4),
+        ResourceKind::Texture3D => (3, 3, 3, 4),
+        ResourceKind::TextureCube => (3, 0, 3, 0),
+        ResourceKind::TextureCubeArray => (4, 0, 3, 0),
+        ResourceKind::Texture2DMS | ResourceKind::RWTexture2DMS => (2, 2, 2, 2),
+        ResourceKind::Texture2DMSArray | ResourceKind::RWTexture2DMSArray => (3, 2, 2, 3),
+        ResourceKind::RWTexture1D | ResourceKind::RasterizerOrderedTexture1D => (1, 1, 1, 1),
+        ResourceKind::RWTexture1DArray | ResourceKind::RasterizerOrderedTexture1DArray => (2, 1, 1, 2),
+        ResourceKind::RWTexture2D | ResourceKind::RasterizerOrderedTexture2D => (2, 2, 2, 2),
+        ResourceKind::RWTexture2DArray | ResourceKind::RasterizerOrderedTexture2DArray => (3, 2, 2, 3),
+        ResourceKind::RWTexture3D | ResourceKind::RasterizerOrderedTexture3D => (3, 3, 3, 3),
+        _ => (1, 1, 1, 1)
+    };
+    return [('C', coordinates), ('O', offset), ('G', gradient), ('L', location)];
+}
+
+// The cost of converting an argument to a parameter, None when it does not convert
+fn conversion_cost(source: &Type, target: &Type) -> Option<(u32, bool)>
+{
+    let rank = conversion_rank(source, target)?;
+    let cost = match rank
+    {
+        ConversionRank::Exact => 0,
+        ConversionRank::Promotion => 1,
+        ConversionRank::Conversion => 2,
+        ConversionRank::Splat => 4,
+        ConversionRank::Truncation => 8
+    };
+    return Some((cost, rank == ConversionRank::Truncation));
+}
+
+impl IntrinsicOverload
+{
+    // Deduces the template arguments and the conversion cost of the arguments, None when the overload is not viable
+    fn instantiate(&'static self, arguments: &[Type], receiver: Option<&Type>) -> Option<IntrinsicCall>
+    {
+        if arguments.len() != self.parameters.len()
+        {
+            return None;
+        }
+
+        let mut deduction = Deduction::default();
+        if let Some(Type::Resource{ kind, element, .. }) = receiver
+        {
+            deduction.element = element.as_deref().cloned();
+            for (name, size) in resource_sizes(*kind)
+            {
+                deduction.sizes.insert(name, size);
+            }
+        }
+
+        // Arguments with errors match anything, the error is reported where the argument is
+        for (parameter, argument) in self.parameters.iter().zip(arguments).filter(|(_, a)| !a.is_error())
+        {
+            let (class, layout) = match &parameter.ty
+            {
+                TemplateType::Numeric(class, layout) => (class, layout),
+                _ => continue
+            };
+
+            let (scalar, shape) = (argument.scalar_type()?, argument.shape()?);
+            if class.is_generic()
+            {
+                deduction.component = match deduction.component
+                {
+                    Some(component) if component != scalar => Some(promote_scalars(component, scalar)),
+                    _ => Some(scalar)
+                };
+            }
+
+            match (layout, shape)
+            {
+                (_, Shape::Scalar) | (TemplateLayout::Scalar, _) => {},
+                (TemplateLayout::Vector(size), Shape::Vector(argument_size)) => deduction.bind_size(*size, argument_size),
+                (TemplateLayout::Matrix(rows, columns), Shape::Matrix(argument_rows, argument_columns)) => {
+                    deduction.bind_size(*rows, argument_rows);
+                    deduction.bind_size(*columns, argument_columns);
+                },
+                (TemplateLayout::Any, shape) => {
+                    deduction.shape = match (deduction.shape, shape)
+                    {
+                        (None, shape) => Some(shape),
+                        (Some(Shape::Vector(size)), Shape::Vector(other)) => Some(Shape::Vector(size.min(other))),
+                        (Some(Shape::Matrix(rows, columns)), Shape::Matrix(other_rows, other_columns)) => {
+                            Some(Shape::Matrix(rows.min(other_rows), columns.min(other_columns)))
+                        },
+                        _ => return None
+                    };
+                },
+                _ => return None
+            }
+        }
+
+        let mut call = IntrinsicCall{ overload: self, return_type: deduction.instantiate(&self.return_type)?, parameter_types: Vec::new(), cost: 0, truncates: false };
+        for (parameter, argument) in self.parameters.iter().zip(arguments)
+        {
+            let parameter_type = deduction.instantiate(&parameter.ty)?;
+            if !argument.is_error()
+            {
+                let accepted = match &parameter.ty
+                {
+                    TemplateType::Numeric(..) | TemplateType::Element if parameter_type.is_numeric() => {
+                        let (cost, truncates) = conversion_cost(argument, &parameter_type)?;
+                        call.cost += cost;
+                        call.truncates |= truncates;
+                        true
+                    },
+                    TemplateType::Sampler => matches!(argument, Type::Resource{ kind, .. } if kind.is_sampler() && *kind != ResourceKind::SamplerComparisonState),
+                    TemplateType::ComparisonSampler => matches!(argument, Type::Resource{ kind, .. }
+                        if *kind == ResourceKind::SamplerComparisonState || kind.name().starts_with("sampler")),
+                    TemplateType::UserDefined => matches!(argument, Type::Struct{ .. } | Type::TemplateParameter{ .. }),
+                    TemplateType::Resource(kind) => matches!(argument, Type::Resource{ kind: argument_kind, .. } if argument_kind == kind),
+                    TemplateType::Object(kind) => matches!(argument, Type::Object(argument_kind) if argument_kind == kind),
+                    _ => *argument == parameter_type || matches!(argument, Type::TemplateParameter{ .. })
+                };
+
+                if !accepted
+                {
+                    return None;
+                }
+            }
+            call.parameter_types.push(parameter_type);
+        }
+        return Some(call);
+    }
+}
+
+// IntrinsicCatalogue
+// Signatures of the intrinsic functions and of the methods of resources and builtin objects.
+// The tables are parsed once, use IntrinsicCatalogue::get() to access them
+pub struct IntrinsicCatalogue
+{
+    overloads: Vec<IntrinsicOverload>,
+    functions: HashMap<String, Vec<usize>>,
+    methods: HashMap<(String, String), Vec<usize>>,
+}
+
+impl IntrinsicCatalogue
+{
+    pub fn get() -> &'static IntrinsicCatalogue
+    {
+        static CATALOGUE: OnceLock<IntrinsicCatalogue> = OnceLock::new();
+        return CATALOGUE.get_or_init(IntrinsicCatalogue::build);
+    }
+
+    fn build() -> Self
+    {
+        let mut catalogue = Self{ overloads: Vec::new(), functions: HashMap::new(), methods: HashMap::new() };
+        for (signature, shader_model, stages) in FUNCTIONS
+        {
+            catalogue.add(signature, "", *shader_model, stages);
+        }
+
+        for (receivers, signature, shader_model, stages) in METHODS
+        {
+            catalogue.add(signature, receivers, *shader_model, stages);
+        }
+        return catalogue;
+    }
+
+    fn add(&mut self, signature: &str, receivers: &str, shader_model: ShaderModel, stages: &'static [ShaderStage])
+    {
+        let (names, return_type, parameters) = parse_signature(signature).unwrap_or_else(|| panic!("Invalid intrinsic signature '{}'", signature));
+        let receivers: Vec<String> = receivers.split_whitespace().map(String::from).collect();
+        for name in names
+        {
+            let index = self.overloads.len();
+            if receivers.is_empty()
+            {
+                self.functions.entry(name.clone()).or_default().push(index);
+            }
+
+            for receiver in &receivers
+            {
+                self.methods.entry((receiver.clone(), name.clone())).or_default().push(index);
+            }
+
+            self.overloads.push(IntrinsicOverload{
+                name: name,
+                receivers: receivers.clone(),
+                return_type: return_type.clone(),
+                parameters: parameters.clone(),
+                shader_model: shader_model,
+                stages: stages
+            });
+        }
+    }
+
+    pub fn get_overloads(&self) -> &Vec<IntrinsicOverload>
+    {
+        return &self.overloads;
+    }
+
+    pub fn is_function(&self, name: &str) -> bool
+    {
+        return self.functions.contains_key(name);
+    }
+
+    // Overloads of an intrinsic function, in table order
+    pub fn functions(&self, name: &str) -> Vec<&IntrinsicOverload>
+    {
+        return self.functions.get(name).map(|o| o.iter().map(|i| &self.overloads[*i]).collect()).unwrap_or_default();
+    }
+
+    // Overloads of a method of a resource or builtin object type, like Texture2D or RayQuery
+    pub fn methods(&self, receiver: &str, name: &str) -> Vec<&IntrinsicOverload>
+    {
+        let key = (String::from(receiver), String::from(name));
+        return self.methods.get(&key).map(|o| o.iter().map(|i| &self.overloads[*i]).collect()).unwrap_or_default();
+    }
+
+    // Names for completion, sorted and without duplicates
+    pub fn function_names(&self) -> Vec<&str>
+    {
+        let mut names: Vec<&str> = self.functions.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        return names;
+    }
+
+    pub fn method_names(&self, receiver: &str) -> Vec<&str>
+    {
+        let mut names: Vec<&str> = self.methods.keys().filter(|(r, _)| r == receiver).map(|(_, n)| n.as_str()).collect();
+        names.sort();
+        return names;
+    }
+
+    // Picks the overload with the lowest conversion cost, the first one in the table wins ties
+    fn best_match(overloads: Vec<&'static IntrinsicOverload>, arguments: &[Type], receiver: Option<&Type>) -> Option<IntrinsicCall>
+    {
+        let mut best: Option<IntrinsicCall> = None;
+        for overload in overloads
+        {
+            if let Some(call) = overload.instantiate(arguments, receiver)
+            {
+                if best.as_ref().map(|b| call.cost < b.cost).unwrap_or(true)
+                {
+                    best = Some(call);
+                }
+            }
+        }
+        return best;
+    }
+
+    pub fn resolve_function(&'static self, name: &str, arguments: &[Type]) -> Option<IntrinsicCall>
+    {
+        return Self::best_match(self.functions(name), arguments, None);
+    }
+
+    // Resolves a method call on a resource or builtin object
+    pub fn resolve_method(&'static self, receiver: &Type, name: &str, arguments: &[Type]) -> Option<IntrinsicCall>
+    {
+        let receiver_name = match receiver
+        {
+            Type::Resource{ kind, .. } => kind.name(),
+            Type::Object(kind) => kind.name(),
+            _ => return None
+        };
+        return Self::best_match(self.methods(receiver_name, name), arguments, Some(receiver));
+    }
+}
+
+// Parses 'return_type name|other_name(in type name, out type name)'
+fn parse_signature(signature: &str) -> Option<(Vec<String>, TemplateType, Vec<IntrinsicParameter>)>
+{
+    let open = signature.find('(')?;
+    let close = signature.rfind(')')?;
+    let head = &signature[..open];
+    let split = head.rfind(' ')?;
+    let return_type = parse_template_type(head[..split].trim())?;
+    let names = head[split + 1..].split('|').map(String::from).collect();
+
+    let mut parameters = Vec::new();
+    let list = signature[open + 1..close].trim();
+    // Commas inside <r,c> do not separate parameters
+    let mut depth = 0;
+    let mut start = 0;
+    let mut pieces = Vec::new();
+    for (index, c) in list.char_indices()
+    {
+        match c
+        {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                pieces.push(&list[start..index]);
+                start = index + 1;
+            },
+            _ => {}
+        }
+    }
+    if !list.is_empty()
+    {
+        pieces.push(&list[start..]);
+    }
+
+    for piece in pieces
+    {
+        let mut words: Vec<&str> = piece.split_whitespace().collect();
+        let direction = match words.first()
+        {
+            Some(&"out") => ParameterDirection::Out,
+            Some(&"inout") => ParameterDirection::InOut,
+            Some(&"in") => ParameterDirection::In,
+            _ => ParameterDirection::In
+        };
+        if matches!(words.first(), Some(&"in" | &"out" | &"inout"))
+        {
+            words.remove(0);
+        }
+
+        let name = words.pop()?;
+        parameters.push(IntrinsicParameter{ name: String::from(name), ty: parse_template_type(&words.join(""))?, direction: direction });
+    }
+    return Some((names, return_type, parameters));
+}
+
+fn parse_dimension(text: &str) -> Option<Dimension>
+{
+    if let Ok(size) = text.parse::<u32>()
+    {
+        return Some(Dimension::Fixed(size));
+    }
+
+    let mut chars = text.chars();
+    return match (chars.next(), chars.next())
+    {
+        (Some(name), None) if name.is_ascii_alphabetic() => Some(Dimension::Variable(name)),
+        _ => None
+    };
+}
+
+fn parse_template_type(text: &str) -> Option<TemplateType>
+{
+    let (name, layout) = match text.find('<')
+    {
+        Some(open) => {
+            let dimensions: Vec<&str> = text[open + 1..text.len() - 1].split(',').map(|d| d.trim()).collect();
+            let layout = match dimensions.as_slice()
+            {
+                ["*"] => TemplateLayout::Any,
+                [size] => TemplateLayout::Vector(parse_dimension(size)?),
+                [rows, columns] => TemplateLayout::Matrix(parse_dimension(rows)?, parse_dimension(columns)?),
+                _ => return None
+            };
+            (&text[..open], layout)
+        },
+        None => (text, TemplateLayout::Scalar)
+    };
+
+    let class = match name
+    {
+        "void" => return Some(TemplateType::Void),
+        "element" => return Some(TemplateType::Element),
+        "sampler" => return Some(TemplateType::Sampler),
+        "sampler_cmp" => return Some(TemplateType::ComparisonSampler),
+        "udt" => return Some(TemplateType::UserDefined),
+        "float_like" => ComponentClass::FloatLike,
+        "int_like" => ComponentClass::IntLike,
+        "numeric" => ComponentClass::Numeric,
+        "any" => ComponentClass::Any,
+        "component" => ComponentClass::Component,
+        _ => {
+            if let Some(kind) = ResourceKind::from_name(name)
+            {
+                return Some(TemplateType::Resource(kind));
+            }
+            if let Some(kind) = ObjectKind::from_name(name)
+            {
+                return Some(TemplateType::Object(kind));
+            }
+
+            // Keywords like float4 and uint3x4
+            return match Type::from_keyword(name)?
+            {
+                Type::Scalar(scalar) => Some(TemplateType::Numeric(ComponentClass::Scalar(scalar), layout)),
+                Type::Vector(scalar, size) => Some(TemplateType::Numeric(ComponentClass::Scalar(scalar), TemplateLayout::Vector(Dimension::Fixed(size)))),
+                Type::Matrix(scalar, rows, columns) => {
+                    Some(TemplateType::Numeric(ComponentClass::Scalar(scalar), TemplateLayout::Matrix(Dimension::Fixed(rows), Dimension::Fixed(columns))))
+                },
+                _ => None
+            };
+        }
+    };
+    return Some(TemplateType::Numeric(class, layout));
+}
+
+impl fmt::Display for Dimension
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        return match self
+        {
+            Dimension::Fixed(size) => write!(f, "{}", size),
+            Dimension::Variable(name) => write!(f, "{}", name)
+        };
+    }
+}
+
+impl fmt::Display for TemplateType
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let (class, layout) = match self
+        {
+            TemplateType::Void => return write!(f, "void"),
+            TemplateType::Element => return write!(f, "element"),
+            TemplateType::Sampler => return write!(f, "sampler"),
+            TemplateType::ComparisonSampler => return write!(f, "sampler_cmp"),
+            TemplateType::UserDefined => return write!(f, "udt"),
+            TemplateType::Resource(kind) => return write!(f, "{}", kind.name()),
+            TemplateType::Object(kind) => return write!(f, "{}", kind.name()),
+            TemplateType::Numeric(class, layout) => (class, layout)
+        };
+
+        match class
+        {
+            ComponentClass::FloatLike => write!(f, "float_like")?,
+            ComponentClass::IntLike => write!(f, "int_like")?,
+            ComponentClass::Numeric => write!(f, "numeric")?,
+            ComponentClass::Any => write!(f, "any")?,
+            ComponentClass::Component => write!(f, "component")?,
+            ComponentClass::Scalar(scalar) => {
+                // float4 instead of float<4>
+                return match layout
+                {
+                    TemplateLayout::Vector(Dimension::Fixed(size)) => write!(f, "{}{}", scalar.name(), size),
+                    TemplateLayout::Matrix(Dimension::Fixed(rows), Dimension::Fixed(columns)) => write!(f, "{}{}x{}", scalar.name(), rows, columns),
+                    TemplateLayout::Scalar => write!(f, "{}", scalar.name()),
+                    TemplateLayout::Vector(size) => write!(f, "{}<{}>", scalar.name(), size),
+                    TemplateLayout::Matrix(rows, columns) => write!(f, "{}<{},{}>", scalar.name(), rows, columns),
+                    TemplateLayout::Any => write!(f, "{}<*>", scalar.name())
+                };
+            }
+        }
+
+        return match layout
+        {
+            TemplateLayout::Scalar => Ok(()),
+            TemplateLayout::Vector(size) => write!(f, "<{}>", size),
+            TemplateLayout::Matrix(rows, columns) => write!(f, "<{},{}>", rows, columns),
+            TemplateLayout::Any => write!(f, "<*>")
+        };
+    }
+}
+
+fn write_direction(f: &mut fmt::Formatter<'_>, direction: ParameterDirection) -> fmt::Result
+{
+    return match direction
+    {
+        ParameterDirection::Out => write!(f, "out "),
+        ParameterDirection::InOut => write!(f, "inout "),
+        _ => Ok(())
+    };
+}
+
+// The template signature, like 'float_like<*> lerp(float_like<*> x, float_like<*> y, float_like<*> s)'
+impl fmt::Display for IntrinsicOverload
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{} {}(", self.return_type, self.name)?;
+        for (index, parameter) in self.parameters.iter().enumerate()
+        {
+            if index > 0
+            {
+                write!(f, ", ")?;
+            }
+            write_direction(f, parameter.direction)?;
+            write!(f, "{} {}", parameter.ty, parameter.name)?;
+        }
+        return write!(f, ")");
+    }
+}
+
+// The signature with deduced types, like 'float3 lerp(float3 x, float3 y, float3 s)', used for hover
+impl fmt::Display for IntrinsicCall
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{} {}(", self.return_type, self.overload.name)?;
+        for (index, (parameter, ty)) in self.overload.parameters.iter().zip(&self.parameter_types).enumerate()
+        {
+            if index > 0
+            {
+                write!(f, ", ")?;
+            }
+            write_direction(f, parameter.direction)?;
+            // Samplers and payloads are not deduced
+            match ty
+            {
+                Type::Error => write!(f, "{} {}", parameter.ty, parameter.name)?,
+                ty => write!(f, "{} {}", ty, parameter.name)?
+            }
+        }
+        return write!(f, ")");
+    }
+}
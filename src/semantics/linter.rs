@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::SyntaxNode, syntax_tokens::{SyntaxToken, SyntaxTokenType, SyntaxTriviaType, TextSpan}, LintLevel, LintSettings, ParserSettings};
+
+use super::{call_graph::function_body, constant_evaluator::{ConstantFolder, ConstantValue}, symbol_table::{ScopeKind, SymbolId, SymbolKind, SymbolTable},
+    type_checker::{literal_type, ImplicitConversion, TypeChecker}, types::*, uniformity::UniformityAnalysis};
+
+// A check of the linter, the name is used in the settings and in suppression comments
+#[derive(Clone, Copy, Debug)]
+pub struct LintRule
+{
+    pub name: &'static str,
+    pub description: &'static str,
+    pub level: LintLevel,
+}
+
+pub const LINT_RULES: &[LintRule] = &[
+    LintRule{ name: "implicit-truncation", description: "Vectors implicitly converted to a smaller vector or a scalar", level: LintLevel::Warning },
+    LintRule{ name: "half-literal", description: "Float literals without the h suffix in half precision expressions", level: LintLevel::Warning },
+    LintRule{ name: "pow-negative-base", description: "Calls to pow with a constant negative base, which return NaN", level: LintLevel::Warning },
+    LintRule{ name: "division-by-zero", description: "Division or remainder by a constant zero", level: LintLevel::Warning },
+    LintRule{ name: "unused-variable", description: "Local variables that are never used", level: LintLevel::Warning },
+    LintRule{ name: "unused-parameter", description: "Parameters without a semantic that are never used", level: LintLevel::Hint },
+    LintRule{ name: "shadowed-variable", description: "Local variables and parameters that hide a variable of an enclosing scope", level: LintLevel::Warning },
+    LintRule{ name: "divergent-gradient", description: "Gradient operations like Sample or ddx under conditions that differ between pixels", level: LintLevel::Warning },
+];
+
+pub fn lint_rule(name: &str) -> Option<&'static LintRule>
+{
+    return LINT_RULES.iter().find(|r| r.name == name);
+}
+
+// A '// hlsl-lint: disable=rule' comment, it covers the line it is written on or the next line when it has a line of its own
+struct Suppression
+{
+    file_index: usize,
+    line: usize,
+    // None disables every rule
+    rules: Option<Vec<String>>,
+}
+
+// The rules listed by a suppression comment, like 'disable=unused-variable, shadowed-variable'
+fn parse_suppression(comment: &str) -> Option<Option<Vec<String>>>
+{
+    let directive = comment.split_once("hlsl-lint:")?.1.trim().trim_end_matches("*/").trim();
+    let rules = directive.strip_prefix("disable")?.trim();
+    if rules.is_empty()
+    {
+        return Some(None);
+    }
+    let rules = rules.strip_prefix('=')?;
+    return Some(Some(rules.split(',').map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect()));
+}
+
+// Unsuffixed float literals like 0.5 are float, the h suffix keeps them half
+fn unsuffixed_float_literal<'a>(expression: Option<Expression<'a>>) -> Option<&'a SyntaxToken>
+{
+    return match expression?
+    {
+        Expression::Literal(literal) if literal_type(&literal) == Type::Scalar(ScalarType::LiteralFloat) => Some(literal.token()),
+        Expression::Parenthesized(parenthesized) => unsuffixed_float_literal(parenthesized.expression()),
+        _ => None
+    };
+}
+
+fn is_zero(value: ConstantValue) -> bool
+{
+    return match value
+    {
+        ConstantValue::Bool(value) => !value,
+        ConstantValue::Int(value, _) => value == 0,
+        ConstantValue::Float(value, _) => value == 0.0
+    };
+}
+
+// Linter
+// Checks a compilation unit for code that compiles but is likely wrong or wasteful. Every rule has a default level that
+// the lint settings can change or turn off, and comments like '// hlsl-lint: disable=unused-variable' suppress findings
+// on their line, or on the next line when the comment has a line of its own
+pub struct Linter
+{
+    settings: LintSettings,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter
+{
+    pub fn new<'a>(unit: CompilationUnit<'a>, table: &SymbolTable<'a>, settings: &ParserSettings) -> Self
+    {
+        let mut linter = Self{
+            settings: settings.lints.clone(),
+            diagnostics: Vec::new()
+        };
+
+        let checker = TypeChecker::new(unit, table, settings);
+        let mut folder = ConstantFolder::new(table);
+        for node in unit.syntax().descendant_nodes()
+        {
+            if let Some(conversion) = checker.conversion_of(node)
+            {
+                linter.check_conversion(conversion, node);
+            }
+            match Expression::cast(node)
+            {
+                Some(Expression::Binary(binary)) => {
+                    linter.check_division(&mut folder, &binary.operator().text, binary.right(), node.span(), node.file_index());
+                },
+                Some(Expression::Assignment(assignment)) => {
+                    linter.check_division(&mut folder, &assignment.operator().text, assignment.right(), node.span(), node.file_index());
+                },
+                Some(Expression::Call(call)) => {
+                    if call.function_name().is_some_and(|n| n.text == "pow") && checker.intrinsic_of(node).is_some()
+                    {
+                        let base = call.arguments().first().copied();
+                        if base.and_then(|b| folder.fold(&b)).is_some_and(|v| v.as_f64() < 0.0)
+                        {
+                            let base = base.unwrap();
+                            linter.report("pow-negative-base", String::from("'pow' with a negative base returns NaN"), base.span(), base.file_index());
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        linter.check_unused(unit, table);
+        linter.check_shadowing(table);
+        for gradient in UniformityAnalysis::new(unit, table, &checker).get_divergent_gradients()
+        {
+            let message = match gradient.is_intrinsic
+            {
+                true => format!("gradient operation '{}' inside divergent control flow", gradient.name),
+                false => format!("call to '{}' performs gradient operations inside divergent control flow", gradient.name)
+            };
+            linter.report("divergent-gradient", message, gradient.call.span(), gradient.call.file_index());
+        }
+
+        linter.suppress(unit);
+        linter.diagnostics.sort_by_key(|d| (d.file_index, d.span.start));
+        return linter;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    fn report(&mut self, rule: &'static str, message: String, span: TextSpan, file_index: usize)
+    {
+        let level = self.settings.level(rule).unwrap_or(lint_rule(rule).unwrap().level);
+        if let Some(severity) = level.severity()
+        {
+            let mut diagnostic = Diagnostic::warning(message, span).in_file(file_index).with_code(rule);
+            diagnostic.severity = severity;
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    // Implicit conversions the type checker found, vectors that lose components and float literals that become half
+    fn check_conversion(&mut self, conversion: &ImplicitConversion, node: &SyntaxNode)
+    {
+        if conversion.truncates
+        {
+            self.report("implicit-truncation", String::from("implicit truncation of vector type"), node.span(), node.file_index());
+        }
+
+        let is_half = conversion.target.scalar_type().is_some_and(|s| matches!(s, ScalarType::Half | ScalarType::Float16));
+        if let Some(literal) = unsuffixed_float_literal(Expression::cast(node)).filter(|_| is_half)
+        {
+            let message = format!("float literal '{}' in a half precision expression has no 'h' suffix", literal.text);
+            self.report("half-literal", message, literal.span, literal.file_index);
+        }
+    }
+
+    fn check_division<'a>(&mut self, folder: &mut ConstantFolder<'_, 'a>, operator: &str, divisor: Option<Expression<'a>>, span: TextSpan, file_index: usize)
+    {
+        let message = match operator
+        {
+            "/" | "/=" => "division by zero is undefined",
+            "%" | "%=" => "remainder by zero is undefined",
+            _ => return
+        };
+        if divisor.and_then(|d| folder.fold(&d)).is_some_and(is_zero)
+        {
+            self.report("division-by-zero", String::from(message), span, file_index);
+        }
+    }
+
+    fn check_unused<'a>(&mut self, unit: CompilationUnit<'a>, table: &SymbolTable<'a>)
+    {
+        let referenced: HashSet<SymbolId> = unit.syntax().descendant_nodes().into_iter().filter_map(|n| table.symbol_of(n)).collect();
+        for (symbol, entry) in table.get_symbols().iter().enumerate()
+        {
+            if entry.kind == SymbolKind::LocalVariable && !referenced.contains(&symbol)
+            {
+                self.report("unused-variable", format!("unused variable '{}'", entry.name), entry.span(), entry.file_index());
+            }
+        }
+
+        // Parameters with semantics are part of the signature of an entry point and out parameters are reported by the
+        // definite assignment analysis
+        for function in (0..table.get_symbols().len()).filter_map(|s| function_body(table, s))
+        {
+            for parameter in function.params()
+            {
+                let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+                if parameter.semantic().is_some() || modifiers.direction == Some(ParameterDirection::Out) || modifiers.primitive.is_some()
+                {
+                    continue;
+                }
+                let symbol = match table.declared_by(parameter.syntax())
+                {
+                    Some(symbol) => symbol,
+                    None => continue
+                };
+                if !referenced.contains(&symbol)
+                {
+                    let entry = table.get_symbol(symbol);
+                    self.report("unused-parameter", format!("unused parameter '{}'", entry.name), entry.span(), entry.file_index());
+                }
+            }
+        }
+    }
+
+    fn check_shadowing(&mut self, table: &SymbolTable)
+    {
+        for entry in table.get_symbols()
+        {
+            if !matches!(entry.kind, SymbolKind::LocalVariable | SymbolKind::Parameter)
+            {
+                continue;
+            }
+            // Parameters of declarations without a body hide nothing
+            let scope = table.get_scope(entry.scope);
+            if entry.kind == SymbolKind::Parameter && scope.node.and_then(FunctionDecl::cast).is_none_or(|f| f.body().is_none())
+            {
+                continue;
+            }
+
+            let mut current = scope.parent;
+            while let Some(scope_id) = current
+            {
+                let scope = table.get_scope(scope_id);
+                // Locals and parameters are only hidden when they are declared before the variable
+                let shadowed = scope.get(&entry.name).iter().map(|s| table.get_symbol(*s)).find(|other| match other.kind
+                {
+                    SymbolKind::GlobalVariable => true,
+                    SymbolKind::LocalVariable | SymbolKind::Parameter => (other.file_index(), other.span().start) < (entry.file_index(), entry.span().start),
+                    _ => false
+                });
+                if let Some(other) = shadowed
+                {
+                    let message = match scope.kind
+                    {
+                        ScopeKind::Global => String::from("declaration shadows a variable in the global namespace"),
+                        ScopeKind::Namespace => format!("declaration shadows a variable in namespace '{}'",
+                            scope.owner.map(|o| table.get_symbol(o).name.as_str()).unwrap_or_default()),
+                        _ => String::from("declaration shadows a local variable")
+                    };
+                    let note = Diagnostic::note(String::from("previous declaration is here"), other.span()).in_file(other.file_index());
+                    self.report("shadowed-variable", message, entry.span(), entry.file_index());
+                    if let Some(diagnostic) = self.diagnostics.last_mut().filter(|d| d.code == Some("shadowed-variable") && d.span == entry.span())
+                    {
+                        diagnostic.notes.push(note);
+                    }
+                    break;
+                }
+                // Members of the struct around a method are not variables
+                current = scope.parent.filter(|_| scope.kind != ScopeKind::Struct);
+            }
+        }
+    }
+
+    // Drops the findings covered by suppression comments
+    fn suppress(&mut self, unit: CompilationUnit)
+    {
+        let mut newlines: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut comments = Vec::new();
+        let mut started_files = HashSet::new();
+        for token in unit.syntax().descendant_tokens()
+        {
+            // Macro expansions carry the trivia of the macro definition
+            if token.spelling.is_some()
+            {
+                continue;
+            }
+            let lines = newlines.entry(token.file_index).or_default();
+            // The first token of a file starts a line
+            let mut starts_line = started_files.insert(token.file_index);
+            for trivia in &token.leading_trivia
+            {
+                match trivia.trivia_type
+                {
+                    SyntaxTriviaType::LineComment | SyntaxTriviaType::BlockComment => {
+                        if let Some(rules) = parse_suppression(&trivia.text)
+                        {
+                            comments.push((token.file_index, trivia.span.start, starts_line, rules));
+                        }
+                    },
+                    SyntaxTriviaType::EndOfLine => starts_line = true,
+                    _ => {}
+                }
+                lines.extend(trivia.text.match_indices('\n').map(|(index, _)| trivia.span.start + index));
+            }
+            if token.token_type == SyntaxTokenType::EndOfFileToken
+            {
+                started_files.remove(&token.file_index);
+            }
+        }
+        for lines in newlines.values_mut()
+        {
+            lines.sort();
+        }
+
+        let line_of = |file_index: usize, offset: usize| -> usize
+        {
+            return newlines.get(&file_index).map(|lines| lines.partition_point(|l| *l < offset)).unwrap_or(0);
+        };
+        let suppressions: Vec<Suppression> = comments.into_iter().map(|(file_index, offset, own_line, rules)| Suppression{
+            file_index: file_index,
+            line: line_of(file_index, offset) + if own_line { 1 } else { 0 },
+            rules: rules
+        }).collect();
+
+        self.diagnostics.retain(|diagnostic| {
+            let line = line_of(diagnostic.file_index, diagnostic.span.start);
+            return !suppressions.iter().any(|s| s.file_index == diagnostic.file_index && s.line == line &&
+                s.rules.as_ref().is_none_or(|rules| rules.iter().any(|r| Some(r.as_str()) == diagnostic.code)));
+        });
+    }
+}
@@ -0,0 +1,18 @@
+pub mod class_table;
+pub mod symbol_table;
+pub mod binder;
+pub mod types;
+pub mod intrinsics;
+pub mod type_checker;
+pub mod constant_evaluator;
+pub mod buffer_layout;
+pub mod reflection;
+pub mod register_allocator;
+pub mod entry_points;
+pub mod signature_validator;
+pub mod shader_model_checker;
+pub mod call_graph;
+pub mod control_flow;
+pub mod definite_assignment;
+pub mod uniformity;
+pub mod linter;
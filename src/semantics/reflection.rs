@@ -0,0 +1,597 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::parser::{ast::*, syntax_nodes::{SyntaxNode, SyntaxNodeType}, syntax_tokens::SyntaxTokenType, ParserSettings, ShaderStage};
+
+use super::{buffer_layout::*, call_graph::function_body, constant_evaluator::{attribute_name, ConstantEvaluator}, symbol_table::{ScopeId, SymbolId, SymbolKind, SymbolTable},
+    type_checker::TypeChecker, types::*};
+
+// The register classes resources are bound to
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RegisterClass
+{
+    // b registers, constant buffers
+    ConstantBuffer,
+    // t registers, textures and read only buffers
+    ShaderResource,
+    // u registers, resources shaders can write to
+    UnorderedAccess,
+    // s registers
+    Sampler,
+}
+
+impl RegisterClass
+{
+    pub fn letter(&self) -> char
+    {
+        return match self
+        {
+            RegisterClass::ConstantBuffer => 'b',
+            RegisterClass::ShaderResource => 't',
+            RegisterClass::UnorderedAccess => 'u',
+            RegisterClass::Sampler => 's',
+        };
+    }
+
+    // The class a variable of the type is bound to, None for types that are not resources
+    pub fn of(ty: &Type) -> Option<RegisterClass>
+    {
+        return match ty
+        {
+            Type::Array(element, _) => RegisterClass::of(element),
+            Type::Resource{ kind, .. } => {
+                if kind.is_shader_parameter()
+                {
+                    None
+                }
+                else if *kind == ResourceKind::ConstantBuffer
+                {
+                    Some(RegisterClass::ConstantBuffer)
+                }
+                else if kind.is_sampler()
+                {
+                    Some(RegisterClass::Sampler)
+                }
+                else if kind.is_read_write()
+                {
+                    Some(RegisterClass::UnorderedAccess)
+                }
+                else
+                {
+                    Some(RegisterClass::ShaderResource)
+                }
+            },
+            _ => None
+        };
+    }
+}
+
+// A resource bound to the pipeline, like a texture, a sampler or a constant buffer
+#[derive(Clone, PartialEq, Debug)]
+pub struct ResourceBinding
+{
+    pub name: String,
+    // The variable or cbuffer, None for the $Globals buffer
+    pub symbol: Option<SymbolId>,
+    pub ty: Type,
+    pub class: RegisterClass,
+    // The index of an explicit register of the same class, like the 3 of register(t3)
+    pub register: Option<u32>,
+    pub space: u32,
+    // The number of bound resources, 1 for a single resource and None for unbounded arrays like Texture2D textures[]
+    pub count: Option<u32>,
+    // The binding and descriptor set of [[vk::binding(binding, set)]]
+    pub vulkan_binding: Option<(u32, u32)>,
+    // Whether the entry point or a function it calls references the resource
+    pub used: bool,
+}
+
+// An element of the input, output or patch constant signature
+#[derive(Clone, Debug)]
+pub struct SignatureElement<'a>
+{
+    // The semantic without its index, like TEXCOORD for TEXCOORD3
+    pub semantic_name: String,
+    pub semantic_index: u32,
+    // A scalar or vector, matrices take one element per column, or per row when they are row_major
+    pub ty: Type,
+    pub interpolation: Vec<Interpolation>,
+    // The parameter or field path, like input.uv or the function name for the return value
+    pub name: String,
+    // The parameter, field declarator or function the semantic is written on
+    pub declaration: &'a SyntaxNode,
+}
+
+impl<'a> SignatureElement<'a>
+{
+    pub fn is_system_value(&self) -> bool
+    {
+        return self.semantic_name.len() > 3 && self.semantic_name[..3].eq_ignore_ascii_case("SV_");
+    }
+}
+
+// Splits TEXCOORD3 into TEXCOORD and 3
+fn split_semantic(text: &str) -> (String, u32)
+{
+    let name = text.trim_end_matches(|c: char| c.is_ascii_digit());
+    let index = text[name.len()..].parse().unwrap_or(0);
+    return (String::from(name), index);
+}
+
+// The text of a string argument, like the name in [patchconstantfunc("ConstantsHS")]
+pub(crate) fn string_argument(attribute: &Attribute) -> Option<String>
+{
+    let argument = attribute.arguments().into_iter().next()?;
+    return match argument
+    {
+        Expression::Literal(literal) => match &literal.token().token_type
+        {
+            SyntaxTokenType::StringLiteralToken{ value } => Some(value.clone()),
+            _ => None
+        },
+        _ => None
+    };
+}
+
+// ShaderReflection
+// The resources, constant buffers and signatures of one entry point, like ID3D12ShaderReflection without compiling the shader
+pub struct ShaderReflection<'a>
+{
+    pub entry_point: SymbolId,
+    pub function: FunctionDecl<'a>,
+    pub stage: ShaderStage,
+    // Every resource of the compilation unit in declaration order, the $Globals buffer first when there are global uniforms
+    pub resources: Vec<ResourceBinding>,
+    pub constant_buffers: Vec<BufferLayout>,
+    pub inputs: Vec<SignatureElement<'a>>,
+    pub outputs: Vec<SignatureElement<'a>>,
+    // Outputs of the patch constant function of a hull shader, or the patch constant inputs of a domain shader
+    pub patch_constants: Vec<SignatureElement<'a>>,
+    pub thread_group_size: Option<[u32; 3]>,
+}
+
+impl<'a> ShaderReflection<'a>
+{
+    // Reflects the function with the name in the global scope, None when there is no function with a body by that name
+    pub fn reflect(unit: CompilationUnit<'a>, table: &SymbolTable<'a>, settings: &ParserSettings, entry_point: &str, stage: ShaderStage) -> Option<Self>
+    {
+        let symbol = *table.get_scope(table.global_scope()).get(entry_point).iter()
+            .find(|s| table.get_symbol(**s).kind == SymbolKind::Function && function_body(table, **s).is_some())?;
+        let function = function_body(table, symbol)?;
+
+        let evaluator = ConstantEvaluator::new(unit, table);
+        let checker = TypeChecker::new(unit, table, settings);
+        let reflector = Reflector{
+            table: table,
+            resolver: TypeResolver::new(table),
+        };
+
+        let mut entry_functions = vec![symbol];
+        let patch_constant_function = function.attributes().find(|a| attribute_name(a) == "patchconstantfunc")
+            .and_then(|a| string_argument(&a))
+            .and_then(|name| table.get_scope(table.global_scope()).get(&name).iter().copied().find(|s| function_body(table, *s).is_some()));
+        if stage == ShaderStage::Hull
+        {
+            entry_functions.extend(patch_constant_function);
+        }
+        let referenced = reflector.referenced_symbols(&checker, &entry_functions);
+
+        let layouts = LayoutCalculator::new(unit, table, settings, LayoutRules::Legacy);
+        let resources = resource_bindings(unit, table, &evaluator, layouts.buffer("$Globals"), &referenced);
+
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        let mut patch_constants = Vec::new();
+        reflector.collect_parameters(function, stage, &mut inputs, &mut outputs, &mut patch_constants);
+        reflector.collect_return_value(function, &mut outputs);
+        if stage == ShaderStage::Hull
+        {
+            if let Some(patch_function) = patch_constant_function.and_then(|s| function_body(table, s))
+            {
+                reflector.collect_return_value(patch_function, &mut patch_constants);
+            }
+        }
+
+        return Some(Self{
+            entry_point: symbol,
+            function: function,
+            stage: stage,
+            resources: resources,
+            constant_buffers: layouts.get_buffers().clone(),
+            inputs: inputs,
+            outputs: outputs,
+            patch_constants: patch_constants,
+            thread_group_size: evaluator.numthreads(function)
+        });
+    }
+
+    pub fn resource(&self, name: &str) -> Option<&ResourceBinding>
+    {
+        return self.resources.iter().find(|r| r.name == name);
+    }
+
+    pub fn constant_buffer(&self, name: &str) -> Option<&BufferLayout>
+    {
+        return self.constant_buffers.iter().find(|b| b.name == name);
+    }
+}
+
+// Every resource of the compilation unit in declaration order, the ones in referenced are marked as used
+fn resource_bindings<'t, 'a>(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>, evaluator: &ConstantEvaluator<'t, 'a>,
+    globals: Option<&BufferLayout>, referenced: &HashSet<SymbolId>) -> Vec<ResourceBinding>
+{
+    let reflector = Reflector{
+        table: table,
+        resolver: TypeResolver::new(table),
+    };
+    let mut resources = Vec::new();
+    if let Some(globals) = globals
+    {
+        resources.push(ResourceBinding{
+            name: globals.name.clone(),
+            symbol: None,
+            ty: Type::Resource{ kind: ResourceKind::ConstantBuffer, element: None, count: None },
+            class: RegisterClass::ConstantBuffer,
+            register: None,
+            space: 0,
+            count: Some(1),
+            vulkan_binding: None,
+            used: reflector.uses_globals(globals, referenced)
+        });
+    }
+    reflector.collect_resources(unit.declarations(), evaluator, referenced, &mut resources);
+    return resources;
+}
+
+// Whether the function returns or writes values with semantics, which makes it a shader rather than a helper
+pub(crate) fn has_output_semantics<'a>(table: &SymbolTable<'a>, function: FunctionDecl<'a>) -> bool
+{
+    let reflector = Reflector{
+        table: table,
+        resolver: TypeResolver::new(table),
+    };
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut patch_constants = Vec::new();
+    reflector.collect_parameters(function, ShaderStage::Vertex, &mut inputs, &mut outputs, &mut patch_constants);
+    reflector.collect_return_value(function, &mut outputs);
+    return !outputs.is_empty();
+}
+
+struct Reflector<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    resolver: TypeResolver<'t, 'a>,
+}
+
+impl<'t, 'a> Reflector<'t, 'a>
+{
+    // The symbols referenced by the functions and everything they call
+    fn referenced_symbols(&self, checker: &TypeChecker<'t, 'a>, functions: &[SymbolId]) -> HashSet<SymbolId>
+    {
+        let mut visited: HashSet<SymbolId> = functions.iter().copied().collect();
+        let mut queue: VecDeque<SymbolId> = functions.iter().copied().collect();
+        let mut referenced = HashSet::new();
+        while let Some(function) = queue.pop_front()
+        {
+            let body = match function_body(self.table, function)
+            {
+                Some(body) => body,
+                None => continue
+            };
+            for node in body.syntax().descendant_nodes()
+            {
+                match node.node_type
+                {
+                    SyntaxNodeType::IdentifierName | SyntaxNodeType::QualifiedName => {
+                        referenced.extend(self.table.symbol_of(node));
+                    },
+                    SyntaxNodeType::CallExpression => {
+                        if let Some(target) = checker.call_target(node)
+                        {
+                            if visited.insert(target)
+                            {
+                                queue.push_back(target);
+                            }
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+        return referenced;
+    }
+
+    // Global uniforms are the variables of the $Globals buffer, fields of cbuffers are global variables as well
+    fn uses_globals(&self, globals: &BufferLayout, referenced: &HashSet<SymbolId>) -> bool
+    {
+        let mut buffer_fields = HashSet::new();
+        for symbol in self.table.get_symbols()
+        {
+            if let Some(buffer) = ConstantBufferDecl::cast(symbol.declaration)
+            {
+                for field in buffer.fields()
+                {
+                    buffer_fields.extend(field.declarators().filter_map(|d| self.table.declared_by(d.syntax())));
+                }
+            }
+        }
+
+        return referenced.iter().any(|s| {
+            let entry = self.table.get_symbol(*s);
+            return entry.kind == SymbolKind::GlobalVariable && !buffer_fields.contains(s) && globals.members.iter().any(|m| m.name == entry.name);
+        });
+    }
+
+    fn collect_resources(&self, declarations: impl Iterator<Item = Declaration<'a>>, evaluator: &ConstantEvaluator<'t, 'a>,
+        referenced: &HashSet<SymbolId>, resources: &mut Vec<ResourceBinding>)
+    {
+        for declaration in declarations
+        {
+            match declaration
+            {
+                Declaration::ConstantBuffer(buffer) => {
+                    let symbol = self.table.declared_by(buffer.syntax());
+                    let used = buffer.fields().flat_map(|f| f.declarators().collect::<Vec<_>>())
+                        .any(|d| self.table.declared_by(d.syntax()).map(|s| referenced.contains(&s)).unwrap_or(false));
+                    let class = if buffer.is_tbuffer() { RegisterClass::ShaderResource } else { RegisterClass::ConstantBuffer };
+                    let (register, space) = register_of(buffer.register(), class, evaluator);
+                    resources.push(ResourceBinding{
+                        name: buffer.name().map(|n| n.text.clone()).unwrap_or_default(),
+                        symbol: symbol,
+                        ty: symbol.map(|s| self.resolver.symbol_type(s).ty).unwrap_or(Type::Error),
+                        class: class,
+                        register: register,
+                        space: space,
+                        count: Some(1),
+                        vulkan_binding: vulkan_binding(buffer.attributes(), evaluator),
+                        used: used
+                    });
+                },
+                Declaration::Variable(variable) => {
+                    for declarator in variable.declarators()
+                    {
+                        let symbol = match self.table.declared_by(declarator.syntax())
+                        {
+                            Some(symbol) => symbol,
+                            None => continue
+                        };
+                        let ty = self.resolver.symbol_type(symbol);
+                        let class = match RegisterClass::of(&ty.ty)
+                        {
+                            Some(class) if !ty.modifiers.is_static => class,
+                            _ => continue
+                        };
+                        let (register, space) = register_of(declarator.register(), class, evaluator);
+                        resources.push(ResourceBinding{
+                            name: self.table.get_symbol(symbol).name.clone(),
+                            symbol: Some(symbol),
+                            class: class,
+                            register: register,
+                            space: space,
+                            count: binding_count(&ty.ty),
+                            vulkan_binding: vulkan_binding(variable.attributes(), evaluator),
+                            used: referenced.contains(&symbol),
+                            ty: ty.ty
+                        });
+                    }
+                },
+                Declaration::Namespace(namespace) => self.collect_resources(namespace.declarations(), evaluator, referenced, resources),
+                _ => {}
+            }
+        }
+    }
+
+    // Parameters are inputs or outputs depending on their direction, patches and streams hold the elements of their type
+    fn collect_parameters(&self, function: FunctionDecl<'a>, stage: ShaderStage, inputs: &mut Vec<SignatureElement<'a>>,
+        outputs: &mut Vec<SignatureElement<'a>>, patch_constants: &mut Vec<SignatureElement<'a>>)
+    {
+        for parameter in function.params()
+        {
+            let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+            if modifiers.is_uniform
+            {
+                continue;
+            }
+            let base = parameter.param_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+            let mut ty = match parameter.declarator()
+            {
+                Some(declarator) => self.resolver.apply_array_ranks(base, declarator.array_ranks()),
+                None => base
+            };
+            let name = parameter.name().map(|n| n.text.clone()).unwrap_or_default();
+            let semantic = parameter.semantic().and_then(|s| s.name()).map(|s| split_semantic(&s.text));
+
+            let primitive = modifiers.primitive.as_deref();
+            match primitive
+            {
+                // The mesh shader index buffer and the payload are not part of a signature
+                Some("indices" | "payload") => continue,
+                // Arrays of vertices of a geometry shader input primitive, or of the mesh shader outputs
+                Some(_) => {
+                    if let Type::Array(element, _) = ty
+                    {
+                        ty = *element;
+                    }
+                },
+                None => {}
+            }
+
+            match &ty
+            {
+                Type::Resource{ kind: ResourceKind::InputPatch | ResourceKind::OutputPatch, element: Some(element), .. } => {
+                    self.flatten(element, &modifiers, None, name, parameter.syntax(), inputs);
+                },
+                Type::Resource{ kind: ResourceKind::PointStream | ResourceKind::LineStream | ResourceKind::TriangleStream, element: Some(element), .. } => {
+                    self.flatten(element, &modifiers, None, name, parameter.syntax(), outputs);
+                },
+                _ if matches!(primitive, Some("vertices" | "primitives")) => {
+                    self.flatten(&ty, &modifiers, semantic, name, parameter.syntax(), outputs);
+                },
+                // The patch constants a domain shader reads are passed in a struct next to the control points
+                Type::Struct{ .. } if stage == ShaderStage::Domain && modifiers.parameter_direction() == ParameterDirection::In => {
+                    self.flatten(&ty, &modifiers, semantic, name, parameter.syntax(), patch_constants);
+                },
+                _ => {
+                    let direction = modifiers.parameter_direction();
+                    if direction != ParameterDirection::Out
+                    {
+                        self.flatten(&ty, &modifiers, semantic.clone(), name.clone(), parameter.syntax(), inputs);
+                    }
+                    if direction != ParameterDirection::In
+                    {
+                        self.flatten(&ty, &modifiers, semantic, name, parameter.syntax(), outputs);
+                    }
+                }
+            }
+        }
+    }
+
+    // The return value is described by the semantic of the function, or by the semantics of the fields of a returned struct
+    fn collect_return_value(&self, function: FunctionDecl<'a>, elements: &mut Vec<SignatureElement<'a>>)
+    {
+        let ty = match function.return_type()
+        {
+            Some(return_type) => self.resolver.resolve(&return_type),
+            None => return
+        };
+        let name = function.name().map(|n| n.text()).unwrap_or_default();
+        let semantic = function.semantic().and_then(|s| s.name()).map(|s| split_semantic(&s.text));
+        let modifiers = TypeModifiers::from_tokens(function.modifiers());
+        self.flatten(&ty, &modifiers, semantic, name, function.syntax(), elements);
+    }
+
+    // Adds the elements of a value with the semantic, or of the fields of a struct with their own semantics
+    fn flatten(&self, ty: &Type, modifiers: &TypeModifiers, semantic: Option<(String, u32)>, name: String, declaration: &'a SyntaxNode,
+        elements: &mut Vec<SignatureElement<'a>>)
+    {
+        match ty
+        {
+            Type::Struct{ symbol, .. } => {
+                if let Some(scope) = self.table.get_symbol(*symbol).members
+                {
+                    self.flatten_fields(scope, &name, elements);
+                }
+            },
+            // Elements of an array take consecutive semantic indices
+            Type::Array(element, ArraySize::Fixed(size)) => {
+                let mut semantic = semantic;
+                for index in 0..*size
+                {
+                    let before = elements.len();
+                    self.flatten(element, modifiers, semantic.clone(), format!("{}[{}]", name, index), declaration, elements);
+                    if let Some((_, semantic_index)) = &mut semantic
+                    {
+                        *semantic_index += (elements.len() - before) as u32;
+                    }
+                }
+            },
+            Type::Matrix(scalar, rows, columns) => {
+                let (count, size) = match modifiers.orientation
+                {
+                    Some(MatrixOrientation::RowMajor) => (*rows, *columns),
+                    _ => (*columns, *rows)
+                };
+                if let Some((semantic_name, semantic_index)) = semantic
+                {
+                    for index in 0..count
+                    {
+                        elements.push(SignatureElement{
+                            semantic_name: semantic_name.clone(),
+                            semantic_index: semantic_index + index,
+                            ty: Type::Vector(*scalar, size),
+                            interpolation: modifiers.interpolation.clone(),
+                            name: name.clone(),
+                            declaration: declaration
+                        });
+                    }
+                }
+            },
+            Type::Scalar(_) | Type::Vector(..) => {
+                if let Some((semantic_name, semantic_index)) = semantic
+                {
+                    elements.push(SignatureElement{
+                        semantic_name: semantic_name,
+                        semantic_index: semantic_index,
+                        ty: ty.clone(),
+                        interpolation: modifiers.interpolation.clone(),
+                        name: name,
+                        declaration: declaration
+                    });
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn flatten_fields(&self, scope: ScopeId, path: &str, elements: &mut Vec<SignatureElement<'a>>)
+    {
+        let scope = self.table.get_scope(scope);
+        for base in &scope.bases
+        {
+            self.flatten_fields(*base, path, elements);
+        }
+        for symbol in &scope.symbols
+        {
+            let entry = self.table.get_symbol(*symbol);
+            let declarator = match VariableDeclarator::cast(entry.declaration)
+            {
+                Some(declarator) if entry.kind == SymbolKind::Field => declarator,
+                _ => continue
+            };
+            let ty = self.resolver.symbol_type(*symbol);
+            if ty.modifiers.is_static
+            {
+                continue;
+            }
+            let semantic = declarator.semantic().and_then(|s| s.name()).map(|s| split_semantic(&s.text));
+            let name = if path.is_empty() { entry.name.clone() } else { format!("{}.{}", path, entry.name) };
+            self.flatten(&ty.ty, &ty.modifiers, semantic, name, entry.declaration, elements);
+        }
+    }
+}
+
+// The register index when the register matches the class of the resource, and the register space
+fn register_of<'a>(location: Option<RegisterLocation<'a>>, class: RegisterClass, evaluator: &ConstantEvaluator<'_, 'a>) -> (Option<u32>, u32)
+{
+    let location = match location
+    {
+        Some(location) => location,
+        None => return (None, 0)
+    };
+    let register = match evaluator.register_index(location)
+    {
+        Some((letter, index)) if letter == class.letter() => Some(index),
+        _ => None
+    };
+    return (register, evaluator.register_space(location).unwrap_or(0));
+}
+
+// The binding and set of [[vk::binding(binding, set)]], the set is 0 when it is left out
+fn vulkan_binding<'a>(attributes: impl Iterator<Item = Attribute<'a>>, evaluator: &ConstantEvaluator<'_, 'a>) -> Option<(u32, u32)>
+{
+    for attribute in attributes
+    {
+        if attribute_name(&attribute) != "vk::binding"
+        {
+            continue;
+        }
+        return match evaluator.attribute_values(attribute)?.as_slice()
+        {
+            [binding] => Some((*binding, 0)),
+            [binding, set] => Some((*binding, *set)),
+            _ => None
+        };
+    }
+    return None;
+}
+
+// The number of resources an array binds, None for unbounded arrays
+fn binding_count(ty: &Type) -> Option<u32>
+{
+    return match ty
+    {
+        Type::Array(element, ArraySize::Fixed(size)) => binding_count(element).map(|count| count * size),
+        Type::Array(..) => None,
+        _ => Some(1)
+    };
+}
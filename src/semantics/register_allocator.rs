@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use crate::parser::{diagnostics::Diagnostic, syntax_tokens::TextSpan};
+
+use super::{reflection::{RegisterClass, ResourceBinding, ShaderReflection}, symbol_table::{SymbolId, SymbolTable}};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingModel
+{
+    // Registers per class and space, like FXC and DXC assign them for D3D
+    Direct3D,
+    // Bindings per descriptor set shared by all classes, like DXC assigns them for SPIR-V
+    Vulkan,
+}
+
+// The register or binding of a resource after assignment
+#[derive(Clone, PartialEq, Debug)]
+pub struct AssignedBinding
+{
+    pub name: String,
+    // The variable or cbuffer, None for the $Globals buffer
+    pub symbol: Option<SymbolId>,
+    pub class: RegisterClass,
+    // The register index for D3D or the binding number for Vulkan
+    pub index: u32,
+    // The register space for D3D or the descriptor set for Vulkan
+    pub space: u32,
+    // The number of registers taken, None for unbounded arrays that take every register after the index
+    pub count: Option<u32>,
+    // Whether the index comes from register(...) or [[vk::binding]] in the source
+    pub is_explicit: bool,
+}
+
+// A range of registers taken by a resource, the end is exclusive
+struct Range
+{
+    start: u64,
+    end: u64,
+    resource: usize,
+}
+
+// RegisterAllocator
+// Assigns registers to the resources an entry point uses that were declared without one. Explicit bindings are kept and the other
+// resources get the lowest free registers in declaration order, so 'Texture2D a; Texture2D b : register(t0);' puts a in t1.
+// Unused resources are left out like the compilers strip them, entry points may share a register between resources they do not both use
+pub struct RegisterAllocator<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    model: BindingModel,
+    bindings: Vec<AssignedBinding>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> RegisterAllocator<'t, 'a>
+{
+    pub fn new(table: &'t SymbolTable<'a>, reflection: &ShaderReflection<'a>, model: BindingModel) -> Self
+    {
+        let resources: Vec<ResourceBinding> = reflection.resources.iter().filter(|r| r.used).cloned().collect();
+        let mut allocator = Self{
+            table: table,
+            model: model,
+            bindings: Vec::new(),
+            diagnostics: Vec::new()
+        };
+        allocator.assign(&resources);
+        return allocator;
+    }
+
+    pub fn get_bindings(&self) -> &Vec<AssignedBinding>
+    {
+        return &self.bindings;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    pub fn binding(&self, name: &str) -> Option<&AssignedBinding>
+    {
+        return self.bindings.iter().find(|b| b.name == name);
+    }
+
+    fn assign(&mut self, resources: &[ResourceBinding])
+    {
+        self.bindings = resources.iter().map(|resource| AssignedBinding{
+            name: resource.name.clone(),
+            symbol: resource.symbol,
+            class: resource.class,
+            index: 0,
+            space: 0,
+            // Vulkan binds arrays of resources to a single binding
+            count: if self.model == BindingModel::Vulkan { Some(1) } else { resource.count },
+            is_explicit: false
+        }).collect();
+
+        // Ranges taken per register class and space, all classes share the bindings of a descriptor set
+        let mut taken: HashMap<(Option<RegisterClass>, u32), Vec<Range>> = HashMap::new();
+        for (index, resource) in resources.iter().enumerate()
+        {
+            let explicit = match self.model
+            {
+                BindingModel::Direct3D => resource.register.map(|register| (register, resource.space)),
+                BindingModel::Vulkan => resource.vulkan_binding.or(resource.register.map(|register| (register, resource.space)))
+            };
+            if let Some((register, space)) = explicit
+            {
+                self.bindings[index].index = register;
+                self.bindings[index].space = space;
+                self.bindings[index].is_explicit = true;
+                let ranges = taken.entry((self.class_key(resource.class), space)).or_default();
+                let range = self.range(index, register);
+                if let Some(other) = ranges.iter().find(|r| r.start < range.end && range.start < r.end)
+                {
+                    let other = other.resource;
+                    self.report_overlap(index, other);
+                }
+                ranges.push(range);
+            }
+        }
+
+        // Resources without a binding go to space 0 or descriptor set 0
+        for (index, resource) in resources.iter().enumerate()
+        {
+            if self.bindings[index].is_explicit
+            {
+                continue;
+            }
+            let ranges = taken.entry((self.class_key(resource.class), 0)).or_default();
+            let count = self.bindings[index].count.map(|c| c as u64).unwrap_or(u64::MAX);
+            let mut start: u64 = 0;
+            while let Some(other) = ranges.iter().filter(|r| r.start < start.saturating_add(count) && start < r.end).map(|r| r.end).max()
+            {
+                start = other;
+            }
+
+            // Nothing is free after an unbounded array, the index saturates
+            self.bindings[index].index = u32::try_from(start).unwrap_or(u32::MAX);
+            let range = self.range(index, self.bindings[index].index);
+            ranges.push(range);
+        }
+    }
+
+    fn class_key(&self, class: RegisterClass) -> Option<RegisterClass>
+    {
+        return match self.model
+        {
+            BindingModel::Direct3D => Some(class),
+            BindingModel::Vulkan => None
+        };
+    }
+
+    fn range(&self, resource: usize, start: u32) -> Range
+    {
+        let start = start as u64;
+        return Range{
+            start: start,
+            end: self.bindings[resource].count.map(|c| start + c.max(1) as u64).unwrap_or(u64::MAX),
+            resource: resource
+        };
+    }
+
+    fn report_overlap(&mut self, resource: usize, other: usize)
+    {
+        let binding = &self.bindings[resource];
+        let other = &self.bindings[other];
+        let diagnostic = match self.model
+        {
+            BindingModel::Direct3D => Diagnostic::error(format!("resource {} at register {}{} overlaps with resource {} at register {}{}, space {}",
+                binding.name, binding.class.letter(), binding.index, other.name, other.class.letter(), other.index, binding.space), self.span_of(binding)),
+            BindingModel::Vulkan => Diagnostic::warning(format!("resource binding #{} in descriptor set #{} already assigned", binding.index, binding.space),
+                self.span_of(binding))
+        };
+        let file_index = binding.symbol.map(|s| self.table.get_symbol(s).file_index()).unwrap_or(0);
+        self.diagnostics.push(diagnostic.in_file(file_index));
+    }
+
+    fn span_of(&self, binding: &AssignedBinding) -> TextSpan
+    {
+        return binding.symbol.map(|s| self.table.get_symbol(s).span()).unwrap_or_default();
+    }
+}
@@ -0,0 +1,114 @@
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_tokens::{SyntaxToken, TextSpan}, ParserSettings, ShaderModel, ShaderStage};
+
+use super::{binder::is_builtin_variable_name, entry_points::EntryPointFinder, symbol_table::SymbolTable, type_checker::TypeChecker, types::*};
+
+// The shader model a type keyword like float16_t2 or RasterizerOrderedTexture2D needs, and whether it is a native 16-bit type
+fn type_keyword_model(text: &str) -> Option<(ShaderModel, bool)>
+{
+    if let Some(kind) = ResourceKind::from_name(text)
+    {
+        return kind.is_rasterizer_ordered().then_some((ShaderModel::new(5, 1), false));
+    }
+    return match Type::from_keyword(text)?.scalar_type()?
+    {
+        ScalarType::Float16 | ScalarType::Int16 | ScalarType::Uint16 => Some((ShaderModel::new(6, 2), true)),
+        ScalarType::Int64 | ScalarType::Uint64 => Some((ShaderModel::new(6, 0), false)),
+        _ => None
+    };
+}
+
+// ShaderModelChecker
+// Reports the features a file uses that the shader model of the target does not have, like wave intrinsics in ps_5_0 or
+// float16_t without -enable-16bit-types, before the file goes through a compiler
+pub struct ShaderModelChecker
+{
+    shader_model: ShaderModel,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ShaderModelChecker
+{
+    pub fn new<'a>(unit: CompilationUnit<'a>, table: &SymbolTable<'a>, settings: &ParserSettings) -> Self
+    {
+        let target = match settings.target
+        {
+            Some(target) => target,
+            None => return Self{ shader_model: ShaderModel::new(0, 0), diagnostics: Vec::new() }
+        };
+        let mut checker = Self{
+            shader_model: target.shader_model,
+            diagnostics: Vec::new()
+        };
+
+        for token in unit.syntax().descendant_tokens()
+        {
+            if let Some((shader_model, is_16bit)) = type_keyword_model(&token.text)
+            {
+                if !checker.require(&token.text, shader_model, token.span, token.file_index) && is_16bit && !settings.enable_16bit_types
+                {
+                    checker.error(format!("'{}' requires the -enable-16bit-types option", token.text), token.span, token.file_index);
+                }
+            }
+        }
+
+        let type_checker = TypeChecker::new(unit, table, settings);
+        for node in unit.syntax().descendant_nodes()
+        {
+            match Expression::cast(node)
+            {
+                Some(Expression::Identifier(identifier)) => {
+                    let token = identifier.identifier();
+                    if is_builtin_variable_name(&token.text) && table.symbol_of(node).is_none()
+                    {
+                        checker.require_token(token, ShaderModel::new(6, 6));
+                    }
+                },
+                Some(Expression::Call(call)) => {
+                    let (intrinsic, name) = match (type_checker.intrinsic_of(node), call.function_name())
+                    {
+                        (Some(intrinsic), Some(name)) => (intrinsic, name),
+                        _ => continue
+                    };
+                    checker.require_token(name, intrinsic.overload.shader_model);
+                },
+                _ => {}
+            }
+        }
+
+        for entry_point in EntryPointFinder::new(unit, table).get_entry_points()
+        {
+            if matches!(entry_point.stage, ShaderStage::Mesh | ShaderStage::Amplification) && checker.shader_model < ShaderModel::new(6, 5)
+            {
+                let name = entry_point.function.name().unwrap();
+                checker.error(format!("{} shaders require shader model 6.5 or higher", entry_point.stage.name()), name.span(), name.file_index());
+            }
+        }
+        return checker;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    fn require_token(&mut self, token: &SyntaxToken, shader_model: ShaderModel)
+    {
+        self.require(&token.text, shader_model, token.span, token.file_index);
+    }
+
+    // Reports the feature when the target is below the shader model and returns whether it was reported
+    fn require(&mut self, feature: &str, shader_model: ShaderModel, span: TextSpan, file_index: usize) -> bool
+    {
+        if self.shader_model >= shader_model
+        {
+            return false;
+        }
+        self.error(format!("'{}' requires shader model {} or higher", feature, shader_model), span, file_index);
+        return true;
+    }
+
+    fn error(&mut self, message: String, span: TextSpan, file_index: usize)
+    {
+        self.diagnostics.push(Diagnostic::error(message, span).in_file(file_index));
+    }
+}
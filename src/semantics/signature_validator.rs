@@ -0,0 +1,576 @@
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::SyntaxNode, syntax_tokens::TextSpan, ParserSettings, ShaderModel, ShaderStage};
+
+use super::{constant_evaluator::{attribute_name, ConstantEvaluator}, entry_points::{global_function, EntryPoint},
+    reflection::{string_argument, ShaderReflection, SignatureElement}, symbol_table::{ScopeId, SymbolKind, SymbolTable}, types::*};
+
+use ShaderStage::*;
+
+// Where a system value semantic may be written and the first shader model that has it
+struct SystemValue
+{
+    name: &'static str,
+    inputs: &'static [ShaderStage],
+    outputs: &'static [ShaderStage],
+    // Tessellation factors are only passed between the patch constant function and the domain shader
+    patch_constant: bool,
+    shader_model: ShaderModel,
+}
+
+const fn system_value(name: &'static str, inputs: &'static [ShaderStage], outputs: &'static [ShaderStage], major: u32, minor: u32) -> SystemValue
+{
+    return SystemValue{ name: name, inputs: inputs, outputs: outputs, patch_constant: false, shader_model: ShaderModel::new(major, minor) };
+}
+
+const PRE_RASTER: &[ShaderStage] = &[Vertex, Hull, Domain, Geometry, Mesh];
+const THREAD_STAGES: &[ShaderStage] = &[Compute, Mesh, Amplification];
+
+const SYSTEM_VALUES: &[SystemValue] = &[
+    system_value("SV_Position", &[Vertex, Hull, Domain, Geometry, Pixel], PRE_RASTER, 4, 0),
+    system_value("SV_ClipDistance", &[Hull, Domain, Geometry, Pixel], PRE_RASTER, 4, 0),
+    system_value("SV_CullDistance", &[Hull, Domain, Geometry, Pixel], PRE_RASTER, 4, 0),
+    system_value("SV_Target", &[], &[Pixel], 4, 0),
+    system_value("SV_Depth", &[], &[Pixel], 4, 0),
+    system_value("SV_DepthGreaterEqual", &[], &[Pixel], 5, 0),
+    system_value("SV_DepthLessEqual", &[], &[Pixel], 5, 0),
+    system_value("SV_StencilRef", &[], &[Pixel], 5, 0),
+    system_value("SV_Coverage", &[Pixel], &[Pixel], 4, 1),
+    system_value("SV_InnerCoverage", &[Pixel], &[], 5, 0),
+    system_value("SV_IsFrontFace", &[Pixel], &[Geometry], 4, 0),
+    system_value("SV_SampleIndex", &[Pixel], &[], 4, 1),
+    system_value("SV_VertexID", &[Vertex], &[], 4, 0),
+    system_value("SV_InstanceID", &[Vertex, Hull, Domain, Geometry, Pixel], &[Vertex, Hull, Domain, Geometry], 4, 0),
+    system_value("SV_PrimitiveID", &[Hull, Domain, Geometry, Pixel], &[Geometry, Mesh], 4, 0),
+    system_value("SV_RenderTargetArrayIndex", &[Pixel], &[Vertex, Domain, Geometry, Mesh], 4, 0),
+    system_value("SV_ViewportArrayIndex", &[Pixel], &[Vertex, Domain, Geometry, Mesh], 4, 0),
+    system_value("SV_GSInstanceID", &[Geometry], &[], 5, 0),
+    system_value("SV_OutputControlPointID", &[Hull], &[], 5, 0),
+    system_value("SV_DomainLocation", &[Domain], &[], 5, 0),
+    SystemValue{ name: "SV_TessFactor", inputs: &[], outputs: &[], patch_constant: true, shader_model: ShaderModel::new(5, 0) },
+    SystemValue{ name: "SV_InsideTessFactor", inputs: &[], outputs: &[], patch_constant: true, shader_model: ShaderModel::new(5, 0) },
+    system_value("SV_DispatchThreadID", THREAD_STAGES, &[], 4, 0),
+    system_value("SV_GroupID", THREAD_STAGES, &[], 4, 0),
+    system_value("SV_GroupThreadID", THREAD_STAGES, &[], 4, 0),
+    system_value("SV_GroupIndex", THREAD_STAGES, &[], 4, 0),
+    system_value("SV_ViewID", &[Vertex, Hull, Domain, Geometry, Pixel, Mesh], &[], 6, 1),
+    system_value("SV_Barycentrics", &[Pixel], &[], 6, 1),
+    system_value("SV_ShadingRate", &[Pixel], &[Vertex, Geometry, Mesh], 6, 4),
+    system_value("SV_CullPrimitive", &[], &[Mesh], 6, 5),
+    system_value("SV_StartVertexLocation", &[Vertex], &[], 6, 8),
+    system_value("SV_StartInstanceLocation", &[Vertex], &[], 6, 8),
+];
+
+// The number of vertices of a geometry shader input primitive
+fn primitive_vertex_count(primitive: &str) -> Option<u32>
+{
+    return match primitive
+    {
+        "point" => Some(1),
+        "line" => Some(2),
+        "triangle" => Some(3),
+        "lineadj" => Some(4),
+        "triangleadj" => Some(6),
+        _ => None
+    };
+}
+
+// The SV_TessFactor and SV_InsideTessFactor counts of a tessellator domain
+fn tess_factor_counts(domain: &str) -> Option<(usize, usize)>
+{
+    return match domain
+    {
+        "tri" => Some((3, 1)),
+        "quad" => Some((4, 2)),
+        "isoline" => Some((2, 0)),
+        _ => None
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SignatureKind
+{
+    Input,
+    Output,
+    PatchConstant,
+}
+
+// SignatureValidator
+// Checks the signature of an entry point against the rules of its stage and shader model, like the system values it may read
+// and write, the attributes it needs and the sizes of patches, primitives and thread groups
+pub struct SignatureValidator<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    resolver: TypeResolver<'t, 'a>,
+    evaluator: ConstantEvaluator<'t, 'a>,
+    stage: ShaderStage,
+    shader_model: ShaderModel,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> SignatureValidator<'t, 'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>, settings: &ParserSettings, entry_point: &EntryPoint<'a>, shader_model: ShaderModel) -> Self
+    {
+        let mut validator = Self{
+            table: table,
+            resolver: TypeResolver::new(table),
+            evaluator: ConstantEvaluator::new(unit, table),
+            stage: entry_point.stage,
+            shader_model: shader_model,
+            diagnostics: Vec::new()
+        };
+
+        // Ray tracing shaders are library functions with their own parameter rules
+        if entry_point.stage.is_ray_tracing()
+        {
+            return validator;
+        }
+        let reflection = match ShaderReflection::reflect(unit, table, settings, &entry_point.name, entry_point.stage)
+        {
+            Some(reflection) => reflection,
+            None => return validator
+        };
+
+        let function = entry_point.function;
+        validator.check_semantics_defined(function);
+        validator.check_system_values(&reflection.inputs, SignatureKind::Input);
+        validator.check_system_values(&reflection.outputs, SignatureKind::Output);
+        validator.check_system_values(&reflection.patch_constants, SignatureKind::PatchConstant);
+        match entry_point.stage
+        {
+            Vertex => validator.check_vertex(function, &reflection),
+            Hull => validator.check_hull(function, &reflection),
+            Domain => validator.check_domain(function),
+            Geometry => validator.check_geometry(function),
+            Compute | Amplification => validator.check_thread_group(function, &reflection),
+            Mesh => {
+                validator.check_thread_group(function, &reflection);
+                validator.check_mesh(function);
+            },
+            _ => {}
+        }
+        return validator;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    fn check_system_values(&mut self, elements: &[SignatureElement<'a>], kind: SignatureKind)
+    {
+        for element in elements.iter().filter(|e| e.is_system_value())
+        {
+            let system_value = match SYSTEM_VALUES.iter().find(|v| v.name.eq_ignore_ascii_case(&element.semantic_name))
+            {
+                Some(system_value) => system_value,
+                None => {
+                    self.error(format!("unknown system value semantic '{}'", element.semantic_name), element_span(element), element.declaration);
+                    continue;
+                }
+            };
+
+            let is_valid = match kind
+            {
+                SignatureKind::Input => system_value.inputs.contains(&self.stage),
+                SignatureKind::Output => system_value.outputs.contains(&self.stage),
+                SignatureKind::PatchConstant => system_value.patch_constant
+            };
+            if !is_valid
+            {
+                self.error(format!("Semantic '{}' is invalid as {} {:?}", element.semantic_name, self.stage.profile_prefix(), kind), element_span(element), element.declaration);
+            }
+            else if self.shader_model < system_value.shader_model
+            {
+                self.error(format!("semantic '{}' requires shader model {} or higher", element.semantic_name, system_value.shader_model),
+                    element_span(element), element.declaration);
+            }
+        }
+
+        // Compute shaders only read thread ids, there is no input assembler to fill other semantics
+        if kind == SignatureKind::Input && matches!(self.stage, Compute | Amplification)
+        {
+            for element in elements.iter().filter(|e| !e.is_system_value())
+            {
+                self.error(format!("Semantic '{}' is invalid as {} Input", element.semantic_name, self.stage.profile_prefix()), element_span(element), element.declaration);
+            }
+        }
+    }
+
+    // Every value that is passed in or out of an entry point needs a semantic, structs need one on each of their fields
+    fn check_semantics_defined(&mut self, function: FunctionDecl<'a>)
+    {
+        for parameter in function.params()
+        {
+            let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+            if modifiers.is_uniform || matches!(modifiers.primitive.as_deref(), Some("indices" | "payload")) || parameter.semantic().is_some()
+            {
+                continue;
+            }
+            let base = parameter.param_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+            let ty = match parameter.declarator()
+            {
+                Some(declarator) => self.resolver.apply_array_ranks(base, declarator.array_ranks()),
+                None => base
+            };
+            let ty = match ty
+            {
+                Type::Resource{ kind, element: Some(element), .. } if kind.is_shader_parameter() => *element,
+                ty => ty
+            };
+            if let Some(node) = self.missing_semantic(&ty, parameter.syntax())
+            {
+                self.error(String::from("Semantic must be defined for all parameters of an entry function or patch constant function"), node.span(), node);
+            }
+        }
+
+        if function.semantic().is_none()
+        {
+            let ty = function.return_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Void);
+            if let Some(node) = self.missing_semantic(&ty, function.syntax())
+            {
+                let span = if std::ptr::eq(node, function.syntax()) { function.name().map(|n| n.span()).unwrap_or(node.span()) } else { node.span() };
+                self.error(String::from("Semantic must be defined for all outputs of an entry function or patch constant function"), span, node);
+            }
+        }
+    }
+
+    // The declaration of the first value of the type that has no semantic
+    fn missing_semantic(&self, ty: &Type, declaration: &'a SyntaxNode) -> Option<&'a SyntaxNode>
+    {
+        return match ty
+        {
+            Type::Scalar(_) | Type::Vector(..) | Type::Matrix(..) => Some(declaration),
+            Type::Array(element, _) => self.missing_semantic(element, declaration),
+            Type::Struct{ symbol, .. } => self.table.get_symbol(*symbol).members.and_then(|scope| self.missing_field_semantic(scope)),
+            _ => None
+        };
+    }
+
+    fn missing_field_semantic(&self, scope: ScopeId) -> Option<&'a SyntaxNode>
+    {
+        let scope = self.table.get_scope(scope);
+        for base in &scope.bases
+        {
+            if let Some(node) = self.missing_field_semantic(*base)
+            {
+                return Some(node);
+            }
+        }
+        for symbol in &scope.symbols
+        {
+            let entry = self.table.get_symbol(*symbol);
+            let declarator = match VariableDeclarator::cast(entry.declaration)
+            {
+                Some(declarator) if entry.kind == SymbolKind::Field => declarator,
+                _ => continue
+            };
+            let ty = self.resolver.symbol_type(*symbol);
+            if ty.modifiers.is_static || declarator.semantic().is_some()
+            {
+                continue;
+            }
+            if let Some(node) = self.missing_semantic(&ty.ty, entry.declaration)
+            {
+                return Some(node);
+            }
+        }
+        return None;
+    }
+
+    // A vertex shader that does not write a position can only feed tessellation or stream output
+    fn check_vertex(&mut self, function: FunctionDecl<'a>, reflection: &ShaderReflection<'a>)
+    {
+        let writes_position = reflection.outputs.iter().any(|e| e.semantic_name.eq_ignore_ascii_case("SV_Position") || e.semantic_name.eq_ignore_ascii_case("POSITION"));
+        if !writes_position
+        {
+            self.warning(String::from("vertex shader does not output SV_Position"), function_span(&function), function.syntax());
+        }
+    }
+
+    fn check_hull(&mut self, function: FunctionDecl<'a>, reflection: &ShaderReflection<'a>)
+    {
+        for attribute in ["domain", "partitioning", "outputtopology", "outputcontrolpoints", "patchconstantfunc"]
+        {
+            self.require_attribute(function, attribute);
+        }
+
+        let output_control_points = self.attribute_value(function, "outputcontrolpoints");
+        if let Some((count, node)) = output_control_points
+        {
+            if count > 32
+            {
+                self.error(format!("outputcontrolpoints {} outside valid range [0..32]", count), node.span(), node);
+            }
+        }
+        let input_count = self.check_patches(function, None, None);
+
+        let name = match function.attributes().find(|a| attribute_name(a) == "patchconstantfunc").and_then(|a| string_argument(&a).map(|n| (n, a)))
+        {
+            Some((name, attribute)) => match global_function(self.table, &name)
+            {
+                Some((_, patch_function)) => {
+                    self.check_semantics_defined(patch_function);
+                    self.check_patches(patch_function, input_count, output_control_points.map(|(count, _)| count));
+                    name
+                },
+                None => {
+                    self.error(format!("patch constant function '{}' must be defined", name), attribute.span(), attribute.syntax());
+                    return;
+                }
+            },
+            None => return
+        };
+
+        let domain = function.attributes().find(|a| attribute_name(a) == "domain").and_then(|a| string_argument(&a));
+        if let Some((tess_factors, inside_tess_factors)) = domain.as_deref().and_then(tess_factor_counts)
+        {
+            let count = |semantic: &str| reflection.patch_constants.iter().filter(|e| e.semantic_name.eq_ignore_ascii_case(semantic)).count();
+            let written = [("SV_TessFactor", tess_factors, count("SV_TessFactor")), ("SV_InsideTessFactor", inside_tess_factors, count("SV_InsideTessFactor"))];
+            for (semantic, expected, found) in written
+            {
+                if expected != found
+                {
+                    self.error(format!("the '{}' domain requires {} {} outputs but patch constant function '{}' has {}", domain.as_deref().unwrap(), expected, semantic, name, found),
+                        function_span(&function), function.syntax());
+                }
+            }
+        }
+    }
+
+    // Checks the control point counts of the InputPatch and OutputPatch parameters and returns the InputPatch count
+    fn check_patches(&mut self, function: FunctionDecl<'a>, input_count: Option<u32>, output_count: Option<u32>) -> Option<u32>
+    {
+        let mut found = None;
+        for parameter in function.params()
+        {
+            let (kind, count) = match parameter.param_type().map(|t| self.resolver.resolve(&t))
+            {
+                Some(Type::Resource{ kind: kind @ (ResourceKind::InputPatch | ResourceKind::OutputPatch), count: Some(count), .. }) => (kind, count),
+                _ => continue
+            };
+            if !(1..=32).contains(&count)
+            {
+                self.error(format!("{} control point count {} outside valid range [1..32]", kind.name(), count), parameter.span(), parameter.syntax());
+                continue;
+            }
+            let expected = if kind == ResourceKind::InputPatch { input_count } else { output_count };
+            match expected
+            {
+                Some(expected) if expected != count => {
+                    let source = if kind == ResourceKind::InputPatch { "the hull shader InputPatch" } else { "outputcontrolpoints" };
+                    self.error(format!("{} control point count {} does not match {} count {}", kind.name(), count, source, expected), parameter.span(), parameter.syntax());
+                },
+                _ => {}
+            }
+            if kind == ResourceKind::InputPatch
+            {
+                found = Some(count);
+            }
+        }
+        return found;
+    }
+
+    fn check_domain(&mut self, function: FunctionDecl<'a>)
+    {
+        self.require_attribute(function, "domain");
+        self.check_patches(function, None, None);
+    }
+
+    fn check_geometry(&mut self, function: FunctionDecl<'a>)
+    {
+        self.require_attribute(function, "maxvertexcount");
+
+        let mut has_stream = false;
+        let mut has_primitive = false;
+        for parameter in function.params()
+        {
+            let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+            let base = parameter.param_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+            if let Type::Resource{ kind: ResourceKind::PointStream | ResourceKind::LineStream | ResourceKind::TriangleStream, .. } = base
+            {
+                has_stream = true;
+                if modifiers.direction != Some(ParameterDirection::InOut)
+                {
+                    self.error(String::from("stream-output object must be an inout parameter"), parameter.span(), parameter.syntax());
+                }
+                continue;
+            }
+
+            let expected = match modifiers.primitive.as_deref().and_then(primitive_vertex_count)
+            {
+                Some(expected) => expected,
+                None => continue
+            };
+            has_primitive = true;
+            let ty = match parameter.declarator()
+            {
+                Some(declarator) => self.resolver.apply_array_ranks(base, declarator.array_ranks()),
+                None => base
+            };
+            if !matches!(ty, Type::Array(_, ArraySize::Fixed(size)) if size == expected)
+            {
+                self.error(format!("input primitive '{}' requires an array of {} vertices", modifiers.primitive.as_deref().unwrap(), expected), parameter.span(), parameter.syntax());
+            }
+        }
+
+        if !has_primitive
+        {
+            self.error(String::from("geometry entry point must have an input primitive parameter"), function_span(&function), function.syntax());
+        }
+        if !has_stream
+        {
+            self.error(String::from("geometry entry point must have a stream output parameter"), function_span(&function), function.syntax());
+        }
+    }
+
+    fn check_thread_group(&mut self, function: FunctionDecl<'a>, reflection: &ShaderReflection<'a>)
+    {
+        if !self.require_attribute(function, "numthreads")
+        {
+            return;
+        }
+        let size = match reflection.thread_group_size
+        {
+            Some(size) => size,
+            None => return
+        };
+
+        let (dimension_limits, total_limit) = match self.stage
+        {
+            Mesh | Amplification => ([128, 128, 128], 128),
+            _ if self.shader_model < ShaderModel::new(5, 0) => ([768, 768, 1], 768),
+            _ => ([1024, 1024, 64], 1024)
+        };
+        let attribute = function.attributes().find(|a| attribute_name(a) == "numthreads").unwrap();
+        for (index, dimension) in ["X", "Y", "Z"].iter().enumerate()
+        {
+            if size[index] < 1 || size[index] > dimension_limits[index]
+            {
+                self.error(format!("Declared Thread Group {} size {} outside valid range [1..{}]", dimension, size[index], dimension_limits[index]),
+                    attribute.span(), attribute.syntax());
+            }
+        }
+        let total = size.iter().map(|s| *s as u64).product::<u64>();
+        if total > total_limit
+        {
+            self.error(format!("Declared Thread Group Count {} (X*Y*Z) is beyond the valid maximum of {}", total, total_limit), attribute.span(), attribute.syntax());
+        }
+    }
+
+    fn check_mesh(&mut self, function: FunctionDecl<'a>)
+    {
+        self.require_attribute(function, "outputtopology");
+        let topology = function.attributes().find(|a| attribute_name(a) == "outputtopology").and_then(|a| string_argument(&a));
+
+        let mut found: Vec<&'static str> = Vec::new();
+        for parameter in function.params()
+        {
+            let modifiers = TypeModifiers::from_tokens(parameter.modifiers());
+            let object = match modifiers.primitive.as_deref()
+            {
+                Some("indices") => "indices",
+                Some("vertices") => "vertices",
+                Some("primitives") => "primitives",
+                Some("payload") => "payload",
+                _ => continue
+            };
+            if found.contains(&object)
+            {
+                self.error(format!("only one '{}' parameter is allowed", object), parameter.span(), parameter.syntax());
+            }
+            found.push(object);
+
+            let direction = modifiers.parameter_direction();
+            if object == "payload"
+            {
+                if direction != ParameterDirection::In
+                {
+                    self.error(String::from("'payload' object must be an in parameter"), parameter.span(), parameter.syntax());
+                }
+                continue;
+            }
+            if direction != ParameterDirection::Out
+            {
+                self.error(format!("'{}' object must be an out parameter", object), parameter.span(), parameter.syntax());
+            }
+
+            let base = parameter.param_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+            let ty = match parameter.declarator()
+            {
+                Some(declarator) => self.resolver.apply_array_ranks(base, declarator.array_ranks()),
+                None => base
+            };
+            let element = match &ty
+            {
+                Type::Array(element, ArraySize::Fixed(_)) => element,
+                _ => {
+                    self.error(format!("'{}' object must be an array", object), parameter.span(), parameter.syntax());
+                    continue;
+                }
+            };
+            let expected = match topology.as_deref()
+            {
+                Some("triangle") => Some(Type::Vector(ScalarType::Uint, 3)),
+                Some("line") => Some(Type::Vector(ScalarType::Uint, 2)),
+                _ => None
+            };
+            if let Some(expected) = expected.filter(|e| object == "indices" && **element != *e)
+            {
+                self.error(format!("'indices' object must be an array of {} for {} topology", expected, topology.as_deref().unwrap()), parameter.span(), parameter.syntax());
+            }
+        }
+
+        for required in ["indices", "vertices"]
+        {
+            if !found.contains(&required)
+            {
+                self.error(format!("mesh entry point must have an out {} parameter", required), function_span(&function), function.syntax());
+            }
+        }
+    }
+
+    // Reports a missing attribute and returns whether the function has it
+    fn require_attribute(&mut self, function: FunctionDecl<'a>, name: &str) -> bool
+    {
+        if function.attributes().any(|a| attribute_name(&a) == name)
+        {
+            return true;
+        }
+        self.error(format!("{} entry point must have the {} attribute", self.stage.name(), name), function_span(&function), function.syntax());
+        return false;
+    }
+
+    // The first integer argument of an attribute and the node to report it at
+    fn attribute_value(&self, function: FunctionDecl<'a>, name: &str) -> Option<(u32, &'a SyntaxNode)>
+    {
+        let attribute = function.attributes().find(|a| attribute_name(a) == name)?;
+        let value = self.evaluator.attribute_values(attribute)?.first().copied()?;
+        return Some((value, attribute.syntax()));
+    }
+
+    fn error(&mut self, message: String, span: TextSpan, node: &SyntaxNode)
+    {
+        self.diagnostics.push(Diagnostic::error(message, span).in_file(node.file_index()));
+    }
+
+    fn warning(&mut self, message: String, span: TextSpan, node: &SyntaxNode)
+    {
+        self.diagnostics.push(Diagnostic::warning(message, span).in_file(node.file_index()));
+    }
+}
+
+// Entry point errors are reported at the name of the function
+fn function_span(function: &FunctionDecl) -> TextSpan
+{
+    return function.name().map(|n| n.span()).unwrap_or(function.span());
+}
+
+// The semantic of a return value, or the parameter or field with the semantic
+fn element_span(element: &SignatureElement) -> TextSpan
+{
+    return match FunctionDecl::cast(element.declaration)
+    {
+        Some(function) => function.semantic().map(|s| s.span()).unwrap_or(function_span(&function)),
+        None => element.declaration.span()
+    };
+}
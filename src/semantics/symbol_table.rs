@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+
+use crate::parser::{diagnostics::Diagnostic, syntax_nodes::SyntaxNode, syntax_tokens::{SyntaxToken, TextSpan}};
+
+pub type SymbolId = usize;
+pub type ScopeId = usize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SymbolKind
+{
+    Namespace,
+    Struct,
+    Class,
+    Interface,
+    Typedef,
+    TemplateParameter,
+    Function,
+    Method,
+    GlobalVariable,
+    ConstantBuffer,
+    Field,
+    Parameter,
+    LocalVariable,
+    Technique,
+}
+
+impl SymbolKind
+{
+    pub fn is_type(&self) -> bool
+    {
+        return matches!(self, SymbolKind::Struct | SymbolKind::Class | SymbolKind::Interface | SymbolKind::Typedef | SymbolKind::TemplateParameter);
+    }
+
+    pub fn is_function(&self) -> bool
+    {
+        return matches!(self, SymbolKind::Function | SymbolKind::Method);
+    }
+
+    pub fn is_variable(&self) -> bool
+    {
+        return matches!(self, SymbolKind::GlobalVariable | SymbolKind::Field | SymbolKind::Parameter | SymbolKind::LocalVariable);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol<'a>
+{
+    pub name: String,
+    pub kind: SymbolKind,
+    // The function, struct, declarator, parameter or namespace node that declares the symbol
+    pub declaration: &'a SyntaxNode,
+    // The variable declaration or typedef holding the declarator, it has the type and modifiers
+    pub parent: Option<&'a SyntaxNode>,
+    // The body of a function that was declared before it was defined
+    pub definition: Option<&'a SyntaxNode>,
+    pub name_token: Option<&'a SyntaxToken>,
+    // Scope the symbol is declared in
+    pub scope: ScopeId,
+    // Scope with the members of a namespace, struct or function
+    pub members: Option<ScopeId>,
+}
+
+impl<'a> Symbol<'a>
+{
+    pub fn span(&self) -> TextSpan
+    {
+        return match self.name_token
+        {
+            Some(token) => token.span,
+            None => self.declaration.span()
+        };
+    }
+
+    pub fn file_index(&self) -> usize
+    {
+        return match self.name_token
+        {
+            Some(token) => token.file_index,
+            None => self.declaration.file_index()
+        };
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScopeKind
+{
+    Global,
+    Namespace,
+    Struct,
+    Function,
+    Block,
+}
+
+#[derive(Clone, Debug)]
+pub struct Scope<'a>
+{
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    // The node the scope belongs to, None for the global scope
+    pub node: Option<&'a SyntaxNode>,
+    // The namespace, struct or function symbol owning the scope
+    pub owner: Option<SymbolId>,
+    // Member scopes of base structs and classes, searched after the scope itself
+    pub bases: Vec<ScopeId>,
+    // Symbols in declaration order
+    pub symbols: Vec<SymbolId>,
+    names: HashMap<String, Vec<SymbolId>>,
+}
+
+impl<'a> Scope<'a>
+{
+    pub fn get(&self, name: &str) -> &[SymbolId]
+    {
+        return match self.names.get(name)
+        {
+            Some(symbols) => symbols,
+            None => &[]
+        };
+    }
+}
+
+// SymbolTable
+// The scopes and symbols of a compilation unit together with the symbol every name in the tree refers to
+pub struct SymbolTable<'a>
+{
+    scopes: Vec<Scope<'a>>,
+    symbols: Vec<Symbol<'a>>,
+    // Name nodes (IdentifierName, QualifiedName) in the tree and the symbol they refer to, keyed by node address
+    references: HashMap<usize, SymbolId>,
+    reference_nodes: Vec<(&'a SyntaxNode, SymbolId)>,
+    // Scopes introduced by nodes, keyed by node address
+    node_scopes: HashMap<usize, ScopeId>,
+    // Symbols keyed by the address of their declaration node
+    declarations: HashMap<usize, SymbolId>,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+fn address(node: &SyntaxNode) -> usize
+{
+    return node as *const SyntaxNode as usize;
+}
+
+impl<'a> Default for SymbolTable<'a>
+{
+    fn default() -> Self
+    {
+        return Self::new();
+    }
+}
+
+impl<'a> SymbolTable<'a>
+{
+    pub fn new() -> Self
+    {
+        let global = Scope{
+            kind: ScopeKind::Global,
+            parent: None,
+            node: None,
+            owner: None,
+            bases: Vec::new(),
+            symbols: Vec::new(),
+            names: HashMap::new()
+        };
+
+        return Self{
+            scopes: vec![global],
+            symbols: Vec::new(),
+            references: HashMap::new(),
+            reference_nodes: Vec::new(),
+            node_scopes: HashMap::new(),
+            declarations: HashMap::new(),
+            diagnostics: Vec::new()
+        };
+    }
+
+    pub fn global_scope(&self) -> ScopeId
+    {
+        return 0;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    pub fn get_symbols(&self) -> &Vec<Symbol<'a>>
+    {
+        return &self.symbols;
+    }
+
+    pub fn get_symbol(&self, symbol: SymbolId) -> &Symbol<'a>
+    {
+        return &self.symbols[symbol];
+    }
+
+    pub(crate) fn get_symbol_mut(&mut self, symbol: SymbolId) -> &mut Symbol<'a>
+    {
+        return &mut self.symbols[symbol];
+    }
+
+    pub fn get_scope(&self, scope: ScopeId) -> &Scope<'a>
+    {
+        return &self.scopes[scope];
+    }
+
+    pub(crate) fn get_scope_mut(&mut self, scope: ScopeId) -> &mut Scope<'a>
+    {
+        return &mut self.scopes[scope];
+    }
+
+    pub(crate) fn add_scope(&mut self, kind: ScopeKind, parent: ScopeId, node: Option<&'a SyntaxNode>, owner: Option<SymbolId>) -> ScopeId
+    {
+        let scope = Scope{
+            kind: kind,
+            parent: Some(parent),
+            node: node,
+            owner: owner,
+            bases: Vec::new(),
+            symbols: Vec::new(),
+            names: HashMap::new()
+        };
+        self.scopes.push(scope);
+
+        let id = self.scopes.len() - 1;
+        if let Some(node) = node
+        {
+            self.node_scopes.entry(address(node)).or_insert(id);
+        }
+        return id;
+    }
+
+    pub(crate) fn add_symbol(&mut self, symbol: Symbol<'a>) -> SymbolId
+    {
+        let id = self.symbols.len();
+        let scope = &mut self.scopes[symbol.scope];
+        scope.symbols.push(id);
+        scope.names.entry(symbol.name.clone()).or_default().push(id);
+        self.declarations.entry(address(symbol.declaration)).or_insert(id);
+        self.symbols.push(symbol);
+        return id;
+    }
+
+    // Makes the symbol visible in another scope under its name, hiding earlier symbols with the name there
+    pub(crate) fn add_alias(&mut self, scope: ScopeId, symbol: SymbolId)
+    {
+        let name = self.symbols[symbol].name.clone();
+        self.scopes[scope].names.insert(name, vec![symbol]);
+    }
+
+    pub(crate) fn add_reference(&mut self, node: &'a SyntaxNode, symbol: SymbolId)
+    {
+        self.references.insert(address(node), symbol);
+        self.reference_nodes.push((node, symbol));
+    }
+
+    // The symbol a name node in the tree refers to
+    pub fn symbol_of(&self, name: &SyntaxNode) -> Option<SymbolId>
+    {
+        return self.references.get(&address(name)).copied();
+    }
+
+    // The symbol declared by a struct, function, declarator, parameter or namespace node
+    pub fn declared_by(&self, declaration: &SyntaxNode) -> Option<SymbolId>
+    {
+        return self.declarations.get(&address(declaration)).copied();
+    }
+
+    // All name nodes that refer to the symbol
+    pub fn references_to(&self, symbol: SymbolId) -> Vec<&'a SyntaxNode>
+    {
+        return self.reference_nodes.iter().filter(|(_, s)| *s == symbol).map(|(n, _)| *n).collect();
+    }
+
+    // The scope introduced by a namespace, struct, function, block or for statement node
+    pub fn scope_of(&self, node: &SyntaxNode) -> Option<ScopeId>
+    {
+        return self.node_scopes.get(&address(node)).copied();
+    }
+
+    // Symbols with the name declared directly in the scope or in the scopes of its base types
+    pub fn lookup_member(&self, scope: ScopeId, name: &str) -> Vec<SymbolId>
+    {
+        let mut pending = vec![scope];
+        let mut visited = Vec::new();
+        while let Some(scope) = pending.pop()
+        {
+            if visited.contains(&scope)
+            {
+                continue;
+            }
+            visited.push(scope);
+
+            let symbols = self.scopes[scope].get(name);
+            if !symbols.is_empty()
+            {
+                return symbols.to_vec();
+            }
+            pending.extend(self.scopes[scope].bases.iter().rev());
+        }
+        return Vec::new();
+    }
+
+    // Unqualified lookup from the scope outwards, the innermost declaration hides the outer ones
+    pub fn lookup(&self, scope: ScopeId, name: &str) -> Vec<SymbolId>
+    {
+        let mut current = Some(scope);
+        while let Some(scope) = current
+        {
+            let symbols = self.lookup_member(scope, name);
+            if !symbols.is_empty()
+            {
+                return symbols;
+            }
+            current = self.scopes[scope].parent;
+        }
+        return Vec::new();
+    }
+
+    // Looks up a path like ["Outer", "Nested", "MyClass"] from the global scope
+    pub fn lookup_qualified(&self, path: &[&str]) -> Vec<SymbolId>
+    {
+        let mut scope = self.global_scope();
+        for (index, part) in path.iter().enumerate()
+        {
+            let symbols = self.lookup_member(scope, part);
+            if index == path.len() - 1
+            {
+                return symbols;
+            }
+
+            match symbols.first().and_then(|s| self.symbols[*s].members)
+            {
+                Some(members) => scope = members,
+                None => return Vec::new()
+            }
+        }
+        return Vec::new();
+    }
+
+    // The fully qualified name of the symbol, like Outer::Nested::MyClass::TestFunc
+    pub fn qualified_name(&self, symbol: SymbolId) -> String
+    {
+        let mut parts = vec![self.symbols[symbol].name.clone()];
+        let mut scope = Some(self.symbols[symbol].scope);
+        while let Some(current) = scope
+        {
+            let current_scope = &self.scopes[current];
+            if matches!(current_scope.kind, ScopeKind::Namespace | ScopeKind::Struct)
+            {
+                if let Some(owner) = current_scope.owner
+                {
+                    parts.push(self.symbols[owner].name.clone());
+                }
+            }
+            scope = current_scope.parent;
+        }
+
+        parts.reverse();
+        return parts.join("::");
+    }
+
+    // The innermost scope whose node contains the offset, used to find the visible symbols at a cursor position
+    pub fn scope_at(&self, file_index: usize, offset: usize) -> ScopeId
+    {
+        let mut best = self.global_scope();
+        let mut best_length = usize::MAX;
+        for (id, scope) in self.scopes.iter().enumerate()
+        {
+            let node = match scope.node
+            {
+                Some(node) => node,
+                None => continue
+            };
+
+            let span = node.span();
+            if node.file_index() == file_index && span.start <= offset && offset <= span.end && span.len() <= best_length
+            {
+                best = id;
+                best_length = span.len();
+            }
+        }
+        return best;
+    }
+
+    // The symbol declared or referenced at the offset
+    pub fn symbol_at(&self, file_index: usize, offset: usize) -> Option<SymbolId>
+    {
+        for (id, symbol) in self.symbols.iter().enumerate()
+        {
+            if let Some(token) = symbol.name_token
+            {
+                if token.file_index == file_index && token.span.start <= offset && offset <= token.span.end
+                {
+                    return Some(id);
+                }
+            }
+        }
+
+        for (node, symbol) in &self.reference_nodes
+        {
+            let span = node.span();
+            if node.file_index() == file_index && span.start <= offset && offset <= span.end
+            {
+                return Some(*symbol);
+            }
+        }
+        return None;
+    }
+}
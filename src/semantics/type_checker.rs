@@ -0,0 +1,1346 @@
+use std::collections::HashMap;
+
+use crate::parser::{ast::*, diagnostics::Diagnostic, syntax_nodes::SyntaxNode, syntax_tokens::{SyntaxTokenType, TextSpan}, HlslVersion, ParserSettings};
+
+use super::{intrinsics::{IntrinsicCall, IntrinsicCatalogue}, symbol_table::{ScopeKind, SymbolId, SymbolKind, SymbolTable}, types::*};
+
+fn address(node: &SyntaxNode) -> usize
+{
+    return node as *const SyntaxNode as usize;
+}
+
+// The fields of builtin structs used by ray tracing
+fn builtin_field(kind: ObjectKind, name: &str) -> Option<Type>
+{
+    return match (kind, name)
+    {
+        (ObjectKind::RayDesc, "Origin" | "Direction") => Some(Type::Vector(ScalarType::Float, 3)),
+        (ObjectKind::RayDesc, "TMin" | "TMax") => Some(Type::Scalar(ScalarType::Float)),
+        (ObjectKind::BuiltInTriangleIntersectionAttributes, "barycentrics") => Some(Type::Vector(ScalarType::Float, 2)),
+        _ => None
+    };
+}
+
+// Parses a swizzle like 'xyz' or 'rgba' into component indices
+fn parse_vector_swizzle(text: &str) -> Result<Vec<u32>, String>
+{
+    let positions: Option<Vec<u32>> = if text.chars().all(|c| "xyzw".contains(c))
+    {
+        Some(text.chars().map(|c| "xyzw".find(c).unwrap() as u32).collect())
+    }
+    else if text.chars().all(|c| "rgba".contains(c))
+    {
+        Some(text.chars().map(|c| "rgba".find(c).unwrap() as u32).collect())
+    }
+    else
+    {
+        None
+    };
+
+    return match positions
+    {
+        None => Err(format!("invalid format for vector swizzle '{}'", text)),
+        Some(positions) if positions.len() > 4 => Err(format!("more than four positions are referenced in '{}'", text)),
+        Some(positions) => Ok(positions)
+    };
+}
+
+// Parses a matrix swizzle like '_m00_m11' (zero based) or '_11_22' (one based) into row and column pairs
+fn parse_matrix_swizzle(text: &str, rows: u32, columns: u32) -> Result<Vec<(u32, u32)>, String>
+{
+    let bad_format = || format!("invalid format for matrix subscript '{}'", text);
+    if !text.starts_with('_')
+    {
+        return Err(bad_format());
+    }
+
+    let mut positions = Vec::new();
+    for part in text[1..].split('_')
+    {
+        let (zero_based, digits) = match part.strip_prefix('m')
+        {
+            Some(digits) => (true, digits),
+            None => (false, part)
+        };
+
+        let digits: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+        if digits.len() != 2 || part.len() != if zero_based { 3 } else { 2 }
+        {
+            return Err(bad_format());
+        }
+
+        let (row, column) = if zero_based
+        {
+            (digits[0], digits[1])
+        }
+        else
+        {
+            if digits.contains(&0)
+            {
+                return Err(format!("the digit '0' is used in '{}', but the syntax is for one-based rows and columns", text));
+            }
+            (digits[0] - 1, digits[1] - 1)
+        };
+
+        if row >= rows || column >= columns
+        {
+            return Err(format!("matrix subscript '{}' is out of bounds", text));
+        }
+        positions.push((row, column));
+    }
+
+    if positions.len() > 4
+    {
+        return Err(format!("more than four positions are referenced in '{}'", text));
+    }
+    return Ok(positions);
+}
+
+// An implicit conversion of the value of an expression, like the float4 to float3 of 'float3 v = color;'
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImplicitConversion
+{
+    pub source: Type,
+    pub target: Type,
+    // Vectors and matrices converted to a type with fewer components
+    pub truncates: bool,
+}
+
+// Whether converting drops components, float4 to float3 or float2x2 to float
+fn truncates(source: &Type, target: &Type) -> bool
+{
+    return match (source.shape(), target.shape())
+    {
+        (Some(Shape::Scalar), _) => false,
+        (Some(_), Some(Shape::Scalar)) => true,
+        (Some(Shape::Vector(from)), Some(Shape::Vector(to))) => from > to,
+        (Some(Shape::Matrix(from_rows, from_columns)), Some(Shape::Matrix(to_rows, to_columns))) => {
+            from_rows >= to_rows && from_columns >= to_columns && (from_rows > to_rows || from_columns > to_columns)
+        },
+        _ => false
+    };
+}
+
+// TypeChecker
+// Computes the type of every expression in the compilation unit and reports invalid conversions, swizzles and operands.
+// Diagnostics use the wording of DXC
+pub struct TypeChecker<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    resolver: TypeResolver<'t, 'a>,
+    hlsl_version: HlslVersion,
+    // Expression types keyed by node address
+    types: HashMap<usize, Type>,
+    // Intrinsic overloads chosen for calls, keyed by the address of the call
+    intrinsics: HashMap<usize, IntrinsicCall>,
+    // User functions chosen for calls, keyed by the address of the call
+    calls: HashMap<usize, SymbolId>,
+    // Implicit conversions keyed by the address of the converted expression
+    conversions: HashMap<usize, ImplicitConversion>,
+    return_types: Vec<Type>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> TypeChecker<'t, 'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>, settings: &ParserSettings) -> Self
+    {
+        let mut checker = Self{
+            table: table,
+            resolver: TypeResolver::new(table),
+            hlsl_version: settings.hlsl_version,
+            types: HashMap::new(),
+            intrinsics: HashMap::new(),
+            calls: HashMap::new(),
+            conversions: HashMap::new(),
+            return_types: Vec::new(),
+            diagnostics: Vec::new()
+        };
+
+        for declaration in unit.declarations()
+        {
+            checker.check_declaration(declaration);
+        }
+        return checker;
+    }
+
+    pub fn get_diagnostics(&self) -> &Vec<Diagnostic>
+    {
+        return &self.diagnostics;
+    }
+
+    // The type computed for an expression node
+    pub fn type_of(&self, node: &SyntaxNode) -> Option<&Type>
+    {
+        return self.types.get(&address(node));
+    }
+
+    // The intrinsic overload a call resolved to, used for signature hover
+    pub fn intrinsic_of(&self, call: &SyntaxNode) -> Option<&IntrinsicCall>
+    {
+        return self.intrinsics.get(&address(call));
+    }
+
+    // The function or method overload a call resolved to
+    pub fn call_target(&self, call: &SyntaxNode) -> Option<SymbolId>
+    {
+        return self.calls.get(&address(call)).copied();
+    }
+
+    // The implicit conversion applied to the value of an expression, like an initializer, an argument or an operand
+    pub fn conversion_of(&self, node: &SyntaxNode) -> Option<&ImplicitConversion>
+    {
+        return self.conversions.get(&address(node));
+    }
+
+    fn record_conversion(&mut self, source: &Type, target: &Type, node: &SyntaxNode)
+    {
+        if source.is_error() || target.is_error() || source == target
+        {
+            return;
+        }
+        self.conversions.insert(address(node), ImplicitConversion{ source: source.clone(), target: target.clone(), truncates: truncates(source, target) });
+    }
+
+    pub fn expression_type(&self, expression: &Expression) -> Option<&Type>
+    {
+        return self.type_of(expression.syntax());
+    }
+
+    fn error(&mut self, message: String, node: &SyntaxNode)
+    {
+        self.diagnostics.push(Diagnostic::error(message, node.span()).in_file(node.file_index()));
+    }
+
+    fn warning(&mut self, message: String, node: &SyntaxNode)
+    {
+        self.diagnostics.push(Diagnostic::warning(message, node.span()).in_file(node.file_index()));
+    }
+
+    fn error_at(&mut self, message: String, span: TextSpan, file_index: usize)
+    {
+        self.diagnostics.push(Diagnostic::error(message, span).in_file(file_index));
+    }
+
+    // Declarations
+
+    fn check_declaration(&mut self, declaration: Declaration<'a>)
+    {
+        match declaration
+        {
+            Declaration::Function(function) => self.check_function(function),
+            Declaration::Variable(variable) => self.check_variable(variable),
+            Declaration::Type(declaration) => {
+                if let Some(struct_decl) = declaration.struct_decl()
+                {
+                    self.check_struct(struct_decl);
+                }
+            },
+            Declaration::ConstantBuffer(buffer) => {
+                for field in buffer.fields()
+                {
+                    self.check_variable(field);
+                }
+            },
+            Declaration::Namespace(namespace) => {
+                for declaration in namespace.declarations()
+                {
+                    self.check_declaration(declaration);
+                }
+            },
+            Declaration::Template(template) => {
+                if let Some(declaration) = template.declaration()
+                {
+                    self.check_declaration(declaration);
+                }
+            },
+            Declaration::Typedef(_) | Declaration::Technique(_) | Declaration::Skipped(_) => {}
+        }
+    }
+
+    fn check_struct(&mut self, declaration: StructDecl<'a>)
+    {
+        for member in declaration.members()
+        {
+            self.check_declaration(member);
+        }
+    }
+
+    fn check_function(&mut self, function: FunctionDecl<'a>)
+    {
+        let body = match function.body()
+        {
+            Some(body) => body,
+            None => return
+        };
+
+        for parameter in function.params()
+        {
+            if let Some(default_value) = parameter.default_value()
+            {
+                let parameter_type = self.table.declared_by(parameter.syntax()).map(|s| self.resolver.symbol_type(s).ty).unwrap_or(Type::Error);
+                self.check_conversion(default_value, &parameter_type);
+            }
+        }
+
+        let return_type = function.return_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+        self.return_types.push(return_type);
+        for statement in body.statements()
+        {
+            self.check_statement(statement);
+        }
+        self.return_types.pop();
+    }
+
+    fn check_variable(&mut self, variable: VariableDecl<'a>)
+    {
+        for declarator in variable.declarators()
+        {
+            for rank in declarator.array_ranks()
+            {
+                if let Some(size) = rank.size()
+                {
+                    self.check_expression(size);
+                }
+            }
+
+            let initializer = match declarator.initializer()
+            {
+                Some(initializer) => initializer,
+                None => continue
+            };
+
+            let declared_type = match self.table.declared_by(declarator.syntax())
+            {
+                Some(symbol) => self.resolver.symbol_type(symbol).ty,
+                None => Type::Error
+            };
+            self.check_conversion(initializer, &declared_type);
+        }
+    }
+
+    // Statements
+
+    fn check_statement(&mut self, statement: Statement<'a>)
+    {
+        match statement
+        {
+            Statement::Block(block) => {
+                for statement in block.statements()
+                {
+                    self.check_statement(statement);
+                }
+            },
+            Statement::Expression(statement) => self.check_optional_expression(statement.expression()),
+            Statement::Return(statement) => {
+                if let Some(value) = statement.value()
+                {
+                    let return_type = self.return_types.last().cloned().unwrap_or(Type::Error);
+                    if return_type == Type::Void
+                    {
+                        self.check_expression(value);
+                        self.error(String::from("void function should not return a value"), statement.syntax());
+                    }
+                    else
+                    {
+                        self.check_conversion(value, &return_type);
+                    }
+                }
+            },
+            Statement::If(statement) => {
+                self.check_optional_expression(statement.condition());
+                self.check_optional_statement(statement.then_statement());
+                self.check_optional_statement(statement.else_statement());
+            },
+            Statement::For(statement) => {
+                self.check_optional_statement(statement.initializer());
+                self.check_optional_expression(statement.condition());
+                self.check_optional_expression(statement.incrementor());
+                self.check_optional_statement(statement.body());
+            },
+            Statement::While(statement) => {
+                self.check_optional_expression(statement.condition());
+                self.check_optional_statement(statement.body());
+            },
+            Statement::Do(statement) => {
+                self.check_optional_statement(statement.body());
+                self.check_optional_expression(statement.condition());
+            },
+            Statement::Switch(statement) => {
+                self.check_optional_expression(statement.expression());
+                for section in statement.sections()
+                {
+                    for label in section.labels()
+                    {
+                        self.check_optional_expression(label.value());
+                    }
+
+                    for statement in section.statements()
+                    {
+                        self.check_statement(statement);
+                    }
+                }
+            },
+            Statement::Variable(variable) => self.check_variable(variable),
+            Statement::Type(declaration) => {
+                if let Some(struct_decl) = declaration.struct_decl()
+                {
+                    self.check_struct(struct_decl);
+                }
+            },
+            Statement::Empty(_) | Statement::Break(_) | Statement::Continue(_) | Statement::Discard(_) |
+            Statement::Typedef(_) | Statement::Skipped(_) => {}
+        }
+    }
+
+    fn check_optional_statement(&mut self, statement: Option<Statement<'a>>)
+    {
+        if let Some(statement) = statement
+        {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_optional_expression(&mut self, expression: Option<Expression<'a>>)
+    {
+        if let Some(expression) = expression
+        {
+            self.check_expression(expression);
+        }
+    }
+
+    // Conversions
+
+    // Checks the expression and its implicit conversion to the target type
+    fn check_conversion(&mut self, expression: Expression<'a>, target: &Type)
+    {
+        // Initializer lists like { 1, 2, 3 } take the shape of the target
+        if let Expression::ArrayInitializer(initializer) = expression
+        {
+            let element_target = match target
+            {
+                Type::Array(element, _) => (**element).clone(),
+                _ => Type::Error
+            };
+            for element in initializer.elements()
+            {
+                self.check_conversion(element, &element_target);
+            }
+            return;
+        }
+
+        let source = self.check_expression(expression);
+        self.convert(&source, target, expression.syntax());
+    }
+
+    // Reports an implicit conversion that is not allowed or that truncates a vector
+    fn convert(&mut self, source: &Type, target: &Type, node: &SyntaxNode)
+    {
+        if source.is_error() || target.is_error() || source == target
+        {
+            return;
+        }
+        self.record_conversion(source, target, node);
+
+        let cannot_convert = format!("cannot convert from '{}' to '{}'", source, target);
+        match (source.shape(), target.shape())
+        {
+            (Some(Shape::Scalar), Some(_)) => {},
+            (Some(_), Some(Shape::Scalar)) => self.warning(String::from("implicit truncation of vector type"), node),
+            (Some(Shape::Vector(from)), Some(Shape::Vector(to))) => {
+                if from > to
+                {
+                    self.warning(String::from("implicit truncation of vector type"), node);
+                }
+                else if from < to
+                {
+                    self.error(cannot_convert, node);
+                }
+            },
+            (Some(Shape::Matrix(from_rows, from_columns)), Some(Shape::Matrix(to_rows, to_columns))) => {
+                if from_rows < to_rows || from_columns < to_columns
+                {
+                    self.error(cannot_convert, node);
+                }
+                else if from_rows > to_rows || from_columns > to_columns
+                {
+                    self.warning(String::from("implicit truncation of vector type"), node);
+                }
+            },
+            // float4 and float2x2 convert when they have the same number of components
+            (Some(_), Some(_)) => {
+                if source.component_count() != target.component_count()
+                {
+                    self.error(cannot_convert, node);
+                }
+            },
+            _ => {
+                if !self.is_compatible(source, target)
+                {
+                    self.error(cannot_convert, node);
+                }
+            }
+        }
+    }
+
+    // Conversions between types that are not scalars, vectors or matrices
+    fn is_compatible(&self, source: &Type, target: &Type) -> bool
+    {
+        return match (source, target)
+        {
+            (Type::TemplateParameter{ .. }, _) | (_, Type::TemplateParameter{ .. }) => true,
+            (Type::Resource{ kind: source_kind, .. }, Type::Resource{ kind: target_kind, .. }) => source_kind == target_kind,
+            (Type::Array(source_element, _), Type::Array(target_element, _)) => self.is_compatible(source_element, target_element) || source_element == target_element,
+            // Classes convert to the interfaces they implement
+            (Type::Struct{ symbol: source_symbol, .. }, Type::Struct{ symbol: target_symbol, .. }) => self.derives_from(*source_symbol, *target_symbol),
+            _ => false
+        };
+    }
+
+    fn derives_from(&self, symbol: SymbolId, base: SymbolId) -> bool
+    {
+        let base_members = match self.table.get_symbol(base).members
+        {
+            Some(members) => members,
+            None => return false
+        };
+
+        let mut pending: Vec<usize> = self.table.get_symbol(symbol).members.into_iter().collect();
+        let mut visited = Vec::new();
+        while let Some(scope) = pending.pop()
+        {
+            if scope == base_members
+            {
+                return true;
+            }
+            if !visited.contains(&scope)
+            {
+                visited.push(scope);
+                pending.extend(self.table.get_scope(scope).bases.iter());
+            }
+        }
+        return false;
+    }
+
+    // The type both operands of a binary operator are converted to
+    fn combine(&mut self, left: &Type, right: &Type, node: &SyntaxNode) -> Type
+    {
+        let (left_scalar, right_scalar) = match (left.scalar_type(), right.scalar_type())
+        {
+            (Some(left_scalar), Some(right_scalar)) => (left_scalar, right_scalar),
+            _ => return Type::Error
+        };
+
+        let scalar = promote_scalars(left_scalar, right_scalar);
+        let shape = match (left.shape().unwrap(), right.shape().unwrap())
+        {
+            (Shape::Scalar, shape) | (shape, Shape::Scalar) => shape,
+            (Shape::Vector(left_size), Shape::Vector(right_size)) => {
+                if left_size != right_size
+                {
+                    self.warning(String::from("implicit truncation of vector type"), node);
+                }
+                Shape::Vector(left_size.min(right_size))
+            },
+            (Shape::Matrix(left_rows, left_columns), Shape::Matrix(right_rows, right_columns)) => {
+                if left_rows != right_rows || left_columns != right_columns
+                {
+                    self.warning(String::from("implicit truncation of vector type"), node);
+                }
+                Shape::Matrix(left_rows.min(right_rows), left_columns.min(right_columns))
+            },
+            _ => {
+                self.error(format!("cannot convert from '{}' to '{}'", right, left), node);
+                return Type::Error;
+            }
+        };
+        return Type::from_shape(scalar, shape);
+    }
+
+    // Combines the operands of an operator and records how each of them is converted
+    fn combine_operands(&mut self, operands: [(Option<Expression<'a>>, &Type); 2], node: &SyntaxNode) -> Type
+    {
+        let combined = self.combine(operands[0].1, operands[1].1, node);
+        let Some(scalar) = combined.scalar_type() else { return combined };
+        for (operand, ty) in operands
+        {
+            // Scalars are converted to the component type and then splatted
+            let target = if ty.shape() == Some(Shape::Scalar) { Type::Scalar(scalar) } else { combined.clone() };
+            if let Some(operand) = operand
+            {
+                self.record_conversion(ty, &target, operand.syntax());
+            }
+        }
+        return combined;
+    }
+
+    // Expressions
+
+    fn check_expression(&mut self, expression: Expression<'a>) -> Type
+    {
+        let ty = self.compute_type(expression);
+        self.types.insert(address(expression.syntax()), ty.clone());
+        return ty;
+    }
+
+    fn check_optional(&mut self, expression: Option<Expression<'a>>) -> Type
+    {
+        return match expression
+        {
+            Some(expression) => self.check_expression(expression),
+            None => Type::Error
+        };
+    }
+
+    fn compute_type(&mut self, expression: Expression<'a>) -> Type
+    {
+        return match expression
+        {
+            Expression::Literal(literal) => literal_type(&literal),
+            Expression::Identifier(identifier) => {
+                if identifier.identifier().text == "this"
+                {
+                    return self.this_type(identifier.syntax());
+                }
+                self.symbol_value_type(self.table.symbol_of(identifier.syntax()))
+            },
+            Expression::Qualified(qualified) => self.symbol_value_type(self.table.symbol_of(qualified.syntax())),
+            Expression::Parenthesized(parenthesized) => self.check_optional(parenthesized.expression()),
+            Expression::Cast(cast) => {
+                self.check_optional(cast.operand());
+                let target = cast.target_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+                self.resolver.apply_array_ranks(target, cast.array_ranks())
+            },
+            Expression::Binary(binary) => self.check_binary(binary),
+            Expression::Assignment(assignment) => self.check_assignment(assignment),
+            Expression::PrefixUnary(unary) => {
+                let operand = self.check_optional(unary.operand());
+                self.unary_type(&unary.operator().text, operand, unary.syntax())
+            },
+            Expression::PostfixUnary(unary) => self.check_optional(unary.operand()),
+            Expression::Conditional(conditional) => self.check_conditional(conditional),
+            Expression::Call(call) => self.check_call(call),
+            Expression::MemberAccess(access) => self.check_member_access(access),
+            Expression::ElementAccess(access) => {
+                let object = self.check_optional(access.object());
+                self.check_optional(access.index());
+                element_access_type(&object)
+            },
+            Expression::ArrayInitializer(initializer) => {
+                for element in initializer.elements()
+                {
+                    self.check_expression(element);
+                }
+                Type::Error
+            },
+            Expression::Type(type_syntax) => self.resolver.resolve(&type_syntax),
+            // Effect framework expressions
+            Expression::Compile(_) | Expression::SamplerState(_) | Expression::StateReference(_) => Type::Error
+        };
+    }
+
+    // The type of a variable or parameter used as a value
+    fn symbol_value_type(&self, symbol: Option<SymbolId>) -> Type
+    {
+        let symbol = match symbol
+        {
+            Some(symbol) => symbol,
+            None => return Type::Error
+        };
+
+        let kind = self.table.get_symbol(symbol).kind;
+        if kind.is_variable()
+        {
+            return self.resolver.symbol_type(symbol).ty;
+        }
+        return Type::Error;
+    }
+
+    // 'this' inside a method is the struct the method belongs to
+    fn this_type(&self, node: &SyntaxNode) -> Type
+    {
+        let mut scope = Some(self.table.scope_at(node.file_index(), node.span().start));
+        while let Some(current) = scope
+        {
+            let entry = self.table.get_scope(current);
+            if entry.kind == ScopeKind::Struct
+            {
+                return match entry.owner
+                {
+                    Some(owner) => self.resolver.symbol_type(owner).ty,
+                    None => Type::Error
+                };
+            }
+            scope = entry.parent;
+        }
+        return Type::Error;
+    }
+
+    fn check_binary(&mut self, binary: BinaryExpr<'a>) -> Type
+    {
+        let left = self.check_optional(binary.left());
+        let right = self.check_optional(binary.right());
+        let operator = binary.operator().text.as_str();
+        if left.is_error() || right.is_error()
+        {
+            return Type::Error;
+        }
+        // Inside a template the type of an operand can depend on a template parameter, it is not known until the template is used
+        if left.is_dependent() || right.is_dependent()
+        {
+            return Type::Error;
+        }
+
+        return match operator
+        {
+            "&&" | "||" => {
+                // HLSL 2021 short-circuits, which only works on scalars
+                if self.hlsl_version >= HlslVersion::Hlsl2021 && (!is_scalar(&left) || !is_scalar(&right))
+                {
+                    let replacement = if operator == "&&" { "and" } else { "or" };
+                    self.error(format!("operands for short-circuiting logical binary operator must be scalar, for non-scalar types use '{}'", replacement), binary.syntax());
+                    return Type::Error;
+                }
+                self.combine_operands([(binary.left(), &left), (binary.right(), &right)], binary.syntax()).with_scalar_type(ScalarType::Bool)
+            },
+            "<" | ">" | "<=" | ">=" | "==" | "!=" => self.combine_operands([(binary.left(), &left), (binary.right(), &right)], binary.syntax()).with_scalar_type(ScalarType::Bool),
+            "&" | "|" | "^" | "<<" | ">>" => {
+                if !is_integral(&left) || !is_integral(&right)
+                {
+                    self.error(String::from("int or unsigned int type required"), binary.syntax());
+                    return Type::Error;
+                }
+                // Shifts keep the type of the left operand
+                if operator == "<<" || operator == ">>"
+                {
+                    return left;
+                }
+                self.combine_operands([(binary.left(), &left), (binary.right(), &right)], binary.syntax())
+            },
+            _ => {
+                if !left.is_numeric() || !right.is_numeric()
+                {
+                    let operand = if left.is_numeric() { &right } else { &left };
+                    self.error(format!("scalar, vector, or matrix expected for operator '{}', found '{}'", operator, operand), binary.syntax());
+                    return Type::Error;
+                }
+                self.combine_operands([(binary.left(), &left), (binary.right(), &right)], binary.syntax())
+            }
+        };
+    }
+
+    fn check_assignment(&mut self, assignment: AssignmentExpr<'a>) -> Type
+    {
+        let left = self.check_optional(assignment.left());
+        let operator = assignment.operator().text.clone();
+        let right = match assignment.right()
+        {
+            Some(right) => right,
+            None => return left
+        };
+
+        if operator == "="
+        {
+            self.check_conversion(right, &left);
+            return left;
+        }
+
+        // Compound assignments combine both sides, x += v is x = x + v
+        let right_type = self.check_expression(right);
+        if left.is_error() || right_type.is_error() || left.is_dependent() || right_type.is_dependent()
+        {
+            return left;
+        }
+
+        if matches!(operator.as_str(), "&=" | "|=" | "^=" | "<<=" | ">>=") && (!is_integral(&left) || !is_integral(&right_type))
+        {
+            self.error(String::from("int or unsigned int type required"), assignment.syntax());
+            return left;
+        }
+
+        let combined = self.combine_operands([(assignment.left(), &left), (Some(right), &right_type)], assignment.syntax());
+        self.convert(&combined, &left, right.syntax());
+        return left;
+    }
+
+    fn unary_type(&mut self, operator: &str, operand: Type, node: &SyntaxNode) -> Type
+    {
+        if operand.is_error() || operand.is_dependent()
+        {
+            return operand;
+        }
+
+        return match operator
+        {
+            "!" => operand.with_scalar_type(ScalarType::Bool),
+            "~" => {
+                if !is_integral(&operand)
+                {
+                    self.error(String::from("int or unsigned int type required"), node);
+                    return Type::Error;
+                }
+                operand
+            },
+            // -b is an int
+            "-" | "+" if operand.scalar_type() == Some(ScalarType::Bool) => operand.with_scalar_type(ScalarType::Int),
+            _ => operand
+        };
+    }
+
+    fn check_conditional(&mut self, conditional: ConditionalExpr<'a>) -> Type
+    {
+        let condition = self.check_optional(conditional.condition());
+        let when_true = self.check_optional(conditional.when_true());
+        let when_false = self.check_optional(conditional.when_false());
+        if condition.is_error() || when_true.is_error() || when_false.is_error()
+        {
+            return Type::Error;
+        }
+
+        if self.hlsl_version >= HlslVersion::Hlsl2021 && !is_scalar(&condition) && condition.is_numeric()
+        {
+            self.error(String::from("condition for short-circuiting ternary operator must be scalar, for non-scalar types use 'select'"), conditional.syntax());
+            return Type::Error;
+        }
+
+        if when_true == when_false
+        {
+            return when_true;
+        }
+        if when_true.is_dependent() || when_false.is_dependent()
+        {
+            return Type::Error;
+        }
+
+        if !when_true.is_numeric() || !when_false.is_numeric()
+        {
+            self.error(format!("cannot convert from '{}' to '{}'", when_false, when_true), conditional.syntax());
+            return Type::Error;
+        }
+        return self.combine_operands([(conditional.when_true(), &when_true), (conditional.when_false(), &when_false)], conditional.syntax());
+    }
+
+    fn check_member_access(&mut self, access: MemberAccessExpr<'a>) -> Type
+    {
+        let object = self.check_optional(access.object());
+        let member = match access.member()
+        {
+            Some(member) => member,
+            None => return Type::Error
+        };
+
+        return match &object
+        {
+            Type::Scalar(scalar) | Type::Vector(scalar, _) => {
+                let size = object.component_count();
+                match parse_vector_swizzle(&member.text)
+                {
+                    Ok(positions) => {
+                        if positions.iter().any(|p| *p >= size)
+                        {
+                            self.error_at(format!("vector swizzle '{}' is out of bounds", member.text), member.span, member.file_index);
+                            return Type::Error;
+                        }
+                        if positions.len() == 1 { Type::Scalar(*scalar) } else { Type::Vector(*scalar, positions.len() as u32) }
+                    },
+                    Err(message) => {
+                        self.error_at(message, member.span, member.file_index);
+                        Type::Error
+                    }
+                }
+            },
+            Type::Matrix(scalar, rows, columns) => {
+                match parse_matrix_swizzle(&member.text, *rows, *columns)
+                {
+                    Ok(positions) => if positions.len() == 1 { Type::Scalar(*scalar) } else { Type::Vector(*scalar, positions.len() as u32) },
+                    Err(message) => {
+                        self.error_at(message, member.span, member.file_index);
+                        Type::Error
+                    }
+                }
+            },
+            Type::Struct{ symbol, .. } => self.field_type(*symbol, &object, member.text.as_str(), member.span, member.file_index),
+            // ConstantBuffer<Light> light; light.color
+            Type::Resource{ kind: ResourceKind::ConstantBuffer | ResourceKind::TextureBuffer, element: Some(element), .. } => {
+                match &**element
+                {
+                    Type::Struct{ symbol, .. } => self.field_type(*symbol, element, member.text.as_str(), member.span, member.file_index),
+                    _ => Type::Error
+                }
+            },
+            Type::Object(kind) => builtin_field(*kind, &member.text).unwrap_or(Type::Error),
+            _ => Type::Error
+        };
+    }
+
+    fn field_type(&mut self, symbol: SymbolId, object: &Type, name: &str, span: TextSpan, file_index: usize) -> Type
+    {
+        let members = match self.table.get_symbol(symbol).members
+        {
+            Some(members) => members,
+            None => return Type::Error
+        };
+
+        let found = self.table.lookup_member(members, name);
+        match found.first()
+        {
+            Some(field) if self.table.get_symbol(*field).kind == SymbolKind::Field => return self.resolver.symbol_type(*field).ty,
+            Some(_) => return Type::Error,
+            None => {
+                self.error_at(format!("no member named '{}' in '{}'", name, object), span, file_index);
+                return Type::Error;
+            }
+        }
+    }
+
+    fn check_call(&mut self, call: CallExpr<'a>) -> Type
+    {
+        let argument_nodes = call.arguments();
+        let arguments: Vec<Type> = argument_nodes.iter().map(|a| self.check_expression(*a)).collect();
+        let callee = match call.callee()
+        {
+            Some(callee) => callee,
+            None => return Type::Error
+        };
+
+        return match callee
+        {
+            // Functions with explicit template arguments, like Convert<int, float>(value)
+            Expression::Type(TypeSyntax::User(user)) if self.template_function(&user).is_some() => {
+                let symbol = self.template_function(&user).unwrap();
+                let explicit: Vec<Type> = user.type_arguments().map(|list| list.arguments().map(|argument| match argument
+                {
+                    Expression::Type(type_syntax) => self.resolver.resolve(&type_syntax),
+                    // Values for parameters like int N
+                    _ => Type::Error
+                }).collect()).unwrap_or_default();
+                let entry = self.table.get_symbol(symbol);
+                let candidates = self.table.get_scope(entry.scope).get(&entry.name).to_vec();
+                let name = entry.name.clone();
+                self.resolve_call(&name, candidates, &explicit, &arguments, &argument_nodes, call.syntax())
+            },
+            // Constructors like float3(uv, 1)
+            Expression::Type(type_syntax) => {
+                let ty = self.resolver.resolve(&type_syntax);
+                self.types.insert(address(callee.syntax()), ty.clone());
+                self.check_constructor(&ty, &arguments, call.syntax());
+                ty
+            },
+            Expression::Identifier(_) | Expression::Qualified(_) => {
+                let symbol = match self.table.symbol_of(callee.syntax())
+                {
+                    Some(symbol) => symbol,
+                    None => {
+                        let name = callee.syntax().to_text();
+                        return self.check_intrinsic_function(&name, &arguments, &argument_nodes, call.syntax());
+                    }
+                };
+
+                let entry = self.table.get_symbol(symbol);
+                // Typedefs of vectors, like Color(1, 0, 0, 1)
+                if entry.kind.is_type()
+                {
+                    let ty = self.resolver.symbol_type(symbol).ty;
+                    self.check_constructor(&ty, &arguments, call.syntax());
+                    return ty;
+                }
+
+                let candidates = self.table.get_scope(entry.scope).get(&entry.name).to_vec();
+                let name = entry.name.clone();
+                self.resolve_call(&name, candidates, &[], &arguments, &argument_nodes, call.syntax())
+            },
+            // Methods of classes, like light.Illuminate(normal), and of resources, like texture.Sample(s, uv)
+            Expression::MemberAccess(access) => {
+                let object = self.check_optional(access.object());
+                let member = match access.member()
+                {
+                    Some(member) => member,
+                    None => return Type::Error
+                };
+
+                match &object
+                {
+                    Type::Resource{ .. } | Type::Object(_) => self.check_intrinsic_method(&object, &member.text, &arguments, &argument_nodes, call.syntax()),
+                    Type::Struct{ symbol, .. } => {
+                        let candidates = match self.table.get_symbol(*symbol).members
+                        {
+                            Some(members) => self.table.lookup_member(members, &member.text),
+                            None => Vec::new()
+                        };
+                        self.resolve_call(&member.text, candidates, &[], &arguments, &argument_nodes, call.syntax())
+                    },
+                    _ => Type::Error
+                }
+            },
+            _ => {
+                self.check_expression(callee);
+                Type::Error
+            }
+        };
+    }
+
+    // The function a type expression with template arguments names, like the Convert of Convert<int, float>
+    fn template_function(&self, user: &UserType<'a>) -> Option<SymbolId>
+    {
+        let symbol = self.table.symbol_of(user.name()?.syntax())?;
+        return Some(symbol).filter(|s| self.table.get_symbol(*s).kind.is_function());
+    }
+
+    // Binds the template parameters of a candidate to the explicit template arguments of the call
+    fn template_arguments(&self, candidate: SymbolId, explicit: &[Type]) -> HashMap<SymbolId, Type>
+    {
+        let parameters = match self.table.get_symbol(candidate).members
+        {
+            Some(members) => self.table.get_scope(members).symbols.clone(),
+            None => Vec::new()
+        };
+        return parameters.into_iter().filter(|s| self.table.get_symbol(*s).kind == SymbolKind::TemplateParameter)
+            .zip(explicit.iter().cloned()).filter(|(_, ty)| !ty.is_error()).collect();
+    }
+
+    fn parameter_type(&self, parameter: &Parameter<'a>) -> QualifiedType
+    {
+        let ty = parameter.param_type().map(|t| self.resolver.resolve(&t)).unwrap_or(Type::Error);
+        let ty = match parameter.declarator()
+        {
+            Some(declarator) => self.resolver.apply_array_ranks(ty, declarator.array_ranks()),
+            None => ty
+        };
+        return QualifiedType{ ty: ty, modifiers: TypeModifiers::from_tokens(parameter.modifiers()) };
+    }
+
+    fn rank(&self, source: &Type, target: &Type) -> Option<ConversionRank>
+    {
+        return match (source, target)
+        {
+            // Classes convert to the interfaces they implement
+            (Type::Struct{ symbol, .. }, Type::Struct{ symbol: target_symbol, .. }) if symbol != target_symbol => {
+                if self.derives_from(*symbol, *target_symbol) { Some(ConversionRank::Conversion) } else { None }
+            },
+            _ => conversion_rank(source, target)
+        };
+    }
+
+    // The rank of passing an argument, out parameters are also converted back to the argument
+    fn argument_rank(&self, argument: &Type, parameter: &QualifiedType) -> Option<ConversionRank>
+    {
+        return match parameter.modifiers.parameter_direction()
+        {
+            ParameterDirection::In => self.rank(argument, &parameter.ty),
+            ParameterDirection::Out => self.rank(&parameter.ty, argument),
+            ParameterDirection::InOut => Some(self.rank(argument, &parameter.ty)?.max(self.rank(&parameter.ty, argument)?))
+        };
+    }
+
+    fn candidate_note(&self, candidate: SymbolId, message: String) -> Diagnostic
+    {
+        let symbol = self.table.get_symbol(candidate);
+        return Diagnostic::note(message, symbol.span()).in_file(symbol.declaration.file_index());
+    }
+
+    // Picks the overload whose arguments convert best and binds the call to it.
+    // A candidate is better than another when no argument converts worse and at least one converts better.
+    // Template parameters take the explicit template arguments, the others are deduced from the arguments
+    fn resolve_call(&mut self, name: &str, candidates: Vec<SymbolId>, explicit: &[Type], arguments: &[Type], argument_nodes: &[Expression<'a>], call: &SyntaxNode) -> Type
+    {
+        let mut viable: Vec<(SymbolId, Vec<ConversionRank>)> = Vec::new();
+        let mut notes = Vec::new();
+        for candidate in candidates
+        {
+            let function = match FunctionDecl::cast(self.table.get_symbol(candidate).declaration)
+            {
+                Some(function) => function,
+                None => continue
+            };
+
+            let parameters = function.params();
+            let required = parameters.iter().take_while(|p| p.default_value().is_none()).count();
+            if arguments.len() < required || arguments.len() > parameters.len()
+            {
+                let (bound, expected) = if required == parameters.len() { ("", required) }
+                    else if arguments.len() < required { ("at least ", required) }
+                    else { ("at most ", parameters.len()) };
+                let message = format!("candidate function not viable: requires {}{} argument{}, but {} {} provided", bound, expected,
+                    if expected == 1 { "" } else { "s" }, arguments.len(), if arguments.len() == 1 { "was" } else { "were" });
+                notes.push(self.candidate_note(candidate, message));
+                continue;
+            }
+
+            let template_arguments = self.template_arguments(candidate, explicit);
+            let mut ranks = Vec::new();
+            for (index, (argument, parameter)) in arguments.iter().zip(&parameters).enumerate()
+            {
+                let mut parameter_type = self.parameter_type(parameter);
+                parameter_type.ty = parameter_type.ty.substitute(&template_arguments);
+                match self.argument_rank(argument, &parameter_type)
+                {
+                    Some(rank) => ranks.push(rank),
+                    None => {
+                        let message = format!("candidate function not viable: no known conversion from '{}' to '{}' for {} argument", argument, parameter_type, ordinal(index + 1));
+                        notes.push(self.candidate_note(candidate, message));
+                        break;
+                    }
+                }
+            }
+
+            if ranks.len() == arguments.len()
+            {
+                viable.push((candidate, ranks));
+            }
+        }
+
+        if viable.is_empty()
+        {
+            if notes.is_empty()
+            {
+                return Type::Error;
+            }
+
+            let mut diagnostic = Diagnostic::error(format!("no matching function for call to '{}'", name), call.span()).in_file(call.file_index());
+            diagnostic.notes = notes;
+            self.diagnostics.push(diagnostic);
+            return Type::Error;
+        }
+
+        let is_better = |ranks: &Vec<ConversionRank>, other: &Vec<ConversionRank>| {
+            return ranks.iter().zip(other).all(|(a, b)| a <= b) && ranks.iter().zip(other).any(|(a, b)| a < b);
+        };
+        let best = viable.iter().position(|(symbol, ranks)| viable.iter().all(|(other_symbol, other)| other_symbol == symbol || is_better(ranks, other)));
+        let best = match best
+        {
+            Some(best) => viable[best].0,
+            None => {
+                let mut diagnostic = Diagnostic::error(format!("call to '{}' is ambiguous", name), call.span()).in_file(call.file_index());
+                for (candidate, ranks) in &viable
+                {
+                    // Only the candidates that no other candidate beats
+                    if !viable.iter().any(|(_, other)| is_better(other, ranks))
+                    {
+                        diagnostic.notes.push(self.candidate_note(*candidate, String::from("candidate function")));
+                    }
+                }
+                self.diagnostics.push(diagnostic);
+                return Type::Error;
+            }
+        };
+
+        self.calls.insert(address(call), best);
+        let parameters = FunctionDecl::cast(self.table.get_symbol(best).declaration).map(|f| f.params()).unwrap_or_default();
+        let parameter_types: Vec<QualifiedType> = parameters.iter().map(|p| self.parameter_type(p)).collect();
+        let mut template_arguments = self.template_arguments(best, explicit);
+        for (argument, parameter_type) in arguments.iter().zip(&parameter_types)
+        {
+            parameter_type.ty.deduce(argument, &mut template_arguments);
+        }
+        for (((argument, node), parameter), parameter_type) in arguments.iter().zip(argument_nodes).zip(&parameters).zip(&parameter_types)
+        {
+            let direction = parameter_type.modifiers.parameter_direction();
+            let parameter_name = parameter.name().map(|n| n.text.clone()).unwrap_or_default();
+            self.check_argument(argument, *node, &parameter_type.ty.substitute(&template_arguments), direction, &parameter_name);
+        }
+        return self.resolver.symbol_type(best).ty.substitute(&template_arguments);
+    }
+
+    // Warns about truncated arguments and checks that out arguments can be written to
+    fn check_argument(&mut self, argument: &Type, node: Expression<'a>, parameter: &Type, direction: ParameterDirection, name: &str)
+    {
+        match direction
+        {
+            ParameterDirection::In => self.convert(argument, parameter, node.syntax()),
+            ParameterDirection::Out | ParameterDirection::InOut => {
+                if !is_lvalue(&node)
+                {
+                    let modifier = if direction == ParameterDirection::Out { "out" } else { "inout" };
+                    self.error(format!("cannot bind non-lvalue argument to {} parameter '{}'", modifier, name), node.syntax());
+                    return;
+                }
+
+                if direction == ParameterDirection::InOut
+                {
+                    self.convert(argument, parameter, node.syntax());
+                }
+                // The value is copied back to the argument when the function returns
+                self.convert(parameter, argument, node.syntax());
+            }
+        }
+    }
+
+    // Vector and matrix constructors need exactly the number of components of the type
+    fn check_constructor(&mut self, ty: &Type, arguments: &[Type], node: &SyntaxNode)
+    {
+        let kind = match ty
+        {
+            Type::Vector(..) => "vector",
+            Type::Matrix(..) => "matrix",
+            _ => return
+        };
+
+        if arguments.iter().any(|a| !a.is_numeric())
+        {
+            return;
+        }
+
+        let expected = ty.component_count();
+        let have: u32 = arguments.iter().map(|a| a.component_count()).sum();
+        // float4(x) splats a single scalar
+        if have == expected || (arguments.len() == 1 && have == 1)
+        {
+            return;
+        }
+
+        let amount = if have < expected { "few" } else { "many" };
+        self.error(format!("too {} elements in {} initialization (expected {} elements, have {})", amount, kind, expected, have), node);
+    }
+
+    // The type of calls to intrinsics that are not declared in the source
+    fn intrinsic_type(&mut self, call: IntrinsicCall, argument_nodes: &[Expression<'a>], node: &SyntaxNode) -> Type
+    {
+        for (parameter_type, argument) in call.parameter_types.iter().zip(argument_nodes)
+        {
+            if let Some(argument_type) = self.type_of(argument.syntax()).cloned()
+            {
+                self.record_conversion(&argument_type, parameter_type, argument.syntax());
+            }
+        }
+        for (parameter, argument) in call.overload.parameters.iter().zip(argument_nodes)
+        {
+            if parameter.direction != ParameterDirection::In && !is_lvalue(argument)
+            {
+                let modifier = if parameter.direction == ParameterDirection::Out { "out" } else { "inout" };
+                self.error(format!("cannot bind non-lvalue argument to {} parameter '{}'", modifier, parameter.name), argument.syntax());
+            }
+        }
+
+        // mul(float3, float4x4) multiplies with the upper 3x4 part of the matrix
+        if call.truncates
+        {
+            self.warning(String::from("implicit truncation of vector type"), node);
+        }
+
+        let return_type = call.return_type.clone();
+        self.intrinsics.insert(address(node), call);
+        return return_type;
+    }
+
+    fn check_intrinsic_function(&mut self, name: &str, arguments: &[Type], argument_nodes: &[Expression<'a>], node: &SyntaxNode) -> Type
+    {
+        let catalogue = IntrinsicCatalogue::get();
+        if !catalogue.is_function(name) || arguments.iter().any(Type::is_dependent)
+        {
+            return Type::Error;
+        }
+
+        return match catalogue.resolve_function(name, arguments)
+        {
+            Some(call) => self.intrinsic_type(call, argument_nodes, node),
+            None => {
+                self.error(format!("'{}': no matching {} parameter intrinsic function", name, arguments.len()), node);
+                Type::Error
+            }
+        };
+    }
+
+    fn check_intrinsic_method(&mut self, object: &Type, name: &str, arguments: &[Type], argument_nodes: &[Expression<'a>], node: &SyntaxNode) -> Type
+    {
+        let catalogue = IntrinsicCatalogue::get();
+        let receiver = match object
+        {
+            Type::Resource{ kind, .. } => kind.name(),
+            Type::Object(kind) => kind.name(),
+            _ => return Type::Error
+        };
+
+        if catalogue.methods(receiver, name).is_empty() || arguments.iter().any(Type::is_dependent)
+        {
+            return Type::Error;
+        }
+
+        return match catalogue.resolve_method(object, name, arguments)
+        {
+            Some(call) => self.intrinsic_type(call, argument_nodes, node),
+            None => {
+                self.error(format!("'{}': no matching {} parameter intrinsic method", name, arguments.len()), node);
+                Type::Error
+            }
+        };
+    }
+}
+
+// Values that can be passed to out parameters
+fn is_lvalue(expression: &Expression) -> bool
+{
+    return match expression
+    {
+        Expression::Identifier(_) | Expression::Qualified(_) | Expression::MemberAccess(_) | Expression::ElementAccess(_) => true,
+        Expression::Parenthesized(parenthesized) => parenthesized.expression().map(|e| is_lvalue(&e)).unwrap_or(true),
+        _ => false
+    };
+}
+
+fn ordinal(number: usize) -> String
+{
+    let suffix = match (number % 10, number % 100)
+    {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th"
+    };
+    return format!("{}{}", number, suffix);
+}
+
+fn is_scalar(ty: &Type) -> bool
+{
+    return matches!(ty, Type::Scalar(_));
+}
+
+fn is_integral(ty: &Type) -> bool
+{
+    return ty.scalar_type().map(|s| s.is_integer() || s == ScalarType::Bool).unwrap_or(false);
+}
+
+// The value read with [] from an array, vector, matrix or resource
+fn element_access_type(object: &Type) -> Type
+{
+    return match object
+    {
+        Type::Array(element, _) => (**element).clone(),
+        Type::Vector(scalar, _) => Type::Scalar(*scalar),
+        Type::Matrix(scalar, _, columns) => Type::Vector(*scalar, *columns),
+        Type::Resource{ element: Some(element), .. } => (**element).clone(),
+        _ => Type::Error
+    };
+}
+
+// Literals without a suffix adapt to the other operand, 1.0f is a float and 1.0h a half
+pub(crate) fn literal_type(literal: &LiteralExpr) -> Type
+{
+    let token = literal.token();
+    return match &token.token_type
+    {
+        SyntaxTokenType::TrueKeyword | SyntaxTokenType::FalseKeyword => Type::Scalar(ScalarType::Bool),
+        SyntaxTokenType::StringLiteralToken{ .. } => Type::Object(ObjectKind::String),
+        SyntaxTokenType::FloatLiteralToken{ .. } => {
+            let text = token.text.to_ascii_lowercase();
+            let scalar = if text.ends_with('f') { ScalarType::Float }
+                else if text.ends_with('h') { ScalarType::Half }
+                else if text.ends_with('l') { ScalarType::Double }
+                else { ScalarType::LiteralFloat };
+            Type::Scalar(scalar)
+        },
+        SyntaxTokenType::IntegerLiteralToken{ .. } => {
+            let text = token.text.to_ascii_lowercase();
+            let suffix: String = text.chars().rev().take_while(|c| matches!(c, 'u' | 'l')).collect();
+            let scalar = match (suffix.contains('u'), suffix.contains('l'))
+            {
+                (true, true) => ScalarType::Uint64,
+                (true, false) => ScalarType::Uint,
+                (false, true) => ScalarType::Int64,
+                (false, false) => ScalarType::LiteralInt
+            };
+            Type::Scalar(scalar)
+        },
+        _ => Type::Scalar(ScalarType::LiteralInt)
+    };
+}
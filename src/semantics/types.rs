@@ -0,0 +1,1113 @@
+use std::{collections::HashMap, fmt};
+
+use crate::parser::{ast::*, syntax_tokens::SyntaxToken};
+
+use super::{constant_evaluator::ConstantFolder, symbol_table::{SymbolId, SymbolKind, SymbolTable}};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ScalarType
+{
+    Bool,
+    Int,
+    Uint,
+    Half,
+    Float,
+    Double,
+    Min10Float,
+    Min16Float,
+    Min12Int,
+    Min16Int,
+    Min16Uint,
+    Int16,
+    Uint16,
+    Int64,
+    Uint64,
+    Float16,
+    // Literals without a suffix take the type of the other operand, like DXC's 'literal int' and 'literal float'
+    LiteralInt,
+    LiteralFloat,
+}
+
+// Scalar names and their aliases, the first name of a scalar is the one that is displayed
+const SCALAR_NAMES: &[(ScalarType, &str)] = &[
+    (ScalarType::Bool, "bool"),
+    (ScalarType::Int, "int"),
+    (ScalarType::Int, "int32_t"),
+    (ScalarType::Uint, "uint"),
+    (ScalarType::Uint, "dword"),
+    (ScalarType::Uint, "uint32_t"),
+    (ScalarType::Half, "half"),
+    (ScalarType::Float, "float"),
+    (ScalarType::Float, "float32_t"),
+    (ScalarType::Double, "double"),
+    (ScalarType::Double, "float64_t"),
+    (ScalarType::Min10Float, "min10float"),
+    (ScalarType::Min16Float, "min16float"),
+    (ScalarType::Min12Int, "min12int"),
+    (ScalarType::Min16Int, "min16int"),
+    (ScalarType::Min16Uint, "min16uint"),
+    (ScalarType::Int16, "int16_t"),
+    (ScalarType::Uint16, "uint16_t"),
+    (ScalarType::Int64, "int64_t"),
+    (ScalarType::Uint64, "uint64_t"),
+    (ScalarType::Float16, "float16_t"),
+    (ScalarType::LiteralInt, "literal int"),
+    (ScalarType::LiteralFloat, "literal float"),
+];
+
+impl ScalarType
+{
+    pub fn from_name(name: &str) -> Option<ScalarType>
+    {
+        return SCALAR_NAMES.iter().find(|(_, n)| *n == name).map(|(s, _)| *s);
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        return SCALAR_NAMES.iter().find(|(s, _)| s == self).map(|(_, n)| *n).unwrap();
+    }
+
+    pub fn is_float(&self) -> bool
+    {
+        return matches!(self, ScalarType::Half | ScalarType::Float | ScalarType::Double | ScalarType::Min10Float | ScalarType::Min16Float | ScalarType::Float16 | ScalarType::LiteralFloat);
+    }
+
+    pub fn is_integer(&self) -> bool
+    {
+        return !self.is_float() && *self != ScalarType::Bool;
+    }
+
+    pub fn is_signed(&self) -> bool
+    {
+        return !matches!(self, ScalarType::Bool | ScalarType::Uint | ScalarType::Min16Uint | ScalarType::Uint16 | ScalarType::Uint64);
+    }
+
+    pub fn is_literal(&self) -> bool
+    {
+        return matches!(self, ScalarType::LiteralInt | ScalarType::LiteralFloat);
+    }
+
+    // Minimum precision types may be stored with more bits than they need
+    pub fn is_min_precision(&self) -> bool
+    {
+        return matches!(self, ScalarType::Min10Float | ScalarType::Min16Float | ScalarType::Min12Int | ScalarType::Min16Int | ScalarType::Min16Uint);
+    }
+
+    // Size in bits when stored in a buffer, minimum precision types and half take 32 bits unless 16-bit types are enabled
+    pub fn bit_width(&self) -> u32
+    {
+        return match self
+        {
+            ScalarType::Int16 | ScalarType::Uint16 | ScalarType::Float16 => 16,
+            ScalarType::Double | ScalarType::Int64 | ScalarType::Uint64 => 64,
+            _ => 32
+        };
+    }
+}
+
+// Rank of a scalar type when two operands are combined, the higher rank wins
+fn scalar_rank(scalar: ScalarType) -> u32
+{
+    return match scalar
+    {
+        ScalarType::LiteralInt => 0,
+        ScalarType::Bool => 1,
+        ScalarType::Min12Int => 2,
+        ScalarType::Min16Int => 3,
+        ScalarType::Min16Uint => 4,
+        ScalarType::Int16 => 5,
+        ScalarType::Uint16 => 6,
+        ScalarType::Int => 7,
+        ScalarType::Uint => 8,
+        ScalarType::Int64 => 9,
+        ScalarType::Uint64 => 10,
+        ScalarType::LiteralFloat => 11,
+        ScalarType::Min10Float => 12,
+        ScalarType::Min16Float => 13,
+        ScalarType::Half => 14,
+        ScalarType::Float16 => 15,
+        ScalarType::Float => 16,
+        ScalarType::Double => 17
+    };
+}
+
+// The component type two operands are converted to. Literals take the type of the other operand, ints are promoted to floats
+pub fn promote_scalars(left: ScalarType, right: ScalarType) -> ScalarType
+{
+    if left == right
+    {
+        return if left == ScalarType::Bool { ScalarType::Int } else { left };
+    }
+
+    let (low, high) = if scalar_rank(left) < scalar_rank(right) { (left, right) } else { (right, left) };
+    return match (low, high)
+    {
+        (ScalarType::LiteralInt, ScalarType::Bool) => ScalarType::Int,
+        (ScalarType::LiteralInt, high) => high,
+        // A literal float with an integer operand is a float, 1.5 * i
+        (_, ScalarType::LiteralFloat) => ScalarType::Float,
+        (ScalarType::LiteralFloat, high) => high,
+        (_, ScalarType::Bool) => ScalarType::Int,
+        (_, high) => high
+    };
+}
+
+// How well an argument matches a parameter, overload resolution prefers the lower ranks
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ConversionRank
+{
+    Exact,
+    // A wider type of the same kind, like half to float
+    Promotion,
+    // Between kinds, like int to float
+    Conversion,
+    // A scalar to a vector or matrix
+    Splat,
+    // A larger vector or matrix to a smaller one
+    Truncation,
+}
+
+fn scalar_conversion_rank(source: ScalarType, target: ScalarType) -> ConversionRank
+{
+    if source == target
+    {
+        return ConversionRank::Exact;
+    }
+
+    return match source
+    {
+        // f(1) prefers f(int) and f(1.0) prefers f(float)
+        ScalarType::LiteralInt if target == ScalarType::Int => ConversionRank::Exact,
+        ScalarType::LiteralFloat if target == ScalarType::Float => ConversionRank::Exact,
+        ScalarType::LiteralInt if target.is_integer() => ConversionRank::Promotion,
+        ScalarType::LiteralFloat if target.is_float() => ConversionRank::Promotion,
+        _ if source.is_float() && target.is_float() && source.bit_width() <= target.bit_width() => ConversionRank::Promotion,
+        _ if source.is_integer() && target.is_integer() && source.is_signed() == target.is_signed() && source.bit_width() <= target.bit_width() => {
+            ConversionRank::Promotion
+        },
+        _ => ConversionRank::Conversion
+    };
+}
+
+// The rank of the implicit conversion of a value to another type, None when there is no implicit conversion
+pub fn conversion_rank(source: &Type, target: &Type) -> Option<ConversionRank>
+{
+    if source == target || source.is_error() || target.is_error()
+    {
+        return Some(ConversionRank::Exact);
+    }
+
+    return match (source, target)
+    {
+        (Type::TemplateParameter{ .. }, _) | (_, Type::TemplateParameter{ .. }) => Some(ConversionRank::Exact),
+        (Type::Resource{ kind, .. }, Type::Resource{ kind: target_kind, .. }) if kind == target_kind => Some(ConversionRank::Exact),
+        (Type::Array(element, size), Type::Array(target_element, target_size)) if size == target_size || *target_size == ArraySize::Unsized => {
+            conversion_rank(element, target_element).filter(|r| *r == ConversionRank::Exact)
+        },
+        _ if source.is_numeric() && target.is_numeric() => {
+            let component = scalar_conversion_rank(source.scalar_type()?, target.scalar_type()?);
+            let shape = match (source.shape()?, target.shape()?)
+            {
+                (Shape::Scalar, Shape::Scalar) => ConversionRank::Exact,
+                (Shape::Scalar, _) => ConversionRank::Splat,
+                (_, Shape::Scalar) => ConversionRank::Truncation,
+                (Shape::Vector(from), Shape::Vector(to)) if from >= to => if from == to { ConversionRank::Exact } else { ConversionRank::Truncation },
+                (Shape::Matrix(from_rows, from_columns), Shape::Matrix(to_rows, to_columns)) if from_rows >= to_rows && from_columns >= to_columns => {
+                    if from_rows == to_rows && from_columns == to_columns { ConversionRank::Exact } else { ConversionRank::Truncation }
+                },
+                // float4 and float2x2 convert when they have the same number of components
+                (Shape::Vector(_), Shape::Matrix(..)) | (Shape::Matrix(..), Shape::Vector(_)) if source.component_count() == target.component_count() => {
+                    ConversionRank::Conversion
+                },
+                _ => return None
+            };
+            Some(component.max(shape))
+        },
+        _ => None
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MatrixOrientation
+{
+    RowMajor,
+    ColumnMajor,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ResourceKind
+{
+    Buffer,
+    RWBuffer,
+    RasterizerOrderedBuffer,
+    ByteAddressBuffer,
+    RWByteAddressBuffer,
+    RasterizerOrderedByteAddressBuffer,
+    StructuredBuffer,
+    RWStructuredBuffer,
+    RasterizerOrderedStructuredBuffer,
+    AppendStructuredBuffer,
+    ConsumeStructuredBuffer,
+    ConstantBuffer,
+    TextureBuffer,
+    Texture1D,
+    Texture1DArray,
+    Texture2D,
+    Texture2DArray,
+    Texture2DMS,
+    Texture2DMSArray,
+    Texture3D,
+    TextureCube,
+    TextureCubeArray,
+    RWTexture1D,
+    RWTexture1DArray,
+    RWTexture2D,
+    RWTexture2DArray,
+    RWTexture2DMS,
+    RWTexture2DMSArray,
+    RWTexture3D,
+    RasterizerOrderedTexture1D,
+    RasterizerOrderedTexture1DArray,
+    RasterizerOrderedTexture2D,
+    RasterizerOrderedTexture2DArray,
+    RasterizerOrderedTexture3D,
+    FeedbackTexture2D,
+    FeedbackTexture2DArray,
+    RaytracingAccelerationStructure,
+    SamplerState,
+    SamplerComparisonState,
+    InputPatch,
+    OutputPatch,
+    PointStream,
+    LineStream,
+    TriangleStream,
+    // Effect framework textures and samplers
+    LegacyTexture,
+    LegacyTexture1D,
+    LegacyTexture2D,
+    LegacyTexture3D,
+    LegacyTextureCube,
+    LegacySampler,
+    LegacySampler1D,
+    LegacySampler2D,
+    LegacySampler3D,
+    LegacySamplerCube,
+}
+
+const RESOURCE_NAMES: &[(ResourceKind, &str)] = &[
+    (ResourceKind::Buffer, "Buffer"),
+    (ResourceKind::RWBuffer, "RWBuffer"),
+    (ResourceKind::RasterizerOrderedBuffer, "RasterizerOrderedBuffer"),
+    (ResourceKind::ByteAddressBuffer, "ByteAddressBuffer"),
+    (ResourceKind::RWByteAddressBuffer, "RWByteAddressBuffer"),
+    (ResourceKind::RasterizerOrderedByteAddressBuffer, "RasterizerOrderedByteAddressBuffer"),
+    (ResourceKind::StructuredBuffer, "StructuredBuffer"),
+    (ResourceKind::RWStructuredBuffer, "RWStructuredBuffer"),
+    (ResourceKind::RasterizerOrderedStructuredBuffer, "RasterizerOrderedStructuredBuffer"),
+    (ResourceKind::AppendStructuredBuffer, "AppendStructuredBuffer"),
+    (ResourceKind::ConsumeStructuredBuffer, "ConsumeStructuredBuffer"),
+    (ResourceKind::ConstantBuffer, "ConstantBuffer"),
+    (ResourceKind::TextureBuffer, "TextureBuffer"),
+    (ResourceKind::Texture1D, "Texture1D"),
+    (ResourceKind::Texture1DArray, "Texture1DArray"),
+    (ResourceKind::Texture2D, "Texture2D"),
+    (ResourceKind::Texture2DArray, "Texture2DArray"),
+    (ResourceKind::Texture2DMS, "Texture2DMS"),
+    (ResourceKind::Texture2DMSArray, "Texture2DMSArray"),
+    (ResourceKind::Texture3D, "Texture3D"),
+    (ResourceKind::TextureCube, "TextureCube"),
+    (ResourceKind::TextureCubeArray, "TextureCubeArray"),
+    (ResourceKind::RWTexture1D, "RWTexture1D"),
+    (ResourceKind::RWTexture1DArray, "RWTexture1DArray"),
+    (ResourceKind::RWTexture2D, "RWTexture2D"),
+    (ResourceKind::RWTexture2DArray, "RWTexture2DArray"),
+    (ResourceKind::RWTexture2DMS, "RWTexture2DMS"),
+    (ResourceKind::RWTexture2DMSArray, "RWTexture2DMSArray"),
+    (ResourceKind::RWTexture3D, "RWTexture3D"),
+    (ResourceKind::RasterizerOrderedTexture1D, "RasterizerOrderedTexture1D"),
+    (ResourceKind::RasterizerOrderedTexture1DArray, "RasterizerOrderedTexture1DArray"),
+    (ResourceKind::RasterizerOrderedTexture2D, "RasterizerOrderedTexture2D"),
+    (ResourceKind::RasterizerOrderedTexture2DArray, "RasterizerOrderedTexture2DArray"),
+    (ResourceKind::RasterizerOrderedTexture3D, "RasterizerOrderedTexture3D"),
+    (ResourceKind::FeedbackTexture2D, "FeedbackTexture2D"),
+    (ResourceKind::FeedbackTexture2DArray, "FeedbackTexture2DArray"),
+    (ResourceKind::RaytracingAccelerationStructure, "RaytracingAccelerationStructure"),
+    (ResourceKind::SamplerState, "SamplerState"),
+    (ResourceKind::SamplerComparisonState, "SamplerComparisonState"),
+    (ResourceKind::InputPatch, "InputPatch"),
+    (ResourceKind::OutputPatch, "OutputPatch"),
+    (ResourceKind::PointStream, "PointStream"),
+    (ResourceKind::LineStream, "LineStream"),
+    (ResourceKind::TriangleStream, "TriangleStream"),
+    (ResourceKind::LegacyTexture, "texture"),
+    (ResourceKind::LegacyTexture, "Texture"),
+    (ResourceKind::LegacyTexture1D, "texture1D"),
+    (ResourceKind::LegacyTexture2D, "texture2D"),
+    (ResourceKind::LegacyTexture3D, "texture3D"),
+    (ResourceKind::LegacyTextureCube, "textureCUBE"),
+    (ResourceKind::LegacySampler, "sampler"),
+    (ResourceKind::LegacySampler1D, "sampler1D"),
+    (ResourceKind::LegacySampler2D, "sampler2D"),
+    (ResourceKind::LegacySampler3D, "sampler3D"),
+    (ResourceKind::LegacySamplerCube, "samplerCUBE"),
+];
+
+impl ResourceKind
+{
+    pub fn from_name(name: &str) -> Option<ResourceKind>
+    {
+        return RESOURCE_NAMES.iter().find(|(_, n)| *n == name).map(|(k, _)| *k);
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        return RESOURCE_NAMES.iter().find(|(k, _)| k == self).map(|(_, n)| *n).unwrap();
+    }
+
+    pub fn is_texture(&self) -> bool
+    {
+        return self.name().to_ascii_lowercase().contains("texture") && *self != ResourceKind::TextureBuffer;
+    }
+
+    pub fn is_sampler(&self) -> bool
+    {
+        return matches!(self, ResourceKind::SamplerState | ResourceKind::SamplerComparisonState) || self.name().starts_with("sampler");
+    }
+
+    // Resources that shaders can write to, bound to u registers
+    pub fn is_read_write(&self) -> bool
+    {
+        let name = self.name();
+        return name.starts_with("RW") || name.starts_with("RasterizerOrdered") ||
+            matches!(self, ResourceKind::AppendStructuredBuffer | ResourceKind::ConsumeStructuredBuffer);
+    }
+
+    // Read-write resources with ordered access between overlapping pixels
+    pub fn is_rasterizer_ordered(&self) -> bool
+    {
+        return self.name().starts_with("RasterizerOrdered");
+    }
+
+    // Stream outputs and patches are parameters of geometry and tessellation shaders and not bound resources
+    pub fn is_shader_parameter(&self) -> bool
+    {
+        return matches!(self, ResourceKind::InputPatch | ResourceKind::OutputPatch | ResourceKind::PointStream |
+            ResourceKind::LineStream | ResourceKind::TriangleStream);
+    }
+
+    // Resources with a float4 element when none is given, like Texture2D or Buffer
+    pub fn has_default_element(&self) -> bool
+    {
+        let is_typed_texture = self.is_texture() && !matches!(self,
+            ResourceKind::FeedbackTexture2D | ResourceKind::FeedbackTexture2DArray | ResourceKind::LegacyTexture |
+            ResourceKind::LegacyTexture1D | ResourceKind::LegacyTexture2D | ResourceKind::LegacyTexture3D | ResourceKind::LegacyTextureCube);
+        return is_typed_texture || matches!(self, ResourceKind::Buffer | ResourceKind::RWBuffer | ResourceKind::RasterizerOrderedBuffer);
+    }
+}
+
+// Effect framework objects and other builtin types that are not resources
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ObjectKind
+{
+    String,
+    BlendState,
+    DepthStencilState,
+    RasterizerState,
+    VertexShader,
+    PixelShader,
+    GeometryShader,
+    HullShader,
+    DomainShader,
+    ComputeShader,
+    RayDesc,
+    RayQuery,
+    BuiltInTriangleIntersectionAttributes,
+}
+
+const OBJECT_NAMES: &[(ObjectKind, &str)] = &[
+    (ObjectKind::String, "string"),
+    (ObjectKind::BlendState, "BlendState"),
+    (ObjectKind::DepthStencilState, "DepthStencilState"),
+    (ObjectKind::RasterizerState, "RasterizerState"),
+    (ObjectKind::VertexShader, "VertexShader"),
+    (ObjectKind::PixelShader, "PixelShader"),
+    (ObjectKind::GeometryShader, "GeometryShader"),
+    (ObjectKind::HullShader, "HullShader"),
+    (ObjectKind::DomainShader, "DomainShader"),
+    (ObjectKind::ComputeShader, "ComputeShader"),
+    (ObjectKind::RayDesc, "RayDesc"),
+    (ObjectKind::RayQuery, "RayQuery"),
+    (ObjectKind::BuiltInTriangleIntersectionAttributes, "BuiltInTriangleIntersectionAttributes"),
+];
+
+impl ObjectKind
+{
+    pub fn from_name(name: &str) -> Option<ObjectKind>
+    {
+        return OBJECT_NAMES.iter().find(|(_, n)| *n == name).map(|(k, _)| *k);
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        return OBJECT_NAMES.iter().find(|(k, _)| k == self).map(|(_, n)| *n).unwrap();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ArraySize
+{
+    Fixed(u32),
+    // float values[];
+    Unsized,
+    // The size is an expression that is not constant, like float values[count] with a uniform count
+    Unresolved,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Type
+{
+    Void,
+    Scalar(ScalarType),
+    // float3 and vector<float, 3>
+    Vector(ScalarType, u32),
+    // float4x3 and matrix<float, 4, 3>, the dimensions are rows and columns
+    Matrix(ScalarType, u32, u32),
+    Resource
+    {
+        kind: ResourceKind,
+        // Texture2D<float4>, StructuredBuffer<Particle>, InputPatch<HS_INPUT, 3>
+        element: Option<Box<Type>>,
+        // Sample count of Texture2DMS<float4, 8> or the control points of a patch
+        count: Option<u32>,
+    },
+    Object(ObjectKind),
+    // Structs, classes and interfaces
+    Struct
+    {
+        name: String,
+        symbol: SymbolId,
+    },
+    Array(Box<Type>, ArraySize),
+    TemplateParameter
+    {
+        name: String,
+        symbol: SymbolId,
+    },
+    // The type could not be determined, errors are reported where the type is used
+    Error,
+}
+
+// Scalar, vector or matrix shape used when combining operands, vectors and matrices of one component behave like scalars
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shape
+{
+    Scalar,
+    Vector(u32),
+    Matrix(u32, u32),
+}
+
+impl Type
+{
+    pub fn from_shape(scalar: ScalarType, shape: Shape) -> Type
+    {
+        return match shape
+        {
+            Shape::Scalar => Type::Scalar(scalar),
+            Shape::Vector(size) => Type::Vector(scalar, size),
+            Shape::Matrix(rows, columns) => Type::Matrix(scalar, rows, columns)
+        };
+    }
+
+    pub fn shape(&self) -> Option<Shape>
+    {
+        return match self
+        {
+            Type::Scalar(_) | Type::Vector(_, 1) | Type::Matrix(_, 1, 1) => Some(Shape::Scalar),
+            Type::Vector(_, size) => Some(Shape::Vector(*size)),
+            // Single row or column matrices mix with vectors
+            Type::Matrix(_, 1, size) | Type::Matrix(_, size, 1) => Some(Shape::Vector(*size)),
+            Type::Matrix(_, rows, columns) => Some(Shape::Matrix(*rows, *columns)),
+            _ => None
+        };
+    }
+
+    // float3 and vector<float, 3>, parsed from the spelling of a type keyword like float3, min16float2x2 or uint64_t4
+    pub fn from_keyword(text: &str) -> Option<Type>
+    {
+        if text == "void"
+        {
+            return Some(Type::Void);
+        }
+        // vector and matrix without arguments
+        if text == "vector"
+        {
+            return Some(Type::Vector(ScalarType::Float, 4));
+        }
+        if text == "matrix" || text == "Matrix"
+        {
+            return Some(Type::Matrix(ScalarType::Float, 4, 4));
+        }
+
+        if let Some(scalar) = ScalarType::from_name(text)
+        {
+            return Some(Type::Scalar(scalar));
+        }
+
+        // Dimensions are one or two digits at the end, like float3 or float4x4
+        let bytes = text.as_bytes();
+        let length = bytes.len();
+        if length > 3 && bytes[length - 2] == b'x' && bytes[length - 1].is_ascii_digit() && bytes[length - 3].is_ascii_digit()
+        {
+            let scalar = ScalarType::from_name(&text[..length - 3])?;
+            let rows = (bytes[length - 3] - b'0') as u32;
+            let columns = (bytes[length - 1] - b'0') as u32;
+            if (1..=4).contains(&rows) && (1..=4).contains(&columns)
+            {
+                return Some(Type::Matrix(scalar, rows, columns));
+            }
+            return None;
+        }
+
+        if length > 1 && bytes[length - 1].is_ascii_digit()
+        {
+            let scalar = ScalarType::from_name(&text[..length - 1])?;
+            let size = (bytes[length - 1] - b'0') as u32;
+            if (1..=4).contains(&size)
+            {
+                return Some(Type::Vector(scalar, size));
+            }
+        }
+        return None;
+    }
+
+    // The component type of scalars, vectors and matrices
+    pub fn scalar_type(&self) -> Option<ScalarType>
+    {
+        return match self
+        {
+            Type::Scalar(scalar) | Type::Vector(scalar, _) | Type::Matrix(scalar, _, _) => Some(*scalar),
+            _ => None
+        };
+    }
+
+    pub fn is_numeric(&self) -> bool
+    {
+        return self.scalar_type().is_some();
+    }
+
+    // Number of scalars in a scalar, vector or matrix
+    pub fn component_count(&self) -> u32
+    {
+        return match self
+        {
+            Type::Scalar(_) => 1,
+            Type::Vector(_, size) => *size,
+            Type::Matrix(_, rows, columns) => rows * columns,
+            _ => 0
+        };
+    }
+
+    // The same shape with another component type, like float3 to int3
+    pub fn with_scalar_type(&self, scalar: ScalarType) -> Type
+    {
+        return match self
+        {
+            Type::Scalar(_) => Type::Scalar(scalar),
+            Type::Vector(_, size) => Type::Vector(scalar, *size),
+            Type::Matrix(_, rows, columns) => Type::Matrix(scalar, *rows, *columns),
+            _ => self.clone()
+        };
+    }
+
+    // The type of an element of an array, or of the value loaded from a resource
+    pub fn element_type(&self) -> Option<&Type>
+    {
+        return match self
+        {
+            Type::Array(element, _) => Some(element),
+            Type::Resource{ element, .. } => element.as_deref(),
+            _ => None
+        };
+    }
+
+    pub fn is_error(&self) -> bool
+    {
+        return *self == Type::Error;
+    }
+
+    // Types that depend on a template parameter are only known once the template is used
+    pub fn is_dependent(&self) -> bool
+    {
+        return match self
+        {
+            Type::TemplateParameter{ .. } => true,
+            Type::Array(element, _) => element.is_dependent(),
+            Type::Resource{ element: Some(element), .. } => element.is_dependent(),
+            _ => false
+        };
+    }
+
+    // Binds the template parameters in a parameter type to the matching part of the argument type, T[2] and float3[2] bind T to float3
+    pub fn deduce(&self, argument: &Type, arguments: &mut HashMap<SymbolId, Type>)
+    {
+        match (self, argument)
+        {
+            (Type::TemplateParameter{ symbol, .. }, _) if !argument.is_error() => {
+                // Literals are deduced as int and float
+                let deduced = match argument.scalar_type()
+                {
+                    Some(ScalarType::LiteralInt) => argument.with_scalar_type(ScalarType::Int),
+                    Some(ScalarType::LiteralFloat) => argument.with_scalar_type(ScalarType::Float),
+                    _ => argument.clone()
+                };
+                arguments.entry(*symbol).or_insert(deduced);
+            },
+            (Type::Array(element, _), Type::Array(argument_element, _)) => element.deduce(argument_element, arguments),
+            (Type::Resource{ element: Some(element), .. }, Type::Resource{ element: Some(argument_element), .. }) => {
+                element.deduce(argument_element, arguments);
+            },
+            _ => {}
+        }
+    }
+
+    // Replaces the template parameters by the types bound to them
+    pub fn substitute(&self, arguments: &HashMap<SymbolId, Type>) -> Type
+    {
+        return match self
+        {
+            Type::TemplateParameter{ symbol, .. } => arguments.get(symbol).cloned().unwrap_or_else(|| self.clone()),
+            Type::Array(element, size) => Type::Array(Box::new(element.substitute(arguments)), *size),
+            Type::Resource{ kind, element, count } => Type::Resource{
+                kind: *kind,
+                element: element.as_ref().map(|e| Box::new(e.substitute(arguments))),
+                count: *count
+            },
+            _ => self.clone()
+        };
+    }
+}
+
+impl fmt::Display for Type
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self
+        {
+            Type::Void => write!(f, "void"),
+            Type::Scalar(scalar) => write!(f, "{}", scalar.name()),
+            Type::Vector(scalar, size) => write!(f, "{}{}", scalar.name(), size),
+            Type::Matrix(scalar, rows, columns) => write!(f, "{}{}x{}", scalar.name(), rows, columns),
+            Type::Resource{ kind, element, count } => {
+                write!(f, "{}", kind.name())?;
+                match (element, count)
+                {
+                    (Some(element), Some(count)) => write!(f, "<{}, {}>", element, count),
+                    (Some(element), None) => write!(f, "<{}>", element),
+                    _ => Ok(())
+                }
+            },
+            Type::Object(kind) => write!(f, "{}", kind.name()),
+            Type::Struct{ name, .. } | Type::TemplateParameter{ name, .. } => write!(f, "{}", name),
+            Type::Array(..) => {
+                // Nested arrays are written in declaration order, float[2][3] is an array of 2 float[3]
+                let mut dimensions = Vec::new();
+                let mut current = self;
+                while let Type::Array(inner, inner_size) = current
+                {
+                    dimensions.push(*inner_size);
+                    current = inner;
+                }
+
+                write!(f, "{}", current)?;
+                for dimension in dimensions
+                {
+                    match dimension
+                    {
+                        ArraySize::Fixed(size) => write!(f, "[{}]", size)?,
+                        ArraySize::Unsized => write!(f, "[]")?,
+                        ArraySize::Unresolved => write!(f, "[?]")?
+                    }
+                }
+                Ok(())
+            },
+            Type::Error => write!(f, "<error>")
+        };
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ParameterDirection
+{
+    In,
+    Out,
+    InOut,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Interpolation
+{
+    Linear,
+    Centroid,
+    NoInterpolation,
+    NoPerspective,
+    Sample,
+}
+
+// Storage classes, type modifiers and interpolation modifiers written in front of a declaration
+#[derive(Default, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TypeModifiers
+{
+    pub is_const: bool,
+    pub is_static: bool,
+    pub is_uniform: bool,
+    pub is_extern: bool,
+    pub is_volatile: bool,
+    pub is_precise: bool,
+    pub is_shared: bool,
+    pub is_groupshared: bool,
+    pub is_globallycoherent: bool,
+    pub orientation: Option<MatrixOrientation>,
+    pub direction: Option<ParameterDirection>,
+    pub interpolation: Vec<Interpolation>,
+    // Geometry shader primitive types and mesh shader outputs, like 'triangle' or 'vertices'
+    pub primitive: Option<String>,
+}
+
+impl TypeModifiers
+{
+    pub fn from_tokens<'t>(tokens: impl IntoIterator<Item = &'t SyntaxToken>) -> Self
+    {
+        let mut modifiers = Self::default();
+        for token in tokens
+        {
+            match token.text.as_str()
+            {
+                "const" => modifiers.is_const = true,
+                "static" => modifiers.is_static = true,
+                "uniform" => modifiers.is_uniform = true,
+                "extern" => modifiers.is_extern = true,
+                "volatile" => modifiers.is_volatile = true,
+                "precise" => modifiers.is_precise = true,
+                "shared" => modifiers.is_shared = true,
+                "groupshared" => modifiers.is_groupshared = true,
+                "globallycoherent" => modifiers.is_globallycoherent = true,
+                "row_major" => modifiers.orientation = Some(MatrixOrientation::RowMajor),
+                "column_major" => modifiers.orientation = Some(MatrixOrientation::ColumnMajor),
+                // 'in out' is the same as 'inout'
+                "in" if modifiers.direction.is_some() => modifiers.direction = Some(ParameterDirection::InOut),
+                "out" if modifiers.direction.is_some() => modifiers.direction = Some(ParameterDirection::InOut),
+                "in" => modifiers.direction = Some(ParameterDirection::In),
+                "out" => modifiers.direction = Some(ParameterDirection::Out),
+                "inout" => modifiers.direction = Some(ParameterDirection::InOut),
+                "linear" => modifiers.interpolation.push(Interpolation::Linear),
+                "centroid" => modifiers.interpolation.push(Interpolation::Centroid),
+                "nointerpolation" => modifiers.interpolation.push(Interpolation::NoInterpolation),
+                "noperspective" => modifiers.interpolation.push(Interpolation::NoPerspective),
+                "sample" => modifiers.interpolation.push(Interpolation::Sample),
+                "point" | "line" | "triangle" | "lineadj" | "triangleadj" | "vertices" | "indices" | "primitives" | "payload" => {
+                    modifiers.primitive = Some(token.text.clone());
+                },
+                _ => {}
+            }
+        }
+        return modifiers;
+    }
+
+    // Parameters without in, out or inout are inputs
+    pub fn parameter_direction(&self) -> ParameterDirection
+    {
+        return self.direction.unwrap_or(ParameterDirection::In);
+    }
+}
+
+// A type with the modifiers of the declaration it comes from, like 'static const row_major float4x4'
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct QualifiedType
+{
+    pub ty: Type,
+    pub modifiers: TypeModifiers,
+}
+
+impl QualifiedType
+{
+    pub fn new(ty: Type) -> Self
+    {
+        return Self{
+            ty: ty,
+            modifiers: TypeModifiers::default()
+        };
+    }
+}
+
+impl fmt::Display for QualifiedType
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = &self.modifiers;
+        let mut words = Vec::new();
+        match modifiers.direction
+        {
+            Some(ParameterDirection::Out) => words.push("out"),
+            Some(ParameterDirection::InOut) => words.push("inout"),
+            _ => {}
+        }
+        if modifiers.is_static { words.push("static"); }
+        if modifiers.is_groupshared { words.push("groupshared"); }
+        if modifiers.is_const { words.push("const"); }
+        match modifiers.orientation
+        {
+            Some(MatrixOrientation::RowMajor) => words.push("row_major"),
+            Some(MatrixOrientation::ColumnMajor) => words.push("column_major"),
+            None => {}
+        }
+
+        for word in words
+        {
+            write!(f, "{} ", word)?;
+        }
+        return write!(f, "{}", self.ty);
+    }
+}
+
+// TypeResolver
+// Maps type syntax and declared symbols onto the type model, using the symbol table to find structs and typedefs
+pub struct TypeResolver<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+}
+
+impl<'t, 'a> TypeResolver<'t, 'a>
+{
+    pub fn new(table: &'t SymbolTable<'a>) -> Self
+    {
+        return Self{
+            table: table
+        };
+    }
+
+    pub fn resolve(&self, type_syntax: &TypeSyntax<'a>) -> Type
+    {
+        return match type_syntax
+        {
+            TypeSyntax::Predefined(predefined) => self.resolve_predefined(predefined),
+            TypeSyntax::User(user) => self.resolve_user(user),
+            // struct Light { ... } light; anonymous structs have no symbol
+            TypeSyntax::Struct(declaration) => {
+                match self.table.declared_by(declaration.syntax())
+                {
+                    Some(symbol) => self.symbol_type(symbol).ty,
+                    None => Type::Error
+                }
+            }
+        };
+    }
+
+    fn resolve_predefined(&self, predefined: &PredefinedType<'a>) -> Type
+    {
+        let keyword = match predefined.keyword()
+        {
+            Some(keyword) => keyword,
+            None => return Type::Error
+        };
+
+        // 'unsigned int' is uint
+        let is_unsigned = predefined.syntax().first_token().map(|t| t.text == "unsigned").unwrap_or(false);
+        if let Some(ty) = Type::from_keyword(&keyword.text)
+        {
+            if is_unsigned
+            {
+                return match ty.scalar_type()
+                {
+                    Some(ScalarType::Int) => ty.with_scalar_type(ScalarType::Uint),
+                    Some(ScalarType::Int16) => ty.with_scalar_type(ScalarType::Uint16),
+                    Some(ScalarType::Int64) => ty.with_scalar_type(ScalarType::Uint64),
+                    Some(ScalarType::Min16Int) => ty.with_scalar_type(ScalarType::Min16Uint),
+                    _ => ty
+                };
+            }
+
+            return match (&ty, predefined.type_arguments())
+            {
+                (Type::Vector(..), Some(arguments)) => self.resolve_vector(&arguments),
+                (Type::Matrix(..), Some(arguments)) => self.resolve_matrix(&arguments),
+                _ => ty
+            };
+        }
+
+        return self.resolve_builtin(&keyword.text, predefined.type_arguments());
+    }
+
+    fn resolve_user(&self, user: &UserType<'a>) -> Type
+    {
+        let name = match user.name()
+        {
+            Some(name) => name,
+            None => return Type::Error
+        };
+
+        // Struct templates are not instantiated, Pair<float> is the type Pair
+        if let Some(symbol) = self.table.symbol_of(name.syntax())
+        {
+            return self.symbol_type(symbol).ty;
+        }
+        return self.resolve_builtin(&name.text(), user.type_arguments());
+    }
+
+    // Resources and objects, like Texture2D<float4> or RaytracingAccelerationStructure
+    fn resolve_builtin(&self, name: &str, arguments: Option<TypeArgumentList<'a>>) -> Type
+    {
+        if let Some(kind) = ObjectKind::from_name(name)
+        {
+            return Type::Object(kind);
+        }
+
+        let kind = match ResourceKind::from_name(name)
+        {
+            Some(kind) => kind,
+            None => return Type::Error
+        };
+
+        let mut element = arguments.as_ref().and_then(|a| a.types().next()).map(|t| Box::new(self.resolve(&t)));
+        let count = arguments.as_ref().and_then(|a| a.values().next()).and_then(|v| self.constant_size(&v));
+        if element.is_none() && kind.has_default_element()
+        {
+            element = Some(Box::new(Type::Vector(ScalarType::Float, 4)));
+        }
+
+        return Type::Resource{
+            kind: kind,
+            element: element,
+            count: count
+        };
+    }
+
+    // vector<float, 3>
+    fn resolve_vector(&self, arguments: &TypeArgumentList<'a>) -> Type
+    {
+        let scalar = arguments.types().next().map(|t| self.resolve(&t)).and_then(|t| match t { Type::Scalar(s) => Some(s), _ => None });
+        let size = arguments.values().next().and_then(|v| self.constant_size(&v));
+        return match (scalar, size)
+        {
+            (Some(scalar), Some(size)) if (1..=4).contains(&size) => Type::Vector(scalar, size),
+            _ => Type::Error
+        };
+    }
+
+    // matrix<float, 4, 3>
+    fn resolve_matrix(&self, arguments: &TypeArgumentList<'a>) -> Type
+    {
+        let scalar = arguments.types().next().map(|t| self.resolve(&t)).and_then(|t| match t { Type::Scalar(s) => Some(s), _ => None });
+        let dimensions: Vec<Option<u32>> = arguments.values().map(|v| self.constant_size(&v)).collect();
+        return match (scalar, dimensions.as_slice())
+        {
+            (Some(scalar), [Some(rows), Some(columns)]) if (1..=4).contains(rows) && (1..=4).contains(columns) => Type::Matrix(scalar, *rows, *columns),
+            _ => Type::Error
+        };
+    }
+
+    // Sizes are literals or constant expressions, like float values[COUNT * 2] with a static const COUNT
+    fn constant_size(&self, expression: &Expression<'a>) -> Option<u32>
+    {
+        return literal_value(expression).or_else(|| ConstantFolder::new(self.table).fold(expression)?.as_u32());
+    }
+
+    // Wraps the type in the array ranks of a declarator, float a[2][3] is an array of 2 float[3]
+    pub fn apply_array_ranks(&self, ty: Type, ranks: impl Iterator<Item = ArrayRank<'a>>) -> Type
+    {
+        let sizes: Vec<ArraySize> = ranks.map(|rank| match rank.size()
+        {
+            Some(size) => self.constant_size(&size).map(ArraySize::Fixed).unwrap_or(ArraySize::Unresolved),
+            None => ArraySize::Unsized
+        }).collect();
+
+        let mut ty = ty;
+        for size in sizes.into_iter().rev()
+        {
+            ty = Type::Array(Box::new(ty), size);
+        }
+        return ty;
+    }
+
+    // The type of a variable, field, parameter, typedef or function return value, or the type a struct symbol names
+    pub fn symbol_type(&self, symbol: SymbolId) -> QualifiedType
+    {
+        let entry = self.table.get_symbol(symbol);
+        return match entry.kind
+        {
+            SymbolKind::Struct | SymbolKind::Class | SymbolKind::Interface => QualifiedType::new(Type::Struct{
+                name: self.table.qualified_name(symbol),
+                symbol: symbol
+            }),
+            SymbolKind::TemplateParameter => QualifiedType::new(Type::TemplateParameter{
+                name: entry.name.clone(),
+                symbol: symbol
+            }),
+            SymbolKind::Typedef => {
+                let typedef = match entry.parent.and_then(TypedefDecl::cast)
+                {
+                    Some(typedef) => typedef,
+                    None => return QualifiedType::new(Type::Error)
+                };
+                let declarator = TypedefDeclarator::cast(entry.declaration).unwrap();
+                let aliased = typedef.aliased_type().map(|t| self.resolve(&t)).unwrap_or(Type::Error);
+                QualifiedType{
+                    ty: self.apply_array_ranks(aliased, declarator.array_ranks()),
+                    modifiers: TypeModifiers::from_tokens(typedef.modifiers())
+                }
+            },
+            SymbolKind::GlobalVariable | SymbolKind::Field | SymbolKind::LocalVariable => {
+                let variable = match entry.parent.and_then(VariableDecl::cast)
+                {
+                    Some(variable) => variable,
+                    None => return QualifiedType::new(Type::Error)
+                };
+                let declarator = VariableDeclarator::cast(entry.declaration).unwrap();
+                let base = variable.var_type().map(|t| self.resolve(&t)).unwrap_or(Type::Error);
+                QualifiedType{
+                    ty: self.apply_array_ranks(base, declarator.array_ranks()),
+                    modifiers: TypeModifiers::from_tokens(variable.modifiers())
+                }
+            },
+            SymbolKind::Parameter => {
+                let parameter = Parameter::cast(entry.declaration).unwrap();
+                let base = parameter.param_type().map(|t| self.resolve(&t)).unwrap_or(Type::Error);
+                let ty = match parameter.declarator()
+                {
+                    Some(declarator) => self.apply_array_ranks(base, declarator.array_ranks()),
+                    None => base
+                };
+                QualifiedType{
+                    ty: ty,
+                    modifiers: TypeModifiers::from_tokens(parameter.modifiers())
+                }
+            },
+            SymbolKind::Function | SymbolKind::Method => {
+                let function = FunctionDecl::cast(entry.declaration).unwrap();
+                QualifiedType{
+                    ty: function.return_type().map(|t| self.resolve(&t)).unwrap_or(Type::Error),
+                    modifiers: TypeModifiers::from_tokens(function.modifiers())
+                }
+            },
+            SymbolKind::ConstantBuffer => QualifiedType::new(Type::Resource{
+                kind: if ConstantBufferDecl::cast(entry.declaration).map(|b| b.is_tbuffer()).unwrap_or(false) { ResourceKind::TextureBuffer } else { ResourceKind::ConstantBuffer },
+                element: None,
+                count: None
+            }),
+            SymbolKind::Namespace | SymbolKind::Technique => QualifiedType::new(Type::Error)
+        };
+    }
+}
+
+// The value of an integer literal, the common case that does not need the constant folder
+fn literal_value(expression: &Expression) -> Option<u32>
+{
+    let literal = match expression
+    {
+        Expression::Literal(literal) => literal,
+        _ => return None
+    };
+    let text = literal.token().text.trim_end_matches(['u', 'U', 'l', 'L']);
+    return match text.strip_prefix("0x").or(text.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok()
+    };
+}
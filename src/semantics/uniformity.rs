@@ -0,0 +1,365 @@
+use std::collections::HashSet;
+
+use crate::parser::{ast::*, syntax_nodes::{SyntaxNode, SyntaxNodeType}, syntax_tokens::SyntaxTokenType};
+
+use super::{call_graph::function_body, symbol_table::{SymbolId, SymbolKind, SymbolTable}, type_checker::TypeChecker, types::*};
+
+// Intrinsics that compare values between the pixels of a quad, Sample picks its mip level from the gradients of the coordinates
+const GRADIENT_INTRINSICS: &[&str] = &[
+    "ddx", "ddy", "ddx_coarse", "ddx_fine", "ddy_coarse", "ddy_fine", "fwidth",
+    "Sample", "SampleBias", "SampleCmp", "CalculateLevelOfDetail", "CalculateLevelOfDetailUnclamped",
+    "tex1D", "tex2D", "tex3D", "texCUBE", "tex1Dbias", "tex2Dbias", "tex3Dbias", "texCUBEbias",
+    "tex1Dproj", "tex2Dproj", "tex3Dproj", "texCUBEproj",
+];
+
+fn address(node: &SyntaxNode) -> usize
+{
+    return node as *const SyntaxNode as usize;
+}
+
+// The variable an assignment writes to, 'color' for 'color.rgb[0] = 1'
+fn assigned_variable(table: &SymbolTable, target: Option<Expression>) -> Option<SymbolId>
+{
+    return match target?
+    {
+        Expression::Identifier(identifier) => table.symbol_of(identifier.syntax()),
+        Expression::MemberAccess(access) => assigned_variable(table, access.object()),
+        Expression::ElementAccess(access) => assigned_variable(table, access.object()),
+        Expression::Parenthesized(parenthesized) => assigned_variable(table, parenthesized.expression()),
+        _ => None
+    };
+}
+
+// A gradient operation that runs in control flow that can differ between the pixels of a quad
+#[derive(Clone, Debug)]
+pub struct DivergentGradient<'a>
+{
+    pub call: &'a SyntaxNode,
+    // The intrinsic, or the function that performs gradient operations
+    pub name: String,
+    pub is_intrinsic: bool,
+}
+
+// UniformityAnalysis
+// Finds the variables whose value can differ between pixels and the gradient operations that run under conditions on them.
+// Parameters of functions that nothing calls are inputs that vary per pixel, parameters of other functions vary when a
+// call passes a varying argument, and variables assigned from varying values or under varying conditions vary as well
+pub struct UniformityAnalysis<'t, 'a>
+{
+    table: &'t SymbolTable<'a>,
+    checker: &'t TypeChecker<'t, 'a>,
+    varying: HashSet<SymbolId>,
+    // Functions that perform gradient operations, directly or through the functions they call
+    gradient_functions: HashSet<SymbolId>,
+    // Set when a break or continue runs under a varying condition, the rest of the loop runs divergent
+    loop_diverges: bool,
+    changed: bool,
+    reporting: bool,
+    reported: HashSet<usize>,
+    divergent_gradients: Vec<DivergentGradient<'a>>,
+}
+
+impl<'t, 'a> UniformityAnalysis<'t, 'a>
+{
+    pub fn new(unit: CompilationUnit<'a>, table: &'t SymbolTable<'a>, checker: &'t TypeChecker<'t, 'a>) -> Self
+    {
+        let mut analysis = Self{
+            table: table,
+            checker: checker,
+            varying: HashSet::new(),
+            gradient_functions: HashSet::new(),
+            loop_diverges: false,
+            changed: true,
+            reporting: false,
+            reported: HashSet::new(),
+            divergent_gradients: Vec::new()
+        };
+
+        let functions: Vec<FunctionDecl<'a>> = (0..table.get_symbols().len()).filter_map(|s| function_body(table, s)).collect();
+        let called: HashSet<SymbolId> = unit.syntax().descendant_nodes().into_iter().filter_map(|n| checker.call_target(n)).collect();
+        for function in &functions
+        {
+            if table.declared_by(function.syntax()).is_some_and(|s| called.contains(&s))
+            {
+                continue;
+            }
+            for parameter in function.params()
+            {
+                if !TypeModifiers::from_tokens(parameter.modifiers()).is_uniform
+                {
+                    analysis.varying.extend(table.declared_by(parameter.syntax()));
+                }
+            }
+        }
+
+        while analysis.changed
+        {
+            analysis.changed = false;
+            for function in &functions
+            {
+                let symbol = table.declared_by(function.syntax());
+                let uses_gradients = function.body().unwrap().syntax().descendant_nodes().into_iter().any(|n| analysis.gradient_call(n).is_some());
+                if let Some(symbol) = symbol.filter(|_| uses_gradients)
+                {
+                    analysis.changed |= analysis.gradient_functions.insert(symbol);
+                }
+                analysis.statement(Statement::Block(function.body().unwrap()), false);
+            }
+        }
+
+        analysis.reporting = true;
+        for function in &functions
+        {
+            analysis.statement(Statement::Block(function.body().unwrap()), false);
+        }
+        return analysis;
+    }
+
+    pub fn get_divergent_gradients(&self) -> &Vec<DivergentGradient<'a>>
+    {
+        return &self.divergent_gradients;
+    }
+
+    // Whether the value of the variable or parameter can differ between pixels
+    pub fn is_varying(&self, symbol: SymbolId) -> bool
+    {
+        return self.varying.contains(&symbol);
+    }
+
+    pub fn uses_gradients(&self, function: SymbolId) -> bool
+    {
+        return self.gradient_functions.contains(&function);
+    }
+
+    fn is_varying_expression(&self, expression: Option<Expression<'a>>) -> bool
+    {
+        return match expression
+        {
+            Some(expression) => expression.syntax().descendant_nodes().into_iter().any(|n| self.table.symbol_of(n).is_some_and(|s| self.varying.contains(&s))),
+            None => false
+        };
+    }
+
+    fn mark(&mut self, symbol: Option<SymbolId>)
+    {
+        if let Some(symbol) = symbol
+        {
+            if matches!(self.table.get_symbol(symbol).kind, SymbolKind::LocalVariable | SymbolKind::Parameter) && self.varying.insert(symbol)
+            {
+                self.changed = true;
+            }
+        }
+    }
+
+    // The name of a gradient intrinsic or of a function that performs gradient operations called by the node
+    fn gradient_call(&self, node: &'a SyntaxNode) -> Option<(String, bool)>
+    {
+        if node.node_type != SyntaxNodeType::CallExpression
+        {
+            return None;
+        }
+        let call = CallExpr::cast(node)?;
+        if let Some(target) = self.checker.call_target(node).filter(|t| self.gradient_functions.contains(t))
+        {
+            return Some((self.table.get_symbol(target).name.clone(), false));
+        }
+        let name = call.function_name()?;
+        // Legacy texture functions with explicit gradients, like tex2D(s, uv, dx, dy)
+        let explicit = name.text.starts_with("tex") && call.arguments().len() > 2;
+        if self.checker.intrinsic_of(node).is_some() && GRADIENT_INTRINSICS.contains(&name.text.as_str()) && !explicit
+        {
+            return Some((name.text.clone(), true));
+        }
+        return None;
+    }
+
+    // Walks the statement and returns whether the code after it runs divergent, which is the case after a return under a
+    // varying condition
+    fn statement(&mut self, statement: Statement<'a>, divergent: bool) -> bool
+    {
+        return match statement
+        {
+            Statement::Block(block) => {
+                let mut divergent = divergent;
+                for statement in block.statements()
+                {
+                    divergent = self.statement(statement, divergent);
+                }
+                divergent
+            },
+            Statement::Expression(expression) => {
+                self.expression(expression.expression(), divergent);
+                divergent
+            },
+            Statement::Return(statement) => {
+                self.expression(statement.value(), divergent);
+                divergent
+            },
+            Statement::Variable(declaration) => {
+                for declarator in declaration.declarators()
+                {
+                    let initializer = declarator.initializer();
+                    self.expression(initializer, divergent);
+                    if initializer.is_some() && (divergent || self.is_varying_expression(initializer))
+                    {
+                        self.mark(self.table.declared_by(declarator.syntax()));
+                    }
+                }
+                divergent
+            },
+            Statement::If(statement) => {
+                self.expression(statement.condition(), divergent);
+                let varying = self.is_varying_expression(statement.condition());
+                let branches = [statement.then_statement(), statement.else_statement()];
+                self.branches(statement.syntax(), &branches, divergent, varying)
+            },
+            Statement::Switch(statement) => {
+                self.expression(statement.expression(), divergent);
+                let varying = self.is_varying_expression(statement.expression());
+                let loop_diverges = self.loop_diverges;
+                let branch = divergent || varying;
+                let mut after = divergent || (varying && contains_return(statement.syntax()));
+                for section in statement.sections()
+                {
+                    for statement in section.statements()
+                    {
+                        after |= self.statement(statement, branch) && !branch;
+                    }
+                }
+                // Breaks leave the switch, not the loop around it
+                self.loop_diverges = loop_diverges;
+                after
+            },
+            Statement::While(statement) => {
+                self.expression(statement.condition(), divergent);
+                let varying = self.is_varying_expression(statement.condition());
+                self.looping(statement.syntax(), statement.body(), None, divergent, varying)
+            },
+            Statement::Do(statement) => {
+                self.expression(statement.condition(), divergent);
+                let varying = self.is_varying_expression(statement.condition());
+                self.looping(statement.syntax(), statement.body(), None, divergent, varying)
+            },
+            Statement::For(statement) => {
+                let divergent = match statement.initializer()
+                {
+                    Some(initializer) => self.statement(initializer, divergent),
+                    None => divergent
+                };
+                self.expression(statement.condition(), divergent);
+                let varying = self.is_varying_expression(statement.condition());
+                self.looping(statement.syntax(), statement.body(), statement.incrementor(), divergent, varying)
+            },
+            Statement::Break(_) | Statement::Continue(_) => {
+                if divergent
+                {
+                    self.loop_diverges = true;
+                }
+                divergent
+            },
+            Statement::Discard(_) | Statement::Empty(_) | Statement::Typedef(_) | Statement::Type(_) | Statement::Skipped(_) => divergent
+        };
+    }
+
+    fn branches(&mut self, node: &'a SyntaxNode, branches: &[Option<Statement<'a>>], divergent: bool, varying: bool) -> bool
+    {
+        let branch = divergent || varying;
+        let mut after = divergent || (varying && contains_return(node));
+        for statement in branches.iter().flatten()
+        {
+            after |= self.statement(*statement, branch) && !branch;
+        }
+        return after;
+    }
+
+    // Runs the body of a loop, again as divergent when a break or continue under a varying condition lets pixels leave early
+    fn looping(&mut self, node: &'a SyntaxNode, body: Option<Statement<'a>>, incrementor: Option<Expression<'a>>, divergent: bool, varying: bool) -> bool
+    {
+        let loop_diverges = self.loop_diverges;
+        self.loop_diverges = false;
+        let branch = divergent || varying;
+        let mut after = divergent || (varying && contains_return(node));
+        if let Some(body) = body
+        {
+            after |= self.statement(body, branch) && !branch;
+            if self.loop_diverges && !branch
+            {
+                self.statement(body, true);
+            }
+        }
+        self.expression(incrementor, branch || self.loop_diverges);
+        self.loop_diverges = loop_diverges;
+        return after;
+    }
+
+    fn expression(&mut self, expression: Option<Expression<'a>>, divergent: bool)
+    {
+        let expression = match expression
+        {
+            Some(expression) => expression,
+            None => return
+        };
+        for node in expression.syntax().descendant_nodes()
+        {
+            match Expression::cast(node)
+            {
+                Some(Expression::Assignment(assignment)) => {
+                    if divergent || self.is_varying_expression(assignment.right())
+                    {
+                        self.mark(assigned_variable(self.table, assignment.left()));
+                    }
+                },
+                Some(Expression::PrefixUnary(unary)) if divergent && is_increment(&unary.operator().token_type) => {
+                    self.mark(assigned_variable(self.table, unary.operand()));
+                },
+                Some(Expression::PostfixUnary(unary)) if divergent && is_increment(&unary.operator().token_type) => {
+                    self.mark(assigned_variable(self.table, unary.operand()));
+                },
+                Some(Expression::Call(call)) => self.call(call, divergent),
+                _ => {}
+            }
+        }
+    }
+
+    fn call(&mut self, call: CallExpr<'a>, divergent: bool)
+    {
+        let arguments = call.arguments();
+        let varying_arguments = arguments.iter().any(|a| self.is_varying_expression(Some(*a)));
+        let (parameters, directions): (Vec<Option<SymbolId>>, Vec<ParameterDirection>) = match self.checker.call_target(call.syntax())
+        {
+            Some(target) => function_body(self.table, target).map(|f| f.params()).unwrap_or_default().iter()
+                .map(|p| (self.table.declared_by(p.syntax()), TypeModifiers::from_tokens(p.modifiers()).parameter_direction())).unzip(),
+            None => self.checker.intrinsic_of(call.syntax()).map(|i| i.overload.parameters.iter().map(|p| (None, p.direction)).unzip()).unwrap_or_default()
+        };
+        for (index, argument) in arguments.iter().enumerate()
+        {
+            if self.is_varying_expression(Some(*argument))
+            {
+                self.mark(parameters.get(index).copied().flatten());
+            }
+            let writes = matches!(directions.get(index), Some(ParameterDirection::Out | ParameterDirection::InOut));
+            if writes && (divergent || varying_arguments)
+            {
+                self.mark(assigned_variable(self.table, Some(*argument)));
+            }
+        }
+
+        if self.reporting && divergent && self.reported.insert(address(call.syntax()))
+        {
+            if let Some((name, is_intrinsic)) = self.gradient_call(call.syntax())
+            {
+                self.divergent_gradients.push(DivergentGradient{ call: call.syntax(), name: name, is_intrinsic: is_intrinsic });
+            }
+        }
+    }
+}
+
+fn is_increment(token_type: &SyntaxTokenType) -> bool
+{
+    return matches!(token_type, SyntaxTokenType::PlusPlusToken | SyntaxTokenType::MinusMinusToken);
+}
+
+fn contains_return(node: &SyntaxNode) -> bool
+{
+    return node.descendant_nodes().iter().any(|n| n.node_type == SyntaxNodeType::ReturnStatement);
+}
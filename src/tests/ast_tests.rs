@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod ast_tests {
+    use crate::parser::{ast::*, syntax_parser::SyntaxTree, ParserSettings};
+
+    fn parse(text: &str) -> SyntaxTree
+    {
+        let tree = SyntaxTree::parse_text(text, &ParserSettings::default());
+        assert!(!tree.has_errors(), "Failed to parse '{}': {:?}", text, tree.diagnostics);
+        return tree;
+    }
+
+    fn span_text<'a>(text: &'a str, node: &impl AstNode<'a>) -> &'a str
+    {
+        let span = node.span();
+        return &text[span.start..span.end];
+    }
+
+    #[test]
+    fn function_declarations()
+    {
+        let text = "[numthreads(8, 1, 1)] float4 main(in float4 pos : POSITION, out uint id, float scale = 2.0f) : SV_Target { return pos; }";
+        let tree = parse(text);
+        let functions = tree.compilation_unit().functions();
+        assert_eq!(functions.len(), 1);
+
+        let function = functions[0];
+        assert!(function.is_definition());
+        assert_eq!(function.name().unwrap().text(), "main");
+        assert_eq!(span_text(text, &function.return_type().unwrap()), "float4");
+        assert_eq!(function.semantic().unwrap().name().unwrap().text, "SV_Target");
+        assert_eq!(span_text(text, &function.body().unwrap()), "{ return pos; }");
+
+        let attribute = function.attributes().next().unwrap();
+        assert_eq!(attribute.name().unwrap().text(), "numthreads");
+        assert_eq!(attribute.arguments().len(), 3);
+
+        let params = function.params();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[0].name().unwrap().text, "pos");
+        assert_eq!(params[0].modifiers()[0].text, "in");
+        assert_eq!(params[0].semantic().unwrap().name().unwrap().text, "POSITION");
+        assert_eq!(span_text(text, &params[1].param_type().unwrap()), "uint");
+        assert_eq!(span_text(text, &params[2].default_value().unwrap()), "2.0f");
+        assert_eq!(span_text(text, &params[2]), "float scale = 2.0f");
+
+        let tree = parse("float4 f(float4 x);");
+        assert!(!tree.compilation_unit().functions()[0].is_definition());
+        assert!(tree.compilation_unit().functions()[0].body().is_none());
+    }
+
+    #[test]
+    fn qualified_function_names()
+    {
+        let tree = parse("namespace Outer { struct MyClass { void TestFunc(); }; } void Outer::MyClass::TestFunc() {}");
+        let functions = tree.compilation_unit().functions();
+        let name = functions.last().unwrap().name().unwrap();
+        assert_eq!(name.text(), "Outer::MyClass::TestFunc");
+        assert_eq!(name.identifier().unwrap().text, "TestFunc");
+        assert_eq!(name.parts().len(), 3);
+        assert!(!name.is_global());
+    }
+
+    #[test]
+    fn struct_fields()
+    {
+        let text = "struct VSOutput { float4 pos : SV_Position; float2 uv[2], extra; };";
+        let tree = parse(text);
+        let declaration = tree.compilation_unit().declarations().next().unwrap();
+        let structure = match declaration
+        {
+            Declaration::Type(declaration) => declaration.struct_decl().unwrap(),
+            _ => panic!("Expected a struct declaration")
+        };
+        assert_eq!(structure.name().unwrap().text, "VSOutput");
+
+        let fields: Vec<VariableDecl> = structure.fields().collect();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(span_text(text, &fields[0]), "float4 pos : SV_Position;");
+
+        let declarators: Vec<VariableDeclarator> = fields[1].declarators().collect();
+        assert_eq!(declarators.len(), 2);
+        assert_eq!(declarators[0].name().unwrap().text, "uv");
+        assert_eq!(span_text(text, &declarators[0].array_ranks().next().unwrap().size().unwrap()), "2");
+        assert_eq!(declarators[1].name().unwrap().text, "extra");
+    }
+
+    #[test]
+    fn buffers_and_registers()
+    {
+        let tree = parse("cbuffer PerFrame : register(b1, space2) { float4x4 view; float time; }; Texture2D<float4> tex : register(t3);");
+        let declarations: Vec<Declaration> = tree.compilation_unit().declarations().collect();
+
+        let buffer = match declarations[0] { Declaration::ConstantBuffer(buffer) => buffer, _ => panic!("Expected a cbuffer") };
+        assert!(!buffer.is_tbuffer());
+        assert_eq!(buffer.name().unwrap().text, "PerFrame");
+        assert_eq!(buffer.register().unwrap().register().unwrap().text, "b1");
+        assert_eq!(buffer.register().unwrap().space().unwrap().text, "space2");
+        assert_eq!(buffer.fields().count(), 2);
+
+        let variable = match declarations[1] { Declaration::Variable(variable) => variable, _ => panic!("Expected a variable") };
+        let texture = match variable.var_type().unwrap() { TypeSyntax::Predefined(texture) => texture, _ => panic!("Expected a predefined type") };
+        assert_eq!(texture.keyword().unwrap().text, "Texture2D");
+        assert_eq!(texture.type_arguments().unwrap().types().count(), 1);
+        assert_eq!(variable.declarators().next().unwrap().register().unwrap().register().unwrap().text, "t3");
+
+        let tree = parse("[[vk::binding(0, 1)]] tbuffer Bones { float4x4 bones[64]; }");
+        let buffer = match tree.compilation_unit().declarations().next() { Some(Declaration::ConstantBuffer(buffer)) => buffer, _ => panic!("Expected a tbuffer") };
+        assert!(buffer.is_tbuffer());
+        assert_eq!(buffer.name().unwrap().text, "Bones");
+        assert_eq!(buffer.attributes().next().unwrap().name().unwrap().text(), "vk::binding");
+    }
+
+    #[test]
+    fn statements_and_expressions()
+    {
+        let text = "void f() { for (int i = 0; i < 4; i++) { x += tex.Sample(s, uv).r * 2; } if (a) b = c ? d : e; else discard; }";
+        let tree = parse(text);
+        let function = tree.compilation_unit().functions()[0];
+        let statements: Vec<Statement> = function.body().unwrap().statements().collect();
+        assert_eq!(statements.len(), 2);
+
+        let for_statement = match statements[0] { Statement::For(statement) => statement, _ => panic!("Expected a for statement") };
+        assert_eq!(span_text(text, &for_statement.initializer().unwrap()), "int i = 0;");
+        assert_eq!(span_text(text, &for_statement.condition().unwrap()), "i < 4");
+        assert_eq!(span_text(text, &for_statement.incrementor().unwrap()), "i++");
+
+        let body = match for_statement.body().unwrap() { Statement::Block(block) => block, _ => panic!("Expected a block") };
+        let expression = match body.statements().next().unwrap() { Statement::Expression(statement) => statement.expression().unwrap(), _ => panic!("Expected an expression") };
+        let assignment = match expression { Expression::Assignment(assignment) => assignment, _ => panic!("Expected an assignment") };
+        assert_eq!(assignment.operator().text, "+=");
+        let multiply = match assignment.right().unwrap() { Expression::Binary(binary) => binary, _ => panic!("Expected a binary expression") };
+        assert_eq!(span_text(text, &multiply.left().unwrap()), "tex.Sample(s, uv).r");
+
+        let access = match multiply.left().unwrap() { Expression::MemberAccess(access) => access, _ => panic!("Expected a member access") };
+        assert_eq!(access.member().unwrap().text, "r");
+        let call = match access.object().unwrap() { Expression::Call(call) => call, _ => panic!("Expected a call") };
+        assert_eq!(call.function_name().unwrap().text, "Sample");
+        assert_eq!(call.arguments().len(), 2);
+
+        let if_statement = match statements[1] { Statement::If(statement) => statement, _ => panic!("Expected an if statement") };
+        assert_eq!(span_text(text, &if_statement.condition().unwrap()), "a");
+        assert!(matches!(if_statement.else_statement(), Some(Statement::Discard(_))));
+
+        let tree = parse("void f() { for (;;) break; }");
+        let function = tree.compilation_unit().functions()[0];
+        let for_statement = match function.body().unwrap().statements().next().unwrap() { Statement::For(statement) => statement, _ => panic!("Expected a for statement") };
+        assert!(for_statement.initializer().is_none());
+        assert!(for_statement.condition().is_none());
+        assert!(for_statement.incrementor().is_none());
+        assert!(matches!(for_statement.body(), Some(Statement::Break(_))));
+    }
+
+    #[test]
+    fn techniques()
+    {
+        let tree = parse("technique11 Main { pass P0 { SetVertexShader(CompileShader(vs_5_0, VS())); CullMode = NONE; } }");
+        let technique = match tree.compilation_unit().declarations().next().unwrap() { Declaration::Technique(technique) => technique, _ => panic!("Expected a technique") };
+        assert_eq!(technique.name().unwrap().text, "Main");
+
+        let pass = technique.passes().next().unwrap();
+        assert_eq!(pass.name().unwrap().text, "P0");
+        let state = pass.state_block().unwrap();
+        assert_eq!(state.assignments().next().unwrap().name().unwrap().text, "CullMode");
+
+        let call = match state.calls().next().unwrap() { Expression::Call(call) => call, _ => panic!("Expected a call") };
+        let compile = match call.arguments()[0] { Expression::Compile(compile) => compile, _ => panic!("Expected a compile expression") };
+        assert_eq!(compile.profile().unwrap().text, "vs_5_0");
+        assert_eq!(compile.entry_point().unwrap().text, "VS");
+    }
+
+    #[test]
+    fn missing_nodes_after_errors()
+    {
+        let tree = SyntaxTree::parse_text("float4 main( : SV_Target { return ; }", &ParserSettings::default());
+        assert!(tree.has_errors());
+        let function = tree.compilation_unit().functions()[0];
+        assert_eq!(function.name().unwrap().text(), "main");
+        assert!(function.body().is_some());
+    }
+}
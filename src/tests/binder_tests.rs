@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod binder_tests {
+    use crate::parser::{ast::*, source_file::SourceFile, syntax_nodes::SyntaxNodeType, syntax_parser::SyntaxTree, HlslVersion, ParserSettings};
+    use crate::semantics::{binder::Binder, symbol_table::{ScopeKind, SymbolKind, SymbolTable}};
+    use crate::tests::test_utils::{create_full_path, find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    fn messages(table: &SymbolTable) -> Vec<String>
+    {
+        return table.get_diagnostics().iter().map(|d| d.message.clone()).collect();
+    }
+
+    // The name of the symbol the identifier at the offset refers to, with the offset of its declaration
+    fn resolve(text: &str, table: &SymbolTable, needle: &str) -> (String, usize)
+    {
+        let offset = text.find(needle).unwrap();
+        let symbol = table.symbol_at(0, offset).unwrap_or_else(|| panic!("Nothing found at '{}'", needle));
+        return (table.qualified_name(symbol), table.get_symbol(symbol).span().start);
+    }
+
+    #[test]
+    fn qualified_names_in_textures_sample()
+    {
+        let settings = ParserSettings::default();
+        let mut source = SourceFile::from_path(&create_full_path("test_files/Internal/Textures.hlsl"), &settings);
+        let tree = SyntaxTree::parse_file(&mut source, &settings);
+        let table = Binder::bind(tree.compilation_unit(), &settings);
+        assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+
+        // The out-of-line definition belongs to the method declared in the class
+        let method = table.lookup_qualified(&["Outer", "Nested", "MyClass", "TestFunc"])[0];
+        assert_eq!(table.get_symbol(method).kind, SymbolKind::Method);
+        let definition = FunctionDecl::cast(table.get_symbol(method).definition.unwrap()).unwrap();
+        assert_eq!(definition.name().unwrap().text(), "Outer::Nested::MyClass::TestFunc");
+
+        // return Outer::bar; inside it resolves to the static const in the namespace
+        let bar = table.lookup_qualified(&["Outer", "bar"])[0];
+        assert_eq!(table.get_symbol(bar).kind, SymbolKind::GlobalVariable);
+        let references: Vec<String> = table.references_to(bar).iter().map(|n| n.to_text()).collect();
+        assert_eq!(references, vec!["bar", "Outer :: bar"]);
+
+        // Outer::Nested::VertexShaderInput is not Outer::VertexShaderInput
+        let nested_input = table.lookup_qualified(&["Outer", "Nested", "VertexShaderInput"])[0];
+        let outer_input = table.lookup_qualified(&["Outer", "VertexShaderInput"])[0];
+        assert_ne!(nested_input, outer_input);
+        assert_eq!(table.references_to(nested_input).iter().filter(|n| n.node_type == SyntaxNodeType::QualifiedName).count(), 2);
+        assert!(table.references_to(outer_input).is_empty());
+        assert_eq!(table.qualified_name(nested_input), "Outer::Nested::VertexShaderInput");
+    }
+
+    #[test]
+    fn scopes_and_shadowing()
+    {
+        let text = "static float x = 1; struct S { float x; float Get() { return x; } }; \
+            float f(float x) { float y = x; { float x = 2; y += x; } for (int i = 0; i < 2; i++) { y += i; } return y; }";
+        with_bound(text, &ParserSettings::default(), |unit, table| {
+            assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+
+            // The field hides the global inside the method
+            assert_eq!(resolve(text, table, "x; } }").0, "S::x");
+            // The parameter hides the global, the block local hides the parameter
+            assert_eq!(resolve(text, table, "x; {").1, text.find("x) {").unwrap());
+            assert_eq!(resolve(text, table, "x; } for").1, text.find("x = 2").unwrap());
+            assert_eq!(resolve(text, table, "i; }").1, text.find("i = 0").unwrap());
+
+            let function = table.lookup_qualified(&["f"])[0];
+            let members = table.get_symbol(function).members.unwrap();
+            assert_eq!(table.get_scope(members).kind, ScopeKind::Function);
+            assert_eq!(table.scope_at(0, text.find("y += x").unwrap()), table.scope_of(unit.syntax().descendant_nodes()
+                .into_iter().filter(|n| n.node_type == SyntaxNodeType::Block).nth(2).unwrap()).unwrap());
+        });
+    }
+
+    #[test]
+    fn for_loop_scope_depends_on_version()
+    {
+        let text = "float f() { for (int i = 0; i < 2; i++) {} for (int i = 0; i < 2; i++) {} return i; }";
+        with_bound(text, &ParserSettings::default(), |_, table| {
+            assert_eq!(messages(table), vec!["use of undeclared identifier 'i'"]);
+        });
+
+        // Before HLSL 2021 the loop variable is visible after the loop, the last declaration wins
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        with_bound(text, &settings, |_, table| {
+            assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+            assert_eq!(resolve(text, table, "i; }").1, text.rfind("i = 0").unwrap());
+        });
+    }
+
+    #[test]
+    fn reports_undeclared_and_duplicate_names()
+    {
+        let text = "float a; float a; struct S { int m; int m; }; float4 f(float p) { float l = missing + p; Unknown u; \
+            return mul(l, N::x); } namespace N { } void S::g() {} float h(float p); float h(float p) { return p; } float h(float p) { return p; }";
+        with_bound(text, &ParserSettings::default(), |_, table| {
+            let diagnostics: Vec<(String, &str)> = table.get_diagnostics().iter().map(|d| (d.message.clone(), &text[d.span.start..d.span.end])).collect();
+            assert_eq!(diagnostics, vec![
+                (String::from("redefinition of 'a'"), "a"),
+                (String::from("redefinition of 'm'"), "m"),
+                (String::from("use of undeclared identifier 'missing'"), "missing"),
+                (String::from("use of undeclared identifier 'Unknown'"), "Unknown"),
+                (String::from("use of undeclared identifier 'N'"), "N"),
+                (String::from("out-of-line definition of 'g' does not match any declaration in 'S'"), "g"),
+                (String::from("redefinition of 'h'"), "h"),
+            ]);
+        });
+
+        // Intrinsics like mul are not declared, and overloads are not redefinitions
+        let text = "float f(float x) { return saturate(x); } float f(float2 x) { return 0; }";
+        with_bound(text, &ParserSettings::default(), |_, table| {
+            assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+        });
+    }
+
+    #[test]
+    fn binds_corpus()
+    {
+        // Many samples use loop variables after the loop, which is only valid before HLSL 2021
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            assert!(table.get_diagnostics().is_empty(), "Failed to bind {}: {:?}", path.to_str().unwrap(), table.get_diagnostics());
+        }
+    }
+}
@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod buffer_layout_tests {
+    use crate::parser::{source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings};
+    use crate::semantics::{binder::Binder, buffer_layout::*, types::ResourceKind};
+    use crate::tests::test_utils::{create_full_path, find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // Name, offset and size of a member
+    type Member = (String, u32, u32);
+
+    // The buffers of the text with their name, size and members
+    fn lay_out(text: &str, rules: LayoutRules) -> Vec<(String, u32, Vec<Member>)>
+    {
+        return lay_out_with(text, rules, &ParserSettings::default());
+    }
+
+    fn lay_out_with(text: &str, rules: LayoutRules, settings: &ParserSettings) -> Vec<(String, u32, Vec<Member>)>
+    {
+        return with_bound(text, settings, |unit, table| {
+            let calculator = LayoutCalculator::new(unit, table, settings, rules);
+            assert!(calculator.get_diagnostics().is_empty(), "{:?}", calculator.get_diagnostics());
+            return calculator.get_buffers().iter()
+                .map(|b| (b.name.clone(), b.size, b.members.iter().map(|m| (m.name.clone(), m.offset, m.size)).collect()))
+                .collect();
+        });
+    }
+
+    fn members(names: &[(&str, u32, u32)]) -> Vec<Member>
+    {
+        return names.iter().map(|(n, o, s)| (String::from(*n), *o, *s)).collect();
+    }
+
+    #[test]
+    fn packs_into_registers()
+    {
+        let text = "struct Light { float3 color; float range; float2 cone; }; \
+            cbuffer Frame { float3 position; float time; float2 jitter; float3 direction; float weights[3]; float bias; \
+            float3x3 rotation; float scale; row_major float2x3 skew; Light light; float after; double3 accurate; } \
+            cbuffer Explicit { float4 tint : packoffset(c1); float alpha : packoffset(c0.y); } \
+            tbuffer Bones { float4x4 bones[2]; }";
+        let buffers = lay_out(text, LayoutRules::Legacy);
+        assert_eq!(buffers[0], (String::from("Frame"), 256, members(&[
+            ("position", 0, 12), ("time", 12, 4), ("jitter", 16, 8),
+            // float3 does not fit in the 8 bytes left of the register
+            ("direction", 32, 12),
+            ("weights", 48, 36), ("bias", 84, 4),
+            // Column major matrices are stored as columns, the last column does not take a whole register
+            ("rotation", 96, 44), ("scale", 140, 4), ("skew", 144, 28),
+            // Structs start and end a register
+            ("light", 176, 24), ("after", 208, 4), ("accurate", 224, 24),
+        ])));
+        assert_eq!(buffers[1], (String::from("Explicit"), 32, members(&[("tint", 16, 16), ("alpha", 4, 4)])));
+        assert_eq!(buffers[2], (String::from("Bones"), 128, members(&[("bones", 0, 128)])));
+    }
+
+    #[test]
+    fn follows_spirv_rules()
+    {
+        let text = "struct Material { float3 albedo; float roughness; float2 offsets[2]; float3x3 transform; }; ConstantBuffer<Material> material;";
+        let layout = |rules: LayoutRules| lay_out(text, rules).remove(0);
+        assert_eq!(layout(LayoutRules::Legacy), (String::from("material"), 96, members(&[
+            ("albedo", 0, 12), ("roughness", 12, 4), ("offsets", 16, 24), ("transform", 48, 44)
+        ])));
+        assert_eq!(layout(LayoutRules::Std140), (String::from("material"), 96, members(&[
+            ("albedo", 0, 12), ("roughness", 12, 4), ("offsets", 16, 32), ("transform", 48, 48)
+        ])));
+        assert_eq!(layout(LayoutRules::Std430), (String::from("material"), 80, members(&[
+            ("albedo", 0, 12), ("roughness", 12, 4), ("offsets", 16, 16), ("transform", 32, 48)
+        ])));
+        assert_eq!(layout(LayoutRules::Scalar), (String::from("material"), 68, members(&[
+            ("albedo", 0, 12), ("roughness", 12, 4), ("offsets", 16, 16), ("transform", 32, 36)
+        ])));
+    }
+
+    #[test]
+    fn packs_half_by_16bit_types()
+    {
+        let text = "cbuffer Packed { half a; half2 b; min16float c; half3 d; float e; }";
+        assert_eq!(lay_out(text, LayoutRules::Legacy)[0], (String::from("Packed"), 32, members(&[
+            ("a", 0, 4), ("b", 4, 8), ("c", 12, 4), ("d", 16, 12), ("e", 28, 4)
+        ])));
+        let settings = ParserSettings{ enable_16bit_types: true, ..Default::default() };
+        assert_eq!(lay_out_with(text, LayoutRules::Legacy, &settings)[0], (String::from("Packed"), 32, members(&[
+            ("a", 0, 2), ("b", 2, 4), ("c", 6, 2), ("d", 8, 6), ("e", 16, 4)
+        ])));
+        assert_eq!(lay_out_with(text, LayoutRules::Std430, &settings)[0], (String::from("Packed"), 28, members(&[
+            ("a", 0, 2), ("b", 4, 4), ("c", 8, 2), ("d", 16, 6), ("e", 24, 4)
+        ])));
+    }
+
+    #[test]
+    fn reports_strides_and_padding()
+    {
+        let settings = ParserSettings::default();
+        with_bound("cbuffer Skinning { row_major float3x4 bones[2]; float weight; }", &settings, |unit, table| {
+            let calculator = LayoutCalculator::new(unit, table, &settings, LayoutRules::Legacy);
+            let buffer = calculator.buffer("Skinning").unwrap();
+            assert_eq!(buffer.kind, ResourceKind::ConstantBuffer);
+            let bones = &buffer.members[0];
+            assert_eq!((bones.offset, bones.size, bones.array_stride, bones.matrix_stride, bones.padding), (0, 96, 48, 16, 0));
+            assert_eq!((buffer.members[1].offset, buffer.members[1].padding), (96, 12));
+        });
+    }
+
+    #[test]
+    fn reports_invalid_packoffsets()
+    {
+        let text = "cbuffer Overlapping { float4 color : packoffset(c0); float alpha : packoffset(c0.w); float3 normal : packoffset(c1.z); \
+            float4x4 world : packoffset(c2.y); }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let calculator = LayoutCalculator::new(unit, table, &settings, LayoutRules::Legacy);
+
+            let diagnostics: Vec<(String, &str)> = calculator.get_diagnostics().iter().map(|d| (d.message.clone(), &text[d.span.start..d.span.end])).collect();
+            assert_eq!(diagnostics, vec![
+                (String::from("packoffset for 'normal' crosses a register boundary"), "normal : packoffset(c1.z)"),
+                (String::from("packoffset for 'world' crosses a register boundary"), "world : packoffset(c2.y)"),
+                (String::from("packoffset overlap between 'color' and 'alpha'"), "alpha : packoffset(c0.w)"),
+            ]);
+        });
+    }
+
+    #[test]
+    fn lays_out_multiple_const_buffers()
+    {
+        let settings = ParserSettings::default();
+        let mut source = SourceFile::from_path(&create_full_path("test_files/HlslCrossCompiler/vs4/multiple_const_buffers.hlsl"), &settings);
+        let tree = SyntaxTree::parse_file(&mut source, &settings);
+        let table = Binder::bind(tree.compilation_unit(), &settings);
+        let calculator = LayoutCalculator::new(tree.compilation_unit(), &table, &settings, LayoutRules::Legacy);
+
+        let listing: Vec<String> = calculator.get_buffers().iter().map(|b| b.to_string()).collect();
+        assert_eq!(listing.join("\n"), "\
+cbuffer $Globals // Size: 16
+{
+    float4 diffuse; // Offset: 0, Size: 16
+}
+cbuffer transformsA // Size: 80
+{
+    float4x4 modelview; // Offset: 0, Size: 64
+    int unusedTestA; // Offset: 64, Size: 4, Padding: 12
+}
+cbuffer transformsB // Size: 80
+{
+    int unusedTestB; // Offset: 0, Size: 4, Padding: 12
+    float4x4 projection; // Offset: 16, Size: 64
+}");
+    }
+
+    #[test]
+    fn lays_out_corpus()
+    {
+        let settings = ParserSettings::default();
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            for rules in [LayoutRules::Legacy, LayoutRules::Std140, LayoutRules::Std430, LayoutRules::Scalar]
+            {
+                let calculator = LayoutCalculator::new(tree.compilation_unit(), &table, &settings, rules);
+                assert!(calculator.get_diagnostics().is_empty(), "Failed to lay out {}: {:?}", path.to_str().unwrap(), calculator.get_diagnostics());
+            }
+        }
+    }
+}
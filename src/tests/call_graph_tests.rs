@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod call_graph_tests {
+    use crate::parser::{diagnostics::DiagnosticSeverity, source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings};
+    use crate::semantics::{binder::Binder, call_graph::*, symbol_table::SymbolTable};
+    use crate::tests::test_utils::{find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    fn names(table: &SymbolTable, symbols: impl IntoIterator<Item = usize>) -> Vec<String>
+    {
+        return symbols.into_iter().map(|s| table.get_symbol(s).name.clone()).collect();
+    }
+
+    // The symbol of the function with the name and a body
+    fn function(graph: &CallGraph, table: &SymbolTable, name: &str) -> usize
+    {
+        return *graph.get_functions().iter().find(|s| table.get_symbol(**s).name == name).expect(name);
+    }
+
+    #[test]
+    fn follows_calls_into_out_of_line_methods()
+    {
+        let text = "namespace Outer { namespace Nested { class MyClass { int TestFunc(); int Helper() { return 1; } }; } } \
+            int Outer::Nested::MyClass::TestFunc() { return Helper(); } \
+            Texture2D colors; SamplerState linear_sampler; cbuffer Constants { float4 tint; }; float scale; float unused_value; \
+            float4 Shade(float2 uv) { return colors.Sample(linear_sampler, uv) * tint; } \
+            float4 main(float2 uv : TEXCOORD) : SV_Target { Outer::Nested::MyClass c; return Shade(uv) * c.TestFunc() * scale; }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let graph = CallGraph::new(unit, table, &settings);
+
+            let main = function(&graph, table, "main");
+            assert_eq!(names(table, graph.callees(main)), vec!["Shade", "TestFunc"]);
+            assert_eq!(names(table, graph.callees(function(&graph, table, "TestFunc"))), vec!["Helper"]);
+            assert_eq!(names(table, graph.callers(function(&graph, table, "Shade"))), vec!["main"]);
+            assert_eq!(names(table, graph.get_entry_points().iter().copied()), vec!["main"]);
+            assert_eq!(names(table, graph.globals_of(table, main)), vec!["colors", "linear_sampler", "tint", "scale"]);
+            assert_eq!(graph.reachable(&[main]).len(), 4);
+            assert!(graph.get_diagnostics().is_empty(), "{:?}", graph.get_diagnostics());
+        });
+    }
+
+    #[test]
+    fn reports_recursion()
+    {
+        let text = "int Even(int n); int Odd(int n) { return n == 0 ? 0 : Even(n - 1); } int Even(int n) { return n == 0 ? 1 : Odd(n - 1); } \
+            int Factorial(int n) { return n <= 1 ? 1 : n * Factorial(n - 1); } \
+            [numthreads(1, 1, 1)] void main() { Odd(3); Factorial(4); }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let graph = CallGraph::new(unit, table, &settings);
+
+            let messages: Vec<&str> = graph.get_diagnostics().iter().map(|d| d.message.as_str()).collect();
+            assert_eq!(messages, vec![
+                "recursive functions are not allowed: function 'Odd' calls recursive function 'Even'",
+                "recursive functions are not allowed: function 'Factorial' calls recursive function 'Factorial'",
+            ]);
+            assert!(graph.is_recursive(function(&graph, table, "Even")));
+            assert!(!graph.is_recursive(function(&graph, table, "main")));
+        });
+    }
+
+    #[test]
+    fn reports_functions_that_are_never_called()
+    {
+        let text = "float Square(float x) { return x * x; } float Cube(float x) { return x * Square(x); } float Unused(float x) { return Cube(x); } \
+            float Offset() { return 0.5f; } struct VSOut { float4 position : SV_Position; }; \
+            VSOut VS(float4 position : POSITION) { VSOut output; output.position = position; return output; } \
+            float4 PS(float4 position : SV_Position) : SV_Target { return Square(position.x); } \
+            technique11 Render { pass P0 { SetVertexShader(CompileShader(vs_5_0, VS())); SetPixelShader(CompileShader(ps_5_0, PS())); } } \
+            technique Legacy { pass P0 { VertexShader = compile vs_2_0 VS(Offset()); } }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let graph = CallGraph::new(unit, table, &settings);
+
+            let diagnostics: Vec<(DiagnosticSeverity, &str)> = graph.get_diagnostics().iter().map(|d| (d.severity, d.message.as_str())).collect();
+            assert_eq!(diagnostics, vec![
+                (DiagnosticSeverity::Warning, "function 'Cube' is never called"),
+                (DiagnosticSeverity::Warning, "function 'Unused' is never called"),
+            ]);
+        });
+
+        // Files without entry points are libraries of helper functions
+        with_bound("float Square(float x) { return x * x; }", &settings, |unit, table| {
+            assert!(CallGraph::new(unit, table, &settings).get_diagnostics().is_empty());
+        });
+    }
+
+    #[test]
+    fn follows_calls_to_every_overload_they_can_mean()
+    {
+        let text = "void Blend(float a, int b) { } void Blend(int a, float b) { } void Mix(float2 a) { } void Mix(Texture2D a) { } \
+            float Scale(float x) { return x; } float2 Scale(float2 x) { return x; } float3 Scale(float3 x) { return x; } \
+            float4 main(float4 position : SV_Position) : SV_Target { Blend(1, 1); Mix(position.xyz, 1); return Scale(position.x); }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let graph = CallGraph::new(unit, table, &settings);
+
+            // The ambiguous call to Blend and the call to Mix that matches no overload reach both overloads
+            let main = function(&graph, table, "main");
+            assert_eq!(names(table, graph.callees(main)), vec!["Blend", "Blend", "Mix", "Mix", "Scale"]);
+            // Each overload that is never called is reported once
+            let diagnostics: Vec<(&str, &str)> = graph.get_diagnostics().iter().map(|d| (d.message.as_str(), &text[d.span.start..d.span.end])).collect();
+            assert_eq!(diagnostics, vec![("function 'Scale' is never called", "Scale"), ("function 'Scale' is never called", "Scale")]);
+            assert_eq!(graph.get_diagnostics()[0].span.start, text.find("Scale(float2").unwrap());
+        });
+    }
+
+    #[test]
+    fn finds_no_recursion_in_corpus()
+    {
+        let settings = ParserSettings::default();
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let graph = CallGraph::new(tree.compilation_unit(), &table, &settings);
+            let errors: Vec<_> = graph.get_diagnostics().iter().filter(|d| d.severity == DiagnosticSeverity::Error).collect();
+            assert!(errors.is_empty(), "{}: {:?}", path.to_str().unwrap(), errors);
+        }
+    }
+}
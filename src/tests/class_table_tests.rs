@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod class_table_tests {
+    use crate::parser::{ast::*, source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings};
+    use crate::semantics::class_table::ClassTable;
+    use crate::tests::test_utils::create_full_path;
+
+    fn parse(text: &str) -> SyntaxTree
+    {
+        let tree = SyntaxTree::parse_text(text, &ParserSettings::default());
+        assert!(!tree.has_errors(), "Failed to parse '{}': {:?}", text, tree.diagnostics);
+        return tree;
+    }
+
+    fn parse_file(path: &str) -> SyntaxTree
+    {
+        let settings = ParserSettings::default();
+        let mut source_file = SourceFile::from_path(&create_full_path(path), &settings);
+        let tree = SyntaxTree::parse_file(&mut source_file, &settings);
+        assert!(!tree.has_errors(), "Failed to parse '{}': {:?}", path, tree.diagnostics);
+        return tree;
+    }
+
+    fn calls<'a>(tree: &'a SyntaxTree) -> Vec<CallExpr<'a>>
+    {
+        return tree.root.descendant_nodes().into_iter().filter_map(CallExpr::cast).collect();
+    }
+
+    // Owner and name of the method a call resolves to, like iAlpha::ChooseAlpha
+    fn resolve(table: &ClassTable, call: &CallExpr) -> Option<String>
+    {
+        let method = table.resolve_method_call(call)?;
+        let owner = table.get_types().iter().find(|t| t.methods().any(|m| std::ptr::eq(m.syntax(), method.syntax())))?;
+        return Some(format!("{}::{}", owner.name()?.text, method.name()?.text()));
+    }
+
+    #[test]
+    fn resolves_interface_calls()
+    {
+        for path in ["test_files/HlslCrossCompiler/ps5/interfaces.hlsl", "test_files/HlslCrossCompiler/ps5/interface_arrays.hlsl"]
+        {
+            let tree = parse_file(path);
+            let table = ClassTable::new(tree.compilation_unit());
+            assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+            assert_eq!(table.get_types().iter().filter(|t| t.kind() == StructKind::Interface).count(), 2);
+            assert_eq!(table.get_types().iter().filter(|t| t.kind() == StructKind::Class).count(), 5);
+
+            let resolved: Vec<String> = calls(&tree).iter().filter_map(|c| resolve(&table, c)).collect();
+            assert_eq!(resolved, vec!["iChangeColour::ChangeColour", "iChangeColour::ChangeColour", "iAlpha::ChooseAlpha"]);
+        }
+    }
+
+    #[test]
+    fn resolves_class_methods()
+    {
+        let tree = parse_file("test_files/Internal/ClassFieldAccess.hlsl");
+        let table = ClassTable::new(tree.compilation_unit());
+        let resolved: Vec<String> = calls(&tree).iter().filter_map(|c| resolve(&table, c)).collect();
+        assert_eq!(resolved, vec!["Rng::SetState"]);
+
+        // Inherited methods and fields holding instances
+        let tree = parse("class Base { float Get() { return 1; } }; class Derived : Base { }; struct Holder { Derived d; }; \
+            float f(Holder h) { return h.d.Get(); }");
+        let table = ClassTable::new(tree.compilation_unit());
+        let resolved: Vec<String> = calls(&tree).iter().filter_map(|c| resolve(&table, c)).collect();
+        assert_eq!(resolved, vec!["Base::Get"]);
+    }
+
+    #[test]
+    fn accepts_dynamic_shader_linkage_sample()
+    {
+        let tree = parse_file("test_files/Sdk/Direct3D11/DynamicShaderLinkageFX11/DynamicShaderLinkageFX11_PS.hlsl");
+        let table = ClassTable::new(tree.compilation_unit());
+        assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+        assert!(calls(&tree).iter().any(|c| resolve(&table, c).as_deref() == Some("iBaseLight::IlluminateDiffuse")));
+    }
+
+    #[test]
+    fn reports_missing_and_mismatched_methods()
+    {
+        let text = "interface iShape { float Area(); float Scale(float factor); };\n\
+            class Square : iShape { float size; float Area() { return size * size; } };\n\
+            class Circle : iShape { float Area() { return 3; } float Scale(int factor) { return 1; } };";
+        let tree = parse(text);
+        let table = ClassTable::new(tree.compilation_unit());
+        let diagnostics = table.get_diagnostics();
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+
+        assert_eq!(diagnostics[0].message, "'Square' does not implement 'iShape::Scale'");
+        assert_eq!(&text[diagnostics[0].span.start..diagnostics[0].span.end], "Square");
+        assert_eq!(diagnostics[1].message, "'Circle::Scale' does not match the signature 'float(float)' of 'iShape::Scale'");
+        assert_eq!(diagnostics[1].span.start, text.rfind("Scale").unwrap());
+    }
+
+    #[test]
+    fn reports_invalid_interfaces()
+    {
+        let tree = parse("interface iBad { float x; float F() { return 1; } }; class C : iMissing { };");
+        let table = ClassTable::new(tree.compilation_unit());
+        let messages: Vec<&str> = table.get_diagnostics().iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["Interface 'iBad' cannot contain fields", "Interface method 'iBad::F' cannot have a body", "Unknown base type 'iMissing'"]);
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod constant_evaluator_tests {
+    use crate::parser::{ast::*, source_file::SourceFile, syntax_parser::SyntaxTree, HlslVersion, ParserSettings};
+    use crate::semantics::{binder::Binder, constant_evaluator::*, types::*};
+    use crate::tests::test_utils::{find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // The folded values of the constants in declaration order, None for the ones that are not constant
+    fn fold(text: &str) -> Vec<(String, Option<ConstantValue>)>
+    {
+        return with_bound(text, &ParserSettings::default(), |unit, table| {
+            let evaluator = ConstantEvaluator::new(unit, table);
+            assert!(evaluator.get_diagnostics().is_empty(), "{:?}", evaluator.get_diagnostics());
+            return (0..table.get_symbols().len()).map(|s| (table.get_symbol(s).name.clone(), evaluator.symbol_value(s))).collect();
+        });
+    }
+
+    #[test]
+    fn folds_with_hlsl_semantics()
+    {
+        let text = "static const int Max = 2147483647; static const int Wrapped = Max + 1; static const uint Ones = 0u - 1; \
+            static const int Shifted = (int)1 << 33; static const int LiteralShift = 1 << 33; static const int Quotient = -7 / 2; static const int Remainder = -7 % 2; \
+            static const float Third = 1.0 / 3.0; static const half HalfThird = 1.0 / 3.0; static const double Precise = 1.0l / 3.0l; \
+            static const half Overflow = 70000; static const int Truncated = (int)3.7; static const uint Converted = uint(-1); \
+            static const bool Flag = Max > 0 && !false; static const float Chosen = Flag ? 2 : 0.5; static const int64_t Wide = 1l << 40; \
+            static const uint Complement = ~0u; const float Uniform = 1; static float Mutable = 2;";
+        let values = fold(text);
+        let value = |name: &str| values.iter().find(|(n, _)| n == name).unwrap().1;
+
+        assert_eq!(value("Wrapped"), Some(ConstantValue::Int(i32::MIN as i64, ScalarType::Int)));
+        assert_eq!(value("Ones"), Some(ConstantValue::Int(u32::MAX as i64, ScalarType::Uint)));
+        assert_eq!(value("Shifted"), Some(ConstantValue::Int(2, ScalarType::Int)));
+        // Literal ints are 64 bits wide until they are converted
+        assert_eq!(value("LiteralShift"), Some(ConstantValue::Int(0, ScalarType::Int)));
+        assert_eq!(value("Quotient").unwrap().to_string(), "-3");
+        assert_eq!(value("Remainder").unwrap().to_string(), "-1");
+        assert_eq!(value("Third"), Some(ConstantValue::Float((1.0f32 / 3.0) as f64, ScalarType::Float)));
+        assert_eq!(value("HalfThird"), Some(ConstantValue::Float(0.333251953125, ScalarType::Half)));
+        assert_eq!(value("Precise"), Some(ConstantValue::Float(1.0 / 3.0, ScalarType::Double)));
+        assert_eq!(value("Overflow").unwrap().to_string(), "inf");
+        assert_eq!(value("Truncated").unwrap().to_string(), "3");
+        assert_eq!(value("Converted").unwrap().to_string(), "4294967295");
+        assert_eq!(value("Flag"), Some(ConstantValue::Bool(true)));
+        assert_eq!(value("Chosen"), Some(ConstantValue::Float(2.0, ScalarType::Float)));
+        assert_eq!(value("Wide").unwrap().to_string(), "1099511627776");
+        assert_eq!(value("Complement").unwrap().to_string(), "4294967295");
+
+        // Globals without static are uniforms that live in the $Globals buffer
+        assert_eq!(value("Uniform"), None);
+        assert_eq!(value("Mutable"), None);
+    }
+
+    #[test]
+    fn evaluates_sizes_attributes_and_registers()
+    {
+        let text = "static const uint TILE_SIZE = 8; struct Tile { static const int Count = TILE_SIZE / 2; }; \
+            groupshared float cache[TILE_SIZE * TILE_SIZE]; Texture2D<float4> tex : register(t3, space1); SamplerState samplers[4] : register(s1[2]); \
+            [numthreads(TILE_SIZE * 2, 1, 1)] void main(uint index : SV_GroupIndex) { const uint count = Tile::Count + 1; float values[count]; \
+            [unroll(Tile::Count)] for (uint i = 0; i < count; i++) { values[i] = cache[i]; } }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let evaluator = ConstantEvaluator::new(unit, table);
+            assert!(evaluator.get_diagnostics().is_empty(), "{:?}", evaluator.get_diagnostics());
+
+            let nodes = unit.syntax().descendant_nodes();
+            let function = nodes.iter().find_map(|n| FunctionDecl::cast(n)).unwrap();
+            assert_eq!(evaluator.numthreads(function), Some([16, 1, 1]));
+            let for_statement = nodes.iter().find_map(|n| ForStatement::cast(n)).unwrap();
+            assert_eq!(evaluator.unroll_count(Statement::For(for_statement)), Some(4));
+
+            let sizes: Vec<Option<u32>> = nodes.iter().filter_map(|n| ArrayRank::cast(n)).map(|r| evaluator.array_size(r)).collect();
+            assert_eq!(sizes, vec![Some(64), Some(4), Some(5)]);
+
+            let registers: Vec<_> = nodes.iter().filter_map(|n| RegisterLocation::cast(n))
+                .map(|r| (evaluator.register_index(r), evaluator.register_space(r))).collect();
+            assert_eq!(registers, vec![(Some(('t', 3)), Some(1)), (Some(('s', 3)), Some(0))]);
+
+            // Declared types use the folded sizes
+            let resolver = TypeResolver::new(table);
+            let type_of = |name: &str| resolver.symbol_type(table.get_symbols().iter().position(|s| s.name == name).unwrap()).ty.to_string();
+            assert_eq!(type_of("cache"), "float[64]");
+            assert_eq!(type_of("values"), "float[5]");
+        });
+    }
+
+    #[test]
+    fn reports_values_that_are_not_constant()
+    {
+        let text = "uint count; static const int Zero = 0; static const int Bad = 1 / Zero; static const int Loop = Loop + 1; \
+            float values[count]; float negative[-1]; [numthreads(count, 1, 1)] void main() { [unroll(Bad)] for (;;) {} }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let evaluator = ConstantEvaluator::new(unit, table);
+
+            let diagnostics: Vec<(String, &str)> = evaluator.get_diagnostics().iter().map(|d| (d.message.clone(), &text[d.span.start..d.span.end])).collect();
+            assert_eq!(diagnostics, vec![
+                (String::from("division by zero is undefined"), "1 / Zero"),
+                (String::from("variable length arrays are not supported in HLSL"), "count"),
+                (String::from("array size is negative"), "-1"),
+                (String::from("'numthreads' attribute requires an integer constant"), "count"),
+                (String::from("'unroll' attribute requires an integer constant"), "Bad"),
+            ]);
+            let loop_symbol = table.get_symbols().iter().position(|s| s.name == "Loop").unwrap();
+            assert_eq!(evaluator.symbol_value(loop_symbol), None);
+        });
+    }
+
+    #[test]
+    fn evaluates_corpus()
+    {
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let evaluator = ConstantEvaluator::new(tree.compilation_unit(), &table);
+            assert!(evaluator.get_diagnostics().is_empty(), "Failed to evaluate {}: {:?}", path.to_str().unwrap(), evaluator.get_diagnostics());
+
+            for node in tree.root.descendant_nodes()
+            {
+                if let Some(function) = FunctionDecl::cast(node)
+                {
+                    let has_numthreads = function.attributes().any(|a| a.name().map(|n| n.text() == "numthreads").unwrap_or(false));
+                    assert_eq!(has_numthreads, evaluator.numthreads(function).is_some(), "numthreads of {}", path.to_str().unwrap());
+                }
+            }
+        }
+    }
+}
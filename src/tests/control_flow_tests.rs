@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod control_flow_tests {
+    use crate::parser::{ast::*, ParserSettings};
+    use crate::semantics::control_flow::*;
+    use crate::tests::test_utils::with_bound;
+
+    // Builds the graph of the first function in the text
+    fn check<R>(text: &str, check: impl FnOnce(&ControlFlowGraph) -> R) -> R
+    {
+        return with_bound(text, &ParserSettings::default(), |unit, _| {
+            let function = unit.syntax().descendant_nodes().into_iter().find_map(FunctionDecl::cast).unwrap();
+            let graph = ControlFlowGraph::new(function).unwrap();
+            return check(&graph);
+        });
+    }
+
+    #[test]
+    fn ends_functions_that_return_on_every_path()
+    {
+        check("int Sign(int x) { if (x < 0) { return -1; } else if (x > 0) { return 1; } else { return 0; } }", |graph| {
+            assert!(!graph.falls_through());
+            assert!(graph.has_reachable_return());
+        });
+        check("int Sign(int x) { if (x < 0) { return -1; } else if (x > 0) { return 1; } }", |graph| {
+            assert!(graph.falls_through());
+        });
+        check("int Pick(int x) { switch (x) { case 0: return 1; default: return 2; } }", |graph| {
+            assert!(!graph.falls_through());
+        });
+        check("int Pick(int x) { switch (x) { case 0: return 1; case 1: break; } }", |graph| {
+            assert!(graph.falls_through());
+        });
+    }
+
+    #[test]
+    fn follows_loops_and_jumps()
+    {
+        // Loops with constant conditions only end with a break or return
+        check("int Find(int x) { while (true) { if (x > 10) { return x; } x++; } }", |graph| {
+            assert!(!graph.falls_through());
+        });
+        check("int Find(int x) { for (;;) { if (x > 10) { break; } x++; } }", |graph| {
+            assert!(graph.falls_through());
+            assert!(!graph.has_reachable_return());
+        });
+        check("int Find(int x) { do { x++; } while (1); }", |graph| {
+            assert!(!graph.falls_through());
+            assert!(!graph.is_reachable(graph.exit()));
+        });
+
+        // Code after a return has no predecessors
+        check("float Half(float x) { return x * 0.5f; x = 2.0f; }", |graph| {
+            let unreachable: Vec<BlockId> = (0..graph.get_blocks().len())
+                .filter(|b| !graph.is_reachable(*b) && !graph.get_blocks()[*b].items.is_empty()).collect();
+            assert_eq!(unreachable.len(), 1);
+            assert!(graph.predecessors(unreachable[0]).is_empty());
+            assert_eq!(graph.predecessors(graph.exit()).len(), 2);
+        });
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod definite_assignment_tests {
+    use crate::parser::{diagnostics::DiagnosticSeverity, source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings};
+    use crate::semantics::{binder::Binder, definite_assignment::*};
+    use crate::tests::test_utils::{find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // The messages of the warnings with the text they point at
+    fn check(text: &str) -> Vec<(String, String)>
+    {
+        let settings = ParserSettings::default();
+        return with_bound(text, &settings, |unit, table| {
+            let analysis = DefiniteAssignment::new(unit, table, &settings);
+            return analysis.get_diagnostics().iter().map(|d| (d.message.clone(), text[d.span.start..d.span.end].to_string())).collect();
+        });
+    }
+
+    fn messages(text: &str) -> Vec<String>
+    {
+        return check(text).into_iter().map(|(message, _)| message).collect();
+    }
+
+    #[test]
+    fn reports_reads_before_writes()
+    {
+        let text = "float4 main(float4 color : COLOR, bool fade : FADE) : SV_Target \
+            { float scale; float bias; float alpha; float unused; if (fade) { scale = 0.5f; } alpha = color.a; return color * scale + bias * alpha; }";
+        assert_eq!(check(text), vec![
+            (String::from("variable 'scale' may be uninitialized when used here"), String::from("scale")),
+            (String::from("variable 'bias' is uninitialized when used here"), String::from("bias")),
+        ]);
+
+        // Partial writes, out arguments and compound assignments
+        let text = "float4 main(float2 uv : TEXCOORD) : SV_Target \
+            { float4 color; color.rgb = 1.0f; color.a = 0.5f; float whole; float fraction = modf(uv.x, whole); \
+            int count; count += 1; float total; for (int i = 0; i < 4; i++) { total = i; } return color * whole * fraction * count * total; }";
+        assert_eq!(messages(text), vec![
+            String::from("variable 'count' is uninitialized when used here"),
+            String::from("variable 'total' may be uninitialized when used here"),
+        ]);
+
+        // Static locals are zero initialized and code after a return never runs
+        let text = "float Count() { static float calls; calls += 1.0f; return calls; float late; return late; }";
+        assert!(messages(text).is_empty(), "{:?}", messages(text));
+    }
+
+    #[test]
+    fn reports_out_parameters_that_are_not_assigned()
+    {
+        let text = "void Split(float value, out float whole, out float fraction, out float sign, inout float total) \
+            { whole = floor(value); if (value > 0) { fraction = value - whole; } total += value; } \
+            void Fill(out float4 color, bool bright) { if (bright) { color = 1.0f; return; } color = 0.5f; } \
+            void Forward(out float4 color) { Fill(color, true); }";
+        assert_eq!(check(text), vec![
+            (String::from("out parameter 'fraction' is not assigned on all paths"), String::from("fraction")),
+            (String::from("out parameter 'sign' is never assigned"), String::from("sign")),
+        ]);
+        assert!(messages("void Clear(in out float value) { value = value * 2.0f; }").is_empty());
+    }
+
+    #[test]
+    fn reports_missing_returns()
+    {
+        let text = "float Sign(float x) { if (x < 0) { return -1.0f; } else if (x > 0) { return 1.0f; } } \
+            float Zero(float x) { x = 0.0f; } \
+            float Loop(float x) { while (true) { if (x > 1.0f) { return x; } x *= 2.0f; } } \
+            int Pick(int x) { switch (x) { case 0: return 1; default: return 2; } } \
+            void Nothing() { }";
+        assert_eq!(check(text), vec![
+            (String::from("non-void function does not return a value in all control paths"), String::from("}")),
+            (String::from("non-void function does not return a value"), String::from("}")),
+        ]);
+    }
+
+    #[test]
+    fn finds_no_missing_returns_in_corpus()
+    {
+        let settings = ParserSettings::default();
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let analysis = DefiniteAssignment::new(tree.compilation_unit(), &table, &settings);
+            for diagnostic in analysis.get_diagnostics()
+            {
+                assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning, "{}: {:?}", path.to_str().unwrap(), diagnostic);
+                assert!(!diagnostic.message.starts_with("non-void function"), "{}: {:?}", path.to_str().unwrap(), diagnostic);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod entry_points_tests {
+    use crate::parser::{ast::*, source_file::SourceFile, syntax_parser::SyntaxTree, HlslVersion, ParserSettings, ShaderModel, ShaderStage, ShaderTarget};
+    use crate::semantics::{binder::Binder, entry_points::*};
+    use crate::tests::test_utils::{find_all_effect_paths, with_bound};
+
+    // The names and stages of the entry points of the text
+    fn find(text: &str) -> Vec<(String, ShaderStage, Option<ShaderModel>)>
+    {
+        return with_bound(text, &ParserSettings::default(), |unit, table| {
+            let finder = EntryPointFinder::new(unit, table);
+            return finder.get_entry_points().iter().map(|e| (e.name.clone(), e.stage, e.shader_model)).collect();
+        });
+    }
+
+    #[test]
+    fn infers_stages_from_attributes()
+    {
+        let text = "struct Payload { uint count; }; struct Vertex { float4 position : SV_Position; }; struct Patch { float edges[3] : SV_TessFactor; float inside : SV_InsideTessFactor; }; \
+            [shader(\"raygeneration\")] void rays() { } \
+            [numthreads(8, 8, 1)] void cs(uint3 id : SV_DispatchThreadID) { } \
+            [numthreads(32, 1, 1)] [outputtopology(\"triangle\")] void ms(out vertices Vertex verts[3]) { } \
+            groupshared Payload payload; [numthreads(1, 1, 1)] void as() { DispatchMesh(1, 1, 1, payload); } \
+            Patch constants() { return (Patch)0; } \
+            [domain(\"tri\")] [partitioning(\"fractional_odd\")] [outputtopology(\"triangle_cw\")] [outputcontrolpoints(3)] [patchconstantfunc(\"constants\")] \
+            Vertex hs(InputPatch<Vertex, 3> patch, uint id : SV_OutputControlPointID) { return patch[id]; } \
+            [domain(\"tri\")] Vertex ds(Patch input, float3 location : SV_DomainLocation, const OutputPatch<Vertex, 3> patch) { return patch[0]; } \
+            [maxvertexcount(3)] void gs(triangle Vertex input[3], inout TriangleStream<Vertex> stream) { } \
+            [earlydepthstencil] float4 ps(Vertex input) : SV_Target { return 1; } \
+            float4 helper() { return 0; }";
+        assert_eq!(find(text), vec![
+            (String::from("rays"), ShaderStage::RayGeneration, None),
+            (String::from("cs"), ShaderStage::Compute, None),
+            (String::from("ms"), ShaderStage::Mesh, None),
+            (String::from("as"), ShaderStage::Amplification, None),
+            (String::from("hs"), ShaderStage::Hull, None),
+            (String::from("ds"), ShaderStage::Domain, None),
+            (String::from("gs"), ShaderStage::Geometry, None),
+            (String::from("ps"), ShaderStage::Pixel, None),
+        ]);
+    }
+
+    #[test]
+    fn finds_compiled_effect_functions()
+    {
+        let text = "float4 VS(float4 position : POSITION) : SV_Position { return position; } float4 PS() : SV_Target { return 1; } float4 Legacy() : COLOR { return 0; } \
+            technique11 Render { pass P0 { SetVertexShader(CompileShader(vs_5_0, VS())); SetPixelShader(CompileShader(ps_5_0, PS())); } \
+                pass P1 { SetVertexShader(CompileShader(vs_4_0_level_9_1, VS())); SetPixelShader(CompileShader(ps_5_0, Missing())); } } \
+            technique Fallback { pass P0 { PixelShader = compile ps_2_a Legacy(); } }";
+        assert_eq!(find(text), vec![
+            (String::from("VS"), ShaderStage::Vertex, Some(ShaderModel::new(5, 0))),
+            (String::from("PS"), ShaderStage::Pixel, Some(ShaderModel::new(5, 0))),
+            (String::from("Legacy"), ShaderStage::Pixel, Some(ShaderModel::new(2, 0))),
+        ]);
+        assert_eq!(ShaderStage::from_profile("cs_6_6"), Some((ShaderStage::Compute, ShaderModel::new(6, 6))));
+        assert_eq!(ShaderStage::from_profile("lib_6_3"), None);
+    }
+
+    #[test]
+    fn infers_stages_from_semantics()
+    {
+        let text = "struct VSOut { float4 position : SV_POSITION; float2 uv : TEXCOORD; }; \
+            VSOut VSMain(float3 position : POSITION) { return (VSOut)0; } float4 PSMain(VSOut input) : SV_Target { return 1; } \
+            void CSMain(uint3 id : SV_DispatchThreadID) { } float4 helper(float4 value) { return value; }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let finder = EntryPointFinder::new(unit, table);
+            assert!(finder.get_entry_points().is_empty());
+
+            let stage = |name: &str| finder.entry_point(name, &settings).map(|e| e.stage);
+            assert_eq!(stage("VSMain"), Some(ShaderStage::Vertex));
+            assert_eq!(stage("PSMain"), Some(ShaderStage::Pixel));
+            assert_eq!(stage("CSMain"), Some(ShaderStage::Compute));
+            assert_eq!(stage("helper"), None);
+            assert_eq!(stage("missing"), None);
+
+            // A target profile decides the stage
+            let mut settings = settings.clone();
+            settings.target = ShaderTarget::from_profile("vs_6_0");
+            let entry = finder.entry_point("PSMain", &settings).unwrap();
+            assert_eq!((entry.stage, entry.shader_model), (ShaderStage::Vertex, Some(ShaderModel::new(6, 0))));
+            assert_eq!(finder.entry_point("helper", &settings).map(|e| e.stage), Some(ShaderStage::Vertex));
+            settings.target = ShaderTarget::from_profile("lib_6_3");
+            assert_eq!(finder.entry_point("PSMain", &settings).map(|e| e.stage), Some(ShaderStage::Pixel));
+        });
+    }
+
+    #[test]
+    fn reading_a_position_does_not_make_a_vertex_shader()
+    {
+        let text = "struct PSOut { float4 color : SV_Target0; float depth : SV_Depth; }; \
+            float4 Shade(float4 position : SV_Position, float2 uv : TEXCOORD0) : SV_Target1 { return position; } \
+            PSOut Resolve(float4 position : SV_POSITION) { return (PSOut)0; } \
+            void Transform(float3 position : POSITION, out float4 clip : SV_Position) { clip = float4(position, 1); }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let finder = EntryPointFinder::new(unit, table);
+            assert!(finder.get_entry_points().is_empty());
+
+            let stage = |name: &str| finder.entry_point(name, &settings).map(|e| e.stage);
+            assert_eq!(stage("Shade"), Some(ShaderStage::Pixel));
+            assert_eq!(stage("Resolve"), Some(ShaderStage::Pixel));
+            assert_eq!(stage("Transform"), Some(ShaderStage::Vertex));
+        });
+    }
+
+    #[test]
+    fn finds_corpus_effect_entry_points()
+    {
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        for path in find_all_effect_paths()
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let finder = EntryPointFinder::new(tree.compilation_unit(), &table);
+
+            for compile in tree.root.descendant_nodes().into_iter().filter_map(CompileExpr::cast)
+            {
+                let name = compile.entry_point().unwrap().text.clone();
+                let stage = ShaderStage::from_profile(&compile.profile().unwrap().text).unwrap().0;
+                assert!(finder.get_entry_points().iter().any(|e| e.name == name && e.stage == stage), "Missing {} in {}", name, path.to_str().unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,272 @@
+#[cfg(test)]
+mod formatter_tests {
+    use crate::formatting::*;
+    use crate::parser::{syntax_parser::SyntaxTree, syntax_tokens::TextSpan, ParserSettings};
+    use crate::tests::test_utils::*;
+
+    fn format_with(text: &str, settings: &FormatSettings) -> String
+    {
+        let formatted = Formatter::format_text(text, &ParserSettings::default(), settings).unwrap();
+        let again = Formatter::format_text(&formatted, &ParserSettings::default(), settings).unwrap();
+        assert_eq!(formatted, again, "Formatting is not idempotent for '{}'", text);
+        return formatted;
+    }
+
+    fn format(text: &str) -> String
+    {
+        return format_with(text, &FormatSettings::default());
+    }
+
+    // The text of the tokens of the file itself, macros expanded
+    fn token_texts(text: &str, settings: &ParserSettings) -> Vec<String>
+    {
+        let tree = SyntaxTree::parse_text(text, settings);
+        return tree.root.descendant_tokens().iter().filter(|t| t.file_index == 0).map(|t| t.text.clone()).collect();
+    }
+
+    #[test]
+    fn reads_format_settings()
+    {
+        let config = "# project style\n[format]\nindent_width = 2\nbrace_style = \"attach\"\nuse_tabs = true # for now\nmax_line_width = 80\n";
+        let settings = FormatSettings::from_config(config).unwrap();
+        assert_eq!(settings.indent_width, 2);
+        assert_eq!(settings.brace_style, BraceStyle::Attach);
+        assert!(settings.use_tabs);
+        assert_eq!(settings.max_line_width, 80);
+        assert!(settings.align_semantics);
+
+        assert_eq!(FormatSettings::from_config("brace_style = gnu").unwrap_err(), "line 1: invalid value 'gnu' for 'brace_style'");
+        assert_eq!(FormatSettings::from_config("\nindent_width").unwrap_err(), "line 2: expected 'key = value'");
+        assert_eq!(FormatSettings::from_config("tab_width = 4").unwrap_err(), "line 1: unknown setting 'tab_width'");
+    }
+
+    #[test]
+    fn places_braces_and_indents()
+    {
+        let text = "float4 main(float4 p : POSITION) : SV_Position { if (p.x > 0) { p.x = 0; } else p.y = 1; \
+            for (int i = 0; i < 4; ++i) p.z += i; return p; }";
+        assert_eq!(format(text), "\
+float4 main(float4 p : POSITION) : SV_Position
+{
+    if (p.x > 0)
+    {
+        p.x = 0;
+    }
+    else
+        p.y = 1;
+    for (int i = 0; i < 4; ++i)
+        p.z += i;
+    return p;
+}
+");
+
+        let settings = FormatSettings{ brace_style: BraceStyle::Attach, indent_width: 2, ..Default::default() };
+        let text = "struct S { float a; }; void f(int x) { switch (x) { case 0: { break; } default: x = 1; } \
+            do { x--; } while (x > 0); if (x) { } else if (x < 0) { x = 2; } }";
+        assert_eq!(format_with(text, &settings), "\
+struct S {
+  float a;
+};
+void f(int x) {
+  switch (x) {
+    case 0: {
+      break;
+    }
+    default:
+      x = 1;
+  }
+  do {
+    x--;
+  } while (x > 0);
+  if (x) {
+  } else if (x < 0) {
+    x = 2;
+  }
+}
+");
+    }
+
+    #[test]
+    fn spaces_operators()
+    {
+        let text = "float f(float a,float b){float c=a+b*-a;c+=(float)b?a:b;return max(c,a[0]).x++;}";
+        assert_eq!(format(text), "\
+float f(float a, float b)
+{
+    float c = a + b * -a;
+    c += (float)b ? a : b;
+    return max(c, a[0]).x++;
+}
+");
+
+        let settings = FormatSettings{ space_around_operators: false, ..Default::default() };
+        assert_eq!(format_with("int x = 1 + 2 * 3;", &settings), "int x=1+2*3;\n");
+        // Tokens that would run together keep a space
+        assert_eq!(format_with("int y = 1 - -2;", &settings), "int y=1- -2;\n");
+    }
+
+    #[test]
+    fn preserves_comments_and_directives()
+    {
+        let text = "// header\n#define SCALE 2 // doubled\n\n\n\nfloat x = SCALE;   // trailing\n/* block */ float y;\n\
+            void f()\n{\n    // inside\n    x = 1; /* after */\n    // before the brace\n}\n";
+        assert_eq!(format(text), "// header\n#define SCALE 2 // doubled\n\nfloat x = SCALE; // trailing\n/* block */\nfloat y;\n\
+            void f()\n{\n    // inside\n    x = 1; /* after */\n    // before the brace\n}\n");
+    }
+
+    #[test]
+    fn leaves_disabled_regions_alone()
+    {
+        let text = "float a;\n#if 0\nint   broken = ;;\n   ugly  (  ) \n#else\nfloat  b ;\n#endif\nfloat  c;\n";
+        assert_eq!(format(text), "float a;\n#if 0\nint   broken = ;;\n   ugly  (  ) \n#else\nfloat b;\n#endif\nfloat c;\n");
+    }
+
+    #[test]
+    fn aligns_semantics_and_registers()
+    {
+        let text = "struct VSOut { float4 position : SV_Position; float2 uv : TEXCOORD0;\n\n float3 normalWS : NORMAL; float4 tangent : TANGENT; float a, b; };\n\
+            cbuffer Constants : register(b0) { float4x4 world : packoffset(c0); float4 tint : packoffset(c4); }";
+        assert_eq!(format(text), "\
+struct VSOut
+{
+    float4 position : SV_Position;
+    float2 uv       : TEXCOORD0;
+
+    float3 normalWS : NORMAL;
+    float4 tangent  : TANGENT;
+    float a, b;
+};
+cbuffer Constants : register(b0)
+{
+    float4x4 world : packoffset(c0);
+    float4 tint    : packoffset(c4);
+}
+");
+
+        let settings = FormatSettings{ align_semantics: false, ..Default::default() };
+        assert_eq!(format_with("struct S { float4 position : SV_Position; float2 uv : TEXCOORD0; };", &settings),
+            "struct S\n{\n    float4 position : SV_Position;\n    float2 uv : TEXCOORD0;\n};\n");
+
+        // Empty lines that are removed do not split the members
+        let settings = FormatSettings{ max_blank_lines: 0, ..Default::default() };
+        assert_eq!(format_with("struct S { float4 position : SV_Position;\n\n float2 uv : TEXCOORD0; };", &settings),
+            "struct S\n{\n    float4 position : SV_Position;\n    float2 uv       : TEXCOORD0;\n};\n");
+    }
+
+    #[test]
+    fn wraps_long_lines()
+    {
+        let settings = FormatSettings{ max_line_width: 40, ..Default::default() };
+        let text = "float value = compute(firstArgument, secondArgument, thirdArgument) + offset;";
+        assert_eq!(format_with(text, &settings), "\
+float value = compute(firstArgument,
+    secondArgument, thirdArgument) +
+    offset;
+");
+        // Line breaks after commas are kept
+        let text = "float4 color = float4(\n    1,\n    0, 0, 1);";
+        assert_eq!(format(text), "float4 color = float4(\n    1,\n    0, 0, 1);\n");
+    }
+
+    #[test]
+    fn refuses_text_with_syntax_errors()
+    {
+        let errors = Formatter::format_text("float x = ;", &ParserSettings::default(), &FormatSettings::default()).unwrap_err();
+        assert!(!errors.is_empty());
+    }
+
+    fn apply(text: &str, edits: &[TextEdit]) -> String
+    {
+        let mut result = String::from(text);
+        for edit in edits.iter().rev()
+        {
+            result.replace_range(edit.span.start..edit.span.end, &edit.new_text);
+        }
+        return result;
+    }
+
+    fn edit(start: usize, end: usize, new_text: &str) -> TextEdit
+    {
+        return TextEdit{ span: TextSpan::new(start, end), new_text: String::from(new_text) };
+    }
+
+    #[test]
+    fn formats_ranges()
+    {
+        let settings = FormatSettings::default();
+        let text = "float  a;\nvoid f()\n{\n  a=1;\n      if (a)   { a =2; }\n}\nfloat  b;\n";
+        let start = text.find("if").unwrap();
+        let edits = Formatter::format_range(text, &ParserSettings::default(), &settings, TextSpan::new(start, start + 19)).unwrap();
+        assert_eq!(apply(text, &edits), "float  a;\nvoid f()\n{\n  a=1;\n    if (a)\n    {\n        a = 2;\n    }\n}\nfloat  b;\n");
+        // Only the whitespace that changes is edited
+        assert_eq!(edits[0], edit(start - 2, start, ""));
+
+        // A selection over several statements formats the block around them
+        let start = text.find("a=1").unwrap();
+        let edits = Formatter::format_range(text, &ParserSettings::default(), &settings, TextSpan::new(start, start + 7)).unwrap();
+        assert_eq!(apply(text, &edits), "float  a;\nvoid f()\n{\n    a = 1;\n    if (a)   { a =2; }\n}\nfloat  b;\n");
+
+        // The whole file gives the same text as formatting it
+        let edits = Formatter::format_range(text, &ParserSettings::default(), &settings, TextSpan::new(0, text.len())).unwrap();
+        assert_eq!(apply(text, &edits), format(text));
+    }
+
+    #[test]
+    fn formats_ranges_next_to_syntax_errors()
+    {
+        let text = "float a = ;\nfloat   b;\n";
+        let start = text.find("float   b").unwrap();
+        let edits = Formatter::format_range(text, &ParserSettings::default(), &FormatSettings::default(), TextSpan::new(start, text.len() - 1)).unwrap();
+        assert_eq!(edits, vec![edit(start + 6, start + 8, "")]);
+
+        let errors = Formatter::format_range(text, &ParserSettings::default(), &FormatSettings::default(), TextSpan::new(0, 5)).unwrap_err();
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn formats_on_type()
+    {
+        let settings = FormatSettings::default();
+        let text = "void f(int x)\n{\n  int y=x*2;\n  if(y>0){\n  y--;}\n}\n";
+        let offset = text.find(";").unwrap() + 1;
+        let edits = Formatter::format_on_type(text, &ParserSettings::default(), &settings, offset).unwrap();
+        assert_eq!(apply(text, &edits), "void f(int x)\n{\n    int y = x * 2;\n  if(y>0){\n  y--;}\n}\n");
+
+        let offset = text.find("--;}").unwrap() + 4;
+        let edits = Formatter::format_on_type(text, &ParserSettings::default(), &settings, offset).unwrap();
+        assert_eq!(apply(text, &edits), "void f(int x)\n{\n  int y=x*2;\n    if (y > 0)\n    {\n        y--;\n    }\n}\n");
+
+        // Anything but a ';' or '}' in front of the offset leaves the text alone
+        assert!(Formatter::format_on_type(text, &ParserSettings::default(), &settings, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn formats_all_test_files_idempotently()
+    {
+        let all_settings = [
+            FormatSettings::default(),
+            FormatSettings{ max_blank_lines: 0, ..Default::default() },
+            FormatSettings{ brace_style: BraceStyle::Attach, indent_width: 2, max_line_width: 40, ..Default::default() },
+            FormatSettings{ use_tabs: true, space_around_operators: false, align_semantics: false, ..Default::default() },
+        ];
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            // A few files use legacy code pages
+            let text = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).into_owned();
+            let settings = ParserSettings{ include_paths: vec![path.parent().unwrap().to_path_buf()], ..Default::default() };
+            for format_settings in &all_settings
+            {
+                let formatted = match Formatter::format_text(&text, &settings, format_settings)
+                {
+                    Ok(formatted) => formatted,
+                    Err(errors) => panic!("Failed to format {}: {:?}", path.to_str().unwrap(), errors)
+                };
+                let again = Formatter::format_text(&formatted, &settings, format_settings).unwrap();
+                assert_eq!(formatted, again, "Formatting {} with {:?} is not idempotent", path.to_str().unwrap(), format_settings);
+                let edits = Formatter::format_range(&text, &settings, format_settings, TextSpan::new(0, text.len())).unwrap();
+                assert_eq!(apply(&text, &edits), formatted, "Formatting all of {} as a range with {:?} differs", path.to_str().unwrap(), format_settings);
+                assert_eq!(token_texts(&text, &settings), token_texts(&formatted, &settings), "Formatting {} changed its tokens", path.to_str().unwrap());
+            }
+        }
+    }
+}
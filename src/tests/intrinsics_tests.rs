@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod intrinsics_tests {
+    use crate::parser::{ast::AstNode, syntax_nodes::SyntaxNodeType, ParserSettings, ShaderModel, ShaderStage};
+    use crate::semantics::{intrinsics::*, type_checker::TypeChecker, types::*};
+    use crate::tests::test_utils::with_bound;
+
+    fn scalar(name: &str) -> Type
+    {
+        return Type::from_keyword(name).unwrap();
+    }
+
+    fn texture(kind: ResourceKind, element: &str) -> Type
+    {
+        return Type::Resource{ kind: kind, element: Some(Box::new(scalar(element))), count: None };
+    }
+
+    // The deduced signature of the function, or None when no overload matches
+    fn resolve(name: &str, arguments: &[&str]) -> Option<String>
+    {
+        let arguments: Vec<Type> = arguments.iter().map(|a| scalar(a)).collect();
+        return IntrinsicCatalogue::get().resolve_function(name, &arguments).map(|c| c.to_string());
+    }
+
+    #[test]
+    fn catalogue_covers_functions_and_methods()
+    {
+        let catalogue = IntrinsicCatalogue::get();
+        for name in ["mul", "lerp", "saturate", "InterlockedAdd", "WaveActiveSum", "DispatchMesh", "TraceRay", "select", "and", "or"]
+        {
+            assert!(catalogue.is_function(name), "{} is missing", name);
+        }
+
+        let methods = catalogue.method_names("Texture2D");
+        for name in ["Sample", "SampleLevel", "GatherRed", "Load", "GetDimensions"]
+        {
+            assert!(methods.contains(&name), "Texture2D.{} is missing", name);
+        }
+        assert!(!catalogue.method_names("TextureCube").contains(&"Load"));
+        assert!(catalogue.function_names().windows(2).all(|w| w[0] < w[1]));
+
+        // Signatures print back in their template form
+        assert_eq!(catalogue.functions("lerp")[0].to_string(), "float_like<*> lerp(float_like<*> x, float_like<*> y, float_like<*> s)");
+        assert_eq!(catalogue.functions("sincos")[0].to_string(), "void sincos(float_like<*> x, out float_like<*> s, out float_like<*> c)");
+        assert_eq!(catalogue.methods("Texture2D", "Sample")[0].to_string(), "element Sample(sampler s, float<C> location)");
+
+        let dispatch = catalogue.functions("DispatchMesh")[0];
+        assert_eq!(dispatch.shader_model, ShaderModel::new(6, 5));
+        assert!(dispatch.is_allowed_in(ShaderStage::Amplification) && !dispatch.is_allowed_in(ShaderStage::Mesh));
+        assert!(!catalogue.functions("ddx")[0].is_allowed_in(ShaderStage::Vertex));
+        assert!(catalogue.functions("WaveActiveSum")[0].is_allowed_in(ShaderStage::Compute));
+        assert_eq!(catalogue.functions("TraceRay")[0].shader_model.to_string(), "6.3");
+    }
+
+    #[test]
+    fn deduces_template_arguments()
+    {
+        assert_eq!(resolve("lerp", &["float3", "float3", "float"]).unwrap(), "float3 lerp(float3 x, float3 y, float3 s)");
+        assert_eq!(resolve("saturate", &["int2"]).unwrap(), "float2 saturate(float2 x)");
+        assert_eq!(resolve("max", &["int", "uint"]).unwrap(), "uint max(uint x, uint y)");
+        assert_eq!(resolve("dot", &["half4", "half4"]).unwrap(), "half dot(half4 a, half4 b)");
+        assert_eq!(resolve("WaveActiveSum", &["int3"]).unwrap(), "int3 WaveActiveSum(int3 value)");
+        assert_eq!(resolve("InterlockedAdd", &["uint", "int", "uint"]).unwrap(), "void InterlockedAdd(inout uint dest, uint value, out uint original)");
+        assert_eq!(resolve("transpose", &["float3x4"]).unwrap(), "float4x3 transpose(float3x4 m)");
+        assert_eq!(resolve("select", &["bool2", "float2", "int"]).unwrap(), "float2 select(bool2 condition, float2 true_value, float2 false_value)");
+        assert_eq!(resolve("asuint", &["float3"]).unwrap(), "uint3 asuint(float3 x)");
+
+        // mul picks the overload with the cheapest conversions
+        assert_eq!(resolve("mul", &["float4", "float4x4"]).unwrap(), "float4 mul(float4 a, float4x4 b)");
+        assert_eq!(resolve("mul", &["float3x4", "float4"]).unwrap(), "float3 mul(float3x4 a, float4 b)");
+        assert_eq!(resolve("mul", &["float", "float3"]).unwrap(), "float3 mul(float a, float3 b)");
+        assert_eq!(resolve("mul", &["float3", "float3"]).unwrap(), "float mul(float3 a, float3 b)");
+        let truncated = IntrinsicCatalogue::get().resolve_function("mul", &[scalar("float3"), scalar("float4x4")]).unwrap();
+        assert_eq!(truncated.to_string(), "float4 mul(float3 a, float3x4 b)");
+        assert!(truncated.truncates);
+
+        assert_eq!(resolve("cross", &["float2", "float3"]), None);
+        assert_eq!(resolve("firstbithigh", &["float"]), None);
+        assert_eq!(resolve("lerp", &["float", "float"]), None);
+    }
+
+    #[test]
+    fn resolves_resource_methods()
+    {
+        let catalogue = IntrinsicCatalogue::get();
+        let sampler = Type::Resource{ kind: ResourceKind::SamplerState, element: None, count: None };
+        let comparison = Type::Resource{ kind: ResourceKind::SamplerComparisonState, element: None, count: None };
+        let color = texture(ResourceKind::Texture2D, "float4");
+        let depth = texture(ResourceKind::Texture2DArray, "float");
+
+        let method = |receiver: &Type, name: &str, arguments: &[Type]| catalogue.resolve_method(receiver, name, arguments).map(|c| c.to_string());
+        assert_eq!(method(&color, "Sample", &[sampler.clone(), scalar("float2")]).unwrap(), "float4 Sample(sampler s, float2 location)");
+        assert_eq!(method(&color, "SampleLevel", &[sampler.clone(), scalar("float2"), scalar("float"), scalar("int2")]).unwrap(),
+            "float4 SampleLevel(sampler s, float2 location, float lod, int2 offset)");
+        assert_eq!(method(&depth, "GatherRed", &[sampler.clone(), scalar("float3")]).unwrap(), "float4 GatherRed(sampler s, float3 location)");
+        assert_eq!(method(&depth, "SampleCmpLevelZero", &[comparison.clone(), scalar("float3"), scalar("float")]).unwrap(),
+            "float SampleCmpLevelZero(sampler_cmp s, float3 location, float compare_value)");
+        assert_eq!(method(&color, "Load", &[scalar("int3")]).unwrap(), "float4 Load(int3 location)");
+        assert_eq!(method(&color, "GetDimensions", &[scalar("uint"), scalar("uint"), scalar("uint")]), None);
+        assert_eq!(method(&color, "GetDimensions", &[scalar("uint"), scalar("float"), scalar("float"), scalar("float")]).unwrap(),
+            "void GetDimensions(uint mip_level, out float width, out float height, out float levels)");
+
+        let output = texture(ResourceKind::RWTexture2D, "uint2");
+        assert_eq!(method(&output, "Load", &[scalar("int2")]).unwrap(), "uint2 Load(int2 location)");
+        assert_eq!(method(&output, "Sample", &[sampler.clone(), scalar("float2")]), None);
+        assert_eq!(method(&color, "Sample", &[comparison, scalar("float2")]), None);
+        assert_eq!(method(&color, "Sample", &[sampler, scalar("float2x2")]), None);
+    }
+
+    #[test]
+    fn type_checker_uses_the_catalogue()
+    {
+        let text = "Texture2D<float4> tex; SamplerState linearSampler; RWByteAddressBuffer output; groupshared uint counter; \
+            float4 f(float2 uv, float3 n) { float4 color = tex.Sample(linearSampler, uv); uint old; InterlockedAdd(counter, 1, old); \
+            output.Store(0, old); float3 l = normalize(n); float d = saturate(dot(l, n)); float e = lerp(d, 1); \
+            float4 g = tex.Sample(uv); return color * d; }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let checker = TypeChecker::new(unit, table, &settings);
+
+            let calls: Vec<String> = unit.syntax().descendant_nodes().into_iter().filter(|n| n.node_type == SyntaxNodeType::CallExpression)
+                .filter_map(|n| checker.intrinsic_of(n)).map(|c| c.to_string()).collect();
+            assert_eq!(calls, vec![
+                "float4 Sample(sampler s, float2 location)",
+                "void InterlockedAdd(inout uint dest, uint value, out uint original)",
+                "void Store(uint address, uint value)",
+                "float3 normalize(float3 x)",
+                "float saturate(float x)",
+                "float dot(float3 a, float3 b)",
+            ]);
+
+            let messages: Vec<String> = checker.get_diagnostics().iter().map(|d| d.message.clone()).collect();
+            assert_eq!(messages, vec!["'lerp': no matching 2 parameter intrinsic function", "'Sample': no matching 1 parameter intrinsic method"]);
+        });
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod language_server_tests {
+    use std::{io::ErrorKind, path::PathBuf};
+
+    use crate::language_server::{document::*, json::JsonValue, transport::*, LanguageServer};
+    use crate::parser::{syntax_tokens::TextSpan, ParserSettings};
+
+    fn message(text: &str) -> JsonValue
+    {
+        return JsonValue::parse(text).unwrap();
+    }
+
+    #[test]
+    fn reads_and_writes_json()
+    {
+        let value = message(" {\"id\": 3, \"params\": {\"text\": \"a\\n\\\"b\\\" \\u00e9\\ud83d\\ude00\", \"list\": [1.5, -2e2, true, null, []]}} ");
+        assert_eq!(value.get("id").and_then(|v| v.as_i64()), Some(3));
+        let params = value.get("params").unwrap();
+        assert_eq!(params.get("text").and_then(|v| v.as_str()), Some("a\n\"b\" é😀"));
+        assert_eq!(params.get("list").unwrap().to_string(), "[1.5,-200,true,null,[]]");
+        assert_eq!(JsonValue::parse(&value.to_string()).unwrap(), value);
+
+        assert!(JsonValue::parse("{\"id\": }").is_err());
+        assert!(JsonValue::parse("[1, 2").is_err());
+        assert!(JsonValue::parse("{} {}").is_err());
+
+        // Nesting is limited instead of running out of stack
+        assert!(JsonValue::parse(&format!("{}{}", "[".repeat(512), "]".repeat(512))).is_ok());
+        assert_eq!(JsonValue::parse(&format!("{}{}", "[".repeat(513), "]".repeat(513))).unwrap_err(), "nesting too deep");
+        assert_eq!(JsonValue::parse(&"{\"a\":".repeat(100_000)).unwrap_err(), "nesting too deep");
+    }
+
+    #[test]
+    fn frames_messages()
+    {
+        let mut output = Vec::new();
+        write_message(&mut output, &message("{\"text\":\"é\"}")).unwrap();
+        assert_eq!(String::from_utf8(output.clone()).unwrap(), "Content-Length: 13\r\n\r\n{\"text\":\"é\"}");
+
+        output.extend_from_slice(b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\nContent-Length: 2\r\n\r\n[]");
+        let mut reader = output.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap().as_deref(), Some("{\"text\":\"é\"}"));
+        assert_eq!(read_message(&mut reader).unwrap().as_deref(), Some("[]"));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+
+        // Headers without a length and lengths nobody sends are errors, not panics or huge allocations
+        let error = read_message(&mut b"Content-Type: application/vscode-jsonrpc\r\n\r\n{}".as_slice()).unwrap_err();
+        assert_eq!((error.kind(), error.to_string()), (ErrorKind::InvalidData, String::from("the message has no Content-Length header")));
+        let error = read_message(&mut b"Content-Length: 18446744073709551615\r\n\r\n".as_slice()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = read_message(&mut b"Content-Length: 67108865\r\n\r\n".as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "Content-Length 67108865 is larger than 67108864 bytes");
+        assert!(read_message(&mut b"Content-Length: abc\r\n\r\n".as_slice()).is_err());
+    }
+
+    #[test]
+    fn converts_positions_and_applies_changes()
+    {
+        let mut document = Document::new("file:///shaders/a%20b.hlsl", 1, "float a;\r\n// é😀 x\nfloat b;");
+        assert_eq!(document.position(20), Position{ line: 1, character: 7 });
+        assert_eq!(document.offset(Position{ line: 1, character: 7 }), 20);
+        // Inside a character
+        assert_eq!(document.position(17), Position{ line: 1, character: 4 });
+        // Past the end of a line or the document
+        assert_eq!(document.offset(Position{ line: 0, character: 40 }), 8);
+        assert_eq!(document.offset(Position{ line: 9, character: 0 }), document.get_text().len());
+
+        let range = message("{\"start\": {\"line\": 2, \"character\": 6}, \"end\": {\"line\": 2, \"character\": 7}}");
+        let span = document.span(&range).unwrap();
+        document.apply_change(Some(span), "value");
+        assert_eq!(document.get_text(), "float a;\r\n// é😀 x\nfloat value;");
+        assert_eq!(document.range(TextSpan::new(28, 33)).to_string(), "{\"start\":{\"line\":2,\"character\":6},\"end\":{\"line\":2,\"character\":11}}");
+        document.apply_change(None, "int c;");
+        assert_eq!(document.get_text(), "int c;");
+
+        assert_eq!(document.path(), Some(PathBuf::from("/shaders/a b.hlsl")));
+        assert_eq!(uri_to_path("file:///C:/shaders/a.hlsl"), Some(PathBuf::from("C:/shaders/a.hlsl")));
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+
+    #[test]
+    fn initializes_once()
+    {
+        let mut server = LanguageServer::new(ParserSettings{ include_paths: vec![PathBuf::from("/default")], ..Default::default() });
+        let initialize = "{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"initialize\", \"params\": {\"rootUri\": \"file:///workspace\", \
+            \"initializationOptions\": {\"includePaths\": [\"/includes\"]}}}";
+        let replies = server.handle_message(&message(initialize));
+        assert!(replies[0].get("result").is_some());
+        assert_eq!(server.get_settings().include_paths, vec![PathBuf::from("/workspace"), PathBuf::from("/includes")]);
+
+        // A second initialize is an error and leaves the settings alone
+        let replies = server.handle_message(&message(initialize));
+        assert_eq!(replies[0].get("error").and_then(|e| e.get("code")).and_then(|c| c.as_i64()), Some(-32600));
+        assert_eq!(server.get_settings().include_paths, vec![PathBuf::from("/workspace"), PathBuf::from("/includes")]);
+    }
+
+    #[test]
+    fn publishes_diagnostics_of_every_stage()
+    {
+        let mut server = LanguageServer::new(ParserSettings::default());
+        let replies = server.handle_message(&message("{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"textDocument/formatting\", \"params\": {}}"));
+        assert_eq!(replies[0].get("error").and_then(|e| e.get("code")).and_then(|c| c.as_i64()), Some(-32002));
+        server.handle_message(&message("{\"jsonrpc\": \"2.0\", \"id\": 2, \"method\": \"initialize\", \"params\": {}}"));
+
+        let open = "{\"jsonrpc\": \"2.0\", \"method\": \"textDocument/didOpen\", \"params\": {\"textDocument\": {\"uri\": \"file:///a.hlsl\", \"languageId\": \"hlsl\", \
+            \"version\": 1, \"text\": \"int a = \\\"text\\\";\\nfloat4 main() : SV_Target { return missing }\\n#if\"}}}";
+        let replies = server.handle_message(&message(open));
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].get("method").and_then(|m| m.as_str()), Some("textDocument/publishDiagnostics"));
+        let diagnostics = replies[0].get("params").and_then(|p| p.get("diagnostics")).and_then(|d| d.as_array()).unwrap();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.get("message").and_then(|m| m.as_str()).unwrap()).collect();
+        // Preprocessor, parser, binder and type checker
+        assert_eq!(messages, vec!["Expected a condition", "Unterminated conditional directive", "Expected ';' but found '}'",
+            "use of undeclared identifier 'missing'", "cannot convert from 'string' to 'int'"]);
+        assert_eq!(diagnostics[3].to_string(), "{\"range\":{\"start\":{\"line\":1,\"character\":35},\"end\":{\"line\":1,\"character\":42}},\
+            \"severity\":1,\"source\":\"hlsl\",\"message\":\"use of undeclared identifier 'missing'\"}");
+    }
+}
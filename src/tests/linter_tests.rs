@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod linter_tests {
+    use crate::parser::{diagnostics::DiagnosticSeverity, LintLevel, LintSettings, ParserSettings};
+    use crate::semantics::linter::*;
+    use crate::tests::test_utils::with_bound;
+
+    // The rule, message and text of the findings
+    fn lint_with(text: &str, lints: LintSettings) -> Vec<(&'static str, String, String)>
+    {
+        let settings = ParserSettings{ lints: lints, ..Default::default() };
+        return with_bound(text, &settings, |unit, table| {
+            let linter = Linter::new(unit, table, &settings);
+            return linter.get_diagnostics().iter().map(|d| (d.code.unwrap(), d.message.clone(), text[d.span.start..d.span.end].to_string())).collect();
+        });
+    }
+
+    fn lint(text: &str) -> Vec<(&'static str, String, String)>
+    {
+        return lint_with(text, LintSettings::default());
+    }
+
+    fn finding(rule: &'static str, message: &str, text: &str) -> (&'static str, String, String)
+    {
+        return (rule, String::from(message), String::from(text));
+    }
+
+    #[test]
+    fn reads_lint_settings()
+    {
+        let config = "# project lints\n[lints]\nunused-parameter = off\ndivision-by-zero = \"error\"\nhalf-literal = warn # noisy\n";
+        let settings = LintSettings::from_config(config).unwrap();
+        assert_eq!(settings.level("unused-parameter"), Some(LintLevel::Off));
+        assert_eq!(settings.level("division-by-zero"), Some(LintLevel::Error));
+        assert_eq!(settings.level("half-literal"), Some(LintLevel::Warning));
+        assert_eq!(settings.level("unused-variable"), None);
+
+        assert_eq!(LintSettings::from_config("unused-variable = loud").unwrap_err(), "line 1: unknown lint level 'loud'");
+        assert_eq!(LintSettings::from_config("\nunused-variable").unwrap_err(), "line 2: expected 'rule = level'");
+        assert_eq!(LintSettings::from_config("unused-variable = off\nunused-local = off").unwrap_err(), "line 2: unknown lint rule 'unused-local'");
+        assert!(LINT_RULES.iter().all(|r| lint_rule(r.name).is_some()));
+    }
+
+    #[test]
+    fn reports_suspicious_expressions()
+    {
+        let text = "half4 main(half4 color : COLOR, float2 uv : TEXCOORD, int index : INDEX) : SV_Target \
+            { half4 scaled = color * 0.5; half3 rgb = color; float bad = pow(-2.0f, uv.x); int slot = index / 0; slot %= 0; \
+            float safe = uv.x / 2.0f + pow(2.0f, uv.y); return scaled * (half)2 + rgb.x * bad * slot * safe; }";
+        assert_eq!(lint(text), vec![
+            finding("half-literal", "float literal '0.5' in a half precision expression has no 'h' suffix", "0.5"),
+            finding("implicit-truncation", "implicit truncation of vector type", "color"),
+            finding("pow-negative-base", "'pow' with a negative base returns NaN", "-2.0f"),
+            finding("division-by-zero", "division by zero is undefined", "index / 0"),
+            finding("division-by-zero", "remainder by zero is undefined", "slot %= 0"),
+        ]);
+
+        // Every conversion of a float literal to half, not only operands
+        let text = "half Scale(half value) { return value; } \
+            half main(half4 color : COLOR) : SV_Target { half h = 1.5; half s = Scale(2.5) + h * (0.25); half3 rgb = lerp(color, 0.5, h); \
+            float f = 0.5; return rgb.x > f ? s : 3.5; }";
+        assert_eq!(lint(text), vec![
+            finding("half-literal", "float literal '1.5' in a half precision expression has no 'h' suffix", "1.5"),
+            finding("half-literal", "float literal '2.5' in a half precision expression has no 'h' suffix", "2.5"),
+            finding("half-literal", "float literal '0.25' in a half precision expression has no 'h' suffix", "0.25"),
+            finding("implicit-truncation", "implicit truncation of vector type", "lerp(color, 0.5, h)"),
+            finding("half-literal", "float literal '0.5' in a half precision expression has no 'h' suffix", "0.5"),
+            finding("half-literal", "float literal '3.5' in a half precision expression has no 'h' suffix", "3.5"),
+        ]);
+    }
+
+    #[test]
+    fn reports_unused_and_shadowed_variables()
+    {
+        let text = "float scale; \
+            float Blend(float a, float b, float weight, out float result) { float unused; float scale = weight; result = a; return a * scale; } \
+            float4 main(float4 position : SV_Position) : SV_Target { for (int i = 0; i < 2; i++) { for (int i = 0; i < 2; i++) { } } return 0; }";
+        let findings = lint(text);
+        assert_eq!(findings, vec![
+            finding("unused-parameter", "unused parameter 'b'", "b"),
+            finding("unused-variable", "unused variable 'unused'", "unused"),
+            finding("shadowed-variable", "declaration shadows a variable in the global namespace", "scale"),
+            finding("shadowed-variable", "declaration shadows a local variable", "i"),
+        ]);
+
+        // The level of a rule sets the severity, rules that are off report nothing
+        let mut lints = LintSettings::default();
+        lints.set("unused-parameter", LintLevel::Off);
+        lints.set("unused-variable", LintLevel::Error);
+        let settings = ParserSettings{ lints: lints, ..Default::default() };
+        with_bound(text, &settings, |unit, table| {
+            let linter = Linter::new(unit, table, &settings);
+            let severities: Vec<(&str, DiagnosticSeverity)> = linter.get_diagnostics().iter().map(|d| (d.code.unwrap(), d.severity)).collect();
+            assert_eq!(severities, vec![
+                ("unused-variable", DiagnosticSeverity::Error),
+                ("shadowed-variable", DiagnosticSeverity::Warning),
+                ("shadowed-variable", DiagnosticSeverity::Warning),
+            ]);
+            assert_eq!(linter.get_diagnostics()[1].notes[0].message, "previous declaration is here");
+        });
+    }
+
+    #[test]
+    fn reports_gradients_in_divergent_control_flow()
+    {
+        let text = "Texture2D colors; SamplerState linear_sampler; cbuffer Constants { int taps; bool enabled; }; \
+            float4 Shade(float2 uv) { return colors.Sample(linear_sampler, uv); } \
+            float4 main(float2 uv : TEXCOORD) : SV_Target \
+            { float4 total = 0; for (int i = 0; i < taps; i++) { total += colors.Sample(linear_sampler, uv + i); } \
+            if (enabled) { total += Shade(uv); } \
+            float mask = colors.Sample(linear_sampler, uv).a; \
+            if (mask > 0.5f) { total += colors.Sample(linear_sampler, uv * 2.0f); total += colors.SampleLevel(linear_sampler, uv, 0); total += Shade(uv); } \
+            if (uv.x < 0.0f) { return 0; } \
+            return total * ddx(uv.x); }";
+        assert_eq!(lint(text), vec![
+            finding("divergent-gradient", "gradient operation 'Sample' inside divergent control flow", "colors.Sample(linear_sampler, uv * 2.0f)"),
+            finding("divergent-gradient", "call to 'Shade' performs gradient operations inside divergent control flow", "Shade(uv)"),
+            finding("divergent-gradient", "gradient operation 'ddx' inside divergent control flow", "ddx(uv.x)"),
+        ]);
+    }
+
+    #[test]
+    fn suppresses_findings_with_comments()
+    {
+        let text = "float4 main(float4 color : COLOR) : SV_Target\n{\n\
+            float first; // hlsl-lint: disable=unused-variable\n\
+            // hlsl-lint: disable=unused-variable, shadowed-variable\n\
+            float second;\n\
+            float third; /* hlsl-lint: disable */\n\
+            float fourth; // hlsl-lint: disable=shadowed-variable\n\
+            return color;\n}\n";
+        assert_eq!(lint(text), vec![finding("unused-variable", "unused variable 'fourth'", "fourth")]);
+    }
+}
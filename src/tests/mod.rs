@@ -1,4 +1,30 @@
-
 mod test_utils;
+// The tests that came before the clippy gate are kept as they were written
+#[allow(clippy::needless_borrow, clippy::useless_conversion)]
 pub mod source_file_tests;
-pub mod lexer_tests;
\ No newline at end of file
+#[allow(clippy::into_iter_on_ref, clippy::assertions_on_constants, clippy::needless_borrow)]
+pub mod lexer_tests;
+pub mod preprocessor_tests;
+pub mod root_signature_tests;
+pub mod syntax_parser_tests;
+pub mod ast_tests;
+pub mod syntax_visitor_tests;
+pub mod class_table_tests;
+pub mod binder_tests;
+pub mod types_tests;
+pub mod intrinsics_tests;
+pub mod type_checker_tests;
+pub mod overload_resolution_tests;
+pub mod constant_evaluator_tests;
+pub mod buffer_layout_tests;
+pub mod reflection_tests;
+pub mod register_allocator_tests;
+pub mod entry_points_tests;
+pub mod signature_validator_tests;
+pub mod shader_model_checker_tests;
+pub mod call_graph_tests;
+pub mod control_flow_tests;
+pub mod definite_assignment_tests;
+pub mod linter_tests;
+pub mod formatter_tests;
+pub mod language_server_tests;
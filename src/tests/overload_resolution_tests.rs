@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod overload_resolution_tests {
+    use crate::parser::{ast::AstNode, diagnostics::Diagnostic, syntax_nodes::SyntaxNodeType, ParserSettings};
+    use crate::semantics::{type_checker::TypeChecker, types::*};
+    use crate::tests::test_utils::with_bound;
+
+    struct Checked
+    {
+        // The offset of the declaration each call resolved to, in call order
+        targets: Vec<Option<usize>>,
+        diagnostics: Vec<Diagnostic>,
+    }
+
+    fn check(text: &str) -> Checked
+    {
+        let settings = ParserSettings::default();
+        return with_bound(text, &settings, |unit, table| {
+            assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+            let checker = TypeChecker::new(unit, table, &settings);
+
+            let targets = unit.syntax().descendant_nodes().into_iter().filter(|n| n.node_type == SyntaxNodeType::CallExpression)
+                .map(|n| checker.call_target(n).map(|s| table.get_symbol(s).span().start)).collect();
+            return Checked{ targets: targets, diagnostics: checker.get_diagnostics().clone() };
+        });
+    }
+
+    #[test]
+    fn ranks_conversions()
+    {
+        let rank = |source: &str, target: &str| conversion_rank(&Type::from_keyword(source).unwrap(), &Type::from_keyword(target).unwrap());
+        assert_eq!(rank("float3", "float3"), Some(ConversionRank::Exact));
+        assert_eq!(rank("half", "float"), Some(ConversionRank::Promotion));
+        assert_eq!(rank("int", "int64_t"), Some(ConversionRank::Promotion));
+        assert_eq!(rank("int", "uint"), Some(ConversionRank::Conversion));
+        assert_eq!(rank("float2", "int2"), Some(ConversionRank::Conversion));
+        assert_eq!(rank("float", "float4"), Some(ConversionRank::Splat));
+        assert_eq!(rank("float4", "float2"), Some(ConversionRank::Truncation));
+        assert_eq!(rank("float4", "float2x2"), Some(ConversionRank::Conversion));
+        assert_eq!(rank("float2", "float3"), None);
+        assert_eq!(rank("float3x3", "float3"), None);
+
+        let literal = Type::Scalar(ScalarType::LiteralInt);
+        assert_eq!(conversion_rank(&literal, &Type::Scalar(ScalarType::Int)), Some(ConversionRank::Exact));
+        assert_eq!(conversion_rank(&literal, &Type::Scalar(ScalarType::Uint)), Some(ConversionRank::Promotion));
+        assert_eq!(conversion_rank(&literal, &Type::Scalar(ScalarType::Float)), Some(ConversionRank::Conversion));
+    }
+
+    #[test]
+    fn picks_the_best_candidate()
+    {
+        let text = "int f(int x) { return x; } float f(float x) { return x; } float f(float2 x) { return x.x; } \
+            float g(float2 x) { return x.x; } float g(float4 x) { return x.x; } \
+            float k(float x, float y = 1) { return x + y; } \
+            void main(half h, float3 v) { f(1); f(1.0); f(h); f(v.xy); g(v); k(2); k(2, 3); }";
+        let checked = check(text);
+        let f_int = text.find("f(int").unwrap();
+        let f_float = text.find("f(float x").unwrap();
+        let f_float2 = text.find("f(float2").unwrap();
+        let g_float2 = text.find("g(float2").unwrap();
+        let k = text.find("k(float").unwrap();
+        assert_eq!(checked.targets, vec![Some(f_int), Some(f_float), Some(f_float), Some(f_float2), Some(g_float2), Some(k), Some(k)]);
+
+        // float3 only converts to float2 by truncation
+        let messages: Vec<&str> = checked.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["implicit truncation of vector type"]);
+        assert_eq!(&text[checked.diagnostics[0].span.start..checked.diagnostics[0].span.end], "v");
+    }
+
+    #[test]
+    fn reports_ambiguous_and_unmatched_calls()
+    {
+        let text = "float g(int a, float b) { return a; } float g(float a, int b) { return b; } \
+            float h(float2 x) { return x.x; } float h(float x, float y) { return x; } \
+            float k(float x, float y = 1) { return x; } \
+            void main(float3x3 m) { g(1, 1); h(m); k(); }";
+        let checked = check(text);
+        assert_eq!(checked.targets, vec![None, None, None]);
+
+        let summary: Vec<(String, Vec<(String, usize)>)> = checked.diagnostics.iter()
+            .map(|d| (d.message.clone(), d.notes.iter().map(|n| (n.message.clone(), n.span.start)).collect()))
+            .collect();
+        assert_eq!(summary, vec![
+            (String::from("call to 'g' is ambiguous"), vec![
+                (String::from("candidate function"), text.find("g(int").unwrap()),
+                (String::from("candidate function"), text.find("g(float").unwrap()),
+            ]),
+            (String::from("no matching function for call to 'h'"), vec![
+                (String::from("candidate function not viable: no known conversion from 'float3x3' to 'float2' for 1st argument"), text.find("h(float2").unwrap()),
+                (String::from("candidate function not viable: requires 2 arguments, but 1 was provided"), text.find("h(float x").unwrap()),
+            ]),
+            (String::from("no matching function for call to 'k'"), vec![
+                (String::from("candidate function not viable: requires at least 1 argument, but 0 were provided"), text.find("k(float").unwrap()),
+            ]),
+        ]);
+    }
+
+    #[test]
+    fn out_parameters_need_lvalues()
+    {
+        let text = "void Split(float4 color, out float3 rgb, inout float alpha) { rgb = color.rgb; alpha *= color.a; } \
+            struct Light { float3 Shade(float3 n) { return n; } float3 Shade(float3 n, float3 l) { return n * l; } }; \
+            void main(float4 color, Light light) { float3 rgb; float alpha = 1; float2 rg; Split(color, rgb, alpha); Split(color, rgb, 1.0); \
+            Split(color, rg, alpha); float s; sincos(alpha, s, 2); light.Shade(rgb, rgb); }";
+        let checked = check(text);
+        let shade = text.rfind("Shade(float3 n, float3 l)").unwrap();
+        assert_eq!(checked.targets.last().unwrap(), &Some(shade));
+
+        let diagnostics: Vec<(String, &str)> = checked.diagnostics.iter().map(|d| (d.message.clone(), &text[d.span.start..d.span.end])).collect();
+        assert_eq!(diagnostics, vec![
+            (String::from("cannot bind non-lvalue argument to inout parameter 'alpha'"), "1.0"),
+            (String::from("implicit truncation of vector type"), "rg"),
+            (String::from("cannot bind non-lvalue argument to out parameter 'c'"), "2"),
+        ]);
+    }
+}
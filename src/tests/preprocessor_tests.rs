@@ -0,0 +1,139 @@
+
+#[cfg(test)]
+mod preprocessor_tests {
+    use std::path::Path;
+
+    use crate::parser::{lexer::ILexer, preprocessor::Preprocessor, syntax_tokens::{SyntaxTokenType, SyntaxTriviaType}, ParserSettings};
+    use crate::tests::test_utils::{find_all_shader_paths, create_full_path};
+    use crate::parser::source_file::SourceFile;
+
+    fn preprocess(text: &str) -> Preprocessor
+    {
+        let settings = ParserSettings::default();
+        return Preprocessor::from_text(text, &settings);
+    }
+
+    // Joins the text of all tokens the parser would see
+    fn preprocessed_text(text: &str) -> String
+    {
+        let preprocessor = preprocess(text);
+        assert!(preprocessor.get_diagnostics().is_empty(), "Unexpected diagnostics: {:?}", preprocessor.get_diagnostics());
+        return preprocessor.get_tokens().iter()
+            .filter(|t| t.token_type != SyntaxTokenType::EndOfFileToken)
+            .map(|t| t.text.clone())
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+
+    #[test]
+    fn object_like_macros()
+    {
+        assert_eq!(preprocessed_text("#define FOO 1\nfloat a = FOO;"), "float a = 1 ;");
+        assert_eq!(preprocessed_text("#define A B\n#define B 2\nint x = A;"), "int x = 2 ;");
+        assert_eq!(preprocessed_text("#define FOO 1\n#undef FOO\nint x = FOO;"), "int x = FOO ;");
+    }
+
+    #[test]
+    fn function_like_macros()
+    {
+        assert_eq!(preprocessed_text("#define ADD(a, b) ((a) + (b))\nint x = ADD(1, f(2, 3));"), "int x = ( ( 1 ) + ( f ( 2 , 3 ) ) ) ;");
+        assert_eq!(preprocessed_text("#define F(a) a\nint F = 1;"), "int F = 1 ;");
+        assert_eq!(preprocessed_text("#define CAT(a, b) a##b\nint CAT(my, Var);"), "int myVar ;");
+        assert_eq!(preprocessed_text("#define STR(a) #a\nstring s = STR(hello world);"), "string s = \"hello world\" ;");
+        assert_eq!(preprocessed_text("#define V(...) f(__VA_ARGS__)\nV(1, 2)"), "f ( 1 , 2 )");
+    }
+
+    #[test]
+    fn recursive_macros_stop()
+    {
+        assert_eq!(preprocessed_text("#define X X + 1\nint a = X;"), "int a = X + 1 ;");
+        assert_eq!(preprocessed_text("#define A B\n#define B A\nint a = A;"), "int a = A ;");
+    }
+
+    #[test]
+    fn conditionals()
+    {
+        let text = "#define A 2\n#if A > 1 && defined(A)\nint yes;\n#elif 1\nint no1;\n#else\nint no2;\n#endif";
+        assert_eq!(preprocessed_text(text), "int yes ;");
+
+        let text = "#ifdef MISSING\nint no;\n#elif !defined MISSING\nint yes;\n#endif";
+        assert_eq!(preprocessed_text(text), "int yes ;");
+
+        let text = "#if 0\n#if 1\nint no;\n#else\nint no2;\n#endif\n#else\nint yes;\n#endif";
+        assert_eq!(preprocessed_text(text), "int yes ;");
+    }
+
+    #[test]
+    fn directives_become_trivia()
+    {
+        let preprocessor = preprocess("#define A 1\n#if 0\nint no;\n#endif\nint a;");
+        let tokens = preprocessor.get_tokens();
+        let trivia_types: Vec<SyntaxTriviaType> = tokens[0].leading_trivia.iter().map(|t| t.trivia_type).collect();
+        assert!(trivia_types.contains(&SyntaxTriviaType::Directive));
+        assert!(trivia_types.contains(&SyntaxTriviaType::DisabledText));
+        assert_eq!(tokens[0].text, "int");
+    }
+
+    #[test]
+    fn expanded_tokens_keep_their_spelling()
+    {
+        let text = "#define FOO 1\nfloat a = FOO;";
+        let preprocessor = preprocess(text);
+        let one = &preprocessor.get_tokens()[3];
+        assert_eq!(one.text, "1");
+        assert_eq!(&text[one.span.start..one.span.end], "FOO");
+        let spelling = one.spelling.expect("Expanded tokens have a spelling");
+        assert_eq!(&text[spelling.span.start..spelling.span.end], "1");
+    }
+
+    #[test]
+    fn reports_errors()
+    {
+        assert_eq!(preprocess("#if 1\nint a;").get_diagnostics().len(), 1);
+        assert_eq!(preprocess("#endif").get_diagnostics().len(), 1);
+        assert_eq!(preprocess("#error stop").get_diagnostics().len(), 1);
+        assert_eq!(preprocess("#include \"missing.hlsl\"").get_diagnostics().len(), 1);
+        assert_eq!(preprocess("#if 1 / 0\n#endif").get_diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn settings_defines()
+    {
+        let mut settings = ParserSettings::default();
+        settings.defines.push((String::from("SHADER_MODEL"), String::from("5")));
+        let preprocessor = Preprocessor::from_text("#if SHADER_MODEL >= 5\nint a;\n#endif", &settings);
+        assert_eq!(preprocessor.get_tokens().len(), 4);
+    }
+
+    #[test]
+    fn includes_files()
+    {
+        let path = create_full_path("./test_files/Nvidia/DebugSpaces.fx");
+        let settings = ParserSettings::default();
+        let mut source_file = SourceFile::from_path(&path, &settings);
+        let preprocessor = Preprocessor::from_file(&mut source_file, &settings);
+
+        assert!(preprocessor.get_files().len() > 1);
+        assert!(preprocessor.get_tokens().iter().any(|t| t.file_index > 0));
+    }
+
+    fn preprocess_file(shader: &Path)
+    {
+        let settings = ParserSettings::default();
+        let mut source_file = SourceFile::from_path(shader, &settings);
+        let preprocessor = Preprocessor::from_file(&mut source_file, &settings);
+        let tokens = preprocessor.get_tokens();
+
+        assert_eq!(tokens.last().unwrap().token_type, SyntaxTokenType::EndOfFileToken, "Missing end of file in {}", shader.to_str().unwrap());
+        assert!(tokens.iter().all(|t| !t.is_directive()), "Directive left in {}", shader.to_str().unwrap());
+    }
+
+    #[test]
+    fn can_preprocess_shaders()
+    {
+        for shader in find_all_shader_paths()
+        {
+            preprocess_file(&shader);
+        }
+    }
+}
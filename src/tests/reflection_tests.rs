@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod reflection_tests {
+    use crate::parser::{ast::*, source_file::SourceFile, syntax_parser::SyntaxTree, HlslVersion, ParserSettings, ShaderStage};
+    use crate::semantics::{binder::Binder, reflection::*, symbol_table::SymbolKind, types::Interpolation};
+    use crate::tests::test_utils::{create_full_path, find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // Semantic, type and name of the elements of a signature
+    fn elements(signature: &[SignatureElement]) -> Vec<String>
+    {
+        return signature.iter().map(|e| format!("{}{} {} {}", e.semantic_name, e.semantic_index, e.ty, e.name)).collect();
+    }
+
+    #[test]
+    fn reflects_resources()
+    {
+        let text = "cbuffer Frame : register(b1) { float4x4 viewProjection; float time; } [[vk::binding(2, 1)]] cbuffer Unused { float4 tint; } \
+            float4 globalColor; [[vk::binding(3)]] Texture2D<float4> albedo : register(t2, space1); Texture2D<float4> textures[] : register(t0, space2); \
+            SamplerState samplers[4] : register(s1); RWStructuredBuffer<uint> counters : register(u0); tbuffer Bones : register(b3) { float4x4 bones[2]; } \
+            static const float Scale = 2; \
+            float4 shade(float2 uv) { return albedo.Sample(samplers[2], uv) * globalColor * Scale; } \
+            float4 main(float4 position : SV_Position) : SV_Target { return shade(position.xy) * time; }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let reflection = ShaderReflection::reflect(unit, table, &settings, "main", ShaderStage::Pixel).unwrap();
+
+            let resources: Vec<String> = reflection.resources.iter()
+                .map(|r| format!("{} {} {}{:?} space{} {:?} {:?} {}", r.name, r.ty, r.class.letter(), r.register, r.space, r.count, r.vulkan_binding, r.used))
+                .collect();
+            assert_eq!(resources, vec![
+                "$Globals ConstantBuffer bNone space0 Some(1) None true",
+                "Frame ConstantBuffer bSome(1) space0 Some(1) None true",
+                "Unused ConstantBuffer bNone space0 Some(1) Some((2, 1)) false",
+                "albedo Texture2D<float4> tSome(2) space1 Some(1) Some((3, 0)) true",
+                "textures Texture2D<float4>[] tSome(0) space2 None None false",
+                "samplers SamplerState[4] sSome(1) space0 Some(4) None true",
+                "counters RWStructuredBuffer<uint> uSome(0) space0 Some(1) None false",
+                // A tbuffer is bound to a t register, register(b3) does not apply to it
+                "Bones TextureBuffer tNone space0 Some(1) None false",
+            ]);
+            assert_eq!(reflection.constant_buffer("Frame").unwrap().size, 80);
+            assert_eq!(elements(&reflection.inputs), vec!["SV_Position0 float4 position"]);
+            assert_eq!(elements(&reflection.outputs), vec!["SV_Target0 float4 main"]);
+            assert!(reflection.outputs[0].is_system_value());
+            assert!(ShaderReflection::reflect(unit, table, &settings, "missing", ShaderStage::Pixel).is_none());
+        });
+    }
+
+    #[test]
+    fn flattens_signatures()
+    {
+        let text = "struct VSIn { float3 position : POSITION; row_major float2x3 skew : SKEW; float3x2 basis : BASIS; nointerpolation uint id : ID; }; \
+            struct VSOut { float4 position : SV_Position; float2 uv[2] : TEXCOORD1; }; \
+            VSOut vs(VSIn input, out float depth : DEPTH, inout float weight : WEIGHT, uniform float bias) { VSOut output = (VSOut)0; depth = bias; return output; } \
+            struct GSOut { float4 position : SV_Position; uint layer : SV_RenderTargetArrayIndex; }; \
+            [maxvertexcount(3)] void gs(triangle VSOut vertices[3], inout TriangleStream<GSOut> stream) { }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let vertex = ShaderReflection::reflect(unit, table, &settings, "vs", ShaderStage::Vertex).unwrap();
+            assert_eq!(elements(&vertex.inputs), vec![
+                "POSITION0 float3 input.position",
+                // Row major matrices take an element per row, column major ones an element per column
+                "SKEW0 float3 input.skew", "SKEW1 float3 input.skew",
+                "BASIS0 float3 input.basis", "BASIS1 float3 input.basis",
+                "ID0 uint input.id",
+                "WEIGHT0 float weight",
+            ]);
+            assert_eq!(elements(&vertex.outputs), vec![
+                "DEPTH0 float depth", "WEIGHT0 float weight",
+                "SV_Position0 float4 vs.position", "TEXCOORD1 float2 vs.uv[0]", "TEXCOORD2 float2 vs.uv[1]",
+            ]);
+            assert_eq!(vertex.inputs[5].interpolation, vec![Interpolation::NoInterpolation]);
+
+            let geometry = ShaderReflection::reflect(unit, table, &settings, "gs", ShaderStage::Geometry).unwrap();
+            assert_eq!(elements(&geometry.inputs), vec!["SV_Position0 float4 vertices.position", "TEXCOORD1 float2 vertices.uv[0]", "TEXCOORD2 float2 vertices.uv[1]"]);
+            assert_eq!(elements(&geometry.outputs), vec!["SV_Position0 float4 stream.position", "SV_RenderTargetArrayIndex0 uint stream.layer"]);
+        });
+    }
+
+    #[test]
+    fn reflects_tessellation_and_mesh_shaders()
+    {
+        let settings = ParserSettings::default();
+        let reflect = |path: &str, stage: ShaderStage, check: &dyn Fn(&ShaderReflection)| {
+            let mut source = SourceFile::from_path(&create_full_path(path), &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            check(&ShaderReflection::reflect(tree.compilation_unit(), &table, &settings, "main", stage).unwrap());
+        };
+
+        reflect("test_files/HlslCrossCompiler/hs5/basic.hlsl", ShaderStage::Hull, &|hull| {
+            assert_eq!(elements(&hull.inputs), vec!["CPOINT0 float3 ip.cpoint", "SV_OutputControlPointID0 uint id"]);
+            assert_eq!(elements(&hull.outputs), vec!["CPOINT0 float3 main.cpoint"]);
+            assert_eq!(elements(&hull.patch_constants), vec!["SV_TessFactor0 float HSConst.edges[0]", "SV_TessFactor1 float HSConst.edges[1]"]);
+        });
+        reflect("test_files/HlslCrossCompiler/ds5/basic.hlsl", ShaderStage::Domain, &|domain| {
+            assert_eq!(elements(&domain.inputs), vec!["CPOINT0 float3 op.cpoint", "SV_DomainLocation0 float2 uv"]);
+            assert_eq!(elements(&domain.patch_constants), vec!["SV_TessFactor0 float input.edges[0]", "SV_TessFactor1 float input.edges[1]"]);
+        });
+        reflect("test_files/Dxc/mesh.hlsl", ShaderStage::Mesh, &|mesh| {
+            assert_eq!(mesh.thread_group_size, Some([32, 1, 1]));
+            // Indices and the payload are not part of the signatures
+            assert_eq!(elements(&mesh.inputs), vec!["SV_GroupIndex0 uint tig", "SV_ViewID0 uint vid"]);
+            assert_eq!(mesh.outputs.len(), 5 + 4 + 6);
+            assert_eq!(elements(&mesh.outputs[..2]), vec!["SV_Position0 float4 verts.position", "COLOR0 float verts.color[0]"]);
+        });
+    }
+
+    #[test]
+    fn reflects_corpus()
+    {
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let global = table.get_scope(table.global_scope());
+            for symbol in global.symbols.iter().map(|s| table.get_symbol(*s)).filter(|s| s.kind == SymbolKind::Function)
+            {
+                // Functions with attributes or semantics look like entry points
+                let function = FunctionDecl::cast(symbol.definition.unwrap_or(symbol.declaration)).unwrap();
+                let is_entry_point = function.attributes().next().is_some() || function.semantic().is_some() || function.params().iter().any(|p| p.semantic().is_some());
+                if function.body().is_none() || !is_entry_point
+                {
+                    continue;
+                }
+                let reflection = ShaderReflection::reflect(tree.compilation_unit(), &table, &settings, &symbol.name, ShaderStage::Pixel);
+                assert!(reflection.is_some(), "Failed to reflect {} in {}", symbol.name, path.to_str().unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod register_allocator_tests {
+    use crate::parser::{ParserSettings, ShaderStage};
+    use crate::semantics::{reflection::ShaderReflection, register_allocator::*};
+    use crate::tests::test_utils::with_bound;
+
+    const SHADER: &str = "cbuffer Frame { float4x4 viewProjection; } float4 tint; \
+        Texture2D albedo; Texture2D normals : register(t1); Texture2D shadows[2]; [[vk::binding(5, 1)]] Texture2D detail : register(t0); \
+        Texture2D unused : register(t2); SamplerState linearSampler : register(s0); SamplerState pointSampler; \
+        RWTexture2D<float4> output; \
+        [numthreads(8, 8, 1)] void main(uint2 id : SV_DispatchThreadID) \
+        { output[id] = mul(albedo[id] + normals[id] + shadows[1][id] + detail.SampleLevel(pointSampler, id, 0) * tint, viewProjection); \
+          output[id] += shadows[0].SampleLevel(linearSampler, id, 0); }";
+
+    // The bindings of the entry point as 'name t3 space0'
+    fn assign(text: &str, model: BindingModel) -> (Vec<String>, Vec<(String, String)>)
+    {
+        let settings = ParserSettings::default();
+        return with_bound(text, &settings, |unit, table| {
+            let reflection = ShaderReflection::reflect(unit, table, &settings, "main", ShaderStage::Compute).unwrap();
+            let allocator = RegisterAllocator::new(table, &reflection, model);
+
+            let bindings = allocator.get_bindings().iter()
+                .map(|b| format!("{} {}{} space{}{}", b.name, b.class.letter(), b.index, b.space, if b.is_explicit { " explicit" } else { "" }))
+                .collect();
+            let diagnostics = allocator.get_diagnostics().iter().map(|d| (d.message.clone(), String::from(&text[d.span.start..d.span.end]))).collect();
+            return (bindings, diagnostics);
+        });
+    }
+
+    #[test]
+    fn assigns_free_registers_in_declaration_order()
+    {
+        let (bindings, diagnostics) = assign(SHADER, BindingModel::Direct3D);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(bindings, vec![
+            "$Globals b0 space0",
+            "Frame b1 space0",
+            "albedo t2 space0",
+            "normals t1 space0 explicit",
+            // The array takes two registers, t2 and t3 are free after albedo took t2
+            "shadows t3 space0",
+            "detail t0 space0 explicit",
+            "linearSampler s0 space0 explicit",
+            "pointSampler s1 space0",
+            "output u0 space0",
+        ]);
+    }
+
+    #[test]
+    fn prefers_vulkan_bindings()
+    {
+        let (bindings, diagnostics) = assign(SHADER, BindingModel::Vulkan);
+        assert_eq!(bindings, vec![
+            "$Globals b2 space0",
+            "Frame b3 space0",
+            "albedo t4 space0",
+            // Registers are used as bindings when there is no [[vk::binding]]
+            "normals t1 space0 explicit",
+            // Arrays take a single binding
+            "shadows t5 space0",
+            "detail t5 space1 explicit",
+            "linearSampler s0 space0 explicit",
+            "pointSampler s6 space0",
+            "output u7 space0",
+        ]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn reports_overlapping_registers()
+    {
+        let text = "Texture2D a : register(t0); Texture2D b[4] : register(t2); Texture2D c : register(t3); Texture2D d : register(t3, space1); \
+            SamplerState s : register(s0); [numthreads(1, 1, 1)] void main() { float4 v = a[0] + b[0][0] + c[0] + d[0] + a.SampleLevel(s, 0, 0); }";
+        let (_, diagnostics) = assign(text, BindingModel::Direct3D);
+        assert_eq!(diagnostics, vec![
+            (String::from("resource c at register t3 overlaps with resource b at register t2, space 0"), String::from("c")),
+        ]);
+
+        // Vulkan bindings are shared by every class of a descriptor set, the array only takes binding 2
+        let (_, diagnostics) = assign(text, BindingModel::Vulkan);
+        assert_eq!(diagnostics, vec![
+            (String::from("resource binding #0 in descriptor set #0 already assigned"), String::from("s")),
+        ]);
+    }
+}
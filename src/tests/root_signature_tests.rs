@@ -0,0 +1,182 @@
+
+#[cfg(test)]
+mod root_signature_tests {
+    use crate::parser::{lexer::ILexer, preprocessor::Preprocessor, root_signature::*, source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings};
+    use crate::tests::test_utils::create_full_path;
+
+    fn parse(text: &str) -> RootSignature
+    {
+        return match RootSignature::parse(text)
+        {
+            Ok(root_signature) => root_signature,
+            Err(error) => panic!("Failed to parse '{}': {:?}", text, error)
+        };
+    }
+
+    // Returns the text the error points at
+    fn error_text(text: &str) -> String
+    {
+        let error = RootSignature::parse(text).expect_err("Expected an error");
+        return String::from(&text[error.span.start..error.span.end]);
+    }
+
+    #[test]
+    fn root_flags()
+    {
+        let root_signature = parse("RootFlags(ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT | DENY_PIXEL_SHADER_ROOT_ACCESS)");
+        assert_eq!(root_signature.flags, vec![RootFlag::AllowInputAssemblerInputLayout, RootFlag::DenyPixelShaderRootAccess]);
+        assert!(parse("RootFlags(0)").flags.is_empty());
+        assert!(parse("").parameters.is_empty());
+    }
+
+    #[test]
+    fn root_descriptors()
+    {
+        let root_signature = parse("CBV(b0, space = 1), SRV(t3, visibility = SHADER_VISIBILITY_PIXEL), UAV(u2, flags = DATA_VOLATILE)");
+        assert_eq!(root_signature.parameters.len(), 3);
+        match &root_signature.parameters[0]
+        {
+            RootParameter::Descriptor(d) => {
+                assert_eq!(d.register.register_type, RegisterType::ConstantBuffer);
+                assert_eq!(d.register.index, 0);
+                assert_eq!(d.space, 1);
+            },
+            _ => panic!("Expected a descriptor")
+        }
+        match &root_signature.parameters[1]
+        {
+            RootParameter::Descriptor(d) => assert_eq!(d.visibility, ShaderVisibility::Pixel),
+            _ => panic!("Expected a descriptor")
+        }
+        match &root_signature.parameters[2]
+        {
+            RootParameter::Descriptor(d) => assert_eq!(d.flags, vec![DescriptorFlag::DataVolatile]),
+            _ => panic!("Expected a descriptor")
+        }
+    }
+
+    #[test]
+    fn root_constants_in_any_order()
+    {
+        let root_signature = parse("RootConstants(b10, num32BitConstants=3), RootConstants(num32BitConstants=1, b1, space = 2)");
+        match &root_signature.parameters[0]
+        {
+            RootParameter::Constants(c) => {
+                assert_eq!(c.num_32bit_constants, 3);
+                assert_eq!(c.register.index, 10);
+            },
+            _ => panic!("Expected root constants")
+        }
+        match &root_signature.parameters[1]
+        {
+            RootParameter::Constants(c) => assert_eq!(c.space, 2),
+            _ => panic!("Expected root constants")
+        }
+    }
+
+    #[test]
+    fn descriptor_tables()
+    {
+        let root_signature = parse("DescriptorTable(CBV(b1), SRV(t1, numDescriptors = 8), UAV(u1, numDescriptors = unbounded, offset = 4), visibility = SHADER_VISIBILITY_ALL)");
+        match &root_signature.parameters[0]
+        {
+            RootParameter::DescriptorTable(table) => {
+                assert_eq!(table.ranges.len(), 3);
+                assert_eq!(table.ranges[1].num_descriptors, DescriptorCount::Bounded(8));
+                assert_eq!(table.ranges[2].num_descriptors, DescriptorCount::Unbounded);
+                assert_eq!(table.ranges[2].offset, DescriptorRangeOffset::Offset(4));
+            },
+            _ => panic!("Expected a descriptor table")
+        }
+    }
+
+    #[test]
+    fn static_samplers()
+    {
+        let root_signature = parse("StaticSampler(s1), StaticSampler(s2, addressU = TEXTURE_ADDRESS_CLAMP, filter = FILTER_COMPARISON_MIN_MAG_MIP_LINEAR, mipLODBias = -0.5f)");
+        assert_eq!(root_signature.static_samplers.len(), 2);
+        let sampler = &root_signature.static_samplers[1];
+        assert_eq!(sampler.address_u, TextureAddressMode::Clamp);
+        assert_eq!(sampler.address_v, TextureAddressMode::Wrap);
+        assert_eq!(sampler.filter, Filter{ reduction: FilterReduction::Comparison, min: FilterType::Linear, mag: FilterType::Linear, mip: FilterType::Linear });
+        assert_eq!(sampler.mip_lod_bias, -0.5);
+    }
+
+    #[test]
+    fn errors_point_at_the_problem()
+    {
+        assert_eq!(error_text("RootFlags(DENY_EVERYTHING)"), "DENY_EVERYTHING");
+        assert_eq!(error_text("CBV(t0)"), "t0");
+        assert_eq!(error_text("CBV(b0, spaces = 1)"), "spaces");
+        assert_eq!(error_text("CBV(b0, space = 1, space = 2)"), "space");
+        assert_eq!(error_text("DescriptorTable(Sampler(s0), SRV(t0))"), "SRV");
+        assert_eq!(error_text("RootFlags(0), RootFlags(0)"), "RootFlags");
+        assert_eq!(error_text("SRV(t0) UAV(u0)"), "UAV");
+        assert_eq!(error_text("Table(t0)"), "Table");
+        // Characters outside of ASCII are never split
+        assert_eq!(error_text("CBV(b0, é)"), "é");
+        assert_eq!(error_text("CBV(b0, space = 1é)"), "é");
+    }
+
+    #[test]
+    fn root_signature_from_macro()
+    {
+        let path = create_full_path("./test_files/Internal/D3D12Features.hlsl");
+        let settings = ParserSettings::default();
+        let mut source_file = SourceFile::from_path(&path, &settings);
+        let preprocessor = Preprocessor::from_file(&mut source_file, &settings);
+
+        let attributes = find_root_signature_attributes(preprocessor.get_tokens());
+        assert_eq!(attributes.len(), 1);
+
+        let root_signature = attributes[0].root_signature.as_ref().expect("MyRS1 is a valid root signature");
+        assert_eq!(root_signature.flags, vec![RootFlag::AllowInputAssemblerInputLayout, RootFlag::DenyVertexShaderRootAccess]);
+        assert_eq!(root_signature.parameters.len(), 6);
+        assert_eq!(root_signature.static_samplers.len(), 2);
+    }
+
+    #[test]
+    fn macro_errors_point_into_the_string_literal()
+    {
+        let text = "#define RS \"CBV(b0), \" \\\n           \"SRV(b1)\"\n[RootSignature(RS)]\nvoid main() {}";
+        let settings = ParserSettings::default();
+        let preprocessor = Preprocessor::from_text(text, &settings);
+
+        let attributes = find_root_signature_attributes(preprocessor.get_tokens());
+        assert_eq!(attributes.len(), 1);
+
+        let error = attributes[0].root_signature.as_ref().expect_err("b1 is not a shader resource register");
+        assert_eq!(&text[error.span.start..error.span.end], "b1");
+    }
+
+    #[test]
+    fn syntax_tree_reports_root_signature_errors()
+    {
+        let text = "#define RS \"CBV(b0), \" \\\n           \"SRV(b1)\"\n[RootSignature(RS)]\nvoid main() {}\n[RootSignature(\"CBV(b0)\")]\nvoid other() {}";
+        let tree = SyntaxTree::parse_text(text, &ParserSettings::default());
+        let errors: Vec<(&str, &str)> = tree.diagnostics.iter().map(|d| (d.message.as_str(), &text[d.span.start..d.span.end])).collect();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].1, "b1");
+        assert_eq!(tree.diagnostics[0].file_index, 0);
+    }
+
+    #[test]
+    fn errors_point_into_literals_spelled_differently()
+    {
+        let settings = ParserSettings::default();
+        let error_text = |text: &str| {
+            let preprocessor = Preprocessor::from_text(text, &settings);
+            let attributes = find_root_signature_attributes(preprocessor.get_tokens());
+            let error = attributes[0].root_signature.as_ref().expect_err("Expected an error");
+            return String::from(&text[error.span.start..error.span.end]);
+        };
+
+        // A line continuation inside the string literal
+        assert_eq!(error_text("[RootSignature(\"CBV(b0), \\\n    SRV(b1)\")]\nvoid main() {}"), "b1");
+        assert_eq!(error_text("#define RS \"RootFlags(0), \\\r\n  CBV(b0, é = 1)\"\n[RootSignature(RS)]\nvoid main() {}"), "é");
+        // A stringized argument is not spelled where it is used, the error stays inside the macro
+        let text = "#define RS(x) #x\n[RootSignature(RS(SRV(b1)))]\nvoid main() {}";
+        let error = error_text(text);
+        assert!(!error.is_empty() && text[..16].contains(&error), "{}", error);
+    }
+}
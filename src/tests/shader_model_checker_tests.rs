@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod shader_model_checker_tests {
+    use crate::parser::{source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings, ShaderModel, ShaderStage, ShaderTarget};
+    use crate::semantics::{binder::Binder, shader_model_checker::*};
+    use crate::tests::test_utils::{find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // The messages of the diagnostics for the text compiled with the profile
+    fn check_with(text: &str, profile: Option<&str>, enable_16bit_types: bool) -> Vec<String>
+    {
+        let settings = ParserSettings{ target: profile.and_then(ShaderTarget::from_profile), enable_16bit_types: enable_16bit_types, ..Default::default() };
+        return with_bound(text, &settings, |unit, table| {
+            assert!(table.get_diagnostics().is_empty(), "Failed to bind '{}': {:?}", text, table.get_diagnostics());
+            let checker = ShaderModelChecker::new(unit, table, &settings);
+            return checker.get_diagnostics().iter().map(|d| d.message.clone()).collect();
+        });
+    }
+
+    fn check(text: &str, profile: &str) -> Vec<String>
+    {
+        return check_with(text, Some(profile), false);
+    }
+
+    #[test]
+    fn parses_targets()
+    {
+        assert_eq!(ShaderTarget::from_profile("ps_5_0"), Some(ShaderTarget{ stage: Some(ShaderStage::Pixel), shader_model: ShaderModel::new(5, 0) }));
+        assert_eq!(ShaderTarget::from_profile("lib_6_3"), Some(ShaderTarget{ stage: None, shader_model: ShaderModel::new(6, 3) }));
+        assert_eq!(ShaderTarget::from_profile("lib_6"), None);
+        assert_eq!(ShaderTarget::from_profile("cs_6_6").unwrap().to_string(), "cs_6_6");
+    }
+
+    #[test]
+    fn reports_types_beyond_the_target()
+    {
+        let text = "RasterizerOrderedTexture2D<float4> ordered; int64_t2 wide; float16_t half_value; uint16_t3 short_values;";
+        assert_eq!(check(text, "ps_5_0"), vec![
+            String::from("'RasterizerOrderedTexture2D' requires shader model 5.1 or higher"),
+            String::from("'int64_t2' requires shader model 6.0 or higher"),
+            String::from("'float16_t' requires shader model 6.2 or higher"),
+            String::from("'uint16_t3' requires shader model 6.2 or higher"),
+        ]);
+        assert_eq!(check(text, "ps_6_2"), vec![
+            String::from("'float16_t' requires the -enable-16bit-types option"),
+            String::from("'uint16_t3' requires the -enable-16bit-types option"),
+        ]);
+        assert!(check_with(text, Some("ps_6_2"), true).is_empty());
+        assert!(check_with(text, None, false).is_empty());
+    }
+
+    #[test]
+    fn reports_intrinsics_and_descriptor_heaps_beyond_the_target()
+    {
+        let text = "RWBuffer<float> output; [numthreads(64, 1, 1)] void main(uint id : SV_DispatchThreadID) \
+            { Texture2D texture = ResourceDescriptorHeap[id]; output[id] = WaveActiveSum(rcp(1.0f)) + texture.Load(int3(0, 0, 0)).x; }";
+        assert_eq!(check(text, "cs_5_0"), vec![
+            String::from("'ResourceDescriptorHeap' requires shader model 6.6 or higher"),
+            String::from("'WaveActiveSum' requires shader model 6.0 or higher"),
+        ]);
+        assert_eq!(check(text, "cs_6_0"), vec![String::from("'ResourceDescriptorHeap' requires shader model 6.6 or higher")]);
+        assert!(check(text, "cs_6_6").is_empty());
+    }
+
+    #[test]
+    fn reports_mesh_shaders_beyond_the_target()
+    {
+        let text = "struct Vertex { float4 position : SV_Position; }; \
+            [numthreads(32, 1, 1)] [outputtopology(\"triangle\")] void main(out indices uint3 tris[1], out vertices Vertex verts[3]) { SetMeshOutputCounts(3, 1); }";
+        assert_eq!(check(text, "lib_6_3"), vec![
+            String::from("'SetMeshOutputCounts' requires shader model 6.5 or higher"),
+            String::from("mesh shaders require shader model 6.5 or higher"),
+        ]);
+        assert!(check(text, "ms_6_5").is_empty());
+    }
+
+    #[test]
+    fn accepts_corpus_with_latest_target()
+    {
+        let settings = ParserSettings{ target: ShaderTarget::from_profile("lib_6_8"), enable_16bit_types: true, ..Default::default() };
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let checker = ShaderModelChecker::new(tree.compilation_unit(), &table, &settings);
+            assert!(checker.get_diagnostics().is_empty(), "{}: {:?}", path.to_str().unwrap(), checker.get_diagnostics());
+        }
+    }
+}
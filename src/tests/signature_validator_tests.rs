@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod signature_validator_tests {
+    use crate::parser::{diagnostics::DiagnosticSeverity, source_file::SourceFile, syntax_parser::SyntaxTree, ParserSettings, ShaderModel, ShaderTarget};
+    use crate::semantics::{binder::Binder, entry_points::*, signature_validator::*};
+    use crate::tests::test_utils::{create_full_path, find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // The messages of the diagnostics of the entry point
+    fn validate_with(text: &str, entry_point: &str, shader_model: ShaderModel) -> Vec<String>
+    {
+        let settings = ParserSettings::default();
+        return with_bound(text, &settings, |unit, table| {
+            let finder = EntryPointFinder::new(unit, table);
+            let entry = finder.entry_point(entry_point, &settings).expect("entry point");
+            let validator = SignatureValidator::new(unit, table, &settings, &entry, shader_model);
+            return validator.get_diagnostics().iter().map(|d| d.message.clone()).collect();
+        });
+    }
+
+    fn validate(text: &str, entry_point: &str) -> Vec<String>
+    {
+        return validate_with(text, entry_point, ShaderModel::new(6, 0));
+    }
+
+    #[test]
+    fn accepts_mesh_and_hull_shaders()
+    {
+        let settings = ParserSettings::default();
+        for path in ["test_files/Dxc/mesh.hlsl", "test_files/HlslCrossCompiler/hs5/basic.hlsl"]
+        {
+            let mut source = SourceFile::from_path(&create_full_path(path), &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let finder = EntryPointFinder::new(tree.compilation_unit(), &table);
+            assert!(!finder.get_entry_points().is_empty(), "No entry points in {}", path);
+            for entry in finder.get_entry_points()
+            {
+                let validator = SignatureValidator::new(tree.compilation_unit(), &table, &settings, entry, ShaderModel::new(6, 5));
+                assert!(validator.get_diagnostics().is_empty(), "{} in {}: {:?}", entry.name, path, validator.get_diagnostics());
+            }
+        }
+    }
+
+    #[test]
+    fn checks_system_values_per_stage()
+    {
+        assert_eq!(validate("float4 PSMain(uint id : SV_DispatchThreadID) : SV_Target { return id; } \
+            technique11 T { pass P { SetPixelShader(CompileShader(ps_5_0, PSMain())); } }", "PSMain"),
+            vec![String::from("Semantic 'SV_DispatchThreadID' is invalid as ps Input")]);
+        assert_eq!(validate("[numthreads(8, 8, 1)] void CSMain(float2 uv : TEXCOORD0, uint3 id : SV_DispatchThreadID) { }", "CSMain"),
+            vec![String::from("Semantic 'TEXCOORD' is invalid as cs Input")]);
+        assert_eq!(validate("float4 VSMain(float4 position : POSITION) : SV_Target { return position; } \
+            technique11 T { pass P { SetVertexShader(CompileShader(vs_5_0, VSMain())); } }", "VSMain"), vec![
+            String::from("Semantic 'SV_Target' is invalid as vs Output"),
+            String::from("vertex shader does not output SV_Position"),
+        ]);
+        assert_eq!(validate("float4 PSMain(float4 position : SV_Position, uint view : SV_ViewID, float x : SV_Bogus) : SV_Target { return position; }", "PSMain"), vec![
+            String::from("semantic 'SV_ViewID' requires shader model 6.1 or higher"),
+            String::from("unknown system value semantic 'SV_Bogus'"),
+        ]);
+        assert_eq!(validate("struct VSOut { float4 position : SV_Position; float2 uv; }; VSOut VSMain(float4 position : POSITION, float4 color) { return (VSOut)0; }", "VSMain"), vec![
+            String::from("Semantic must be defined for all parameters of an entry function or patch constant function"),
+            String::from("Semantic must be defined for all outputs of an entry function or patch constant function"),
+        ]);
+    }
+
+    #[test]
+    fn validates_the_stage_of_the_target_profile()
+    {
+        let text = "float4 VSMain(float4 position : POSITION) : TEXCOORD0 { return position; }";
+        with_bound(text, &ParserSettings::default(), |unit, table| {
+            let mut settings = ParserSettings::default();
+            let finder = EntryPointFinder::new(unit, table);
+            // Nothing tells the stage without a profile
+            assert!(finder.entry_point("VSMain", &settings).is_none());
+
+            settings.target = ShaderTarget::from_profile("vs_6_0");
+            let entry = finder.entry_point("VSMain", &settings).expect("entry point");
+            let validator = SignatureValidator::new(unit, table, &settings, &entry, entry.shader_model.unwrap());
+            let messages: Vec<&str> = validator.get_diagnostics().iter().map(|d| d.message.as_str()).collect();
+            assert_eq!(messages, vec!["vertex shader does not output SV_Position"]);
+        });
+    }
+
+    #[test]
+    fn checks_stage_attributes_and_thread_groups()
+    {
+        assert_eq!(validate("[numthreads(2048, 1, 1)] void CSMain() { }", "CSMain"),
+            vec![String::from("Declared Thread Group X size 2048 outside valid range [1..1024]"), String::from("Declared Thread Group Count 2048 (X*Y*Z) is beyond the valid maximum of 1024")]);
+        assert_eq!(validate("[numthreads(32, 32, 2)] void CSMain() { }", "CSMain"),
+            vec![String::from("Declared Thread Group Count 2048 (X*Y*Z) is beyond the valid maximum of 1024")]);
+        assert_eq!(validate_with("[numthreads(1, 1, 2)] void CSMain() { }", "CSMain", ShaderModel::new(4, 0)),
+            vec![String::from("Declared Thread Group Z size 2 outside valid range [1..1]")]);
+        assert_eq!(validate("struct V { float4 p : SV_Position; }; [maxvertexcount(3)] void GSMain(triangle V input[2], TriangleStream<V> stream) { }", "GSMain"), vec![
+            String::from("input primitive 'triangle' requires an array of 3 vertices"),
+            String::from("stream-output object must be an inout parameter"),
+        ]);
+        assert_eq!(validate("struct V { float4 p : SV_Position; }; [maxvertexcount(3)] void GSMain(point V input[1]) { }", "GSMain"),
+            vec![String::from("geometry entry point must have a stream output parameter")]);
+    }
+
+    #[test]
+    fn checks_mesh_shader_outputs()
+    {
+        let text = "struct V { float4 p : SV_Position; }; \
+            [numthreads(32, 1, 1)] [outputtopology(\"triangle\")] void MSMain(out indices uint2 tris[4], in vertices V verts[3]) { } \
+            [numthreads(256, 1, 1)] [outputtopology(\"line\")] void MSLines(out indices uint2 lines, out vertices V verts[2]) { } \
+            [numthreads(32, 1, 1)] [outputtopology(\"triangle\")] void MSNone(out vertices V verts[3]) { }";
+        assert_eq!(validate(text, "MSMain"), vec![
+            String::from("'indices' object must be an array of uint3 for triangle topology"),
+            String::from("'vertices' object must be an out parameter"),
+        ]);
+        assert_eq!(validate(text, "MSLines"), vec![
+            String::from("Declared Thread Group X size 256 outside valid range [1..128]"),
+            String::from("Declared Thread Group Count 256 (X*Y*Z) is beyond the valid maximum of 128"),
+            String::from("'indices' object must be an array"),
+        ]);
+        assert_eq!(validate(text, "MSNone"), vec![String::from("mesh entry point must have an out indices parameter")]);
+    }
+
+    #[test]
+    fn checks_hull_shader_patches()
+    {
+        let text = "struct CP { float3 p : POSITION; }; struct Factors { float edges[3] : SV_TessFactor; float inside : SV_InsideTessFactor; }; \
+            Factors Constants(InputPatch<CP, 4> patch, OutputPatch<CP, 3> points) { return (Factors)0; } \
+            [domain(\"tri\")] [partitioning(\"integer\")] [outputtopology(\"triangle_cw\")] [outputcontrolpoints(4)] [patchconstantfunc(\"Constants\")] \
+            CP HSMain(InputPatch<CP, 3> patch, uint id : SV_OutputControlPointID) { return patch[id]; } \
+            [domain(\"quad\")] [partitioning(\"integer\")] [outputtopology(\"triangle_cw\")] [outputcontrolpoints(3)] [patchconstantfunc(\"Missing\")] \
+            CP HSMissing(InputPatch<CP, 40> patch) { return patch[0]; } \
+            [domain(\"quad\")] [partitioning(\"integer\")] [outputtopology(\"triangle_cw\")] [outputcontrolpoints(4)] [patchconstantfunc(\"Constants\")] \
+            CP HSQuad(InputPatch<CP, 4> patch) { return patch[0]; }";
+        assert_eq!(validate(text, "HSMain"), vec![
+            String::from("InputPatch control point count 4 does not match the hull shader InputPatch count 3"),
+            String::from("OutputPatch control point count 3 does not match outputcontrolpoints count 4"),
+        ]);
+        assert_eq!(validate(text, "HSMissing"), vec![
+            String::from("InputPatch control point count 40 outside valid range [1..32]"),
+            String::from("patch constant function 'Missing' must be defined"),
+        ]);
+        assert_eq!(validate(text, "HSQuad"), vec![
+            String::from("OutputPatch control point count 3 does not match outputcontrolpoints count 4"),
+            String::from("the 'quad' domain requires 4 SV_TessFactor outputs but patch constant function 'Constants' has 3"),
+            String::from("the 'quad' domain requires 2 SV_InsideTessFactor outputs but patch constant function 'Constants' has 1"),
+        ]);
+        assert_eq!(validate("struct CP { float3 p : POSITION; }; CP DSMain(OutputPatch<CP, 3> patch, float3 uv : SV_DomainLocation) { return patch[0]; } \
+            technique11 T { pass P { SetDomainShader(CompileShader(ds_5_0, DSMain())); } }", "DSMain"),
+            vec![String::from("domain entry point must have the domain attribute")]);
+    }
+
+    #[test]
+    fn validates_corpus_entry_points()
+    {
+        let settings = ParserSettings::default();
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let finder = EntryPointFinder::new(tree.compilation_unit(), &table);
+            for entry in finder.get_entry_points()
+            {
+                let shader_model = entry.shader_model.unwrap_or(ShaderModel::new(6, 8));
+                let validator = SignatureValidator::new(tree.compilation_unit(), &table, &settings, entry, shader_model);
+                let errors: Vec<_> = validator.get_diagnostics().iter().filter(|d| d.severity == DiagnosticSeverity::Error).collect();
+                assert!(errors.is_empty(), "{} in {}: {:?}", entry.name, path.to_str().unwrap(), errors);
+            }
+        }
+    }
+}
@@ -0,0 +1,354 @@
+#[cfg(test)]
+mod syntax_parser_tests {
+    use std::path::Path;
+
+    use crate::parser::{lexer::ILexer, preprocessor::Preprocessor, source_file::SourceFile, syntax_nodes::{SyntaxNode, SyntaxNodeType}, syntax_parser::SyntaxTree, syntax_tokens::SyntaxTokenType, HlslVersion, ParserSettings};
+    use crate::tests::test_utils::{create_full_path, find_all_effect_paths, find_all_shader_paths};
+
+    fn parse(text: &str) -> SyntaxTree
+    {
+        let tree = SyntaxTree::parse_text(text, &ParserSettings::default());
+        assert!(!tree.has_errors(), "Failed to parse '{}': {:?}", text, tree.diagnostics);
+        return tree;
+    }
+
+    fn find_all(root: &SyntaxNode, node_type: SyntaxNodeType) -> Vec<&SyntaxNode>
+    {
+        return root.descendant_nodes().into_iter().filter(|n| n.node_type == node_type).collect();
+    }
+
+    fn find_first(root: &SyntaxNode, node_type: SyntaxNodeType) -> &SyntaxNode
+    {
+        return match find_all(root, node_type).first()
+        {
+            Some(node) => node,
+            None => panic!("No {} found", node_type)
+        };
+    }
+
+    #[test]
+    fn tree_is_lossless()
+    {
+        let text = "// comment\nfloat4 main(float4 p : POSITION) : SV_Target\n{\n    return p * 2.0f; /* done */\n}\n";
+        let tree = parse(text);
+        assert_eq!(tree.root.to_full_string(), text);
+    }
+
+    #[test]
+    fn function_definitions()
+    {
+        let tree = parse("[numthreads(8, 8, 1)] void main(uint3 id : SV_DispatchThreadID, in float a, out float b) { b = a; }");
+        let function = find_first(&tree.root, SyntaxNodeType::FunctionDefinition);
+        assert!(function.find_node(SyntaxNodeType::AttributeList).is_some());
+        assert_eq!(find_all(function, SyntaxNodeType::Parameter).len(), 3);
+        assert_eq!(find_all(function, SyntaxNodeType::Semantic).len(), 1);
+
+        let tree = parse("float4 f(float4 x);");
+        assert!(tree.root.find_node(SyntaxNodeType::FunctionDeclaration).is_some());
+    }
+
+    #[test]
+    fn variable_declarations()
+    {
+        let tree = parse("Texture2D<float4> g_tex : register(t0, space1); static const float weights[3] = { 1, 2, 3 }, other = 1;");
+        let declarators = find_all(&tree.root, SyntaxNodeType::VariableDeclarator);
+        assert_eq!(declarators.len(), 3);
+        assert!(declarators[0].find_node(SyntaxNodeType::RegisterLocation).is_some());
+        assert!(declarators[1].find_node(SyntaxNodeType::ArrayRankSpecifier).is_some());
+        assert!(find_first(&tree.root, SyntaxNodeType::PredefinedType).find_node(SyntaxNodeType::TypeArgumentList).is_some());
+    }
+
+    #[test]
+    fn nested_template_arguments()
+    {
+        let tree = parse("StructuredBuffer<vector<float, 4>> buffer; Texture2DMS<float4, 32> ms;");
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::TypeArgumentList).len(), 3);
+    }
+
+    #[test]
+    fn casts_and_parenthesized_expressions()
+    {
+        let tree = parse("struct Cat { int a; }; void f() { Cat c = (Cat) 0; float x = (float)c.a; float y = (x) + 1; }");
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::CastExpression).len(), 2);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::ParenthesizedExpression).len(), 1);
+    }
+
+    #[test]
+    fn operator_precedence()
+    {
+        let tree = parse("int x = 1 + 2 * 3;");
+        let binary = find_first(&tree.root, SyntaxNodeType::BinaryExpression);
+        assert_eq!(binary.child_tokens().next().unwrap().text, "+");
+    }
+
+    #[test]
+    fn statements()
+    {
+        let text = "void f(int a) { [unroll] for (int i = 0; i < 4; i++) { if (i == a) break; else continue; }
+            while (a > 0) a--; do { a++; } while (a < 10); switch (a) { case 0: case 1: a = 2; break; default: discard; } }";
+        let tree = parse(text);
+        let for_statement = find_first(&tree.root, SyntaxNodeType::ForStatement);
+        assert_eq!(for_statement.children.first().unwrap().as_node().unwrap().node_type, SyntaxNodeType::AttributeList);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::SwitchSection).len(), 2);
+        assert!(tree.root.descendant_nodes().iter().any(|n| n.node_type == SyntaxNodeType::DiscardStatement));
+    }
+
+    #[test]
+    fn namespaces_and_qualified_names()
+    {
+        let tree = parse("namespace Outer { struct S { int TestFunc(); }; } int Outer::S::TestFunc() { return Outer::bar; }");
+        assert!(tree.root.find_node(SyntaxNodeType::NamespaceDeclaration).is_some());
+        let function = tree.root.find_node(SyntaxNodeType::FunctionDefinition).unwrap();
+        assert_eq!(function.find_node(SyntaxNodeType::QualifiedName).unwrap().to_text(), "Outer :: S :: TestFunc");
+    }
+
+    #[test]
+    fn techniques_and_passes()
+    {
+        let text = "technique11 Render < string Script = \"Pass=p0;\"; >
+        {
+            pass P0
+            {
+                SetVertexShader(CompileShader(vs_5_0, VS()));
+                SetGeometryShader(NULL);
+                SetBlendState(NoBlend, float4(0, 0, 0, 0), 0xFFFFFFFF);
+            }
+            pass P1 { VertexShader = compile vs_2_0 VS(1, true); ZEnable = true; }
+        }";
+        let tree = parse(text);
+        let technique = tree.root.find_node(SyntaxNodeType::TechniqueDeclaration).unwrap();
+        assert!(technique.find_node(SyntaxNodeType::Annotations).is_some());
+        assert_eq!(technique.child_nodes().filter(|n| n.node_type == SyntaxNodeType::PassDeclaration).count(), 2);
+        assert_eq!(find_all(technique, SyntaxNodeType::CompileExpression).len(), 2);
+        assert_eq!(find_all(technique, SyntaxNodeType::StateAssignment).len(), 2);
+
+        let compile = find_first(technique, SyntaxNodeType::CompileExpression);
+        assert_eq!(compile.child_tokens().nth(2).unwrap().text, "vs_5_0");
+
+        for keyword in ["technique", "technique10", "Technique"]
+        {
+            parse(&format!("{} T {{ pass {{ }} }}", keyword));
+        }
+    }
+
+    #[test]
+    fn state_objects()
+    {
+        let text = "BlendState AdditiveBlending { AlphaToCoverageEnable = FALSE; BlendEnable[0] = TRUE; };
+        DepthStencilState DisableDepth { DepthEnable = FALSE; DepthWriteMask = ZERO; };";
+        let tree = parse(text);
+        let state_blocks = find_all(&tree.root, SyntaxNodeType::StateBlock);
+        assert_eq!(state_blocks.len(), 2);
+        assert_eq!(find_all(state_blocks[0], SyntaxNodeType::StateAssignment).len(), 2);
+        assert!(find_first(state_blocks[0], SyntaxNodeType::StateAssignment).find_node(SyntaxNodeType::ArrayRankSpecifier).is_none());
+    }
+
+    #[test]
+    fn legacy_sampler_state()
+    {
+        let text = "texture gTex : DIFFUSE < string UIName = \"Diffuse\"; string ResourceType = \"2D\"; >;
+        sampler2D gSampler = sampler_state { Texture = <gTex>; MinFilter = linear; AddressU = Clamp; };";
+        let tree = parse(text);
+        let annotations = find_first(&tree.root, SyntaxNodeType::Annotations);
+        assert_eq!(annotations.child_nodes().count(), 2);
+
+        let sampler_state = find_first(&tree.root, SyntaxNodeType::SamplerStateExpression);
+        assert_eq!(find_all(sampler_state, SyntaxNodeType::StateAssignment).len(), 3);
+        let reference = find_first(sampler_state, SyntaxNodeType::StateReferenceExpression);
+        assert_eq!(reference.to_text(), "< gTex >");
+    }
+
+    #[test]
+    fn reports_errors_with_span()
+    {
+        let tree = SyntaxTree::parse_text("float4 x = ;", &ParserSettings::default());
+        assert!(tree.has_errors());
+        assert_eq!(tree.diagnostics[0].span.start, 11);
+    }
+
+    fn parse_with_errors(text: &str) -> SyntaxTree
+    {
+        let tree = SyntaxTree::parse_text(text, &ParserSettings::default());
+        assert_covers_tokens(text, &tree);
+        return tree;
+    }
+
+    // Every token of the preprocessed file has to end up in the tree, in order
+    fn assert_covers_tokens(text: &str, tree: &SyntaxTree)
+    {
+        let preprocessor = Preprocessor::from_text(text, &ParserSettings::default());
+        let expected: String = preprocessor.get_tokens().iter().map(|t| t.text.as_str()).collect();
+        let found: String = tree.root.descendant_tokens().iter().filter(|t| !t.is_missing).map(|t| t.text.as_str()).collect();
+        assert_eq!(found, expected);
+        assert_eq!(tree.root.last_token().unwrap().token_type, crate::parser::syntax_tokens::SyntaxTokenType::EndOfFileToken);
+    }
+
+    #[test]
+    fn recovers_from_missing_semicolon()
+    {
+        let text = "void f()\n{\n    int a = 1\n    int b = 2;\n}\nfloat4 g() { return 0; }";
+        let tree = parse_with_errors(text);
+        assert_eq!(tree.diagnostics.len(), 1);
+        assert_eq!(tree.diagnostics[0].message, "Expected ';' but found 'int'");
+        // The error is placed right after the '1'
+        assert_eq!(tree.diagnostics[0].span.start, text.find("1\n").unwrap() + 1);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::FunctionDefinition).len(), 2);
+        assert!(tree.root.descendant_tokens().iter().any(|t| t.is_missing));
+    }
+
+    #[test]
+    fn skips_unexpected_tokens()
+    {
+        let text = "int a; ) ) int b; void f() { a = ) ; b = 1; }";
+        let tree = parse_with_errors(text);
+        assert_eq!(tree.diagnostics.len(), 2, "{:?}", tree.diagnostics);
+        assert_eq!(tree.diagnostics[0].message, "Unexpected ')'");
+        assert_eq!(&text[tree.diagnostics[0].span.start..tree.diagnostics[0].span.end], ") )");
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::SkippedTokens).len(), 2);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::VariableDeclarationStatement).len(), 2);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::AssignmentExpression).len(), 2);
+    }
+
+    #[test]
+    fn recovers_inside_declarations()
+    {
+        let tree = parse_with_errors("struct S { float a float b; }; cbuffer C { float4 x; ] float4 y; } technique T { pass P { A = ; } foo } float4 z;");
+        assert!(tree.has_errors());
+        assert!(tree.root.find_node(SyntaxNodeType::TechniqueDeclaration).is_some());
+        assert_eq!(tree.root.child_nodes().last().unwrap().node_type, SyntaxNodeType::VariableDeclarationStatement);
+
+        let tree = parse_with_errors("void f() { if (a) { switch (a) { a = 1; case 1: break; ");
+        assert!(tree.diagnostics.iter().any(|d| d.message == "Expected '}' but found end of file"));
+    }
+
+    #[test]
+    fn recovers_in_large_file()
+    {
+        let path = create_full_path("./test_files/Sdk/Direct3D11/BC6HBC7EncoderDecoder11/BC7Encode.hlsl");
+        let text = String::from_utf8_lossy(&std::fs::read(path).unwrap()).to_string();
+        let original = parse(&text);
+        let function_count = find_all(&original.root, SyntaxNodeType::FunctionDefinition).len();
+
+        let broken = text.replacen("uint bits2 = candidateSectionBit2[partition - 64];", "uint bits2 = candidateSectionBit2[partition - 64]", 1);
+        assert_ne!(broken, text);
+        let tree = parse_with_errors(&broken);
+        assert_eq!(tree.diagnostics.len(), 1);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::FunctionDefinition).len(), function_count);
+    }
+
+    #[test]
+    fn recovers_from_truncated_files()
+    {
+        let path = create_full_path("./test_files/Nvidia/post_glow.fx");
+        let text = String::from_utf8_lossy(&std::fs::read(path).unwrap()).to_string();
+        let mut end = 0;
+        while end < text.len()
+        {
+            if text.is_char_boundary(end)
+            {
+                let truncated = &text[..end];
+                let tree = parse_with_errors(truncated);
+                for diagnostic in &tree.diagnostics
+                {
+                    assert!(diagnostic.span.end <= truncated.len(), "{:?} outside of the text", diagnostic);
+                }
+            }
+            end += 97;
+        }
+    }
+
+    #[test]
+    fn templates()
+    {
+        let tree = parse("template<typename T, int N = 4> struct Array { T values[N]; Array<T, N> copy() { return this; } };\n\
+            template<class T> T twice(T x) { return (T)(x * 2); }\n\
+            Array<float, 2> values;\n\
+            void main() { Array<int, 3> local; float y = twice<float>(1.0f); }");
+        let templates = find_all(&tree.root, SyntaxNodeType::TemplateDeclaration);
+        assert_eq!(templates.len(), 2);
+        assert_eq!(find_all(templates[0], SyntaxNodeType::TemplateParameter).len(), 2);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::VariableDeclarationStatement).len(), 4);
+
+        let call = find_first(&tree.root, SyntaxNodeType::CallExpression);
+        assert_eq!(call.child_nodes().next().unwrap().node_type, SyntaxNodeType::UserType);
+    }
+
+    #[test]
+    fn operator_overloads()
+    {
+        let tree = parse("struct Complex { float re; float im; Complex operator+(Complex rhs) { Complex c; c.re = re + rhs.re; c.im = im + rhs.im; return c; } \
+            bool operator==(Complex rhs); float operator[](uint i) { return i == 0 ? re : im; } };");
+        let names: Vec<String> = find_all(&tree.root, SyntaxNodeType::OperatorName).iter().map(|n| n.to_text()).collect();
+        assert_eq!(names, vec!["operator +", "operator ==", "operator [ ]"]);
+    }
+
+    #[test]
+    fn bitfields()
+    {
+        let tree = parse("struct Flags { uint a : 4; uint b : 28; float c : TEXCOORD0; };");
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::BitField).len(), 2);
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::Semantic).len(), 1);
+
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        let tree = SyntaxTree::parse_text("struct Flags { uint a : 4; };", &settings);
+        assert_eq!(tree.diagnostics.len(), 1);
+        assert_eq!(tree.diagnostics[0].message, "Bitfields are only supported in HLSL 2021");
+    }
+
+    #[test]
+    fn hlsl_2021_keywords_are_identifiers_in_older_versions()
+    {
+        let text = "float template = 1; float typename(float operator) { return operator * template; }";
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        let tree = SyntaxTree::parse_text(text, &settings);
+        assert!(!tree.has_errors(), "{:?}", tree.diagnostics);
+        assert!(SyntaxTree::parse_text(text, &ParserSettings::default()).has_errors());
+
+        // and, or and select are intrinsic functions in HLSL 2021
+        parse("bool3 f(bool3 a, bool3 b) { return select(and(a, b), or(a, b), a); }");
+    }
+
+    #[test]
+    fn classes_and_interfaces()
+    {
+        let tree = parse("interface iLight { float3 Illuminate(float3 n); }; class cAmbient : iLight { float3 color; float3 Illuminate(float3 n) { return color; } };\n\
+            class cSpot : cAmbient { }; iLight gLight; float3 main(float3 n : NORMAL) : SV_Target { return gLight.Illuminate(n); }");
+        let types = find_all(&tree.root, SyntaxNodeType::StructType);
+        assert_eq!(types.len(), 3);
+        assert_eq!(types[0].first_token().unwrap().token_type, SyntaxTokenType::InterfaceKeyword);
+        assert_eq!(types[1].first_token().unwrap().token_type, SyntaxTokenType::ClassKeyword);
+        assert!(types[2].find_node(SyntaxNodeType::BaseList).is_some());
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::FunctionDeclaration).len(), 1);
+
+        // State object arrays
+        let tree = parse("RasterizerState states[2] { { FillMode = SOLID; }, { FillMode = WIREFRAME; } };");
+        assert_eq!(find_all(&tree.root, SyntaxNodeType::StateBlock).len(), 3);
+    }
+
+    fn parse_file(shader: &Path) -> SyntaxTree
+    {
+        let settings = ParserSettings::default();
+        let mut source_file = SourceFile::from_path(shader, &settings);
+        return SyntaxTree::parse_file(&mut source_file, &settings);
+    }
+
+    #[test]
+    fn can_parse_effect_files()
+    {
+        for shader in find_all_effect_paths()
+        {
+            let tree = parse_file(&shader);
+            assert!(!tree.has_errors(), "Failed to parse {}: {:?}", shader.to_str().unwrap(), tree.diagnostics);
+        }
+    }
+
+    #[test]
+    fn can_parse_shaders()
+    {
+        for shader in find_all_shader_paths()
+        {
+            let tree = parse_file(&shader);
+            assert!(!tree.has_errors(), "Failed to parse {}: {:?}", shader.to_str().unwrap(), tree.diagnostics);
+        }
+    }
+}
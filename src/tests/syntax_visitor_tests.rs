@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod syntax_visitor_tests {
+    use crate::parser::{source_file::SourceFile, syntax_nodes::{SyntaxNode, SyntaxNodeType}, syntax_parser::SyntaxTree, syntax_tokens::{SyntaxToken, SyntaxTokenType}, syntax_visitor::*, ParserSettings};
+    use crate::tests::test_utils::{find_all_effect_paths, find_all_shader_paths};
+
+    fn parse(text: &str) -> SyntaxTree
+    {
+        let tree = SyntaxTree::parse_text(text, &ParserSettings::default());
+        assert!(!tree.has_errors(), "Failed to parse '{}': {:?}", text, tree.diagnostics);
+        return tree;
+    }
+
+    // Counts function definitions and declarations, the bodies are skipped as functions cannot be nested
+    #[derive(Default)]
+    struct FunctionCounter
+    {
+        definitions: usize,
+        declarations: usize,
+    }
+
+    impl<'a> Visitor<'a> for FunctionCounter
+    {
+        fn visit_function_definition(&mut self, _node: &'a SyntaxNode) -> VisitAction
+        {
+            self.definitions += 1;
+            return VisitAction::SkipChildren;
+        }
+
+        fn visit_function_declaration(&mut self, _node: &'a SyntaxNode) -> VisitAction
+        {
+            self.declarations += 1;
+            return VisitAction::SkipChildren;
+        }
+    }
+
+    #[test]
+    fn counts_functions()
+    {
+        let tree = parse("float f(float x); float f(float x) { return x; } namespace N { void g() {} } struct S { void h() {} };");
+        let mut counter = FunctionCounter::default();
+        assert_eq!(walk(&mut counter, &tree.root), VisitAction::Continue);
+        assert_eq!(counter.definitions, 3);
+        assert_eq!(counter.declarations, 1);
+    }
+
+    #[test]
+    fn counts_functions_in_corpus()
+    {
+        let mut total = 0;
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &ParserSettings::default());
+            let tree = SyntaxTree::parse_file(&mut source, &ParserSettings::default());
+
+            let mut counter = FunctionCounter::default();
+            walk(&mut counter, &tree.root);
+
+            let expected = tree.root.descendant_nodes().iter().filter(|n| n.node_type == SyntaxNodeType::FunctionDefinition).count();
+            assert_eq!(counter.definitions, expected, "Wrong function count for {}", path.to_str().unwrap());
+            total += counter.definitions;
+        }
+        assert!(total > 1000, "Only found {} functions", total);
+    }
+
+    // Records the order of the hooks
+    #[derive(Default)]
+    struct Tracer
+    {
+        events: Vec<String>,
+    }
+
+    impl<'a> Visitor<'a> for Tracer
+    {
+        fn visit_binary_expression(&mut self, node: &'a SyntaxNode) -> VisitAction
+        {
+            self.events.push(format!("enter {}", node.to_text()));
+            return VisitAction::Continue;
+        }
+
+        fn leave_binary_expression(&mut self, node: &'a SyntaxNode)
+        {
+            self.events.push(format!("leave {}", node.to_text()));
+        }
+
+        fn visit_token(&mut self, token: &'a SyntaxToken) -> VisitAction
+        {
+            if token.token_type == SyntaxTokenType::AsteriskToken
+            {
+                self.events.push(String::from("*"));
+            }
+            return VisitAction::Continue;
+        }
+    }
+
+    #[test]
+    fn pre_and_post_hooks()
+    {
+        let tree = parse("static float x = a * b + c;");
+        let mut tracer = Tracer::default();
+        walk(&mut tracer, &tree.root);
+        assert_eq!(tracer.events, vec!["enter a * b + c", "enter a * b", "*", "leave a * b", "leave a * b + c"]);
+    }
+
+    // Finds the first call and stops the walk
+    struct FirstCall<'a>
+    {
+        call: Option<&'a SyntaxNode>,
+    }
+
+    impl<'a> Visitor<'a> for FirstCall<'a>
+    {
+        fn visit_call_expression(&mut self, node: &'a SyntaxNode) -> VisitAction
+        {
+            self.call = Some(node);
+            return VisitAction::Stop;
+        }
+    }
+
+    #[test]
+    fn stops_walk()
+    {
+        let tree = parse("void f() { g(1); h(2); }");
+        let mut visitor = FirstCall{ call: None };
+        assert_eq!(walk(&mut visitor, &tree.root), VisitAction::Stop);
+        assert_eq!(visitor.call.unwrap().to_text(), "g ( 1 )");
+    }
+
+    #[test]
+    fn collects_results()
+    {
+        let tree = parse("float a; void f() { float b; { int c; } }");
+        let names = collect(&tree.root, |node| {
+            if node.node_type != SyntaxNodeType::VariableDeclarator
+            {
+                return None;
+            }
+            return node.first_token().map(|t| t.text.as_str());
+        });
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    // Renames an identifier and removes empty statements
+    struct Renamer;
+
+    impl Rewriter for Renamer
+    {
+        fn rewrite_token(&mut self, token: SyntaxToken) -> Option<SyntaxToken>
+        {
+            let mut token = token;
+            if token.token_type == SyntaxTokenType::IdentifierToken && token.text == "old"
+            {
+                token.text = String::from("renamed");
+            }
+            return Some(token);
+        }
+
+        fn rewrite_empty_statement(&mut self, _node: SyntaxNode) -> Option<SyntaxNode>
+        {
+            return None;
+        }
+    }
+
+    // Rewriting with the default hooks does not change anything
+    struct Identity;
+
+    impl Rewriter for Identity
+    {
+    }
+
+    #[test]
+    fn rewrites_tree()
+    {
+        let text = "float old = 1;\nvoid f() { old += 2;; }\n";
+        let tree = parse(text);
+        assert_eq!(rewrite(&mut Identity, tree.root.clone()).unwrap().to_full_string(), text);
+
+        let rewritten = rewrite(&mut Renamer, tree.root).unwrap();
+        assert_eq!(rewritten.to_full_string(), "float renamed = 1;\nvoid f() { renamed += 2; }\n");
+    }
+}
@@ -1,5 +1,8 @@
 use std::path::PathBuf;
 
+use crate::parser::{ast::CompilationUnit, syntax_parser::SyntaxTree, ParserSettings};
+use crate::semantics::{binder::Binder, symbol_table::SymbolTable};
+
 // Finds the root of the project
 pub fn find_project_dir() -> PathBuf
 {
@@ -10,7 +13,7 @@ pub fn find_project_dir() -> PathBuf
     };
 
     let mut found_project_dir = PathBuf::new();
-    while directory.parent() != None
+    while directory.parent().is_some()
     {
         let check_path = directory.join("Cargo.toml");
         if check_path.is_file()
@@ -30,7 +33,7 @@ pub fn create_full_path(p: &str) -> PathBuf
     return project_dir.join(p);
 }
 
-fn find_all_shaders_in_dir(dir: PathBuf) -> Vec<PathBuf>
+fn find_all_files_in_dir(dir: PathBuf, extension: &str) -> Vec<PathBuf>
 {
     let mut paths: Vec<PathBuf> = Vec::new();
     for file_or_directory in std::fs::read_dir(dir).unwrap()
@@ -39,7 +42,7 @@ fn find_all_shaders_in_dir(dir: PathBuf) -> Vec<PathBuf>
 
         if path.is_dir()
         {
-            paths.extend(find_all_shaders_in_dir(path))
+            paths.extend(find_all_files_in_dir(path, extension))
         }
         else 
         {
@@ -47,7 +50,7 @@ fn find_all_shaders_in_dir(dir: PathBuf) -> Vec<PathBuf>
             {
                 None => {},
                 Some(ext) => {
-                    if ext == "hlsl"
+                    if ext == extension
                     {
                         paths.push(path);
                     }
@@ -65,5 +68,23 @@ pub fn find_all_shader_paths() -> Vec<PathBuf>
     let project_dir = find_project_dir();
     let shaders_dir = project_dir.join("test_files");
 
-    return find_all_shaders_in_dir(shaders_dir);
+    return find_all_files_in_dir(shaders_dir, "hlsl");
+}
+
+// Creates a vector to all effect files
+pub fn find_all_effect_paths() -> Vec<PathBuf>
+{
+    let project_dir = find_project_dir();
+    let shaders_dir = project_dir.join("test_files");
+
+    return find_all_files_in_dir(shaders_dir, "fx");
+}
+
+// Parses the text, which must not have syntax errors, binds it and hands the unit and its symbols to the check
+pub fn with_bound<R>(text: &str, settings: &ParserSettings, check: impl for<'a> FnOnce(CompilationUnit<'a>, &SymbolTable<'a>) -> R) -> R
+{
+    let tree = SyntaxTree::parse_text(text, settings);
+    assert!(!tree.has_errors(), "Failed to parse '{}': {:?}", text, tree.diagnostics);
+    let table = Binder::bind(tree.compilation_unit(), settings);
+    return check(tree.compilation_unit(), &table);
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod type_checker_tests {
+    use crate::parser::{ast::AstNode, source_file::SourceFile, syntax_parser::SyntaxTree, HlslVersion, ParserSettings};
+    use crate::semantics::{binder::Binder, type_checker::*, types::{promote_scalars, ScalarType}};
+    use crate::tests::test_utils::{find_all_effect_paths, find_all_shader_paths, with_bound};
+
+    // The displayed types of the expressions, and the diagnostics with the text they point at
+    fn check(text: &str, expressions: &[&str], settings: &ParserSettings) -> (Vec<String>, Vec<(String, String)>)
+    {
+        return with_bound(text, settings, |unit, table| {
+            assert!(table.get_diagnostics().is_empty(), "{:?}", table.get_diagnostics());
+            let checker = TypeChecker::new(unit, table, settings);
+
+            let nodes = unit.syntax().descendant_nodes();
+            let types = expressions.iter().map(|expression| {
+                let start = text.find(expression).unwrap_or_else(|| panic!("'{}' is not in the text", expression));
+                let node = nodes.iter().find(|n| n.span().start == start && n.span().end == start + expression.len() && checker.type_of(n).is_some())
+                    .unwrap_or_else(|| panic!("'{}' is not an expression", expression));
+                return checker.type_of(node).unwrap().to_string();
+            }).collect();
+
+            let diagnostics = checker.get_diagnostics().iter().map(|d| (d.message.clone(), String::from(&text[d.span.start..d.span.end]))).collect();
+            return (types, diagnostics);
+        });
+    }
+
+    #[test]
+    fn splats_and_promotes_operands()
+    {
+        let text = "float4 f(float3 v, int i, uint u, half h, double d, bool b) { \
+            float4 a = 1; float3 s = v * 2; float3 p = v * i; uint q = i + u; float t = h * 1.5; double w = d * 2.0f; int n = b + b; \
+            float4 c = float4(v, 1); half k = h * 2; return a; }";
+        let (types, diagnostics) = check(text, &["v * 2", "v * i", "i + u", "h * 1.5", "d * 2.0f", "b + b", "float4(v, 1)", "h * 2"], &ParserSettings::default());
+        assert_eq!(types, vec!["float3", "float3", "uint", "half", "double", "int", "float4", "half"]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        assert_eq!(promote_scalars(ScalarType::LiteralInt, ScalarType::LiteralFloat), ScalarType::LiteralFloat);
+        assert_eq!(promote_scalars(ScalarType::Int, ScalarType::LiteralFloat), ScalarType::Float);
+        assert_eq!(promote_scalars(ScalarType::Min16Float, ScalarType::Float), ScalarType::Float);
+    }
+
+    #[test]
+    fn truncation_and_invalid_conversions()
+    {
+        let text = "float2 f(float4 v, float3x3 m, float2x2 n) { float3 a = v; float4 b = a; float2 c = v + a; float3 d = m; \
+            float2x2 e = m; float3x3 g = n; float4 h = n; float3 k = m + v; return v; }";
+        let (types, diagnostics) = check(text, &["v + a"], &ParserSettings::default());
+        assert_eq!(types, vec!["float3"]);
+        assert_eq!(diagnostics, vec![
+            (String::from("implicit truncation of vector type"), String::from("v")),
+            (String::from("cannot convert from 'float3' to 'float4'"), String::from("a")),
+            (String::from("implicit truncation of vector type"), String::from("v + a")),
+            (String::from("implicit truncation of vector type"), String::from("v + a")),
+            (String::from("cannot convert from 'float3x3' to 'float3'"), String::from("m")),
+            (String::from("implicit truncation of vector type"), String::from("m")),
+            (String::from("cannot convert from 'float2x2' to 'float3x3'"), String::from("n")),
+            (String::from("cannot convert from 'float4' to 'float3x3'"), String::from("m + v")),
+            (String::from("implicit truncation of vector type"), String::from("v")),
+        ]);
+    }
+
+    #[test]
+    fn records_implicit_conversions()
+    {
+        let text = "half g(half x) { return x; } half f(half4 v, float2 uv) { half a = 1.5; half3 b = v; half c = g(0.5) + a * 2; \
+            float d = dot(uv, v); return a > 0 ? c : 3.5; }";
+        let settings = ParserSettings::default();
+        with_bound(text, &settings, |unit, table| {
+            let checker = TypeChecker::new(unit, table, &settings);
+            let conversions: Vec<(String, String, String, bool)> = unit.syntax().descendant_nodes().into_iter().filter_map(|node| {
+                let conversion = checker.conversion_of(node)?;
+                return Some((String::from(&text[node.span().start..node.span().end]), conversion.source.to_string(), conversion.target.to_string(), conversion.truncates));
+            }).collect();
+            let conversion = |text: &str, source: &str, target: &str, truncates: bool| (String::from(text), String::from(source), String::from(target), truncates);
+            assert_eq!(conversions, vec![
+                conversion("1.5", "literal float", "half", false),
+                conversion("v", "half4", "half3", true),
+                conversion("0.5", "literal float", "half", false),
+                conversion("2", "literal int", "half", false),
+                conversion("v", "half4", "float2", true),
+                conversion("0", "literal int", "half", false),
+                conversion("3.5", "literal float", "half", false),
+            ]);
+        });
+    }
+
+    #[test]
+    fn mul_is_a_matrix_product()
+    {
+        let text = "float4x4 world; float4 f(float4 v, float3x4 m) { float4 a = v * v; float b = mul(v, v); float4 c = mul(v, world); \
+            float3 d = mul(m, v); float4x4 e = mul(world, world); float3 g = mul(v.xyz, m); return mul(2, v); }";
+        let (types, diagnostics) = check(text, &["v * v", "mul(v, v)", "mul(v, world)", "mul(m, v)", "mul(world, world)", "mul(v.xyz, m)", "mul(2, v)"],
+            &ParserSettings::default());
+        assert_eq!(types, vec!["float4", "float", "float4", "float3", "float4x4", "float4", "float4"]);
+        assert_eq!(diagnostics, vec![(String::from("implicit truncation of vector type"), String::from("mul(v.xyz, m)"))]);
+    }
+
+    #[test]
+    fn swizzles_and_members()
+    {
+        let text = "struct Light { float3 color; float4x4 transform; }; ConstantBuffer<Light> light; \
+            float4 f(float4 v, float s, Light l, RayDesc ray) { float2 a = v.xy; float4 b = v.rgba; float3 c = s.xxx; \
+            float2 d = l.transform._m00_m11; float e = l.transform._44; float3 g = light.color; float h = v[2]; \
+            float3 k = ray.Origin; float m = l.intensity; float n = v.xr; float o = v.xyzwx; float p = float2(1, 2).z; \
+            float q = l.transform._m04; float r = l.transform._00; float t = l.transform._1; return v; }";
+        let (types, diagnostics) = check(text, &["v.xy", "v.rgba", "s.xxx", "l.transform._m00_m11", "l.transform._44", "light.color", "v[2]", "ray.Origin"],
+            &ParserSettings::default());
+        assert_eq!(types, vec!["float2", "float4", "float3", "float2", "float", "float3", "float", "float3"]);
+        assert_eq!(diagnostics, vec![
+            (String::from("no member named 'intensity' in 'Light'"), String::from("intensity")),
+            (String::from("invalid format for vector swizzle 'xr'"), String::from("xr")),
+            (String::from("more than four positions are referenced in 'xyzwx'"), String::from("xyzwx")),
+            (String::from("vector swizzle 'z' is out of bounds"), String::from("z")),
+            (String::from("matrix subscript '_m04' is out of bounds"), String::from("_m04")),
+            (String::from("the digit '0' is used in '_00', but the syntax is for one-based rows and columns"), String::from("_00")),
+            (String::from("invalid format for matrix subscript '_1'"), String::from("_1")),
+        ]);
+    }
+
+    #[test]
+    fn operators_and_constructors()
+    {
+        let text = "float4 f(float4 v, int i, float x, bool3 c) { bool4 a = v < 1; int b = i << 2; float d = x & 1; \
+            float3 e = float3(x, x); float2 g = float2(v, x); float2x2 h = float2x2(1, 2, 3); bool k = !c.x; float3 m = k ? v.xyz : 0; return v; }";
+        let (types, diagnostics) = check(text, &["v < 1", "i << 2", "!c.x", "k ? v.xyz : 0"], &ParserSettings::default());
+        assert_eq!(types, vec!["bool4", "int", "bool", "float3"]);
+        assert_eq!(diagnostics, vec![
+            (String::from("int or unsigned int type required"), String::from("x & 1")),
+            (String::from("too few elements in vector initialization (expected 3 elements, have 2)"), String::from("float3(x, x)")),
+            (String::from("too many elements in vector initialization (expected 2 elements, have 5)"), String::from("float2(v, x)")),
+            (String::from("too few elements in matrix initialization (expected 4 elements, have 3)"), String::from("float2x2(1, 2, 3)")),
+        ]);
+    }
+
+    #[test]
+    fn logical_operators_depend_on_version()
+    {
+        let text = "bool2 f(bool2 a, bool2 b, bool c) { bool d = c && c; bool2 e = a ? a : b; return a || b; }";
+        let (_, diagnostics) = check(text, &[], &ParserSettings::default());
+        assert_eq!(diagnostics, vec![
+            (String::from("condition for short-circuiting ternary operator must be scalar, for non-scalar types use 'select'"), String::from("a ? a : b")),
+            (String::from("operands for short-circuiting logical binary operator must be scalar, for non-scalar types use 'or'"), String::from("a || b")),
+        ]);
+
+        // Before HLSL 2021 both are evaluated per component
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        let (types, diagnostics) = check(text, &["a ? a : b", "a || b"], &settings);
+        assert_eq!(types, vec!["bool2", "bool2"]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn defers_template_types_and_substitutes_them_at_calls()
+    {
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2021, ..Default::default() };
+        let text = "template<typename T> T twice(T v) { T w = -v; w += v; return v + w * 2 + abs(v) + v.x; } \
+            template<typename T, typename U> U convert(T v) { return (U)v; } \
+            template<typename T> T first(T values[2]) { return values[0]; } \
+            template<typename T> struct Box { T value; T get() { return value; } }; \
+            float2 f(float3 x, float3 pair[2], Box<float> box) { float s = twice(2.0); int i = twice(1); float3 y = twice(x).zyx; \
+                float2 t = first(pair); float4 c = convert<int, float2>(i); return box.value + box.get(); }";
+        let (types, diagnostics) = check(text, &["v + w * 2", "twice(2.0)", "twice(1)", "twice(x)", "first(pair)", "convert<int, float2>(i)"], &settings);
+        assert_eq!(types, vec!["<error>", "float", "int", "float3", "float3", "float2"]);
+        assert_eq!(diagnostics, vec![
+            (String::from("implicit truncation of vector type"), String::from("first(pair)")),
+            (String::from("cannot convert from 'float2' to 'float4'"), String::from("convert<int, float2>(i)")),
+        ]);
+    }
+
+    #[test]
+    fn checks_corpus()
+    {
+        let settings = ParserSettings{ hlsl_version: HlslVersion::Hlsl2018, ..Default::default() };
+        for path in find_all_shader_paths().into_iter().chain(find_all_effect_paths())
+        {
+            let mut source = SourceFile::from_path(&path, &settings);
+            let tree = SyntaxTree::parse_file(&mut source, &settings);
+            let table = Binder::bind(tree.compilation_unit(), &settings);
+            let checker = TypeChecker::new(tree.compilation_unit(), &table, &settings);
+            let errors: Vec<_> = checker.get_diagnostics().iter().filter(|d| d.is_error()).collect();
+            assert!(errors.is_empty(), "Failed to check {}: {:?}", path.to_str().unwrap(), errors);
+        }
+    }
+}
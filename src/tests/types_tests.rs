@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod types_tests {
+    use crate::parser::{syntax_tokens::{get_keyword_token_type, SyntaxTokenType}, ParserSettings};
+    use crate::semantics::{symbol_table::SymbolTable, types::*};
+    use crate::tests::test_utils::with_bound;
+
+    // The displayed type of every global variable, in declaration order
+    fn global_types(table: &SymbolTable) -> Vec<String>
+    {
+        let resolver = TypeResolver::new(table);
+        return table.get_scope(table.global_scope()).symbols.iter()
+            .filter(|s| table.get_symbol(**s).kind.is_variable())
+            .map(|s| resolver.symbol_type(*s).to_string())
+            .collect();
+    }
+
+    #[test]
+    fn maps_every_numeric_keyword()
+    {
+        let scalars = ["bool", "int", "uint", "dword", "half", "float", "double", "min10float", "min16float", "min12int", "min16int",
+            "min16uint", "int16_t", "uint16_t", "int64_t", "uint64_t", "float16_t"];
+
+        let mut keywords = 0;
+        for scalar in scalars
+        {
+            let scalar_type = ScalarType::from_name(scalar).unwrap();
+            assert_eq!(Type::from_keyword(scalar), Some(Type::Scalar(scalar_type)));
+            for size in 1..=4
+            {
+                let vector = format!("{}{}", scalar, size);
+                assert_eq!(Type::from_keyword(&vector), Some(Type::Vector(scalar_type, size)));
+                for columns in 1..=4
+                {
+                    let matrix = format!("{}{}x{}", scalar, size, columns);
+                    assert_eq!(Type::from_keyword(&matrix), Some(Type::Matrix(scalar_type, size, columns)));
+                    if get_keyword_token_type(&matrix) != SyntaxTokenType::IdentifierToken
+                    {
+                        keywords += 1;
+                    }
+                }
+            }
+        }
+
+        // Every matrix keyword of the lexer is covered
+        assert_eq!(keywords, 16 * 16);
+        assert_eq!(Type::from_keyword("float5"), None);
+        assert_eq!(Type::from_keyword("float4x5"), None);
+        assert_eq!(Type::from_keyword("Texture2D"), None);
+        assert_eq!(Type::from_keyword("dword3").unwrap().to_string(), "uint3");
+        assert_eq!(Type::from_keyword("min16float2x2").unwrap().to_string(), "min16float2x2");
+        assert!(ScalarType::Min16Float.is_min_precision() && ScalarType::Min16Float.is_float());
+        assert!(!ScalarType::Uint64.is_signed() && ScalarType::Uint64.bit_width() == 64);
+    }
+
+    #[test]
+    fn template_spellings_are_the_same_type()
+    {
+        let text = "float3 a; vector<float, 3> b; float4x3 c; matrix<float, 4, 3> d; vector e; matrix f; unsigned int g; vector<min16float, 2> h;";
+        with_bound(text, &ParserSettings::default(), |_, table| {
+            let types = global_types(table);
+            assert_eq!(types, vec!["float3", "float3", "float4x3", "float4x3", "float4", "float4x4", "uint", "min16float2"]);
+
+            let resolver = TypeResolver::new(table);
+            let symbols = &table.get_scope(table.global_scope()).symbols;
+            assert_eq!(resolver.symbol_type(symbols[0]).ty, resolver.symbol_type(symbols[1]).ty);
+            assert_eq!(resolver.symbol_type(symbols[2]).ty, Type::Matrix(ScalarType::Float, 4, 3));
+        });
+    }
+
+    #[test]
+    fn resources_structs_and_arrays()
+    {
+        let text = "struct Particle { float3 position; }; typedef float4 Color; typedef Color Palette[4]; \
+            Texture2D tex; Texture2DMS<float4, 8> ms; StructuredBuffer<Particle> particles; RWTexture2D<uint2> output; \
+            Buffer<unorm float4> unorms; SamplerState linearSampler; ByteAddressBuffer raw; ConstantBuffer<Particle> constants; \
+            static const row_major float4x4 transforms[2][3]; Palette palette; float values[]; float sized[COUNT]; string name; \
+            RaytracingAccelerationStructure scene;";
+        with_bound(text, &ParserSettings::default(), |_, table| {
+            assert_eq!(global_types(table), vec![
+                "Texture2D<float4>", "Texture2DMS<float4, 8>", "StructuredBuffer<Particle>", "RWTexture2D<uint2>", "Buffer<float4>",
+                "SamplerState", "ByteAddressBuffer", "ConstantBuffer<Particle>", "static const row_major float4x4[2][3]",
+                "float4[4]", "float[]", "float[?]", "string", "RaytracingAccelerationStructure"]);
+
+            let resolver = TypeResolver::new(table);
+            let particles = table.lookup(table.global_scope(), "particles")[0];
+            let particle = table.lookup(table.global_scope(), "Particle")[0];
+            let element = resolver.symbol_type(particles).ty.element_type().cloned().unwrap();
+            assert_eq!(element, Type::Struct{ name: String::from("Particle"), symbol: particle });
+
+            let transforms = resolver.symbol_type(table.lookup(table.global_scope(), "transforms")[0]);
+            assert_eq!(transforms.modifiers.orientation, Some(MatrixOrientation::RowMajor));
+            assert!(transforms.modifiers.is_static && transforms.modifiers.is_const);
+            let inner = transforms.ty.element_type().unwrap();
+            assert_eq!(*inner, Type::Array(Box::new(Type::Matrix(ScalarType::Float, 4, 4)), ArraySize::Fixed(3)));
+
+            assert!(ResourceKind::RWTexture2D.is_read_write() && ResourceKind::RWTexture2D.is_texture());
+            assert!(!ResourceKind::StructuredBuffer.is_read_write());
+            assert!(ResourceKind::LegacySampler2D.is_sampler());
+        });
+    }
+
+    #[test]
+    fn parameters_and_qualified_structs()
+    {
+        let text = "namespace N { struct Light { float3 color; }; } \
+            float4 Shade(in N::Light light, out float3 normal, inout uint count, nointerpolation float2 uv[2]) { return 0; }";
+        with_bound(text, &ParserSettings::default(), |_, table| {
+            let resolver = TypeResolver::new(table);
+
+            let shade = table.lookup(table.global_scope(), "Shade")[0];
+            assert_eq!(resolver.symbol_type(shade).to_string(), "float4");
+
+            let scope = table.get_symbol(shade).members.unwrap();
+            let parameters: Vec<String> = table.get_scope(scope).symbols.iter().map(|s| resolver.symbol_type(*s).to_string()).collect();
+            assert_eq!(parameters, vec!["N::Light", "out float3", "inout uint", "float2[2]"]);
+
+            let uv = resolver.symbol_type(table.get_scope(scope).symbols[3]);
+            assert_eq!(uv.modifiers.interpolation, vec![Interpolation::NoInterpolation]);
+            assert_eq!(uv.modifiers.parameter_direction(), ParameterDirection::In);
+        });
+    }
+}
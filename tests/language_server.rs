@@ -0,0 +1,239 @@
+// Drives the hlsl-language-server binary over stdio like an editor does
+
+use std::collections::VecDeque;
+use std::io::{BufReader, Write};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use hlsl_parser::language_server::{json::JsonValue, transport::{read_message, write_message}};
+
+// A scripted client, the messages of the server are read on a thread so a silent server fails the test instead of hanging it
+struct LspClient
+{
+    server: Child,
+    input: ChildStdin,
+    messages: Receiver<JsonValue>,
+    // Notifications that arrived while waiting for a response
+    pending: VecDeque<JsonValue>,
+    next_id: i64,
+}
+
+impl LspClient
+{
+    fn start() -> Self
+    {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_hlsl-language-server"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .expect("Failed to start the language server");
+        let input = server.stdin.take().unwrap();
+        let mut output = BufReader::new(server.stdout.take().unwrap());
+        let (sender, messages) = channel();
+        std::thread::spawn(move || {
+            while let Ok(Some(body)) = read_message(&mut output)
+            {
+                if sender.send(JsonValue::parse(&body).expect("The server sent invalid JSON")).is_err()
+                {
+                    break;
+                }
+            }
+        });
+        return Self{ server: server, input: input, messages: messages, pending: VecDeque::new(), next_id: 1 };
+    }
+
+    fn receive(&mut self) -> JsonValue
+    {
+        return self.messages.recv_timeout(Duration::from_secs(30)).expect("The server did not answer");
+    }
+
+    fn send(&mut self, message: JsonValue)
+    {
+        write_message(&mut self.input, &message).unwrap();
+    }
+
+    // Sends a body that does not have to be valid JSON
+    fn send_text(&mut self, body: &str)
+    {
+        write!(self.input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.input.flush().unwrap();
+    }
+
+    fn notify(&mut self, method: &str, params: JsonValue)
+    {
+        self.send(JsonValue::object(vec![("jsonrpc", "2.0".into()), ("method", method.into()), ("params", params)]));
+    }
+
+    // Sends a request and waits for its response
+    fn request(&mut self, method: &str, params: JsonValue) -> JsonValue
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(JsonValue::object(vec![("jsonrpc", "2.0".into()), ("id", id.into()), ("method", method.into()), ("params", params)]));
+        loop
+        {
+            let message = self.receive();
+            if message.get("id").and_then(|i| i.as_i64()) == Some(id) && message.get("method").is_none()
+            {
+                return message;
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    fn wait_for_notification(&mut self, method: &str) -> JsonValue
+    {
+        if let Some(index) = self.pending.iter().position(|m| m.get("method").and_then(|m| m.as_str()) == Some(method))
+        {
+            return self.pending.remove(index).unwrap();
+        }
+        loop
+        {
+            let message = self.receive();
+            if message.get("method").and_then(|m| m.as_str()) == Some(method)
+            {
+                return message;
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    fn initialize(&mut self) -> JsonValue
+    {
+        let response = self.request("initialize", JsonValue::object(vec![("processId", JsonValue::Null), ("rootUri", JsonValue::Null),
+            ("capabilities", JsonValue::object(Vec::new()))]));
+        self.notify("initialized", JsonValue::object(Vec::new()));
+        return response;
+    }
+
+    fn exit(mut self) -> ExitStatus
+    {
+        self.notify("exit", JsonValue::Null);
+        return self.server.wait().unwrap();
+    }
+}
+
+fn document(uri: &str) -> JsonValue
+{
+    return JsonValue::object(vec![("uri", uri.into())]);
+}
+
+fn position(line: usize, character: usize) -> JsonValue
+{
+    return JsonValue::object(vec![("line", line.into()), ("character", character.into())]);
+}
+
+fn range(start: (usize, usize), end: (usize, usize)) -> JsonValue
+{
+    return JsonValue::object(vec![("start", position(start.0, start.1)), ("end", position(end.0, end.1))]);
+}
+
+// The messages of the diagnostics of a publishDiagnostics notification
+fn messages(notification: &JsonValue) -> Vec<String>
+{
+    let diagnostics = notification.get("params").and_then(|p| p.get("diagnostics")).and_then(|d| d.as_array()).unwrap();
+    return diagnostics.iter().map(|d| d.get("message").and_then(|m| m.as_str()).unwrap().to_string()).collect();
+}
+
+#[test]
+fn publishes_diagnostics_on_every_change()
+{
+    let mut client = LspClient::start();
+    let response = client.initialize();
+    let capabilities = response.get("result").and_then(|r| r.get("capabilities")).unwrap();
+    assert_eq!(capabilities.get("textDocumentSync").and_then(|s| s.get("change")).and_then(|c| c.as_i64()), Some(2));
+
+    let uri = "file:///shaders/pixel.hlsl";
+    let text = "float4 main(float2 uv : TEXCOORD) : SV_Target\n{\n    return tint * uv.xyxy\n}\n";
+    client.notify("textDocument/didOpen", JsonValue::object(vec![("textDocument", JsonValue::object(vec![("uri", uri.into()),
+        ("languageId", "hlsl".into()), ("version", 1i64.into()), ("text", text.into())]))]));
+    let published = client.wait_for_notification("textDocument/publishDiagnostics");
+    assert_eq!(published.get("params").and_then(|p| p.get("uri")).and_then(|u| u.as_str()), Some(uri));
+    assert_eq!(messages(&published), vec!["Expected ';' but found '}'", "use of undeclared identifier 'tint'"]);
+    let diagnostic = &published.get("params").and_then(|p| p.get("diagnostics")).and_then(|d| d.as_array()).unwrap()[1];
+    assert_eq!(diagnostic.get("range"), Some(&range((2, 11), (2, 15))));
+    assert_eq!(diagnostic.get("severity").and_then(|s| s.as_i64()), Some(1));
+
+    // Incremental changes: the missing ';', then a declaration of tint
+    let change = |version: i64, start: (usize, usize), end: (usize, usize), text: &str| JsonValue::object(vec![
+        ("textDocument", JsonValue::object(vec![("uri", uri.into()), ("version", version.into())])),
+        ("contentChanges", vec![JsonValue::object(vec![("range", range(start, end)), ("text", text.into())])].into()),
+    ]);
+    client.notify("textDocument/didChange", change(2, (2, 25), (2, 25), ";"));
+    assert_eq!(messages(&client.wait_for_notification("textDocument/publishDiagnostics")), vec!["use of undeclared identifier 'tint'"]);
+    client.notify("textDocument/didChange", change(3, (0, 0), (0, 0), "float4 tint;\n"));
+    let published = client.wait_for_notification("textDocument/publishDiagnostics");
+    assert!(messages(&published).is_empty());
+    assert_eq!(published.get("params").and_then(|p| p.get("version")).and_then(|v| v.as_i64()), Some(3));
+
+    // A full change with a preprocessor error
+    client.notify("textDocument/didChange", JsonValue::object(vec![
+        ("textDocument", JsonValue::object(vec![("uri", uri.into()), ("version", 4i64.into())])),
+        ("contentChanges", vec![JsonValue::object(vec![("text", "#endif\n".into())])].into()),
+    ]));
+    assert_eq!(messages(&client.wait_for_notification("textDocument/publishDiagnostics")).len(), 1);
+
+    client.notify("textDocument/didClose", JsonValue::object(vec![("textDocument", document(uri))]));
+    assert!(messages(&client.wait_for_notification("textDocument/publishDiagnostics")).is_empty());
+
+    assert_eq!(client.request("shutdown", JsonValue::Null).get("result"), Some(&JsonValue::Null));
+    assert_eq!(client.exit().code(), Some(0));
+}
+
+#[test]
+fn formats_open_documents()
+{
+    let mut client = LspClient::start();
+    client.initialize();
+    let uri = "file:///shaders/compute.hlsl";
+    client.notify("textDocument/didOpen", JsonValue::object(vec![("textDocument", JsonValue::object(vec![("uri", uri.into()),
+        ("languageId", "hlsl".into()), ("version", 1i64.into()), ("text", "[numthreads(8,8,1)]\nvoid main(){int x=1;\n}\n".into())]))]));
+    client.wait_for_notification("textDocument/publishDiagnostics");
+
+    let options = JsonValue::object(vec![("tabSize", 2usize.into()), ("insertSpaces", true.into())]);
+    let response = client.request("textDocument/formatting", JsonValue::object(vec![("textDocument", document(uri)), ("options", options.clone())]));
+    let edits = response.get("result").and_then(|r| r.as_array()).unwrap();
+    let new_texts: Vec<&str> = edits.iter().map(|e| e.get("newText").and_then(|t| t.as_str()).unwrap()).collect();
+    assert_eq!(new_texts, vec![" ", " ", "\n", "\n  ", " ", " "]);
+    assert_eq!(edits[2].get("range"), Some(&range((1, 11), (1, 11))));
+
+    // Typing the ';' formats the statement it ends
+    let response = client.request("textDocument/onTypeFormatting", JsonValue::object(vec![("textDocument", document(uri)),
+        ("position", position(1, 20)), ("ch", ";".into()), ("options", options)]));
+    let edits = response.get("result").and_then(|r| r.as_array()).unwrap();
+    let new_texts: Vec<&str> = edits.iter().map(|e| e.get("newText").and_then(|t| t.as_str()).unwrap()).collect();
+    assert_eq!(new_texts, vec!["\n  ", " ", " "]);
+
+    let response = client.request("textDocument/hover", JsonValue::object(vec![("textDocument", document(uri)), ("position", position(0, 0))]));
+    assert_eq!(response.get("error").and_then(|e| e.get("code")).and_then(|c| c.as_i64()), Some(-32601));
+
+    client.request("shutdown", JsonValue::Null);
+    assert_eq!(client.exit().code(), Some(0));
+}
+
+#[test]
+fn exits_with_an_error_without_shutdown()
+{
+    let mut client = LspClient::start();
+    let response = client.request("shutdown", JsonValue::Null);
+    assert_eq!(response.get("error").and_then(|e| e.get("code")).and_then(|c| c.as_i64()), Some(-32002));
+    client.initialize();
+    assert_eq!(client.exit().code(), Some(1));
+}
+
+#[test]
+fn answers_deeply_nested_messages_with_a_parse_error()
+{
+    let mut client = LspClient::start();
+    client.send_text(&"[".repeat(100_000));
+    let response = client.receive();
+    assert_eq!(response.get("id"), Some(&JsonValue::Null));
+    assert_eq!(response.get("error").and_then(|e| e.get("code")).and_then(|c| c.as_i64()), Some(-32700));
+
+    // The server keeps running
+    assert!(client.initialize().get("result").is_some());
+    client.request("shutdown", JsonValue::Null);
+    assert_eq!(client.exit().code(), Some(0));
+}